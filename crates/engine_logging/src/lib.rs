@@ -1,14 +1,34 @@
 #![deny(missing_docs)]
 //! Shared logging utilities for the engine workspace.
 //!
-//! This crate provides the `engine_*` logging macros used across the codebase
-//! and a minimal test initializer for the global logger.
+//! This crate provides the `engine_*` logging macros used across the codebase,
+//! which prefix messages with the thread's tick and job context,
+//! runtime control of the log level, and a minimal test initializer for the
+//! global logger.
 
 use std::cell::Cell;
 
+use log::LevelFilter;
+
+/// Environment variable naming the log level to start with: `off`, `error`,
+/// `warn`, `info`, `debug` or `trace`.
+pub const LOG_LEVEL_ENV: &str = "HARVESTER_LOG";
+
+/// Levels offered to users, most severe first.
+pub const LOG_LEVELS: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
 thread_local! {
     /// Thread-local storage for the current simulation tick count.
     static SIM_TICK: Cell<u64> = const { Cell::new(0) };
+    /// Job whose work this thread is doing, see [`with_job_context`].
+    static JOB_CONTEXT: Cell<Option<u64>> = const { Cell::new(None) };
 }
 
 /// Sets the simulation tick count for the current thread.
@@ -23,13 +43,86 @@ pub fn get_sim_tick() -> u64 {
     SIM_TICK.with(|v| v.get())
 }
 
+/// The level named by [`LOG_LEVEL_ENV`], or `default` when it is unset or
+/// not a level name.
+pub fn level_from_env(default: LevelFilter) -> LevelFilter {
+    std::env::var(LOG_LEVEL_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Change the most verbose level that is logged, effective immediately.
+///
+/// Loggers must be created with [`LevelFilter::Trace`] so that this global
+/// filter is the only one that applies.
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// The most verbose level currently logged.
+pub fn level() -> LevelFilter {
+    log::max_level()
+}
+
+/// Runs `f` with `job_id` as this thread's job context, so the `engine_*`
+/// macros tag its messages with the job. The previous context is restored
+/// afterwards, even if `f` panics.
+///
+/// The context is per thread: wrap synchronous work such as a blocking task,
+/// not code that awaits.
+pub fn with_job_context<R>(job_id: u64, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<u64>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            JOB_CONTEXT.with(|v| v.set(self.0));
+        }
+    }
+    let _restore = Restore(JOB_CONTEXT.with(|v| v.replace(Some(job_id))));
+    f()
+}
+
+/// The job set by an enclosing [`with_job_context`] on this thread.
+pub fn job_context() -> Option<u64> {
+    JOB_CONTEXT.with(|v| v.get())
+}
+
+/// Prefix the `engine_*` macros put before each message: `[tick N] ` once
+/// the thread has a tick, then `[job N] ` inside a job context.
+#[doc(hidden)]
+pub fn log_prefix() -> LogPrefix {
+    LogPrefix {
+        tick: get_sim_tick(),
+        job_id: job_context(),
+    }
+}
+
+/// Display form of [`log_prefix`].
+#[doc(hidden)]
+pub struct LogPrefix {
+    tick: u64,
+    job_id: Option<u64>,
+}
+
+impl std::fmt::Display for LogPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tick > 0 {
+            write!(f, "[tick {}] ", self.tick)?;
+        }
+        if let Some(job_id) = self.job_id {
+            write!(f, "[job {job_id}] ")?;
+        }
+        Ok(())
+    }
+}
+
 // TODO: Replace all log:: with the macros below.
 
 /// Logs a trace-level message using the global logging facade.
 #[macro_export]
 macro_rules! engine_trace {
     ($($arg:tt)*) => {{
-        log::trace!($($arg)*);
+        log::trace!("{}{}", $crate::log_prefix(), format_args!($($arg)*));
     }};
 }
 
@@ -37,7 +130,7 @@ macro_rules! engine_trace {
 #[macro_export]
 macro_rules! engine_info {
     ($($arg:tt)*) => {{
-        log::info!($($arg)*);
+        log::info!("{}{}", $crate::log_prefix(), format_args!($($arg)*));
     }};
 }
 
@@ -45,7 +138,7 @@ macro_rules! engine_info {
 #[macro_export]
 macro_rules! engine_debug {
     ($($arg:tt)*) => {{
-        log::debug!($($arg)*);
+        log::debug!("{}{}", $crate::log_prefix(), format_args!($($arg)*));
     }};
 }
 
@@ -53,7 +146,7 @@ macro_rules! engine_debug {
 #[macro_export]
 macro_rules! engine_warn {
     ($($arg:tt)*) => {{
-        log::warn!($($arg)*);
+        log::warn!("{}{}", $crate::log_prefix(), format_args!($($arg)*));
     }};
 }
 
@@ -61,7 +154,7 @@ macro_rules! engine_warn {
 #[macro_export]
 macro_rules! engine_error {
     ($($arg:tt)*) => {{
-        log::error!($($arg)*);
+        log::error!("{}{}", $crate::log_prefix(), format_args!($($arg)*));
     }};
 }
 
@@ -86,3 +179,20 @@ pub fn initialize_for_tests() {
         ColorChoice::Auto,
     )]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_carries_tick_and_nested_job_context() {
+        assert_eq!(log_prefix().to_string(), "");
+        set_sim_tick(7);
+        with_job_context(3, || {
+            assert_eq!(log_prefix().to_string(), "[tick 7] [job 3] ");
+            with_job_context(4, || assert_eq!(job_context(), Some(4)));
+            assert_eq!(job_context(), Some(3));
+        });
+        assert_eq!(log_prefix().to_string(), "[tick 7] ");
+    }
+}
@@ -1,12 +1,70 @@
-#[cfg(target_os = "windows")]
+#[cfg(feature = "native-messaging")]
+mod native_messaging;
+#[cfg(feature = "pipe")]
+mod pipe;
+#[cfg(any(target_os = "windows", feature = "egui"))]
 mod platform;
+#[cfg(any(feature = "api", feature = "native-messaging"))]
+mod remote;
+#[cfg(any(target_os = "windows", feature = "egui"))]
+mod single_instance;
 
-#[cfg(target_os = "windows")]
-fn main() -> commanductui::PlatformResult<()> {
-    platform::run_app()
-}
+#[cfg(any(target_os = "windows", feature = "egui"))]
+use single_instance::{Instance, InstanceLock};
 
-#[cfg(not(target_os = "windows"))]
 fn main() {
-    eprintln!("harvester_app UI is only available on Windows.");
+    #[cfg(feature = "native-messaging")]
+    if native_messaging::is_host_launch(std::env::args().skip(1)) {
+        if let Err(err) = native_messaging::run_host() {
+            eprintln!("harvester_app native messaging host failed: {err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(feature = "pipe")]
+    if let Some(format) = pipe::pipe_format(std::env::args().skip(1)) {
+        match pipe::run_pipe(format) {
+            Ok(summary) if summary.failed == 0 => return,
+            Ok(_) => std::process::exit(2),
+            Err(err) => {
+                eprintln!("harvester_app pipe mode failed: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "windows", feature = "egui"))]
+    {
+        let urls = single_instance::launch_urls(std::env::args().skip(1));
+        let lock = match single_instance::claim(&urls) {
+            Instance::Primary(lock) => Some(lock),
+            Instance::Forwarded => return,
+            Instance::Unlocked => None,
+        };
+        run_ui(lock, urls);
+    }
+    #[cfg(not(any(target_os = "windows", feature = "egui")))]
+    run_ui();
+}
+
+#[cfg(feature = "egui")]
+fn run_ui(lock: Option<InstanceLock>, launch_urls: Vec<String>) {
+    if let Err(err) = platform::run_egui_app(lock, launch_urls) {
+        eprintln!("harvester_app failed: {err}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(all(target_os = "windows", not(feature = "egui")))]
+fn run_ui(lock: Option<InstanceLock>, launch_urls: Vec<String>) {
+    if let Err(err) = platform::run_app(lock, launch_urls) {
+        eprintln!("harvester_app failed: {err:?}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(any(target_os = "windows", feature = "egui")))]
+fn run_ui() {
+    eprintln!("harvester_app native UI is only available on Windows; build with `--features egui` for the cross-platform UI.");
 }
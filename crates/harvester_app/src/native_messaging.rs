@@ -0,0 +1,211 @@
+//! Browser-extension native messaging host.
+//!
+//! The browser starts the host and exchanges messages over stdio. Each
+//! message is a UTF-8 JSON document preceded by its length as a 32-bit
+//! native-endian integer. Requests look like `{"url": "https://…"}` (or
+//! `{"urls": [...]}`), and each one gets a `{"ok": …}` reply.
+//!
+//! The host holds no session of its own. URLs are forwarded to the running
+//! instance's local API (`POST /jobs`), so the GUI must be built with the
+//! `api` feature.
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use engine_logging::{engine_info, engine_warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::remote;
+
+/// Browsers cap host→browser messages at 1 MiB. Incoming requests only carry
+/// URLs, so the same cap applies to them.
+const MAX_MESSAGE_BYTES: u32 = 1024 * 1024;
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether the process was launched by a browser as a native messaging host.
+///
+/// Chrome passes the caller origin (`chrome-extension://…/`). Firefox passes
+/// the path of the host manifest, which only counts when it names an existing
+/// file so that pasted `.json` URLs still open the GUI. `--native-messaging`
+/// forces the mode.
+pub(crate) fn is_host_launch<I, S>(args: I) -> bool
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().any(|arg| {
+        let arg = arg.as_ref();
+        arg == "--native-messaging"
+            || arg.starts_with("chrome-extension://")
+            || is_manifest_path(arg)
+    })
+}
+
+fn is_manifest_path(arg: &str) -> bool {
+    arg.ends_with(".json") && !arg.contains("://") && std::path::Path::new(arg).is_file()
+}
+
+/// Serve requests from stdin until the browser closes the pipe.
+pub(crate) fn run_host() -> io::Result<()> {
+    let endpoint = format!("http://{}/jobs", remote::api_addr());
+    engine_info!("[NativeMessaging] Host started, forwarding to {}", endpoint);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(FORWARD_TIMEOUT)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    while let Some(message) = read_message(&mut stdin)? {
+        let reply = match parse_request(&message) {
+            Ok(urls) => match forward(&client, &endpoint, &urls) {
+                Ok(submitted) => json!({ "ok": true, "submitted": submitted }),
+                Err(err) => {
+                    engine_warn!("[NativeMessaging] Forward failed: {}", err);
+                    json!({ "ok": false, "error": err })
+                }
+            },
+            Err(err) => json!({ "ok": false, "error": err }),
+        };
+        write_message(&mut stdout, &reply)?;
+    }
+    engine_info!("[NativeMessaging] Browser closed the pipe");
+    Ok(())
+}
+
+/// Read one framed message; `None` on a clean end of stream.
+fn read_message(reader: &mut impl Read) -> io::Result<Option<Value>> {
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_ne_bytes(len_bytes);
+    if len > MAX_MESSAGE_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {len} bytes exceeds {MAX_MESSAGE_BYTES}"),
+        ));
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    serde_json::from_slice(&payload)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn write_message(writer: &mut impl Write, value: &Value) -> io::Result<()> {
+    let payload = serde_json::to_vec(value).map_err(io::Error::other)?;
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_BYTES)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "reply too large"))?;
+    writer.write_all(&len.to_ne_bytes())?;
+    writer.write_all(&payload)?;
+    writer.flush()
+}
+
+#[derive(Debug, Deserialize)]
+struct HarvestRequest {
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    urls: Vec<String>,
+}
+
+fn parse_request(message: &Value) -> Result<Vec<String>, String> {
+    let request = HarvestRequest::deserialize(message).map_err(|err| err.to_string())?;
+    let urls: Vec<String> = request
+        .url
+        .into_iter()
+        .chain(request.urls)
+        .filter(|url| !url.trim().is_empty())
+        .collect();
+    if urls.is_empty() {
+        return Err("request has no url".to_string());
+    }
+    Ok(urls)
+}
+
+fn forward(
+    client: &reqwest::blocking::Client,
+    endpoint: &str,
+    urls: &[String],
+) -> Result<usize, String> {
+    let response = client
+        .post(endpoint)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(json!({ "urls": urls }).to_string())
+        .send()
+        .map_err(|err| format!("harvester is not reachable: {err}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("harvester rejected the request: {status}"));
+    }
+    Ok(urls.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn frame(payload: &str) -> Vec<u8> {
+        let mut bytes = (payload.len() as u32).to_ne_bytes().to_vec();
+        bytes.extend_from_slice(payload.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn detects_browser_launch_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("harvester.json");
+        std::fs::write(&manifest, "{}").unwrap();
+
+        assert!(is_host_launch(["chrome-extension://abcdef/"]));
+        assert!(is_host_launch([
+            manifest.to_str().unwrap(),
+            "harvester@example.org",
+        ]));
+        assert!(is_host_launch(["--native-messaging"]));
+        assert!(!is_host_launch(Vec::<String>::new()));
+    }
+
+    #[test]
+    fn json_urls_and_missing_manifests_open_the_gui() {
+        assert!(!is_host_launch(["https://example.com/data.json"]));
+        assert!(!is_host_launch(["/no/such/dir/harvester.json"]));
+    }
+
+    #[test]
+    fn reads_framed_messages_until_eof() {
+        let mut input = frame(r#"{"url":"https://example.com"}"#);
+        input.extend(frame(
+            r#"{"urls":["https://a.example","https://b.example"]}"#,
+        ));
+        let mut reader = Cursor::new(input);
+
+        let first = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(parse_request(&first).unwrap(), vec!["https://example.com"]);
+        let second = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(parse_request(&second).unwrap().len(), 2);
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_oversized_and_empty_requests() {
+        let mut reader = Cursor::new((MAX_MESSAGE_BYTES + 1).to_ne_bytes().to_vec());
+        assert!(read_message(&mut reader).is_err());
+        assert!(parse_request(&json!({ "url": " " })).is_err());
+    }
+
+    #[test]
+    fn writes_length_prefixed_reply() {
+        let mut out = Vec::new();
+        write_message(&mut out, &json!({ "ok": true })).unwrap();
+        let len = u32::from_ne_bytes(out[..4].try_into().unwrap()) as usize;
+        assert_eq!(&out[4..], br#"{"ok":true}"#);
+        assert_eq!(len, out.len() - 4);
+    }
+}
@@ -0,0 +1,200 @@
+//! stdin/stdout pipe mode.
+//!
+//! Reads URLs line by line from stdin and streams one result per URL to
+//! stdout, in input order. Each result is either the markdown document or,
+//! with `--jsonl`, one JSON record per line. Blank lines and `#` comments
+//! are skipped. Failures go to stderr in markdown mode and become
+//! `"ok": false` records in JSONL mode. Nothing is written to disk.
+//!
+//! ```text
+//! cat urls.txt | harvester_app --pipe --jsonl | jq .tokens
+//! ```
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Utc;
+use engine_logging::engine_info;
+use harvester_engine::{harvest_one_with, EngineConfig, FailureKind, HarvestedDoc, ReqwestFetcher};
+use serde_json::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PipeFormat {
+    Markdown,
+    Jsonl,
+}
+
+/// `Some(format)` when `--pipe` is among the arguments.
+pub(crate) fn pipe_format<I, S>(args: I) -> Option<PipeFormat>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut pipe = false;
+    let mut format = PipeFormat::Markdown;
+    for arg in args {
+        match arg.as_ref() {
+            "--pipe" => pipe = true,
+            "--jsonl" => format = PipeFormat::Jsonl,
+            _ => {}
+        }
+    }
+    pipe.then_some(format)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PipeSummary {
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+/// Process stdin until EOF or until stdout is closed by the reader.
+pub(crate) fn run_pipe(format: PipeFormat) -> io::Result<PipeSummary> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let mut config = EngineConfig::default_with_output(PathBuf::from("."));
+    config.fetched_utc = Arc::new(|| Utc::now().to_rfc3339());
+    // One fetcher for the whole run so connections to the same host are reused.
+    let fetcher = ReqwestFetcher::new(config.fetch_settings.clone());
+
+    let mut summary = PipeSummary::default();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let url = line.trim();
+        if url.is_empty() || url.starts_with('#') {
+            continue;
+        }
+        let result = runtime.block_on(harvest_one_with(url, &config, &fetcher));
+        if result.is_ok() {
+            summary.succeeded += 1;
+        } else {
+            summary.failed += 1;
+        }
+        match write_record(&mut stdout, format, url, &result) {
+            Ok(()) => {}
+            // Downstream (`head`, a closed pager) stopped reading; not an error.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
+            Err(err) => return Err(err),
+        }
+    }
+    engine_info!(
+        "[Pipe] Done: {} succeeded, {} failed",
+        summary.succeeded,
+        summary.failed
+    );
+    Ok(summary)
+}
+
+fn write_record(
+    out: &mut impl Write,
+    format: PipeFormat,
+    url: &str,
+    result: &Result<HarvestedDoc, FailureKind>,
+) -> io::Result<()> {
+    match (format, result) {
+        (PipeFormat::Markdown, Ok(doc)) => {
+            out.write_all(doc.document.as_bytes())?;
+            if !doc.document.ends_with('\n') {
+                out.write_all(b"\n")?;
+            }
+        }
+        (PipeFormat::Markdown, Err(kind)) => {
+            eprintln!("{url}: {kind}");
+        }
+        (PipeFormat::Jsonl, Ok(doc)) => {
+            let record = json!({
+                "url": url,
+                "ok": true,
+                "final_url": doc.final_url,
+                "doc_id": doc.doc_id,
+                "title": doc.title,
+                "filename": doc.filename,
+                "tokens": doc.token_count,
+                "words": doc.reading.word_count,
+                "markdown": doc.document,
+            });
+            writeln!(out, "{record}")?;
+        }
+        (PipeFormat::Jsonl, Err(kind)) => {
+            let record = json!({ "url": url, "ok": false, "error": kind.to_string() });
+            writeln!(out, "{record}")?;
+        }
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harvester_engine::{QualityReport, ReadingMetrics};
+
+    fn doc() -> HarvestedDoc {
+        HarvestedDoc {
+            final_url: "https://example.com/final".to_string(),
+            canonical_url: None,
+            title: Some("Title".to_string()),
+            filename: "Title--abc.md".to_string(),
+            token_count: 2,
+            document: "---\nurl: https://example.com/final\n---\nHello world".to_string(),
+            content_preview: "Hello world".to_string(),
+            doc_id: "doc-1".to_string(),
+            links: Vec::new(),
+            quality: QualityReport::default(),
+            reading: ReadingMetrics::of("Hello world"),
+            content_warning: None,
+        }
+    }
+
+    #[test]
+    fn parses_pipe_flags() {
+        assert_eq!(pipe_format(["--pipe"]), Some(PipeFormat::Markdown));
+        assert_eq!(pipe_format(["--jsonl", "--pipe"]), Some(PipeFormat::Jsonl));
+        assert_eq!(pipe_format(["--jsonl"]), None);
+    }
+
+    #[test]
+    fn markdown_records_are_newline_terminated() {
+        let mut out = Vec::new();
+        write_record(
+            &mut out,
+            PipeFormat::Markdown,
+            "https://example.com",
+            &Ok(doc()),
+        )
+        .unwrap();
+        assert!(String::from_utf8(out).unwrap().ends_with("Hello world\n"));
+
+        let mut out = Vec::new();
+        let failed = Err(FailureKind::HttpStatus(404));
+        write_record(&mut out, PipeFormat::Markdown, "https://x", &failed).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn jsonl_records_are_one_object_per_line() {
+        let mut out = Vec::new();
+        write_record(
+            &mut out,
+            PipeFormat::Jsonl,
+            "https://example.com",
+            &Ok(doc()),
+        )
+        .unwrap();
+        let failed = Err(FailureKind::HttpStatus(404));
+        write_record(&mut out, PipeFormat::Jsonl, "https://x", &failed).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["tokens"], 2);
+        assert_eq!(lines[0]["title"], "Title");
+        assert_eq!(lines[0]["doc_id"], "doc-1");
+        assert_eq!(lines[1]["ok"], false);
+        assert_eq!(lines[1]["error"], FailureKind::HttpStatus(404).to_string());
+    }
+}
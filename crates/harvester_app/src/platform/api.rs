@@ -0,0 +1,380 @@
+//! Optional local REST API for driving a running instance from scripts or
+//! browser extensions.
+//!
+//! Writes are translated into core `Msg`s and go through the normal update
+//! loop; reads are served from the last view model the front-end published.
+//!
+//! - `POST /jobs` with `{"urls": ["https://…"]}` enqueues URLs.
+//! - `GET /jobs` lists job rows.
+//! - `GET /jobs/{id}/markdown` returns the written markdown document.
+//! - `POST /export` requests a full archive export.
+//! - `POST /export/images` writes the session's image links to `images.csv`.
+//! - `GET /history?url=…` lists the dated snapshots kept for a URL.
+use std::convert::Infallible;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use engine_logging::{engine_error, engine_info, engine_warn};
+use harvester_core::{AppViewModel, JobId, Msg};
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::CONTENT_TYPE;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::net::TcpListener;
+
+use super::effects::DocumentIndex;
+use crate::remote;
+
+const JSON: &str = "application/json";
+const MARKDOWN: &str = "text/markdown; charset=utf-8";
+
+/// State shared between the front-end and the API server thread.
+pub(crate) struct ApiShared {
+    msg_tx: mpsc::Sender<Msg>,
+    view: Mutex<AppViewModel>,
+    documents: DocumentIndex,
+    output_dir: PathBuf,
+}
+
+impl ApiShared {
+    pub(crate) fn new(
+        msg_tx: mpsc::Sender<Msg>,
+        documents: DocumentIndex,
+        output_dir: PathBuf,
+    ) -> Self {
+        Self {
+            msg_tx,
+            view: Mutex::new(AppViewModel::default()),
+            documents,
+            output_dir,
+        }
+    }
+
+    /// Called by the front-end whenever it renders a new view model.
+    pub(crate) fn publish_view(&self, view: &AppViewModel) {
+        if let Ok(mut guard) = self.view.lock() {
+            *guard = view.clone();
+        }
+    }
+}
+
+/// Start the API server on a background thread, listening on [`remote::api_addr`].
+pub(crate) fn start(shared: Arc<ApiShared>) {
+    let addr = remote::api_addr();
+    thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(err) => {
+                engine_error!("[Api] Failed to create runtime: {}", err);
+                return;
+            }
+        };
+        if let Err(err) = runtime.block_on(serve(&addr, shared)) {
+            engine_error!("[Api] Server on {} stopped: {}", addr, err);
+        }
+    });
+}
+
+async fn serve(addr: &str, shared: Arc<ApiShared>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    engine_info!("[Api] Listening on http://{}", listener.local_addr()?);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let shared = shared.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |req| handle(req, shared.clone()));
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                engine_warn!("[Api] Connection error: {}", err);
+            }
+        });
+    }
+}
+
+async fn handle(
+    req: Request<Incoming>,
+    shared: Arc<ApiShared>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+    let query = req.uri().query().unwrap_or_default().to_owned();
+    let body = match req.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(err) => {
+            engine_warn!("[Api] Failed to read request body: {}", err);
+            Bytes::new()
+        }
+    };
+    let reply = route(&method, &path, &query, &body, &shared);
+    engine_info!("[Api] {} {} -> {}", method, path, reply.status.as_u16());
+    Ok(reply.into_response())
+}
+
+#[derive(Debug, PartialEq)]
+struct ApiReply {
+    status: StatusCode,
+    content_type: &'static str,
+    body: String,
+}
+
+impl ApiReply {
+    fn json(status: StatusCode, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: JSON,
+            body: value.to_string(),
+        }
+    }
+
+    fn error(status: StatusCode, message: &str) -> Self {
+        Self::json(status, json!({ "error": message }))
+    }
+
+    fn into_response(self) -> Response<Full<Bytes>> {
+        let mut response = Response::new(Full::new(Bytes::from(self.body)));
+        *response.status_mut() = self.status;
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            self.content_type.parse().expect("static header"),
+        );
+        response
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EnqueueRequest {
+    urls: Vec<String>,
+}
+
+fn route(method: &Method, path: &str, query: &str, body: &[u8], shared: &ApiShared) -> ApiReply {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (&Method::POST, ["jobs"]) => enqueue_jobs(body, shared),
+        (&Method::GET, ["jobs"]) => list_jobs(shared),
+        (&Method::GET, ["jobs", id, "markdown"]) => job_markdown(id, shared),
+        (&Method::POST, ["export"]) => send(shared, Msg::ArchiveClicked, json!({})),
+        (&Method::POST, ["export", "images"]) => {
+            send(shared, Msg::ImageInventoryRequested, json!({}))
+        }
+        (&Method::GET, ["history"]) => url_history(query, shared),
+        (
+            _,
+            ["jobs"] | ["jobs", _, "markdown"] | ["export"] | ["export", "images"] | ["history"],
+        ) => ApiReply::error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed"),
+        _ => ApiReply::error(StatusCode::NOT_FOUND, "not found"),
+    }
+}
+
+fn enqueue_jobs(body: &[u8], shared: &ApiShared) -> ApiReply {
+    let request: EnqueueRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return ApiReply::error(StatusCode::BAD_REQUEST, &err.to_string()),
+    };
+    let submitted = request.urls.len();
+    send(
+        shared,
+        Msg::UrlsReceived(request.urls),
+        json!({ "submitted": submitted }),
+    )
+}
+
+fn send(shared: &ApiShared, msg: Msg, accepted: serde_json::Value) -> ApiReply {
+    match shared.msg_tx.send(msg) {
+        Ok(()) => ApiReply::json(StatusCode::ACCEPTED, accepted),
+        Err(_) => ApiReply::error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "application is shutting down",
+        ),
+    }
+}
+
+fn list_jobs(shared: &ApiShared) -> ApiReply {
+    let view = match shared.view.lock() {
+        Ok(guard) => guard,
+        Err(_) => return ApiReply::error(StatusCode::INTERNAL_SERVER_ERROR, "state unavailable"),
+    };
+    ApiReply::json(
+        StatusCode::OK,
+        json!({ "session": view.session, "jobs": view.jobs }),
+    )
+}
+
+fn job_markdown(id: &str, shared: &ApiShared) -> ApiReply {
+    let Ok(job_id) = id.parse::<JobId>() else {
+        return ApiReply::error(StatusCode::BAD_REQUEST, "invalid job id");
+    };
+    let path = shared
+        .documents
+        .lock()
+        .ok()
+        .and_then(|index| index.get(&job_id).cloned());
+    let Some(path) = path else {
+        return ApiReply::error(StatusCode::NOT_FOUND, "no document for job");
+    };
+    match fs::read_to_string(&path) {
+        Ok(markdown) => ApiReply {
+            status: StatusCode::OK,
+            content_type: MARKDOWN,
+            body: markdown,
+        },
+        Err(err) => {
+            engine_warn!("[Api] Failed to read {:?}: {}", path, err);
+            ApiReply::error(StatusCode::NOT_FOUND, "document not readable")
+        }
+    }
+}
+
+fn url_history(query: &str, shared: &ApiShared) -> ApiReply {
+    let url = url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "url")
+        .map(|(_, value)| value.into_owned());
+    let Some(url) = url.filter(|url| !url.is_empty()) else {
+        return ApiReply::error(StatusCode::BAD_REQUEST, "missing url parameter");
+    };
+    match harvester_engine::page_history(&shared.output_dir, &url) {
+        Ok(versions) => {
+            let versions: Vec<_> = versions
+                .iter()
+                .map(|version| json!({ "date": version.date, "path": version.path }))
+                .collect();
+            ApiReply::json(StatusCode::OK, json!({ "url": url, "versions": versions }))
+        }
+        Err(err) => {
+            engine_warn!("[Api] Failed to read history for {}: {}", url, err);
+            ApiReply::error(StatusCode::INTERNAL_SERVER_ERROR, "history unavailable")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harvester_core::{JobRowView, Stage};
+    use tempfile::tempdir;
+
+    fn shared() -> (ApiShared, mpsc::Receiver<Msg>) {
+        let (msg_tx, msg_rx) = mpsc::channel();
+        (
+            ApiShared::new(msg_tx, DocumentIndex::default(), PathBuf::from("missing")),
+            msg_rx,
+        )
+    }
+
+    #[test]
+    fn post_jobs_forwards_urls_to_core() {
+        let (shared, msg_rx) = shared();
+        let reply = route(
+            &Method::POST,
+            "/jobs",
+            "",
+            br#"{"urls": ["https://example.com/a"]}"#,
+            &shared,
+        );
+        assert_eq!(reply.status, StatusCode::ACCEPTED);
+        assert_eq!(
+            msg_rx.try_recv().unwrap(),
+            Msg::UrlsReceived(vec!["https://example.com/a".to_string()])
+        );
+
+        let reply = route(&Method::POST, "/jobs", "", b"not json", &shared);
+        assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn get_jobs_serves_published_view() {
+        let (shared, _msg_rx) = shared();
+        shared.publish_view(&AppViewModel {
+            jobs: vec![JobRowView {
+                job_id: 7,
+                url: "https://example.com".to_string(),
+                stage: Stage::Downloading,
+                outcome: None,
+                tokens: None,
+                bytes: Some(10),
+                watched: false,
+                changed_from: None,
+                duplicate_of: None,
+                change: None,
+                retry_in_secs: None,
+                quality_score: None,
+                low_quality: false,
+                nav_heavy: false,
+                word_count: None,
+                reading_minutes: None,
+                elapsed_ms: None,
+                pinned: false,
+                failure: None,
+            }],
+            ..AppViewModel::default()
+        });
+        let reply = route(&Method::GET, "/jobs", "", b"", &shared);
+        let body: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+        assert_eq!(body["jobs"][0]["job_id"], 7);
+        assert_eq!(body["jobs"][0]["stage"], "Downloading");
+    }
+
+    #[test]
+    fn markdown_is_read_from_document_index() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("doc.md");
+        fs::write(&path, "# Title\n").unwrap();
+        let (shared, _msg_rx) = shared();
+        shared.documents.lock().unwrap().insert(3, path);
+
+        let reply = route(&Method::GET, "/jobs/3/markdown", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::OK);
+        assert_eq!(reply.content_type, MARKDOWN);
+        assert_eq!(reply.body, "# Title\n");
+
+        let reply = route(&Method::GET, "/jobs/4/markdown", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn export_and_unknown_routes() {
+        let (shared, msg_rx) = shared();
+        let reply = route(&Method::POST, "/export", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::ACCEPTED);
+        assert_eq!(msg_rx.try_recv().unwrap(), Msg::ArchiveClicked);
+
+        let reply = route(&Method::GET, "/export", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::METHOD_NOT_ALLOWED);
+        let reply = route(&Method::GET, "/nope", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn history_lists_snapshots_for_url() {
+        let temp = tempdir().unwrap();
+        let (msg_tx, _msg_rx) = mpsc::channel();
+        let shared = ApiShared::new(msg_tx, DocumentIndex::default(), temp.path().into());
+        let url = "https://example.com/page";
+        let dir = temp.path().join(
+            harvester_engine::deterministic_filename(Some("Page"), url).trim_end_matches(".md"),
+        );
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("2024-05-01.md"), "old").unwrap();
+        fs::write(dir.join("2024-05-02.md"), "new").unwrap();
+
+        let query = "url=https%3A%2F%2Fexample.com%2Fpage";
+        let reply = route(&Method::GET, "/history", query, b"", &shared);
+        assert_eq!(reply.status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+        assert_eq!(body["versions"][1]["date"], "2024-05-02");
+
+        let reply = route(&Method::GET, "/history", "", b"", &shared);
+        assert_eq!(reply.status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -7,36 +8,85 @@ use commanductui::{
     AppEvent, PlatformCommand, PlatformEventHandler, PlatformInterface, UiStateProvider,
     WindowConfig, WindowId,
 };
-use harvester_core::{update, AppState, AppViewModel, Effect, JobResultKind, Msg};
+use harvester_core::{
+    update, AppState, AppViewModel, Effect, JobColumn, LinkKind, LinkScope, Msg, PanelWidths,
+    Splitter, WindowGeometry,
+};
 
-use engine_logging::engine_info;
+use engine_logging::{engine_error, engine_info, engine_warn};
+use harvester_engine::Passphrase;
 
+#[cfg(feature = "api")]
+use super::api;
 use super::effects::EffectRunner;
+use super::journal::MsgRecorder;
 use super::logging::{self, LogDestination};
 use super::ui;
-use super::{effects, persistence};
+use super::window_placement::MainWindow;
+use super::{effects, format, persistence};
+use crate::single_instance::InstanceLock;
+
+const WINDOW_TITLE: &str = "Harvester";
+
+/// Tell the user why the app cannot start; no window exists yet, so a plain
+/// message box is used.
+fn show_startup_error(text: &str) {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (text, caption) = (wide(text), wide("Harvester"));
+    // SAFETY: both strings are NUL-terminated and outlive the call.
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            text.as_ptr(),
+            caption.as_ptr(),
+            MB_ICONERROR | MB_OK,
+        )
+    };
+}
 
-pub fn run_app() -> commanductui::PlatformResult<()> {
+/// `launch_urls` are submitted once the previous session is restored, and
+/// `lock` receives URLs from later launches while the window is open.
+pub fn run_app(
+    lock: Option<InstanceLock>,
+    launch_urls: Vec<String>,
+) -> commanductui::PlatformResult<()> {
     logging::initialize(LogDestination::Both);
     engine_info!("Logger initialized. Starting harvester_app...");
 
     let platform = PlatformInterface::new("harvester_app".to_string())?;
-    let window_id = platform.create_window(WindowConfig {
-        title: "Harvester",
-        width: 960,
-        height: 720,
-    })?;
 
     let shared_state = Arc::new(Mutex::new(SharedState::default()));
-    let output_dir = effects::default_output_dir();
     let (msg_tx, msg_rx) = mpsc::channel::<Msg>();
-    let effect_runner = EffectRunner::new(msg_tx.clone());
+    if let Some(lock) = lock {
+        lock.forward_to(msg_tx.clone());
+    }
+    let mut effect_runner = match EffectRunner::new(msg_tx.clone()) {
+        Ok(runner) => runner,
+        Err(err) => {
+            let text = format!(
+                "Failed to start engine in {:?}: {}",
+                effects::default_output_dir(),
+                err
+            );
+            engine_error!("{}", text);
+            show_startup_error(&text);
+            std::process::exit(1);
+        }
+    };
+    let output_dir = effect_runner.output_dir().to_path_buf();
+    let mut recorder = MsgRecorder::from_env(&output_dir);
     {
-        let completed = persistence::load_completed_jobs(&output_dir);
-        if !completed.is_empty() {
-            let mut guard = shared_state.lock().unwrap();
+        let mut restore_msgs = session_restore_msgs(&output_dir, effect_runner.passphrase());
+        if !launch_urls.is_empty() {
+            restore_msgs.push(Msg::UrlsReceived(launch_urls));
+        }
+        let mut guard = shared_state.lock().unwrap();
+        for msg in restore_msgs {
+            recorder.record(&msg);
             let state = std::mem::take(&mut guard.state);
-            let (state, effects) = update(state, Msg::RestoreCompletedJobs(completed));
+            let (state, effects) = update(state, msg);
             if !effects.is_empty() {
                 effect_runner.enqueue(effects);
             }
@@ -44,25 +94,49 @@ pub fn run_app() -> commanductui::PlatformResult<()> {
         }
     }
 
+    // Created once the session is restored so it opens at the saved size;
+    // position and maximized state follow once the UI is set up.
+    let geometry = shared_state.lock().unwrap().state.window_geometry();
+    let window_id = platform.create_window(WindowConfig {
+        title: WINDOW_TITLE,
+        width: geometry.width,
+        height: geometry.height,
+    })?;
+
     let initial_view = shared_state.lock().unwrap().state.view();
     let mut tree_render_state = ui::render::TreeRenderState::new();
-    let mut initial_commands = ui::layout::initial_commands(window_id);
+    let mut initial_commands = ui::layout::initial_commands(window_id, initial_view.panel_widths);
     initial_commands.extend(ui::render::render(
         window_id,
         &initial_view,
         &mut tree_render_state,
     ));
 
-    let event_handler: Arc<Mutex<dyn PlatformEventHandler>> =
-        Arc::new(Mutex::new(AppEventHandler::new(
-            window_id,
-            shared_state.clone(),
-            msg_rx,
+    let mut handler = AppEventHandler::new(
+        window_id,
+        shared_state.clone(),
+        msg_rx,
+        msg_tx.clone(),
+        effect_runner,
+        tree_render_state,
+        output_dir,
+    );
+    handler.recorder = recorder;
+    handler.panel_widths = initial_view.panel_widths;
+    handler.geometry = geometry;
+    handler.view = initial_view.clone();
+    #[cfg(feature = "api")]
+    {
+        let shared = Arc::new(api::ApiShared::new(
             msg_tx.clone(),
-            effect_runner,
-            tree_render_state,
-            output_dir,
-        )));
+            handler.effect_runner.documents(),
+            output_dir.clone(),
+        ));
+        shared.publish_view(&initial_view);
+        api::start(shared.clone());
+        handler.api = Some(shared);
+    }
+    let event_handler: Arc<Mutex<dyn PlatformEventHandler>> = Arc::new(Mutex::new(handler));
     let ui_state_provider: Arc<Mutex<dyn UiStateProvider>> =
         Arc::new(Mutex::new(AppUiStateProvider::new(shared_state)));
 
@@ -77,11 +151,18 @@ pub fn run_app() -> commanductui::PlatformResult<()> {
     platform.main_event_loop(event_handler, ui_state_provider, initial_commands)
 }
 
-#[derive(Default)]
 struct SharedState {
     state: AppState,
 }
 
+impl Default for SharedState {
+    fn default() -> Self {
+        Self {
+            state: effects::initial_state(),
+        }
+    }
+}
+
 struct AppEventHandler {
     window_id: WindowId,
     shared: Arc<Mutex<SharedState>>,
@@ -90,7 +171,18 @@ struct AppEventHandler {
     msg_tx: mpsc::Sender<Msg>,
     effect_runner: EffectRunner,
     tree_render_state: ui::render::TreeRenderState,
+    /// Widths of the layout last sent to the window.
+    panel_widths: PanelWidths,
+    /// View last rendered; cycling buttons pick their next option from it.
+    view: AppViewModel,
+    /// Found once the UI is set up; `None` until then.
+    main_window: Option<MainWindow>,
+    /// Geometry last sent as `Msg::WindowGeometryChanged`.
+    geometry: WindowGeometry,
     output_dir: std::path::PathBuf,
+    recorder: MsgRecorder,
+    #[cfg(feature = "api")]
+    api: Option<Arc<api::ApiShared>>,
 }
 
 impl AppEventHandler {
@@ -111,7 +203,14 @@ impl AppEventHandler {
             msg_tx,
             effect_runner,
             tree_render_state,
+            panel_widths: PanelWidths::default(),
+            view: AppViewModel::default(),
+            main_window: None,
+            geometry: WindowGeometry::default(),
             output_dir,
+            recorder: MsgRecorder::default(),
+            #[cfg(feature = "api")]
+            api: None,
         }
     }
 
@@ -128,38 +227,32 @@ impl AppEventHandler {
     }
 
     fn dispatch_msg(&mut self, msg: Msg) {
-        let (maybe_view, clear_input) = {
+        self.recorder.record(&msg);
+        if matches!(msg, Msg::Tick) {
+            engine_logging::set_sim_tick(engine_logging::get_sim_tick() + 1);
+            // Moves and (un)maximizing raise no event; poll the placement.
+            self.record_geometry();
+        }
+        let activated = matches!(msg, Msg::ProfileActivated(_));
+        let (maybe_view, clear_input, quit) = {
             let msg_for_log = msg.clone();
             let mut guard = self.shared.lock().expect("lock shared state");
             let state = std::mem::take(&mut guard.state);
             let (state, effects) = update(state, msg);
-            let should_persist = matches!(
-                msg_for_log,
-                Msg::JobDone {
-                    result: JobResultKind::Success,
-                    ..
-                }
-            );
-            let clear_input = effects
-                .iter()
-                .any(|effect| matches!(effect, Effect::EnqueueUrl { .. }));
+            let clear_input = matches!(msg_for_log, Msg::UrlsSubmitted | Msg::PasteConfirmed)
+                && effects
+                    .iter()
+                    .any(|effect| matches!(effect, Effect::EnqueueUrl { .. }));
+            let quit = effects.contains(&Effect::Quit);
             let view = state.view();
             let mut state = state;
-            let completed_snapshot = if should_persist {
-                Some(state.completed_jobs_snapshot())
-            } else {
-                None
-            };
             let was_dirty = state.consume_dirty();
             guard.state = state;
             self.effect_runner.enqueue(effects);
-            if let Some(snapshot) = completed_snapshot {
-                persistence::save_completed_jobs(&self.output_dir, &snapshot);
-            }
             if was_dirty {
-                (Some(view), clear_input)
+                (Some(view), clear_input, quit)
             } else {
-                (None, clear_input)
+                (None, clear_input, quit)
             }
         };
 
@@ -174,21 +267,90 @@ impl AppEventHandler {
         if let Some(view) = maybe_view {
             self.enqueue_render(&view);
         }
+        if quit {
+            self.commands.push_back(PlatformCommand::QuitApplication);
+        }
+        if activated {
+            self.output_dir = self.effect_runner.output_dir().to_path_buf();
+            for msg in session_restore_msgs(&self.output_dir, self.effect_runner.passphrase()) {
+                self.dispatch_msg(msg);
+            }
+        }
+    }
+
+    /// Send the window's geometry when it moved, was resized or (un)maximized.
+    fn record_geometry(&mut self) {
+        let Some(geometry) = self.main_window.as_ref().and_then(MainWindow::geometry) else {
+            return;
+        };
+        if geometry != self.geometry {
+            self.geometry = geometry;
+            let _ = self.msg_tx.send(Msg::WindowGeometryChanged(geometry));
+        }
     }
 
     fn enqueue_render(&mut self, view: &AppViewModel) {
+        #[cfg(feature = "api")]
+        if let Some(api) = &self.api {
+            api.publish_view(view);
+        }
+        if view.panel_widths != self.panel_widths {
+            self.panel_widths = view.panel_widths;
+            self.commands.push_back(ui::layout::layout_command(
+                self.window_id,
+                view.panel_widths,
+            ));
+        }
         self.commands.extend(ui::render::render(
             self.window_id,
             view,
             &mut self.tree_render_state,
         ));
+        self.view = view.clone();
     }
 }
 
+fn splitter_for(control_id: commanductui::types::ControlId) -> Option<Splitter> {
+    [
+        (ui::constants::SPLITTER_INPUT_JOBS, Splitter::InputJobs),
+        (ui::constants::SPLITTER_JOBS_PREVIEW, Splitter::JobsPreview),
+    ]
+    .into_iter()
+    .find_map(|(id, splitter)| (id == control_id).then_some(splitter))
+}
+
+/// The jobs list column a header button sorts by.
+fn job_column_for(control_id: commanductui::types::ControlId) -> Option<JobColumn> {
+    ui::constants::BUTTON_JOB_COLUMNS
+        .into_iter()
+        .zip(JobColumn::ALL)
+        .find_map(|(id, column)| (id == control_id).then_some(column))
+}
+
+/// The links-pane filter a button toggles, as the message it sends.
+fn link_filter_msg(control_id: commanductui::types::ControlId) -> Option<Msg> {
+    let kinds = ui::constants::BUTTON_LINK_KINDS
+        .into_iter()
+        .zip(LinkKind::ALL)
+        .map(|(id, kind)| (id, Msg::LinkKindToggled(kind)));
+    let scopes = ui::constants::BUTTON_LINK_SCOPES
+        .into_iter()
+        .zip(LinkScope::ALL)
+        .map(|(id, scope)| (id, Msg::LinkScopeToggled(scope)));
+    kinds
+        .chain(scopes)
+        .find_map(|(id, msg)| (id == control_id).then_some(msg))
+}
+
 impl PlatformEventHandler for AppEventHandler {
     fn handle_event(&mut self, event: AppEvent) {
         match event {
             AppEvent::MainWindowUISetupComplete { .. } => {
+                self.main_window = MainWindow::find(WINDOW_TITLE);
+                match &self.main_window {
+                    Some(window) => window.restore(self.geometry),
+                    None => engine_warn!("Main window not found; its position is not restored"),
+                }
                 let _ = self.msg_tx.send(Msg::Tick);
             }
             AppEvent::ButtonClicked { control_id, .. }
@@ -196,11 +358,109 @@ impl PlatformEventHandler for AppEventHandler {
             {
                 let _ = self.msg_tx.send(Msg::StopFinishClicked);
             }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_STOP_NOW =>
+            {
+                // The first click relabels the button; the second one confirms.
+                let _ = self.msg_tx.send(Msg::StopNowClicked);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_HARVEST_PASTE =>
+            {
+                let _ = self.msg_tx.send(Msg::PasteConfirmed);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_DISCARD_PASTE =>
+            {
+                let _ = self.msg_tx.send(Msg::PasteCancelled);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_NEW_SESSION =>
+            {
+                let _ = self.msg_tx.send(Msg::NewSessionRequested);
+            }
             AppEvent::ButtonClicked { control_id, .. }
                 if control_id == ui::constants::BUTTON_ARCHIVE =>
             {
                 let _ = self.msg_tx.send(Msg::ArchiveClicked);
             }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_ARCHIVE_NEW =>
+            {
+                let _ = self.msg_tx.send(Msg::ArchiveNewClicked);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_EXPORT_IMAGES =>
+            {
+                let _ = self.msg_tx.send(Msg::ImageInventoryRequested);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_WATCH =>
+            {
+                let _ = self.msg_tx.send(Msg::ToggleWatchSelected {
+                    interval_secs: effects::DEFAULT_WATCH_INTERVAL_SECS,
+                });
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_PIN =>
+            {
+                let _ = self.msg_tx.send(Msg::TogglePinSelected);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_COPY_URL =>
+            {
+                let _ = self.msg_tx.send(Msg::CopySelectedUrl);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_OPEN_URL =>
+            {
+                let _ = self.msg_tx.send(Msg::OpenSelectedUrl);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_BUDGET =>
+            {
+                let budget = format::next_budget_preset(self.view.budget);
+                let _ = self.msg_tx.send(Msg::BudgetPresetSelected(budget));
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_LOG_LEVEL =>
+            {
+                let level = format::next_log_level(self.view.log_level);
+                let _ = self.msg_tx.send(Msg::LogLevelSelected(level));
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_MIN_QUALITY =>
+            {
+                let min_quality = format::next_export_min_quality(self.view.export_min_quality);
+                let _ = self.msg_tx.send(Msg::ExportMinQualitySelected(min_quality));
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_PROFILE =>
+            {
+                if let Some(name) = format::next_profile(&self.view) {
+                    let _ = self.msg_tx.send(Msg::ProfileSelected(name));
+                }
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_DIFF =>
+            {
+                let _ = self.msg_tx.send(Msg::DiffModeToggled);
+            }
+            AppEvent::ButtonClicked { control_id, .. }
+                if control_id == ui::constants::BUTTON_HARVEST_LINKS =>
+            {
+                let _ = self.msg_tx.send(Msg::HarvestCheckedLinks);
+            }
+            AppEvent::ButtonClicked { control_id, .. } if job_column_for(control_id).is_some() => {
+                if let Some(column) = job_column_for(control_id) {
+                    let _ = self.msg_tx.send(Msg::JobColumnClicked(column));
+                }
+            }
+            AppEvent::ButtonClicked { control_id, .. } if link_filter_msg(control_id).is_some() => {
+                if let Some(msg) = link_filter_msg(control_id) {
+                    let _ = self.msg_tx.send(msg);
+                }
+            }
             AppEvent::InputTextChanged {
                 control_id, text, ..
             } if control_id == ui::constants::INPUT_URLS => {
@@ -213,12 +473,60 @@ impl PlatformEventHandler for AppEventHandler {
                 let _ = self.msg_tx.send(Msg::UrlsSubmitted);
             }
             AppEvent::TreeViewItemSelectionChanged { window_id, item_id }
-                if window_id == self.window_id =>
+                if window_id == self.window_id && !ui::render::is_link_item(item_id) =>
             {
                 let _ = self.msg_tx.send(Msg::JobSelected { job_id: item_id.0 });
             }
+            AppEvent::TreeViewItemToggledByUser { item_id, .. } => {
+                if let Some(link) = ui::render::link_for_item(&self.view, item_id) {
+                    let msg = Msg::LinkCheckToggled {
+                        url: link.url.clone(),
+                    };
+                    let _ = self.msg_tx.send(msg);
+                }
+            }
+            AppEvent::SplitterDragging {
+                control_id,
+                desired_left_width_px,
+                ..
+            } => {
+                if let Some(splitter) = splitter_for(control_id) {
+                    let _ = self.msg_tx.send(Msg::SplitterDragged {
+                        splitter,
+                        desired_left_width_px,
+                    });
+                }
+            }
+            AppEvent::SplitterDragEnded {
+                control_id,
+                desired_left_width_px,
+                ..
+            } => {
+                if let Some(splitter) = splitter_for(control_id) {
+                    engine_info!(
+                        "Splitter {:?} released at {}px",
+                        splitter,
+                        desired_left_width_px
+                    );
+                    let _ = self.msg_tx.send(Msg::SplitterReleased {
+                        splitter,
+                        desired_left_width_px,
+                    });
+                }
+            }
+            AppEvent::WindowResized {
+                window_id, width, ..
+            } if window_id == self.window_id => {
+                let _ = self.msg_tx.send(Msg::WindowResized {
+                    window_width: width,
+                });
+                self.record_geometry();
+            }
             AppEvent::WindowCloseRequestedByUser { .. } => {
-                self.commands.push_back(PlatformCommand::QuitApplication);
+                // Quits once the engine has stopped; see `Effect::Quit`.
+                let _ = self.msg_tx.send(Msg::CloseRequested {
+                    policy: effects::close_policy(),
+                });
             }
             _ => {}
         }
@@ -246,3 +554,30 @@ impl UiStateProvider for AppUiStateProvider {
         false
     }
 }
+
+/// Messages that restore the session saved in `output_dir`.
+fn session_restore_msgs(output_dir: &Path, passphrase: Option<&Passphrase>) -> Vec<Msg> {
+    let session = persistence::load_session(output_dir, passphrase);
+    let mut msgs = Vec::new();
+    if !session.completed.is_empty() {
+        msgs.push(Msg::RestoreCompletedJobs(session.completed));
+    }
+    if let Some(exported_utc) = session.last_export_utc {
+        msgs.push(Msg::RestoreLastExport(exported_utc));
+    }
+    if session.recovered_from_backup {
+        msgs.push(Msg::StateRecoveredFromBackup);
+    }
+    msgs.push(Msg::RestoreBudget(session.budget.unwrap_or_default()));
+    msgs.push(Msg::RestoreLogLevel(logging::startup_level(
+        session.log_level,
+    )));
+    msgs.push(Msg::RestoreExportMinQuality(session.export_min_quality));
+    if let Some(widths) = session.panel_widths {
+        msgs.push(Msg::RestorePanelWidths(widths));
+    }
+    if let Some(geometry) = session.window_geometry {
+        msgs.push(Msg::WindowGeometryChanged(geometry));
+    }
+    msgs
+}
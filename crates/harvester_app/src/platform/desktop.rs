@@ -0,0 +1,94 @@
+//! Clipboard and browser hand-offs through the operating system's own tools,
+//! so neither front-end needs a clipboard or browser crate.
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// Put `text` on the clipboard with `clip` (Windows), `pbcopy` (macOS) or,
+/// elsewhere, `wl-copy` falling back to `xclip`.
+pub(crate) fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "windows") {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[("wl-copy", &[]), ("xclip", &["-selection", "clipboard"])]
+    };
+    let mut last_err = io::Error::new(io::ErrorKind::NotFound, "no clipboard tool");
+    for (program, args) in candidates {
+        match pipe_to(program, args, text) {
+            Ok(()) => return Ok(()),
+            Err(err) => last_err = err,
+        }
+    }
+    Err(last_err)
+}
+
+/// Open `url` in the default browser. Only http(s) and file URLs are handed
+/// to the shell.
+pub(crate) fn open_in_browser(url: &str) -> io::Result<()> {
+    if !is_openable(url) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("refusing to open {url:?}"),
+        ));
+    }
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+fn is_openable(url: &str) -> bool {
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+    matches!(scheme.as_deref(), Some("http" | "https" | "file"))
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{program} exited with {status}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_web_and_file_urls_are_opened() {
+        assert!(is_openable("https://example.com/a"));
+        assert!(is_openable("HTTP://example.com"));
+        assert!(is_openable("file:///tmp/page.html"));
+        assert!(!is_openable("javascript:alert(1)"));
+        assert!(!is_openable("example.com"));
+        assert!(open_in_browser("calc.exe").is_err());
+    }
+}
@@ -1,11 +1,24 @@
-use std::sync::mpsc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
 use chrono::Utc;
 use engine_logging::{engine_info, engine_warn};
-use harvester_core::{Effect, JobResultKind, Msg, Stage, StopPolicy};
-use harvester_engine::{EngineConfig, EngineEvent, EngineHandle};
+use harvester_core::{
+    intercept_effects, AppState, ContentChange, Effect, EffectInterceptor, JobId, JobLink,
+    JobResultKind, LinkKind, Msg, Stage, StopPolicy, DEFAULT_ALLOWED_SCHEMES,
+};
+use harvester_engine::{
+    write_image_inventory, ApproxTokenCounter, Browser, ChunkOptions, ContentDiff, CookieJar,
+    EngineBuildError, EngineBuilder, EngineEvent, EngineHandle, ExportFormat, ExportScope,
+    ExtractedLink, FailureKind, FetchSettings, FilenameOptions, FitOrder, ImageRef, Passphrase,
+    WarcArchive, WhitespaceTokenCounter,
+};
+
+use super::profiles::{self, OutputProfile, DEFAULT_PROFILE};
+use super::{desktop, logging, persistence};
 
 pub(crate) fn default_output_dir() -> std::path::PathBuf {
     std::env::current_dir()
@@ -13,58 +26,372 @@ pub(crate) fn default_output_dir() -> std::path::PathBuf {
         .join("output")
 }
 
+/// Desktop session state: web URLs plus saved pages on disk (`file://` URLs
+/// or pasted and dropped paths).
+pub(crate) fn initial_state() -> AppState {
+    AppState::new().with_allowed_schemes(DEFAULT_ALLOWED_SCHEMES.into_iter().chain(["file"]))
+}
+
+/// Re-check interval used when the user starts watching a page.
+pub(crate) const DEFAULT_WATCH_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Set to `1` to keep dated snapshots of each page instead of overwriting.
+const VERSIONED_SNAPSHOTS_ENV: &str = "HARVESTER_VERSIONED_SNAPSHOTS";
+
+/// Set to `1` to follow "next page" links and write multi-page articles as one document.
+const STITCH_PAGES_ENV: &str = "HARVESTER_STITCH_PAGES";
+
+/// Set to `1` to strip blocks that repeat across pages of one site (headers,
+/// footers, sidebars) from later pages.
+const STRIP_SITE_CHROME_ENV: &str = "HARVESTER_STRIP_SITE_CHROME";
+
+/// Set to `1` to count tokens with the fast estimator while harvesting and
+/// recount each written document with the regular counter in the background.
+const APPROX_TOKENS_ENV: &str = "HARVESTER_APPROX_TOKENS";
+
+/// Set to `1` to fail pages containing bytes invalid in their encoding
+/// instead of replacing those bytes.
+const STRICT_DECODE_ENV: &str = "HARVESTER_STRICT_DECODE";
+
+/// Set to `1` to transliterate titles to ASCII in document filenames.
+const ASCII_FILENAMES_ENV: &str = "HARVESTER_ASCII_FILENAMES";
+
+/// Export token-bounded chunks as JSONL instead of one text file:
+/// `{max_tokens}` or `{max_tokens}:{overlap_tokens}`, e.g. `512:64`.
+const EXPORT_CHUNKS_ENV: &str = "HARVESTER_EXPORT_CHUNKS";
+
+/// Set to `1` to export an Obsidian vault (`output/vault/`) instead of one text file.
+const EXPORT_OBSIDIAN_ENV: &str = "HARVESTER_EXPORT_OBSIDIAN";
+
+/// Set to `1` to export one self-contained HTML file instead of one text file.
+const EXPORT_HTML_ENV: &str = "HARVESTER_EXPORT_HTML";
+
+/// Export only as many documents as fit the token budget, offered in this
+/// order: `filename`, `newest`, `published`, `quality` or `smallest` (`1`
+/// means `filename`).
+const EXPORT_FIT_ENV: &str = "HARVESTER_EXPORT_FIT";
+
+/// Export only documents published in this range of `YYYY-MM-DD` dates,
+/// `{from}..{until}` with either end left empty, e.g. `2024-01-01..`.
+const EXPORT_PUBLISHED_ENV: &str = "HARVESTER_EXPORT_PUBLISHED";
+
+/// Export only documents from pastes labeled with this `# source:` name.
+const EXPORT_SOURCE_BATCH_ENV: &str = "HARVESTER_EXPORT_SOURCE_BATCH";
+
+/// Path to a `.warc` or `.warc.gz` file to harvest offline: its pages are
+/// queued at startup and served from the archive instead of the network.
+const WARC_SOURCE_ENV: &str = "HARVESTER_WARC_SOURCE";
+
+/// Path of a WARC file that fetched responses are appended to; `1` writes
+/// `output/harvest.warc`.
+const WARC_OUTPUT_ENV: &str = "HARVESTER_WARC_OUTPUT";
+
+/// Browser whose cookie store is read at startup (`chrome`, `edge` or
+/// `firefox`), so pages the user is logged in to can be harvested.
+const COOKIES_BROWSER_ENV: &str = "HARVESTER_COOKIES_BROWSER";
+
+/// Path to a Netscape `cookies.txt` export, used when no browser is set or
+/// its store cannot be read.
+const COOKIES_FILE_ENV: &str = "HARVESTER_COOKIES_FILE";
+
+/// Optional domain that limits which imported cookies are used.
+const COOKIES_DOMAIN_ENV: &str = "HARVESTER_COOKIES_DOMAIN";
+
+/// Passphrase that encrypts the session state file and concatenated exports.
+const PASSPHRASE_ENV: &str = "HARVESTER_PASSPHRASE";
+
+/// Directory of HTML fixtures served in place of the network, for offline demos.
+const FIXTURE_DIR_ENV: &str = "HARVESTER_FIXTURE_DIR";
+
+/// Seconds a fetched response is kept in `output/.response_cache/` and
+/// reused when the same URL is harvested again.
+const RESPONSE_CACHE_TTL_ENV: &str = "HARVESTER_RESPONSE_CACHE_TTL_SECS";
+
+/// Tokens one site may contribute to a session; its later jobs fail as
+/// `domain_budget` instead of being fetched.
+const DOMAIN_TOKEN_CAP_ENV: &str = "HARVESTER_DOMAIN_TOKEN_CAP";
+
+/// Set to `1` to export the documents fetched since the last export when
+/// "New Session" clears the current one.
+const EXPORT_ON_NEW_SESSION_ENV: &str = "HARVESTER_EXPORT_ON_NEW_SESSION";
+
+/// Set to `1` to log fetches, watches and exports instead of running them.
+const DRY_RUN_ENV: &str = "HARVESTER_DRY_RUN";
+
+/// Set to `drain` to let running jobs finish when the window is closed
+/// instead of cancelling them.
+const CLOSE_POLICY_ENV: &str = "HARVESTER_CLOSE_POLICY";
+
+/// How running jobs are stopped when the window is closed; see [`CLOSE_POLICY_ENV`].
+pub(crate) fn close_policy() -> StopPolicy {
+    if std::env::var(CLOSE_POLICY_ENV).is_ok_and(|v| v.eq_ignore_ascii_case("drain")) {
+        StopPolicy::Finish
+    } else {
+        StopPolicy::Immediate
+    }
+}
+
+/// Paths of written markdown documents, keyed by job id.
+pub(crate) type DocumentIndex = Arc<Mutex<HashMap<JobId, PathBuf>>>;
+
 pub struct EffectRunner {
     engine: EngineHandle,
+    output_dir: PathBuf,
+    /// The profile `output_dir` belongs to is the one last switched to.
+    profiles: Vec<OutputProfile>,
+    documents: DocumentIndex,
+    passphrase: Option<Passphrase>,
+    /// Pages harvested offline, kept across profile switches.
+    offline_archive: Option<Arc<WarcArchive>>,
+    interceptors: Vec<Box<dyn EffectInterceptor>>,
+    /// Reports back when a job was handed to the engine.
+    msg_tx: mpsc::Sender<Msg>,
 }
 
 impl EffectRunner {
-    pub fn new(msg_tx: mpsc::Sender<Msg>) -> Self {
-        let output_dir = default_output_dir();
+    pub fn new(msg_tx: mpsc::Sender<Msg>) -> Result<Self, EngineBuildError> {
+        let profiles = profiles::load_profiles();
+        let profile = profiles
+            .iter()
+            .find(|profile| profile.name == DEFAULT_PROFILE)
+            .cloned()
+            .unwrap_or_else(|| profiles[0].clone());
+        let passphrase = load_passphrase();
+        let archive = load_warc_source();
+        let engine = profile
+            .apply(engine_builder(
+                profile.output_dir.clone(),
+                passphrase.as_ref(),
+                archive.as_ref(),
+            ))
+            .build()?;
+        let _ = msg_tx.send(Msg::QueueCapacity(engine.queue_capacity()));
+        if profiles.len() > 1 {
+            let _ = msg_tx.send(Msg::ProfilesLoaded {
+                names: profiles
+                    .iter()
+                    .map(|profile| profile.name.clone())
+                    .collect(),
+                active: profile.name.clone(),
+            });
+        }
+        let mut runner = Self {
+            engine,
+            output_dir: profile.output_dir,
+            profiles,
+            documents: DocumentIndex::default(),
+            passphrase,
+            offline_archive: archive.clone(),
+            interceptors: Vec::new(),
+            msg_tx: msg_tx.clone(),
+        };
+        if std::env::var(DRY_RUN_ENV).is_ok_and(|v| v == "1") {
+            runner.add_interceptor(Box::new(dry_run));
+        }
+        if let Some(archive) = archive {
+            let _ = msg_tx.send(Msg::UrlsReceived(archive.page_urls()));
+        }
+        runner.spawn_event_loop(msg_tx);
+        Ok(runner)
+    }
 
-        let mut config = EngineConfig::default_with_output(output_dir);
-        config.fetched_utc = std::sync::Arc::new(|| Utc::now().to_rfc3339());
+    /// Directory documents, exports and the session state are written to.
+    pub(crate) fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
 
-        let engine = EngineHandle::new(config);
-        let runner = Self { engine };
-        runner.spawn_event_loop(msg_tx);
-        runner
+    /// Reconfigure the engine for profile `name`; the session has no running
+    /// jobs by then.
+    fn switch_profile(&mut self, name: &str) {
+        let Some(profile) = self.profiles.iter().find(|profile| profile.name == name) else {
+            engine_warn!("Unknown output profile {}", name);
+            return;
+        };
+        let builder = engine_builder(
+            profile.output_dir.clone(),
+            self.passphrase.as_ref(),
+            self.offline_archive.as_ref(),
+        );
+        match profile.apply(builder).build_config() {
+            Ok(config) => {
+                engine_info!(
+                    "Switched to output profile {} in {:?}",
+                    name,
+                    profile.output_dir
+                );
+                self.engine.reconfigure(config);
+                let _ = self
+                    .msg_tx
+                    .send(Msg::QueueCapacity(self.engine.queue_capacity()));
+                self.output_dir = profile.output_dir.clone();
+                if let Ok(mut index) = self.documents.lock() {
+                    index.clear();
+                }
+                let _ = self.msg_tx.send(Msg::ProfileActivated(name.to_string()));
+            }
+            Err(err) => engine_warn!("Failed to switch to output profile {}: {}", name, err),
+        }
+    }
+
+    /// Passphrase for encrypted state and exports, when one is configured.
+    pub(crate) fn passphrase(&self) -> Option<&Passphrase> {
+        self.passphrase.as_ref()
+    }
+
+    #[cfg(feature = "api")]
+    pub(crate) fn documents(&self) -> DocumentIndex {
+        self.documents.clone()
+    }
+
+    /// Run `interceptor` on effects before they reach the engine, after the
+    /// interceptors added before it.
+    pub(crate) fn add_interceptor(&mut self, interceptor: Box<dyn EffectInterceptor>) {
+        self.interceptors.push(interceptor);
     }
 
-    pub fn enqueue(&self, effects: Vec<Effect>) {
-        for effect in effects {
+    pub fn enqueue(&mut self, effects: Vec<Effect>) {
+        for effect in intercept_effects(&mut self.interceptors, effects) {
             match effect {
-                Effect::EnqueueUrl { job_id, url } => {
+                Effect::EnqueueUrl {
+                    job_id,
+                    url,
+                    source_batch,
+                } => {
                     engine_info!(
                         "EnqueueUrl job_id={} url_len={} url={}",
                         job_id,
                         url.len(),
                         url
                     );
-                    self.engine.enqueue(job_id, url);
+                    self.engine.enqueue_labeled(job_id, url, source_batch);
+                    let _ = self.msg_tx.send(Msg::JobProgress {
+                        job_id,
+                        at_ms: now_ms(),
+                        stage: Stage::Queued,
+                        tokens: None,
+                        bytes: None,
+                        content_preview: None,
+                    });
                 }
                 Effect::StartSession => {
-                    // no-op; engine starts on first enqueue
+                    // The engine runs from launch; this only lifts a previous stop.
+                    self.engine.resume();
                 }
                 Effect::StopFinish { policy } => {
                     let immediate = matches!(policy, StopPolicy::Immediate);
                     self.engine.stop(immediate);
                 }
-                Effect::ArchiveRequested => {
+                Effect::ArchiveRequested { min_quality } => {
                     engine_info!("Archive requested: enqueue export job");
+                    self.engine.set_export_min_quality(min_quality);
                     self.engine.request_export();
                 }
+                Effect::ArchiveNewRequested {
+                    since_utc,
+                    min_quality,
+                } => {
+                    engine_info!(
+                        "Archive (new only) requested: since={}",
+                        since_utc.as_deref().unwrap_or("<never>")
+                    );
+                    self.engine.set_export_min_quality(min_quality);
+                    self.engine
+                        .request_export_scoped(ExportScope::NewSince(since_utc));
+                }
+                Effect::PersistState { session } => persistence::save_session(
+                    &self.output_dir,
+                    &session.into(),
+                    self.passphrase.as_ref(),
+                ),
+                Effect::SaveSessionSnapshot {
+                    completed,
+                    since_utc,
+                    min_quality,
+                } => {
+                    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+                    persistence::archive_session(
+                        &self.output_dir,
+                        &completed,
+                        &stamp,
+                        self.passphrase.as_ref(),
+                    );
+                    if std::env::var(EXPORT_ON_NEW_SESSION_ENV).is_ok_and(|v| v == "1") {
+                        engine_info!("Exporting the cleared session");
+                        self.engine.set_export_min_quality(min_quality);
+                        self.engine
+                            .request_export_scoped(ExportScope::NewSince(since_utc));
+                    }
+                }
+                Effect::WatchUrl {
+                    job_id,
+                    url,
+                    interval_secs,
+                } => {
+                    self.engine
+                        .watch(job_id, url, Duration::from_secs(interval_secs));
+                }
+                Effect::UnwatchUrl { job_id } => {
+                    self.engine.unwatch(job_id);
+                }
+                Effect::PinnedUrlsChanged { urls } => {
+                    engine_info!("Pinned URLs: {}", urls.len());
+                    self.engine.set_pinned_urls(urls);
+                }
+                Effect::TokenBudgetChanged { tokens } => {
+                    engine_info!("Token budget set to {}", tokens);
+                    self.engine.set_token_budget(tokens);
+                }
+                Effect::SetLogLevel(level) => {
+                    engine_logging::set_level(logging::level_filter(level));
+                    engine_info!("Log level set to {}", level.id());
+                }
+                Effect::CopyToClipboard { text } => {
+                    if let Err(err) = desktop::copy_to_clipboard(&text) {
+                        engine_warn!("Failed to copy to the clipboard: {}", err);
+                    }
+                }
+                Effect::OpenInBrowser { url } => {
+                    engine_info!("Opening {} in the browser", url);
+                    if let Err(err) = desktop::open_in_browser(&url) {
+                        engine_warn!("Failed to open {}: {}", url, err);
+                    }
+                }
+                Effect::ExportImageInventory { images } => {
+                    let images: Vec<ImageRef> = images
+                        .into_iter()
+                        .map(|image| ImageRef {
+                            image_url: image.image_url,
+                            page_url: image.page_url,
+                            alt_text: image.alt_text,
+                        })
+                        .collect();
+                    match write_image_inventory(&self.output_dir, &images) {
+                        Ok(path) => {
+                            engine_info!("Wrote {} image links to {:?}", images.len(), path)
+                        }
+                        Err(err) => engine_warn!("Failed to write the image inventory: {}", err),
+                    }
+                }
+                Effect::SwitchProfile { name } => self.switch_profile(&name),
+                Effect::Quit => {
+                    // The session was saved by the `PersistState` before it;
+                    // the front-end closes the window.
+                    engine_info!("[Shutdown] Engine drained, quitting");
+                }
             }
         }
     }
 
     fn spawn_event_loop(&self, msg_tx: mpsc::Sender<Msg>) {
-        let engine = self.engine.clone();
-        thread::spawn(move || loop {
-            if let Some(event) = engine.try_recv() {
+        let engine = self.engine.events();
+        let documents = self.documents.clone();
+        thread::spawn(move || {
+            while let Some(event) = engine.recv() {
                 match event {
                     EngineEvent::Progress(progress) => {
                         let _ = msg_tx.send(Msg::JobProgress {
                             job_id: progress.job_id,
+                            at_ms: now_ms(),
                             stage: map_stage(progress.stage),
                             tokens: progress.tokens,
                             bytes: progress.bytes,
@@ -74,22 +401,77 @@ impl EffectRunner {
                     EngineEvent::JobCompleted { job_id, result } => {
                         let msg = match result {
                             Ok(outcome) => {
-                                let extracted_links = outcome
-                                    .extracted_links
-                                    .into_iter()
-                                    .map(|link| link.url)
-                                    .collect();
+                                if let (Some(path), Ok(mut index)) =
+                                    (outcome.output_path, documents.lock())
+                                {
+                                    index.insert(job_id, path);
+                                }
+                                if let Some(canonical_url) = outcome.canonical_url {
+                                    let _ = msg_tx.send(Msg::CanonicalUrlResolved {
+                                        job_id,
+                                        canonical_url,
+                                    });
+                                }
+                                if let Some(quality) = outcome.quality {
+                                    let _ = msg_tx.send(Msg::QualityAssessed {
+                                        job_id,
+                                        score: quality.score,
+                                        low: quality.is_low(),
+                                    });
+                                }
+                                if let Some(reading) = outcome.reading {
+                                    let _ = msg_tx.send(Msg::ReadingMeasured {
+                                        job_id,
+                                        words: reading.word_count,
+                                        minutes: reading.reading_minutes,
+                                    });
+                                }
+                                if let Some(original) = outcome.duplicate_of {
+                                    let _ =
+                                        msg_tx.send(Msg::DuplicateDetected { job_id, original });
+                                }
+                                if let Some(diff) = outcome.diff {
+                                    let _ = msg_tx.send(Msg::ContentCompared {
+                                        job_id,
+                                        change: map_diff(diff),
+                                    });
+                                }
+                                let extracted_links =
+                                    outcome.extracted_links.into_iter().map(map_link).collect();
+                                let result = if outcome.content_warning.is_some() {
+                                    JobResultKind::PartialContent
+                                } else if outcome.from_cache {
+                                    JobResultKind::FromCache
+                                } else {
+                                    JobResultKind::Success
+                                };
                                 Msg::JobDone {
                                     job_id,
-                                    result: JobResultKind::Success,
+                                    at_ms: now_ms(),
+                                    result,
                                     content_preview: outcome.content_preview,
                                     extracted_links,
                                 }
                             }
+                            Err(FailureKind::Cancelled) => {
+                                engine_info!("Job {} cancelled", job_id);
+                                Msg::JobDone {
+                                    job_id,
+                                    at_ms: now_ms(),
+                                    result: JobResultKind::Cancelled,
+                                    content_preview: None,
+                                    extracted_links: Vec::new(),
+                                }
+                            }
                             Err(failure_kind) => {
                                 engine_warn!("Job {} failed: {}", job_id, failure_kind);
+                                let _ = msg_tx.send(Msg::JobFailureReported {
+                                    job_id,
+                                    kind: failure_kind.key(),
+                                });
                                 Msg::JobDone {
                                     job_id,
+                                    at_ms: now_ms(),
                                     result: JobResultKind::Failed,
                                     content_preview: None,
                                     extracted_links: Vec::new(),
@@ -98,14 +480,296 @@ impl EffectRunner {
                         };
                         let _ = msg_tx.send(msg);
                     }
+                    EngineEvent::JobRetryScheduled { job_id, delay } => {
+                        let _ = msg_tx.send(Msg::JobRetryScheduled {
+                            job_id,
+                            delay_secs: delay.as_secs(),
+                        });
+                    }
+                    EngineEvent::WatchChanged { watch_id, outcome } => {
+                        let extracted_links =
+                            outcome.extracted_links.into_iter().map(map_link).collect();
+                        let _ = msg_tx.send(Msg::WatchedPageChanged {
+                            watch_id,
+                            tokens: outcome.tokens,
+                            bytes: outcome.bytes_written,
+                            content_preview: outcome.content_preview,
+                            extracted_links,
+                            change: outcome.diff.map(map_diff),
+                        });
+                    }
+                    EngineEvent::TokenCountRefined { job_id, tokens } => {
+                        let _ = msg_tx.send(Msg::TokenCountRefined { job_id, tokens });
+                    }
+                    EngineEvent::ExportCompleted { exported_utc, .. } => {
+                        let _ = msg_tx.send(Msg::ExportCompleted { exported_utc });
+                    }
+                    EngineEvent::ExportFailed { message } => {
+                        engine_warn!("Export failed: {}", message);
+                        let _ = msg_tx.send(Msg::ExportFailed { message });
+                    }
+                    EngineEvent::QueueSaturated { capacity } => {
+                        engine_warn!("Engine queue saturated at {} jobs", capacity);
+                        let _ = msg_tx.send(Msg::QueueSaturated);
+                    }
+                    EngineEvent::MemoryThrottled {
+                        in_use_bytes,
+                        budget_bytes,
+                    } => {
+                        engine_warn!(
+                            "Engine memory budget spent ({} of {} bytes); deferring new jobs",
+                            in_use_bytes,
+                            budget_bytes
+                        );
+                        let _ = msg_tx.send(Msg::MemoryThrottled {
+                            in_use_bytes,
+                            budget_bytes,
+                        });
+                    }
+                    EngineEvent::QueueResumed => {
+                        let _ = msg_tx.send(Msg::QueueResumed);
+                    }
+                    EngineEvent::AllJobsDrained => {
+                        let _ = msg_tx.send(Msg::AllJobsDrained);
+                    }
                 }
-            } else {
-                thread::sleep(Duration::from_millis(20));
             }
+            engine_info!("Engine event stream closed");
         });
     }
 }
 
+/// Cookies of the browser named by [`COOKIES_BROWSER_ENV`], else of the file
+/// named by [`COOKIES_FILE_ENV`]; empty when neither gives any.
+fn load_cookies() -> CookieJar {
+    let Some((jar, source)) = load_browser_cookies().or_else(load_cookies_file) else {
+        return CookieJar::default();
+    };
+    let jar = match std::env::var(COOKIES_DOMAIN_ENV) {
+        Ok(domain) => jar.for_domain(&domain),
+        Err(_) => jar,
+    };
+    engine_info!("Loaded {} cookies from {}", jar.len(), source);
+    jar
+}
+
+fn load_browser_cookies() -> Option<(CookieJar, String)> {
+    let id = std::env::var(COOKIES_BROWSER_ENV).ok()?;
+    let Some(browser) = Browser::from_id(&id) else {
+        engine_warn!("Ignoring unknown cookie browser {:?}", id);
+        return None;
+    };
+    match CookieJar::load_browser(browser) {
+        Ok(jar) => Some((jar, browser.id().to_string())),
+        Err(err) => {
+            engine_warn!("Ignoring {} cookies: {}", browser.id(), err);
+            None
+        }
+    }
+}
+
+fn load_cookies_file() -> Option<(CookieJar, String)> {
+    let path = std::env::var_os(COOKIES_FILE_ENV)?;
+    match CookieJar::load_netscape_file(Path::new(&path)) {
+        Ok(jar) => Some((jar, format!("{:?}", path))),
+        Err(err) => {
+            engine_warn!("Ignoring cookies file {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// Engine settings from the environment, writing to `output_dir`.
+fn engine_builder(
+    output_dir: PathBuf,
+    passphrase: Option<&Passphrase>,
+    archive: Option<&Arc<WarcArchive>>,
+) -> EngineBuilder {
+    let mut builder = EngineBuilder::new(output_dir.clone());
+    if let Some(chunking) = std::env::var(EXPORT_CHUNKS_ENV)
+        .ok()
+        .and_then(|v| parse_chunk_options(&v))
+    {
+        builder = builder.export_format(ExportFormat::Chunks(chunking));
+    } else if std::env::var(EXPORT_OBSIDIAN_ENV).is_ok_and(|v| v == "1") {
+        builder = builder.export_format(ExportFormat::ObsidianVault);
+    } else if std::env::var(EXPORT_HTML_ENV).is_ok_and(|v| v == "1") {
+        builder = builder.export_format(ExportFormat::Html);
+    } else if let Some(order) = std::env::var(EXPORT_FIT_ENV)
+        .ok()
+        .and_then(|v| parse_fit_order(&v))
+    {
+        builder = builder.export_format(ExportFormat::FitToContext(order));
+    }
+    if let Some((from, until)) = std::env::var(EXPORT_PUBLISHED_ENV)
+        .ok()
+        .and_then(|v| parse_date_range(&v))
+    {
+        builder = builder.export_published(from, until);
+    }
+    if let Some(label) = std::env::var(EXPORT_SOURCE_BATCH_ENV)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        builder = builder.export_source_batch(label.trim());
+    }
+    if std::env::var(APPROX_TOKENS_ENV).is_ok_and(|v| v == "1") {
+        builder = builder
+            .token_counter(Arc::new(ApproxTokenCounter))
+            .backfill_token_counter(Arc::new(WhitespaceTokenCounter));
+    }
+    if let Ok(value) = std::env::var(WARC_OUTPUT_ENV) {
+        let path = if value == "1" {
+            output_dir.join("harvest.warc")
+        } else {
+            PathBuf::from(value)
+        };
+        builder = builder.warc_output(path);
+    }
+    if let Some(passphrase) = passphrase {
+        builder = builder.encrypt_exports(passphrase.clone());
+    }
+    if let Some(secs) = std::env::var(RESPONSE_CACHE_TTL_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+    {
+        builder = builder.response_cache(Duration::from_secs(secs));
+    }
+    if let Some(tokens) = std::env::var(DOMAIN_TOKEN_CAP_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|&tokens| tokens > 0)
+    {
+        builder = builder.domain_token_cap(tokens);
+    }
+    if let Some(dir) = std::env::var_os(FIXTURE_DIR_ENV) {
+        builder = builder.fixture_dir(PathBuf::from(dir));
+    }
+    if let Some(archive) = archive {
+        builder = builder.offline_archive(archive.clone());
+    }
+    builder
+        .fetched_utc(|| Utc::now().to_rfc3339())
+        .versioned_snapshots(std::env::var(VERSIONED_SNAPSHOTS_ENV).is_ok_and(|v| v == "1"))
+        .stitch_pages(std::env::var(STITCH_PAGES_ENV).is_ok_and(|v| v == "1"))
+        .strip_site_chrome(std::env::var(STRIP_SITE_CHROME_ENV).is_ok_and(|v| v == "1"))
+        .lossy_decode(!std::env::var(STRICT_DECODE_ENV).is_ok_and(|v| v == "1"))
+        .filename_options(FilenameOptions {
+            transliterate: std::env::var(ASCII_FILENAMES_ENV).is_ok_and(|v| v == "1"),
+        })
+        .fetch_settings(FetchSettings {
+            cookies: load_cookies(),
+            ..FetchSettings::default()
+        })
+}
+
+/// Dry-run interceptor: effects that would fetch or write are logged and dropped.
+fn dry_run(effect: Effect) -> Vec<Effect> {
+    match effect {
+        Effect::EnqueueUrl { .. }
+        | Effect::WatchUrl { .. }
+        | Effect::ArchiveRequested { .. }
+        | Effect::ArchiveNewRequested { .. }
+        | Effect::ExportImageInventory { .. }
+        | Effect::SaveSessionSnapshot { .. } => {
+            engine_info!("[DryRun] Skipping {:?}", effect);
+            Vec::new()
+        }
+        other => vec![other],
+    }
+}
+
+/// Passphrase named by [`PASSPHRASE_ENV`]; `None` when unset or empty.
+fn load_passphrase() -> Option<Passphrase> {
+    let secret = std::env::var(PASSPHRASE_ENV).ok()?;
+    match Passphrase::new(&secret) {
+        Ok(passphrase) => {
+            engine_info!("Encrypting session state and exports");
+            Some(passphrase)
+        }
+        Err(err) => {
+            engine_warn!("Ignoring {}: {}", PASSPHRASE_ENV, err);
+            None
+        }
+    }
+}
+
+fn load_warc_source() -> Option<Arc<WarcArchive>> {
+    let path = std::env::var_os(WARC_SOURCE_ENV)?;
+    match WarcArchive::open(Path::new(&path)) {
+        Ok(archive) => {
+            engine_info!("Harvesting {} pages offline from {:?}", archive.len(), path);
+            Some(Arc::new(archive))
+        }
+        Err(err) => {
+            engine_warn!("Ignoring WARC source {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+/// `512` or `512:64`; the overlap defaults to [`ChunkOptions::default`]'s.
+pub(super) fn parse_chunk_options(value: &str) -> Option<ChunkOptions> {
+    let (max, overlap) = match value.split_once(':') {
+        Some((max, overlap)) => (max, Some(overlap)),
+        None => (value, None),
+    };
+    let max_tokens = max.trim().parse().ok()?;
+    let overlap_tokens = match overlap {
+        Some(overlap) => overlap.trim().parse().ok()?,
+        None => ChunkOptions::default().overlap_tokens.min(max_tokens / 2),
+    };
+    Some(ChunkOptions {
+        max_tokens,
+        overlap_tokens,
+    })
+}
+
+/// See [`EXPORT_PUBLISHED_ENV`].
+fn parse_date_range(value: &str) -> Option<(Option<String>, Option<String>)> {
+    let (from, until) = value.split_once("..")?;
+    let end = |date: &str| Some(date.trim().to_string()).filter(|date| !date.is_empty());
+    Some((end(from), end(until)))
+}
+
+/// See [`EXPORT_FIT_ENV`].
+pub(super) fn parse_fit_order(value: &str) -> Option<FitOrder> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "filename" => Some(FitOrder::Filename),
+        "newest" => Some(FitOrder::Newest),
+        "published" => Some(FitOrder::Published),
+        "quality" => Some(FitOrder::BestQuality),
+        "smallest" => Some(FitOrder::Smallest),
+        _ => None,
+    }
+}
+
+fn map_diff(diff: ContentDiff) -> ContentChange {
+    ContentChange {
+        added: diff.added,
+        removed: diff.removed,
+        diff: diff.text,
+    }
+}
+
+fn map_link(link: ExtractedLink) -> JobLink {
+    JobLink {
+        url: link.url,
+        text: link.text,
+        kind: match link.kind {
+            harvester_engine::LinkKind::Hyperlink => LinkKind::Hyperlink,
+            harvester_engine::LinkKind::Image => LinkKind::Image,
+            harvester_engine::LinkKind::Email => LinkKind::Email,
+        },
+        internal: link.internal,
+    }
+}
+
+/// Wall-clock time stamped on job messages.
+fn now_ms() -> u64 {
+    u64::try_from(Utc::now().timestamp_millis()).unwrap_or(0)
+}
+
 fn map_stage(stage: harvester_engine::Stage) -> Stage {
     match stage {
         harvester_engine::Stage::Queued => Stage::Queued,
@@ -0,0 +1,824 @@
+//! Cross-platform egui/eframe front-end.
+//!
+//! Drives the same `AppState`/`update`/`AppViewModel` loop as the native
+//! Windows UI; only the rendering differs. egui is immediate mode, so the
+//! latest view model is simply redrawn every frame.
+use std::path::PathBuf;
+use std::sync::mpsc;
+#[cfg(feature = "api")]
+use std::sync::Arc;
+use std::time::Duration;
+
+use eframe::egui;
+use engine_logging::{engine_error, engine_info};
+use harvester_core::{
+    update, AppState, AppViewModel, BudgetLevel, BudgetPreset, Effect, JobColumn, JobResultKind,
+    LogLevel, Msg, SessionState, WindowGeometry,
+};
+
+#[cfg(feature = "api")]
+use super::api;
+use super::effects::{self, EffectRunner};
+use super::format::{
+    activity_text, archive_button_label, archive_enabled, column_header, diff_button_label,
+    export_min_quality_text, format_preview_header, format_session_summary, harvest_links_label,
+    job_cells, job_row_tone, link_filter_label, link_row_text, link_scope_label,
+    new_session_enabled, pending_paste_text, status_is_warning, status_text, stop_now_button_label,
+    stop_now_enabled, token_percent, token_progress_text, RowTone, EXPORT_MIN_QUALITY_OPTIONS,
+};
+use super::journal::MsgRecorder;
+use super::logging::{self, LogDestination};
+use super::persistence;
+use crate::single_instance::InstanceLock;
+
+/// Matches the tick interval of the native UI.
+const REPAINT_INTERVAL: Duration = Duration::from_millis(75);
+/// Extra job rows built above and below the visible ones, so small scrolls
+/// don't need a fresh view window.
+const JOB_ROW_OVERSCAN: usize = 32;
+/// Widths of the jobs list columns after the URL, which takes the rest.
+const JOB_COLUMN_WIDTHS: [f32; 5] = [150.0, 70.0, 80.0, 60.0, 110.0];
+
+fn toned(text: String, tone: RowTone) -> egui::RichText {
+    let text = egui::RichText::new(text);
+    match tone {
+        RowTone::Normal => text,
+        RowTone::InProgress => text
+            .italics()
+            .color(egui::Color32::from_rgb(0x61, 0xAF, 0xEF)),
+        RowTone::Warning => text.color(egui::Color32::from_rgb(0xE5, 0xC0, 0x7B)),
+        RowTone::Failed => text.color(egui::Color32::from_rgb(0xE0, 0x6C, 0x75)),
+        RowTone::Cancelled => text.color(egui::Color32::from_rgb(0x7F, 0x84, 0x8E)),
+    }
+}
+
+/// The window's current geometry. While maximized or minimized the
+/// restored-down size and position from `previous` are kept.
+fn viewport_geometry(viewport: &egui::ViewportInfo, previous: WindowGeometry) -> WindowGeometry {
+    let maximized = viewport.maximized.unwrap_or(previous.maximized);
+    if maximized || viewport.minimized == Some(true) {
+        return WindowGeometry {
+            maximized,
+            ..previous
+        };
+    }
+    let Some(inner) = viewport.inner_rect else {
+        return previous;
+    };
+    WindowGeometry {
+        width: inner.width().round() as i32,
+        height: inner.height().round() as i32,
+        position: viewport
+            .outer_rect
+            .map(|outer| (outer.min.x.round() as i32, outer.min.y.round() as i32))
+            .or(previous.position),
+        maximized: false,
+    }
+}
+
+/// Column widths in [`JobColumn::ALL`] order, the URL column filling what
+/// the others leave.
+fn job_column_widths(ui: &egui::Ui) -> [f32; JobColumn::ALL.len()] {
+    let spacing = ui.spacing().item_spacing.x * JOB_COLUMN_WIDTHS.len() as f32;
+    let fixed: f32 = JOB_COLUMN_WIDTHS.iter().sum::<f32>() + spacing;
+    let mut widths = [0.0; JobColumn::ALL.len()];
+    widths[0] = (ui.available_width() - fixed).max(120.0);
+    widths[1..].copy_from_slice(&JOB_COLUMN_WIDTHS);
+    widths
+}
+
+/// `launch_urls` are submitted once the previous session is restored, and
+/// `lock` receives URLs from later launches while the window is open.
+pub fn run_egui_app(lock: Option<InstanceLock>, launch_urls: Vec<String>) -> eframe::Result<()> {
+    logging::initialize(LogDestination::Both);
+    engine_info!("Logger initialized. Starting harvester_app (egui)...");
+
+    let (msg_tx, msg_rx) = mpsc::channel::<Msg>();
+    if let Some(lock) = lock {
+        lock.forward_to(msg_tx.clone());
+    }
+    let effect_runner = match EffectRunner::new(msg_tx.clone()) {
+        Ok(runner) => runner,
+        Err(err) => {
+            let text = format!(
+                "Failed to start engine in {:?}: {}",
+                effects::default_output_dir(),
+                err
+            );
+            engine_error!("{}", text);
+            show_startup_error(text);
+            return Err(eframe::Error::AppCreation(Box::new(err)));
+        }
+    };
+    let output_dir = effect_runner.output_dir().to_path_buf();
+
+    #[cfg(feature = "api")]
+    let api = {
+        let shared = Arc::new(api::ApiShared::new(
+            msg_tx,
+            effect_runner.documents(),
+            output_dir.clone(),
+        ));
+        api::start(shared.clone());
+        shared
+    };
+    let mut app = HarvesterApp::new(msg_rx, effect_runner, output_dir);
+    #[cfg(feature = "api")]
+    {
+        api.publish_view(&app.view);
+        app.api = Some(api);
+    }
+    app.restore_session();
+    if !launch_urls.is_empty() {
+        app.dispatch(Msg::UrlsReceived(launch_urls));
+    }
+
+    let geometry = app.state.window_geometry();
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_title("Harvester")
+        .with_inner_size([geometry.width as f32, geometry.height as f32])
+        .with_maximized(geometry.maximized);
+    if let Some((x, y)) = geometry.position {
+        viewport = viewport.with_position([x as f32, y as f32]);
+    }
+    let options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
+    eframe::run_native("Harvester", options, Box::new(|_cc| Ok(Box::new(app))))
+}
+
+/// A small window naming why the app could not start; returns once closed.
+fn show_startup_error(text: String) {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Harvester")
+            .with_inner_size([520.0, 140.0]),
+        ..Default::default()
+    };
+    let _ = eframe::run_simple_native("Harvester", options, move |ctx, _frame| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.colored_label(ui.visuals().error_fg_color, &text);
+            if ui.button("Quit").clicked() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        });
+    });
+}
+
+struct HarvesterApp {
+    state: AppState,
+    view: AppViewModel,
+    msg_rx: mpsc::Receiver<Msg>,
+    effect_runner: EffectRunner,
+    output_dir: PathBuf,
+    recorder: MsgRecorder,
+    input: String,
+    /// Job rows the cached view model was built for (`offset`, `count`).
+    job_window: (usize, usize),
+    /// Token count offered for the custom budget preset.
+    custom_budget: u64,
+    /// Shutdown finished and the session is saved; the window may close.
+    quit_ready: bool,
+    #[cfg(feature = "api")]
+    api: Option<Arc<api::ApiShared>>,
+}
+
+impl HarvesterApp {
+    fn new(msg_rx: mpsc::Receiver<Msg>, effect_runner: EffectRunner, output_dir: PathBuf) -> Self {
+        let state = effects::initial_state();
+        let job_window = (0, 2 * JOB_ROW_OVERSCAN);
+        let view = state.view_window(job_window.0, job_window.1);
+        Self {
+            state,
+            view,
+            msg_rx,
+            effect_runner,
+            recorder: MsgRecorder::from_env(&output_dir),
+            output_dir,
+            input: String::new(),
+            job_window,
+            custom_budget: BudgetPreset::default().token_limit(),
+            quit_ready: false,
+            #[cfg(feature = "api")]
+            api: None,
+        }
+    }
+
+    fn restore_session(&mut self) {
+        let session = persistence::load_session(&self.output_dir, self.effect_runner.passphrase());
+        if !session.completed.is_empty() {
+            self.dispatch(Msg::RestoreCompletedJobs(session.completed));
+        }
+        if let Some(exported_utc) = session.last_export_utc {
+            self.dispatch(Msg::RestoreLastExport(exported_utc));
+        }
+        if session.recovered_from_backup {
+            self.dispatch(Msg::StateRecoveredFromBackup);
+        }
+        self.dispatch(Msg::RestoreBudget(session.budget.unwrap_or_default()));
+        self.dispatch(Msg::RestoreLogLevel(logging::startup_level(
+            session.log_level,
+        )));
+        self.dispatch(Msg::RestoreExportMinQuality(session.export_min_quality));
+        if let Some(widths) = session.panel_widths {
+            self.dispatch(Msg::RestorePanelWidths(widths));
+        }
+        if let Some(geometry) = session.window_geometry {
+            self.dispatch(Msg::WindowGeometryChanged(geometry));
+        }
+    }
+
+    fn process_pending_messages(&mut self) {
+        while let Ok(msg) = self.msg_rx.try_recv() {
+            self.dispatch(msg);
+        }
+    }
+
+    fn dispatch(&mut self, msg: Msg) {
+        self.recorder.record(&msg);
+        let from_input = matches!(msg, Msg::UrlsSubmitted | Msg::PasteConfirmed);
+        let activated = match &msg {
+            Msg::ProfileActivated(name) => Some(name.clone()),
+            _ => None,
+        };
+        let state = std::mem::take(&mut self.state);
+        let (mut state, effects) = update(state, msg);
+        if from_input
+            && effects
+                .iter()
+                .any(|effect| matches!(effect, Effect::EnqueueUrl { .. }))
+        {
+            self.input.clear();
+        }
+        if let Some(name) = &activated {
+            engine_info!("Output profile {} is active", name);
+            self.output_dir = self.effect_runner.output_dir().to_path_buf();
+        }
+        let quit = effects.contains(&Effect::Quit);
+        let dirty = state.consume_dirty();
+        self.state = state;
+        if dirty {
+            self.refresh_view();
+        }
+        self.quit_ready |= quit;
+        self.effect_runner.enqueue(effects);
+        if activated.is_some() {
+            self.restore_session();
+        }
+    }
+
+    fn refresh_view(&mut self) {
+        let (offset, count) = self.job_window;
+        self.view = self.state.view_window(offset, count);
+        if let BudgetPreset::Custom(tokens) = self.view.budget {
+            self.custom_budget = tokens;
+        }
+        #[cfg(feature = "api")]
+        if let Some(api) = &self.api {
+            api.publish_view(&self.state.view());
+        }
+    }
+
+    fn controls(&mut self, ui: &mut egui::Ui) {
+        let view = &self.view;
+        let stop_enabled = matches!(view.session, SessionState::Running);
+        let new_session_enabled = new_session_enabled(view);
+        let archive_enabled = archive_enabled(view);
+        let images_enabled = view.job_count > 0;
+        let header = view.preview_header.as_ref();
+        let watch_enabled =
+            header.is_some_and(|h| h.outcome.is_some_and(JobResultKind::is_complete));
+        let diff_enabled = header.is_some_and(|h| h.change.is_some());
+        let watch_label = if header.is_some_and(|h| h.watched) {
+            "Unwatch"
+        } else {
+            "Watch"
+        };
+        let pin_label = if header.is_some_and(|h| h.pinned) {
+            "Unpin"
+        } else {
+            "Pin"
+        };
+        let mut clicked = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    archive_enabled,
+                    egui::Button::new(archive_button_label(view)),
+                )
+                .clicked()
+            {
+                clicked = Some(Msg::ArchiveClicked);
+            }
+            if view.export_in_progress {
+                ui.spinner();
+            }
+            if ui
+                .add_enabled(archive_enabled, egui::Button::new("Export New Only"))
+                .clicked()
+            {
+                clicked = Some(Msg::ArchiveNewClicked);
+            }
+            if ui
+                .add_enabled(images_enabled, egui::Button::new("Export Images"))
+                .clicked()
+            {
+                clicked = Some(Msg::ImageInventoryRequested);
+            }
+            if ui
+                .add_enabled(stop_enabled, egui::Button::new("Stop"))
+                .clicked()
+            {
+                clicked = Some(Msg::StopFinishClicked);
+            }
+            if ui
+                .add_enabled(
+                    stop_now_enabled(view),
+                    egui::Button::new(stop_now_button_label(view)),
+                )
+                .clicked()
+            {
+                clicked = Some(Msg::StopNowClicked);
+            }
+            if ui
+                .add_enabled(new_session_enabled, egui::Button::new("New Session"))
+                .clicked()
+            {
+                clicked = Some(Msg::NewSessionRequested);
+            }
+            if ui
+                .add_enabled(watch_enabled, egui::Button::new(watch_label))
+                .clicked()
+            {
+                clicked = Some(Msg::ToggleWatchSelected {
+                    interval_secs: effects::DEFAULT_WATCH_INTERVAL_SECS,
+                });
+            }
+            if ui
+                .add_enabled(header.is_some(), egui::Button::new(pin_label))
+                .clicked()
+            {
+                clicked = Some(Msg::TogglePinSelected);
+            }
+            if ui
+                .add_enabled(header.is_some(), egui::Button::new("Copy URL"))
+                .clicked()
+            {
+                clicked = Some(Msg::CopySelectedUrl);
+            }
+            if ui
+                .add_enabled(header.is_some(), egui::Button::new("Open"))
+                .clicked()
+            {
+                clicked = Some(Msg::OpenSelectedUrl);
+            }
+            if ui
+                .add_enabled(diff_enabled, egui::Button::new(diff_button_label(view)))
+                .clicked()
+            {
+                clicked = Some(Msg::DiffModeToggled);
+            }
+            if let Some(budget) = budget_picker(ui, view.budget, &mut self.custom_budget) {
+                clicked = Some(Msg::BudgetPresetSelected(budget));
+            }
+            if let Some(name) = profile_picker(ui, view) {
+                clicked = Some(Msg::ProfileSelected(name));
+            }
+            if let Some(level) = log_level_picker(ui, view.log_level) {
+                clicked = Some(Msg::LogLevelSelected(level));
+            }
+            if let Some(min_quality) = export_min_quality_picker(ui, view.export_min_quality) {
+                clicked = Some(Msg::ExportMinQualitySelected(min_quality));
+            }
+        });
+        if let Some(msg) = clicked {
+            self.dispatch(msg);
+        }
+    }
+
+    /// Asks before a large paste creates its jobs.
+    fn paste_confirmation(&mut self, ctx: &egui::Context) {
+        let Some(paste) = &self.view.pending_paste else {
+            return;
+        };
+        let mut answer = None;
+        egui::Window::new("Harvest pasted URLs?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(pending_paste_text(paste));
+                ui.horizontal(|ui| {
+                    if ui.button(format!("Harvest {}", paste.count)).clicked() {
+                        answer = Some(Msg::PasteConfirmed);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        answer = Some(Msg::PasteCancelled);
+                    }
+                });
+            });
+        if let Some(msg) = answer {
+            self.dispatch(msg);
+        }
+    }
+
+    /// Asks before "Stop now" cancels the running jobs.
+    fn stop_now_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.view.stop_now_pending {
+            return;
+        }
+        let mut answer = None;
+        egui::Window::new("Stop now?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Cancel all running and queued jobs? Their pages are not written.");
+                ui.horizontal(|ui| {
+                    if ui.button("Stop Now").clicked() {
+                        answer = Some(Msg::StopNowConfirmed);
+                    }
+                    if ui.button("Keep Running").clicked() {
+                        answer = Some(Msg::StopNowDismissed);
+                    }
+                });
+            });
+        if let Some(msg) = answer {
+            self.dispatch(msg);
+        }
+    }
+
+    fn status(&self, ui: &mut egui::Ui) {
+        let view = &self.view;
+        let text = status_text(view);
+        if status_is_warning(view) {
+            ui.colored_label(ui.visuals().warn_fg_color, text);
+        } else {
+            ui.label(text);
+        }
+        let mut bar = egui::ProgressBar::new((token_percent(view) / 100.0) as f32)
+            .text(token_progress_text(view));
+        if view.budget_level != BudgetLevel::Normal {
+            bar = bar.fill(egui::Color32::from_rgb(0xE5, 0xC0, 0x7B));
+        }
+        ui.add(bar);
+    }
+
+    fn url_input(&mut self, ui: &mut egui::Ui) {
+        ui.label("Paste URLs (one per line):");
+        let response = ui.add(
+            egui::TextEdit::multiline(&mut self.input)
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        );
+        if response.changed() {
+            engine_info!("InputTextChanged: {} chars", self.input.len());
+            self.dispatch(Msg::InputChanged(self.input.clone()));
+            self.dispatch(Msg::UrlsSubmitted);
+        }
+    }
+
+    fn job_list(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        let mut sort_by = None;
+        let row_height = ui.spacing().interact_size.y;
+        let view = &self.view;
+        let widths = job_column_widths(ui);
+        ui.horizontal(|ui| {
+            for (column, width) in JobColumn::ALL.into_iter().zip(widths) {
+                let header = egui::Button::new(column_header(column, view.job_sort)).frame(false);
+                if ui.add_sized([width, row_height], header).clicked() {
+                    sort_by = Some(column);
+                }
+            }
+        });
+        ui.separator();
+        let visible = egui::ScrollArea::vertical()
+            .id_salt("jobs")
+            .auto_shrink([false, false])
+            .show_rows(ui, row_height, view.job_count, |ui, rows| {
+                for row in rows.clone() {
+                    let Some(job) = row
+                        .checked_sub(view.jobs_offset)
+                        .and_then(|index| view.jobs.get(index))
+                    else {
+                        ui.weak("…");
+                        continue;
+                    };
+                    let is_selected = view.selected_job_id == Some(job.job_id);
+                    ui.horizontal(|ui| {
+                        let tone = job_row_tone(job);
+                        let [url, cells @ ..] = job_cells(job).map(|cell| toned(cell, tone));
+                        let url = egui::SelectableLabel::new(is_selected, url);
+                        if ui.add_sized([widths[0], row_height], url).clicked() {
+                            selected = Some(job.job_id);
+                        }
+                        for (cell, width) in cells.into_iter().zip(&widths[1..]) {
+                            ui.add_sized([*width, row_height], egui::Label::new(cell).truncate());
+                        }
+                    });
+                }
+                rows
+            })
+            .inner;
+        let built = self.view.jobs_offset..self.view.jobs_offset + self.view.jobs.len();
+        let covered = visible.start >= built.start
+            && (visible.end <= built.end || built.end == self.view.job_count);
+        if !covered {
+            let offset = visible.start.saturating_sub(JOB_ROW_OVERSCAN);
+            self.job_window = (offset, visible.len() + 2 * JOB_ROW_OVERSCAN);
+            self.refresh_view();
+            ui.ctx().request_repaint();
+        }
+        if let Some(column) = sort_by {
+            self.dispatch(Msg::JobColumnClicked(column));
+        }
+        if let Some(job_id) = selected {
+            self.dispatch(Msg::JobSelected { job_id });
+        }
+    }
+
+    fn activity(&self, ui: &mut egui::Ui) {
+        let text = activity_text(&self.view);
+        if text.is_empty() {
+            ui.weak("(no activity yet)");
+        } else {
+            ui.small(text);
+        }
+    }
+
+    fn links(&mut self, ui: &mut egui::Ui) {
+        let Some(pane) = &self.view.links_pane else {
+            return;
+        };
+        let mut clicked = None;
+        ui.horizontal(|ui| {
+            ui.strong(format!("Links of #{}", pane.job_id));
+            let harvest = egui::Button::new(harvest_links_label(pane));
+            if ui.add_enabled(!pane.checked.is_empty(), harvest).clicked() {
+                clicked = Some(Msg::HarvestCheckedLinks);
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            for filter in &pane.filters {
+                let mut shown = filter.shown;
+                if ui.checkbox(&mut shown, link_filter_label(filter)).changed() {
+                    clicked = Some(Msg::LinkKindToggled(filter.kind));
+                }
+            }
+            ui.separator();
+            for filter in &pane.scopes {
+                let mut shown = filter.shown;
+                if ui.checkbox(&mut shown, link_scope_label(filter)).changed() {
+                    clicked = Some(Msg::LinkScopeToggled(filter.scope));
+                }
+            }
+        });
+        ui.separator();
+        let row_height = ui.spacing().interact_size.y;
+        egui::ScrollArea::vertical()
+            .id_salt("links")
+            .auto_shrink([false, false])
+            .show_rows(ui, row_height, pane.links.len(), |ui, rows| {
+                for link in &pane.links[rows] {
+                    let mut checked = pane.checked.contains(&link.url);
+                    let row = egui::Checkbox::new(&mut checked, link_row_text(link));
+                    if ui.add(row).on_hover_text(&link.url).changed() {
+                        clicked = Some(Msg::LinkCheckToggled {
+                            url: link.url.clone(),
+                        });
+                    }
+                }
+            });
+        if let Some(msg) = clicked {
+            self.dispatch(msg);
+        }
+    }
+
+    fn preview(&self, ui: &mut egui::Ui) {
+        let header = self
+            .view
+            .preview_header
+            .as_ref()
+            .map(format_preview_header)
+            .unwrap_or_else(|| "(no selection)".to_string());
+        ui.strong(header);
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("preview")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                ui.monospace(self.view.preview_text.as_deref().unwrap_or_default());
+            });
+    }
+}
+
+/// Dropped files, plus the saved pages anywhere below dropped folders, as
+/// paths for intake to turn into `file://` URLs.
+fn saved_pages(paths: &[PathBuf]) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+    while let Some(path) = pending.pop() {
+        if !path.is_dir() {
+            pages.push(path.display().to_string());
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let child = entry.path();
+            let is_page = child
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ["html", "htm", "shtml", "xhtml", "xht", "mht", "mhtml"]
+                        .iter()
+                        .any(|page| ext.eq_ignore_ascii_case(page))
+                });
+            if child.is_dir() || is_page {
+                pending.push(child);
+            }
+        }
+    }
+    pages.sort();
+    pages
+}
+
+/// Token budget setting; returns the preset the user switched to.
+fn budget_picker(
+    ui: &mut egui::Ui,
+    current: BudgetPreset,
+    custom_tokens: &mut u64,
+) -> Option<BudgetPreset> {
+    let mut selected = current;
+    egui::ComboBox::from_label("Budget")
+        .selected_text(current.label())
+        .show_ui(ui, |ui| {
+            for preset in BudgetPreset::NAMED {
+                ui.selectable_value(&mut selected, preset, preset.label());
+            }
+            let custom = BudgetPreset::Custom(*custom_tokens);
+            ui.selectable_value(&mut selected, custom, custom.label());
+        });
+    if let BudgetPreset::Custom(_) = selected {
+        let response = ui.add(
+            egui::DragValue::new(custom_tokens)
+                .range(1_000..=10_000_000)
+                .speed(1_000)
+                .suffix(" tokens"),
+        );
+        if response.changed() {
+            selected = BudgetPreset::Custom(*custom_tokens);
+        }
+    }
+    (selected != current).then_some(selected)
+}
+
+/// Output profile selector, shown when there is more than one profile.
+/// Switching waits until no jobs are running.
+fn profile_picker(ui: &mut egui::Ui, view: &AppViewModel) -> Option<String> {
+    if view.profiles.len() < 2 {
+        return None;
+    }
+    let current = view.active_profile.clone().unwrap_or_default();
+    let mut selected = current.clone();
+    let idle = matches!(view.session, SessionState::Idle | SessionState::Finished);
+    ui.add_enabled_ui(idle, |ui| {
+        egui::ComboBox::from_label("Profile")
+            .selected_text(&current)
+            .show_ui(ui, |ui| {
+                for name in &view.profiles {
+                    ui.selectable_value(&mut selected, name.clone(), name);
+                }
+            });
+    });
+    (selected != current).then_some(selected)
+}
+
+/// Log level setting; returns the level the user switched to.
+fn log_level_picker(ui: &mut egui::Ui, current: LogLevel) -> Option<LogLevel> {
+    let mut selected = current;
+    egui::ComboBox::from_label("Log level")
+        .selected_text(current.id())
+        .show_ui(ui, |ui| {
+            for option in LogLevel::ALL {
+                ui.selectable_value(&mut selected, option, option.id());
+            }
+        });
+    (selected != current).then_some(selected)
+}
+
+/// Export quality threshold setting; returns the threshold the user switched to.
+fn export_min_quality_picker(ui: &mut egui::Ui, current: Option<u8>) -> Option<Option<u8>> {
+    let mut selected = current;
+    egui::ComboBox::from_label("Min quality")
+        .selected_text(export_min_quality_text(current))
+        .show_ui(ui, |ui| {
+            for option in EXPORT_MIN_QUALITY_OPTIONS {
+                ui.selectable_value(&mut selected, option, export_min_quality_text(option));
+            }
+        });
+    (selected != current).then_some(selected)
+}
+
+impl eframe::App for HarvesterApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        engine_logging::set_sim_tick(engine_logging::get_sim_tick() + 1);
+        self.process_pending_messages();
+        let previous = self.state.window_geometry();
+        let (geometry, close_requested) = ctx.input(|input| {
+            let viewport = input.viewport();
+            (
+                viewport_geometry(viewport, previous),
+                viewport.close_requested(),
+            )
+        });
+        if geometry != previous {
+            self.dispatch(Msg::WindowGeometryChanged(geometry));
+        }
+        if close_requested && !self.quit_ready {
+            // Keep the window until the engine has stopped; see `Effect::Quit`.
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.dispatch(Msg::CloseRequested {
+                policy: effects::close_policy(),
+            });
+        }
+        let dropped: Vec<PathBuf> = ctx.input(|input| {
+            input
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() {
+            let pages = saved_pages(&dropped);
+            engine_info!(
+                "Dropped {} paths: {} saved pages",
+                dropped.len(),
+                pages.len()
+            );
+            self.dispatch(Msg::UrlsReceived(pages));
+        }
+        if !ctx.wants_keyboard_input() {
+            let keys = ctx.input(|input| {
+                [
+                    (egui::Key::ArrowDown, Msg::SelectNextJob),
+                    (egui::Key::ArrowUp, Msg::SelectPrevJob),
+                    (egui::Key::F, Msg::SelectFirstFailed),
+                    (egui::Key::Enter, Msg::OpenSelectedUrl),
+                ]
+                .into_iter()
+                .filter(|(key, _)| input.key_pressed(*key))
+                .map(|(_, msg)| msg)
+                // Ctrl+C arrives as a copy event rather than a key press.
+                .chain(
+                    input
+                        .events
+                        .iter()
+                        .any(|event| matches!(event, egui::Event::Copy))
+                        .then_some(Msg::CopySelectedUrl),
+                )
+                .collect::<Vec<_>>()
+            });
+            for msg in keys {
+                self.dispatch(msg);
+            }
+        }
+
+        egui::TopBottomPanel::top("controls").show(ctx, |ui| {
+            self.controls(ui);
+            self.status(ui);
+            self.url_input(ui);
+        });
+        egui::TopBottomPanel::bottom("activity").show(ctx, |ui| self.activity(ui));
+        if let Some(summary) = &self.view.session_summary {
+            egui::TopBottomPanel::bottom("session_summary").show(ctx, |ui| {
+                ui.strong("Session summary");
+                ui.label(format_session_summary(summary));
+            });
+        }
+        egui::SidePanel::left("jobs")
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| self.job_list(ui));
+        if self.view.links_pane.is_some() {
+            egui::SidePanel::right("links")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| self.links(ui));
+        }
+        egui::CentralPanel::default().show(ctx, |ui| self.preview(ui));
+        self.paste_confirmation(ctx);
+        self.stop_now_confirmation(ctx);
+
+        if self.quit_ready && !close_requested {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+
+        // Engine events arrive on a channel; keep polling while the window is open.
+        ctx.request_repaint_after(REPAINT_INTERVAL);
+    }
+}
@@ -0,0 +1,1113 @@
+//! Text formatting shared by the native and egui front-ends.
+use harvester_core::{
+    ActivityEvent, AppViewModel, BudgetLevel, JobColumn, JobLink, JobResultKind, JobRowView,
+    JobSort, LinkFilterView, LinkScopeFilterView, LinksPaneView, PendingPaste, PreviewHeaderView,
+    RejectedUrls, SessionState, SessionSummary, Stage,
+};
+
+/// Domains named in the pending paste prompt; the rest are summed up.
+const PENDING_PASTE_DOMAINS: usize = 3;
+
+pub(crate) fn session_label(session: SessionState) -> &'static str {
+    match session {
+        SessionState::Idle => "Idle",
+        SessionState::Running => "Running",
+        SessionState::Finishing => "Finishing",
+        SessionState::Finished => "Finished",
+    }
+}
+
+pub(crate) fn status_text(view: &AppViewModel) -> String {
+    let session_label = session_label(view.session);
+    let mut status_text = match &view.last_paste_stats {
+        Some(stats) => format!(
+            "Session: {} | Jobs: {} | Last paste: enqueued {}, skipped {}",
+            session_label, view.job_count, stats.enqueued, stats.skipped
+        ),
+        None => format!("Session: {} | Jobs: {}", session_label, view.job_count),
+    };
+    if let Some(rejected) = view
+        .last_paste_stats
+        .as_ref()
+        .map(|stats| stats.rejected)
+        .filter(|rejected| rejected.total() > 0)
+    {
+        status_text.push_str(&format!(", rejected {}", rejected_text(&rejected)));
+    }
+    if view.total_bytes > 0 {
+        status_text.push_str(&format!(" | Size: {}", format_bytes(view.total_bytes)));
+    }
+    if let Some(paste) = &view.pending_paste {
+        status_text.push_str(&format!(
+            " | Paste of {} URLs waiting for confirmation",
+            paste.count
+        ));
+    }
+    if view.intake_paused {
+        status_text.push_str(" | Queue full, intake paused");
+    }
+    if let Some((in_use, budget)) = view.memory_throttled {
+        status_text.push_str(&format!(
+            " | Memory budget spent ({} of {}), new jobs wait",
+            format_bytes(in_use),
+            format_bytes(budget)
+        ));
+    }
+    match view.budget_level {
+        BudgetLevel::Normal => {}
+        BudgetLevel::Warning => {
+            status_text.push_str(&format!(" | Token budget {:.0}% used", token_percent(view)))
+        }
+        BudgetLevel::Exceeded => status_text.push_str(" | Token budget reached, harvest stopped"),
+    }
+    if view.state_recovered {
+        status_text.push_str(" | Saved state was corrupt, restored from backup");
+    }
+    if view.closing {
+        status_text.push_str(" | Finishing up…");
+    }
+    status_text
+}
+
+/// "Harvest 31 URLs from 2 domains (docs.example 30, blog.example 1)? 2
+/// duplicates are skipped."
+pub(crate) fn pending_paste_text(paste: &PendingPaste) -> String {
+    let mut domains: Vec<String> = paste
+        .domains
+        .iter()
+        .take(PENDING_PASTE_DOMAINS)
+        .map(|(domain, count)| format!("{domain} {count}"))
+        .collect();
+    let others = paste.domains.len().saturating_sub(PENDING_PASTE_DOMAINS);
+    if others > 0 {
+        domains.push(format!("{others} more"));
+    }
+    let mut text = format!(
+        "Harvest {} URLs from {} domains ({})?",
+        paste.count,
+        paste.domains.len(),
+        domains.join(", ")
+    );
+    if let Some(batch) = &paste.source_batch {
+        text.push_str(&format!(" Labeled {batch}."));
+    }
+    if paste.duplicates > 0 {
+        text.push_str(&format!(" {} duplicates are skipped.", paste.duplicates));
+    }
+    if paste.rejected.total() > 0 {
+        text.push_str(&format!(" Rejected {}.", rejected_text(&paste.rejected)));
+    }
+    text
+}
+
+/// "3 (2 unsupported scheme, 1 missing scheme)"; zero counts are left out.
+fn rejected_text(rejected: &RejectedUrls) -> String {
+    let reasons: Vec<String> = [
+        (rejected.unsupported_scheme, "unsupported scheme"),
+        (rejected.missing_scheme, "missing scheme"),
+        (rejected.malformed, "malformed"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, reason)| format!("{count} {reason}"))
+    .collect();
+    format!("{} ({})", rejected.total(), reasons.join(", "))
+}
+
+/// The status line and token bar are drawn as warnings.
+pub(crate) fn status_is_warning(view: &AppViewModel) -> bool {
+    view.intake_paused
+        || view.memory_throttled.is_some()
+        || view.state_recovered
+        || view.budget_level != BudgetLevel::Normal
+}
+
+/// Fraction of the token budget used, in percent (0 when there is no limit).
+pub(crate) fn token_percent(view: &AppViewModel) -> f64 {
+    let raw_limit = view.token_limit;
+    if raw_limit > 0 {
+        (view.total_tokens.min(raw_limit) as f64 / raw_limit as f64) * 100.0
+    } else {
+        0.0
+    }
+}
+
+pub(crate) fn token_progress_text(view: &AppViewModel) -> String {
+    format!(
+        "Tokens: {} / {} ({:.1}%)",
+        format_with_commas(view.total_tokens),
+        format_with_commas(view.token_limit),
+        token_percent(view)
+    )
+}
+
+/// Single-string job row for the native tree view.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn format_job_row(job: &JobRowView) -> String {
+    let status = job_status(job);
+    let metrics = [
+        job.tokens.map(|t| format!("{t} tok")),
+        job.bytes.map(format_bytes),
+        job.elapsed_ms.map(format_elapsed),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(", ");
+    let row = if metrics.is_empty() {
+        format!(
+            "[#{id}] {status} — {url}",
+            id = job.job_id,
+            status = status,
+            url = job.url
+        )
+    } else {
+        format!(
+            "[#{id}] {status} — {url} ({metrics})",
+            id = job.job_id,
+            status = status,
+            url = job.url,
+            metrics = metrics
+        )
+    };
+    row + &job_markers(job)
+}
+
+/// Cell texts of a job row, one per entry of [`JobColumn::ALL`].
+pub(crate) fn job_cells(job: &JobRowView) -> [String; JobColumn::ALL.len()] {
+    JobColumn::ALL.map(|column| match column {
+        JobColumn::Url => format!("{}{}", job.url, job_markers(job)),
+        JobColumn::Stage => job_status(job),
+        JobColumn::Tokens => job.tokens.map(|t| t.to_string()).unwrap_or_default(),
+        JobColumn::Bytes => job.bytes.map(format_bytes).unwrap_or_default(),
+        JobColumn::Time => job.elapsed_ms.map(format_elapsed).unwrap_or_default(),
+        JobColumn::Error => job.failure.clone().unwrap_or_default(),
+    })
+}
+
+/// Header text, with an arrow on the column the rows are sorted by.
+pub(crate) fn column_header(column: JobColumn, sort: Option<JobSort>) -> String {
+    match sort {
+        Some(sort) if sort.column == column => {
+            let arrow = if sort.descending { "▼" } else { "▲" };
+            format!("{} {arrow}", column.label())
+        }
+        _ => column.label().to_string(),
+    }
+}
+
+/// How a job row is highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RowTone {
+    Normal,
+    /// Fetched, sanitized or converted right now.
+    InProgress,
+    /// Done, but the capture is partial, thin or mostly navigation.
+    Warning,
+    Failed,
+    /// Cut short by "Stop now".
+    Cancelled,
+}
+
+pub(crate) fn job_row_tone(job: &JobRowView) -> RowTone {
+    match job.outcome {
+        Some(JobResultKind::Failed) => RowTone::Failed,
+        Some(JobResultKind::Cancelled) => RowTone::Cancelled,
+        Some(JobResultKind::PartialContent) => RowTone::Warning,
+        Some(_) if job.low_quality || job.nav_heavy => RowTone::Warning,
+        Some(_) => RowTone::Normal,
+        None if job.stage == Stage::Queued => RowTone::Normal,
+        None => RowTone::InProgress,
+    }
+}
+
+fn job_status(job: &JobRowView) -> String {
+    match (job.outcome, job.retry_in_secs) {
+        (Some(JobResultKind::Success), _) => "OK".to_string(),
+        (Some(JobResultKind::PartialContent), _) => "PARTIAL".to_string(),
+        (Some(JobResultKind::FromCache), _) => "CACHED".to_string(),
+        (Some(JobResultKind::Failed), _) => "ERR".to_string(),
+        (Some(JobResultKind::Cancelled), _) => "CANCELLED".to_string(),
+        (None, Some(secs)) => format!("Rate limited, retrying in {secs}s"),
+        (None, None) => stage_label(job.stage).to_string(),
+    }
+}
+
+/// " [+3 -1] [watched]" and the like; empty for a plain job.
+fn job_markers(job: &JobRowView) -> String {
+    let mut markers = String::new();
+    match job.change {
+        Some(change) if change.is_unchanged() => markers.push_str(" [unchanged]"),
+        Some(change) => markers.push_str(&format!(
+            " [+{added} -{removed}]",
+            added = change.added,
+            removed = change.removed
+        )),
+        None => {}
+    }
+    if job.low_quality {
+        markers.push_str(&format!(
+            " [low quality {}]",
+            job.quality_score.unwrap_or_default()
+        ));
+    }
+    if let Some(original) = job.changed_from {
+        markers.push_str(&format!(" [changed #{original}]"));
+    }
+    if let Some(original) = job.duplicate_of {
+        markers.push_str(&format!(" [dup of #{original}]"));
+    }
+    if job.watched {
+        markers.push_str(" [watched]");
+    }
+    if job.pinned {
+        markers.push_str(" [pinned]");
+    }
+    markers
+}
+
+/// "850ms", "4.2s" or "3m 05s".
+pub(crate) fn format_elapsed(ms: u64) -> String {
+    match ms {
+        0..=999 => format!("{ms}ms"),
+        1_000..=59_999 => format!("{:.1}s", ms as f64 / 1_000.0),
+        _ => {
+            let secs = ms / 1_000;
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        }
+    }
+}
+
+pub(crate) fn stage_label(stage: Stage) -> &'static str {
+    match stage {
+        Stage::Queued => "Queued",
+        Stage::Downloading => "Downloading",
+        Stage::Sanitizing => "Sanitizing",
+        Stage::Converting => "Converting",
+        Stage::Tokenizing => "Tokenizing",
+        Stage::Writing => "Writing",
+        Stage::Done => "Done",
+    }
+}
+
+/// "512 B", "1.5 KB", "12.0 MB"; binary units with one decimal.
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+pub(crate) fn format_with_commas(value: u64) -> String {
+    let mut out = String::new();
+    for (i, ch) in value.to_string().chars().rev().enumerate() {
+        if i != 0 && i % 3 == 0 {
+            out.push(',');
+        }
+        out.push(ch);
+    }
+    out.chars().rev().collect()
+}
+
+pub(crate) fn format_preview_header(header: &PreviewHeaderView) -> String {
+    let mut parts = Vec::new();
+    if !header.domain.is_empty() {
+        parts.push(header.domain.clone());
+    }
+    if let Some(batch) = &header.source_batch {
+        parts.push(format!("from {batch}"));
+    }
+    if let Some(tokens) = header.tokens {
+        parts.push(format!("{} tokens", format_with_commas(tokens as u64)));
+    }
+    if let Some(bytes) = header.bytes {
+        parts.push(format_bytes(bytes));
+    }
+    if let Some((words, minutes)) = header.reading {
+        parts.push(format!(
+            "{} words, {minutes} min read",
+            format_with_commas(words as u64)
+        ));
+    }
+    parts.push(format!("{count} headings", count = header.heading_count));
+    let stage_desc = match header.outcome {
+        Some(JobResultKind::Failed) => "Failed".to_string(),
+        Some(JobResultKind::Success) => "Done".to_string(),
+        Some(JobResultKind::PartialContent) => "Partial (paywall or consent wall?)".to_string(),
+        Some(JobResultKind::FromCache) => "Done (from cache)".to_string(),
+        Some(JobResultKind::Cancelled) => "Cancelled".to_string(),
+        None => stage_label(header.stage).to_string(),
+    };
+    parts.push(stage_desc);
+    if header.nav_heavy {
+        parts.push("[nav-heavy]".to_string());
+    }
+    if header.watched {
+        parts.push("[watched]".to_string());
+    }
+    if header.pinned {
+        parts.push("[pinned]".to_string());
+    }
+    match header.change {
+        Some(change) if change.is_unchanged() => parts.push("unchanged".to_string()),
+        Some(change) => parts.push(format!("+{} -{} lines", change.added, change.removed)),
+        None => {}
+    }
+    parts.join(" | ")
+}
+
+/// Lines shown in the activity ticker.
+pub(crate) const ACTIVITY_TICKER_LINES: usize = 5;
+
+/// The latest activity, newest first, one event per line.
+pub(crate) fn activity_text(view: &AppViewModel) -> String {
+    view.activity_log
+        .iter()
+        .rev()
+        .take(ACTIVITY_TICKER_LINES)
+        .map(format_activity)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn format_activity(event: &ActivityEvent) -> String {
+    match event {
+        ActivityEvent::JobStarted { job_id, url } => format!("#{job_id} started: {url}"),
+        ActivityEvent::JobFinished {
+            job_id,
+            url,
+            result,
+        } => {
+            let note = match result {
+                JobResultKind::PartialContent => " (partial)",
+                JobResultKind::FromCache => " (from cache)",
+                JobResultKind::Success | JobResultKind::Failed | JobResultKind::Cancelled => "",
+            };
+            format!("#{job_id} finished{note}: {url}")
+        }
+        ActivityEvent::JobFailed { job_id, url } => format!("#{job_id} failed: {url}"),
+        ActivityEvent::JobCancelled { job_id, url } => format!("#{job_id} cancelled: {url}"),
+        ActivityEvent::ExportCompleted { exported_utc } => {
+            format!("Export completed at {exported_utc}")
+        }
+        ActivityEvent::ExportFailed { message } => format!("Export failed: {message}"),
+        ActivityEvent::BudgetLevelReached {
+            level,
+            tokens,
+            limit,
+        } => {
+            let what = match level {
+                BudgetLevel::Exceeded => "limit reached, stopping",
+                BudgetLevel::Warning | BudgetLevel::Normal => "warning",
+            };
+            format!(
+                "Token budget {what}: {} / {}",
+                format_with_commas(*tokens),
+                format_with_commas(*limit)
+            )
+        }
+    }
+}
+
+/// The finished session's totals, one figure per line.
+pub(crate) fn format_session_summary(summary: &SessionSummary) -> String {
+    let mut lines = vec![format!(
+        "Pages: {} ({} succeeded, {} partial, {} failed)",
+        summary.pages,
+        summary.succeeded,
+        summary.partial,
+        summary.failed()
+    )];
+    if summary.from_cache > 0 {
+        lines.push(format!("From cache: {}", summary.from_cache));
+    }
+    if summary.cancelled > 0 {
+        lines.push(format!("Cancelled: {}", summary.cancelled));
+    }
+    if !summary.failures.is_empty() {
+        let failures = summary
+            .failures
+            .iter()
+            .map(|(kind, count)| format!("{kind} {count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(format!("Failures: {failures}"));
+    }
+    lines.push(format!(
+        "Tokens: {} | Size: {}",
+        format_with_commas(summary.total_tokens),
+        format_bytes(summary.total_bytes)
+    ));
+    if let Some(ms) = summary.duration_ms {
+        lines.push(format!("Duration: {}", format_elapsed(ms)));
+    }
+    lines.join("\n")
+}
+
+/// "New Session" clears a finished session, or the jobs restored at launch.
+pub(crate) fn new_session_enabled(view: &AppViewModel) -> bool {
+    match view.session {
+        SessionState::Finished => true,
+        SessionState::Idle => view.job_count > 0,
+        SessionState::Running | SessionState::Finishing => false,
+    }
+}
+
+/// Archive and "Export New Only" wait until a running export is written.
+pub(crate) fn archive_enabled(view: &AppViewModel) -> bool {
+    view.job_count > 0 && !view.export_in_progress
+}
+
+pub(crate) fn archive_button_label(view: &AppViewModel) -> &'static str {
+    if view.export_in_progress {
+        "Archiving…"
+    } else {
+        "Archive"
+    }
+}
+
+/// "Stop now" cancels running jobs, also while a graceful stop drains them.
+pub(crate) fn stop_now_enabled(view: &AppViewModel) -> bool {
+    matches!(
+        view.session,
+        SessionState::Running | SessionState::Finishing
+    )
+}
+
+/// The native Stop button backs out of a pending "Stop now".
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn stop_button_label(view: &AppViewModel) -> &'static str {
+    if view.stop_now_pending {
+        "Keep Running"
+    } else {
+        "Stop / Finish"
+    }
+}
+
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn stop_button_enabled(view: &AppViewModel) -> bool {
+    view.stop_now_pending || matches!(view.session, SessionState::Running)
+}
+
+pub(crate) fn stop_now_button_label(view: &AppViewModel) -> &'static str {
+    if view.stop_now_pending {
+        "Confirm Stop Now"
+    } else {
+        "Stop Now"
+    }
+}
+
+pub(crate) fn diff_button_label(view: &AppViewModel) -> &'static str {
+    if view.diff_mode {
+        "Show Document"
+    } else {
+        "Show Diff"
+    }
+}
+
+/// "Budget: Claude (200k)", or "Budget: 350,000" for a custom budget.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn budget_button_label(budget: harvester_core::BudgetPreset) -> String {
+    match budget {
+        harvester_core::BudgetPreset::Custom(tokens) => {
+            format!("Budget: {}", format_with_commas(tokens))
+        }
+        named => format!("Budget: {}", named.label()),
+    }
+}
+
+/// The named preset the native budget button switches to next; a custom
+/// budget starts the cycle over.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn next_budget_preset(
+    current: harvester_core::BudgetPreset,
+) -> harvester_core::BudgetPreset {
+    let named = harvester_core::BudgetPreset::NAMED;
+    named
+        .iter()
+        .position(|&preset| preset == current)
+        .map_or(named[0], |index| named[(index + 1) % named.len()])
+}
+
+/// "Log: info"; the native log level button's caption.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn log_level_button_label(level: harvester_core::LogLevel) -> String {
+    format!("Log: {}", level.id())
+}
+
+/// The level the native log level button switches to next, wrapping from
+/// the most verbose back to `off`.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn next_log_level(current: harvester_core::LogLevel) -> harvester_core::LogLevel {
+    let levels = harvester_core::LogLevel::ALL;
+    let index = levels
+        .iter()
+        .position(|&level| level == current)
+        .unwrap_or(0);
+    levels[(index + 1) % levels.len()]
+}
+
+/// Export quality thresholds offered in settings; `None` keeps the one the
+/// profile or config sets.
+pub(crate) const EXPORT_MIN_QUALITY_OPTIONS: [Option<u8>; 5] =
+    [None, Some(20), Some(40), Some(60), Some(80)];
+
+/// "40", or "default" when no threshold is picked.
+pub(crate) fn export_min_quality_text(min_quality: Option<u8>) -> String {
+    min_quality.map_or_else(|| "default".to_string(), |score| score.to_string())
+}
+
+/// "Min quality: 40"; the native export quality button's caption.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn export_min_quality_button_label(min_quality: Option<u8>) -> String {
+    format!("Min quality: {}", export_min_quality_text(min_quality))
+}
+
+/// The threshold the native export quality button switches to next,
+/// wrapping from the strictest back to the default.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn next_export_min_quality(current: Option<u8>) -> Option<u8> {
+    let options = EXPORT_MIN_QUALITY_OPTIONS;
+    let index = options
+        .iter()
+        .position(|&option| option == current)
+        .unwrap_or(0);
+    options[(index + 1) % options.len()]
+}
+
+/// "Profile: work"; the native profile button's caption.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn profile_button_label(view: &AppViewModel) -> String {
+    format!(
+        "Profile: {}",
+        view.active_profile.as_deref().unwrap_or("default")
+    )
+}
+
+/// The profile the native profile button switches to next, once there is
+/// more than one to choose from and no jobs are running.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn next_profile(view: &AppViewModel) -> Option<String> {
+    let idle = matches!(view.session, SessionState::Idle | SessionState::Finished);
+    if view.profiles.len() < 2 || !idle {
+        return None;
+    }
+    let next = view
+        .active_profile
+        .as_ref()
+        .and_then(|active| view.profiles.iter().position(|name| name == active))
+        .map_or(0, |index| (index + 1) % view.profiles.len());
+    Some(view.profiles[next].clone())
+}
+
+/// "Images (3)"; the links pane's filter toggles.
+pub(crate) fn link_filter_label(filter: &LinkFilterView) -> String {
+    format!("{} ({})", filter.kind.label(), filter.count)
+}
+
+/// "External (12)"; the links pane's internal/external toggles.
+pub(crate) fn link_scope_label(filter: &LinkScopeFilterView) -> String {
+    format!("{} ({})", filter.scope.label(), filter.count)
+}
+
+/// "[x] Images (3)"; the native filter buttons show their toggle state in
+/// the caption.
+#[cfg(any(test, all(target_os = "windows", not(feature = "egui"))))]
+pub(crate) fn toggle_button_label(label: &str, on: bool) -> String {
+    format!("[{}] {label}", if on { 'x' } else { ' ' })
+}
+
+/// "Harvest selected (2)"; enabled while any link is checked.
+pub(crate) fn harvest_links_label(pane: &LinksPaneView) -> String {
+    match pane.checked.len() {
+        0 => "Harvest selected".to_string(),
+        count => format!("Harvest selected ({count})"),
+    }
+}
+
+/// "Getting started — https://example.com/start", or the bare URL when the
+/// link has no text.
+pub(crate) fn link_row_text(link: &JobLink) -> String {
+    match link.text.as_deref().map(str::trim) {
+        Some(text) if !text.is_empty() && text != link.url => format!("{text} — {}", link.url),
+        _ => link.url.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harvester_core::{
+        BudgetPreset, ChangeSummary, LastPasteStats, LinkKind, LinkScope, LogLevel,
+    };
+
+    #[test]
+    fn preview_header_includes_headings_and_tokens() {
+        let header = PreviewHeaderView {
+            domain: "example.com".to_string(),
+            tokens: Some(1234),
+            bytes: Some(2048),
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Success),
+            reading: Some((1_530, 7)),
+            heading_count: 8,
+            link_density: 0.0,
+            nav_heavy: false,
+            watched: false,
+            pinned: false,
+            change: None,
+            source_batch: Some("reading-list".to_string()),
+        };
+        assert_eq!(
+            format_preview_header(&header),
+            "example.com | from reading-list | 1,234 tokens | 2.0 KB | 1,530 words, 7 min read | 8 headings | Done"
+        );
+    }
+
+    #[test]
+    fn preview_header_appends_nav_heavy_indicator() {
+        let header = PreviewHeaderView {
+            domain: "dense.example".to_string(),
+            tokens: None,
+            bytes: None,
+            stage: Stage::Converting,
+            outcome: None,
+            reading: None,
+            heading_count: 0,
+            link_density: 1.0,
+            nav_heavy: true,
+            watched: false,
+            pinned: false,
+            change: None,
+            source_batch: None,
+        };
+        assert_eq!(
+            format_preview_header(&header),
+            "dense.example | 0 headings | Converting | [nav-heavy]"
+        );
+    }
+
+    #[test]
+    fn job_row_marks_watched_and_changed_jobs() {
+        let job = JobRowView {
+            job_id: 4,
+            url: "https://example.com".to_string(),
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Success),
+            tokens: Some(10),
+            bytes: None,
+            watched: false,
+            changed_from: Some(2),
+            duplicate_of: None,
+            change: None,
+            retry_in_secs: None,
+            quality_score: None,
+            low_quality: false,
+            nav_heavy: false,
+            word_count: None,
+            reading_minutes: None,
+            elapsed_ms: None,
+            pinned: false,
+            failure: None,
+        };
+        assert_eq!(
+            format_job_row(&job),
+            "[#4] OK — https://example.com (10 tok) [changed #2]"
+        );
+        let watched = JobRowView {
+            watched: true,
+            changed_from: None,
+            ..job
+        };
+        assert!(format_job_row(&watched).ends_with("(10 tok) [watched]"));
+        let changed = JobRowView {
+            change: Some(ChangeSummary {
+                added: 3,
+                removed: 1,
+            }),
+            ..watched
+        };
+        assert!(format_job_row(&changed).ends_with("(10 tok) [+3 -1] [watched]"));
+        let pinned = JobRowView {
+            pinned: true,
+            ..changed
+        };
+        assert!(format_job_row(&pinned).ends_with("[watched] [pinned]"));
+        let duplicate = JobRowView {
+            duplicate_of: Some(12),
+            watched: false,
+            change: None,
+            pinned: false,
+            ..pinned
+        };
+        assert!(format_job_row(&duplicate).ends_with("(10 tok) [dup of #12]"));
+    }
+
+    #[test]
+    fn job_row_shows_rate_limit_countdown_and_quality_flag() {
+        let job = JobRowView {
+            job_id: 5,
+            url: "https://example.com".to_string(),
+            stage: Stage::Downloading,
+            outcome: None,
+            tokens: None,
+            bytes: None,
+            watched: false,
+            changed_from: None,
+            duplicate_of: None,
+            change: None,
+            retry_in_secs: Some(30),
+            quality_score: None,
+            low_quality: false,
+            nav_heavy: false,
+            word_count: None,
+            reading_minutes: None,
+            elapsed_ms: None,
+            pinned: false,
+            failure: None,
+        };
+        assert_eq!(
+            format_job_row(&job),
+            "[#5] Rate limited, retrying in 30s — https://example.com"
+        );
+        let flagged = JobRowView {
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Success),
+            retry_in_secs: None,
+            quality_score: Some(23),
+            low_quality: true,
+            ..job
+        };
+        assert_eq!(
+            format_job_row(&flagged),
+            "[#5] OK — https://example.com [low quality 23]"
+        );
+        let partial = JobRowView {
+            outcome: Some(JobResultKind::PartialContent),
+            ..flagged
+        };
+        assert!(format_job_row(&partial).starts_with("[#5] PARTIAL — "));
+        let cached = JobRowView {
+            outcome: Some(JobResultKind::FromCache),
+            ..partial
+        };
+        assert!(format_job_row(&cached).starts_with("[#5] CACHED — "));
+        let timed = JobRowView {
+            tokens: Some(40),
+            elapsed_ms: Some(4_250),
+            low_quality: false,
+            ..cached
+        };
+        assert_eq!(
+            format_job_row(&timed),
+            "[#5] CACHED — https://example.com (40 tok, 4.2s)"
+        );
+        assert_eq!(format_elapsed(850), "850ms");
+        assert_eq!(format_elapsed(185_000), "3m 05s");
+        assert_eq!(format_bytes(1_023), "1023 B");
+        assert_eq!(format_bytes(1_536), "1.5 KB");
+        assert_eq!(format_bytes(1_048_576), "1.0 MB");
+        assert_eq!(format_bytes(5 * 1_073_741_824), "5.0 GB");
+    }
+
+    #[test]
+    fn row_tone_follows_job_status() {
+        let job = JobRowView {
+            job_id: 1,
+            url: "https://example.com".to_string(),
+            stage: Stage::Queued,
+            outcome: None,
+            tokens: None,
+            bytes: None,
+            watched: false,
+            changed_from: None,
+            duplicate_of: None,
+            change: None,
+            retry_in_secs: None,
+            quality_score: None,
+            low_quality: false,
+            nav_heavy: false,
+            word_count: None,
+            reading_minutes: None,
+            elapsed_ms: None,
+            pinned: false,
+            failure: None,
+        };
+        assert_eq!(job_row_tone(&job), RowTone::Normal);
+        let converting = JobRowView {
+            stage: Stage::Converting,
+            ..job.clone()
+        };
+        assert_eq!(job_row_tone(&converting), RowTone::InProgress);
+        let done = JobRowView {
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Success),
+            ..job.clone()
+        };
+        assert_eq!(job_row_tone(&done), RowTone::Normal);
+        let nav_heavy = JobRowView {
+            nav_heavy: true,
+            ..done.clone()
+        };
+        assert_eq!(job_row_tone(&nav_heavy), RowTone::Warning);
+        let failed = JobRowView {
+            outcome: Some(JobResultKind::Failed),
+            ..nav_heavy
+        };
+        assert_eq!(job_row_tone(&failed), RowTone::Failed);
+        let cancelled = JobRowView {
+            outcome: Some(JobResultKind::Cancelled),
+            ..failed
+        };
+        assert_eq!(job_row_tone(&cancelled), RowTone::Cancelled);
+        assert_eq!(job_cells(&cancelled)[1], "CANCELLED");
+    }
+
+    #[test]
+    fn job_cells_split_the_row_into_columns() {
+        let job = JobRowView {
+            job_id: 2,
+            url: "https://example.com".to_string(),
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Failed),
+            tokens: None,
+            bytes: Some(512),
+            watched: false,
+            changed_from: None,
+            duplicate_of: None,
+            change: None,
+            retry_in_secs: None,
+            quality_score: None,
+            low_quality: false,
+            nav_heavy: false,
+            word_count: None,
+            reading_minutes: None,
+            elapsed_ms: Some(1_500),
+            pinned: true,
+            failure: Some("http_404".to_string()),
+        };
+        assert_eq!(
+            job_cells(&job),
+            [
+                "https://example.com [pinned]",
+                "ERR",
+                "",
+                "512 B",
+                "1.5s",
+                "http_404",
+            ]
+            .map(String::from)
+        );
+        let sort = JobSort {
+            column: JobColumn::Bytes,
+            descending: true,
+        };
+        assert_eq!(column_header(JobColumn::Bytes, Some(sort)), "Bytes ▼");
+        assert_eq!(column_header(JobColumn::Url, Some(sort)), "URL");
+    }
+
+    #[test]
+    fn pending_paste_names_the_busiest_domains() {
+        let paste = PendingPaste {
+            count: 40,
+            duplicates: 2,
+            domains: [
+                ("a.example", 30),
+                ("b.example", 5),
+                ("c.example", 3),
+                ("d.example", 2),
+            ]
+            .map(|(domain, count)| (domain.to_string(), count))
+            .to_vec(),
+            rejected: RejectedUrls::default(),
+            source_batch: None,
+        };
+        assert_eq!(
+            pending_paste_text(&paste),
+            "Harvest 40 URLs from 4 domains (a.example 30, b.example 5, c.example 3, 1 more)? 2 duplicates are skipped."
+        );
+    }
+
+    #[test]
+    fn status_text_breaks_down_rejected_lines() {
+        let view = AppViewModel {
+            job_count: 1,
+            last_paste_stats: Some(LastPasteStats {
+                enqueued: 1,
+                skipped: 0,
+                rejected: RejectedUrls {
+                    unsupported_scheme: 2,
+                    missing_scheme: 1,
+                    malformed: 0,
+                },
+            }),
+            ..AppViewModel::default()
+        };
+        assert_eq!(
+            status_text(&view),
+            "Session: Idle | Jobs: 1 | Last paste: enqueued 1, skipped 0, rejected 3 (2 unsupported scheme, 1 missing scheme)"
+        );
+    }
+
+    #[test]
+    fn activity_text_lists_the_newest_events_first() {
+        let view = AppViewModel {
+            activity_log: vec![
+                ActivityEvent::JobStarted {
+                    job_id: 1,
+                    url: "https://a.example".to_string(),
+                },
+                ActivityEvent::JobFinished {
+                    job_id: 1,
+                    url: "https://a.example".to_string(),
+                    result: JobResultKind::FromCache,
+                },
+                ActivityEvent::ExportCompleted {
+                    exported_utc: "2024-01-01T00:00:00Z".to_string(),
+                },
+            ],
+            ..AppViewModel::default()
+        };
+        assert_eq!(
+            activity_text(&view),
+            "Export completed at 2024-01-01T00:00:00Z\n\
+             #1 finished (from cache): https://a.example\n\
+             #1 started: https://a.example"
+        );
+    }
+
+    #[test]
+    fn session_summary_lists_totals_and_failure_kinds() {
+        let summary = SessionSummary {
+            pages: 5,
+            succeeded: 2,
+            from_cache: 1,
+            partial: 0,
+            cancelled: 1,
+            failures: [("http_404".to_string(), 1), ("timeout".to_string(), 1)].into(),
+            total_tokens: 12_345,
+            total_bytes: 67_890,
+            duration_ms: Some(4_200),
+        };
+        assert_eq!(
+            format_session_summary(&summary),
+            "Pages: 5 (2 succeeded, 0 partial, 2 failed)\n\
+             From cache: 1\n\
+             Cancelled: 1\n\
+             Failures: http_404 1, timeout 1\n\
+             Tokens: 12,345 | Size: 66.3 KB\n\
+             Duration: 4.2s"
+        );
+    }
+
+    #[test]
+    fn budget_button_cycles_through_the_named_presets() {
+        assert_eq!(
+            budget_button_label(BudgetPreset::Claude),
+            "Budget: Claude (200k)"
+        );
+        assert_eq!(
+            budget_button_label(BudgetPreset::Custom(350_000)),
+            "Budget: 350,000"
+        );
+        assert_eq!(
+            next_budget_preset(BudgetPreset::Gpt4o),
+            BudgetPreset::Claude
+        );
+        assert_eq!(
+            next_budget_preset(BudgetPreset::Gemini),
+            BudgetPreset::Gpt4o
+        );
+        assert_eq!(
+            next_budget_preset(BudgetPreset::Custom(350_000)),
+            BudgetPreset::Gpt4o
+        );
+    }
+
+    #[test]
+    fn log_level_button_cycles_and_wraps() {
+        assert_eq!(log_level_button_label(LogLevel::Info), "Log: info");
+        assert_eq!(next_log_level(LogLevel::Info), LogLevel::Debug);
+        assert_eq!(next_log_level(LogLevel::Trace), LogLevel::Off);
+    }
+
+    #[test]
+    fn export_quality_button_cycles_and_wraps() {
+        assert_eq!(
+            export_min_quality_button_label(None),
+            "Min quality: default"
+        );
+        assert_eq!(export_min_quality_button_label(Some(40)), "Min quality: 40");
+        assert_eq!(next_export_min_quality(None), Some(20));
+        assert_eq!(next_export_min_quality(Some(80)), None);
+        assert_eq!(next_export_min_quality(Some(55)), Some(20));
+    }
+
+    #[test]
+    fn stop_button_backs_out_of_a_pending_stop_now() {
+        let mut view = AppViewModel {
+            session: SessionState::Finishing,
+            ..AppViewModel::default()
+        };
+        assert_eq!(stop_button_label(&view), "Stop / Finish");
+        assert!(!stop_button_enabled(&view));
+        view.stop_now_pending = true;
+        assert_eq!(stop_button_label(&view), "Keep Running");
+        assert!(stop_button_enabled(&view));
+    }
+
+    #[test]
+    fn profile_button_cycles_while_idle() {
+        let mut view = AppViewModel {
+            profiles: vec!["default".to_string(), "work".to_string()],
+            active_profile: Some("work".to_string()),
+            ..AppViewModel::default()
+        };
+        assert_eq!(profile_button_label(&view), "Profile: work");
+        assert_eq!(next_profile(&view).as_deref(), Some("default"));
+        view.session = SessionState::Running;
+        assert_eq!(next_profile(&view), None);
+        view.session = SessionState::Idle;
+        view.profiles.truncate(1);
+        assert_eq!(next_profile(&view), None);
+    }
+
+    #[test]
+    fn links_pane_labels_filters_and_rows() {
+        let filter = LinkFilterView {
+            kind: LinkKind::Image,
+            count: 3,
+            shown: true,
+        };
+        assert_eq!(link_filter_label(&filter), "Images (3)");
+        assert_eq!(
+            toggle_button_label(&link_filter_label(&filter), filter.shown),
+            "[x] Images (3)"
+        );
+        let scope = LinkScopeFilterView {
+            scope: LinkScope::External,
+            count: 12,
+            shown: false,
+        };
+        assert_eq!(link_scope_label(&scope), "External (12)");
+        let link = JobLink {
+            url: "https://example.com/start".to_string(),
+            text: Some(" Getting started ".to_string()),
+            kind: LinkKind::Hyperlink,
+            internal: true,
+        };
+        assert_eq!(
+            link_row_text(&link),
+            "Getting started — https://example.com/start"
+        );
+        let bare = JobLink { text: None, ..link };
+        assert_eq!(link_row_text(&bare), "https://example.com/start");
+    }
+}
@@ -0,0 +1,103 @@
+//! Opt-in session journal: with the `journal` feature and `HARVESTER_JOURNAL`
+//! set, every dispatched [`Msg`] is appended to a JSON-lines file that
+//! `harvester_core::replay` turns back into the session's state. `1` writes
+//! `session.journal.jsonl` in the output directory; any other value names the
+//! file. Each launch starts a new journal.
+#[cfg(feature = "journal")]
+use std::fs::File;
+#[cfg(feature = "journal")]
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[cfg(feature = "journal")]
+use engine_logging::{engine_info, engine_warn};
+use harvester_core::Msg;
+
+#[cfg(feature = "journal")]
+const JOURNAL_ENV: &str = "HARVESTER_JOURNAL";
+
+/// Appends dispatched messages to the journal; does nothing when recording is off.
+#[derive(Default)]
+pub(crate) struct MsgRecorder {
+    #[cfg(feature = "journal")]
+    out: Option<BufWriter<File>>,
+}
+
+impl MsgRecorder {
+    #[cfg(feature = "journal")]
+    pub(crate) fn from_env(output_dir: &Path) -> Self {
+        let Ok(value) = std::env::var(JOURNAL_ENV) else {
+            return Self::default();
+        };
+        let path = if value == "1" {
+            output_dir.join("session.journal.jsonl")
+        } else {
+            value.into()
+        };
+        match File::create(&path) {
+            Ok(file) => {
+                engine_info!("Recording messages to {:?}", path);
+                Self {
+                    out: Some(BufWriter::new(file)),
+                }
+            }
+            Err(err) => {
+                engine_warn!("Cannot create journal {:?}: {}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "journal"))]
+    pub(crate) fn from_env(_output_dir: &Path) -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&mut self, msg: &Msg) {
+        #[cfg(feature = "journal")]
+        if let Some(out) = &mut self.out {
+            // Ticks only pace rendering and would swamp the journal.
+            if matches!(msg, Msg::Tick | Msg::NoOp) {
+                return;
+            }
+            // Flushed per line so a crash keeps the messages leading up to it.
+            let written =
+                writeln!(out, "{}", harvester_core::journal_line(msg)).and_then(|()| out.flush());
+            if let Err(err) = written {
+                engine_warn!("Stopped recording the journal: {}", err);
+                self.out = None;
+            }
+        }
+        #[cfg(not(feature = "journal"))]
+        let _ = msg;
+    }
+}
+
+#[cfg(all(test, feature = "journal"))]
+mod tests {
+    use super::*;
+    use harvester_core::{read_journal, replay, update, AppState};
+
+    #[test]
+    fn recorded_session_replays_to_the_same_state() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("journal.jsonl");
+        let mut recorder = MsgRecorder {
+            out: Some(BufWriter::new(File::create(&path).unwrap())),
+        };
+        let mut state = AppState::new();
+        for msg in [
+            Msg::InputChanged("https://a.example".to_string()),
+            Msg::Tick,
+            Msg::UrlsSubmitted,
+            Msg::StopFinishClicked,
+        ] {
+            recorder.record(&msg);
+            state = update(state, msg).0;
+        }
+
+        let msgs = read_journal(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(msgs.len(), 3);
+        assert_eq!(replay(AppState::new(), msgs).0, state);
+    }
+}
@@ -1,10 +1,15 @@
 //! Platform logging initialization for harvester_app.
 //!
-//! Writes logs to `./engine.log` in the current working directory.
+//! Writes logs to `./engine.log` in the current working directory. The level
+//! starts at `HARVESTER_LOG`, else the level saved with the session (default
+//! `info`), and can be changed while running from settings. With the `json-log` feature,
+//! `HARVESTER_LOG_JSON` adds a JSON-lines sink: `1` writes `./engine.jsonl`,
+//! any other value names the file.
 
 use std::fs::File;
 use std::path::PathBuf;
 
+use harvester_core::LogLevel;
 use log::LevelFilter;
 use simplelog::{
     ColorChoice, CombinedLogger, Config, ConfigBuilder, SharedLogger, TermLogger, TerminalMode,
@@ -27,7 +32,8 @@ pub enum LogDestination {
 /// For `LogDestination::File` or `Both`, creates `./engine.log` in the
 /// current working directory.
 pub fn initialize(destination: LogDestination) {
-    let level = LevelFilter::Info;
+    // Loggers pass everything; the reloadable global filter decides.
+    let level = LevelFilter::Trace;
 
     let config = build_config();
 
@@ -61,7 +67,38 @@ pub fn initialize(destination: LogDestination) {
         }
     };
 
+    #[cfg(feature = "json-log")]
+    let loggers = {
+        let mut loggers = loggers;
+        if let Some(json_logger) = json::from_env() {
+            loggers.push(json_logger);
+        }
+        loggers
+    };
+
     let _ = CombinedLogger::init(loggers);
+    engine_logging::set_level(engine_logging::level_from_env(LevelFilter::Info));
+}
+
+/// The level to restore at startup: `HARVESTER_LOG` when set, else `saved`.
+pub(crate) fn startup_level(saved: Option<LogLevel>) -> LogLevel {
+    let default = level_filter(saved.unwrap_or_default());
+    let level = engine_logging::level_from_env(default);
+    LogLevel::ALL
+        .into_iter()
+        .find(|&option| level_filter(option) == level)
+        .unwrap_or_default()
+}
+
+pub(crate) fn level_filter(level: LogLevel) -> LevelFilter {
+    match level {
+        LogLevel::Off => LevelFilter::Off,
+        LogLevel::Error => LevelFilter::Error,
+        LogLevel::Warn => LevelFilter::Warn,
+        LogLevel::Info => LevelFilter::Info,
+        LogLevel::Debug => LevelFilter::Debug,
+        LogLevel::Trace => LevelFilter::Trace,
+    }
 }
 
 fn build_config() -> Config {
@@ -84,3 +121,123 @@ fn create_file_logger(level: LevelFilter, config: Config) -> Option<Box<WriteLog
         }
     }
 }
+
+#[cfg(feature = "json-log")]
+mod json {
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    use log::{LevelFilter, Log, Metadata, Record};
+    use serde_json::json;
+    use simplelog::{Config, SharedLogger};
+
+    const LOG_JSON_ENV: &str = "HARVESTER_LOG_JSON";
+
+    /// One JSON object per record: level, timestamp, target, job_id, message.
+    pub(super) struct JsonLogger<W: Write + Send> {
+        out: Mutex<W>,
+    }
+
+    pub(super) fn from_env() -> Option<Box<JsonLogger<BufWriter<File>>>> {
+        let value = std::env::var(LOG_JSON_ENV).ok()?;
+        let path = if value == "1" {
+            PathBuf::from("./engine.jsonl")
+        } else {
+            PathBuf::from(value)
+        };
+        match File::create(&path) {
+            Ok(file) => Some(Box::new(JsonLogger::new(BufWriter::new(file)))),
+            Err(err) => {
+                eprintln!("Warning: Could not create JSON log at {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    impl<W: Write + Send> JsonLogger<W> {
+        pub(super) fn new(out: W) -> Self {
+            Self {
+                out: Mutex::new(out),
+            }
+        }
+
+        #[cfg(test)]
+        pub(super) fn into_inner(self) -> W {
+            self.out.into_inner().expect("json log lock")
+        }
+    }
+
+    impl<W: Write + Send> Log for JsonLogger<W> {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            // The engine_* macros already put the job in the message; it
+            // has its own field here.
+            let message = record.args().to_string();
+            let prefix = engine_logging::log_prefix().to_string();
+            let message = message.strip_prefix(prefix.as_str()).unwrap_or(&message);
+            let line = json!({
+                "level": record.level().as_str(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "target": record.target(),
+                "job_id": engine_logging::job_context(),
+                "message": message,
+            });
+            if let Ok(mut out) = self.out.lock() {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+
+        fn flush(&self) {
+            if let Ok(mut out) = self.out.lock() {
+                let _ = out.flush();
+            }
+        }
+    }
+
+    impl<W: Write + Send + 'static> SharedLogger for JsonLogger<W> {
+        fn level(&self) -> LevelFilter {
+            LevelFilter::Trace
+        }
+
+        fn config(&self) -> Option<&Config> {
+            None
+        }
+
+        fn as_log(self: Box<Self>) -> Box<dyn Log> {
+            Box::new(*self)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn records_become_json_lines_with_the_job_context() {
+            let logger = JsonLogger::new(Vec::new());
+            engine_logging::with_job_context(9, || {
+                let prefix = engine_logging::log_prefix();
+                logger.log(
+                    &Record::builder()
+                        .level(log::Level::Warn)
+                        .target("harvester_engine::fetch")
+                        .args(format_args!("{prefix}Slow host"))
+                        .build(),
+                );
+            });
+
+            let out = String::from_utf8(logger.into_inner()).unwrap();
+            let record: serde_json::Value = serde_json::from_str(out.trim_end()).unwrap();
+            assert_eq!(record["level"], "WARN");
+            assert_eq!(record["target"], "harvester_engine::fetch");
+            assert_eq!(record["job_id"], 9);
+            assert_eq!(record["message"], "Slow host");
+            assert!(record["timestamp"].is_string());
+        }
+    }
+}
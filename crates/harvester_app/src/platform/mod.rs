@@ -1,7 +1,22 @@
+#[cfg(feature = "api")]
+mod api;
+#[cfg(all(target_os = "windows", not(feature = "egui")))]
 mod app;
+mod desktop;
 mod effects;
+#[cfg(feature = "egui")]
+mod egui_app;
+mod format;
+mod journal;
 mod logging;
 mod persistence;
+mod profiles;
+#[cfg(all(target_os = "windows", not(feature = "egui")))]
 mod ui;
+#[cfg(all(target_os = "windows", not(feature = "egui")))]
+mod window_placement;
 
+#[cfg(all(target_os = "windows", not(feature = "egui")))]
 pub use app::run_app;
+#[cfg(feature = "egui")]
+pub use egui_app::run_egui_app;
@@ -2,11 +2,17 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use engine_logging::{engine_error, engine_info, engine_warn};
-use harvester_core::CompletedJobSnapshot;
-use harvester_engine::{ensure_output_dir, AtomicFileWriter};
+use harvester_core::{
+    BudgetPreset, CompletedJobSnapshot, LogLevel, PanelWidths, PersistedSession, WindowGeometry,
+};
+use harvester_engine::{ensure_output_dir, is_encrypted, AtomicFileWriter, Passphrase};
 use serde::{Deserialize, Serialize};
 
 const STATE_FILENAME: &str = ".harvester_state.ron";
+/// The previous good state file, restored when the current one is corrupt.
+const BACKUP_FILENAME: &str = ".harvester_state.ron.bak";
+/// Directory under the output dir that keeps the sessions cleared by "New Session".
+const SESSIONS_DIRNAME: &str = "sessions";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PersistedJob {
@@ -15,31 +21,96 @@ struct PersistedJob {
     bytes: Option<u64>,
     #[serde(default)]
     links: Vec<String>,
+    #[serde(default)]
+    pinned: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct PersistedState {
     completed: Vec<PersistedJob>,
+    #[serde(default)]
+    last_export_utc: Option<String>,
+    /// [`BudgetPreset::id`] of the active budget.
+    #[serde(default)]
+    budget: Option<String>,
+    /// [`LogLevel::id`] of the log level picked in settings.
+    #[serde(default)]
+    log_level: Option<String>,
+    /// Export quality threshold picked in settings; `None` keeps the configured one.
+    #[serde(default)]
+    export_min_quality: Option<u8>,
+    #[serde(default)]
+    panel_widths: Option<PersistedPanelWidths>,
+    #[serde(default)]
+    window: Option<PersistedWindowGeometry>,
 }
 
-pub(crate) fn load_completed_jobs(output_dir: &Path) -> Vec<CompletedJobSnapshot> {
-    let path = output_dir.join(STATE_FILENAME);
-    let content = match fs::read_to_string(&path) {
-        Ok(text) => text,
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-            return Vec::new();
-        }
-        Err(err) => {
-            engine_warn!("Failed to read persisted state from {:?}: {}", path, err);
-            return Vec::new();
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PersistedPanelWidths {
+    input: i32,
+    jobs: i32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct PersistedWindowGeometry {
+    width: i32,
+    height: i32,
+    #[serde(default)]
+    position: Option<(i32, i32)>,
+    #[serde(default)]
+    maximized: bool,
+}
+
+/// Everything the app restores from (and saves to) the state file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct SessionSnapshot {
+    pub completed: Vec<CompletedJobSnapshot>,
+    pub last_export_utc: Option<String>,
+    pub budget: Option<BudgetPreset>,
+    pub log_level: Option<LogLevel>,
+    pub export_min_quality: Option<u8>,
+    pub panel_widths: Option<PanelWidths>,
+    pub window_geometry: Option<WindowGeometry>,
+    /// The state file was corrupt and this came from its backup.
+    pub recovered_from_backup: bool,
+}
+
+impl From<PersistedSession> for SessionSnapshot {
+    fn from(session: PersistedSession) -> Self {
+        Self {
+            completed: session.completed,
+            last_export_utc: session.last_export_utc,
+            budget: Some(session.budget),
+            log_level: Some(session.log_level),
+            export_min_quality: session.export_min_quality,
+            panel_widths: Some(session.panel_widths),
+            window_geometry: Some(session.window_geometry),
+            recovered_from_backup: false,
         }
-    };
+    }
+}
 
-    let state: PersistedState = match ron::from_str(&content) {
-        Ok(state) => state,
+/// Load the session, decrypting it with `passphrase` when it was saved
+/// encrypted. A plain state file still loads, and is encrypted on next save.
+pub(crate) fn load_session(output_dir: &Path, passphrase: Option<&Passphrase>) -> SessionSnapshot {
+    let path = output_dir.join(STATE_FILENAME);
+    let (state, recovered_from_backup) = match read_state(&path, passphrase) {
+        Ok(Some(state)) => (state, false),
+        Ok(None) => return SessionSnapshot::default(),
         Err(err) => {
-            engine_warn!("Failed to parse persisted state from {:?}: {}", path, err);
-            return Vec::new();
+            engine_warn!("{}", err);
+            let backup = output_dir.join(BACKUP_FILENAME);
+            match read_state(&backup, passphrase) {
+                Ok(Some(state)) => {
+                    engine_warn!("Recovered persisted state from backup {:?}", backup);
+                    (state, true)
+                }
+                Ok(None) => return SessionSnapshot::default(),
+                Err(err) => {
+                    engine_warn!("{}", err);
+                    return SessionSnapshot::default();
+                }
+            }
         }
     };
 
@@ -51,42 +122,103 @@ pub(crate) fn load_completed_jobs(output_dir: &Path) -> Vec<CompletedJobSnapshot
             tokens: job.tokens,
             bytes: job.bytes,
             links: job.links,
+            pinned: job.pinned,
         })
         .collect();
 
     engine_info!("Loaded persisted completed jobs from {:?}", path);
-    completed
+    SessionSnapshot {
+        completed,
+        last_export_utc: state.last_export_utc,
+        budget: state.budget.as_deref().and_then(BudgetPreset::from_id),
+        log_level: state.log_level.as_deref().and_then(LogLevel::from_id),
+        export_min_quality: state.export_min_quality,
+        panel_widths: state.panel_widths.map(|widths| PanelWidths {
+            input: widths.input,
+            jobs: widths.jobs,
+        }),
+        window_geometry: state.window.map(|window| WindowGeometry {
+            width: window.width,
+            height: window.height,
+            position: window.position,
+            maximized: window.maximized,
+        }),
+        recovered_from_backup,
+    }
 }
 
-pub(crate) fn save_completed_jobs(output_dir: &Path, completed: &[CompletedJobSnapshot]) {
+/// The state in `path`; `None` when there is no such file.
+fn read_state(
+    path: &Path,
+    passphrase: Option<&Passphrase>,
+) -> Result<Option<PersistedState>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(format!(
+                "Failed to read persisted state from {path:?}: {err}"
+            ))
+        }
+    };
+    let bytes = match (is_encrypted(&bytes), passphrase) {
+        (false, _) => bytes,
+        (true, Some(passphrase)) => passphrase
+            .decrypt(&bytes)
+            .map_err(|err| format!("Failed to decrypt persisted state {path:?}: {err}"))?,
+        (true, None) => return Err(format!("Persisted state {path:?} is encrypted")),
+    };
+    let content = String::from_utf8_lossy(&bytes);
+    ron::from_str(&content)
+        .map(Some)
+        .map_err(|err| format!("Failed to parse persisted state from {path:?}: {err}"))
+}
+
+/// Write the session, keeping the state file it replaces as a backup. The new
+/// content must parse back before anything on disk is touched, and a primary
+/// that no longer parses is not copied over a good backup. With a
+/// `passphrase` the file is written encrypted.
+pub(crate) fn save_session(
+    output_dir: &Path,
+    session: &SessionSnapshot,
+    passphrase: Option<&Passphrase>,
+) {
     if let Err(err) = ensure_output_dir(output_dir) {
         engine_error!("Failed to ensure output dir {:?}: {}", output_dir, err);
         return;
     }
 
     let state = PersistedState {
-        completed: completed
-            .iter()
-            .map(|job| PersistedJob {
-                url: job.url.clone(),
-                tokens: job.tokens,
-                bytes: job.bytes,
-                links: job.links.clone(),
-            })
-            .collect(),
+        completed: persisted_jobs(&session.completed),
+        last_export_utc: session.last_export_utc.clone(),
+        budget: session.budget.map(BudgetPreset::id),
+        log_level: session.log_level.map(|level| level.id().to_string()),
+        export_min_quality: session.export_min_quality,
+        panel_widths: session.panel_widths.map(|widths| PersistedPanelWidths {
+            input: widths.input,
+            jobs: widths.jobs,
+        }),
+        window: session
+            .window_geometry
+            .map(|geometry| PersistedWindowGeometry {
+                width: geometry.width,
+                height: geometry.height,
+                position: geometry.position,
+                maximized: geometry.maximized,
+            }),
     };
-
-    let pretty = ron::ser::PrettyConfig::new();
-    let content = match ron::ser::to_string_pretty(&state, pretty) {
-        Ok(text) => text,
-        Err(err) => {
-            engine_error!("Failed to serialize persisted state: {}", err);
-            return;
-        }
+    let Some(content) = encode_state(&state, passphrase) else {
+        return;
     };
 
     let writer = AtomicFileWriter::new(PathBuf::from(output_dir));
-    if let Err(err) = writer.write(STATE_FILENAME, &content) {
+    let primary = output_dir.join(STATE_FILENAME);
+    if let (Ok(Some(_)), Ok(previous)) = (read_state(&primary, passphrase), fs::read(&primary)) {
+        if let Err(err) = writer.write_bytes(BACKUP_FILENAME, &previous) {
+            engine_warn!("Failed to back up persisted state: {}", err);
+        }
+    }
+    if let Err(err) = writer.write_bytes(STATE_FILENAME, &content) {
         engine_error!(
             "Failed to write persisted state to {:?}: {}",
             output_dir,
@@ -95,6 +227,74 @@ pub(crate) fn save_completed_jobs(output_dir: &Path, completed: &[CompletedJobSn
     }
 }
 
+/// Keep the completed jobs of a session that "New Session" is about to clear
+/// as `sessions/session-{stamp}.ron`, in the state file format.
+pub(crate) fn archive_session(
+    output_dir: &Path,
+    completed: &[CompletedJobSnapshot],
+    stamp: &str,
+    passphrase: Option<&Passphrase>,
+) -> Option<PathBuf> {
+    let dir = output_dir.join(SESSIONS_DIRNAME);
+    let state = PersistedState {
+        completed: persisted_jobs(completed),
+        ..PersistedState::default()
+    };
+    let content = encode_state(&state, passphrase)?;
+    let filename = format!("session-{stamp}.ron");
+    match AtomicFileWriter::new(dir.clone()).write_bytes(&filename, &content) {
+        Ok(path) => {
+            engine_info!("Archived {} completed jobs to {:?}", completed.len(), path);
+            Some(path)
+        }
+        Err(err) => {
+            engine_error!("Failed to archive session to {:?}: {}", dir, err);
+            None
+        }
+    }
+}
+
+fn persisted_jobs(completed: &[CompletedJobSnapshot]) -> Vec<PersistedJob> {
+    completed
+        .iter()
+        .map(|job| PersistedJob {
+            url: job.url.clone(),
+            tokens: job.tokens,
+            bytes: job.bytes,
+            links: job.links.clone(),
+            pinned: job.pinned,
+        })
+        .collect()
+}
+
+/// Serialize `state`, checking that it parses back, and encrypt it with
+/// `passphrase` when one is given. `None` (after logging) on failure.
+fn encode_state(state: &PersistedState, passphrase: Option<&Passphrase>) -> Option<Vec<u8>> {
+    let pretty = ron::ser::PrettyConfig::new();
+    let content = match ron::ser::to_string_pretty(state, pretty) {
+        Ok(text) => text,
+        Err(err) => {
+            engine_error!("Failed to serialize persisted state: {}", err);
+            return None;
+        }
+    };
+    if let Err(err) = ron::from_str::<PersistedState>(&content) {
+        engine_error!("Serialized state does not parse back, not saving: {}", err);
+        return None;
+    }
+
+    match passphrase {
+        Some(passphrase) => match passphrase.encrypt(content.as_bytes()) {
+            Ok(sealed) => Some(sealed),
+            Err(err) => {
+                engine_error!("Failed to encrypt persisted state: {}", err);
+                None
+            }
+        },
+        None => Some(content.into_bytes()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,24 +323,128 @@ mod tests {
 
         write_state(temp.path(), content);
 
-        let snapshot = load_completed_jobs(temp.path());
-        assert_eq!(snapshot.len(), 1);
-        assert!(snapshot[0].links.is_empty());
+        let snapshot = load_session(temp.path(), None);
+        assert_eq!(snapshot.completed.len(), 1);
+        assert!(snapshot.completed[0].links.is_empty());
+        assert_eq!(snapshot.last_export_utc, None);
     }
 
     #[test]
-    fn save_and_load_roundtrips_links() {
+    fn save_and_load_roundtrips_links_pins_and_layout() {
         let temp = tempdir().expect("tempdir");
-        let snapshot = vec![CompletedJobSnapshot {
+        let snapshot = SessionSnapshot {
+            completed: vec![CompletedJobSnapshot {
+                url: "https://example.com".to_string(),
+                tokens: Some(10),
+                bytes: Some(512),
+                links: vec!["https://a".to_string(), "https://b".to_string()],
+                pinned: true,
+            }],
+            last_export_utc: Some("2024-05-01T10:00:00Z".to_string()),
+            budget: Some(BudgetPreset::Custom(64_000)),
+            log_level: Some(LogLevel::Debug),
+            export_min_quality: Some(60),
+            panel_widths: Some(PanelWidths {
+                input: 400,
+                jobs: 220,
+            }),
+            window_geometry: Some(WindowGeometry {
+                width: 1280,
+                height: 800,
+                position: Some((-40, 24)),
+                maximized: true,
+            }),
+            recovered_from_backup: false,
+        };
+
+        save_session(temp.path(), &snapshot, None);
+        let loaded = load_session(temp.path(), None);
+
+        assert_eq!(loaded, snapshot);
+    }
+
+    #[test]
+    fn corrupt_state_falls_back_to_the_previous_save() {
+        let temp = tempdir().expect("tempdir");
+        let session = |url: &str| SessionSnapshot {
+            completed: vec![CompletedJobSnapshot {
+                url: url.to_string(),
+                tokens: None,
+                bytes: None,
+                links: Vec::new(),
+                pinned: false,
+            }],
+            last_export_utc: None,
+            budget: None,
+            log_level: None,
+            export_min_quality: None,
+            panel_widths: None,
+            window_geometry: None,
+            recovered_from_backup: false,
+        };
+        save_session(temp.path(), &session("https://first"), None);
+        save_session(temp.path(), &session("https://second"), None);
+        assert_eq!(load_session(temp.path(), None), session("https://second"));
+
+        write_state(temp.path(), "(completed: [ truncated");
+        let loaded = load_session(temp.path(), None);
+        assert!(loaded.recovered_from_backup);
+        assert_eq!(loaded.completed, session("https://first").completed);
+
+        // The corrupt file is not rotated over the good backup.
+        save_session(temp.path(), &session("https://third"), None);
+        let backup = fs::read_to_string(temp.path().join(BACKUP_FILENAME)).unwrap();
+        assert!(backup.contains("https://first"));
+    }
+
+    #[test]
+    fn encrypted_state_needs_the_passphrase() {
+        let temp = tempdir().expect("tempdir");
+        let passphrase = Passphrase::new("hunter2").unwrap();
+        let snapshot = SessionSnapshot {
+            completed: vec![CompletedJobSnapshot {
+                url: "https://intranet/spec".to_string(),
+                tokens: None,
+                bytes: None,
+                links: Vec::new(),
+                pinned: false,
+            }],
+            last_export_utc: None,
+            budget: None,
+            log_level: None,
+            export_min_quality: None,
+            panel_widths: None,
+            window_geometry: None,
+            recovered_from_backup: false,
+        };
+
+        save_session(temp.path(), &snapshot, Some(&passphrase));
+
+        let raw = fs::read(temp.path().join(STATE_FILENAME)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("intranet"));
+        assert_eq!(load_session(temp.path(), Some(&passphrase)), snapshot);
+        assert_eq!(load_session(temp.path(), None), SessionSnapshot::default());
+    }
+
+    #[test]
+    fn archived_session_loads_like_a_state_file() {
+        let temp = tempdir().expect("tempdir");
+        let completed = vec![CompletedJobSnapshot {
             url: "https://example.com".to_string(),
-            tokens: Some(10),
-            bytes: Some(512),
-            links: vec!["https://a".to_string(), "https://b".to_string()],
+            tokens: Some(7),
+            bytes: None,
+            links: Vec::new(),
+            pinned: false,
         }];
 
-        save_completed_jobs(temp.path(), &snapshot);
-        let loaded = load_completed_jobs(temp.path());
+        let path =
+            archive_session(temp.path(), &completed, "20240501T100000Z", None).expect("archived");
 
-        assert_eq!(loaded, snapshot);
+        assert_eq!(
+            path,
+            temp.path().join("sessions/session-20240501T100000Z.ron")
+        );
+        fs::copy(&path, temp.path().join(STATE_FILENAME)).unwrap();
+        assert_eq!(load_session(temp.path(), None).completed, completed);
     }
 }
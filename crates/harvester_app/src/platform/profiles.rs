@@ -0,0 +1,253 @@
+//! Output profiles: named output directories, each with its own filename and
+//! export settings, so separate harvests never share a folder.
+//!
+//! Profiles are read from [`PROFILES_FILENAME`] in the working directory:
+//!
+//! ```text
+//! [work]
+//! dir: harvests/work
+//! export: chunks:512:64
+//! export_min_quality: 40
+//! strip_rules: rules/work.txt
+//!
+//! [personal]
+//! dir: harvests/personal
+//! ascii_filenames: true
+//! ```
+//!
+//! Relative paths are resolved against the working directory. A profile
+//! without `strip_rules` uses [`STRIP_RULES_FILENAME`] in its output
+//! directory when there is one (`selector:` and `text:` lines, see
+//! [`StripRules`]). The
+//! [`DEFAULT_PROFILE`] writes to `output/` with the settings from the
+//! environment, unless the file redefines it. `export` takes `concatenated`,
+//! `chunks:{max_tokens}[:{overlap}]`, `obsidian`, `html` or `fit:{order}`.
+use std::path::{Path, PathBuf};
+
+use engine_logging::{engine_info, engine_warn};
+use harvester_engine::{EngineBuilder, ExportFormat, FilenameOptions, StripRules};
+
+use super::effects::{default_output_dir, parse_chunk_options, parse_fit_order};
+
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+
+const PROFILES_FILENAME: &str = "harvester_profiles.txt";
+
+/// Per-project strip rules, kept next to the documents they apply to.
+const STRIP_RULES_FILENAME: &str = "strip_rules.txt";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OutputProfile {
+    pub name: String,
+    pub output_dir: PathBuf,
+    /// Settings left `None` keep what the environment configured.
+    pub export_format: Option<ExportFormat>,
+    pub export_min_quality: Option<u8>,
+    pub ascii_filenames: Option<bool>,
+    pub strip_rules: Option<PathBuf>,
+}
+
+impl OutputProfile {
+    fn new(name: &str, output_dir: PathBuf) -> Self {
+        Self {
+            name: name.to_string(),
+            output_dir,
+            export_format: None,
+            export_min_quality: None,
+            ascii_filenames: None,
+            strip_rules: None,
+        }
+    }
+
+    /// `builder` with this profile's settings on top.
+    pub(crate) fn apply(&self, mut builder: EngineBuilder) -> EngineBuilder {
+        if let Some(format) = self.export_format {
+            builder = builder.export_format(format);
+        }
+        if let Some(min_score) = self.export_min_quality {
+            builder = builder.export_min_quality(min_score);
+        }
+        if let Some(transliterate) = self.ascii_filenames {
+            builder = builder.filename_options(FilenameOptions { transliterate });
+        }
+        if let Some(rules) = self.load_strip_rules() {
+            builder = builder.strip_rules(rules);
+        }
+        builder
+    }
+
+    fn load_strip_rules(&self) -> Option<StripRules> {
+        let path = match &self.strip_rules {
+            Some(path) => path.clone(),
+            None => {
+                Some(self.output_dir.join(STRIP_RULES_FILENAME)).filter(|path| path.exists())?
+            }
+        };
+        match StripRules::load(&path) {
+            Ok(rules) => {
+                engine_info!("Loaded {} strip rules from {:?}", rules.len(), path);
+                Some(rules)
+            }
+            Err(err) => {
+                engine_warn!("Ignoring strip rules {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+}
+
+/// The default profile first, then the ones from the profiles file in
+/// the working directory, if there is one.
+pub(crate) fn load_profiles() -> Vec<OutputProfile> {
+    let default = OutputProfile::new(DEFAULT_PROFILE, default_output_dir());
+    let base_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let path = base_dir.join(PROFILES_FILENAME);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return vec![default];
+    };
+    match parse_profiles(&text, &base_dir) {
+        Ok(mut profiles) => {
+            engine_info!("Loaded {} output profiles from {:?}", profiles.len(), path);
+            if !profiles
+                .iter()
+                .any(|profile| profile.name == DEFAULT_PROFILE)
+            {
+                profiles.insert(0, default);
+            }
+            profiles
+        }
+        Err(err) => {
+            engine_warn!("Ignoring output profiles {:?}: {}", path, err);
+            vec![default]
+        }
+    }
+}
+
+fn parse_profiles(text: &str, base_dir: &Path) -> Result<Vec<OutputProfile>, String> {
+    let mut profiles: Vec<OutputProfile> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: &str| format!("line {}: {message}", index + 1);
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            let name = name.trim();
+            if name.is_empty() || profiles.iter().any(|profile| profile.name == name) {
+                return Err(invalid("missing or repeated profile name"));
+            }
+            profiles.push(OutputProfile::new(name, base_dir.join(name)));
+            continue;
+        }
+        let profile = profiles
+            .last_mut()
+            .ok_or_else(|| invalid("setting outside a [profile] section"))?;
+        let (key, value) = line
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .ok_or_else(|| invalid("expected `key: value`"))?;
+        match key {
+            "dir" => profile.output_dir = base_dir.join(value),
+            "export" => {
+                profile.export_format =
+                    Some(parse_export_format(value).ok_or_else(|| invalid("unknown export"))?)
+            }
+            "export_min_quality" => {
+                profile.export_min_quality =
+                    Some(value.parse().map_err(|_| invalid("expected 0-100"))?)
+            }
+            "strip_rules" => profile.strip_rules = Some(base_dir.join(value)),
+            "ascii_filenames" => {
+                profile.ascii_filenames = Some(
+                    value
+                        .parse()
+                        .map_err(|_| invalid("expected true or false"))?,
+                )
+            }
+            _ => return Err(invalid("unknown setting")),
+        }
+    }
+    Ok(profiles)
+}
+
+fn parse_export_format(value: &str) -> Option<ExportFormat> {
+    let (kind, options) = match value.split_once(':') {
+        Some((kind, options)) => (kind, Some(options)),
+        None => (value, None),
+    };
+    match (kind.trim(), options) {
+        ("concatenated", None) => Some(ExportFormat::Concatenated),
+        ("obsidian", None) => Some(ExportFormat::ObsidianVault),
+        ("html", None) => Some(ExportFormat::Html),
+        ("chunks", Some(options)) => parse_chunk_options(options).map(ExportFormat::Chunks),
+        ("fit", Some(order)) => parse_fit_order(order).map(ExportFormat::FitToContext),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harvester_engine::{ChunkOptions, FitOrder};
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn profiles_resolve_directories_and_export_settings() {
+        let text = "# harvests\n[work]\ndir: harvests/work\nexport: chunks:512:64\nexport_min_quality: 40\nstrip_rules: rules/work.txt\n\n[personal]\nexport: fit:newest\nascii_filenames: true\n";
+        let profiles = parse_profiles(text, Path::new("/projects")).unwrap();
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].output_dir, Path::new("/projects/harvests/work"));
+        assert_eq!(
+            profiles[0].export_format,
+            Some(ExportFormat::Chunks(ChunkOptions {
+                max_tokens: 512,
+                overlap_tokens: 64,
+            }))
+        );
+        assert_eq!(profiles[0].export_min_quality, Some(40));
+        assert_eq!(
+            profiles[0].strip_rules.as_deref(),
+            Some(Path::new("/projects/rules/work.txt"))
+        );
+        assert_eq!(profiles[1].strip_rules, None);
+        assert_eq!(profiles[1].output_dir, Path::new("/projects/personal"));
+        assert_eq!(
+            profiles[1].export_format,
+            Some(ExportFormat::FitToContext(FitOrder::Newest))
+        );
+        assert_eq!(profiles[1].ascii_filenames, Some(true));
+    }
+
+    #[test]
+    fn strip_rules_follow_the_active_profile() {
+        let temp = tempdir().expect("tempdir");
+        let work = temp.path().join("work");
+        let personal = temp.path().join("personal");
+        fs::create_dir_all(&work).unwrap();
+        fs::write(work.join(STRIP_RULES_FILENAME), "selector: nav\n").unwrap();
+        let shared = temp.path().join("shared.txt");
+        fs::write(&shared, "selector: .ad\ntext: Subscribe\n").unwrap();
+
+        let mut profile = OutputProfile::new("work", work.clone());
+        assert_eq!(profile.load_strip_rules().map(|rules| rules.len()), Some(1));
+        profile.strip_rules = Some(shared);
+        assert_eq!(profile.load_strip_rules().map(|rules| rules.len()), Some(2));
+        let profile = OutputProfile::new("personal", personal);
+        assert!(profile.load_strip_rules().is_none());
+    }
+
+    #[test]
+    fn malformed_profiles_report_their_line() {
+        let err = parse_profiles("dir: x\n", Path::new("/p")).unwrap_err();
+        assert!(err.starts_with("line 1:"), "{err}");
+        let err = parse_profiles("[a]\nexport: zip\n", Path::new("/p")).unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        let err = parse_profiles("[a]\n[a]\n", Path::new("/p")).unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+    }
+}
@@ -3,17 +3,61 @@ use commanductui::types::ControlId;
 pub const INPUT_URLS: ControlId = ControlId::new(1001);
 pub const BUTTON_STOP: ControlId = ControlId::new(1003);
 pub const BUTTON_ARCHIVE: ControlId = ControlId::new(1004);
+pub const BUTTON_ARCHIVE_NEW: ControlId = ControlId::new(1005);
+pub const BUTTON_WATCH: ControlId = ControlId::new(1006);
+pub const BUTTON_DIFF: ControlId = ControlId::new(1007);
+pub const BUTTON_NEW_SESSION: ControlId = ControlId::new(1008);
+pub const BUTTON_PIN: ControlId = ControlId::new(1009);
+pub const BUTTON_COPY_URL: ControlId = ControlId::new(1010);
+pub const BUTTON_OPEN_URL: ControlId = ControlId::new(1011);
+pub const BUTTON_STOP_NOW: ControlId = ControlId::new(1012);
+pub const BUTTON_HARVEST_PASTE: ControlId = ControlId::new(1013);
+pub const BUTTON_DISCARD_PASTE: ControlId = ControlId::new(1014);
+pub const BUTTON_EXPORT_IMAGES: ControlId = ControlId::new(1015);
+pub const BUTTON_BUDGET: ControlId = ControlId::new(1016);
+pub const BUTTON_PROFILE: ControlId = ControlId::new(1017);
+pub const BUTTON_LOG_LEVEL: ControlId = ControlId::new(1018);
+pub const BUTTON_HARVEST_LINKS: ControlId = ControlId::new(1019);
+/// Link kind filter toggles, in `LinkKind::ALL` order.
+pub const BUTTON_LINK_KINDS: [ControlId; 3] = [
+    ControlId::new(1020),
+    ControlId::new(1021),
+    ControlId::new(1022),
+];
+/// Internal/external filter toggles, in `LinkScope::ALL` order.
+pub const BUTTON_LINK_SCOPES: [ControlId; 2] = [ControlId::new(1023), ControlId::new(1024)];
+/// Jobs list column headers, in `JobColumn::ALL` order.
+pub const BUTTON_JOB_COLUMNS: [ControlId; 6] = [
+    ControlId::new(1025),
+    ControlId::new(1026),
+    ControlId::new(1027),
+    ControlId::new(1028),
+    ControlId::new(1029),
+    ControlId::new(1030),
+];
+pub const BUTTON_MIN_QUALITY: ControlId = ControlId::new(1031);
 pub const TREE_JOBS: ControlId = ControlId::new(1501);
+pub const TREE_LINKS: ControlId = ControlId::new(1502);
 pub const PANEL_BOTTOM: ControlId = ControlId::new(2001);
 pub const PANEL_INPUT: ControlId = ControlId::new(2002);
 pub const PANEL_PROGRESS: ControlId = ControlId::new(2003);
 pub const PANEL_BUTTONS: ControlId = ControlId::new(2004);
 pub const PANEL_PREVIEW: ControlId = ControlId::new(2005);
 pub const PANEL_JOBS: ControlId = ControlId::new(2006);
+pub const PANEL_ACTIVITY: ControlId = ControlId::new(2007);
+pub const PANEL_SETTINGS: ControlId = ControlId::new(2008);
+pub const PANEL_LINKS: ControlId = ControlId::new(2009);
+pub const PANEL_LINK_FILTERS: ControlId = ControlId::new(2010);
+pub const PANEL_JOB_COLUMNS: ControlId = ControlId::new(2011);
 pub const LABEL_STATUS: ControlId = ControlId::new(3001);
 pub const LABEL_INPUT_HINT: ControlId = ControlId::new(3002);
 pub const LABEL_TOKEN_PROGRESS: ControlId = ControlId::new(3003);
 pub const LABEL_PREVIEW_HEADER: ControlId = ControlId::new(3004);
 pub const LABEL_JOBS_HEADER: ControlId = ControlId::new(3005);
+pub const LABEL_LINKS_HEADER: ControlId = ControlId::new(3006);
 pub const PROGRESS_TOKENS: ControlId = ControlId::new(4001);
 pub const VIEWER_PREVIEW: ControlId = ControlId::new(5001);
+pub const VIEWER_ACTIVITY: ControlId = ControlId::new(5002);
+pub const VIEWER_SUMMARY: ControlId = ControlId::new(5003);
+pub const SPLITTER_INPUT_JOBS: ControlId = ControlId::new(6001);
+pub const SPLITTER_JOBS_PREVIEW: ControlId = ControlId::new(6002);
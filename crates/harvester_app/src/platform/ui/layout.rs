@@ -1,13 +1,19 @@
-use commanductui::types::{DockStyle, LabelClass, LayoutRule};
+use commanductui::types::{DockStyle, LabelClass, LayoutRule, SplitterOrientation};
 use commanductui::{
     Color, ControlStyle, FontDescription, FontWeight, PlatformCommand, StyleId, WindowId,
 };
-use harvester_core::TOKEN_LIMIT;
+use harvester_core::{BudgetPreset, JobColumn, LogLevel, PanelWidths};
 
 use super::constants::*;
+use crate::platform::format::{
+    budget_button_label, export_min_quality_button_label, log_level_button_label,
+};
+
+/// Width of the splitter bars between the side-by-side panels.
+const SPLITTER_WIDTH: i32 = 4;
 
 #[allow(clippy::vec_init_then_push)]
-pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
+pub fn initial_commands(window_id: WindowId, widths: PanelWidths) -> Vec<PlatformCommand> {
     let mut commands = Vec::new();
     define_dark_theme_styles(&mut commands);
 
@@ -29,6 +35,18 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         control_id: PANEL_BUTTONS,
     });
 
+    commands.push(PlatformCommand::CreatePanel {
+        window_id,
+        parent_control_id: None,
+        control_id: PANEL_SETTINGS,
+    });
+
+    commands.push(PlatformCommand::CreatePanel {
+        window_id,
+        parent_control_id: None,
+        control_id: PANEL_ACTIVITY,
+    });
+
     commands.push(PlatformCommand::CreatePanel {
         window_id,
         parent_control_id: None,
@@ -47,6 +65,15 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         control_id: PANEL_PREVIEW,
     });
 
+    for control_id in [SPLITTER_INPUT_JOBS, SPLITTER_JOBS_PREVIEW] {
+        commands.push(PlatformCommand::CreateSplitter {
+            window_id,
+            parent_control_id: None,
+            control_id,
+            orientation: SplitterOrientation::Vertical,
+        });
+    }
+
     commands.push(PlatformCommand::CreateLabel {
         window_id,
         parent_control_id: Some(PANEL_PREVIEW),
@@ -65,6 +92,43 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         vertical_scroll: true,
     });
 
+    commands.push(PlatformCommand::CreatePanel {
+        window_id,
+        parent_control_id: Some(PANEL_PREVIEW),
+        control_id: PANEL_LINKS,
+    });
+    commands.push(PlatformCommand::CreateLabel {
+        window_id,
+        parent_control_id: Some(PANEL_LINKS),
+        control_id: LABEL_LINKS_HEADER,
+        initial_text: String::new(),
+        class: LabelClass::Default,
+    });
+    commands.push(PlatformCommand::CreatePanel {
+        window_id,
+        parent_control_id: Some(PANEL_LINKS),
+        control_id: PANEL_LINK_FILTERS,
+    });
+    for control_id in BUTTON_LINK_KINDS.into_iter().chain(BUTTON_LINK_SCOPES) {
+        commands.push(PlatformCommand::CreateButton {
+            window_id,
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            control_id,
+            text: String::new(),
+        });
+    }
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_LINK_FILTERS),
+        control_id: BUTTON_HARVEST_LINKS,
+        text: "Harvest selected".to_string(),
+    });
+    commands.push(PlatformCommand::CreateTreeView {
+        window_id,
+        parent_control_id: Some(PANEL_LINKS),
+        control_id: TREE_LINKS,
+    });
+
     commands.push(PlatformCommand::CreateLabel {
         window_id,
         parent_control_id: Some(PANEL_JOBS),
@@ -73,17 +137,43 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         class: LabelClass::Default,
     });
 
+    // CommanDuctUI has no list view, so the column headers are a row of
+    // buttons above the tree; clicking one sorts by that column.
+    commands.push(PlatformCommand::CreatePanel {
+        window_id,
+        parent_control_id: Some(PANEL_JOBS),
+        control_id: PANEL_JOB_COLUMNS,
+    });
+    for (control_id, column) in BUTTON_JOB_COLUMNS.into_iter().zip(JobColumn::ALL) {
+        commands.push(PlatformCommand::CreateButton {
+            window_id,
+            parent_control_id: Some(PANEL_JOB_COLUMNS),
+            control_id,
+            text: column.label().to_string(),
+        });
+    }
+
     commands.push(PlatformCommand::CreateTreeView {
         window_id,
         parent_control_id: Some(PANEL_JOBS),
         control_id: TREE_JOBS,
     });
 
+    commands.push(PlatformCommand::CreateInput {
+        window_id,
+        parent_control_id: Some(PANEL_JOBS),
+        control_id: VIEWER_SUMMARY,
+        initial_text: String::new(),
+        read_only: true,
+        multiline: true,
+        vertical_scroll: false,
+    });
+
     commands.push(PlatformCommand::CreateLabel {
         window_id,
         parent_control_id: Some(PANEL_PROGRESS),
         control_id: LABEL_TOKEN_PROGRESS,
-        initial_text: format!("Tokens: 0 / {} (0%)", TOKEN_LIMIT),
+        initial_text: format!("Tokens: 0 / {} (0%)", BudgetPreset::default().token_limit()),
         class: LabelClass::Default,
     });
 
@@ -97,7 +187,7 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         window_id,
         parent_control_id: Some(PANEL_INPUT),
         control_id: LABEL_INPUT_HINT,
-        initial_text: "Paste URL(s) here. Jobs are created immediately.".to_string(),
+        initial_text: "Paste URL(s) here. Large pastes wait for \"Harvest Paste\".".to_string(),
         class: LabelClass::Default,
     });
 
@@ -117,6 +207,30 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         control_id: BUTTON_STOP,
         text: "Stop / Finish".to_string(),
     });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_STOP_NOW,
+        text: "Stop Now".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_NEW_SESSION,
+        text: "New Session".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_HARVEST_PASTE,
+        text: "Harvest Paste".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_DISCARD_PASTE,
+        text: "Discard Paste".to_string(),
+    });
 
     commands.push(PlatformCommand::CreateButton {
         window_id,
@@ -124,6 +238,84 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
         control_id: BUTTON_ARCHIVE,
         text: "Archive".to_string(),
     });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_EXPORT_IMAGES,
+        text: "Export Images".to_string(),
+    });
+
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_ARCHIVE_NEW,
+        text: "Export New Only".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_WATCH,
+        text: "Watch".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_PIN,
+        text: "Pin".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_COPY_URL,
+        text: "Copy URL".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_OPEN_URL,
+        text: "Open".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_BUTTONS),
+        control_id: BUTTON_DIFF,
+        text: "Show Diff".to_string(),
+    });
+
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_SETTINGS),
+        control_id: BUTTON_BUDGET,
+        text: budget_button_label(BudgetPreset::default()),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_SETTINGS),
+        control_id: BUTTON_PROFILE,
+        text: "Profile: default".to_string(),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_SETTINGS),
+        control_id: BUTTON_LOG_LEVEL,
+        text: log_level_button_label(LogLevel::default()),
+    });
+    commands.push(PlatformCommand::CreateButton {
+        window_id,
+        parent_control_id: Some(PANEL_SETTINGS),
+        control_id: BUTTON_MIN_QUALITY,
+        text: export_min_quality_button_label(None),
+    });
+
+    commands.push(PlatformCommand::CreateInput {
+        window_id,
+        parent_control_id: Some(PANEL_ACTIVITY),
+        control_id: VIEWER_ACTIVITY,
+        initial_text: String::new(),
+        read_only: true,
+        multiline: true,
+        vertical_scroll: false,
+    });
 
     commands.push(PlatformCommand::CreateLabel {
         window_id,
@@ -135,159 +327,7 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
 
     apply_dark_theme(window_id, &mut commands);
 
-    commands.push(PlatformCommand::DefineLayout {
-        window_id,
-        rules: vec![
-            // Progress panel at the top
-            LayoutRule {
-                control_id: PANEL_PROGRESS,
-                parent_control_id: None,
-                dock_style: DockStyle::Top,
-                order: 0,
-                fixed_size: Some(64),
-                margin: (0, 0, 0, 0),
-            },
-            // Progress label and bar inside the panel
-            LayoutRule {
-                control_id: LABEL_TOKEN_PROGRESS,
-                parent_control_id: Some(PANEL_PROGRESS),
-                dock_style: DockStyle::Top,
-                order: 0,
-                fixed_size: Some(22),
-                margin: (8, 8, 4, 8),
-            },
-            LayoutRule {
-                control_id: PROGRESS_TOKENS,
-                parent_control_id: Some(PANEL_PROGRESS),
-                dock_style: DockStyle::Fill,
-                order: 1,
-                fixed_size: None,
-                margin: (0, 8, 8, 8),
-            },
-            // Status bar panel at the very bottom
-            LayoutRule {
-                control_id: PANEL_BOTTOM,
-                parent_control_id: None,
-                dock_style: DockStyle::Bottom,
-                order: 100,
-                fixed_size: Some(32),
-                margin: (0, 0, 0, 0),
-            },
-            // Buttons panel above the status bar
-            LayoutRule {
-                control_id: PANEL_BUTTONS,
-                parent_control_id: None,
-                dock_style: DockStyle::Bottom,
-                order: 110,
-                fixed_size: Some(44),
-                margin: (0, 0, 0, 0),
-            },
-            // URL drop box on the left (fixed width)
-            LayoutRule {
-                control_id: PANEL_INPUT,
-                parent_control_id: None,
-                dock_style: DockStyle::Left,
-                order: 200,
-                fixed_size: Some(320),
-                margin: (6, 6, 6, 6),
-            },
-            // Jobs panel fills the new left column
-            LayoutRule {
-                control_id: PANEL_JOBS,
-                parent_control_id: None,
-                dock_style: DockStyle::Left,
-                order: 300,
-                fixed_size: Some(280),
-                margin: (6, 6, 6, 6),
-            },
-            // Jobs header label
-            LayoutRule {
-                control_id: LABEL_JOBS_HEADER,
-                parent_control_id: Some(PANEL_JOBS),
-                dock_style: DockStyle::Top,
-                order: 0,
-                fixed_size: Some(28),
-                margin: (0, 0, 4, 0),
-            },
-            // Jobs tree fills remaining space in panel
-            LayoutRule {
-                control_id: TREE_JOBS,
-                parent_control_id: Some(PANEL_JOBS),
-                dock_style: DockStyle::Fill,
-                order: 1,
-                fixed_size: None,
-                margin: (0, 0, 0, 0),
-            },
-            LayoutRule {
-                control_id: PANEL_PREVIEW,
-                parent_control_id: None,
-                dock_style: DockStyle::Fill,
-                order: 310,
-                fixed_size: None,
-                margin: (6, 6, 6, 6),
-            },
-            LayoutRule {
-                control_id: LABEL_PREVIEW_HEADER,
-                parent_control_id: Some(PANEL_PREVIEW),
-                dock_style: DockStyle::Top,
-                order: 0,
-                fixed_size: Some(28),
-                margin: (6, 6, 4, 0),
-            },
-            LayoutRule {
-                control_id: VIEWER_PREVIEW,
-                parent_control_id: Some(PANEL_PREVIEW),
-                dock_style: DockStyle::Fill,
-                order: 1,
-                fixed_size: None,
-                margin: (0, 0, 0, 0),
-            },
-            // Input hint label above the text box
-            LayoutRule {
-                control_id: LABEL_INPUT_HINT,
-                parent_control_id: Some(PANEL_INPUT),
-                dock_style: DockStyle::Top,
-                order: 0,
-                fixed_size: Some(28),
-                margin: (0, 0, 4, 0),
-            },
-            // URL input fills remaining space
-            LayoutRule {
-                control_id: INPUT_URLS,
-                parent_control_id: Some(PANEL_INPUT),
-                dock_style: DockStyle::Fill,
-                order: 1,
-                fixed_size: None,
-                margin: (0, 0, 0, 0),
-            },
-            // Status label fills the panel
-            LayoutRule {
-                control_id: LABEL_STATUS,
-                parent_control_id: Some(PANEL_BOTTOM),
-                dock_style: DockStyle::Fill,
-                order: 0,
-                fixed_size: None,
-                margin: (6, 6, 6, 6),
-            },
-            // Buttons placed horizontally with fixed width
-            LayoutRule {
-                control_id: BUTTON_ARCHIVE,
-                parent_control_id: Some(PANEL_BUTTONS),
-                dock_style: DockStyle::Left,
-                order: 0,
-                fixed_size: Some(160),
-                margin: (6, 6, 6, 6),
-            },
-            LayoutRule {
-                control_id: BUTTON_STOP,
-                parent_control_id: Some(PANEL_BUTTONS),
-                dock_style: DockStyle::Left,
-                order: 1,
-                fixed_size: Some(160),
-                margin: (6, 6, 6, 0),
-            },
-        ],
-    });
+    commands.push(layout_command(window_id, widths));
 
     commands.push(PlatformCommand::SignalMainWindowUISetupComplete { window_id });
     commands.push(PlatformCommand::ShowWindow { window_id });
@@ -295,6 +335,446 @@ pub fn initial_commands(window_id: WindowId) -> Vec<PlatformCommand> {
     commands
 }
 
+/// The main window layout for the given panel widths; sent again whenever a
+/// splitter moves them.
+pub fn layout_command(window_id: WindowId, widths: PanelWidths) -> PlatformCommand {
+    PlatformCommand::DefineLayout {
+        window_id,
+        rules: layout_rules(widths),
+    }
+}
+
+fn layout_rules(widths: PanelWidths) -> Vec<LayoutRule> {
+    let mut rules = vec![
+        // Progress panel at the top
+        LayoutRule {
+            control_id: PANEL_PROGRESS,
+            parent_control_id: None,
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(64),
+            margin: (0, 0, 0, 0),
+        },
+        // Progress label and bar inside the panel
+        LayoutRule {
+            control_id: LABEL_TOKEN_PROGRESS,
+            parent_control_id: Some(PANEL_PROGRESS),
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(22),
+            margin: (8, 8, 4, 8),
+        },
+        LayoutRule {
+            control_id: PROGRESS_TOKENS,
+            parent_control_id: Some(PANEL_PROGRESS),
+            dock_style: DockStyle::Fill,
+            order: 1,
+            fixed_size: None,
+            margin: (0, 8, 8, 8),
+        },
+        // Status bar panel at the very bottom
+        LayoutRule {
+            control_id: PANEL_BOTTOM,
+            parent_control_id: None,
+            dock_style: DockStyle::Bottom,
+            order: 100,
+            fixed_size: Some(32),
+            margin: (0, 0, 0, 0),
+        },
+        // Buttons panel above the status bar
+        LayoutRule {
+            control_id: PANEL_BUTTONS,
+            parent_control_id: None,
+            dock_style: DockStyle::Bottom,
+            order: 110,
+            fixed_size: Some(44),
+            margin: (0, 0, 0, 0),
+        },
+        // Link filter toggles, then the harvest button
+        LayoutRule {
+            control_id: BUTTON_LINK_KINDS[0],
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 0,
+            fixed_size: Some(110),
+            margin: (4, 4, 4, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_LINK_KINDS[1],
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 1,
+            fixed_size: Some(110),
+            margin: (4, 4, 4, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_LINK_KINDS[2],
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 2,
+            fixed_size: Some(110),
+            margin: (4, 4, 4, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_LINK_SCOPES[0],
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 3,
+            fixed_size: Some(120),
+            margin: (4, 4, 4, 12),
+        },
+        LayoutRule {
+            control_id: BUTTON_LINK_SCOPES[1],
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 4,
+            fixed_size: Some(120),
+            margin: (4, 4, 4, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_HARVEST_LINKS,
+            parent_control_id: Some(PANEL_LINK_FILTERS),
+            dock_style: DockStyle::Left,
+            order: 5,
+            fixed_size: Some(160),
+            margin: (4, 4, 4, 12),
+        },
+        // Settings row (budget, profile, log level) above the buttons
+        LayoutRule {
+            control_id: PANEL_SETTINGS,
+            parent_control_id: None,
+            dock_style: DockStyle::Bottom,
+            order: 115,
+            fixed_size: Some(44),
+            margin: (0, 0, 0, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_BUDGET,
+            parent_control_id: Some(PANEL_SETTINGS),
+            dock_style: DockStyle::Left,
+            order: 0,
+            fixed_size: Some(200),
+            margin: (6, 6, 6, 6),
+        },
+        LayoutRule {
+            control_id: BUTTON_PROFILE,
+            parent_control_id: Some(PANEL_SETTINGS),
+            dock_style: DockStyle::Left,
+            order: 1,
+            fixed_size: Some(200),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_LOG_LEVEL,
+            parent_control_id: Some(PANEL_SETTINGS),
+            dock_style: DockStyle::Left,
+            order: 2,
+            fixed_size: Some(120),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_MIN_QUALITY,
+            parent_control_id: Some(PANEL_SETTINGS),
+            dock_style: DockStyle::Left,
+            order: 3,
+            fixed_size: Some(160),
+            margin: (6, 6, 6, 0),
+        },
+        // Activity ticker above the settings row
+        LayoutRule {
+            control_id: PANEL_ACTIVITY,
+            parent_control_id: None,
+            dock_style: DockStyle::Bottom,
+            order: 120,
+            fixed_size: Some(96),
+            margin: (0, 0, 0, 0),
+        },
+        LayoutRule {
+            control_id: VIEWER_ACTIVITY,
+            parent_control_id: Some(PANEL_ACTIVITY),
+            dock_style: DockStyle::Fill,
+            order: 0,
+            fixed_size: None,
+            margin: (4, 6, 4, 6),
+        },
+        // URL drop box on the left, as wide as the user dragged it
+        LayoutRule {
+            control_id: PANEL_INPUT,
+            parent_control_id: None,
+            dock_style: DockStyle::Left,
+            order: 200,
+            fixed_size: Some(widths.input),
+            margin: (6, 6, 6, 6),
+        },
+        LayoutRule {
+            control_id: SPLITTER_INPUT_JOBS,
+            parent_control_id: None,
+            dock_style: DockStyle::Left,
+            order: 250,
+            fixed_size: Some(SPLITTER_WIDTH),
+            margin: (6, 0, 6, 0),
+        },
+        // Jobs panel in the second left column
+        LayoutRule {
+            control_id: PANEL_JOBS,
+            parent_control_id: None,
+            dock_style: DockStyle::Left,
+            order: 300,
+            fixed_size: Some(widths.jobs),
+            margin: (6, 6, 6, 6),
+        },
+        LayoutRule {
+            control_id: SPLITTER_JOBS_PREVIEW,
+            parent_control_id: None,
+            dock_style: DockStyle::Left,
+            order: 305,
+            fixed_size: Some(SPLITTER_WIDTH),
+            margin: (6, 0, 6, 0),
+        },
+        // Jobs header label
+        LayoutRule {
+            control_id: LABEL_JOBS_HEADER,
+            parent_control_id: Some(PANEL_JOBS),
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(28),
+            margin: (0, 0, 4, 0),
+        },
+        // Column header buttons between the label and the tree
+        LayoutRule {
+            control_id: PANEL_JOB_COLUMNS,
+            parent_control_id: Some(PANEL_JOBS),
+            dock_style: DockStyle::Top,
+            order: 1,
+            fixed_size: Some(30),
+            margin: (0, 0, 2, 0),
+        },
+        // Session summary below the tree; empty until the session finishes
+        LayoutRule {
+            control_id: VIEWER_SUMMARY,
+            parent_control_id: Some(PANEL_JOBS),
+            dock_style: DockStyle::Bottom,
+            order: 2,
+            fixed_size: Some(96),
+            margin: (4, 0, 0, 0),
+        },
+        // Jobs tree fills remaining space in panel
+        LayoutRule {
+            control_id: TREE_JOBS,
+            parent_control_id: Some(PANEL_JOBS),
+            dock_style: DockStyle::Fill,
+            order: 3,
+            fixed_size: None,
+            margin: (0, 0, 0, 0),
+        },
+        LayoutRule {
+            control_id: PANEL_PREVIEW,
+            parent_control_id: None,
+            dock_style: DockStyle::Fill,
+            order: 310,
+            fixed_size: None,
+            margin: (6, 6, 6, 6),
+        },
+        LayoutRule {
+            control_id: LABEL_PREVIEW_HEADER,
+            parent_control_id: Some(PANEL_PREVIEW),
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(28),
+            margin: (6, 6, 4, 0),
+        },
+        LayoutRule {
+            control_id: VIEWER_PREVIEW,
+            parent_control_id: Some(PANEL_PREVIEW),
+            dock_style: DockStyle::Fill,
+            order: 1,
+            fixed_size: None,
+            margin: (0, 0, 0, 0),
+        },
+        // Links of the selected page below the preview
+        LayoutRule {
+            control_id: PANEL_LINKS,
+            parent_control_id: Some(PANEL_PREVIEW),
+            dock_style: DockStyle::Bottom,
+            order: 2,
+            fixed_size: Some(240),
+            margin: (6, 0, 0, 0),
+        },
+        LayoutRule {
+            control_id: LABEL_LINKS_HEADER,
+            parent_control_id: Some(PANEL_LINKS),
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(24),
+            margin: (0, 6, 2, 0),
+        },
+        LayoutRule {
+            control_id: PANEL_LINK_FILTERS,
+            parent_control_id: Some(PANEL_LINKS),
+            dock_style: DockStyle::Top,
+            order: 1,
+            fixed_size: Some(36),
+            margin: (0, 0, 0, 0),
+        },
+        LayoutRule {
+            control_id: TREE_LINKS,
+            parent_control_id: Some(PANEL_LINKS),
+            dock_style: DockStyle::Fill,
+            order: 2,
+            fixed_size: None,
+            margin: (4, 0, 0, 0),
+        },
+        // Input hint label above the text box
+        LayoutRule {
+            control_id: LABEL_INPUT_HINT,
+            parent_control_id: Some(PANEL_INPUT),
+            dock_style: DockStyle::Top,
+            order: 0,
+            fixed_size: Some(28),
+            margin: (0, 0, 4, 0),
+        },
+        // URL input fills remaining space
+        LayoutRule {
+            control_id: INPUT_URLS,
+            parent_control_id: Some(PANEL_INPUT),
+            dock_style: DockStyle::Fill,
+            order: 1,
+            fixed_size: None,
+            margin: (0, 0, 0, 0),
+        },
+        // Status label fills the panel
+        LayoutRule {
+            control_id: LABEL_STATUS,
+            parent_control_id: Some(PANEL_BOTTOM),
+            dock_style: DockStyle::Fill,
+            order: 0,
+            fixed_size: None,
+            margin: (6, 6, 6, 6),
+        },
+        // Buttons placed horizontally with fixed width
+        LayoutRule {
+            control_id: BUTTON_ARCHIVE,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 0,
+            fixed_size: Some(160),
+            margin: (6, 6, 6, 6),
+        },
+        LayoutRule {
+            control_id: BUTTON_EXPORT_IMAGES,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 1,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_ARCHIVE_NEW,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 2,
+            fixed_size: Some(160),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_STOP,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 3,
+            fixed_size: Some(160),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_STOP_NOW,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 4,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_WATCH,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 5,
+            fixed_size: Some(120),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_DIFF,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 6,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_NEW_SESSION,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 7,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_PIN,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 8,
+            fixed_size: Some(100),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_COPY_URL,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 9,
+            fixed_size: Some(100),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_OPEN_URL,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 10,
+            fixed_size: Some(100),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_HARVEST_PASTE,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 11,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+        LayoutRule {
+            control_id: BUTTON_DISCARD_PASTE,
+            parent_control_id: Some(PANEL_BUTTONS),
+            dock_style: DockStyle::Left,
+            order: 12,
+            fixed_size: Some(140),
+            margin: (6, 6, 6, 0),
+        },
+    ];
+    // Column headers left to right; the URL one is widest.
+    rules.extend(
+        BUTTON_JOB_COLUMNS
+            .into_iter()
+            .zip(JobColumn::ALL)
+            .enumerate()
+            .map(|(order, (control_id, column))| LayoutRule {
+                control_id,
+                parent_control_id: Some(PANEL_JOB_COLUMNS),
+                dock_style: DockStyle::Left,
+                order: order as u32,
+                fixed_size: Some(if column == JobColumn::Url { 180 } else { 70 }),
+                margin: (2, 2, 2, 0),
+            }),
+    );
+    rules
+}
+
 fn define_dark_theme_styles(commands: &mut Vec<PlatformCommand>) {
     commands.push(PlatformCommand::DefineStyle {
         style_id: StyleId::MainWindowBackground,
@@ -464,15 +944,84 @@ fn define_dark_theme_styles(commands: &mut Vec<PlatformCommand>) {
             ..Default::default()
         },
     });
+
+    commands.push(PlatformCommand::DefineStyle {
+        style_id: StyleId::Splitter,
+        style: ControlStyle {
+            background_color: Some(Color {
+                r: 0x40,
+                g: 0x44,
+                b: 0x4B,
+            }),
+            ..Default::default()
+        },
+    });
+
+    // Per-row overrides for the jobs tree, picked by job status.
+    commands.push(PlatformCommand::DefineStyle {
+        style_id: StyleId::TreeItemFailed,
+        style: ControlStyle {
+            background_color: Some(Color {
+                r: 0x26,
+                g: 0x2A,
+                b: 0x2E,
+            }),
+            text_color: Some(Color {
+                r: 0xE0,
+                g: 0x6C,
+                b: 0x75,
+            }),
+            ..Default::default()
+        },
+    });
+
+    commands.push(PlatformCommand::DefineStyle {
+        style_id: StyleId::TreeItemWarning,
+        style: ControlStyle {
+            background_color: Some(Color {
+                r: 0x26,
+                g: 0x2A,
+                b: 0x2E,
+            }),
+            text_color: Some(Color {
+                r: 0xE5,
+                g: 0xC0,
+                b: 0x7B,
+            }),
+            ..Default::default()
+        },
+    });
+
+    commands.push(PlatformCommand::DefineStyle {
+        style_id: StyleId::TreeItemInProgress,
+        style: ControlStyle {
+            background_color: Some(Color {
+                r: 0x26,
+                g: 0x2A,
+                b: 0x2E,
+            }),
+            text_color: Some(Color {
+                r: 0x61,
+                g: 0xAF,
+                b: 0xEF,
+            }),
+            ..Default::default()
+        },
+    });
 }
 
 fn apply_dark_theme(window_id: WindowId, commands: &mut Vec<PlatformCommand>) {
     for control_id in [
         PANEL_PROGRESS,
         PANEL_BUTTONS,
+        PANEL_SETTINGS,
+        PANEL_ACTIVITY,
         PANEL_INPUT,
         PANEL_JOBS,
         PANEL_PREVIEW,
+        PANEL_LINKS,
+        PANEL_LINK_FILTERS,
+        PANEL_JOB_COLUMNS,
     ] {
         commands.push(PlatformCommand::ApplyStyleToControl {
             window_id,
@@ -497,6 +1046,11 @@ fn apply_dark_theme(window_id: WindowId, commands: &mut Vec<PlatformCommand>) {
         control_id: LABEL_JOBS_HEADER,
         style_id: StyleId::HeaderLabel,
     });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: LABEL_LINKS_HEADER,
+        style_id: StyleId::HeaderLabel,
+    });
     commands.push(PlatformCommand::ApplyStyleToControl {
         window_id,
         control_id: LABEL_TOKEN_PROGRESS,
@@ -523,27 +1077,113 @@ fn apply_dark_theme(window_id: WindowId, commands: &mut Vec<PlatformCommand>) {
         control_id: VIEWER_PREVIEW,
         style_id: StyleId::ViewerMonospace,
     });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: VIEWER_ACTIVITY,
+        style_id: StyleId::DefaultInput,
+    });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: VIEWER_SUMMARY,
+        style_id: StyleId::DefaultInput,
+    });
 
     commands.push(PlatformCommand::ApplyStyleToControl {
         window_id,
         control_id: BUTTON_STOP,
         style_id: StyleId::DefaultButton,
     });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_STOP_NOW,
+        style_id: StyleId::DefaultButton,
+    });
     commands.push(PlatformCommand::ApplyStyleToControl {
         window_id,
         control_id: BUTTON_ARCHIVE,
         style_id: StyleId::DefaultButton,
     });
-
     commands.push(PlatformCommand::ApplyStyleToControl {
         window_id,
-        control_id: TREE_JOBS,
-        style_id: StyleId::TreeView,
+        control_id: BUTTON_ARCHIVE_NEW,
+        style_id: StyleId::DefaultButton,
+    });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_EXPORT_IMAGES,
+        style_id: StyleId::DefaultButton,
+    });
+    for control_id in [
+        BUTTON_BUDGET,
+        BUTTON_PROFILE,
+        BUTTON_LOG_LEVEL,
+        BUTTON_MIN_QUALITY,
+        BUTTON_HARVEST_LINKS,
+    ]
+    .into_iter()
+    .chain(BUTTON_LINK_KINDS)
+    .chain(BUTTON_LINK_SCOPES)
+    .chain(BUTTON_JOB_COLUMNS)
+    {
+        commands.push(PlatformCommand::ApplyStyleToControl {
+            window_id,
+            control_id,
+            style_id: StyleId::DefaultButton,
+        });
+    }
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_WATCH,
+        style_id: StyleId::DefaultButton,
+    });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_DIFF,
+        style_id: StyleId::DefaultButton,
+    });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_NEW_SESSION,
+        style_id: StyleId::DefaultButton,
+    });
+    commands.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: BUTTON_PIN,
+        style_id: StyleId::DefaultButton,
     });
 
+    for control_id in [
+        BUTTON_COPY_URL,
+        BUTTON_OPEN_URL,
+        BUTTON_HARVEST_PASTE,
+        BUTTON_DISCARD_PASTE,
+    ] {
+        commands.push(PlatformCommand::ApplyStyleToControl {
+            window_id,
+            control_id,
+            style_id: StyleId::DefaultButton,
+        });
+    }
+
+    for control_id in [TREE_JOBS, TREE_LINKS] {
+        commands.push(PlatformCommand::ApplyStyleToControl {
+            window_id,
+            control_id,
+            style_id: StyleId::TreeView,
+        });
+    }
+
     commands.push(PlatformCommand::ApplyStyleToControl {
         window_id,
         control_id: PROGRESS_TOKENS,
         style_id: StyleId::ProgressBar,
     });
+
+    for control_id in [SPLITTER_INPUT_JOBS, SPLITTER_JOBS_PREVIEW] {
+        commands.push(PlatformCommand::ApplyStyleToControl {
+            window_id,
+            control_id,
+            style_id: StyleId::Splitter,
+        });
+    }
 }
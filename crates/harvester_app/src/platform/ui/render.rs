@@ -1,14 +1,34 @@
-use commanductui::types::{TreeItemDescriptor, TreeItemId};
+use commanductui::types::{ControlId, TreeItemDescriptor, TreeItemId};
 use commanductui::{CheckState, MessageSeverity, PlatformCommand, StyleId, WindowId};
 use harvester_core::{
-    AppViewModel, JobResultKind, JobRowView, PreviewHeaderView, SessionState, Stage,
+    AppViewModel, BudgetLevel, JobColumn, JobLink, JobResultKind, JobRowView, LinkKind, LinkScope,
+    LinksPaneView,
 };
 
 use super::constants::*;
+use crate::platform::format::{
+    activity_text, archive_button_label, archive_enabled, budget_button_label, column_header,
+    diff_button_label, export_min_quality_button_label, format_job_row, format_preview_header,
+    format_session_summary, harvest_links_label, job_row_tone, link_filter_label, link_row_text,
+    link_scope_label, log_level_button_label, new_session_enabled, next_profile,
+    profile_button_label, status_is_warning, status_text, stop_button_enabled, stop_button_label,
+    stop_now_button_label, stop_now_enabled, toggle_button_label, token_progress_text, RowTone,
+};
 use std::collections::HashMap;
 
+/// Link rows get ids from here up, clear of job ids; tree events carry no
+/// control id to tell the two trees apart.
+const LINK_ITEM_ID_BASE: u64 = 1 << 48;
+
 #[derive(Debug, Default)]
 pub struct TreeRenderState {
+    jobs: TreeDiffState,
+    links: TreeDiffState,
+}
+
+/// What was last sent to one tree view.
+#[derive(Debug, Default)]
+struct TreeDiffState {
     initialized: bool,
     structure: Vec<TreeStructureItem>,
     text_by_id: HashMap<TreeItemId, String>,
@@ -72,36 +92,13 @@ pub fn render(
     view: &AppViewModel,
     tree_state: &mut TreeRenderState,
 ) -> Vec<PlatformCommand> {
-    let session_label = match view.session {
-        SessionState::Idle => "Idle",
-        SessionState::Running => "Running",
-        SessionState::Finishing => "Finishing",
-        SessionState::Finished => "Finished",
-    };
-
-    let status_text = match &view.last_paste_stats {
-        Some(stats) => format!(
-            "Session: {} | Jobs: {} | Last paste: enqueued {}, skipped {}",
-            session_label, view.job_count, stats.enqueued, stats.skipped
-        ),
-        None => format!("Session: {} | Jobs: {}", session_label, view.job_count),
-    };
+    let status_text = status_text(view);
 
     let raw_limit = view.token_limit;
     let effective_limit = raw_limit.max(1);
     let bar_max = effective_limit.min(u32::MAX as u64);
     let clamped_tokens = view.total_tokens.min(bar_max);
-    let percent = if raw_limit > 0 {
-        (view.total_tokens.min(raw_limit) as f64 / raw_limit as f64) * 100.0
-    } else {
-        0.0
-    };
-    let progress_text = format!(
-        "Tokens: {} / {} ({:.1}%)",
-        format_with_commas(view.total_tokens),
-        format_with_commas(view.token_limit),
-        percent
-    );
+    let progress_text = token_progress_text(view);
 
     let mut cmds = Vec::new();
 
@@ -109,7 +106,11 @@ pub fn render(
         window_id,
         control_id: LABEL_STATUS,
         text: status_text,
-        severity: MessageSeverity::Information,
+        severity: if status_is_warning(view) {
+            MessageSeverity::Warning
+        } else {
+            MessageSeverity::Information
+        },
     });
 
     cmds.push(PlatformCommand::SetProgressBarRange {
@@ -128,21 +129,152 @@ pub fn render(
         control_id: LABEL_TOKEN_PROGRESS,
         text: progress_text,
     });
+    // The amber row style turns the bar amber near and past the limit.
+    cmds.push(PlatformCommand::ApplyStyleToControl {
+        window_id,
+        control_id: PROGRESS_TOKENS,
+        style_id: match view.budget_level {
+            BudgetLevel::Normal => StyleId::ProgressBar,
+            BudgetLevel::Warning | BudgetLevel::Exceeded => StyleId::TreeItemWarning,
+        },
+    });
+
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_BUDGET,
+        text: budget_button_label(view.budget),
+    });
+    for (control_id, column) in BUTTON_JOB_COLUMNS.into_iter().zip(JobColumn::ALL) {
+        cmds.push(PlatformCommand::SetControlText {
+            window_id,
+            control_id,
+            text: column_header(column, view.job_sort),
+        });
+    }
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_LOG_LEVEL,
+        text: log_level_button_label(view.log_level),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_MIN_QUALITY,
+        text: export_min_quality_button_label(view.export_min_quality),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_PROFILE,
+        enabled: next_profile(view).is_some(),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_PROFILE,
+        text: profile_button_label(view),
+    });
 
     cmds.push(PlatformCommand::SetControlEnabled {
         window_id,
         control_id: BUTTON_STOP,
-        enabled: matches!(view.session, SessionState::Running),
+        enabled: stop_button_enabled(view),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_STOP,
+        text: stop_button_label(view).to_string(),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_STOP_NOW,
+        enabled: stop_now_enabled(view),
     });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_STOP_NOW,
+        text: stop_now_button_label(view).to_string(),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_NEW_SESSION,
+        enabled: new_session_enabled(view),
+    });
+    for control_id in [BUTTON_HARVEST_PASTE, BUTTON_DISCARD_PASTE] {
+        cmds.push(PlatformCommand::SetControlEnabled {
+            window_id,
+            control_id,
+            enabled: view.pending_paste.is_some(),
+        });
+    }
 
     cmds.push(PlatformCommand::SetControlEnabled {
         window_id,
         control_id: BUTTON_ARCHIVE,
+        enabled: archive_enabled(view),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_ARCHIVE,
+        text: archive_button_label(view).to_string(),
+    });
+
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_ARCHIVE_NEW,
+        enabled: archive_enabled(view),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_EXPORT_IMAGES,
         enabled: view.job_count > 0,
     });
 
+    let header = view.preview_header.as_ref();
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_WATCH,
+        enabled: header.is_some_and(|h| h.outcome.is_some_and(JobResultKind::is_complete)),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_WATCH,
+        text: watch_button_label(view).to_string(),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_PIN,
+        enabled: header.is_some(),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_PIN,
+        text: pin_button_label(view).to_string(),
+    });
+    for control_id in [BUTTON_COPY_URL, BUTTON_OPEN_URL] {
+        cmds.push(PlatformCommand::SetControlEnabled {
+            window_id,
+            control_id,
+            enabled: header.is_some(),
+        });
+    }
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_DIFF,
+        enabled: header.is_some_and(|h| h.change.is_some()),
+    });
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_DIFF,
+        text: diff_button_label(view).to_string(),
+    });
+
     let job_items = build_job_tree(view);
-    append_tree_commands(window_id, job_items, tree_state, &mut cmds);
+    append_tree_commands(
+        window_id,
+        TREE_JOBS,
+        job_items,
+        &mut tree_state.jobs,
+        &mut cmds,
+    );
+    append_links_pane_commands(window_id, view, &mut tree_state.links, &mut cmds);
 
     let preview_text = view
         .preview_text
@@ -155,6 +287,23 @@ pub fn render(
         text: preview_text,
     });
 
+    cmds.push(PlatformCommand::SetViewerContent {
+        window_id,
+        control_id: VIEWER_ACTIVITY,
+        text: normalize_windows_newlines(&activity_text(view)),
+    });
+
+    let summary_text = view
+        .session_summary
+        .as_ref()
+        .map(format_session_summary)
+        .unwrap_or_default();
+    cmds.push(PlatformCommand::SetViewerContent {
+        window_id,
+        control_id: VIEWER_SUMMARY,
+        text: normalize_windows_newlines(&summary_text),
+    });
+
     let header_text = view
         .preview_header
         .as_ref()
@@ -169,17 +318,106 @@ pub fn render(
     cmds
 }
 
+fn watch_button_label(view: &AppViewModel) -> &'static str {
+    match &view.preview_header {
+        Some(header) if header.watched => "Unwatch",
+        _ => "Watch",
+    }
+}
+
+fn pin_button_label(view: &AppViewModel) -> &'static str {
+    match &view.preview_header {
+        Some(header) if header.pinned => "Unpin",
+        _ => "Pin",
+    }
+}
+
+/// The link a links-pane tree item stands for, if it is one.
+pub fn link_for_item(view: &AppViewModel, item_id: TreeItemId) -> Option<&JobLink> {
+    let index = item_id.0.checked_sub(LINK_ITEM_ID_BASE)?;
+    view.links_pane.as_ref()?.links.get(index as usize)
+}
+
+pub fn is_link_item(item_id: TreeItemId) -> bool {
+    item_id.0 >= LINK_ITEM_ID_BASE
+}
+
+fn append_links_pane_commands(
+    window_id: WindowId,
+    view: &AppViewModel,
+    tree_state: &mut TreeDiffState,
+    cmds: &mut Vec<PlatformCommand>,
+) {
+    let pane = view.links_pane.as_ref();
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: LABEL_LINKS_HEADER,
+        text: pane.map_or_else(
+            || "Links (select a harvested page)".to_string(),
+            |pane| format!("Links of #{}", pane.job_id),
+        ),
+    });
+    for (index, control_id) in BUTTON_LINK_KINDS.into_iter().enumerate() {
+        let filter = pane.and_then(|pane| pane.filters.get(index));
+        let kind = LinkKind::ALL[index];
+        cmds.push(PlatformCommand::SetControlText {
+            window_id,
+            control_id,
+            text: filter.map_or_else(
+                || toggle_button_label(kind.label(), true),
+                |filter| toggle_button_label(&link_filter_label(filter), filter.shown),
+            ),
+        });
+        cmds.push(PlatformCommand::SetControlEnabled {
+            window_id,
+            control_id,
+            enabled: filter.is_some(),
+        });
+    }
+    for (index, control_id) in BUTTON_LINK_SCOPES.into_iter().enumerate() {
+        let filter = pane.and_then(|pane| pane.scopes.get(index));
+        let scope = LinkScope::ALL[index];
+        cmds.push(PlatformCommand::SetControlText {
+            window_id,
+            control_id,
+            text: filter.map_or_else(
+                || toggle_button_label(scope.label(), true),
+                |filter| toggle_button_label(&link_scope_label(filter), filter.shown),
+            ),
+        });
+        cmds.push(PlatformCommand::SetControlEnabled {
+            window_id,
+            control_id,
+            enabled: filter.is_some(),
+        });
+    }
+    cmds.push(PlatformCommand::SetControlText {
+        window_id,
+        control_id: BUTTON_HARVEST_LINKS,
+        text: pane.map_or_else(|| "Harvest selected".to_string(), harvest_links_label),
+    });
+    cmds.push(PlatformCommand::SetControlEnabled {
+        window_id,
+        control_id: BUTTON_HARVEST_LINKS,
+        enabled: pane.is_some_and(|pane| !pane.checked.is_empty()),
+    });
+
+    let items = pane.map(build_link_tree).unwrap_or_default();
+    append_tree_commands(window_id, TREE_LINKS, items, tree_state, cmds);
+}
+
 fn append_tree_commands(
     window_id: WindowId,
+    control_id: ControlId,
     items: Vec<TreeItemDescriptor>,
-    tree_state: &mut TreeRenderState,
+    tree_state: &mut TreeDiffState,
     cmds: &mut Vec<PlatformCommand>,
 ) {
     let snapshot = TreeSnapshot::from_items(&items);
     if !tree_state.initialized || tree_state.structure != snapshot.structure {
         cmds.push(PlatformCommand::PopulateTreeView {
             window_id,
-            control_id: TREE_JOBS,
+            control_id,
             items,
         });
         tree_state.initialized = true;
@@ -194,7 +432,7 @@ fn append_tree_commands(
             if tree_state.text_by_id.get(&item.id) != Some(new_text) {
                 cmds.push(PlatformCommand::UpdateTreeItemText {
                     window_id,
-                    control_id: TREE_JOBS,
+                    control_id,
                     item_id: item.id,
                     text: new_text.clone(),
                 });
@@ -205,7 +443,7 @@ fn append_tree_commands(
             if tree_state.check_state_by_id.get(&item.id) != Some(new_state) {
                 cmds.push(PlatformCommand::UpdateTreeItemVisualState {
                     window_id,
-                    control_id: TREE_JOBS,
+                    control_id,
                     item_id: item.id,
                     new_state: *new_state,
                 });
@@ -227,88 +465,37 @@ fn build_job_tree(view: &AppViewModel) -> Vec<TreeItemDescriptor> {
             is_folder: false,
             state: commanductui::types::CheckState::Unchecked,
             children: Vec::new(),
-            style_override: None,
+            style_override: job_row_style(job),
         })
         .collect()
 }
 
-fn format_job_row(job: &JobRowView) -> String {
-    let status = match job.outcome {
-        Some(JobResultKind::Success) => "OK",
-        Some(JobResultKind::Failed) => "ERR",
-        None => stage_label(job.stage),
-    };
-    let tokens = job.tokens.map(|t| format!("{t} tok"));
-    let bytes = job.bytes.map(|b| format!("{b} B"));
-    let metrics = match (tokens, bytes) {
-        (Some(t), Some(b)) => format!("{t}, {b}"),
-        (Some(t), None) => t,
-        (None, Some(b)) => b,
-        _ => String::new(),
-    };
-    if metrics.is_empty() {
-        format!(
-            "[#{id}] {status} — {url}",
-            id = job.job_id,
-            status = status,
-            url = job.url
-        )
-    } else {
-        format!(
-            "[#{id}] {status} — {url} ({metrics})",
-            id = job.job_id,
-            status = status,
-            url = job.url,
-            metrics = metrics
-        )
-    }
-}
-
-fn stage_label(stage: Stage) -> &'static str {
-    match stage {
-        Stage::Queued => "Queued",
-        Stage::Downloading => "Downloading",
-        Stage::Sanitizing => "Sanitizing",
-        Stage::Converting => "Converting",
-        Stage::Tokenizing => "Tokenizing",
-        Stage::Writing => "Writing",
-        Stage::Done => "Done",
-    }
-}
-
-fn format_with_commas(value: u64) -> String {
-    let mut out = String::new();
-    for (i, ch) in value.to_string().chars().rev().enumerate() {
-        if i != 0 && i % 3 == 0 {
-            out.push(',');
-        }
-        out.push(ch);
-    }
-    out.chars().rev().collect()
+fn build_link_tree(pane: &LinksPaneView) -> Vec<TreeItemDescriptor> {
+    pane.links
+        .iter()
+        .enumerate()
+        .map(|(index, link)| TreeItemDescriptor {
+            id: TreeItemId(LINK_ITEM_ID_BASE + index as u64),
+            text: link_row_text(link),
+            is_folder: false,
+            state: if pane.checked.contains(&link.url) {
+                CheckState::Checked
+            } else {
+                CheckState::Unchecked
+            },
+            children: Vec::new(),
+            style_override: None,
+        })
+        .collect()
 }
 
-fn format_preview_header(header: &PreviewHeaderView) -> String {
-    let mut parts = Vec::new();
-    if !header.domain.is_empty() {
-        parts.push(header.domain.clone());
-    }
-    if let Some(tokens) = header.tokens {
-        parts.push(format!("{} tokens", format_with_commas(tokens as u64)));
+fn job_row_style(job: &JobRowView) -> Option<StyleId> {
+    match job_row_tone(job) {
+        RowTone::Normal | RowTone::Cancelled => None,
+        RowTone::InProgress => Some(StyleId::TreeItemInProgress),
+        RowTone::Warning => Some(StyleId::TreeItemWarning),
+        RowTone::Failed => Some(StyleId::TreeItemFailed),
     }
-    if let Some(bytes) = header.bytes {
-        parts.push(format!("{bytes} B"));
-    }
-    parts.push(format!("{count} headings", count = header.heading_count));
-    let stage_desc = match header.outcome {
-        Some(JobResultKind::Failed) => "Failed".to_string(),
-        Some(JobResultKind::Success) => "Done".to_string(),
-        None => stage_label(header.stage).to_string(),
-    };
-    parts.push(stage_desc);
-    if header.nav_heavy {
-        parts.push("[nav-heavy]".to_string());
-    }
-    parts.join(" | ")
 }
 
 fn normalize_windows_newlines(text: &str) -> String {
@@ -355,6 +542,19 @@ mod tests {
             outcome,
             tokens,
             bytes,
+            watched: false,
+            changed_from: None,
+            duplicate_of: None,
+            change: None,
+            retry_in_secs: None,
+            quality_score: None,
+            low_quality: false,
+            nav_heavy: false,
+            word_count: None,
+            reading_minutes: None,
+            elapsed_ms: None,
+            pinned: false,
+            failure: None,
         }
     }
 
@@ -366,44 +566,6 @@ mod tests {
         }
     }
 
-    #[test]
-    fn preview_header_includes_headings_and_tokens() {
-        init_logging();
-        let header = PreviewHeaderView {
-            domain: "example.com".to_string(),
-            tokens: Some(1234),
-            bytes: Some(2048),
-            stage: Stage::Done,
-            outcome: Some(JobResultKind::Success),
-            heading_count: 8,
-            link_density: 0.0,
-            nav_heavy: false,
-        };
-        assert_eq!(
-            format_preview_header(&header),
-            "example.com | 1,234 tokens | 2048 B | 8 headings | Done"
-        );
-    }
-
-    #[test]
-    fn preview_header_appends_nav_heavy_indicator() {
-        init_logging();
-        let header = PreviewHeaderView {
-            domain: "dense.example".to_string(),
-            tokens: None,
-            bytes: None,
-            stage: Stage::Converting,
-            outcome: None,
-            heading_count: 0,
-            link_density: 1.0,
-            nav_heavy: true,
-        };
-        assert_eq!(
-            format_preview_header(&header),
-            "dense.example | 0 headings | Converting | [nav-heavy]"
-        );
-    }
-
     #[test]
     fn tree_updates_text_without_repopulate_on_progress_change() {
         init_logging();
@@ -510,4 +672,79 @@ mod tests {
             .expect("SetViewerContent emitted");
         assert_eq!(viewer_text, "first\r\nsecond\r\nthird\r\nfourth");
     }
+    #[test]
+    fn column_header_buttons_mark_the_sorted_column() {
+        init_logging();
+        let window_id = WindowId::new(5);
+        let mut tree_state = TreeRenderState::new();
+        let view = AppViewModel {
+            job_sort: Some(harvester_core::JobSort {
+                column: JobColumn::Tokens,
+                descending: true,
+            }),
+            ..Default::default()
+        };
+
+        let commands = render(window_id, &view, &mut tree_state);
+        let header_text = |id| {
+            commands.iter().find_map(|cmd| match cmd {
+                PlatformCommand::SetControlText {
+                    control_id, text, ..
+                } if *control_id == id => Some(text.as_str()),
+                _ => None,
+            })
+        };
+        assert_eq!(header_text(BUTTON_JOB_COLUMNS[2]), Some("Tokens ▼"));
+        assert_eq!(header_text(BUTTON_JOB_COLUMNS[0]), Some("URL"));
+    }
+
+    #[test]
+    fn link_checkbox_updates_visual_state_and_maps_back_to_its_link() {
+        init_logging();
+        let window_id = WindowId::new(4);
+        let mut tree_state = TreeRenderState::new();
+        let link = |url: &str| JobLink {
+            url: url.to_string(),
+            text: None,
+            kind: LinkKind::Hyperlink,
+            internal: true,
+        };
+        let mut view = AppViewModel {
+            links_pane: Some(LinksPaneView {
+                job_id: 1,
+                filters: Vec::new(),
+                scopes: Vec::new(),
+                links: vec![link("https://a.example"), link("https://b.example")],
+                checked: Vec::new(),
+            }),
+            ..Default::default()
+        };
+        let _ = render(window_id, &view, &mut tree_state);
+
+        let pane = view.links_pane.as_mut().expect("pane");
+        pane.checked.push("https://b.example".to_string());
+        let commands = render(window_id, &view, &mut tree_state);
+        let toggled = commands
+            .iter()
+            .filter_map(|cmd| match cmd {
+                PlatformCommand::UpdateTreeItemVisualState {
+                    control_id,
+                    item_id,
+                    new_state,
+                    ..
+                } => Some((*control_id, *item_id, *new_state)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(toggled.len(), 1);
+        let (control_id, item_id, new_state) = toggled[0];
+        assert_eq!(control_id, TREE_LINKS);
+        assert_eq!(new_state, CheckState::Checked);
+        assert!(is_link_item(item_id));
+        assert_eq!(
+            link_for_item(&view, item_id).map(|link| link.url.as_str()),
+            Some("https://b.example")
+        );
+        assert!(link_for_item(&view, TreeItemId(1)).is_none());
+    }
 }
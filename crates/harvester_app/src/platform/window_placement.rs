@@ -0,0 +1,101 @@
+//! Position and maximized state of the native main window. `WindowConfig`
+//! only sizes the window, so the rest of the saved geometry is applied and
+//! read back through the Win32 window placement.
+use std::mem;
+
+use harvester_core::WindowGeometry;
+use windows_sys::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+use windows_sys::Win32::System::Threading::GetCurrentProcessId;
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowPlacement, GetWindowTextW, GetWindowThreadProcessId, SetWindowPlacement,
+    SW_SHOWMAXIMIZED, SW_SHOWNORMAL, WINDOWPLACEMENT,
+};
+
+/// A top-level window of this process. Kept as an integer so the event
+/// handler holding it stays `Send`.
+pub(crate) struct MainWindow(isize);
+
+impl MainWindow {
+    /// This process's top-level window titled `title`.
+    pub(crate) fn find(title: &str) -> Option<Self> {
+        struct Search {
+            title: Vec<u16>,
+            found: HWND,
+        }
+
+        unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+            let search = &mut *(lparam as *mut Search);
+            let mut process_id = 0;
+            GetWindowThreadProcessId(hwnd, &mut process_id);
+            if process_id != GetCurrentProcessId() {
+                return 1;
+            }
+            let mut text = [0u16; 64];
+            let len = GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32);
+            if len > 0 && text[..len as usize] == search.title[..] {
+                search.found = hwnd;
+                return 0;
+            }
+            1
+        }
+
+        let mut search = Search {
+            title: title.encode_utf16().collect(),
+            found: std::ptr::null_mut(),
+        };
+        // SAFETY: `search` outlives the enumeration, which calls `visit`
+        // synchronously.
+        unsafe { EnumWindows(Some(visit), &mut search as *mut Search as LPARAM) };
+        (!search.found.is_null()).then_some(Self(search.found as isize))
+    }
+
+    /// Move the window to the saved position, and maximize it if it was.
+    /// The saved size becomes the restored-down size.
+    pub(crate) fn restore(&self, geometry: WindowGeometry) {
+        let Some(mut placement) = self.placement() else {
+            return;
+        };
+        if let Some((x, y)) = geometry.position {
+            placement.rcNormalPosition = RECT {
+                left: x,
+                top: y,
+                right: x + geometry.width,
+                bottom: y + geometry.height,
+            };
+        }
+        placement.showCmd = if geometry.maximized {
+            SW_SHOWMAXIMIZED as u32
+        } else {
+            SW_SHOWNORMAL as u32
+        };
+        // SAFETY: the handle came from `EnumWindows` and `placement` is
+        // fully initialized.
+        unsafe { SetWindowPlacement(self.hwnd(), &placement) };
+    }
+
+    /// The restored-down size and position, and whether the window is
+    /// maximized.
+    pub(crate) fn geometry(&self) -> Option<WindowGeometry> {
+        let placement = self.placement()?;
+        let rect = placement.rcNormalPosition;
+        Some(WindowGeometry {
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+            position: Some((rect.left, rect.top)),
+            maximized: placement.showCmd == SW_SHOWMAXIMIZED as u32,
+        })
+    }
+
+    fn placement(&self) -> Option<WINDOWPLACEMENT> {
+        // SAFETY: WINDOWPLACEMENT is plain data; all zeroes is a valid value.
+        let mut placement: WINDOWPLACEMENT = unsafe { mem::zeroed() };
+        placement.length = mem::size_of::<WINDOWPLACEMENT>() as u32;
+        // SAFETY: `length` is set as GetWindowPlacement requires.
+        let ok = unsafe { GetWindowPlacement(self.hwnd(), &mut placement) };
+        (ok != 0).then_some(placement)
+    }
+
+    fn hwnd(&self) -> HWND {
+        self.0 as HWND
+    }
+}
@@ -0,0 +1,11 @@
+//! Address of a running instance's local API, shared by the server and by
+//! helper modes that forward work to it.
+
+/// Loopback only; the API has no authentication.
+pub(crate) const DEFAULT_API_ADDR: &str = "127.0.0.1:17878";
+const API_ADDR_ENV: &str = "HARVESTER_API_ADDR";
+
+/// `HARVESTER_API_ADDR`, defaulting to [`DEFAULT_API_ADDR`].
+pub(crate) fn api_addr() -> String {
+    std::env::var(API_ADDR_ENV).unwrap_or_else(|_| DEFAULT_API_ADDR.to_string())
+}
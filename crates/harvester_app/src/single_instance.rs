@@ -0,0 +1,150 @@
+//! One running UI per user session.
+//!
+//! The first instance binds a loopback socket, which serves both as the
+//! instance lock and as the pipe later launches write to: a second launch
+//! finds the address taken, sends its command-line URLs there one per line,
+//! and exits instead of opening another window on its own output directory.
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use engine_logging::{engine_info, engine_warn};
+use harvester_core::Msg;
+
+const DEFAULT_INSTANCE_ADDR: &str = "127.0.0.1:17879";
+const INSTANCE_ADDR_ENV: &str = "HARVESTER_INSTANCE_ADDR";
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of claiming the instance lock.
+pub(crate) enum Instance {
+    /// This is the only instance; URLs from later launches arrive through the lock.
+    Primary(InstanceLock),
+    /// Another instance is running and has been handed the URLs.
+    Forwarded,
+    /// The lock could not be claimed or reached; run without it.
+    Unlocked,
+}
+
+/// Held by the primary instance for as long as it runs.
+pub(crate) struct InstanceLock {
+    listener: TcpListener,
+}
+
+impl InstanceLock {
+    /// Deliver URLs forwarded by later launches to `msg_tx` as [`Msg::UrlsReceived`].
+    pub(crate) fn forward_to(self, msg_tx: mpsc::Sender<Msg>) {
+        thread::spawn(move || {
+            for stream in self.listener.incoming() {
+                let urls = match stream.and_then(read_urls) {
+                    Ok(urls) => urls,
+                    Err(err) => {
+                        engine_warn!("[Instance] Failed to read forwarded URLs: {}", err);
+                        continue;
+                    }
+                };
+                engine_info!("[Instance] Received {} URLs from a new launch", urls.len());
+                if !urls.is_empty() && msg_tx.send(Msg::UrlsReceived(urls)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Claim the instance lock, or forward `urls` to the instance holding it.
+pub(crate) fn claim(urls: &[String]) -> Instance {
+    claim_at(&instance_addr(), urls)
+}
+
+fn claim_at(addr: &str, urls: &[String]) -> Instance {
+    match TcpListener::bind(addr) {
+        Ok(listener) => return Instance::Primary(InstanceLock { listener }),
+        Err(err) if err.kind() != io::ErrorKind::AddrInUse => {
+            engine_warn!("[Instance] Cannot claim {}: {}", addr, err);
+            return Instance::Unlocked;
+        }
+        Err(_) => {}
+    }
+    match send_urls(addr, urls) {
+        Ok(()) => {
+            engine_info!(
+                "[Instance] Forwarded {} URLs to the running instance",
+                urls.len()
+            );
+            Instance::Forwarded
+        }
+        Err(err) => {
+            engine_warn!("[Instance] {} is taken but not answering: {}", addr, err);
+            Instance::Unlocked
+        }
+    }
+}
+
+fn send_urls(addr: &str, urls: &[String]) -> io::Result<()> {
+    let addr = addr
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stream = TcpStream::connect_timeout(&addr, FORWARD_TIMEOUT)?;
+    stream.set_write_timeout(Some(FORWARD_TIMEOUT))?;
+    for url in urls {
+        writeln!(stream, "{url}")?;
+    }
+    stream.flush()
+}
+
+fn read_urls(stream: TcpStream) -> io::Result<Vec<String>> {
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    BufReader::new(stream)
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .collect()
+}
+
+/// `HARVESTER_INSTANCE_ADDR`, defaulting to [`DEFAULT_INSTANCE_ADDR`].
+fn instance_addr() -> String {
+    std::env::var(INSTANCE_ADDR_ENV).unwrap_or_else(|_| DEFAULT_INSTANCE_ADDR.to_string())
+}
+
+/// Positional command-line arguments: URLs or saved-page paths to harvest.
+pub(crate) fn launch_urls<I, S>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter()
+        .map(|arg| arg.as_ref().to_string())
+        .filter(|arg| !arg.starts_with("--"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_launch_forwards_its_urls_to_the_first() {
+        let addr = {
+            let probe = TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().to_string()
+        };
+        let Instance::Primary(lock) = claim_at(&addr, &[]) else {
+            panic!("first launch should hold the lock");
+        };
+        let (msg_tx, msg_rx) = mpsc::channel();
+        lock.forward_to(msg_tx);
+
+        let urls = launch_urls(["--verbose", "https://a.example", "https://b.example"]);
+        assert!(matches!(claim_at(&addr, &urls), Instance::Forwarded));
+
+        let msg = msg_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            msg,
+            Msg::UrlsReceived(vec![
+                "https://a.example".to_string(),
+                "https://b.example".to_string()
+            ])
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Token budgets named after the context windows of common models.
+
+/// The session's token budget: the progress bar's limit and the largest
+/// chunk a chunked export may write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BudgetPreset {
+    /// GPT-4o, 128k tokens.
+    Gpt4o,
+    /// Claude, 200k tokens.
+    #[default]
+    Claude,
+    /// Gemini, 1M tokens.
+    Gemini,
+    /// A budget of the user's choosing.
+    Custom(u64),
+}
+
+/// Share of the token limit, in percent, at which the budget warns.
+pub const BUDGET_WARNING_PERCENT: u64 = 80;
+
+/// How much of the token budget the session has used. Ordered by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BudgetLevel {
+    #[default]
+    Normal,
+    /// At least [`BUDGET_WARNING_PERCENT`] of the limit is used.
+    Warning,
+    /// The limit is reached; a running harvest is stopped.
+    Exceeded,
+}
+
+impl BudgetPreset {
+    /// The presets with a fixed budget, in the order settings list them.
+    pub const NAMED: [BudgetPreset; 3] = [
+        BudgetPreset::Gpt4o,
+        BudgetPreset::Claude,
+        BudgetPreset::Gemini,
+    ];
+
+    pub fn token_limit(self) -> u64 {
+        match self {
+            BudgetPreset::Gpt4o => 128_000,
+            BudgetPreset::Claude => 200_000,
+            BudgetPreset::Gemini => 1_000_000,
+            BudgetPreset::Custom(tokens) => tokens,
+        }
+    }
+
+    /// The level `tokens` reach against this budget.
+    pub fn level(self, tokens: u64) -> BudgetLevel {
+        let limit = self.token_limit();
+        if tokens >= limit {
+            BudgetLevel::Exceeded
+        } else if tokens.saturating_mul(100) >= limit.saturating_mul(BUDGET_WARNING_PERCENT) {
+            BudgetLevel::Warning
+        } else {
+            BudgetLevel::Normal
+        }
+    }
+
+    /// Name shown in settings.
+    pub fn label(self) -> &'static str {
+        match self {
+            BudgetPreset::Gpt4o => "GPT-4o (128k)",
+            BudgetPreset::Claude => "Claude (200k)",
+            BudgetPreset::Gemini => "Gemini (1M)",
+            BudgetPreset::Custom(_) => "Custom",
+        }
+    }
+
+    /// Stable id for settings files and environment variables:
+    /// `gpt-4o`, `claude`, `gemini` or the custom token count.
+    pub fn id(self) -> String {
+        match self {
+            BudgetPreset::Gpt4o => "gpt-4o".to_string(),
+            BudgetPreset::Claude => "claude".to_string(),
+            BudgetPreset::Gemini => "gemini".to_string(),
+            BudgetPreset::Custom(tokens) => tokens.to_string(),
+        }
+    }
+
+    /// Inverse of [`id`](Self::id); `None` for unknown names and a zero budget.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let id = id.trim();
+        if let Some(preset) = Self::NAMED
+            .into_iter()
+            .find(|preset| preset.id().eq_ignore_ascii_case(id))
+        {
+            return Some(preset);
+        }
+        id.parse::<u64>()
+            .ok()
+            .filter(|&tokens| tokens > 0)
+            .map(BudgetPreset::Custom)
+    }
+}
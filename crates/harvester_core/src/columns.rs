@@ -0,0 +1,66 @@
+//! Columns of the jobs list and the order they sort rows in.
+
+/// A column of the jobs list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum JobColumn {
+    Url,
+    /// Pipeline stage, or the outcome once the job is done.
+    Stage,
+    Tokens,
+    Bytes,
+    /// Wall-clock time since the job started.
+    Time,
+    /// Failure kind named by the engine.
+    Error,
+}
+
+impl JobColumn {
+    /// All columns, in display order.
+    pub const ALL: [JobColumn; 6] = [
+        JobColumn::Url,
+        JobColumn::Stage,
+        JobColumn::Tokens,
+        JobColumn::Bytes,
+        JobColumn::Time,
+        JobColumn::Error,
+    ];
+
+    /// Column header text.
+    pub fn label(self) -> &'static str {
+        match self {
+            JobColumn::Url => "URL",
+            JobColumn::Stage => "Stage",
+            JobColumn::Tokens => "Tokens",
+            JobColumn::Bytes => "Bytes",
+            JobColumn::Time => "Time",
+            JobColumn::Error => "Error",
+        }
+    }
+}
+
+/// How the jobs list is sorted. Pinned jobs stay on top either way; ties
+/// keep enqueue order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JobSort {
+    pub column: JobColumn,
+    pub descending: bool,
+}
+
+impl JobSort {
+    /// The sort after the user clicks `column`'s header: a new column sorts
+    /// ascending, clicking the current one flips the direction.
+    pub fn clicked(current: Option<JobSort>, column: JobColumn) -> JobSort {
+        match current {
+            Some(sort) if sort.column == column => JobSort {
+                column,
+                descending: !sort.descending,
+            },
+            _ => JobSort {
+                column,
+                descending: false,
+            },
+        }
+    }
+}
@@ -1,13 +1,123 @@
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Effect {
-    EnqueueUrl { job_id: crate::JobId, url: String },
+    EnqueueUrl {
+        job_id: crate::JobId,
+        url: String,
+        /// Label of the paste or batch the URL came from.
+        source_batch: Option<String>,
+    },
     StartSession,
-    StopFinish { policy: StopPolicy },
-    ArchiveRequested,
+    StopFinish {
+        policy: StopPolicy,
+    },
+    /// Export everything; documents scoring below `min_quality` are left
+    /// out, and `None` keeps the output profile's threshold.
+    ArchiveRequested {
+        min_quality: Option<u8>,
+    },
+    /// Re-fetch the job's URL on a schedule, reporting content changes.
+    WatchUrl {
+        job_id: crate::JobId,
+        url: String,
+        interval_secs: u64,
+    },
+    UnwatchUrl {
+        job_id: crate::JobId,
+    },
+    /// Export only documents fetched after `since_utc` (all when `None`).
+    ArchiveNewRequested {
+        since_utc: Option<String>,
+        min_quality: Option<u8>,
+    },
+    /// Keep the completed jobs of a session that is being cleared, and export
+    /// what was fetched after `since_utc` when auto-export is on.
+    SaveSessionSnapshot {
+        completed: Vec<crate::CompletedJobSnapshot>,
+        since_utc: Option<String>,
+        min_quality: Option<u8>,
+    },
+    /// Rewrite the session state file; emitted whenever what it holds changes
+    /// and before quitting.
+    PersistState {
+        session: crate::PersistedSession,
+    },
+    /// The set of pinned URLs changed; fitted exports always include them.
+    PinnedUrlsChanged {
+        urls: Vec<String>,
+    },
+    /// The active budget changed; chunked exports keep chunks within `tokens`.
+    TokenBudgetChanged {
+        tokens: u64,
+    },
+    /// Log at `level` from now on.
+    SetLogLevel(crate::LogLevel),
+    /// Put `text` on the system clipboard.
+    CopyToClipboard {
+        text: String,
+    },
+    /// Open `url` in the default browser.
+    OpenInBrowser {
+        url: String,
+    },
+    /// Write the session's image links, with the pages they were found on.
+    ExportImageInventory {
+        images: Vec<ImageRef>,
+    },
+    /// Write to the output profile `name` from now on. The platform rebuilds
+    /// the engine for it and reports back with `Msg::ProfileActivated`.
+    SwitchProfile {
+        name: String,
+    },
+    /// Shutdown is complete: save the session and close the window.
+    Quit,
+}
+
+/// An image link of a harvested page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageRef {
+    pub image_url: String,
+    pub page_url: String,
+    pub alt_text: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StopPolicy {
     Finish,
     Immediate,
 }
+
+/// Sits between [`update`](crate::update) and whatever executes effects, to
+/// observe, rewrite or swallow them: test doubles, dry runs, instrumentation.
+pub trait EffectInterceptor: Send {
+    /// The effects to run in place of `effect`: `vec![effect]` passes it on
+    /// unchanged and an empty vector swallows it.
+    fn intercept(&mut self, effect: Effect) -> Vec<Effect>;
+}
+
+impl<F> EffectInterceptor for F
+where
+    F: FnMut(Effect) -> Vec<Effect> + Send,
+{
+    fn intercept(&mut self, effect: Effect) -> Vec<Effect> {
+        self(effect)
+    }
+}
+
+/// Pass `effects` through `interceptors` in order; each one sees only what
+/// the ones before it let through.
+pub fn intercept_effects(
+    interceptors: &mut [Box<dyn EffectInterceptor>],
+    effects: Vec<Effect>,
+) -> Vec<Effect> {
+    interceptors
+        .iter_mut()
+        .fold(effects, |effects, interceptor| {
+            effects
+                .into_iter()
+                .flat_map(|effect| interceptor.intercept(effect))
+                .collect()
+        })
+}
@@ -0,0 +1,58 @@
+//! Session journals for reproducing state bugs: an app appends every [`Msg`]
+//! it dispatches, one JSON object per line, and [`replay`] feeds them back
+//! through [`update`] to rebuild the [`AppState`] the user saw.
+use crate::{update, AppState, Effect, Msg};
+
+/// Apply `msgs` to `state` in order, returning the final state and every
+/// effect `update` asked for along the way.
+///
+/// `update` is pure, so replaying a journal from the state the app started
+/// with (including its allowed schemes) reproduces the recorded session.
+pub fn replay(state: AppState, msgs: impl IntoIterator<Item = Msg>) -> (AppState, Vec<Effect>) {
+    msgs.into_iter()
+        .fold((state, Vec::new()), |(state, mut effects), msg| {
+            let (next, mut out) = update(state, msg);
+            effects.append(&mut out);
+            (next, effects)
+        })
+}
+
+/// One journal line for `msg`, without the trailing newline.
+#[cfg(feature = "journal")]
+pub fn journal_line(msg: &Msg) -> String {
+    serde_json::to_string(msg).expect("messages serialize to JSON")
+}
+
+/// Messages in a journal; blank lines are skipped.
+#[cfg(feature = "journal")]
+pub fn read_journal(text: &str) -> Result<Vec<Msg>, JournalError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|err| JournalError {
+                line: index + 1,
+                message: err.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A journal line that is not a recorded message.
+#[cfg(feature = "journal")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalError {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "journal")]
+impl std::fmt::Display for JournalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "journal line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "journal")]
+impl std::error::Error for JournalError {}
@@ -0,0 +1,187 @@
+//! Main window geometry and the widths of the resizable panels left of the
+//! preview.
+
+/// Narrowest the input and jobs panels may get.
+pub const MIN_PANEL_WIDTH: i32 = 120;
+pub const MIN_PREVIEW_WIDTH: i32 = 200;
+/// One splitter bar plus the panel margins around it.
+pub const SPLITTER_TOTAL_WIDTH: i32 = 16;
+
+/// The draggable bars between the side-by-side panels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Splitter {
+    /// Between the URL input and the jobs list.
+    InputJobs,
+    /// Between the jobs list and the preview.
+    JobsPreview,
+}
+
+/// Widths of the input and jobs panels; the preview takes the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PanelWidths {
+    pub input: i32,
+    pub jobs: i32,
+}
+
+impl Default for PanelWidths {
+    fn default() -> Self {
+        Self {
+            input: 320,
+            jobs: 280,
+        }
+    }
+}
+
+impl PanelWidths {
+    /// Widths after `splitter` was dragged so that the panels left of it span
+    /// `desired_left` pixels. The input splitter trades width with the jobs
+    /// panel; the preview splitter grows or shrinks the jobs panel.
+    pub fn dragged(self, splitter: Splitter, desired_left: i32, window_width: i32) -> Self {
+        match splitter {
+            Splitter::InputJobs => {
+                let total = self.input + self.jobs;
+                let input = desired_left
+                    .min(total - MIN_PANEL_WIDTH)
+                    .max(MIN_PANEL_WIDTH);
+                Self {
+                    input,
+                    jobs: (total - input).max(MIN_PANEL_WIDTH),
+                }
+            }
+            Splitter::JobsPreview => {
+                let left = calc_left_width(
+                    desired_left,
+                    window_width,
+                    self.input + MIN_PANEL_WIDTH,
+                    MIN_PREVIEW_WIDTH,
+                    2 * SPLITTER_TOTAL_WIDTH,
+                );
+                Self {
+                    input: self.input,
+                    jobs: left - self.input,
+                }
+            }
+        }
+    }
+
+    /// Shrinks the jobs panel, then the input panel, until the preview keeps
+    /// its minimum width in a window `window_width` wide. A window width of
+    /// zero (not reported yet) leaves the widths alone.
+    pub fn fitted(self, window_width: i32) -> Self {
+        if window_width <= 0 {
+            return self;
+        }
+        let left = calc_left_width(
+            self.input + self.jobs,
+            window_width,
+            2 * MIN_PANEL_WIDTH,
+            MIN_PREVIEW_WIDTH,
+            2 * SPLITTER_TOTAL_WIDTH,
+        );
+        let jobs = (left - self.input).max(MIN_PANEL_WIDTH).min(self.jobs);
+        Self {
+            input: left - jobs,
+            jobs,
+        }
+    }
+}
+
+/// Size, position and maximized state of the main window, restored on the
+/// next start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowGeometry {
+    pub width: i32,
+    pub height: i32,
+    /// Top-left corner on screen; `None` lets the platform place the window.
+    pub position: Option<(i32, i32)>,
+    /// `width`, `height` and `position` are then the restored-down geometry.
+    pub maximized: bool,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: 960,
+            height: 720,
+            position: None,
+            maximized: false,
+        }
+    }
+}
+
+/// `desired_left` limited to `min_left`, and to what leaves `min_preview`
+/// for the preview. `min_left` wins in windows too narrow for both.
+pub fn calc_left_width(
+    desired_left: i32,
+    window_width: i32,
+    min_left: i32,
+    min_preview: i32,
+    splitter_total: i32,
+) -> i32 {
+    let max_left = window_width - min_preview - splitter_total;
+    desired_left.min(max_left).max(min_left)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calc_left_width_respects_both_minimums() {
+        assert_eq!(calc_left_width(500, 1000, 200, 200, 16), 500);
+        assert_eq!(calc_left_width(900, 1000, 200, 200, 16), 784);
+        assert_eq!(calc_left_width(50, 1000, 200, 200, 16), 200);
+        assert_eq!(calc_left_width(500, 300, 200, 200, 16), 200);
+    }
+
+    #[test]
+    fn input_splitter_trades_width_with_the_jobs_panel() {
+        let widths = PanelWidths::default();
+        assert_eq!(
+            widths.dragged(Splitter::InputJobs, 400, 1200),
+            PanelWidths {
+                input: 400,
+                jobs: 200
+            }
+        );
+        assert_eq!(
+            widths.dragged(Splitter::InputJobs, 590, 1200),
+            PanelWidths {
+                input: 480,
+                jobs: 120
+            }
+        );
+    }
+
+    #[test]
+    fn preview_splitter_keeps_the_preview_minimum() {
+        let widths = PanelWidths::default();
+        assert_eq!(widths.dragged(Splitter::JobsPreview, 700, 1200).jobs, 380);
+        assert_eq!(widths.dragged(Splitter::JobsPreview, 1100, 1200).jobs, 648);
+        assert_eq!(widths.dragged(Splitter::JobsPreview, 100, 1200).jobs, 120);
+    }
+
+    #[test]
+    fn fitted_shrinks_jobs_before_input() {
+        let widths = PanelWidths::default();
+        assert_eq!(widths.fitted(0), widths);
+        assert_eq!(widths.fitted(1200), widths);
+        assert_eq!(
+            widths.fitted(700),
+            PanelWidths {
+                input: 320,
+                jobs: 148
+            }
+        );
+        assert_eq!(
+            widths.fitted(500),
+            PanelWidths {
+                input: 148,
+                jobs: 120
+            }
+        );
+    }
+}
@@ -1,15 +1,39 @@
 //! Harvester core: pure state machine and view-model helpers.
+mod budget;
+mod columns;
 mod effect;
+mod journal;
+mod layout;
+mod log_level;
 mod msg;
 mod state;
 mod update;
 mod view_model;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-pub use effect::{Effect, StopPolicy};
+pub use budget::{BudgetLevel, BudgetPreset, BUDGET_WARNING_PERCENT};
+pub use columns::{JobColumn, JobSort};
+pub use effect::{intercept_effects, Effect, EffectInterceptor, ImageRef, StopPolicy};
+pub use journal::replay;
+#[cfg(feature = "journal")]
+pub use journal::{journal_line, read_journal, JournalError};
+pub use layout::{
+    calc_left_width, PanelWidths, Splitter, WindowGeometry, MIN_PANEL_WIDTH, MIN_PREVIEW_WIDTH,
+    SPLITTER_TOTAL_WIDTH,
+};
+pub use log_level::LogLevel;
 pub use msg::Msg;
 pub use state::{
-    normalize_url_for_dedupe, AppState, CompletedJobSnapshot, JobId, JobResultKind, SessionState,
-    Stage,
+    normalize_url_for_dedupe, AppState, CompletedJobSnapshot, ContentChange, JobId, JobLink,
+    JobResultKind, LinkKind, LinkScope, PersistedSession, SessionState, Stage,
+    DEFAULT_ALLOWED_SCHEMES, PASTE_CONFIRM_THRESHOLD,
 };
 pub use update::update;
-pub use view_model::{AppViewModel, JobRowView, PreviewHeaderView, TOKEN_LIMIT};
+pub use view_model::{
+    ActivityEvent, AppViewModel, ChangeSummary, JobRowView, LastPasteStats, LinkFilterView,
+    LinkScopeFilterView, LinksPaneView, PendingPaste, PreviewHeaderView, RejectedUrls,
+    SessionSummary,
+};
+#[cfg(feature = "wasm")]
+pub use wasm::WasmCore;
@@ -0,0 +1,46 @@
+//! How much the platform logs, chosen in settings and saved with the session.
+
+/// The most verbose level that is logged; `Off` logs nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Levels offered in settings, most severe first.
+    pub const ALL: [LogLevel; 6] = [
+        LogLevel::Off,
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    /// Stable id for settings files and environment variables, e.g. `debug`.
+    pub fn id(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    /// Inverse of [`id`](Self::id), ignoring case.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let id = id.trim();
+        Self::ALL
+            .into_iter()
+            .find(|level| level.id().eq_ignore_ascii_case(id))
+    }
+}
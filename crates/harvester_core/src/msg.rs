@@ -1,20 +1,92 @@
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Msg {
     /// User edited the URL input box (debounced text).
     InputChanged(String),
     /// User submitted the current URL input for ingestion.
     UrlsSubmitted,
+    /// User confirmed the large paste shown as pending; its jobs are created.
+    PasteConfirmed,
+    /// User discarded the pending paste.
+    PasteCancelled,
+    /// URLs from outside the input box (API, extension, stdin); the input buffer is left alone.
+    UrlsReceived(Vec<String>),
     /// Restore previously completed jobs from persisted state.
     RestoreCompletedJobs(Vec<crate::CompletedJobSnapshot>),
-    /// User clicked Stop/Finish.
+    /// User clicked Stop/Finish; dismisses a pending "Stop now" instead.
     StopFinishClicked,
+    /// User clicked "Stop now"; asks for confirmation before cancelling, and
+    /// a second click while asking confirms.
+    StopNowClicked,
+    /// User confirmed "Stop now": in-flight and queued jobs are cancelled.
+    StopNowConfirmed,
+    /// User backed out of the "Stop now" confirmation.
+    StopNowDismissed,
+    /// Engine completed every queued and running job after a stop.
+    AllJobsDrained,
+    /// User closed the window: jobs are stopped with `policy` and the app
+    /// quits once the engine has drained. A second request quits right away.
+    CloseRequested { policy: crate::StopPolicy },
+    /// User asked for a fresh session: the current one is snapshotted and its
+    /// jobs cleared. Ignored while jobs are still running.
+    NewSessionRequested,
+    /// The output profiles the platform offers, and the one in use.
+    ProfilesLoaded { names: Vec<String>, active: String },
+    /// User picked an output profile. Ignored while jobs are still running.
+    ProfileSelected(String),
+    /// The platform now writes to the profile's output directory; the
+    /// session is cleared so that directory's saved state can be restored.
+    ProfileActivated(String),
     /// User clicked Archive.
     ArchiveClicked,
+    /// User clicked "Export new only".
+    ArchiveNewClicked,
+    /// Engine finished an export at the given (engine clock) timestamp.
+    ExportCompleted { exported_utc: String },
+    /// Engine could not write the requested export.
+    ExportFailed { message: String },
+    /// Restore the last export timestamp from persisted state.
+    RestoreLastExport(String),
+    /// The persisted state file was unreadable and its backup was restored instead.
+    StateRecoveredFromBackup,
+    /// User picked a token budget preset in settings.
+    BudgetPresetSelected(crate::BudgetPreset),
+    /// Restore the budget preset from persisted state.
+    RestoreBudget(crate::BudgetPreset),
+    /// User picked the quality score (0-100) exports require; `None` keeps
+    /// the output profile's threshold.
+    ExportMinQualitySelected(Option<u8>),
+    /// Restore the export quality threshold from persisted state.
+    RestoreExportMinQuality(Option<u8>),
+    /// User picked a log level in settings.
+    LogLevelSelected(crate::LogLevel),
+    /// Restore the log level from persisted state or the environment.
+    RestoreLogLevel(crate::LogLevel),
+    /// Panel widths saved by an earlier run.
+    RestorePanelWidths(crate::PanelWidths),
+    /// A splitter is being dragged; the panels left of it should span
+    /// `desired_left_width_px`.
+    SplitterDragged {
+        splitter: crate::Splitter,
+        desired_left_width_px: i32,
+    },
+    /// The drag ended; like `SplitterDragged`, and the widths are saved.
+    SplitterReleased {
+        splitter: crate::Splitter,
+        desired_left_width_px: i32,
+    },
+    /// The main window's client area changed width.
+    WindowResized { window_width: i32 },
+    /// The main window was moved, resized or (un)maximized, or its saved
+    /// geometry was restored.
+    WindowGeometryChanged(crate::WindowGeometry),
     /// UI/render tick to coalesce rendering.
     Tick,
-    /// Engine progress for a job.
+    /// Engine progress for a job; `Stage::Queued` marks its enqueueing.
     JobProgress {
         job_id: crate::JobId,
+        /// Unix time in milliseconds when the event was received.
+        at_ms: u64,
         stage: crate::Stage,
         tokens: Option<u32>,
         bytes: Option<u64>,
@@ -23,12 +95,102 @@ pub enum Msg {
     /// Engine completion for a job.
     JobDone {
         job_id: crate::JobId,
+        /// Unix time in milliseconds when the event was received.
+        at_ms: u64,
         result: crate::JobResultKind,
         content_preview: Option<String>,
-        extracted_links: Vec<String>,
+        extracted_links: Vec<crate::JobLink>,
+    },
+    /// Engine named why a job failed, e.g. `http_404`; sent before its `JobDone`.
+    JobFailureReported { job_id: crate::JobId, kind: String },
+    /// Engine recounted a finished job's document with its exact counter.
+    TokenCountRefined { job_id: crate::JobId, tokens: u32 },
+    /// Engine was rate limited on a job and fetches it again after `delay_secs`.
+    JobRetryScheduled {
+        job_id: crate::JobId,
+        delay_secs: u64,
+    },
+    /// Engine queue is full; hold new intake until it resumes.
+    QueueSaturated,
+    /// Engine queue has room again.
+    QueueResumed,
+    /// Engine queues at most this many jobs; later ones wait in the app until
+    /// it has room.
+    QueueCapacity(usize),
+    /// Engine's memory budget is spent; queued jobs wait until running ones
+    /// finish. Cleared when the next job starts.
+    MemoryThrottled {
+        in_use_bytes: u64,
+        budget_bytes: u64,
+    },
+    /// Start or stop watching the selected job, re-fetching every `interval_secs`.
+    ToggleWatchSelected { interval_secs: u64 },
+    /// Pin or unpin the selected job.
+    TogglePinSelected,
+    /// Copy the selected job's URL to the clipboard.
+    CopySelectedUrl,
+    /// Open the selected job's URL in the default browser.
+    OpenSelectedUrl,
+    /// Engine found new content for a watched job.
+    WatchedPageChanged {
+        watch_id: crate::JobId,
+        tokens: Option<u32>,
+        bytes: Option<u64>,
+        content_preview: Option<String>,
+        extracted_links: Vec<crate::JobLink>,
+        change: Option<crate::ContentChange>,
+    },
+    /// Engine found the page's canonical URL; it becomes a dedupe key.
+    CanonicalUrlResolved {
+        job_id: crate::JobId,
+        canonical_url: String,
+    },
+    /// Engine scored the extracted content (0-100); `low` when below its threshold.
+    QualityAssessed {
+        job_id: crate::JobId,
+        score: u8,
+        low: bool,
+    },
+    /// Engine measured a written document: `words` and `minutes` to read it.
+    ReadingMeasured {
+        job_id: crate::JobId,
+        words: u32,
+        minutes: u32,
+    },
+    /// Engine found the job's content identical to an earlier job's document
+    /// and wrote no file of its own.
+    DuplicateDetected {
+        job_id: crate::JobId,
+        original: crate::JobId,
+    },
+    /// Engine compared a written document with the version it replaced.
+    ContentCompared {
+        job_id: crate::JobId,
+        change: crate::ContentChange,
     },
+    /// User toggled between the document preview and its diff.
+    DiffModeToggled,
+    /// User showed or hid one kind of link in the links pane.
+    LinkKindToggled(crate::LinkKind),
+    /// User showed or hid the internal or the external links.
+    LinkScopeToggled(crate::LinkScope),
+    /// User checked or unchecked a link in the links pane.
+    LinkCheckToggled { url: String },
+    /// User asked for the list of every image link found this session.
+    ImageInventoryRequested,
+    /// User clicked "Harvest selected": the checked links become jobs, through
+    /// the same validation and dedupe as pasted URLs.
+    HarvestCheckedLinks,
     /// User selected a job from the tree view.
     JobSelected { job_id: crate::JobId },
+    /// User clicked a column header of the jobs list.
+    JobColumnClicked(crate::JobColumn),
+    /// Keyboard: select the next job in list order.
+    SelectNextJob,
+    /// Keyboard: select the previous job in list order.
+    SelectPrevJob,
+    /// Keyboard: jump to the first failed job in list order.
+    SelectFirstFailed,
     /// Fallback for placeholder wiring.
     NoOp,
 }
@@ -1,31 +1,186 @@
-use crate::view_model::{AppViewModel, JobRowView, LastPasteStats, PreviewHeaderView, TOKEN_LIMIT};
-use std::collections::{BTreeMap, HashSet};
+use crate::view_model::{
+    ActivityEvent, AppViewModel, ChangeSummary, JobRowView, LastPasteStats, LinkFilterView,
+    LinkScopeFilterView, LinksPaneView, PendingPaste, PreviewHeaderView, RejectedUrls,
+    SessionSummary,
+};
+use crate::{
+    BudgetLevel, BudgetPreset, ImageRef, JobColumn, JobSort, LogLevel, PanelWidths, Splitter,
+    WindowGeometry,
+};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use url::Url;
 
 pub type JobId = u64;
 
 const MAX_EXTRACTED_LINKS: usize = 5_000;
+/// Events kept for the activity ticker; older ones are dropped.
+const ACTIVITY_LOG_CAPACITY: usize = 50;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CompletedJobSnapshot {
     pub url: String,
     pub tokens: Option<u32>,
     pub bytes: Option<u64>,
     pub links: Vec<String>,
+    pub pinned: bool,
 }
 
+/// What the platform writes to the session state file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedSession {
+    pub completed: Vec<CompletedJobSnapshot>,
+    pub last_export_utc: Option<String>,
+    pub budget: BudgetPreset,
+    pub log_level: LogLevel,
+    pub export_min_quality: Option<u8>,
+    pub panel_widths: PanelWidths,
+    pub window_geometry: WindowGeometry,
+}
+
+/// How a re-written document differs from the version it replaced.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentChange {
+    pub added: usize,
+    pub removed: usize,
+    /// Line diff shown in diff preview mode; empty when nothing changed.
+    pub diff: String,
+}
+
+/// A link the engine extracted from a job's page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JobLink {
+    pub url: String,
+    /// Anchor text or image alt text.
+    pub text: Option<String>,
+    pub kind: LinkKind,
+    /// Same registrable domain as the job's page.
+    pub internal: bool,
+}
+
+impl JobLink {
+    /// Link known only by its URL, as restored from a saved session; it
+    /// counts as external.
+    pub fn from_url(url: impl Into<String>) -> Self {
+        let url = url.into();
+        let kind = if url.starts_with("mailto:") {
+            LinkKind::Email
+        } else {
+            LinkKind::Hyperlink
+        };
+        Self {
+            url,
+            text: None,
+            kind,
+            internal: false,
+        }
+    }
+
+    pub fn scope(&self) -> LinkScope {
+        if self.internal {
+            LinkScope::Internal
+        } else {
+            LinkScope::External
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkKind {
+    Hyperlink,
+    Image,
+    Email,
+}
+
+impl LinkKind {
+    pub const ALL: [LinkKind; 3] = [LinkKind::Hyperlink, LinkKind::Image, LinkKind::Email];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkKind::Hyperlink => "Links",
+            LinkKind::Image => "Images",
+            LinkKind::Email => "Emails",
+        }
+    }
+}
+
+/// Whether a link stays on the site of the page it was found on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkScope {
+    Internal,
+    External,
+}
+
+impl LinkScope {
+    pub const ALL: [LinkScope; 2] = [LinkScope::Internal, LinkScope::External];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LinkScope::Internal => "Internal",
+            LinkScope::External => "External",
+        }
+    }
+}
+
+const NO_CHANGES_TEXT: &str = "(no changes since the previous harvest)";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct AppState {
     session: SessionState,
     jobs: BTreeMap<JobId, JobState>,
     metrics: MetricsState,
     ui: UiState,
+    /// The job shown in the preview and acted on by Watch, Pin and the like.
+    selected: Option<JobId>,
+    /// Column the jobs list is sorted by; `None` keeps enqueue order.
+    job_sort: Option<JobSort>,
     seen_urls: HashSet<String>,
     last_paste_stats: Option<LastPasteStats>,
+    /// A large paste held back until confirmed, with its accepted URLs.
+    pending_paste: Option<(PendingPaste, Vec<String>)>,
     dirty: bool,
     next_job_id: JobId,
+    last_export_utc: Option<String>,
+    intake_paused: bool,
+    /// Most jobs the engine queues at once; `None` until the platform reports it.
+    queue_capacity: Option<usize>,
+    /// Jobs past the engine's queue capacity, in enqueue order; not sent yet.
+    held_jobs: VecDeque<JobId>,
+    /// Bytes in use and the budget, while the engine defers jobs for memory.
+    memory_throttled: Option<(u64, u64)>,
+    /// "Stop now" was clicked and waits for the user to confirm.
+    stop_now_pending: bool,
+    /// The window is closing once the engine has drained.
+    closing: bool,
+    state_recovered: bool,
+    /// An export was requested and the engine has not reported back yet.
+    /// Independent of the session, which may finish or restart meanwhile.
+    export_in_progress: bool,
+    budget: BudgetPreset,
+    log_level: LogLevel,
+    /// Exports leave out documents scoring below this (0-100); `None` keeps
+    /// the output profile's threshold.
+    export_min_quality: Option<u8>,
+    activity: VecDeque<ActivityEvent>,
+    /// Lowercase URL schemes accepted at intake.
+    allowed_schemes: Vec<String>,
+    /// Output profiles offered by the platform, in its order.
+    profiles: Vec<String>,
+    active_profile: Option<String>,
 }
 
+/// Pastes with more new URLs than this wait for `Msg::PasteConfirmed`.
+pub const PASTE_CONFIRM_THRESHOLD: usize = 25;
+
+/// Schemes accepted at intake unless overridden with [`AppState::with_allowed_schemes`].
+pub const DEFAULT_ALLOWED_SCHEMES: [&str; 2] = ["http", "https"];
+
 impl Default for AppState {
     fn default() -> Self {
         Self {
@@ -33,10 +188,29 @@ impl Default for AppState {
             jobs: BTreeMap::new(),
             metrics: MetricsState::default(),
             ui: UiState::default(),
+            selected: None,
+            job_sort: None,
             seen_urls: HashSet::new(),
             last_paste_stats: None,
+            pending_paste: None,
             dirty: false,
             next_job_id: 1,
+            last_export_utc: None,
+            intake_paused: false,
+            queue_capacity: None,
+            held_jobs: VecDeque::new(),
+            memory_throttled: None,
+            stop_now_pending: false,
+            closing: false,
+            state_recovered: false,
+            export_in_progress: false,
+            budget: BudgetPreset::default(),
+            log_level: LogLevel::default(),
+            export_min_quality: None,
+            activity: VecDeque::new(),
+            allowed_schemes: DEFAULT_ALLOWED_SCHEMES.map(str::to_string).to_vec(),
+            profiles: Vec::new(),
+            active_profile: None,
         }
     }
 }
@@ -46,40 +220,145 @@ impl AppState {
         Self::default()
     }
 
+    /// Replace the URL schemes accepted at intake (compared case-insensitively).
+    pub fn with_allowed_schemes<I, S>(mut self, schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_schemes = schemes
+            .into_iter()
+            .map(|scheme| scheme.as_ref().to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    pub(crate) fn is_scheme_allowed(&self, scheme: &str) -> bool {
+        self.allowed_schemes.iter().any(|allowed| allowed == scheme)
+    }
+
     pub fn view(&self) -> AppViewModel {
-        let jobs: Vec<JobRowView> = self.jobs.iter().map(|(id, job)| job.to_view(*id)).collect();
-        let preview_text = self.ui.preview_content().map(ToOwned::to_owned);
-        let preview_header = self
-            .ui
-            .selected_job_id()
-            .and_then(|job_id| self.jobs.get(&job_id))
-            .map(|job| {
-                let quality = job.preview_quality.unwrap_or_default();
-                PreviewHeaderView {
-                    domain: domain_from_url(&job.url),
-                    tokens: job.tokens,
-                    bytes: job.bytes,
-                    stage: job.stage,
-                    outcome: job.outcome,
-                    heading_count: quality.heading_count,
-                    link_density: quality.link_density,
-                    nav_heavy: quality.nav_heavy(),
+        self.view_window(0, usize::MAX)
+    }
+
+    /// Like [`AppState::view`], but only builds the `count` job rows starting
+    /// at row `offset`, so large job lists cost what is on screen.
+    pub fn view_window(&self, offset: usize, count: usize) -> AppViewModel {
+        let order = self.row_order();
+        let offset = offset.min(order.len());
+        let jobs: Vec<JobRowView> = order[offset..]
+            .iter()
+            .take(count)
+            .copied()
+            .filter_map(|id| self.jobs.get(&id).map(|job| job.to_view(id)))
+            .collect();
+        let selected = self.selected.and_then(|job_id| self.jobs.get(&job_id));
+        let diff_text = selected
+            .filter(|_| self.ui.diff_mode)
+            .and_then(|job| job.change.as_ref())
+            .map(|change| {
+                if change.diff.is_empty() {
+                    NO_CHANGES_TEXT.to_string()
+                } else {
+                    change.diff.clone()
                 }
             });
+        let preview_text = diff_text.or_else(|| self.ui.preview_content().map(ToOwned::to_owned));
+        let preview_header = selected.map(|job| {
+            let quality = job.preview_quality.unwrap_or_default();
+            PreviewHeaderView {
+                domain: domain_from_url(&job.url),
+                tokens: job.tokens,
+                bytes: job.bytes,
+                stage: job.stage,
+                outcome: job.outcome,
+                reading: job.reading,
+                heading_count: quality.heading_count,
+                link_density: quality.link_density,
+                nav_heavy: quality.nav_heavy(),
+                watched: job.watch_interval_secs.is_some(),
+                pinned: job.pinned,
+                change: job.change_summary(),
+                source_batch: job.source_batch.clone(),
+            }
+        });
+        let links_pane = self.view_links_pane();
         AppViewModel {
             session: self.session,
             queued_urls: self.ui.urls.clone(),
             job_count: self.jobs.len(),
+            jobs_offset: offset,
             jobs,
             last_paste_stats: self.last_paste_stats.clone(),
+            pending_paste: self.pending_paste.as_ref().map(|(paste, _)| paste.clone()),
             dirty: self.dirty,
             total_tokens: self.metrics.total_tokens,
-            token_limit: TOKEN_LIMIT,
+            total_bytes: self.jobs.values().filter_map(|job| job.bytes).sum(),
+            budget: self.budget,
+            token_limit: self.budget.token_limit(),
+            budget_level: self.budget_level(),
+            log_level: self.log_level,
+            export_min_quality: self.export_min_quality,
+            panel_widths: self.ui.panel_widths,
+            selected_job_id: self.selected,
+            job_sort: self.job_sort,
             preview_text,
             preview_header,
+            diff_mode: self.ui.diff_mode,
+            links_pane,
+            intake_paused: self.intake_paused,
+            memory_throttled: self.memory_throttled,
+            stop_now_pending: self.stop_now_pending,
+            closing: self.closing,
+            state_recovered: self.state_recovered,
+            export_in_progress: self.export_in_progress,
+            activity_log: self.activity.iter().cloned().collect(),
+            session_summary: (self.session == SessionState::Finished)
+                .then(|| self.session_summary()),
+            profiles: self.profiles.clone(),
+            active_profile: self.active_profile.clone(),
         }
     }
 
+    fn session_summary(&self) -> SessionSummary {
+        let mut summary = SessionSummary {
+            total_tokens: self.metrics.total_tokens,
+            ..SessionSummary::default()
+        };
+        let mut first_ms: Option<u64> = None;
+        let mut last_ms: Option<u64> = None;
+        for job in self.jobs.values() {
+            let Some(outcome) = job.outcome else {
+                continue;
+            };
+            summary.pages += 1;
+            summary.total_bytes += job.bytes.unwrap_or(0);
+            match outcome {
+                JobResultKind::Success => summary.succeeded += 1,
+                JobResultKind::FromCache => {
+                    summary.succeeded += 1;
+                    summary.from_cache += 1;
+                }
+                JobResultKind::PartialContent => summary.partial += 1,
+                JobResultKind::Cancelled => summary.cancelled += 1,
+                JobResultKind::Failed => {
+                    let kind = job.failure.as_deref().unwrap_or("unknown");
+                    *summary.failures.entry(kind.to_string()).or_default() += 1;
+                }
+            }
+            if let Some(start) = job.timing.enqueued_at_ms.or(job.timing.started_at_ms) {
+                first_ms = Some(first_ms.map_or(start, |first| first.min(start)));
+            }
+            if let Some(end) = job.timing.finished_at_ms {
+                last_ms = Some(last_ms.map_or(end, |last| last.max(end)));
+            }
+        }
+        summary.duration_ms = first_ms
+            .zip(last_ms)
+            .map(|(first, last)| last.saturating_sub(first));
+        summary
+    }
+
     /// Returns the current dirty flag and clears it in one step.
     pub fn consume_dirty(&mut self) -> bool {
         let was_dirty = self.dirty;
@@ -87,19 +366,145 @@ impl AppState {
         was_dirty
     }
 
+    fn links_pane(&self, job_id: JobId, job: &JobState) -> LinksPaneView {
+        let hidden = &self.ui.hidden_link_kinds;
+        let hidden_scopes = &self.ui.hidden_link_scopes;
+        let all_links = job.extracted_links();
+        let links: Vec<JobLink> = all_links
+            .iter()
+            .filter(|link| !hidden.contains(&link.kind) && !hidden_scopes.contains(&link.scope()))
+            .cloned()
+            .collect();
+        LinksPaneView {
+            job_id,
+            filters: LinkKind::ALL
+                .into_iter()
+                .map(|kind| LinkFilterView {
+                    kind,
+                    count: all_links.iter().filter(|link| link.kind == kind).count(),
+                    shown: !hidden.contains(&kind),
+                })
+                .collect(),
+            scopes: LinkScope::ALL
+                .into_iter()
+                .map(|scope| LinkScopeFilterView {
+                    scope,
+                    count: all_links
+                        .iter()
+                        .filter(|link| link.scope() == scope)
+                        .count(),
+                    shown: !hidden_scopes.contains(&scope),
+                })
+                .collect(),
+            checked: links
+                .iter()
+                .filter(|link| self.ui.checked_links.contains(&link.url))
+                .map(|link| link.url.clone())
+                .collect(),
+            links,
+        }
+    }
+
+    /// Image links of every job, in job order, with the page they are on.
+    pub(crate) fn image_inventory(&self) -> Vec<ImageRef> {
+        self.jobs
+            .values()
+            .flat_map(|job| {
+                let page_url = job.canonical_url.as_ref().unwrap_or(&job.url);
+                job.extracted_links()
+                    .iter()
+                    .filter(|link| link.kind == LinkKind::Image)
+                    .map(move |link| ImageRef {
+                        image_url: link.url.clone(),
+                        page_url: page_url.clone(),
+                        alt_text: link.text.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    pub fn persisted_session(&self) -> PersistedSession {
+        PersistedSession {
+            completed: self.completed_jobs_snapshot(),
+            last_export_utc: self.last_export_utc.clone(),
+            budget: self.budget(),
+            log_level: self.log_level,
+            export_min_quality: self.export_min_quality,
+            panel_widths: self.panel_widths(),
+            window_geometry: self.window_geometry(),
+        }
+    }
+
+    /// Partial-content jobs are left out so they are harvested again next
+    /// session, e.g. after signing in.
     pub fn completed_jobs_snapshot(&self) -> Vec<CompletedJobSnapshot> {
         self.jobs
             .values()
-            .filter(|job| job.outcome == Some(JobResultKind::Success))
+            .filter(|job| job.outcome.is_some_and(JobResultKind::is_complete))
             .map(|job| CompletedJobSnapshot {
-                url: job.url.clone(),
+                url: job.canonical_url.as_ref().unwrap_or(&job.url).clone(),
                 tokens: job.tokens,
                 bytes: job.bytes,
-                links: job.extracted_links().to_vec(),
+                links: job
+                    .extracted_links()
+                    .iter()
+                    .map(|link| link.url.clone())
+                    .collect(),
+                pinned: job.pinned,
             })
             .collect()
     }
 
+    /// URLs of pinned jobs, including their canonical URLs, for the engine's
+    /// fitted exports.
+    pub(crate) fn pinned_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self
+            .jobs
+            .values()
+            .filter(|job| job.pinned)
+            .flat_map(|job| std::iter::once(&job.url).chain(job.canonical_url.as_ref()))
+            .cloned()
+            .collect();
+        urls.sort();
+        urls.dedup();
+        urls
+    }
+
+    /// URL of the selected job, if any.
+    pub(crate) fn selected_url(&self) -> Option<String> {
+        self.selected
+            .and_then(|job_id| self.jobs.get(&job_id))
+            .map(|job| job.url.clone())
+    }
+
+    /// Pin or unpin the selected job; `false` when nothing is selected.
+    pub(crate) fn toggle_pin_selected(&mut self) -> bool {
+        let Some(job) = self.selected.and_then(|job_id| self.jobs.get_mut(&job_id)) else {
+            return false;
+        };
+        job.pinned = !job.pinned;
+        self.dirty = true;
+        true
+    }
+
+    /// Timestamp of the most recent export, used for "export new only".
+    pub fn last_export_utc(&self) -> Option<&str> {
+        self.last_export_utc.as_deref()
+    }
+
+    pub(crate) fn record_export(&mut self, exported_utc: String) {
+        self.last_export_utc = Some(exported_utc);
+    }
+
+    /// Append to the activity log, dropping the oldest event when full.
+    pub(crate) fn log_activity(&mut self, event: ActivityEvent) {
+        if self.activity.len() == ACTIVITY_LOG_CAPACITY {
+            self.activity.pop_front();
+        }
+        self.activity.push_back(event);
+        self.dirty = true;
+    }
+
     pub(crate) fn restore_completed_jobs(&mut self, entries: Vec<CompletedJobSnapshot>) {
         if entries.is_empty() {
             return;
@@ -110,10 +515,12 @@ impl AppState {
         self.metrics = MetricsState::default();
         self.ui.urls.clear();
         self.ui.clear_preview();
+        self.selected = None;
+        self.ui.checked_links.clear();
         self.ui.clear_input_buffer();
         self.last_paste_stats = None;
-        self.next_job_id = 1;
 
+        // Ids keep counting: the engine may still report on earlier jobs.
         for entry in entries {
             let job_id = self.next_job_id;
             self.next_job_id += 1;
@@ -127,7 +534,19 @@ impl AppState {
                     bytes: entry.bytes,
                     content_preview: None,
                     preview_quality: None,
-                    extracted_links: entry.links.clone(),
+                    extracted_links: entry.links.iter().cloned().map(JobLink::from_url).collect(),
+                    watch_interval_secs: None,
+                    changed_from: None,
+                    duplicate_of: None,
+                    change: None,
+                    retry_in_secs: None,
+                    canonical_url: None,
+                    quality: None,
+                    reading: None,
+                    timing: JobTiming::default(),
+                    failure: None,
+                    pinned: entry.pinned,
+                    source_batch: None,
                 },
             );
             let normalized = normalize_url_for_dedupe(&entry.url);
@@ -144,16 +563,249 @@ impl AppState {
 
     pub(crate) fn select_job(&mut self, job_id: JobId) {
         if let Some(job) = self.jobs.get(&job_id) {
-            if self.ui.select_job(job_id, job.content_preview.as_deref()) {
+            let preview_changed = self.ui.select_job(job_id, job.content_preview.as_deref());
+            if preview_changed || self.selected != Some(job_id) {
+                if self.selected != Some(job_id) {
+                    self.ui.checked_links.clear();
+                }
+                self.selected = Some(job_id);
                 self.dirty = true;
             }
         }
     }
 
+    /// Job ids in list order: pinned jobs first, each group by id.
+    fn row_order(&self) -> Vec<JobId> {
+        let mut order: Vec<JobId> = self.jobs.keys().copied().collect();
+        order.sort_by(|a, b| {
+            let (job_a, job_b) = (&self.jobs[a], &self.jobs[b]);
+            job_b.pinned.cmp(&job_a.pinned).then_with(|| {
+                self.job_sort.map_or(Ordering::Equal, |sort| {
+                    let ordering = job_a.compare_by(job_b, sort.column);
+                    if sort.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+            })
+        });
+        order
+    }
+
+    /// Panel widths, saved with the session settings.
+    pub fn panel_widths(&self) -> PanelWidths {
+        self.ui.panel_widths
+    }
+
+    pub(crate) fn drag_splitter(&mut self, splitter: Splitter, desired_left_width_px: i32) {
+        let widths =
+            self.ui
+                .panel_widths
+                .dragged(splitter, desired_left_width_px, self.ui.window_width);
+        self.set_panel_widths(widths);
+    }
+
+    pub(crate) fn set_window_width(&mut self, window_width: i32) {
+        self.ui.window_width = window_width;
+        self.set_panel_widths(self.ui.panel_widths);
+    }
+
+    /// Main window geometry, saved with the session settings.
+    pub fn window_geometry(&self) -> WindowGeometry {
+        self.ui.window_geometry
+    }
+
+    pub(crate) fn set_window_geometry(&mut self, geometry: WindowGeometry) {
+        self.ui.window_geometry = geometry;
+    }
+
+    pub(crate) fn set_panel_widths(&mut self, widths: PanelWidths) {
+        let widths = widths.fitted(self.ui.window_width);
+        if self.ui.panel_widths != widths {
+            self.ui.panel_widths = widths;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn sort_jobs_by(&mut self, column: JobColumn) {
+        self.job_sort = Some(JobSort::clicked(self.job_sort, column));
+        self.dirty = true;
+    }
+
+    /// Move the selection `step` rows down (or up when negative), stopping at
+    /// either end. With nothing selected, down starts at the top and up at the
+    /// bottom.
+    pub(crate) fn select_adjacent(&mut self, step: isize) {
+        let order = self.row_order();
+        let Some(last) = order.len().checked_sub(1) else {
+            return;
+        };
+        let current = self
+            .selected
+            .and_then(|job_id| order.iter().position(|id| *id == job_id));
+        let index = match current {
+            Some(index) => index.saturating_add_signed(step).min(last),
+            None if step < 0 => last,
+            None => 0,
+        };
+        self.select_job(order[index]);
+    }
+
+    /// Select the first failed job in list order, if any.
+    pub(crate) fn select_first_failed(&mut self) {
+        let failed = self
+            .row_order()
+            .into_iter()
+            .find(|id| self.jobs[id].outcome == Some(JobResultKind::Failed));
+        if let Some(job_id) = failed {
+            self.select_job(job_id);
+        }
+    }
+
     pub(crate) fn session(&self) -> SessionState {
         self.session
     }
 
+    pub(crate) fn intake_paused(&self) -> bool {
+        self.intake_paused
+    }
+
+    pub(crate) fn set_intake_paused(&mut self, paused: bool) {
+        if self.intake_paused != paused {
+            self.intake_paused = paused;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn set_queue_capacity(&mut self, capacity: usize) {
+        self.queue_capacity = Some(capacity.max(1));
+    }
+
+    pub(crate) fn held_job_count(&self) -> usize {
+        self.held_jobs.len()
+    }
+
+    /// Queue `job_ids` behind the jobs already held for the engine.
+    pub(crate) fn hold_jobs(&mut self, job_ids: impl IntoIterator<Item = JobId>) {
+        self.held_jobs.extend(job_ids);
+    }
+
+    /// Take the held jobs the engine has room for, oldest first, with their
+    /// URL and source batch.
+    pub(crate) fn release_held_jobs(&mut self) -> Vec<(JobId, String, Option<String>)> {
+        let room = match self.queue_capacity {
+            Some(capacity) => {
+                let queued = self
+                    .jobs
+                    .values()
+                    .filter(|job| job.stage == Stage::Queued)
+                    .count();
+                let in_engine = queued.saturating_sub(self.held_jobs.len());
+                capacity.saturating_sub(in_engine)
+            }
+            None => self.held_jobs.len(),
+        };
+        let count = room.min(self.held_jobs.len());
+        self.held_jobs
+            .drain(..count)
+            .filter_map(|job_id| {
+                let job = self.jobs.get(&job_id)?;
+                Some((job_id, job.url.clone(), job.source_batch.clone()))
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_memory_throttled(&mut self, throttled: Option<(u64, u64)>) {
+        if self.memory_throttled != throttled {
+            self.memory_throttled = throttled;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn closing(&self) -> bool {
+        self.closing
+    }
+
+    pub(crate) fn begin_close(&mut self) {
+        self.closing = true;
+        self.stop_now_pending = false;
+        self.dirty = true;
+    }
+
+    /// "Stop now" waits for the user to confirm it.
+    pub fn stop_now_pending(&self) -> bool {
+        self.stop_now_pending
+    }
+
+    pub(crate) fn set_stop_now_pending(&mut self, pending: bool) {
+        if self.stop_now_pending != pending {
+            self.stop_now_pending = pending;
+            self.dirty = true;
+        }
+    }
+
+    /// The active token budget, saved with the session settings.
+    pub fn budget(&self) -> BudgetPreset {
+        self.budget
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.metrics.total_tokens
+    }
+
+    pub fn budget_level(&self) -> BudgetLevel {
+        self.budget.level(self.metrics.total_tokens)
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: BudgetPreset) {
+        if self.budget != budget {
+            self.budget = budget;
+            self.dirty = true;
+        }
+    }
+
+    /// The log level, saved with the session settings.
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level
+    }
+
+    pub(crate) fn set_log_level(&mut self, level: LogLevel) {
+        if self.log_level != level {
+            self.log_level = level;
+            self.dirty = true;
+        }
+    }
+
+    /// The quality threshold exports are requested with, saved with the
+    /// session settings.
+    pub fn export_min_quality(&self) -> Option<u8> {
+        self.export_min_quality
+    }
+
+    pub(crate) fn set_export_min_quality(&mut self, min_quality: Option<u8>) {
+        if self.export_min_quality != min_quality {
+            self.export_min_quality = min_quality;
+            self.dirty = true;
+        }
+    }
+
+    pub fn export_in_progress(&self) -> bool {
+        self.export_in_progress
+    }
+
+    pub(crate) fn set_export_in_progress(&mut self, in_progress: bool) {
+        if self.export_in_progress != in_progress {
+            self.export_in_progress = in_progress;
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn mark_state_recovered(&mut self) {
+        self.state_recovered = true;
+        self.dirty = true;
+    }
+
     pub(crate) fn set_urls(&mut self, urls: Vec<String>) {
         self.ui.urls = urls;
         self.metrics.total_urls = self.ui.urls.len();
@@ -172,7 +824,10 @@ impl AppState {
         self.ui.clear_input_buffer();
     }
 
-    pub(crate) fn enqueue_jobs_from_ui(&mut self) -> Vec<(JobId, String)> {
+    pub(crate) fn enqueue_jobs_from_ui(
+        &mut self,
+        source_batch: Option<&str>,
+    ) -> Vec<(JobId, String)> {
         let mut enqueued = Vec::new();
         for url in self.ui.urls.iter() {
             let job_id = self.next_job_id;
@@ -188,6 +843,18 @@ impl AppState {
                     content_preview: None,
                     preview_quality: None,
                     extracted_links: Vec::new(),
+                    watch_interval_secs: None,
+                    changed_from: None,
+                    duplicate_of: None,
+                    change: None,
+                    retry_in_secs: None,
+                    canonical_url: None,
+                    quality: None,
+                    reading: None,
+                    timing: JobTiming::default(),
+                    failure: None,
+                    pinned: false,
+                    source_batch: source_batch.map(str::to_owned),
                 },
             );
             enqueued.push((job_id, url.clone()));
@@ -200,30 +867,33 @@ impl AppState {
     pub(crate) fn apply_progress(
         &mut self,
         job_id: JobId,
+        at_ms: u64,
         stage: Stage,
         tokens: Option<u32>,
         bytes: Option<u64>,
         content_preview: Option<String>,
     ) {
+        if let Some(t) = tokens {
+            self.set_job_tokens(job_id, t);
+        }
+        let started = self
+            .jobs
+            .get(&job_id)
+            .filter(|job| job.stage == Stage::Queued && stage != Stage::Queued)
+            .map(|job| job.url.clone());
+        if let Some(url) = started {
+            self.log_activity(ActivityEvent::JobStarted { job_id, url });
+            self.set_memory_throttled(None);
+        }
         if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.timing.record(stage, at_ms);
             job.stage = stage;
-            if let Some(t) = tokens {
-                if job.tokens != Some(t) {
-                    let previous = job.tokens.unwrap_or(0) as u64;
-                    self.metrics.total_tokens = self
-                        .metrics
-                        .total_tokens
-                        .saturating_sub(previous)
-                        .saturating_add(t as u64);
-                    job.tokens = Some(t);
-                }
-            }
+            job.retry_in_secs = None;
             if let Some(b) = bytes {
                 job.bytes = Some(b);
             }
             if let Some(content) = content_preview {
-                let selected = self.ui.selected_job_id() == Some(job_id);
-                if selected {
+                if self.selected == Some(job_id) {
                     self.ui.set_preview_state(PreviewState::InProgress {
                         job_id,
                         content: content.clone(),
@@ -235,17 +905,37 @@ impl AppState {
         }
     }
 
+    /// Replace a job's token count, keeping the session total in step.
+    pub(crate) fn set_job_tokens(&mut self, job_id: JobId, tokens: u32) {
+        let Some(job) = self.jobs.get_mut(&job_id) else {
+            return;
+        };
+        if job.tokens != Some(tokens) {
+            let previous = job.tokens.unwrap_or(0) as u64;
+            self.metrics.total_tokens = self
+                .metrics
+                .total_tokens
+                .saturating_sub(previous)
+                .saturating_add(tokens as u64);
+            job.tokens = Some(tokens);
+            self.dirty = true;
+        }
+    }
+
     pub(crate) fn apply_done(
         &mut self,
         job_id: JobId,
+        at_ms: u64,
         result: JobResultKind,
         content_preview: Option<String>,
-        extracted_links: Vec<String>,
+        extracted_links: Vec<JobLink>,
     ) {
         let job_updated = if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.timing.record(Stage::Done, at_ms);
             job.stage = Stage::Done;
             job.outcome = Some(result);
-            if matches!(result, JobResultKind::Success) {
+            job.retry_in_secs = None;
+            if result.has_document() {
                 if let Some(content) = content_preview {
                     job.set_preview_content(content);
                 }
@@ -258,27 +948,269 @@ impl AppState {
         } else {
             false
         };
-        if job_updated && self.ui.selected_job_id() == Some(job_id) {
+        if job_updated && self.selected == Some(job_id) {
             let preview_content = self.jobs.get(&job_id).and_then(|job| job.content_preview());
             self.ui.select_job(job_id, preview_content);
         }
-        if job_updated {
+        if let Some(job) = self.jobs.get(&job_id) {
+            let url = job.url.clone();
+            self.log_activity(match result {
+                JobResultKind::Failed => ActivityEvent::JobFailed { job_id, url },
+                JobResultKind::Cancelled => ActivityEvent::JobCancelled { job_id, url },
+                _ => ActivityEvent::JobFinished {
+                    job_id,
+                    url,
+                    result,
+                },
+            });
+        }
+    }
+
+    /// The engine is waiting out a rate limit before fetching the job again.
+    pub(crate) fn schedule_retry(&mut self, job_id: JobId, delay_secs: u64) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.retry_in_secs = Some(delay_secs);
+            self.dirty = true;
+        }
+    }
+
+    /// Remember the canonical URL so later pastes of it, or of other variants
+    /// resolving to it, are skipped as duplicates.
+    pub(crate) fn record_canonical_url(&mut self, job_id: JobId, canonical_url: String) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            self.seen_urls
+                .insert(normalize_url_for_dedupe(&canonical_url));
+            job.canonical_url = Some(canonical_url);
+        }
+    }
+
+    pub(crate) fn record_failure(&mut self, job_id: JobId, kind: String) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.failure = Some(kind);
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn record_quality(&mut self, job_id: JobId, score: u8, low: bool) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.quality = Some((score, low));
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn record_reading(&mut self, job_id: JobId, words: u32, minutes: u32) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.reading = Some((words, minutes));
+            self.dirty = true;
+        }
+    }
+
+    pub(crate) fn record_duplicate(&mut self, job_id: JobId, original: JobId) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.duplicate_of = Some(original);
+            self.dirty = true;
+        }
+    }
+
+    /// Attach the diff against the previous harvest of the same document.
+    pub(crate) fn record_content_change(&mut self, job_id: JobId, change: ContentChange) {
+        if let Some(job) = self.jobs.get_mut(&job_id) {
+            job.change = Some(change);
             self.dirty = true;
         }
     }
 
+    /// Switch the preview between the document and its diff.
+    pub(crate) fn toggle_diff_mode(&mut self) {
+        self.ui.diff_mode = !self.ui.diff_mode;
+        self.dirty = true;
+    }
+
+    /// Check or uncheck one of the selected job's links.
+    pub(crate) fn toggle_link_checked(&mut self, url: String) {
+        if !self.ui.checked_links.remove(&url) {
+            self.ui.checked_links.insert(url);
+        }
+        self.dirty = true;
+    }
+
+    /// The checked links still listed in the links pane, in pane order;
+    /// clears the checks.
+    pub(crate) fn take_checked_links(&mut self) -> Vec<String> {
+        let checked = self
+            .view_links_pane()
+            .map(|pane| pane.checked)
+            .unwrap_or_default();
+        self.ui.checked_links.clear();
+        self.dirty = true;
+        checked
+    }
+
+    fn view_links_pane(&self) -> Option<LinksPaneView> {
+        let job_id = self.selected?;
+        self.jobs
+            .get(&job_id)
+            .map(|job| self.links_pane(job_id, job))
+    }
+
+    /// Show or hide the internal or the external links in the links pane.
+    pub(crate) fn toggle_link_scope(&mut self, scope: LinkScope) {
+        if !self.ui.hidden_link_scopes.remove(&scope) {
+            self.ui.hidden_link_scopes.insert(scope);
+        }
+        self.dirty = true;
+    }
+
+    /// Show or hide one kind of link in the links pane.
+    pub(crate) fn toggle_link_kind(&mut self, kind: LinkKind) {
+        if !self.ui.hidden_link_kinds.remove(&kind) {
+            self.ui.hidden_link_kinds.insert(kind);
+        }
+        self.dirty = true;
+    }
+
+    /// Toggle the watch on the selected job; only successful harvests can be watched.
+    pub(crate) fn toggle_watch_selected(&mut self, interval_secs: u64) -> Option<WatchToggle> {
+        let job_id = self.selected?;
+        let job = self.jobs.get_mut(&job_id)?;
+        if !job.outcome.is_some_and(JobResultKind::is_complete) {
+            return None;
+        }
+        self.dirty = true;
+        if job.watch_interval_secs.take().is_some() {
+            Some(WatchToggle::Stopped { job_id })
+        } else {
+            job.watch_interval_secs = Some(interval_secs);
+            Some(WatchToggle::Started {
+                job_id,
+                url: job.url.clone(),
+            })
+        }
+    }
+
+    /// Record a new version of a watched page as its own completed job.
+    pub(crate) fn add_changed_job(
+        &mut self,
+        watch_id: JobId,
+        tokens: Option<u32>,
+        bytes: Option<u64>,
+        content_preview: Option<String>,
+        extracted_links: Vec<JobLink>,
+        change: Option<ContentChange>,
+    ) -> Option<JobId> {
+        let url = self.jobs.get(&watch_id)?.url.clone();
+        let job_id = self.next_job_id;
+        self.next_job_id += 1;
+        let mut job = JobState {
+            url,
+            stage: Stage::Done,
+            outcome: Some(JobResultKind::Success),
+            tokens,
+            bytes,
+            changed_from: Some(watch_id),
+            change,
+            ..Default::default()
+        };
+        if let Some(content) = content_preview {
+            job.set_preview_content(content);
+        }
+        job.set_extracted_links(extracted_links);
+        self.jobs.insert(job_id, job);
+        if let Some(tokens) = tokens {
+            self.metrics.total_tokens = self.metrics.total_tokens.saturating_add(tokens as u64);
+        }
+        self.dirty = true;
+        Some(job_id)
+    }
+
+    /// Drop all watch marks; the engine ends watches when the session stops.
+    pub(crate) fn clear_watches(&mut self) {
+        for job in self.jobs.values_mut() {
+            if job.watch_interval_secs.take().is_some() {
+                self.dirty = true;
+            }
+        }
+    }
+
     pub(crate) fn start_session(&mut self) {
         self.session = SessionState::Running;
         self.dirty = true;
     }
 
+    /// Held jobs never reached the engine, so they are cancelled here rather
+    /// than by the engine's stop.
     pub(crate) fn finish_session(&mut self) {
         self.session = SessionState::Finishing;
+        while let Some(job_id) = self.held_jobs.pop_front() {
+            let at_ms = self
+                .jobs
+                .get(&job_id)
+                .and_then(|job| job.timing.last_seen_ms)
+                .unwrap_or(0);
+            self.apply_done(job_id, at_ms, JobResultKind::Cancelled, None, Vec::new());
+        }
         self.dirty = true;
     }
 
-    pub(crate) fn set_last_paste_stats(&mut self, enqueued: usize, skipped: usize) {
-        self.last_paste_stats = Some(LastPasteStats { enqueued, skipped });
+    pub(crate) fn complete_session(&mut self) {
+        self.session = SessionState::Finished;
+        self.stop_now_pending = false;
+        self.dirty = true;
+    }
+
+    /// Forget the session's jobs, counters and seen URLs and go back to `Idle`.
+    /// The budget, the last export time and the input box are kept; job ids
+    /// keep counting up so late engine events can't hit a new job.
+    pub(crate) fn start_new_session(&mut self) {
+        self.jobs.clear();
+        self.metrics = MetricsState::default();
+        self.seen_urls.clear();
+        self.last_paste_stats = None;
+        self.pending_paste = None;
+        self.ui.urls.clear();
+        self.ui.preview = PreviewState::Empty;
+        self.selected = None;
+        self.ui.checked_links.clear();
+        self.ui.diff_mode = false;
+        self.activity.clear();
+        self.intake_paused = false;
+        self.held_jobs.clear();
+        self.memory_throttled = None;
+        self.session = SessionState::Idle;
+        self.dirty = true;
+    }
+
+    pub(crate) fn set_profiles(&mut self, names: Vec<String>, active: String) {
+        self.profiles = names;
+        self.active_profile = Some(active);
+        self.dirty = true;
+    }
+
+    /// `name` is an offered profile other than the active one.
+    pub(crate) fn can_switch_profile(&self, name: &str) -> bool {
+        self.profiles.iter().any(|profile| profile == name)
+            && self.active_profile.as_deref() != Some(name)
+    }
+
+    /// Start over in profile `name`: its jobs and last export come from its
+    /// own output directory.
+    pub(crate) fn activate_profile(&mut self, name: String) {
+        self.start_new_session();
+        self.last_export_utc = None;
+        self.active_profile = Some(name);
+    }
+
+    pub(crate) fn set_last_paste_stats(
+        &mut self,
+        enqueued: usize,
+        skipped: usize,
+        rejected: RejectedUrls,
+    ) {
+        self.last_paste_stats = Some(LastPasteStats {
+            enqueued,
+            skipped,
+            rejected,
+        });
         self.dirty = true;
     }
 
@@ -287,6 +1219,29 @@ impl AppState {
     pub(crate) fn is_url_seen(&mut self, normalized_url: &str) -> bool {
         !self.seen_urls.insert(normalized_url.to_owned())
     }
+
+    /// Like [`Self::is_url_seen`], without recording the URL.
+    pub(crate) fn has_seen_url(&self, normalized_url: &str) -> bool {
+        self.seen_urls.contains(normalized_url)
+    }
+
+    pub(crate) fn set_pending_paste(&mut self, paste: PendingPaste, urls: Vec<String>) {
+        self.pending_paste = Some((paste, urls));
+        self.dirty = true;
+    }
+
+    /// The accepted URLs of the pending paste, and its summary.
+    pub(crate) fn take_pending_paste(&mut self) -> Option<(Vec<String>, PendingPaste)> {
+        let (paste, urls) = self.pending_paste.take()?;
+        self.dirty = true;
+        Some((urls, paste))
+    }
+
+    pub(crate) fn clear_pending_paste(&mut self) {
+        if self.pending_paste.take().is_some() {
+            self.dirty = true;
+        }
+    }
 }
 
 /// Normalize URL for deduplication: trim whitespace, lowercase, strip trailing `/`.
@@ -296,16 +1251,19 @@ pub fn normalize_url_for_dedupe(url: &str) -> String {
     lowercased.trim_end_matches('/').to_owned()
 }
 
-fn dedupe_extracted_links(links: Vec<String>) -> Vec<String> {
+fn dedupe_extracted_links(links: Vec<JobLink>) -> Vec<JobLink> {
     let mut seen = HashSet::new();
     let mut deduped = Vec::new();
     for link in links.into_iter() {
         if deduped.len() >= MAX_EXTRACTED_LINKS {
             break;
         }
-        let normalized = normalize_extracted_link(&link);
+        let normalized = normalize_extracted_link(&link.url);
         if seen.insert(normalized.clone()) {
-            deduped.push(normalized);
+            deduped.push(JobLink {
+                url: normalized,
+                ..link
+            });
         }
     }
     deduped
@@ -351,6 +1309,7 @@ fn domain_from_url(url: &str) -> String {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SessionState {
     #[default]
     Idle,
@@ -361,6 +1320,11 @@ pub enum SessionState {
     Finished,
 }
 
+pub(crate) enum WatchToggle {
+    Started { job_id: JobId, url: String },
+    Stopped { job_id: JobId },
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 struct JobState {
     url: String,
@@ -370,7 +1334,64 @@ struct JobState {
     bytes: Option<u64>,
     content_preview: Option<String>,
     preview_quality: Option<PreviewQuality>,
-    extracted_links: Vec<String>,
+    extracted_links: Vec<JobLink>,
+    /// Re-fetch interval while the page is watched.
+    watch_interval_secs: Option<u64>,
+    /// Set on jobs created by a watched page changing.
+    changed_from: Option<JobId>,
+    /// Earlier job whose document has the same content; no file was written.
+    duplicate_of: Option<JobId>,
+    /// Diff against the previous harvest; `None` for first harvests.
+    change: Option<ContentChange>,
+    /// Rate-limit delay the engine is waiting out before the next attempt.
+    retry_in_secs: Option<u64>,
+    /// The page's `<link rel="canonical">` target, once fetched.
+    canonical_url: Option<String>,
+    /// Engine quality score and whether it was flagged as low.
+    quality: Option<(u8, bool)>,
+    /// Words and reading minutes of the written document.
+    reading: Option<(u32, u32)>,
+    timing: JobTiming,
+    /// Why the job failed, as named by the engine.
+    failure: Option<String>,
+    /// Listed first and always part of fitted exports.
+    pinned: bool,
+    /// Label of the paste or batch the job came from.
+    source_batch: Option<String>,
+}
+
+/// Wall-clock timestamps (Unix milliseconds) taken from the job's messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct JobTiming {
+    enqueued_at_ms: Option<u64>,
+    /// First stage past `Queued`.
+    started_at_ms: Option<u64>,
+    finished_at_ms: Option<u64>,
+    /// Latest message, so a running job's elapsed time keeps growing.
+    last_seen_ms: Option<u64>,
+}
+
+impl JobTiming {
+    fn record(&mut self, stage: Stage, at_ms: u64) {
+        match stage {
+            Stage::Queued => {
+                self.enqueued_at_ms.get_or_insert(at_ms);
+            }
+            Stage::Done => self.finished_at_ms = Some(at_ms),
+            _ => {
+                self.started_at_ms.get_or_insert(at_ms);
+            }
+        }
+        self.last_seen_ms = Some(at_ms);
+    }
+
+    /// Time from the first stage (or enqueueing) to completion, or to the
+    /// latest message while the job is still running.
+    fn elapsed_ms(&self) -> Option<u64> {
+        let start = self.started_at_ms.or(self.enqueued_at_ms)?;
+        let end = self.finished_at_ms.or(self.last_seen_ms)?;
+        Some(end.saturating_sub(start))
+    }
 }
 
 impl JobState {
@@ -382,9 +1403,42 @@ impl JobState {
             outcome: self.outcome,
             tokens: self.tokens,
             bytes: self.bytes,
+            watched: self.watch_interval_secs.is_some(),
+            changed_from: self.changed_from,
+            duplicate_of: self.duplicate_of,
+            change: self.change_summary(),
+            retry_in_secs: self.retry_in_secs,
+            quality_score: self.quality.map(|(score, _)| score),
+            low_quality: self.quality.is_some_and(|(_, low)| low),
+            nav_heavy: self
+                .preview_quality
+                .is_some_and(|quality| quality.nav_heavy()),
+            word_count: self.reading.map(|(words, _)| words),
+            reading_minutes: self.reading.map(|(_, minutes)| minutes),
+            elapsed_ms: self.timing.elapsed_ms(),
+            pinned: self.pinned,
+            failure: self.failure.clone(),
+        }
+    }
+
+    fn compare_by(&self, other: &JobState, column: JobColumn) -> Ordering {
+        match column {
+            JobColumn::Url => self.url.cmp(&other.url),
+            JobColumn::Stage => (self.stage, self.outcome).cmp(&(other.stage, other.outcome)),
+            JobColumn::Tokens => self.tokens.cmp(&other.tokens),
+            JobColumn::Bytes => self.bytes.cmp(&other.bytes),
+            JobColumn::Time => self.timing.elapsed_ms().cmp(&other.timing.elapsed_ms()),
+            JobColumn::Error => self.failure.cmp(&other.failure),
         }
     }
 
+    fn change_summary(&self) -> Option<ChangeSummary> {
+        self.change.as_ref().map(|change| ChangeSummary {
+            added: change.added,
+            removed: change.removed,
+        })
+    }
+
     #[allow(dead_code)]
     pub(crate) fn content_preview(&self) -> Option<&str> {
         self.content_preview.as_deref()
@@ -400,11 +1454,11 @@ impl JobState {
         self.content_preview = None;
     }
 
-    fn set_extracted_links(&mut self, links: Vec<String>) {
+    fn set_extracted_links(&mut self, links: Vec<JobLink>) {
         self.extracted_links = dedupe_extracted_links(links);
     }
 
-    fn extracted_links(&self) -> &[String] {
+    fn extracted_links(&self) -> &[JobLink] {
         &self.extracted_links
     }
 }
@@ -478,15 +1532,6 @@ enum PreviewState {
 }
 
 impl PreviewState {
-    fn job_id(&self) -> Option<JobId> {
-        match self {
-            PreviewState::Empty => None,
-            PreviewState::Available { job_id, .. }
-            | PreviewState::InProgress { job_id, .. }
-            | PreviewState::Unavailable { job_id } => Some(*job_id),
-        }
-    }
-
     fn content(&self) -> Option<&str> {
         match self {
             PreviewState::Available { content, .. } | PreviewState::InProgress { content, .. } => {
@@ -502,6 +1547,18 @@ struct UiState {
     urls: Vec<String>,
     input_buffer: String,
     preview: PreviewState,
+    /// Preview shows the diff against the previous harvest.
+    diff_mode: bool,
+    /// Link kinds filtered out of the links pane.
+    hidden_link_kinds: BTreeSet<LinkKind>,
+    /// Internal or external links filtered out of the links pane.
+    hidden_link_scopes: BTreeSet<LinkScope>,
+    /// URLs checked in the selected job's links pane.
+    checked_links: BTreeSet<String>,
+    panel_widths: PanelWidths,
+    /// Client width of the main window; 0 until the platform reports it.
+    window_width: i32,
+    window_geometry: WindowGeometry,
 }
 
 impl UiState {
@@ -509,10 +1566,6 @@ impl UiState {
         self.preview.content()
     }
 
-    fn selected_job_id(&self) -> Option<JobId> {
-        self.preview.job_id()
-    }
-
     fn select_job(&mut self, job_id: JobId, content: Option<&str>) -> bool {
         let next_state = match content {
             Some(text) => PreviewState::Available {
@@ -550,7 +1603,8 @@ impl UiState {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stage {
     #[default]
     Queued,
@@ -562,10 +1616,33 @@ pub enum Stage {
     Done,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum JobResultKind {
     Success,
+    /// A document was written, but it looks like a paywall or consent wall
+    /// rather than the full page.
+    PartialContent,
+    /// A document was written from a cached response instead of a fresh fetch.
+    FromCache,
     Failed,
+    /// "Stop now" cancelled the job before it finished.
+    Cancelled,
+}
+
+impl JobResultKind {
+    /// The job produced a document.
+    pub fn has_document(self) -> bool {
+        matches!(
+            self,
+            JobResultKind::Success | JobResultKind::PartialContent | JobResultKind::FromCache
+        )
+    }
+
+    /// The job produced a full document, fetched or cached.
+    pub fn is_complete(self) -> bool {
+        matches!(self, JobResultKind::Success | JobResultKind::FromCache)
+    }
 }
 
 #[cfg(test)]
@@ -586,6 +1663,7 @@ mod tests {
         );
         state.apply_done(
             1,
+            0,
             JobResultKind::Success,
             Some("preview content".to_string()),
             Vec::new(),
@@ -594,6 +1672,29 @@ mod tests {
         assert_eq!(job.content_preview(), Some("preview content"));
     }
 
+    #[test]
+    fn partial_content_keeps_preview_but_is_not_snapshotted() {
+        let mut state = AppState::new();
+        state.jobs.insert(
+            5,
+            JobState {
+                url: "https://example.com/paywalled".to_string(),
+                stage: Stage::Queued,
+                ..Default::default()
+            },
+        );
+        state.apply_done(
+            5,
+            0,
+            JobResultKind::PartialContent,
+            Some("Subscribe to continue".to_string()),
+            Vec::new(),
+        );
+        let job = state.jobs.get(&5).expect("job exists");
+        assert_eq!(job.content_preview(), Some("Subscribe to continue"));
+        assert!(state.completed_jobs_snapshot().is_empty());
+    }
+
     #[test]
     fn job_done_failure_clears_preview() {
         let mut state = AppState::new();
@@ -608,6 +1709,7 @@ mod tests {
         );
         state.apply_done(
             2,
+            0,
             JobResultKind::Failed,
             Some("ignored".to_string()),
             Vec::new(),
@@ -698,6 +1800,7 @@ mod tests {
             state,
             Msg::JobProgress {
                 job_id: 6,
+                at_ms: 0,
                 stage: Stage::Converting,
                 tokens: None,
                 bytes: None,
@@ -727,6 +1830,7 @@ mod tests {
             state,
             Msg::JobProgress {
                 job_id: 7,
+                at_ms: 0,
                 stage: Stage::Converting,
                 tokens: None,
                 bytes: None,
@@ -757,6 +1861,7 @@ mod tests {
             state,
             Msg::JobProgress {
                 job_id: 8,
+                at_ms: 0,
                 stage: Stage::Converting,
                 tokens: None,
                 bytes: None,
@@ -767,6 +1872,7 @@ mod tests {
             state,
             Msg::JobDone {
                 job_id: 8,
+                at_ms: 0,
                 result: JobResultKind::Success,
                 content_preview: Some("final".to_string()),
                 extracted_links: Vec::new(),
@@ -808,14 +1914,15 @@ mod tests {
         );
 
         let links = vec![
-            "HTTP://EXAMPLE.com".to_string(),
-            "http://example.com/".to_string(),
-            "https://other.example:443/path".to_string(),
+            JobLink::from_url("HTTP://EXAMPLE.com"),
+            JobLink::from_url("http://example.com/"),
+            JobLink::from_url("https://other.example:443/path"),
         ];
         let (state, _) = update(
             state,
             Msg::JobDone {
                 job_id: 9,
+                at_ms: 0,
                 result: JobResultKind::Success,
                 content_preview: None,
                 extracted_links: links,
@@ -826,9 +1933,110 @@ mod tests {
         assert_eq!(
             job.extracted_links(),
             &[
-                "http://example.com/".to_string(),
-                "https://other.example/path".to_string()
+                JobLink::from_url("http://example.com/"),
+                JobLink::from_url("https://other.example/path")
             ]
         );
     }
+
+    #[test]
+    fn view_window_builds_only_the_requested_rows() {
+        let mut state = AppState::new();
+        for id in 1..=5 {
+            state.jobs.insert(
+                id,
+                JobState {
+                    url: format!("https://example.com/{id}"),
+                    pinned: id == 4,
+                    ..Default::default()
+                },
+            );
+        }
+        let ids = |view: &AppViewModel| view.jobs.iter().map(|job| job.job_id).collect::<Vec<_>>();
+
+        let view = state.view_window(1, 2);
+        assert_eq!(view.job_count, 5);
+        assert_eq!(view.jobs_offset, 1);
+        assert_eq!(ids(&view), vec![1, 2]);
+
+        let view = state.view_window(4, 10);
+        assert_eq!(ids(&view), vec![5]);
+        let view = state.view_window(9, 10);
+        assert_eq!((view.jobs_offset, ids(&view)), (5, Vec::new()));
+        assert_eq!(ids(&state.view()), vec![4, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn finished_session_summarizes_outcomes_and_failure_kinds() {
+        let mut state = AppState::new();
+        for (job_id, url) in [(1, "https://a.example"), (2, "https://b.example")] {
+            state.jobs.insert(
+                job_id,
+                JobState {
+                    url: url.to_string(),
+                    ..Default::default()
+                },
+            );
+        }
+        let (state, _) = update(
+            state,
+            Msg::JobProgress {
+                job_id: 1,
+                at_ms: 1_000,
+                stage: Stage::Queued,
+                tokens: None,
+                bytes: None,
+                content_preview: None,
+            },
+        );
+        let (state, _) = update(
+            state,
+            Msg::JobProgress {
+                job_id: 1,
+                at_ms: 2_000,
+                stage: Stage::Writing,
+                tokens: Some(40),
+                bytes: Some(400),
+                content_preview: None,
+            },
+        );
+        let (state, _) = update(
+            state,
+            Msg::JobDone {
+                job_id: 1,
+                at_ms: 3_000,
+                result: JobResultKind::Success,
+                content_preview: None,
+                extracted_links: Vec::new(),
+            },
+        );
+        let (state, _) = update(
+            state,
+            Msg::JobFailureReported {
+                job_id: 2,
+                kind: "http_404".to_string(),
+            },
+        );
+        let (mut state, _) = update(
+            state,
+            Msg::JobDone {
+                job_id: 2,
+                at_ms: 4_500,
+                result: JobResultKind::Failed,
+                content_preview: None,
+                extracted_links: Vec::new(),
+            },
+        );
+        assert_eq!(state.view().session_summary, None);
+
+        state.session = SessionState::Finished;
+        let summary = state.view().session_summary.expect("summary once finished");
+        assert_eq!(
+            (summary.pages, summary.succeeded, summary.failed()),
+            (2, 1, 1)
+        );
+        assert_eq!(summary.failures.get("http_404"), Some(&1));
+        assert_eq!((summary.total_tokens, summary.total_bytes), (40, 400));
+        assert_eq!(summary.duration_ms, Some(3_500));
+    }
 }
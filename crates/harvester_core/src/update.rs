@@ -1,69 +1,75 @@
-use crate::{normalize_url_for_dedupe, AppState, Effect, Msg, SessionState, StopPolicy};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::state::WatchToggle;
+use url::Url;
+
+use crate::{
+    normalize_url_for_dedupe, ActivityEvent, AppState, BudgetLevel, Effect, JobResultKind, Msg,
+    PendingPaste, RejectedUrls, SessionState, StopPolicy, PASTE_CONFIRM_THRESHOLD,
+};
 
 /// Pure update function: applies a message to state and returns any effects.
 pub fn update(mut state: AppState, msg: Msg) -> (AppState, Vec<Effect>) {
-    let effects = match msg {
+    let persist = changes_persisted_state(&msg);
+    let budget_level = state.budget_level();
+    let mut effects = match msg {
         Msg::InputChanged(text) => {
+            if text != state.input_buffer() {
+                state.clear_pending_paste();
+            }
             state.set_input_buffer(text);
             Vec::new()
         }
         Msg::UrlsSubmitted => {
             let raw = state.input_buffer().to_owned();
-            // Phase 0 invariant: when paste handling grows, keep `SessionState::Finishing`
-            // as a strict block (no auto-resume, no new intake) unless gated by a feature flag.
             let urls = parse_urls(&raw);
-            if urls.is_empty() {
-                return (state, Vec::new());
-            }
-            match state.session() {
-                SessionState::Finishing | SessionState::Finished => {
-                    return (state, Vec::new());
-                }
-                SessionState::Idle | SessionState::Running => {}
-            }
-
-            // Phase 4: deduplicate URLs before enqueuing
-            let mut unique_urls = Vec::new();
-            let mut skipped_count = 0;
-            for url in urls {
-                let normalized = normalize_url_for_dedupe(&url);
-                if state.is_url_seen(&normalized) {
-                    skipped_count += 1;
-                } else {
-                    unique_urls.push(url);
-                }
-            }
-
-            // If all URLs were duplicates, we still update stats but don't enqueue or start
-            if unique_urls.is_empty() {
-                state.set_last_paste_stats(0, skipped_count);
-                return (state, Vec::new());
-            }
-
-            let should_start = state.session() == SessionState::Idle;
-            if should_start {
-                state.start_session();
-            }
-
-            state.set_urls(unique_urls);
-            let enqueued = state.enqueue_jobs_from_ui();
-            let enqueued_count = enqueued.len();
-            state.set_last_paste_stats(enqueued_count, skipped_count);
-            if enqueued_count > 0 {
+            let held = state.held_job_count();
+            let (next, effects) = submit_urls(state, urls, source_label(&raw), true);
+            state = next;
+            if state.held_job_count() > held
+                || effects
+                    .iter()
+                    .any(|effect| matches!(effect, Effect::EnqueueUrl { .. }))
+            {
                 state.clear_input_buffer();
             }
-            let mut effects = Vec::with_capacity(enqueued.len() + usize::from(should_start));
-            if should_start {
-                effects.push(Effect::StartSession);
+            effects
+        }
+        Msg::UrlsReceived(urls) => {
+            let label = urls.iter().find_map(|chunk| source_label(chunk));
+            let urls = urls.iter().flat_map(|chunk| parse_urls(chunk)).collect();
+            let (next, effects) = submit_urls(state, urls, label, false);
+            state = next;
+            effects
+        }
+        Msg::PasteConfirmed => match state.take_pending_paste() {
+            Some((urls, paste)) if intake_open(&state) => {
+                let (next, effects) =
+                    enqueue_new_urls(state, urls, paste.rejected, paste.source_batch);
+                state = next;
+                state.clear_input_buffer();
+                effects
             }
-            for (job_id, url) in enqueued {
-                effects.push(Effect::EnqueueUrl { job_id, url });
+            Some((urls, paste)) => {
+                let paste = preview_paste(&state, &urls, paste.rejected, paste.source_batch);
+                state.set_pending_paste(paste, urls);
+                Vec::new()
             }
-            effects
+            None => Vec::new(),
+        },
+        Msg::PasteCancelled => {
+            state.clear_pending_paste();
+            Vec::new()
+        }
+        // While "Stop now" waits for confirmation, Stop backs out of it.
+        Msg::StopFinishClicked if state.stop_now_pending() => {
+            state.set_stop_now_pending(false);
+            Vec::new()
         }
         Msg::StopFinishClicked => {
             if state.session() == SessionState::Running {
                 state.finish_session();
+                state.clear_watches();
                 vec![Effect::StopFinish {
                     policy: StopPolicy::Finish,
                 }]
@@ -71,44 +77,689 @@ pub fn update(mut state: AppState, msg: Msg) -> (AppState, Vec<Effect>) {
                 Vec::new()
             }
         }
-        Msg::ArchiveClicked => vec![Effect::ArchiveRequested],
+        // A second click on "Stop now" is the confirmation.
+        Msg::StopNowClicked if state.stop_now_pending() => stop_now(&mut state),
+        Msg::StopNowClicked => {
+            if matches!(
+                state.session(),
+                SessionState::Running | SessionState::Finishing
+            ) {
+                state.set_stop_now_pending(true);
+            }
+            Vec::new()
+        }
+        Msg::StopNowConfirmed => stop_now(&mut state),
+        Msg::StopNowDismissed => {
+            state.set_stop_now_pending(false);
+            Vec::new()
+        }
+        Msg::AllJobsDrained => {
+            if state.session() == SessionState::Finishing {
+                state.complete_session();
+            }
+            if state.closing() {
+                vec![Effect::Quit]
+            } else {
+                Vec::new()
+            }
+        }
+        Msg::CloseRequested { policy } => {
+            if state.closing() {
+                vec![Effect::Quit]
+            } else {
+                state.begin_close();
+                match state.session() {
+                    SessionState::Running => {
+                        state.finish_session();
+                        state.clear_watches();
+                        vec![Effect::StopFinish { policy }]
+                    }
+                    // A graceful stop is already draining; only cut it short.
+                    SessionState::Finishing => match policy {
+                        StopPolicy::Immediate => vec![Effect::StopFinish { policy }],
+                        StopPolicy::Finish => Vec::new(),
+                    },
+                    SessionState::Idle | SessionState::Finished => vec![Effect::Quit],
+                }
+            }
+        }
+        Msg::NewSessionRequested => match state.session() {
+            SessionState::Idle | SessionState::Finished => {
+                let completed = state.completed_jobs_snapshot();
+                let since_utc = state.last_export_utc().map(ToOwned::to_owned);
+                let had_pins = !state.pinned_urls().is_empty();
+                state.start_new_session();
+                let mut effects = Vec::new();
+                if !completed.is_empty() {
+                    effects.push(Effect::SaveSessionSnapshot {
+                        completed,
+                        since_utc,
+                        min_quality: state.export_min_quality(),
+                    });
+                }
+                if had_pins {
+                    effects.push(Effect::PinnedUrlsChanged { urls: Vec::new() });
+                }
+                effects.push(Effect::PersistState {
+                    session: state.persisted_session(),
+                });
+                effects
+            }
+            SessionState::Running | SessionState::Finishing => Vec::new(),
+        },
+        Msg::ProfilesLoaded { names, active } => {
+            state.set_profiles(names, active);
+            Vec::new()
+        }
+        Msg::ProfileSelected(name) => match state.session() {
+            SessionState::Idle | SessionState::Finished if state.can_switch_profile(&name) => {
+                vec![Effect::SwitchProfile { name }]
+            }
+            _ => Vec::new(),
+        },
+        Msg::ProfileActivated(name) => {
+            state.activate_profile(name);
+            Vec::new()
+        }
+        // One export at a time; further clicks wait for it to finish.
+        Msg::ArchiveClicked | Msg::ArchiveNewClicked if state.export_in_progress() => Vec::new(),
+        Msg::ArchiveClicked => {
+            state.set_export_in_progress(true);
+            vec![Effect::ArchiveRequested {
+                min_quality: state.export_min_quality(),
+            }]
+        }
+        Msg::ArchiveNewClicked => {
+            state.set_export_in_progress(true);
+            vec![Effect::ArchiveNewRequested {
+                since_utc: state.last_export_utc().map(ToOwned::to_owned),
+                min_quality: state.export_min_quality(),
+            }]
+        }
+        Msg::ExportCompleted { exported_utc } => {
+            state.set_export_in_progress(false);
+            state.record_export(exported_utc.clone());
+            state.log_activity(ActivityEvent::ExportCompleted { exported_utc });
+            Vec::new()
+        }
+        Msg::ExportFailed { message } => {
+            state.set_export_in_progress(false);
+            state.log_activity(ActivityEvent::ExportFailed { message });
+            Vec::new()
+        }
+        Msg::RestoreLastExport(exported_utc) => {
+            state.record_export(exported_utc);
+            Vec::new()
+        }
+        Msg::StateRecoveredFromBackup => {
+            state.mark_state_recovered();
+            Vec::new()
+        }
+        Msg::BudgetPresetSelected(budget) | Msg::RestoreBudget(budget) => {
+            state.set_budget(budget);
+            vec![Effect::TokenBudgetChanged {
+                tokens: budget.token_limit(),
+            }]
+        }
+        Msg::ExportMinQualitySelected(min_quality) | Msg::RestoreExportMinQuality(min_quality) => {
+            state.set_export_min_quality(min_quality);
+            Vec::new()
+        }
+        Msg::LogLevelSelected(level) | Msg::RestoreLogLevel(level) => {
+            state.set_log_level(level);
+            vec![Effect::SetLogLevel(level)]
+        }
         Msg::JobProgress {
             job_id,
+            at_ms,
             stage,
             tokens,
             bytes,
             content_preview,
         } => {
-            state.apply_progress(job_id, stage, tokens, bytes, content_preview);
-            Vec::new()
+            state.apply_progress(job_id, at_ms, stage, tokens, bytes, content_preview);
+            release_held_jobs(&mut state)
         }
         Msg::JobDone {
             job_id,
+            at_ms,
             result,
             content_preview,
             extracted_links,
         } => {
-            state.apply_done(job_id, result, content_preview, extracted_links);
+            state.apply_done(job_id, at_ms, result, content_preview, extracted_links);
+            release_held_jobs(&mut state)
+        }
+        Msg::JobFailureReported { job_id, kind } => {
+            state.record_failure(job_id, kind);
+            Vec::new()
+        }
+        Msg::TokenCountRefined { job_id, tokens } => {
+            state.set_job_tokens(job_id, tokens);
+            Vec::new()
+        }
+        Msg::JobRetryScheduled { job_id, delay_secs } => {
+            state.schedule_retry(job_id, delay_secs);
+            Vec::new()
+        }
+        Msg::QueueSaturated => {
+            state.set_intake_paused(true);
+            Vec::new()
+        }
+        Msg::QueueResumed => {
+            state.set_intake_paused(false);
+            release_held_jobs(&mut state)
+        }
+        Msg::QueueCapacity(capacity) => {
+            state.set_queue_capacity(capacity);
+            release_held_jobs(&mut state)
+        }
+        Msg::MemoryThrottled {
+            in_use_bytes,
+            budget_bytes,
+        } => {
+            state.set_memory_throttled(Some((in_use_bytes, budget_bytes)));
+            Vec::new()
+        }
+        Msg::ToggleWatchSelected { interval_secs } => {
+            match state.toggle_watch_selected(interval_secs) {
+                Some(WatchToggle::Started { job_id, url }) => vec![Effect::WatchUrl {
+                    job_id,
+                    url,
+                    interval_secs,
+                }],
+                Some(WatchToggle::Stopped { job_id }) => vec![Effect::UnwatchUrl { job_id }],
+                None => Vec::new(),
+            }
+        }
+        Msg::TogglePinSelected => {
+            if state.toggle_pin_selected() {
+                vec![Effect::PinnedUrlsChanged {
+                    urls: state.pinned_urls(),
+                }]
+            } else {
+                Vec::new()
+            }
+        }
+        Msg::CopySelectedUrl => state
+            .selected_url()
+            .map(|text| vec![Effect::CopyToClipboard { text }])
+            .unwrap_or_default(),
+        Msg::OpenSelectedUrl => state
+            .selected_url()
+            .map(|url| vec![Effect::OpenInBrowser { url }])
+            .unwrap_or_default(),
+        Msg::WatchedPageChanged {
+            watch_id,
+            tokens,
+            bytes,
+            content_preview,
+            extracted_links,
+            change,
+        } => {
+            state.add_changed_job(
+                watch_id,
+                tokens,
+                bytes,
+                content_preview,
+                extracted_links,
+                change,
+            );
+            Vec::new()
+        }
+        Msg::CanonicalUrlResolved {
+            job_id,
+            canonical_url,
+        } => {
+            state.record_canonical_url(job_id, canonical_url);
+            Vec::new()
+        }
+        Msg::QualityAssessed { job_id, score, low } => {
+            state.record_quality(job_id, score, low);
+            Vec::new()
+        }
+        Msg::ReadingMeasured {
+            job_id,
+            words,
+            minutes,
+        } => {
+            state.record_reading(job_id, words, minutes);
+            Vec::new()
+        }
+        Msg::DuplicateDetected { job_id, original } => {
+            state.record_duplicate(job_id, original);
+            Vec::new()
+        }
+        Msg::ContentCompared { job_id, change } => {
+            state.record_content_change(job_id, change);
+            Vec::new()
+        }
+        Msg::DiffModeToggled => {
+            state.toggle_diff_mode();
+            Vec::new()
+        }
+        Msg::LinkKindToggled(kind) => {
+            state.toggle_link_kind(kind);
+            Vec::new()
+        }
+        Msg::LinkScopeToggled(scope) => {
+            state.toggle_link_scope(scope);
+            Vec::new()
+        }
+        Msg::LinkCheckToggled { url } => {
+            state.toggle_link_checked(url);
             Vec::new()
         }
+        Msg::ImageInventoryRequested => {
+            let images = state.image_inventory();
+            if images.is_empty() {
+                Vec::new()
+            } else {
+                vec![Effect::ExportImageInventory { images }]
+            }
+        }
+        Msg::HarvestCheckedLinks => {
+            let urls = state.take_checked_links();
+            let (next, effects) = submit_urls(state, urls, None, false);
+            state = next;
+            effects
+        }
         Msg::JobSelected { job_id } => {
             state.select_job(job_id);
             Vec::new()
         }
+        Msg::RestorePanelWidths(widths) => {
+            state.set_panel_widths(widths);
+            Vec::new()
+        }
+        Msg::SplitterDragged {
+            splitter,
+            desired_left_width_px,
+        }
+        | Msg::SplitterReleased {
+            splitter,
+            desired_left_width_px,
+        } => {
+            state.drag_splitter(splitter, desired_left_width_px);
+            Vec::new()
+        }
+        Msg::WindowResized { window_width } => {
+            state.set_window_width(window_width);
+            Vec::new()
+        }
+        Msg::WindowGeometryChanged(geometry) => {
+            state.set_window_geometry(geometry);
+            Vec::new()
+        }
+        Msg::JobColumnClicked(column) => {
+            state.sort_jobs_by(column);
+            Vec::new()
+        }
+        Msg::SelectNextJob => {
+            state.select_adjacent(1);
+            Vec::new()
+        }
+        Msg::SelectPrevJob => {
+            state.select_adjacent(-1);
+            Vec::new()
+        }
+        Msg::SelectFirstFailed => {
+            state.select_first_failed();
+            Vec::new()
+        }
         Msg::RestoreCompletedJobs(entries) => {
             state.restore_completed_jobs(entries);
-            Vec::new()
+            let urls = state.pinned_urls();
+            if urls.is_empty() {
+                Vec::new()
+            } else {
+                vec![Effect::PinnedUrlsChanged { urls }]
+            }
         }
         Msg::Tick | Msg::NoOp => Vec::new(),
     };
+    if state.budget_level() > budget_level {
+        effects.extend(budget_level_reached(&mut state));
+    }
 
+    let quit_at = effects.iter().position(|effect| *effect == Effect::Quit);
+    if persist || quit_at.is_some() {
+        let session = state.persisted_session();
+        effects.insert(
+            quit_at.unwrap_or(effects.len()),
+            Effect::PersistState { session },
+        );
+    }
     (state, effects)
 }
 
+/// Cancel the running and queued jobs once "Stop now" was confirmed.
+fn stop_now(state: &mut AppState) -> Vec<Effect> {
+    let stoppable = matches!(
+        state.session(),
+        SessionState::Running | SessionState::Finishing
+    );
+    if !state.stop_now_pending() || !stoppable {
+        return Vec::new();
+    }
+    state.set_stop_now_pending(false);
+    state.finish_session();
+    state.clear_watches();
+    vec![Effect::StopFinish {
+        policy: StopPolicy::Immediate,
+    }]
+}
+
+/// Log the budget level the session just reached, and stop a running harvest
+/// once the limit is reached. Running jobs finish; queued ones are cancelled.
+fn budget_level_reached(state: &mut AppState) -> Vec<Effect> {
+    let level = state.budget_level();
+    state.log_activity(ActivityEvent::BudgetLevelReached {
+        level,
+        tokens: state.total_tokens(),
+        limit: state.budget().token_limit(),
+    });
+    if level != BudgetLevel::Exceeded || state.session() != SessionState::Running {
+        return Vec::new();
+    }
+    state.finish_session();
+    state.clear_watches();
+    vec![Effect::StopFinish {
+        policy: StopPolicy::Finish,
+    }]
+}
+
+/// Messages after which the session state file is rewritten.
+fn changes_persisted_state(msg: &Msg) -> bool {
+    matches!(
+        msg,
+        Msg::JobDone {
+            result: JobResultKind::Success | JobResultKind::FromCache,
+            ..
+        } | Msg::ExportCompleted { .. }
+            | Msg::WatchedPageChanged { .. }
+            | Msg::TokenCountRefined { .. }
+            | Msg::BudgetPresetSelected(_)
+            | Msg::LogLevelSelected(_)
+            | Msg::ExportMinQualitySelected(_)
+            | Msg::TogglePinSelected
+            | Msg::SplitterReleased { .. }
+    )
+}
+
+/// Shared intake path for pasted and externally received URLs, whose jobs
+/// are labeled `source_batch`. Pastes with more than
+/// [`PASTE_CONFIRM_THRESHOLD`] new URLs are held back when `confirm_large`
+/// is set.
+fn submit_urls(
+    mut state: AppState,
+    urls: Vec<String>,
+    source_batch: Option<String>,
+    confirm_large: bool,
+) -> (AppState, Vec<Effect>) {
+    if urls.is_empty() || !intake_open(&state) {
+        return (state, Vec::new());
+    }
+
+    let (urls, rejected) = validate_urls(&state, urls);
+    if confirm_large {
+        let paste = preview_paste(&state, &urls, rejected, source_batch.clone());
+        if paste.count > PASTE_CONFIRM_THRESHOLD {
+            state.set_pending_paste(paste, urls);
+            return (state, Vec::new());
+        }
+    }
+    enqueue_new_urls(state, urls, rejected, source_batch)
+}
+
+fn intake_open(state: &AppState) -> bool {
+    // Phase 0 invariant: when paste handling grows, keep `SessionState::Finishing`
+    // as a strict block (no auto-resume, no new intake) unless gated by a feature flag.
+    let accepting = matches!(state.session(), SessionState::Idle | SessionState::Running);
+    // While intake is paused the input buffer is kept, so the paste can be
+    // resubmitted once the queue drains.
+    accepting && !state.closing() && !state.intake_paused()
+}
+
+/// What enqueuing `urls` would do, without recording any of them as seen.
+fn preview_paste(
+    state: &AppState,
+    urls: &[String],
+    rejected: RejectedUrls,
+    source_batch: Option<String>,
+) -> PendingPaste {
+    let mut fresh = HashSet::new();
+    let mut domains: BTreeMap<String, usize> = BTreeMap::new();
+    let mut duplicates = 0;
+    for url in urls {
+        let normalized = normalize_url_for_dedupe(url);
+        if state.has_seen_url(&normalized) || !fresh.insert(normalized) {
+            duplicates += 1;
+            continue;
+        }
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_ascii_lowercase))
+            .unwrap_or_default();
+        *domains.entry(host).or_default() += 1;
+    }
+    let mut domains: Vec<(String, usize)> = domains.into_iter().collect();
+    domains.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    PendingPaste {
+        count: fresh.len(),
+        duplicates,
+        domains,
+        rejected,
+        source_batch,
+    }
+}
+
+/// Enqueue the `urls` not seen yet and start the session if it is idle.
+fn enqueue_new_urls(
+    mut state: AppState,
+    urls: Vec<String>,
+    rejected: RejectedUrls,
+    source_batch: Option<String>,
+) -> (AppState, Vec<Effect>) {
+    // Phase 4: deduplicate URLs before enqueuing
+    let mut unique_urls = Vec::new();
+    let mut skipped_count = 0;
+    for url in urls {
+        let normalized = normalize_url_for_dedupe(&url);
+        if state.is_url_seen(&normalized) {
+            skipped_count += 1;
+        } else {
+            unique_urls.push(url);
+        }
+    }
+
+    // If all URLs were duplicates, we still update stats but don't enqueue or start
+    if unique_urls.is_empty() {
+        state.set_last_paste_stats(0, skipped_count, rejected);
+        return (state, Vec::new());
+    }
+
+    let should_start = state.session() == SessionState::Idle;
+    if should_start {
+        state.start_session();
+    }
+
+    state.set_urls(unique_urls);
+    let enqueued = state.enqueue_jobs_from_ui(source_batch.as_deref());
+    state.set_last_paste_stats(enqueued.len(), skipped_count, rejected);
+    // Jobs past the engine's queue capacity stay here until it drains.
+    state.hold_jobs(enqueued.into_iter().map(|(job_id, _)| job_id));
+    let mut effects = Vec::new();
+    if should_start {
+        effects.push(Effect::StartSession);
+    }
+    effects.extend(release_held_jobs(&mut state));
+    (state, effects)
+}
+
+/// Send the engine as many held jobs as its queue has room for.
+fn release_held_jobs(state: &mut AppState) -> Vec<Effect> {
+    state
+        .release_held_jobs()
+        .into_iter()
+        .map(|(job_id, url, source_batch)| Effect::EnqueueUrl {
+            job_id,
+            url,
+            source_batch,
+        })
+        .collect()
+}
+
+/// Keep lines that parse as URLs with an allowed scheme; count the rest by reason.
+fn validate_urls(state: &AppState, urls: Vec<String>) -> (Vec<String>, RejectedUrls) {
+    let mut rejected = RejectedUrls::default();
+    let accepted = urls
+        .into_iter()
+        .filter(|url| match Url::parse(url) {
+            Ok(parsed) if state.is_scheme_allowed(parsed.scheme()) => true,
+            Ok(_) => {
+                rejected.unsupported_scheme += 1;
+                false
+            }
+            Err(url::ParseError::RelativeUrlWithoutBase) => {
+                rejected.missing_scheme += 1;
+                false
+            }
+            Err(_) => {
+                rejected.malformed += 1;
+                false
+            }
+        })
+        .collect();
+    (accepted, rejected)
+}
+
 fn parse_urls(raw: &str) -> Vec<String> {
     raw.lines()
         .map(str::trim)
-        .filter(|line| !line.is_empty())
-        .map(ToOwned::to_owned)
+        .filter(|line| !line.is_empty() && source_directive(line).is_none())
+        .flat_map(|line| match local_path_url(line) {
+            Some(url) => vec![url],
+            None => expand_url_pattern(line),
+        })
         .collect()
 }
+
+/// Label of a paste, from its first `# source: <label>` line.
+fn source_label(raw: &str) -> Option<String> {
+    raw.lines()
+        .find_map(|line| source_directive(line.trim()))
+        .filter(|label| !label.is_empty())
+        .map(str::to_owned)
+}
+
+fn source_directive(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix('#')?.trim_start();
+    let (key, label) = rest.split_once(':')?;
+    key.trim()
+        .eq_ignore_ascii_case("source")
+        .then(|| label.trim())
+}
+
+/// URLs one pasted pattern expands to at most; the rest are dropped.
+const MAX_PATTERN_EXPANSION: usize = 1_000;
+
+/// Every combination of the `[1-20]` ranges and `{intro,setup,api}` lists in
+/// a pasted line, in order. Ranges keep the zero padding of their start, as
+/// in `[01-12]`. Lines without a pattern come back as they are.
+fn expand_url_pattern(line: &str) -> Vec<String> {
+    let mut expanded = vec![String::new()];
+    let mut rest = line;
+    while let Some((before, alternatives, after)) = next_pattern(rest) {
+        expanded = expanded
+            .iter()
+            .flat_map(|prefix| {
+                alternatives
+                    .iter()
+                    .map(move |alternative| format!("{prefix}{before}{alternative}"))
+            })
+            .take(MAX_PATTERN_EXPANSION)
+            .collect();
+        rest = after;
+    }
+    for url in &mut expanded {
+        url.push_str(rest);
+    }
+    expanded
+}
+
+/// The text before the first pattern in `text`, its alternatives, and the
+/// text after it. Brackets that are no range, such as an IPv6 host, are
+/// skipped.
+fn next_pattern(text: &str) -> Option<(&str, Vec<String>, &str)> {
+    let mut search = 0;
+    while let Some(offset) = text[search..].find(['[', '{']) {
+        let open = search + offset;
+        let is_range = text.as_bytes()[open] == b'[';
+        let close = if is_range { ']' } else { '}' };
+        if let Some(len) = text[open + 1..].find(close) {
+            let inner = &text[open + 1..open + 1 + len];
+            let alternatives = if is_range {
+                range_alternatives(inner)
+            } else {
+                list_alternatives(inner)
+            };
+            if let Some(alternatives) = alternatives {
+                return Some((&text[..open], alternatives, &text[open + len + 2..]));
+            }
+        }
+        search = open + 1;
+    }
+    None
+}
+
+/// `1-20` or `01-20`.
+fn range_alternatives(inner: &str) -> Option<Vec<String>> {
+    let (start, end) = inner.split_once('-')?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_number(start) || !is_number(end) {
+        return None;
+    }
+    let width = if start.len() > 1 && start.starts_with('0') {
+        start.len()
+    } else {
+        0
+    };
+    let (start, end): (u64, u64) = (start.parse().ok()?, end.parse().ok()?);
+    (start <= end).then(|| {
+        (start..=end)
+            .take(MAX_PATTERN_EXPANSION)
+            .map(|n| format!("{n:0width$}"))
+            .collect()
+    })
+}
+
+/// `intro,setup,api`; a single item is no list.
+fn list_alternatives(inner: &str) -> Option<Vec<String>> {
+    inner
+        .contains(',')
+        .then(|| inner.split(',').map(str::to_owned).collect())
+}
+
+/// `file://` URL for an absolute local path (`C:\dir\page.html`,
+/// `\\server\share\page.html` or `/home/me/page.html`), as pasted or
+/// dropped from a file manager, optionally in double quotes.
+fn local_path_url(line: &str) -> Option<String> {
+    let path = line.trim_matches('"');
+    let bytes = path.as_bytes();
+    let url = if bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/')
+    {
+        format!("file:///{}", path.replace('\\', "/"))
+    } else if let Some(unc) = path.strip_prefix("\\\\") {
+        format!("file://{}", unc.replace('\\', "/"))
+    } else if path.starts_with('/') && !path.starts_with("//") {
+        format!("file://{path}")
+    } else {
+        return None;
+    };
+    // Parsing percent-encodes spaces and other characters paths allow.
+    Url::parse(&url).ok().map(String::from)
+}
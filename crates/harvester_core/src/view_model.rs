@@ -1,37 +1,242 @@
-use crate::{JobId, JobResultKind, SessionState, Stage};
+use std::collections::BTreeMap;
 
-pub const TOKEN_LIMIT: u64 = 200_000;
+use crate::{
+    BudgetLevel, BudgetPreset, JobId, JobLink, JobResultKind, JobSort, LinkKind, LinkScope,
+    LogLevel, PanelWidths, SessionState, Stage,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LastPasteStats {
     pub enqueued: usize,
     pub skipped: usize,
+    pub rejected: RejectedUrls,
+}
+
+/// A paste of more than [`crate::PASTE_CONFIRM_THRESHOLD`] new URLs; no job
+/// is created until the user confirms it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PendingPaste {
+    /// New URLs that would be enqueued.
+    pub count: usize,
+    /// Pasted URLs already seen this session or repeated within the paste.
+    pub duplicates: usize,
+    /// Hosts of the new URLs with their counts, most frequent first.
+    pub domains: Vec<(String, usize)>,
+    pub rejected: RejectedUrls,
+    /// Label from the paste's `# source:` line.
+    pub source_batch: Option<String>,
+}
+
+/// Pasted lines that were not accepted as URLs, by reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RejectedUrls {
+    /// Parsed, but the scheme is not allowed (`file:`, `javascript:`, ...).
+    pub unsupported_scheme: usize,
+    /// No scheme at all, e.g. a bare `example.com`.
+    pub missing_scheme: usize,
+    /// Not parseable as a URL.
+    pub malformed: usize,
+}
+
+impl RejectedUrls {
+    pub fn total(&self) -> usize {
+        self.unsupported_scheme + self.missing_scheme + self.malformed
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PreviewHeaderView {
     pub domain: String,
     pub tokens: Option<u32>,
     pub bytes: Option<u64>,
     pub stage: Stage,
     pub outcome: Option<JobResultKind>,
+    /// Words in the written document and minutes it takes to read.
+    pub reading: Option<(u32, u32)>,
     pub heading_count: usize,
     pub link_density: f64,
     pub nav_heavy: bool,
+    pub watched: bool,
+    pub pinned: bool,
+    pub change: Option<ChangeSummary>,
+    /// Label of the paste or batch the job came from.
+    pub source_batch: Option<String>,
+}
+
+/// Extracted links of the selected job, filtered by kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinksPaneView {
+    pub job_id: JobId,
+    /// One filter per link kind, in `LinkKind::ALL` order.
+    pub filters: Vec<LinkFilterView>,
+    /// Internal and external filters, in `LinkScope::ALL` order; they apply
+    /// together with the kind filters.
+    pub scopes: Vec<LinkScopeFilterView>,
+    /// Links of the kinds currently shown.
+    pub links: Vec<JobLink>,
+    /// URLs of the shown links checked for "Harvest selected".
+    pub checked: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkFilterView {
+    pub kind: LinkKind,
+    /// Links of this kind, shown or not.
+    pub count: usize,
+    pub shown: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinkScopeFilterView {
+    pub scope: LinkScope,
+    /// Links of this scope, shown or not.
+    pub count: usize,
+    pub shown: bool,
+}
+
+/// Line counts of a diff against the previous harvest of the same document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeSummary {
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl ChangeSummary {
+    pub fn is_unchanged(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// Totals shown once the session has finished.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionSummary {
+    /// Jobs that are done, whatever their outcome.
+    pub pages: usize,
+    /// Jobs with a full document, fetched or from the response cache.
+    pub succeeded: usize,
+    pub from_cache: usize,
+    /// Jobs whose document looks like a paywall or consent wall.
+    pub partial: usize,
+    /// Jobs cut short by "Stop now".
+    pub cancelled: usize,
+    /// Failed jobs by the kind the engine reported; `unknown` when it gave none.
+    pub failures: BTreeMap<String, usize>,
+    pub total_tokens: u64,
+    pub total_bytes: u64,
+    /// From the first job being enqueued to the last one finishing.
+    pub duration_ms: Option<u64>,
+}
+
+impl SessionSummary {
+    pub fn failed(&self) -> usize {
+        self.failures.values().sum()
+    }
+}
+
+/// Something that just happened in the session, shown in the activity ticker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ActivityEvent {
+    /// The engine picked the job up.
+    JobStarted {
+        job_id: JobId,
+        url: String,
+    },
+    /// The job wrote a document.
+    JobFinished {
+        job_id: JobId,
+        url: String,
+        result: JobResultKind,
+    },
+    JobFailed {
+        job_id: JobId,
+        url: String,
+    },
+    /// "Stop now" cancelled the job.
+    JobCancelled {
+        job_id: JobId,
+        url: String,
+    },
+    ExportCompleted {
+        exported_utc: String,
+    },
+    ExportFailed {
+        message: String,
+    },
+    /// The session's tokens reached a new [`BudgetLevel`].
+    BudgetLevelReached {
+        level: BudgetLevel,
+        tokens: u64,
+        limit: u64,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppViewModel {
     pub session: SessionState,
     pub queued_urls: Vec<String>,
     pub job_count: usize,
+    /// List row of `jobs[0]`; non-zero only for windowed views.
+    pub jobs_offset: usize,
     pub jobs: Vec<JobRowView>,
     pub last_paste_stats: Option<LastPasteStats>,
+    /// A large paste waiting for `Msg::PasteConfirmed`.
+    pub pending_paste: Option<PendingPaste>,
     pub dirty: bool,
     pub total_tokens: u64,
+    /// Size of all fetched pages.
+    pub total_bytes: u64,
+    /// The active budget preset; `token_limit` is its token count.
+    pub budget: BudgetPreset,
     pub token_limit: u64,
+    /// How close `total_tokens` is to `token_limit`.
+    pub budget_level: BudgetLevel,
+    /// The level the platform logs at.
+    pub log_level: LogLevel,
+    /// Quality score exports require; `None` keeps the profile's threshold.
+    pub export_min_quality: Option<u8>,
+    pub panel_widths: PanelWidths,
+    /// The selected job, in list order; drives the preview.
+    pub selected_job_id: Option<JobId>,
+    /// Column the job rows are sorted by; `None` keeps enqueue order.
+    pub job_sort: Option<JobSort>,
     pub preview_text: Option<String>,
     pub preview_header: Option<PreviewHeaderView>,
+    /// `preview_text` is the diff against the previous harvest when one exists.
+    pub diff_mode: bool,
+    /// Links of the selected job.
+    pub links_pane: Option<LinksPaneView>,
+    /// Engine queue is saturated; new pastes are held back.
+    pub intake_paused: bool,
+    /// Bytes in use and the budget, while the engine defers new jobs because
+    /// its memory budget is spent.
+    pub memory_throttled: Option<(u64, u64)>,
+    /// "Stop now" waits for confirmation before cancelling jobs.
+    pub stop_now_pending: bool,
+    /// The window was closed; jobs are being stopped before quitting.
+    pub closing: bool,
+    /// The session was restored from the backup state file because the
+    /// primary one was corrupt.
+    pub state_recovered: bool,
+    /// An export is being written; Archive stays disabled until it is done.
+    pub export_in_progress: bool,
+    /// The most recent events, oldest first.
+    pub activity_log: Vec<ActivityEvent>,
+    /// Set once the session is `Finished`.
+    pub session_summary: Option<SessionSummary>,
+    /// Output profiles the user can switch between.
+    pub profiles: Vec<String>,
+    pub active_profile: Option<String>,
 }
 
 impl Default for AppViewModel {
@@ -40,18 +245,41 @@ impl Default for AppViewModel {
             session: SessionState::Idle,
             queued_urls: Vec::new(),
             job_count: 0,
+            jobs_offset: 0,
             jobs: Vec::new(),
             last_paste_stats: None,
+            pending_paste: None,
             dirty: false,
             total_tokens: 0,
-            token_limit: TOKEN_LIMIT,
+            total_bytes: 0,
+            budget: BudgetPreset::default(),
+            token_limit: BudgetPreset::default().token_limit(),
+            budget_level: BudgetLevel::Normal,
+            log_level: LogLevel::default(),
+            export_min_quality: None,
+            panel_widths: PanelWidths::default(),
+            selected_job_id: None,
+            job_sort: None,
             preview_text: None,
             preview_header: None,
+            diff_mode: false,
+            links_pane: None,
+            intake_paused: false,
+            memory_throttled: None,
+            stop_now_pending: false,
+            closing: false,
+            state_recovered: false,
+            export_in_progress: false,
+            activity_log: Vec::new(),
+            session_summary: None,
+            profiles: Vec::new(),
+            active_profile: None,
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct JobRowView {
     pub job_id: JobId,
     pub url: String,
@@ -59,4 +287,30 @@ pub struct JobRowView {
     pub outcome: Option<JobResultKind>,
     pub tokens: Option<u32>,
     pub bytes: Option<u64>,
+    /// The page is re-fetched on a schedule.
+    pub watched: bool,
+    /// This row is a new version of the watched job it names.
+    pub changed_from: Option<JobId>,
+    /// Same content as this earlier job's document; no second file was written.
+    pub duplicate_of: Option<JobId>,
+    /// Diff against the previous harvest; `None` when there was nothing to compare.
+    pub change: Option<ChangeSummary>,
+    /// Set while the job waits out a rate limit before being fetched again.
+    pub retry_in_secs: Option<u64>,
+    /// Content quality score (0-100) from the engine.
+    pub quality_score: Option<u8>,
+    /// The engine judged the capture low quality (thin, boilerplate-heavy).
+    pub low_quality: bool,
+    /// The captured text is mostly navigation links.
+    pub nav_heavy: bool,
+    /// Words in the written document, independent of the token counter.
+    pub word_count: Option<u32>,
+    /// Estimated minutes to read the document.
+    pub reading_minutes: Option<u32>,
+    /// Wall-clock time since the job started; final once it is done.
+    pub elapsed_ms: Option<u64>,
+    /// Listed before unpinned jobs and always part of fitted exports.
+    pub pinned: bool,
+    /// Why the job failed, as named by the engine.
+    pub failure: Option<String>,
 }
@@ -0,0 +1,47 @@
+//! JS-facing adapter for browser/Tauri front-ends.
+//!
+//! Messages, effects and view models cross the boundary as JSON so the host
+//! only needs `JSON.parse`/`JSON.stringify`; no glue types are generated.
+//!
+//! The crate stays an rlib; build the module with `just wasm`, i.e.
+//! `cargo rustc -p harvester_core --crate-type cdylib --features wasm
+//! --target wasm32-unknown-unknown --release`.
+use wasm_bindgen::prelude::*;
+
+use crate::{update, AppState, Msg};
+
+/// Owns an [`AppState`] and drives it with serialized [`Msg`] values.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmCore {
+    state: AppState,
+}
+
+#[wasm_bindgen]
+impl WasmCore {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a JSON-encoded `Msg` and return the resulting effects as a JSON array.
+    pub fn dispatch(&mut self, msg_json: &str) -> Result<String, JsError> {
+        let msg: Msg = serde_json::from_str(msg_json)?;
+        let state = std::mem::take(&mut self.state);
+        let (next, effects) = update(state, msg);
+        self.state = next;
+        Ok(serde_json::to_string(&effects)?)
+    }
+
+    /// Current view model as JSON.
+    #[wasm_bindgen(js_name = viewModel)]
+    pub fn view_model(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.state.view())?)
+    }
+
+    /// Returns `true` once per state change so the host can coalesce renders.
+    #[wasm_bindgen(js_name = consumeDirty)]
+    pub fn consume_dirty(&mut self) -> bool {
+        self.state.consume_dirty()
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex};
+
+use harvester_core::{
+    intercept_effects, update, AppState, Effect, EffectInterceptor, Msg, StopPolicy,
+};
+
+fn submit_urls(input: &str) -> Vec<Effect> {
+    let (state, _) = update(AppState::new(), Msg::InputChanged(input.to_string()));
+    update(state, Msg::UrlsSubmitted).1
+}
+
+#[test]
+fn interceptors_observe_rewrite_and_swallow_in_order() {
+    let effects = submit_urls("https://a.example\nhttps://b.example\n");
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let observed = seen.clone();
+    let mut interceptors: Vec<Box<dyn EffectInterceptor>> = vec![
+        // Dry run: drop the second fetch.
+        Box::new(|effect: Effect| match effect {
+            Effect::EnqueueUrl { job_id: 2, .. } => Vec::new(),
+            other => vec![other],
+        }),
+        // Rewrite: every enqueue also stops the session.
+        Box::new(|effect: Effect| match effect {
+            Effect::EnqueueUrl { .. } => vec![
+                effect,
+                Effect::StopFinish {
+                    policy: StopPolicy::Finish,
+                },
+            ],
+            other => vec![other],
+        }),
+        // Instrumentation sees what survived the interceptors before it.
+        Box::new(move |effect: Effect| {
+            observed.lock().unwrap().push(effect.clone());
+            vec![effect]
+        }),
+    ];
+
+    let out = intercept_effects(&mut interceptors, effects);
+
+    assert!(!out
+        .iter()
+        .any(|effect| matches!(effect, Effect::EnqueueUrl { job_id: 2, .. })));
+    let stops = out
+        .iter()
+        .filter(|effect| matches!(effect, Effect::StopFinish { .. }))
+        .count();
+    assert_eq!(stops, 1);
+    assert_eq!(*seen.lock().unwrap(), out);
+    assert_eq!(intercept_effects(&mut [], out.clone()), out);
+}
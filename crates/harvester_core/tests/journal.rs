@@ -0,0 +1,37 @@
+#![cfg(feature = "journal")]
+
+use harvester_core::{
+    journal_line, read_journal, replay, update, AppState, JobLink, JobResultKind, Msg,
+};
+
+#[test]
+fn replaying_a_journal_rebuilds_the_recorded_state() {
+    let msgs = vec![
+        Msg::InputChanged("https://a.example\nhttps://b.example".to_string()),
+        Msg::UrlsSubmitted,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: JobResultKind::Success,
+            content_preview: Some("# A".to_string()),
+            extracted_links: vec![JobLink::from_url("https://c.example")],
+        },
+        Msg::JobSelected { job_id: 1 },
+    ];
+    let mut live = AppState::new();
+    let mut journal = String::new();
+    for msg in &msgs {
+        journal.push_str(&journal_line(msg));
+        journal.push('\n');
+        live = update(live, msg.clone()).0;
+    }
+
+    let recorded = read_journal(&journal).unwrap();
+    assert_eq!(recorded, msgs);
+    let (replayed, effects) = replay(AppState::new(), recorded);
+    assert_eq!(replayed, live);
+    assert_eq!(effects.len(), 4);
+
+    let err = read_journal("\"UrlsSubmitted\"\n\n{not json\n").unwrap_err();
+    assert_eq!(err.line, 3);
+}
@@ -25,6 +25,7 @@ fn completed_jobs_can_be_restored_for_resume() {
         state,
         Msg::JobProgress {
             job_id,
+            at_ms: 0,
             stage: Stage::Tokenizing,
             tokens: Some(42),
             bytes: Some(1234),
@@ -35,6 +36,7 @@ fn completed_jobs_can_be_restored_for_resume() {
         state,
         Msg::JobDone {
             job_id,
+            at_ms: 0,
             result: JobResultKind::Success,
             content_preview: None,
             extracted_links: Vec::new(),
@@ -55,6 +57,43 @@ fn completed_jobs_can_be_restored_for_resume() {
     assert_eq!(view.jobs[0].stage, Stage::Done);
 }
 
+#[test]
+fn restoring_jobs_after_a_profile_switch_keeps_job_ids_unique() {
+    init_logging();
+    let (state, effects) = submit_urls(AppState::new(), "https://example.com\n");
+    let first_id = effects
+        .iter()
+        .find_map(|effect| match effect {
+            Effect::EnqueueUrl { job_id, .. } => Some(*job_id),
+            _ => None,
+        })
+        .expect("enqueue effect");
+
+    let (state, _) = update(state, Msg::ProfileActivated("work".to_string()));
+    let (state, _) = update(
+        state,
+        Msg::RestoreCompletedJobs(vec![CompletedJobSnapshot {
+            url: "https://restored.example".to_string(),
+            tokens: None,
+            bytes: None,
+            links: Vec::new(),
+            pinned: false,
+        }]),
+    );
+    let restored_id = state.view().jobs[0].job_id;
+    assert!(restored_id > first_id);
+
+    let (_, effects) = submit_urls(state, "https://fresh.example\n");
+    let fresh_id = effects
+        .iter()
+        .find_map(|effect| match effect {
+            Effect::EnqueueUrl { job_id, .. } => Some(*job_id),
+            _ => None,
+        })
+        .expect("enqueue effect");
+    assert!(fresh_id > restored_id);
+}
+
 #[test]
 fn restored_jobs_are_deduped_on_paste() {
     init_logging();
@@ -65,6 +104,7 @@ fn restored_jobs_are_deduped_on_paste() {
             tokens: None,
             bytes: None,
             links: Vec::new(),
+            pinned: false,
         }]),
     );
 
@@ -72,3 +112,13 @@ fn restored_jobs_are_deduped_on_paste() {
     assert_eq!(next.view().job_count, 1);
     assert!(effects.is_empty());
 }
+
+#[test]
+fn recovery_from_backup_is_surfaced_in_the_view() {
+    init_logging();
+    assert!(!AppState::new().view().state_recovered);
+
+    let (state, effects) = update(AppState::new(), Msg::StateRecoveredFromBackup);
+    assert!(effects.is_empty());
+    assert!(state.view().state_recovered);
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "serde")]
+
+use harvester_core::{update, AppState, AppViewModel, Effect, Msg};
+
+#[test]
+fn msg_and_effects_round_trip_through_json() {
+    let msgs: Vec<Msg> = vec![
+        serde_json::from_str(r#"{"InputChanged":"https://example.com/a"}"#).unwrap(),
+        serde_json::from_str(r#""UrlsSubmitted""#).unwrap(),
+    ];
+
+    let mut state = AppState::new();
+    let mut effects = Vec::new();
+    for msg in msgs {
+        let (next, mut out) = update(state, msg);
+        state = next;
+        effects.append(&mut out);
+    }
+
+    let json = serde_json::to_string(&effects).unwrap();
+    let decoded: Vec<Effect> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, effects);
+    assert!(decoded.iter().any(
+        |effect| matches!(effect, Effect::EnqueueUrl { url, .. } if url == "https://example.com/a")
+    ));
+}
+
+#[test]
+fn view_model_serializes_for_js_front_ends() {
+    let view = AppState::new().view();
+    let json = serde_json::to_string(&view).unwrap();
+    let decoded: AppViewModel = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, view);
+    assert!(json.contains(r#""session":"Idle""#));
+}
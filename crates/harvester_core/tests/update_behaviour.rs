@@ -1,17 +1,44 @@
 use std::sync::Once;
 
-use harvester_core::{update, AppState, Effect, Msg, SessionState, StopPolicy};
+use harvester_core::{
+    update, ActivityEvent, AppState, AppViewModel, BudgetLevel, BudgetPreset, ChangeSummary,
+    CompletedJobSnapshot, ContentChange, Effect, ImageRef, JobColumn, JobLink, JobResultKind,
+    JobSort, LinkKind, LinkScope, LogLevel, Msg, PanelWidths, RejectedUrls, SessionState, Splitter,
+    Stage, StopPolicy,
+};
 
 fn init_logging() {
     static INIT: Once = Once::new();
     INIT.call_once(engine_logging::initialize_for_tests);
 }
 
+fn persist(state: &AppState) -> Effect {
+    Effect::PersistState {
+        session: state.persisted_session(),
+    }
+}
+
 fn submit_urls(state: AppState, input: &str) -> (AppState, Vec<Effect>) {
     let (state, _) = update(state, Msg::InputChanged(input.to_string()));
     update(state, Msg::UrlsSubmitted)
 }
 
+#[test]
+fn picked_log_level_is_applied_and_saved_but_a_restored_one_is_not_saved() {
+    init_logging();
+    let (state, effects) = update(AppState::new(), Msg::LogLevelSelected(LogLevel::Debug));
+    assert_eq!(state.view().log_level, LogLevel::Debug);
+    assert_eq!(state.persisted_session().log_level, LogLevel::Debug);
+    assert_eq!(
+        effects,
+        vec![Effect::SetLogLevel(LogLevel::Debug), persist(&state)]
+    );
+
+    let (state, effects) = update(state, Msg::RestoreLogLevel(LogLevel::Warn));
+    assert_eq!(state.view().log_level, LogLevel::Warn);
+    assert_eq!(effects, vec![Effect::SetLogLevel(LogLevel::Warn)]);
+}
+
 #[test]
 fn urls_pasted_trims_and_ignores_empty() {
     init_logging();
@@ -32,10 +59,12 @@ fn urls_pasted_trims_and_ignores_empty() {
             Effect::EnqueueUrl {
                 job_id: 1,
                 url: "https://a.example.com".to_string(),
+                source_batch: None,
             },
             Effect::EnqueueUrl {
                 job_id: 2,
                 url: "https://b.example.com".to_string(),
+                source_batch: None,
             },
         ]
     );
@@ -71,6 +100,263 @@ fn stop_finish_emits_effect() {
     );
 }
 
+#[test]
+fn second_stop_now_click_confirms_and_stop_dismisses() {
+    init_logging();
+    let (state, _effects) = submit_urls(AppState::new(), "https://example.com\n");
+
+    let (state, _effects) = update(state, Msg::StopNowClicked);
+    let (state, effects) = update(state, Msg::StopFinishClicked);
+    assert!(
+        effects.is_empty(),
+        "Stop only backs out of the confirmation"
+    );
+    assert!(!state.view().stop_now_pending);
+    assert_eq!(state.view().session, SessionState::Running);
+
+    let (state, _effects) = update(state, Msg::StopNowClicked);
+    let (state, effects) = update(state, Msg::StopNowClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::StopFinish {
+            policy: StopPolicy::Immediate
+        }]
+    );
+    assert!(!state.view().stop_now_pending);
+}
+
+#[test]
+fn stop_now_asks_for_confirmation_before_cancelling() {
+    init_logging();
+    let state = AppState::new();
+    let (state, _effects) = submit_urls(state, "https://example.com\n");
+
+    let (state, effects) = update(state, Msg::StopNowConfirmed);
+    assert!(effects.is_empty(), "nothing to confirm yet");
+
+    let (state, effects) = update(state, Msg::StopNowClicked);
+    assert!(effects.is_empty());
+    assert!(state.view().stop_now_pending);
+    assert_eq!(state.view().session, SessionState::Running);
+
+    let (state, _effects) = update(state, Msg::StopNowDismissed);
+    assert!(!state.view().stop_now_pending);
+
+    // A graceful stop can still be cut short.
+    let (state, _effects) = update(state, Msg::StopFinishClicked);
+    let (state, _effects) = update(state, Msg::StopNowClicked);
+    let (state, effects) = update(state, Msg::StopNowConfirmed);
+    assert_eq!(
+        effects,
+        vec![Effect::StopFinish {
+            policy: StopPolicy::Immediate
+        }]
+    );
+    assert!(!state.view().stop_now_pending);
+    assert_eq!(state.view().session, SessionState::Finishing);
+
+    let (state, _effects) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: JobResultKind::Cancelled,
+            content_preview: None,
+            extracted_links: Vec::new(),
+        },
+    );
+    let (state, _effects) = update(state, Msg::AllJobsDrained);
+    let view = state.view();
+    assert_eq!(view.jobs[0].outcome, Some(JobResultKind::Cancelled));
+    let summary = view.session_summary.expect("finished");
+    assert_eq!((summary.cancelled, summary.failed()), (1, 0));
+    assert!(state.completed_jobs_snapshot().is_empty());
+}
+
+#[test]
+fn completed_jobs_are_persisted_through_effects() {
+    init_logging();
+    let (state, _effects) = submit_urls(AppState::new(), "https://a.example\nhttps://b.example\n");
+    let done = |job_id, result| Msg::JobDone {
+        job_id,
+        at_ms: 0,
+        result,
+        content_preview: None,
+        extracted_links: Vec::new(),
+    };
+
+    let (state, effects) = update(state, done(1, JobResultKind::Failed));
+    assert!(
+        effects.is_empty(),
+        "a failure changes nothing that is saved"
+    );
+
+    let (state, effects) = update(state, done(2, JobResultKind::Success));
+    let session = state.persisted_session();
+    assert_eq!(
+        effects,
+        vec![Effect::PersistState {
+            session: session.clone()
+        }]
+    );
+    assert_eq!(session.completed.len(), 1);
+    assert_eq!(session.completed[0].url, "https://b.example");
+}
+
+#[test]
+fn token_budget_warns_at_eighty_percent_and_stops_at_the_limit() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::BudgetPresetSelected(BudgetPreset::Custom(100)),
+    );
+    let (state, _effects) = submit_urls(state, "https://a.example\nhttps://b.example\n");
+
+    let (state, effects) = update(
+        state,
+        Msg::TokenCountRefined {
+            job_id: 1,
+            tokens: 80,
+        },
+    );
+    assert_eq!(state.view().budget_level, BudgetLevel::Warning);
+    assert!(!effects
+        .iter()
+        .any(|effect| matches!(effect, Effect::StopFinish { .. })));
+
+    let (state, effects) = update(
+        state,
+        Msg::TokenCountRefined {
+            job_id: 2,
+            tokens: 20,
+        },
+    );
+    assert_eq!(state.view().budget_level, BudgetLevel::Exceeded);
+    assert_eq!(state.view().session, SessionState::Finishing);
+    assert_eq!(
+        effects[0],
+        Effect::StopFinish {
+            policy: StopPolicy::Finish
+        }
+    );
+    assert!(matches!(
+        state.view().activity_log.last(),
+        Some(ActivityEvent::BudgetLevelReached {
+            level: BudgetLevel::Exceeded,
+            tokens: 100,
+            limit: 100,
+        })
+    ));
+}
+
+#[test]
+fn large_pastes_wait_for_confirmation() {
+    init_logging();
+    let (state, _effects) = submit_urls(AppState::new(), "https://seen.example/0\n");
+    let mut input: String = (0..30)
+        .map(|n| format!("https://docs.example/{n}\n"))
+        .collect();
+    input.push_str("https://blog.example/a\nhttps://seen.example/0\nhttps://docs.example/0\n");
+
+    let (state, effects) = submit_urls(state, &input);
+    assert!(effects.is_empty(), "nothing is enqueued before confirming");
+    let view = state.view();
+    assert_eq!(view.job_count, 1);
+    let pending = view.pending_paste.expect("paste is pending");
+    assert_eq!((pending.count, pending.duplicates), (31, 2));
+    assert_eq!(
+        pending.domains,
+        vec![
+            ("docs.example".to_string(), 30),
+            ("blog.example".to_string(), 1),
+        ]
+    );
+
+    let (state, effects) = update(state, Msg::PasteConfirmed);
+    assert_eq!(effects.len(), 31);
+    let view = state.view();
+    assert_eq!(view.job_count, 32);
+    assert!(view.pending_paste.is_none());
+    assert_eq!(view.last_paste_stats.map(|stats| stats.skipped), Some(2));
+
+    let (state, _effects) = submit_urls(AppState::new(), &input);
+    assert!(state.view().pending_paste.is_some());
+    let (state, effects) = update(state, Msg::PasteCancelled);
+    assert!(effects.is_empty());
+    assert!(state.view().pending_paste.is_none());
+    let (state, effects) = update(state, Msg::PasteConfirmed);
+    assert!(effects.is_empty(), "nothing left to confirm");
+    assert_eq!(state.view().job_count, 0);
+}
+
+#[test]
+fn closing_stops_jobs_and_quits_once_drained() {
+    init_logging();
+    let (state, effects) = update(
+        AppState::new(),
+        Msg::CloseRequested {
+            policy: StopPolicy::Immediate,
+        },
+    );
+    assert_eq!(
+        effects,
+        vec![persist(&state), Effect::Quit],
+        "nothing to stop"
+    );
+    assert!(state.view().closing);
+
+    let (state, _effects) = submit_urls(AppState::new(), "https://example.com\n");
+    let (state, effects) = update(
+        state,
+        Msg::CloseRequested {
+            policy: StopPolicy::Finish,
+        },
+    );
+    assert_eq!(
+        effects,
+        vec![Effect::StopFinish {
+            policy: StopPolicy::Finish
+        }]
+    );
+    assert!(state.view().closing);
+    assert_eq!(state.view().session, SessionState::Finishing);
+
+    let (state, effects) = submit_urls(state, "https://a.example.com\n");
+    assert!(effects.is_empty(), "no intake while closing");
+
+    let (state, effects) = update(state, Msg::AllJobsDrained);
+    assert_eq!(effects, vec![persist(&state), Effect::Quit]);
+    assert_eq!(state.view().session, SessionState::Finished);
+}
+
+#[test]
+fn closing_again_quits_without_waiting() {
+    init_logging();
+    let (state, _effects) = submit_urls(AppState::new(), "https://example.com\n");
+    let (state, _effects) = update(state, Msg::StopFinishClicked);
+    let (state, effects) = update(
+        state,
+        Msg::CloseRequested {
+            policy: StopPolicy::Immediate,
+        },
+    );
+    assert_eq!(
+        effects,
+        vec![Effect::StopFinish {
+            policy: StopPolicy::Immediate
+        }],
+        "the graceful stop is cut short"
+    );
+
+    let (state, effects) = update(
+        state,
+        Msg::CloseRequested {
+            policy: StopPolicy::Immediate,
+        },
+    );
+    assert_eq!(effects, vec![persist(&state), Effect::Quit]);
+}
+
 #[test]
 fn urls_pasted_ignored_while_finishing() {
     init_logging();
@@ -87,6 +373,114 @@ fn urls_pasted_ignored_while_finishing() {
     assert!(!next.consume_dirty());
 }
 
+#[test]
+fn drained_engine_finishes_the_session_and_new_session_returns_to_idle() {
+    init_logging();
+    let state = AppState::new();
+    let (state, _effects) = submit_urls(state, "https://example.com\n");
+    let (state, _effects) = update(state, Msg::NewSessionRequested);
+    assert_eq!(
+        state.view().session,
+        SessionState::Running,
+        "not finished yet"
+    );
+
+    let (state, _effects) = update(state, Msg::StopFinishClicked);
+    let (state, effects) = update(state, Msg::AllJobsDrained);
+    assert_eq!(state.view().session, SessionState::Finished);
+    assert!(effects.is_empty());
+
+    let (state, _effects) = update(state, Msg::NewSessionRequested);
+    assert_eq!(state.view().session, SessionState::Idle);
+    let (state, effects) = submit_urls(state, "https://a.example.com\n");
+    assert_eq!(state.view().session, SessionState::Running);
+    assert_eq!(effects.first(), Some(&Effect::StartSession));
+}
+
+#[test]
+fn new_session_snapshots_completed_jobs_and_clears_the_state() {
+    init_logging();
+    let state = AppState::new();
+    let (state, _) = update(
+        state,
+        Msg::RestoreCompletedJobs(vec![CompletedJobSnapshot {
+            url: "https://example.com/".to_string(),
+            tokens: Some(12),
+            bytes: Some(80),
+            links: Vec::new(),
+            pinned: false,
+        }]),
+    );
+    let (state, _) = update(state, Msg::RestoreLastExport("2024-05-01T00:00:00Z".into()));
+
+    let (state, effects) = update(state, Msg::NewSessionRequested);
+
+    assert_eq!(
+        effects,
+        vec![
+            Effect::SaveSessionSnapshot {
+                completed: vec![CompletedJobSnapshot {
+                    url: "https://example.com/".to_string(),
+                    tokens: Some(12),
+                    bytes: Some(80),
+                    links: Vec::new(),
+                    pinned: false,
+                }],
+                since_utc: Some("2024-05-01T00:00:00Z".to_string()),
+                min_quality: None,
+            },
+            persist(&state),
+        ]
+    );
+    let view = state.view();
+    assert_eq!(view.session, SessionState::Idle);
+    assert_eq!((view.job_count, view.total_tokens), (0, 0));
+
+    let (state, effects) = submit_urls(state, "https://example.com/\n");
+    assert_eq!(state.view().job_count, 1, "seen URLs were forgotten");
+    assert_eq!(effects.len(), 2);
+    let (_state, effects) = update(state, Msg::NewSessionRequested);
+    assert!(effects.is_empty(), "ignored while running");
+}
+
+#[test]
+fn switching_profiles_waits_for_idle_and_starts_a_fresh_session() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::ProfilesLoaded {
+            names: vec!["default".to_string(), "work".to_string()],
+            active: "default".to_string(),
+        },
+    );
+    let (state, _) = update(state, Msg::RestoreLastExport("2024-05-01T00:00:00Z".into()));
+    let (state, _) = submit_urls(state, "https://example.com/\n");
+
+    let (state, effects) = update(state, Msg::ProfileSelected("work".to_string()));
+    assert!(effects.is_empty(), "ignored while running");
+
+    let (state, _) = update(state, Msg::StopFinishClicked);
+    let (state, _) = update(state, Msg::AllJobsDrained);
+    let (state, effects) = update(state, Msg::ProfileSelected("default".to_string()));
+    assert!(effects.is_empty(), "already active");
+    let (state, effects) = update(state, Msg::ProfileSelected("personal".to_string()));
+    assert!(effects.is_empty(), "unknown profile");
+    let (state, effects) = update(state, Msg::ProfileSelected("work".to_string()));
+    assert_eq!(
+        effects,
+        vec![Effect::SwitchProfile {
+            name: "work".to_string()
+        }]
+    );
+    assert_eq!(state.view().active_profile.as_deref(), Some("default"));
+
+    let (state, _) = update(state, Msg::ProfileActivated("work".to_string()));
+    let view = state.view();
+    assert_eq!(view.active_profile.as_deref(), Some("work"));
+    assert_eq!((view.session, view.job_count), (SessionState::Idle, 0));
+    assert_eq!(state.last_export_utc(), None);
+}
+
 #[test]
 fn urls_pasted_while_running_stays_running() {
     init_logging();
@@ -105,6 +499,7 @@ fn urls_pasted_while_running_stays_running() {
         vec![Effect::EnqueueUrl {
             job_id: 2,
             url: "https://second.example.com".to_string(),
+            source_batch: None,
         }]
     );
 }
@@ -180,13 +575,954 @@ fn paste_with_mixed_new_and_duplicate_urls() {
 }
 
 #[test]
-fn archive_click_emits_effect_without_state_change() {
+fn archive_click_emits_effect_and_only_marks_the_export_running() {
     init_logging();
     let state = AppState::new();
     let before = state.view();
 
     let (next, effects) = update(state, Msg::ArchiveClicked);
 
-    assert_eq!(next.view(), before);
-    assert_eq!(effects, vec![Effect::ArchiveRequested]);
+    assert_eq!(
+        next.view(),
+        AppViewModel {
+            export_in_progress: true,
+            dirty: true,
+            ..before
+        }
+    );
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveRequested { min_quality: None }]
+    );
+}
+
+#[test]
+fn archive_new_carries_last_export_timestamp() {
+    init_logging();
+    let (state, effects) = update(AppState::new(), Msg::ArchiveNewClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveNewRequested {
+            since_utc: None,
+            min_quality: None
+        }]
+    );
+
+    let (state, _) = update(
+        state,
+        Msg::ExportCompleted {
+            exported_utc: "2024-05-01T10:00:00Z".to_string(),
+        },
+    );
+    assert_eq!(state.last_export_utc(), Some("2024-05-01T10:00:00Z"));
+
+    let (_state, effects) = update(state, Msg::ArchiveNewClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveNewRequested {
+            since_utc: Some("2024-05-01T10:00:00Z".to_string()),
+            min_quality: None
+        }]
+    );
+}
+
+#[test]
+fn picked_export_quality_is_saved_and_sent_with_exports() {
+    init_logging();
+    let (state, effects) = update(AppState::new(), Msg::ExportMinQualitySelected(Some(40)));
+    assert_eq!(effects, vec![persist(&state)]);
+    assert_eq!(state.view().export_min_quality, Some(40));
+    assert_eq!(state.persisted_session().export_min_quality, Some(40));
+
+    let (_state, effects) = update(state, Msg::ArchiveClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveRequested {
+            min_quality: Some(40)
+        }]
+    );
+
+    let (state, effects) = update(AppState::new(), Msg::RestoreExportMinQuality(Some(60)));
+    assert!(effects.is_empty());
+    assert_eq!(state.export_min_quality(), Some(60));
+}
+
+#[test]
+fn archive_waits_for_the_running_export_to_finish() {
+    init_logging();
+    let (state, effects) = update(AppState::new(), Msg::ArchiveClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveRequested { min_quality: None }]
+    );
+    assert!(state.view().export_in_progress);
+
+    let (state, effects) = update(state, Msg::ArchiveClicked);
+    assert!(effects.is_empty(), "a second click is ignored");
+    let (state, effects) = update(state, Msg::ArchiveNewClicked);
+    assert!(effects.is_empty());
+
+    let (state, _) = update(
+        state,
+        Msg::ExportFailed {
+            message: "disk full".to_string(),
+        },
+    );
+    assert!(!state.view().export_in_progress);
+    let (state, effects) = update(state, Msg::ArchiveNewClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::ArchiveNewRequested {
+            since_utc: None,
+            min_quality: None
+        }]
+    );
+
+    let (state, _) = update(
+        state,
+        Msg::ExportCompleted {
+            exported_utc: "2024-05-01T10:00:00Z".to_string(),
+        },
+    );
+    assert!(!state.view().export_in_progress);
+}
+
+#[test]
+fn saturated_queue_holds_pastes_until_resumed() {
+    init_logging();
+    let (state, _) = submit_urls(AppState::new(), "https://first.example.com\n");
+    let (state, _) = update(state, Msg::QueueSaturated);
+    assert!(state.view().intake_paused);
+
+    let (state, effects) = submit_urls(state, "https://second.example.com\n");
+    assert!(effects.is_empty());
+    assert_eq!(state.view().job_count, 1);
+
+    let (state, _) = update(state, Msg::QueueResumed);
+    assert!(!state.view().intake_paused);
+    let (state, effects) = update(state, Msg::UrlsSubmitted);
+    assert_eq!(
+        effects,
+        vec![Effect::EnqueueUrl {
+            job_id: 2,
+            url: "https://second.example.com".to_string(),
+            source_batch: None,
+        }]
+    );
+    assert_eq!(state.view().job_count, 2);
+}
+
+#[test]
+fn paste_past_the_queue_capacity_waits_for_room_in_the_engine() {
+    init_logging();
+    let enqueue = |job_id: u64, host: &str| Effect::EnqueueUrl {
+        job_id,
+        url: format!("https://{host}.example.com"),
+        source_batch: None,
+    };
+    let (state, _) = update(AppState::new(), Msg::QueueCapacity(2));
+    let (state, effects) = submit_urls(
+        state,
+        "https://a.example.com\nhttps://b.example.com\nhttps://c.example.com\nhttps://d.example.com\n",
+    );
+    assert_eq!(
+        effects,
+        vec![Effect::StartSession, enqueue(1, "a"), enqueue(2, "b")]
+    );
+    assert_eq!(state.view().job_count, 4);
+
+    let (state, effects) = update(
+        state,
+        Msg::JobProgress {
+            job_id: 1,
+            at_ms: 10,
+            stage: Stage::Downloading,
+            tokens: None,
+            bytes: None,
+            content_preview: None,
+        },
+    );
+    assert_eq!(effects, vec![enqueue(3, "c")]);
+
+    // Stopping cancels the job the engine never got.
+    let (state, effects) = update(state, Msg::StopFinishClicked);
+    assert_eq!(
+        effects,
+        vec![Effect::StopFinish {
+            policy: StopPolicy::Finish,
+        }]
+    );
+    let view = state.view();
+    assert_eq!(view.jobs[3].outcome, Some(JobResultKind::Cancelled));
+    assert_eq!(view.jobs[2].outcome, None);
+}
+
+#[test]
+fn memory_throttling_shows_until_the_next_job_starts() {
+    init_logging();
+    let (state, _) = submit_urls(AppState::new(), "https://first.example.com\n");
+    let (state, _) = update(
+        state,
+        Msg::MemoryThrottled {
+            in_use_bytes: 900,
+            budget_bytes: 1000,
+        },
+    );
+    assert_eq!(state.view().memory_throttled, Some((900, 1000)));
+
+    let (state, _) = update(
+        state,
+        Msg::JobProgress {
+            job_id: 1,
+            at_ms: 10,
+            stage: Stage::Downloading,
+            tokens: None,
+            bytes: None,
+            content_preview: None,
+        },
+    );
+    assert_eq!(state.view().memory_throttled, None);
+}
+
+#[test]
+fn received_urls_enqueue_without_touching_input_buffer() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::InputChanged("https://typed.example.com".to_string()),
+    );
+    let (state, effects) = update(
+        state,
+        Msg::UrlsReceived(vec![
+            "https://remote.example.com".to_string(),
+            " ".to_string(),
+        ]),
+    );
+    assert_eq!(
+        effects,
+        vec![
+            Effect::StartSession,
+            Effect::EnqueueUrl {
+                job_id: 1,
+                url: "https://remote.example.com".to_string(),
+                source_batch: None,
+            },
+        ]
+    );
+
+    let (_state, effects) = update(state, Msg::UrlsSubmitted);
+    assert_eq!(
+        effects,
+        vec![Effect::EnqueueUrl {
+            job_id: 2,
+            url: "https://typed.example.com".to_string(),
+            source_batch: None,
+        }]
+    );
+}
+
+#[test]
+fn watched_job_changes_surface_as_new_jobs() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![CompletedJobSnapshot {
+            url: "https://example.com/news".to_string(),
+            tokens: Some(10),
+            bytes: Some(100),
+            links: Vec::new(),
+            pinned: false,
+        }]),
+    );
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    let (state, effects) = update(state, Msg::ToggleWatchSelected { interval_secs: 600 });
+    assert_eq!(
+        effects,
+        vec![Effect::WatchUrl {
+            job_id: 1,
+            url: "https://example.com/news".to_string(),
+            interval_secs: 600,
+        }]
+    );
+    assert!(state.view().jobs[0].watched);
+
+    let (state, effects) = update(
+        state,
+        Msg::WatchedPageChanged {
+            watch_id: 1,
+            tokens: Some(12),
+            bytes: Some(120),
+            content_preview: Some("new content".to_string()),
+            extracted_links: Vec::new(),
+            change: None,
+        },
+    );
+    assert_eq!(effects, vec![persist(&state)]);
+    let view = state.view();
+    assert_eq!(view.job_count, 2);
+    assert_eq!(view.jobs[1].changed_from, Some(1));
+    assert_eq!(view.jobs[1].url, "https://example.com/news");
+    assert_eq!(view.total_tokens, 22);
+
+    let (state, effects) = update(state, Msg::ToggleWatchSelected { interval_secs: 600 });
+    assert_eq!(effects, vec![Effect::UnwatchUrl { job_id: 1 }]);
+    assert!(!state.view().jobs[0].watched);
+}
+
+#[test]
+fn pinned_jobs_are_listed_first_and_reported_to_the_engine() {
+    init_logging();
+    let snapshot = |url: &str, pinned| CompletedJobSnapshot {
+        url: url.to_string(),
+        tokens: None,
+        bytes: None,
+        links: Vec::new(),
+        pinned,
+    };
+    let (state, effects) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![
+            snapshot("https://example.com/a", false),
+            snapshot("https://example.com/b", true),
+            snapshot("https://example.com/c", false),
+        ]),
+    );
+    assert_eq!(
+        effects,
+        vec![Effect::PinnedUrlsChanged {
+            urls: vec!["https://example.com/b".to_string()],
+        }]
+    );
+    let order =
+        |state: &AppState| -> Vec<u64> { state.view().jobs.iter().map(|job| job.job_id).collect() };
+    assert_eq!(order(&state), vec![2, 1, 3]);
+
+    let (state, _) = update(state, Msg::JobSelected { job_id: 3 });
+    let (state, effects) = update(state, Msg::TogglePinSelected);
+    assert_eq!(
+        effects,
+        vec![
+            Effect::PinnedUrlsChanged {
+                urls: vec![
+                    "https://example.com/b".to_string(),
+                    "https://example.com/c".to_string(),
+                ],
+            },
+            persist(&state),
+        ]
+    );
+    assert_eq!(order(&state), vec![2, 3, 1]);
+    assert!(state.view().preview_header.unwrap().pinned);
+    assert!(state.completed_jobs_snapshot()[2].pinned);
+}
+
+#[test]
+fn keyboard_selection_follows_list_order_and_clamps() {
+    init_logging();
+    let snapshot = |url: &str, pinned| CompletedJobSnapshot {
+        url: url.to_string(),
+        tokens: None,
+        bytes: None,
+        links: Vec::new(),
+        pinned,
+    };
+    let (state, _) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![
+            snapshot("https://example.com/a", false),
+            snapshot("https://example.com/b", true),
+            snapshot("https://example.com/c", false),
+        ]),
+    );
+    assert_eq!(state.view().selected_job_id, None);
+
+    let (state, _) = update(state, Msg::SelectPrevJob);
+    assert_eq!(state.view().selected_job_id, Some(3));
+    let (state, _) = update(state, Msg::SelectNextJob);
+    assert_eq!(state.view().selected_job_id, Some(3));
+
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    let (state, _) = update(state, Msg::SelectPrevJob);
+    assert_eq!(state.view().selected_job_id, Some(2));
+    assert!(state.view().preview_header.unwrap().pinned);
+    let (state, _) = update(state, Msg::SelectPrevJob);
+    assert_eq!(state.view().selected_job_id, Some(2));
+
+    let (state, _) = update(state, Msg::SelectFirstFailed);
+    assert_eq!(state.view().selected_job_id, Some(2));
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 3,
+            at_ms: 0,
+            result: JobResultKind::Failed,
+            content_preview: None,
+            extracted_links: Vec::new(),
+        },
+    );
+    let (state, _) = update(state, Msg::SelectFirstFailed);
+    assert_eq!(state.view().selected_job_id, Some(3));
+}
+
+#[test]
+fn selected_url_can_be_copied_or_opened() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![CompletedJobSnapshot {
+            url: "https://example.com/a".to_string(),
+            tokens: None,
+            bytes: None,
+            links: Vec::new(),
+            pinned: false,
+        }]),
+    );
+    let (state, effects) = update(state, Msg::CopySelectedUrl);
+    assert!(effects.is_empty());
+
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    let (state, effects) = update(state, Msg::CopySelectedUrl);
+    assert_eq!(
+        effects,
+        vec![Effect::CopyToClipboard {
+            text: "https://example.com/a".to_string()
+        }]
+    );
+    let (_, effects) = update(state, Msg::OpenSelectedUrl);
+    assert_eq!(
+        effects,
+        vec![Effect::OpenInBrowser {
+            url: "https://example.com/a".to_string()
+        }]
+    );
+}
+
+#[test]
+fn column_clicks_sort_unpinned_rows_and_flip_direction() {
+    init_logging();
+    let snapshot = |url: &str, tokens, pinned| CompletedJobSnapshot {
+        url: url.to_string(),
+        tokens,
+        bytes: None,
+        links: Vec::new(),
+        pinned,
+    };
+    let (state, _) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![
+            snapshot("https://example.com/c", Some(30), false),
+            snapshot("https://example.com/a", Some(10), false),
+            snapshot("https://example.com/b", None, true),
+            snapshot("https://example.com/d", Some(20), false),
+        ]),
+    );
+    let order =
+        |state: &AppState| -> Vec<u64> { state.view().jobs.iter().map(|job| job.job_id).collect() };
+
+    let (state, _) = update(state, Msg::JobColumnClicked(JobColumn::Tokens));
+    assert_eq!(order(&state), vec![3, 2, 4, 1]);
+    let (state, _) = update(state, Msg::JobColumnClicked(JobColumn::Tokens));
+    assert_eq!(
+        state.view().job_sort,
+        Some(JobSort {
+            column: JobColumn::Tokens,
+            descending: true,
+        })
+    );
+    assert_eq!(order(&state), vec![3, 1, 4, 2]);
+
+    let (state, _) = update(state, Msg::JobColumnClicked(JobColumn::Url));
+    assert_eq!(order(&state), vec![3, 2, 1, 4]);
+    let (state, _) = update(state, Msg::SelectNextJob);
+    let (state, _) = update(state, Msg::SelectNextJob);
+    assert_eq!(state.view().selected_job_id, Some(2));
+}
+
+#[test]
+fn splitters_resize_panels_within_the_window() {
+    init_logging();
+    let (state, _) = update(AppState::new(), Msg::WindowResized { window_width: 1000 });
+    assert_eq!(state.view().panel_widths, PanelWidths::default());
+
+    let (mut state, _) = update(
+        state,
+        Msg::SplitterDragged {
+            splitter: Splitter::JobsPreview,
+            desired_left_width_px: 900,
+        },
+    );
+    assert!(state.consume_dirty());
+    assert_eq!(
+        state.view().panel_widths,
+        PanelWidths {
+            input: 320,
+            jobs: 448,
+        }
+    );
+
+    let (state, _) = update(state, Msg::WindowResized { window_width: 800 });
+    assert_eq!(
+        state.view().panel_widths,
+        PanelWidths {
+            input: 320,
+            jobs: 248,
+        }
+    );
+    let (state, _) = update(
+        state,
+        Msg::SplitterReleased {
+            splitter: Splitter::InputJobs,
+            desired_left_width_px: 200,
+        },
+    );
+    assert_eq!(
+        state.panel_widths(),
+        PanelWidths {
+            input: 200,
+            jobs: 368,
+        }
+    );
+}
+
+#[test]
+fn diff_mode_previews_changes_against_previous_harvest() {
+    init_logging();
+    let (state, _) = update(
+        AppState::new(),
+        Msg::RestoreCompletedJobs(vec![
+            CompletedJobSnapshot {
+                url: "https://example.com/a".to_string(),
+                tokens: Some(10),
+                bytes: Some(100),
+                links: Vec::new(),
+                pinned: false,
+            },
+            CompletedJobSnapshot {
+                url: "https://example.com/b".to_string(),
+                tokens: Some(10),
+                bytes: Some(100),
+                links: Vec::new(),
+                pinned: false,
+            },
+        ]),
+    );
+    let (state, _) = update(
+        state,
+        Msg::ContentCompared {
+            job_id: 1,
+            change: ContentChange {
+                added: 1,
+                removed: 1,
+                diff: "-old\n+new".to_string(),
+            },
+        },
+    );
+    let (state, _) = update(
+        state,
+        Msg::ContentCompared {
+            job_id: 2,
+            change: ContentChange::default(),
+        },
+    );
+    let view = state.view();
+    assert_eq!(
+        view.jobs[0].change,
+        Some(ChangeSummary {
+            added: 1,
+            removed: 1
+        })
+    );
+    assert!(view.jobs[1].change.unwrap().is_unchanged());
+
+    let (state, _) = update(state, Msg::DiffModeToggled);
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    assert!(state.view().diff_mode);
+    assert_eq!(state.view().preview_text.as_deref(), Some("-old\n+new"));
+
+    let (state, _) = update(state, Msg::JobSelected { job_id: 2 });
+    assert_eq!(
+        state.view().preview_text.as_deref(),
+        Some("(no changes since the previous harvest)")
+    );
+
+    let (state, _) = update(state, Msg::DiffModeToggled);
+    assert!(!state.view().diff_mode);
+}
+
+#[test]
+fn paste_rejects_unsupported_schemes_with_reasons() {
+    init_logging();
+    let input =
+        "file:///etc/passwd\njavascript:alert(1)\nexample.com\nhttps://\nhttps://ok.example.com\n";
+    let (state, effects) = submit_urls(AppState::new(), input);
+
+    assert_eq!(state.view().job_count, 1);
+    assert_eq!(effects.len(), 2); // StartSession + 1x EnqueueUrl
+    let stats = state.view().last_paste_stats.unwrap();
+    assert_eq!(stats.enqueued, 1);
+    assert_eq!(
+        stats.rejected,
+        RejectedUrls {
+            unsupported_scheme: 2,
+            missing_scheme: 1,
+            malformed: 1,
+        }
+    );
+
+    // Only rejected lines: nothing starts, but the stats still explain why.
+    let (state, effects) = submit_urls(AppState::new(), "ftp://files.example.com\n");
+    assert!(effects.is_empty());
+    assert_eq!(state.view().session, SessionState::Idle);
+    assert_eq!(state.view().last_paste_stats.unwrap().rejected.total(), 1);
+
+    // Extra schemes can be allowed explicitly.
+    let state = AppState::new().with_allowed_schemes(["https", "ftp"]);
+    let (state, _) = submit_urls(state, "ftp://files.example.com\nhttp://a.example.com\n");
+    assert_eq!(state.view().job_count, 1);
+    assert_eq!(
+        state
+            .view()
+            .last_paste_stats
+            .unwrap()
+            .rejected
+            .unsupported_scheme,
+        1
+    );
+}
+
+#[test]
+fn canonical_url_is_used_for_dedupe_and_snapshot() {
+    init_logging();
+    let (state, _) = submit_urls(
+        AppState::new(),
+        "https://example.com/story?utm_source=feed\n",
+    );
+    let (state, _) = update(
+        state,
+        Msg::CanonicalUrlResolved {
+            job_id: 1,
+            canonical_url: "https://example.com/story".to_string(),
+        },
+    );
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: harvester_core::JobResultKind::Success,
+            content_preview: None,
+            extracted_links: Vec::new(),
+        },
+    );
+
+    let (state, effects) = submit_urls(state, "https://example.com/story\n");
+    assert!(effects.is_empty());
+    assert_eq!(state.view().last_paste_stats.unwrap().skipped, 1);
+    assert_eq!(
+        state.completed_jobs_snapshot()[0].url,
+        "https://example.com/story"
+    );
+}
+
+#[test]
+fn reading_metrics_reach_the_row_and_preview_header() {
+    init_logging();
+    let (state, _) = submit_urls(AppState::new(), "https://example.com/essay\n");
+    let (state, _) = update(
+        state,
+        Msg::ReadingMeasured {
+            job_id: 1,
+            words: 2_300,
+            minutes: 10,
+        },
+    );
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+
+    let view = state.view();
+    assert_eq!(view.jobs[0].word_count, Some(2_300));
+    assert_eq!(view.jobs[0].reading_minutes, Some(10));
+    assert_eq!(view.preview_header.unwrap().reading, Some((2_300, 10)));
+}
+
+#[test]
+fn duplicate_content_marks_the_row_with_its_original() {
+    init_logging();
+    let (state, _) = submit_urls(
+        AppState::new(),
+        "https://example.com/post\nhttps://mirror.example/post\n",
+    );
+    let (state, _) = update(
+        state,
+        Msg::DuplicateDetected {
+            job_id: 2,
+            original: 1,
+        },
+    );
+
+    let view = state.view();
+    assert_eq!(view.jobs[0].duplicate_of, None);
+    assert_eq!(view.jobs[1].duplicate_of, Some(1));
+}
+
+#[test]
+fn links_pane_lists_the_selected_jobs_links_by_kind() {
+    init_logging();
+    let (state, _) = submit_urls(AppState::new(), "https://example.com/guide\n");
+    let link = |url: &str, kind| JobLink {
+        url: url.to_string(),
+        text: None,
+        kind,
+        internal: url.starts_with("https://example.com/"),
+    };
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: JobResultKind::Success,
+            content_preview: None,
+            extracted_links: vec![
+                link("https://example.com/next", LinkKind::Hyperlink),
+                link("https://example.com/diagram.png", LinkKind::Image),
+                link("mailto:docs@example.com", LinkKind::Email),
+                link("https://example.com/faq", LinkKind::Hyperlink),
+            ],
+        },
+    );
+    assert_eq!(state.view().links_pane, None);
+
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    let pane = state.view().links_pane.expect("pane for the selected job");
+    assert_eq!(pane.job_id, 1);
+    let counts: Vec<_> = pane
+        .filters
+        .iter()
+        .map(|filter| (filter.kind, filter.count, filter.shown))
+        .collect();
+    assert_eq!(
+        counts,
+        vec![
+            (LinkKind::Hyperlink, 2, true),
+            (LinkKind::Image, 1, true),
+            (LinkKind::Email, 1, true),
+        ]
+    );
+    assert_eq!(pane.links.len(), 4);
+
+    let (state, _) = update(state, Msg::LinkKindToggled(LinkKind::Hyperlink));
+    let pane = state.view().links_pane.unwrap();
+    assert!(!pane.filters[0].shown);
+    let urls: Vec<_> = pane.links.iter().map(|link| link.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/diagram.png", "mailto:docs@example.com"]
+    );
+
+    let (state, _) = update(state, Msg::LinkScopeToggled(LinkScope::External));
+    let pane = state.view().links_pane.unwrap();
+    let scopes: Vec<_> = pane
+        .scopes
+        .iter()
+        .map(|filter| (filter.scope, filter.count, filter.shown))
+        .collect();
+    assert_eq!(
+        scopes,
+        vec![
+            (LinkScope::Internal, 3, true),
+            (LinkScope::External, 1, false)
+        ]
+    );
+    let urls: Vec<_> = pane.links.iter().map(|link| link.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://example.com/diagram.png"]);
+}
+
+#[test]
+fn checked_links_are_harvested_through_the_paste_dedupe() {
+    init_logging();
+    let (state, _) = submit_urls(
+        AppState::new(),
+        "https://example.com/guide\nhttps://example.com/faq\n",
+    );
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: JobResultKind::Success,
+            content_preview: None,
+            extracted_links: ["https://example.com/faq", "https://example.com/next"]
+                .map(JobLink::from_url)
+                .to_vec(),
+        },
+    );
+    let (state, _) = update(state, Msg::JobSelected { job_id: 1 });
+    let mut state = state;
+    for url in ["https://example.com/faq", "https://example.com/next"] {
+        state = update(
+            state,
+            Msg::LinkCheckToggled {
+                url: url.to_string(),
+            },
+        )
+        .0;
+    }
+    assert_eq!(state.view().links_pane.unwrap().checked.len(), 2);
+
+    let (state, effects) = update(state, Msg::HarvestCheckedLinks);
+    assert_eq!(
+        effects,
+        vec![Effect::EnqueueUrl {
+            job_id: 3,
+            url: "https://example.com/next".to_string(),
+            source_batch: None,
+        }]
+    );
+    let stats = state.view().last_paste_stats.unwrap();
+    assert_eq!((stats.enqueued, stats.skipped), (1, 1));
+    assert!(state.view().links_pane.unwrap().checked.is_empty());
+}
+
+#[test]
+fn image_inventory_collects_image_links_of_every_job() {
+    init_logging();
+    let (mut state, effects) = update(AppState::new(), Msg::ImageInventoryRequested);
+    assert!(effects.is_empty());
+    state = submit_urls(state, "https://a.example/\nhttps://b.example/\n").0;
+    for (job_id, image) in [
+        (1, "https://a.example/fig.png"),
+        (2, "https://cdn.example/b.jpg"),
+    ] {
+        let links = vec![
+            JobLink {
+                url: image.to_string(),
+                text: Some("Figure".to_string()),
+                kind: LinkKind::Image,
+                internal: false,
+            },
+            JobLink::from_url("https://a.example/next"),
+        ];
+        state = update(
+            state,
+            Msg::JobDone {
+                job_id,
+                at_ms: 0,
+                result: JobResultKind::Success,
+                content_preview: None,
+                extracted_links: links,
+            },
+        )
+        .0;
+    }
+
+    let (_, effects) = update(state, Msg::ImageInventoryRequested);
+    let image = |image_url: &str, page_url: &str| ImageRef {
+        image_url: image_url.to_string(),
+        page_url: page_url.to_string(),
+        alt_text: Some("Figure".to_string()),
+    };
+    assert_eq!(
+        effects,
+        vec![Effect::ExportImageInventory {
+            images: vec![
+                image("https://a.example/fig.png", "https://a.example/"),
+                image("https://cdn.example/b.jpg", "https://b.example/"),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn pasted_ranges_and_lists_expand_to_every_url() {
+    init_logging();
+    let (state, effects) = submit_urls(
+        AppState::new(),
+        "https://docs.example.com/page/[1-3]\nhttps://docs.example.com/{intro,setup}/v[08-10]\nhttp://[::1]:8080/{a}\n",
+    );
+    let urls: Vec<&str> = effects
+        .iter()
+        .filter_map(|effect| match effect {
+            Effect::EnqueueUrl { url, .. } => Some(url.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        urls,
+        [
+            "https://docs.example.com/page/1",
+            "https://docs.example.com/page/2",
+            "https://docs.example.com/page/3",
+            "https://docs.example.com/intro/v08",
+            "https://docs.example.com/intro/v09",
+            "https://docs.example.com/intro/v10",
+            "https://docs.example.com/setup/v08",
+            "https://docs.example.com/setup/v09",
+            "https://docs.example.com/setup/v10",
+            "http://[::1]:8080/{a}",
+        ]
+    );
+    assert_eq!(state.view().job_count, 10);
+
+    let (state, _effects) = submit_urls(AppState::new(), "https://docs.example.com/[1-5000]/{a,b}");
+    let pending = state.view().pending_paste.expect("large expansion waits");
+    assert_eq!(pending.count, 1_000, "expansion is capped");
+}
+
+#[test]
+fn source_line_labels_every_job_of_the_paste() {
+    init_logging();
+    let (state, effects) = submit_urls(
+        AppState::new(),
+        "# Source: release-notes\nhttps://a.example.com\nhttps://b.example.com\n",
+    );
+    let labels: Vec<Option<&str>> = effects
+        .iter()
+        .filter_map(|effect| match effect {
+            Effect::EnqueueUrl { source_batch, .. } => Some(source_batch.as_deref()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(labels, [Some("release-notes"), Some("release-notes")]);
+    assert_eq!(state.view().job_count, 2);
+    assert_eq!(state.view().last_paste_stats.unwrap().rejected.total(), 0);
+
+    let (state, _effects) = update(state, Msg::JobSelected { job_id: 1 });
+    assert_eq!(
+        state.view().preview_header.unwrap().source_batch.as_deref(),
+        Some("release-notes")
+    );
+}
+
+#[test]
+fn pasted_local_paths_become_file_urls_when_file_scheme_is_allowed() {
+    init_logging();
+    let input = "C:\\Saved Pages\\story.html\n\"/home/me/saved/page.htm\"\n";
+
+    let (state, effects) = submit_urls(AppState::new(), input);
+    assert!(effects.is_empty());
+    assert_eq!(
+        state
+            .view()
+            .last_paste_stats
+            .unwrap()
+            .rejected
+            .unsupported_scheme,
+        2
+    );
+
+    let state = AppState::new().with_allowed_schemes(["https", "file"]);
+    let (_, effects) = submit_urls(state, input);
+    let urls: Vec<_> = effects
+        .iter()
+        .filter_map(|effect| match effect {
+            Effect::EnqueueUrl { url, .. } => Some(url.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        urls,
+        vec![
+            "file:///C:/Saved%20Pages/story.html",
+            "file:///home/me/saved/page.htm"
+        ]
+    );
 }
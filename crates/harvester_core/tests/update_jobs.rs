@@ -1,4 +1,6 @@
-use harvester_core::{update, AppState, Effect, JobResultKind, Msg, Stage, TOKEN_LIMIT};
+use harvester_core::{
+    update, ActivityEvent, AppState, BudgetPreset, Effect, JobResultKind, Msg, Stage,
+};
 
 fn submit_urls(state: AppState, input: &str) -> (AppState, Vec<Effect>) {
     let (state, _) = update(state, Msg::InputChanged(input.to_string()));
@@ -22,6 +24,7 @@ fn urls_pasted_trims_and_ignores_empty() {
         next,
         Msg::JobProgress {
             job_id: 1,
+            at_ms: 0,
             stage: Stage::Downloading,
             tokens: Some(10),
             bytes: Some(1024),
@@ -45,6 +48,7 @@ fn urls_pasted_trims_and_ignores_empty() {
         next,
         Msg::JobDone {
             job_id: 1,
+            at_ms: 0,
             result: JobResultKind::Success,
             content_preview: None,
             extracted_links: Vec::new(),
@@ -65,7 +69,7 @@ fn urls_pasted_trims_and_ignores_empty() {
 #[test]
 fn jobs_are_ordered_by_btree_key() {
     let state = AppState::new();
-    let (mut state, _effects) = submit_urls(state, "b.com\na.com\n");
+    let (mut state, _effects) = submit_urls(state, "https://b.com\nhttps://a.com\n");
 
     // BTreeMap iteration should yield deterministic ascending JobId order (1,2,...)
     let ids: Vec<_> = state.view().jobs.iter().map(|j| j.job_id).collect();
@@ -76,12 +80,13 @@ fn jobs_are_ordered_by_btree_key() {
 #[test]
 fn token_totals_accumulate_and_replace_previous_values() {
     let state = AppState::new();
-    let (state, _effects) = submit_urls(state, "a.com\nb.com\n");
+    let (state, _effects) = submit_urls(state, "https://a.com\nhttps://b.com\n");
 
     let (mut state, _effects) = update(
         state,
         Msg::JobProgress {
             job_id: 1,
+            at_ms: 0,
             stage: Stage::Tokenizing,
             tokens: Some(120),
             bytes: None,
@@ -90,13 +95,17 @@ fn token_totals_accumulate_and_replace_previous_values() {
     );
     let view_after_first = state.view();
     assert_eq!(view_after_first.total_tokens, 120);
-    assert_eq!(view_after_first.token_limit, TOKEN_LIMIT);
+    assert_eq!(
+        view_after_first.token_limit,
+        BudgetPreset::default().token_limit()
+    );
     assert!(state.consume_dirty());
 
     let (mut state, _effects) = update(
         state,
         Msg::JobProgress {
             job_id: 1,
+            at_ms: 0,
             stage: Stage::Tokenizing,
             tokens: Some(150),
             bytes: None,
@@ -110,6 +119,7 @@ fn token_totals_accumulate_and_replace_previous_values() {
         state,
         Msg::JobProgress {
             job_id: 2,
+            at_ms: 0,
             stage: Stage::Tokenizing,
             tokens: Some(50),
             bytes: None,
@@ -119,3 +129,209 @@ fn token_totals_accumulate_and_replace_previous_values() {
     assert_eq!(state.view().total_tokens, 200);
     assert!(state.consume_dirty());
 }
+
+#[test]
+fn rate_limit_retry_is_shown_until_next_progress() {
+    let (state, _) = submit_urls(AppState::new(), "https://a.example.com");
+    let (state, _) = update(
+        state,
+        Msg::JobRetryScheduled {
+            job_id: 1,
+            delay_secs: 30,
+        },
+    );
+    assert_eq!(state.view().jobs[0].retry_in_secs, Some(30));
+
+    let (state, _) = update(
+        state,
+        Msg::JobProgress {
+            job_id: 1,
+            at_ms: 0,
+            stage: Stage::Downloading,
+            tokens: None,
+            bytes: Some(0),
+            content_preview: None,
+        },
+    );
+    assert_eq!(state.view().jobs[0].retry_in_secs, None);
+}
+
+#[test]
+fn refined_token_count_replaces_the_estimate_in_row_and_total() {
+    let (state, _) = submit_urls(AppState::new(), "https://a.example.com\n");
+    let (state, _) = update(
+        state,
+        Msg::JobProgress {
+            job_id: 1,
+            at_ms: 0,
+            stage: Stage::Tokenizing,
+            tokens: Some(120),
+            bytes: None,
+            content_preview: None,
+        },
+    );
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 0,
+            result: JobResultKind::Success,
+            content_preview: None,
+            extracted_links: Vec::new(),
+        },
+    );
+
+    let (state, _) = update(
+        state,
+        Msg::TokenCountRefined {
+            job_id: 1,
+            tokens: 100,
+        },
+    );
+
+    let view = state.view();
+    assert_eq!(view.jobs[0].tokens, Some(100));
+    assert_eq!(view.jobs[0].stage, Stage::Done);
+    assert_eq!(view.total_tokens, 100);
+}
+
+#[test]
+fn selected_budget_preset_sets_the_limit_and_notifies_the_engine() {
+    let (state, effects) = update(
+        AppState::new(),
+        Msg::BudgetPresetSelected(BudgetPreset::Gpt4o),
+    );
+    assert_eq!(
+        effects,
+        vec![
+            Effect::TokenBudgetChanged { tokens: 128_000 },
+            Effect::PersistState {
+                session: state.persisted_session(),
+            },
+        ]
+    );
+    let view = state.view();
+    assert_eq!(view.budget, BudgetPreset::Gpt4o);
+    assert_eq!(view.token_limit, 128_000);
+
+    let (state, _) = update(
+        state,
+        Msg::BudgetPresetSelected(BudgetPreset::Custom(32_000)),
+    );
+    assert_eq!(state.view().token_limit, 32_000);
+    assert_eq!(state.budget().id(), "32000");
+    assert_eq!(BudgetPreset::from_id("Gemini"), Some(BudgetPreset::Gemini));
+    assert_eq!(BudgetPreset::from_id("0"), None);
+}
+
+#[test]
+fn activity_log_records_job_and_export_events_in_order() {
+    let (state, _) = submit_urls(
+        AppState::new(),
+        "https://a.example.com\nhttps://b.example.com",
+    );
+    let progress = |job_id, stage| Msg::JobProgress {
+        job_id,
+        at_ms: 0,
+        stage,
+        tokens: None,
+        bytes: None,
+        content_preview: None,
+    };
+    let done = |job_id, result| Msg::JobDone {
+        job_id,
+        at_ms: 0,
+        result,
+        content_preview: None,
+        extracted_links: Vec::new(),
+    };
+    let (state, _) = update(state, progress(1, Stage::Downloading));
+    let (state, _) = update(state, progress(1, Stage::Converting));
+    let (state, _) = update(state, done(1, JobResultKind::Success));
+    let (state, _) = update(state, done(2, JobResultKind::Failed));
+    let (state, _) = update(
+        state,
+        Msg::ExportCompleted {
+            exported_utc: "2024-01-01T00:00:00Z".to_string(),
+        },
+    );
+    let (state, _) = update(
+        state,
+        Msg::RestoreLastExport("2023-01-01T00:00:00Z".to_string()),
+    );
+
+    assert_eq!(
+        state.view().activity_log,
+        vec![
+            ActivityEvent::JobStarted {
+                job_id: 1,
+                url: "https://a.example.com".to_string(),
+            },
+            ActivityEvent::JobFinished {
+                job_id: 1,
+                url: "https://a.example.com".to_string(),
+                result: JobResultKind::Success,
+            },
+            ActivityEvent::JobFailed {
+                job_id: 2,
+                url: "https://b.example.com".to_string(),
+            },
+            ActivityEvent::ExportCompleted {
+                exported_utc: "2024-01-01T00:00:00Z".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn activity_log_keeps_only_the_most_recent_events() {
+    let mut state = AppState::new();
+    for n in 0..60 {
+        state = update(
+            state,
+            Msg::ExportCompleted {
+                exported_utc: n.to_string(),
+            },
+        )
+        .0;
+    }
+    let log = state.view().activity_log;
+    assert_eq!(log.len(), 50);
+    assert_eq!(
+        log.first(),
+        Some(&ActivityEvent::ExportCompleted {
+            exported_utc: "10".to_string(),
+        })
+    );
+}
+
+#[test]
+fn elapsed_time_runs_from_the_first_stage_to_completion() {
+    let (state, _) = submit_urls(AppState::new(), "https://slow.example");
+    let progress = |at_ms, stage| Msg::JobProgress {
+        job_id: 1,
+        at_ms,
+        stage,
+        tokens: None,
+        bytes: None,
+        content_preview: None,
+    };
+    let elapsed = |state: &AppState| state.view().jobs[0].elapsed_ms;
+
+    let (state, _) = update(state, progress(1_000, Stage::Queued));
+    assert_eq!(elapsed(&state), Some(0));
+    let (state, _) = update(state, progress(5_000, Stage::Downloading));
+    let (state, _) = update(state, progress(7_500, Stage::Converting));
+    assert_eq!(elapsed(&state), Some(2_500));
+    let (state, _) = update(
+        state,
+        Msg::JobDone {
+            job_id: 1,
+            at_ms: 9_000,
+            result: JobResultKind::Success,
+            content_preview: None,
+            extracted_links: Vec::new(),
+        },
+    );
+    assert_eq!(elapsed(&state), Some(4_000));
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use engine_logging::engine_info;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::{self, JoinSet};
+use tokio::time::{sleep_until, Instant};
+
+use crate::engine::{
+    domain_key, run_export, run_job, EngineCommand, QueuedJob, QueuedWork, SessionShared,
+    WorkerState,
+};
+use crate::export::ExportScope;
+use crate::fetch::{Fetcher, ProgressSink, ReqwestFetcher};
+use crate::fixture::FixtureFetcher;
+use crate::monitor::run_watch_check;
+use crate::warc::WarcFetcher;
+use crate::{EngineConfig, EngineEvent, JobId};
+
+/// Engine handle for embedding in an existing tokio runtime.
+///
+/// Unlike [`crate::EngineHandle`] this spawns no thread: the worker is a task on the
+/// caller's runtime and events are awaited with [`AsyncEngineHandle::next_event`].
+pub struct AsyncEngineHandle {
+    cmd_tx: UnboundedSender<EngineCommand>,
+    event_rx: UnboundedReceiver<EngineEvent>,
+}
+
+impl AsyncEngineHandle {
+    /// Spawn the worker task. Must be called from within a tokio runtime.
+    pub fn spawn(config: EngineConfig) -> Self {
+        let (cmd_tx, cmd_rx) = unbounded_channel();
+        let (event_tx, event_rx) = unbounded_channel();
+        tokio::spawn(run_worker(
+            cmd_rx,
+            Arc::new(TokioProgressSink { tx: event_tx }),
+            Arc::new(config),
+        ));
+        Self { cmd_tx, event_rx }
+    }
+
+    pub fn enqueue(&self, job_id: JobId, url: impl Into<String>) {
+        self.enqueue_labeled(job_id, url, None);
+    }
+
+    /// Like [`AsyncEngineHandle::enqueue`], labeled with the paste or batch
+    /// the URL came from.
+    pub fn enqueue_labeled(
+        &self,
+        job_id: JobId,
+        url: impl Into<String>,
+        source_batch: Option<String>,
+    ) {
+        let _ = self.cmd_tx.send(EngineCommand::Enqueue(QueuedJob {
+            job_id,
+            url: url.into(),
+            source_batch,
+        }));
+    }
+
+    pub fn stop(&self, immediate: bool) {
+        let _ = self.cmd_tx.send(EngineCommand::Stop { immediate });
+    }
+
+    /// Accept new jobs again after [`AsyncEngineHandle::stop`].
+    pub fn resume(&self) {
+        let _ = self.cmd_tx.send(EngineCommand::Resume);
+    }
+
+    pub fn request_export(&self) {
+        self.request_export_scoped(ExportScope::All);
+    }
+
+    pub fn request_export_scoped(&self, scope: ExportScope) {
+        let _ = self.cmd_tx.send(EngineCommand::Export(scope));
+    }
+
+    /// See [`crate::EngineHandle::watch`].
+    pub fn watch(&self, watch_id: JobId, url: impl Into<String>, interval: Duration) {
+        let _ = self.cmd_tx.send(EngineCommand::Watch {
+            watch_id,
+            url: url.into(),
+            interval,
+        });
+    }
+
+    pub fn unwatch(&self, watch_id: JobId) {
+        let _ = self.cmd_tx.send(EngineCommand::Unwatch { watch_id });
+    }
+
+    /// See [`crate::EngineHandle::set_token_budget`].
+    pub fn set_token_budget(&self, tokens: u64) {
+        let _ = self.cmd_tx.send(EngineCommand::SetTokenBudget(tokens));
+    }
+
+    /// See [`crate::EngineHandle::set_pinned_urls`].
+    pub fn set_pinned_urls(&self, urls: Vec<String>) {
+        let _ = self.cmd_tx.send(EngineCommand::SetPinnedUrls(urls));
+    }
+
+    /// See [`crate::EngineHandle::set_export_min_quality`].
+    pub fn set_export_min_quality(&self, min_score: Option<u8>) {
+        let _ = self
+            .cmd_tx
+            .send(EngineCommand::SetExportMinQuality(min_score));
+    }
+
+    /// See [`crate::EngineHandle::reconfigure`].
+    pub fn reconfigure(&self, config: EngineConfig) {
+        let _ = self
+            .cmd_tx
+            .send(EngineCommand::Reconfigure(Box::new(config)));
+    }
+
+    /// Wait for the next engine event. Returns `None` once the worker has stopped.
+    pub async fn next_event(&mut self) -> Option<EngineEvent> {
+        self.event_rx.recv().await
+    }
+}
+
+struct TokioProgressSink {
+    tx: UnboundedSender<EngineEvent>,
+}
+
+impl ProgressSink for TokioProgressSink {
+    fn emit(&self, event: EngineEvent) {
+        let _ = self.tx.send(event);
+    }
+}
+
+/// What a task on the worker's [`JoinSet`] reports when it ends.
+enum TaskDone {
+    /// A job, with the tokens it wrote.
+    Job(u32),
+    /// A watch check, with the page digest unless the check failed.
+    WatchCheck(Option<u64>),
+}
+
+/// Worker shared by [`AsyncEngineHandle`] and [`crate::EngineHandle`].
+///
+/// Up to `max_concurrent_jobs` jobs and watch checks run as tasks at once,
+/// limited per host by `max_concurrent_per_domain`. The loop ends once the
+/// command channel is closed and the remaining queue has drained.
+pub(crate) async fn run_worker(
+    mut cmd_rx: UnboundedReceiver<EngineCommand>,
+    sink: Arc<dyn ProgressSink>,
+    mut config: Arc<EngineConfig>,
+) {
+    let mut fetcher = fetcher_for(&config);
+    let mut session = Arc::new(SessionShared::from_config(&config));
+    let mut worker = WorkerState::from_config(&config).with_memory(session.memory.clone());
+    let mut running = JoinSet::new();
+    let mut domains: HashMap<task::Id, String> = HashMap::new();
+    let mut checks: HashMap<task::Id, JobId> = HashMap::new();
+    let mut commands_open = true;
+
+    loop {
+        while let Ok(cmd) = cmd_rx.try_recv() {
+            worker.handle_command(cmd, sink.as_ref());
+        }
+        if let Some(next) = worker.take_config() {
+            engine_info!("[Engine] Reconfigured, writing to {:?}", next.output_dir);
+            config = Arc::new(next);
+            fetcher = fetcher_for(&config);
+            session = Arc::new(SessionShared::from_config(&config));
+            worker.set_memory(session.memory.clone());
+        }
+        while let Some(check) = worker.next_watch_check(std::time::Instant::now()) {
+            let domain = domain_key(&check.url);
+            let watch_id = check.watch_id;
+            let check = run_watch_check(check, fetcher.clone(), sink.clone(), config.clone());
+            let handle = running.spawn(async move { TaskDone::WatchCheck(check.await) });
+            domains.insert(handle.id(), domain);
+            checks.insert(handle.id(), watch_id);
+        }
+
+        match worker.next_work(sink.as_ref()) {
+            Some(QueuedWork::Export(scope)) => {
+                // Export does blocking file IO; keep it off the async workers.
+                let sink = sink.clone();
+                let mut config = EngineConfig::clone(&config);
+                if worker.export_min_quality.is_some() {
+                    config.export_min_quality = worker.export_min_quality;
+                }
+                let token_budget = worker.token_budget;
+                let pinned_urls = worker.pinned_urls.clone();
+                let report = session.stats.report();
+                let _ = task::spawn_blocking(move || {
+                    run_export(
+                        &scope,
+                        &config,
+                        token_budget,
+                        pinned_urls,
+                        report,
+                        sink.as_ref(),
+                    )
+                })
+                .await;
+            }
+            Some(QueuedWork::Job(job)) => {
+                let domain = domain_key(&job.url);
+                let job = run_job(
+                    job,
+                    fetcher.clone(),
+                    sink.clone(),
+                    config.clone(),
+                    session.clone(),
+                    worker.cancel_token.child_token(),
+                );
+                let handle = running.spawn(async move { TaskDone::Job(job.await) });
+                domains.insert(handle.id(), domain);
+            }
+            None if running.is_empty() && worker.take_drained() => {
+                sink.emit(EngineEvent::AllJobsDrained);
+            }
+            None if !commands_open && running.is_empty() => break,
+            None => {
+                // Wait for a command, a finished task, or a due watch check.
+                // A check already past due waits for its host to free up.
+                let due = worker
+                    .watches
+                    .next_due()
+                    .filter(|due| *due > std::time::Instant::now());
+                tokio::select! {
+                    cmd = cmd_rx.recv(), if commands_open => match cmd {
+                        Some(cmd) => worker.handle_command(cmd, sink.as_ref()),
+                        None => {
+                            commands_open = false;
+                            worker.watches.clear();
+                        }
+                    },
+                    Some(joined) = running.join_next_with_id() => {
+                        let (id, tokens, digest) = match joined {
+                            Ok((id, TaskDone::Job(tokens))) => (id, tokens, None),
+                            Ok((id, TaskDone::WatchCheck(digest))) => (id, 0, digest),
+                            Err(err) => (err.id(), 0, None),
+                        };
+                        if let Some(domain) = domains.remove(&id) {
+                            match checks.remove(&id) {
+                                Some(watch_id) => worker.watch_checked(&domain, watch_id, digest),
+                                None => worker.job_finished(&domain, tokens),
+                            }
+                        }
+                    },
+                    _ = sleep_until_due(due) => {}
+                }
+            }
+        }
+    }
+    engine_info!("[Engine] Worker stopped");
+}
+
+fn fetcher_for(config: &EngineConfig) -> Arc<dyn Fetcher> {
+    match (&config.offline_archive, &config.fixture_dir) {
+        (Some(archive), _) => Arc::new(WarcFetcher::new(
+            archive.clone(),
+            config.fetch_settings.clone(),
+        )),
+        (None, Some(dir)) => Arc::new(FixtureFetcher::new(
+            dir.clone(),
+            config.fetch_settings.clone(),
+        )),
+        (None, None) => Arc::new(ReqwestFetcher::new(config.fetch_settings.clone())),
+    }
+}
+
+async fn sleep_until_due(due: Option<std::time::Instant>) {
+    match due {
+        Some(due) => sleep_until(Instant::from_std(due)).await,
+        None => std::future::pending().await,
+    }
+}
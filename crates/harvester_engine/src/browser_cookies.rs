@@ -0,0 +1,411 @@
+//! Cookies read straight from an installed browser's profile.
+//!
+//! Chrome and Edge keep cookies in a SQLite `Cookies` database with values
+//! encrypted by AES-256-GCM; the key sits in the profile's `Local State`,
+//! itself wrapped with the Windows DPAPI. Firefox keeps them unencrypted in
+//! `cookies.sqlite`. The databases are copied before reading because a
+//! running browser holds them locked.
+use std::path::{Path, PathBuf};
+
+use base64::Engine as _;
+use engine_logging::engine_warn;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::cookies::{Cookie, CookieImportError, CookieJar};
+
+/// Seconds between 1601-01-01, Chromium's epoch, and the Unix epoch.
+const CHROMIUM_EPOCH_OFFSET_SECS: u64 = 11_644_473_600;
+
+/// From this `meta` version on, Chromium prefixes each decrypted value with
+/// the SHA-256 of its host.
+const CHROMIUM_HOST_DIGEST_VERSION: i64 = 24;
+
+/// A browser whose cookie store can be imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+}
+
+impl Browser {
+    pub const ALL: [Browser; 3] = [Browser::Chrome, Browser::Edge, Browser::Firefox];
+
+    /// Stable id for environment variables, e.g. `chrome`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Browser::Chrome => "chrome",
+            Browser::Edge => "edge",
+            Browser::Firefox => "firefox",
+        }
+    }
+
+    /// Inverse of [`id`](Self::id), ignoring case.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let id = id.trim();
+        Self::ALL
+            .into_iter()
+            .find(|browser| browser.id().eq_ignore_ascii_case(id))
+    }
+}
+
+impl CookieJar {
+    /// Cookies of `browser`'s default profile for the current user.
+    pub fn load_browser(browser: Browser) -> Result<Self, CookieImportError> {
+        let missing = || CookieImportError::StoreNotFound(browser.id());
+        match browser {
+            Browser::Chrome | Browser::Edge => {
+                // The key is wrapped with the DPAPI, which only Windows has.
+                if !cfg!(windows) {
+                    return Err(CookieImportError::UnsupportedPlatform(browser.id()));
+                }
+                let vendor_dir = match browser {
+                    Browser::Chrome => Path::new("Google").join("Chrome"),
+                    _ => Path::new("Microsoft").join("Edge"),
+                };
+                let user_data = local_app_data()
+                    .ok_or_else(missing)?
+                    .join(vendor_dir)
+                    .join("User Data");
+                let profile = user_data.join("Default");
+                let db = [
+                    profile.join("Network").join("Cookies"),
+                    profile.join("Cookies"),
+                ]
+                .into_iter()
+                .find(|path| path.is_file())
+                .ok_or_else(missing)?;
+                let key = chromium_key(&user_data.join("Local State"))?;
+                read_chromium_store(&db, &key)
+            }
+            Browser::Firefox => {
+                let profiles = roaming_app_data()
+                    .ok_or_else(missing)?
+                    .join("Mozilla")
+                    .join("Firefox")
+                    .join("Profiles");
+                read_firefox_store(&latest_firefox_store(&profiles).ok_or_else(missing)?)
+            }
+        }
+    }
+}
+
+fn local_app_data() -> Option<PathBuf> {
+    std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+}
+
+fn roaming_app_data() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(PathBuf::from)
+}
+
+/// The most recently written `cookies.sqlite` among the Firefox profiles,
+/// which is the one in use.
+fn latest_firefox_store(profiles: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(profiles)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path().join("cookies.sqlite");
+            let modified = path.metadata().ok()?.modified().ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Open a read-only copy of `db`, with its WAL if any, so a running browser's
+/// lock does not get in the way and recent writes are included.
+fn open_copy(db: &Path) -> Result<(tempfile::TempDir, Connection), CookieImportError> {
+    let dir = tempfile::tempdir()?;
+    let copy = dir.path().join("cookies.sqlite");
+    std::fs::copy(db, &copy)?;
+    let wal = PathBuf::from(format!("{}-wal", db.display()));
+    if wal.is_file() {
+        std::fs::copy(&wal, dir.path().join("cookies.sqlite-wal"))?;
+    }
+    let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    Ok((dir, conn))
+}
+
+/// The AES key from Chromium's `Local State`, unwrapped with the DPAPI.
+fn chromium_key(local_state: &Path) -> Result<Vec<u8>, CookieImportError> {
+    let unavailable = |reason: &str| CookieImportError::KeyUnavailable(reason.to_string());
+    let state: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(local_state)?)
+        .map_err(|_| unavailable("Local State is not JSON"))?;
+    let encoded = state["os_crypt"]["encrypted_key"]
+        .as_str()
+        .ok_or_else(|| unavailable("Local State has no os_crypt.encrypted_key"))?;
+    let wrapped = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|_| unavailable("encrypted_key is not base64"))?;
+    let wrapped = wrapped
+        .strip_prefix(b"DPAPI")
+        .ok_or_else(|| unavailable("encrypted_key is not DPAPI-wrapped"))?;
+    dpapi::unprotect(wrapped).ok_or_else(|| unavailable("DPAPI refused to unwrap the key"))
+}
+
+/// Cookies from a Chromium `Cookies` database; `key` decrypts `v10` values.
+/// Values the key cannot open, such as Chrome's app-bound `v20` ones, are
+/// skipped with a warning.
+fn read_chromium_store(db: &Path, key: &[u8]) -> Result<CookieJar, CookieImportError> {
+    let key = UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| CookieImportError::KeyUnavailable("key is not 32 bytes".to_string()))?;
+    let (_dir, conn) = open_copy(db)?;
+    let version: i64 = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|version| version.parse().ok())
+        .unwrap_or(0);
+    let mut stmt = conn.prepare(
+        "SELECT host_key, path, is_secure, expires_utc, name, value, encrypted_value FROM cookies",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut cookies = Vec::new();
+    let mut skipped = 0;
+    while let Some(row) = rows.next()? {
+        let host: String = row.get(0)?;
+        let value: String = row.get(5)?;
+        let encrypted: Vec<u8> = row.get(6)?;
+        let value = if !value.is_empty() || encrypted.is_empty() {
+            value
+        } else {
+            match decrypt_chromium_value(&key, &encrypted, version) {
+                Some(value) => value,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            }
+        };
+        let expires_utc: i64 = row.get(3)?;
+        let expires =
+            (expires_utc.max(0) as u64 / 1_000_000).saturating_sub(CHROMIUM_EPOCH_OFFSET_SECS);
+        cookies.push(Cookie {
+            include_subdomains: host.starts_with('.'),
+            domain: host.trim_start_matches('.').to_ascii_lowercase(),
+            path: row.get(1)?,
+            secure: row.get::<_, i64>(2)? != 0,
+            expires,
+            name: row.get(4)?,
+            value,
+        });
+    }
+    if skipped > 0 {
+        engine_warn!("Skipped {} cookies that could not be decrypted", skipped);
+    }
+    Ok(CookieJar::from_cookies(cookies))
+}
+
+fn decrypt_chromium_value(key: &LessSafeKey, data: &[u8], version: i64) -> Option<String> {
+    let sealed = data
+        .strip_prefix(b"v10")
+        .or_else(|| data.strip_prefix(b"v11"))?;
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = key.open_in_place(nonce, Aad::empty(), &mut buffer).ok()?;
+    let plaintext = if version >= CHROMIUM_HOST_DIGEST_VERSION {
+        plaintext.get(32..)?
+    } else {
+        plaintext
+    };
+    String::from_utf8(plaintext.to_vec()).ok()
+}
+
+/// Cookies from a Firefox `cookies.sqlite` database.
+fn read_firefox_store(db: &Path) -> Result<CookieJar, CookieImportError> {
+    let (_dir, conn) = open_copy(db)?;
+    let mut stmt =
+        conn.prepare("SELECT host, path, isSecure, expiry, name, value FROM moz_cookies")?;
+    let cookies = stmt
+        .query_map([], |row| {
+            let host: String = row.get(0)?;
+            let expiry: i64 = row.get(3)?;
+            Ok(Cookie {
+                include_subdomains: host.starts_with('.'),
+                domain: host.trim_start_matches('.').to_ascii_lowercase(),
+                path: row.get(1)?,
+                secure: row.get::<_, i64>(2)? != 0,
+                expires: firefox_expiry_secs(expiry),
+                name: row.get(4)?,
+                value: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(CookieJar::from_cookies(cookies))
+}
+
+/// Firefox stored `expiry` in seconds and now stores milliseconds; no
+/// cookie expires past the year 5138, so larger values are milliseconds.
+fn firefox_expiry_secs(expiry: i64) -> u64 {
+    let expiry = expiry.max(0) as u64;
+    if expiry > 100_000_000_000 {
+        expiry / 1000
+    } else {
+        expiry
+    }
+}
+
+#[cfg(windows)]
+mod dpapi {
+    use windows_sys::Win32::Foundation::LocalFree;
+    use windows_sys::Win32::Security::Cryptography::{CryptUnprotectData, CRYPT_INTEGER_BLOB};
+
+    /// Unwrap `data` protected for the current user.
+    pub(super) fn unprotect(data: &[u8]) -> Option<Vec<u8>> {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: u32::try_from(data.len()).ok()?,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: std::ptr::null_mut(),
+        };
+        // SAFETY: `input` points at `data`, which outlives the call and is
+        // only read; the optional arguments are null.
+        let ok = unsafe {
+            CryptUnprotectData(
+                &input,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                0,
+                &mut output,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        // SAFETY: on success `output` holds `cbData` bytes allocated with
+        // LocalAlloc, freed right after copying them.
+        unsafe {
+            let plain = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+            LocalFree(output.pbData.cast());
+            Some(plain)
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod dpapi {
+    /// Never reached: `load_browser` refuses Chromium browsers off Windows.
+    pub(super) fn unprotect(_data: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seal(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).unwrap());
+        let nonce = [7u8; NONCE_LEN];
+        let mut buffer = plaintext.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut buffer,
+        )
+        .unwrap();
+        [b"v10".as_slice(), &nonce, &buffer].concat()
+    }
+
+    #[test]
+    fn chromium_store_decrypts_values_and_skips_app_bound_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = temp.path().join("Cookies");
+        let key = [3u8; 32];
+        let conn = Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE meta (key TEXT, value TEXT);
+             INSERT INTO meta VALUES ('version', '24');
+             CREATE TABLE cookies (host_key TEXT, path TEXT, is_secure INTEGER,
+                 expires_utc INTEGER, name TEXT, value TEXT, encrypted_value BLOB);",
+        )
+        .unwrap();
+        let digest_and_value = [[0u8; 32].as_slice(), b"abc"].concat();
+        let insert = "INSERT INTO cookies VALUES (?1, '/', 1, ?2, ?3, ?4, ?5)";
+        let expires_utc = (1_900_000_000 + CHROMIUM_EPOCH_OFFSET_SECS) * 1_000_000;
+        conn.execute(
+            insert,
+            rusqlite::params![
+                ".example.com",
+                expires_utc as i64,
+                "session",
+                "",
+                seal(&key, &digest_and_value)
+            ],
+        )
+        .unwrap();
+        conn.execute(
+            insert,
+            rusqlite::params!["plain.example.com", 0, "plain", "xyz", Vec::<u8>::new()],
+        )
+        .unwrap();
+        conn.execute(
+            insert,
+            rusqlite::params!["example.com", 0, "bound", "", b"v20opaque".to_vec()],
+        )
+        .unwrap();
+        drop(conn);
+
+        let jar = read_chromium_store(&db, &key).unwrap();
+        assert_eq!(jar.len(), 2);
+        let url = reqwest::Url::parse("https://plain.example.com/").unwrap();
+        assert_eq!(
+            jar.header_for(&url),
+            Some("session=abc; plain=xyz".to_string())
+        );
+    }
+
+    #[test]
+    fn firefox_store_reads_plain_values() {
+        let temp = tempfile::tempdir().unwrap();
+        let db = temp.path().join("cookies.sqlite");
+        let conn = Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_cookies (host TEXT, path TEXT, isSecure INTEGER,
+                 expiry INTEGER, name TEXT, value TEXT);
+             INSERT INTO moz_cookies VALUES ('.example.com', '/', 0, 1900000000000, 'sid', 'abc');
+             INSERT INTO moz_cookies VALUES ('other.org', '/', 0, 1, 'old', 'gone');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let jar = read_firefox_store(&db).unwrap();
+        assert_eq!(jar.len(), 2);
+        let url = |s: &str| reqwest::Url::parse(s).unwrap();
+        assert_eq!(
+            jar.header_for(&url("http://www.example.com/")),
+            Some("sid=abc".to_string())
+        );
+        assert_eq!(jar.header_for(&url("https://other.org/")), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn chromium_browsers_are_unsupported_off_windows() {
+        let err = CookieJar::load_browser(Browser::Edge).unwrap_err();
+        assert!(matches!(
+            err,
+            CookieImportError::UnsupportedPlatform("edge")
+        ));
+    }
+
+    #[test]
+    fn browser_ids_round_trip() {
+        for browser in Browser::ALL {
+            assert_eq!(Browser::from_id(browser.id()), Some(browser));
+        }
+        assert_eq!(Browser::from_id(" Edge "), Some(Browser::Edge));
+        assert_eq!(Browser::from_id("safari"), None);
+    }
+}
@@ -0,0 +1,369 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+use tokio::time::Duration;
+
+use crate::convert::Converter;
+use crate::crypt::Passphrase;
+use crate::engine::{EngineConfig, EngineHandle};
+use crate::export::ExportFormat;
+use crate::extract::Extractor;
+use crate::fetch::FetchSettings;
+use crate::filename::FilenameOptions;
+use crate::persist::{ensure_output_dir, PersistError};
+use crate::strip_rules::StripRules;
+use crate::summarize::Summarizer;
+use crate::token::TokenCounter;
+use crate::warc::WarcArchive;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EngineBuildError {
+    #[error("{name} must be greater than zero")]
+    ZeroDuration { name: &'static str },
+    #[error("{name} must be at least 1")]
+    ZeroLimit { name: &'static str },
+    #[error("chunk overlap ({overlap_tokens}) must be smaller than chunk size ({max_tokens})")]
+    ChunkOverlap {
+        overlap_tokens: usize,
+        max_tokens: usize,
+    },
+    #[error("output directory unusable: {0}")]
+    OutputDir(#[from] PersistError),
+    #[error("output directory {dir:?} is not writable: {source}")]
+    OutputNotWritable {
+        dir: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to start async runtime: {0}")]
+    Runtime(#[from] std::io::Error),
+}
+
+/// Validating constructor for [`EngineConfig`] and [`EngineHandle`].
+///
+/// Starts from the same defaults as [`EngineConfig::default_with_output`]; every
+/// setting is checked in [`EngineBuilder::build_config`] so bad values fail at
+/// construction rather than midway through a job.
+pub struct EngineBuilder {
+    config: EngineConfig,
+}
+
+impl EngineBuilder {
+    pub fn new(output_dir: PathBuf) -> Self {
+        Self {
+            config: EngineConfig::default_with_output(output_dir),
+        }
+    }
+
+    pub fn fetch_settings(mut self, settings: FetchSettings) -> Self {
+        self.config.fetch_settings = settings;
+        self
+    }
+
+    pub fn extractor(mut self, extractor: Arc<dyn Extractor>) -> Self {
+        self.config.extractor = extractor;
+        self
+    }
+
+    pub fn converter(mut self, converter: Arc<dyn Converter>) -> Self {
+        self.config.converter = converter;
+        self
+    }
+
+    pub fn token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.config.token_counter = token_counter;
+        self
+    }
+
+    /// Cache token counts by tokenizer and content hash in the output directory.
+    pub fn token_cache(mut self, enabled: bool) -> Self {
+        self.config.token_cache = enabled;
+        self
+    }
+
+    /// Recount written documents with `token_counter` in the background,
+    /// for use with a fast estimating primary counter.
+    pub fn backfill_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.config.backfill_token_counter = Some(token_counter);
+        self
+    }
+
+    /// Summarize each converted document; the default writes no summary.
+    pub fn summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.config.summarizer = summarizer;
+        self
+    }
+
+    pub fn fetched_utc(mut self, clock: impl Fn() -> String + Send + Sync + 'static) -> Self {
+        self.config.fetched_utc = Arc::new(clock);
+        self
+    }
+
+    pub fn extract_timeout(mut self, timeout: Duration) -> Self {
+        self.config.extract_timeout = timeout;
+        self
+    }
+
+    pub fn convert_timeout(mut self, timeout: Duration) -> Self {
+        self.config.convert_timeout = timeout;
+        self
+    }
+
+    pub fn tokenize_timeout(mut self, timeout: Duration) -> Self {
+        self.config.tokenize_timeout = timeout;
+        self
+    }
+
+    pub fn writing_timeout(mut self, timeout: Duration) -> Self {
+        self.config.writing_timeout = timeout;
+        self
+    }
+
+    /// Replace bytes invalid in a page's encoding instead of failing the job.
+    pub fn lossy_decode(mut self, enabled: bool) -> Self {
+        self.config.lossy_decode = enabled;
+        self
+    }
+
+    /// Defer new jobs while running ones buffer about `bytes` between them.
+    pub fn memory_budget(mut self, bytes: u64) -> Self {
+        self.config.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Fail a job that takes longer than `deadline` from fetch through write.
+    pub fn job_deadline(mut self, deadline: Duration) -> Self {
+        self.config.job_deadline = Some(deadline);
+        self
+    }
+
+    pub fn max_queued_jobs(mut self, max_queued_jobs: usize) -> Self {
+        self.config.max_queued_jobs = max_queued_jobs;
+        self
+    }
+
+    pub fn max_concurrent_jobs(mut self, max_concurrent_jobs: usize) -> Self {
+        self.config.max_concurrent_jobs = max_concurrent_jobs;
+        self
+    }
+
+    pub fn max_concurrent_per_domain(mut self, max_concurrent_per_domain: usize) -> Self {
+        self.config.max_concurrent_per_domain = max_concurrent_per_domain;
+        self
+    }
+
+    pub fn versioned_snapshots(mut self, enabled: bool) -> Self {
+        self.config.versioned_snapshots = enabled;
+        self
+    }
+
+    pub fn max_retry_after(mut self, max_retry_after: Duration) -> Self {
+        self.config.max_retry_after = max_retry_after;
+        self
+    }
+
+    pub fn stitch_pages(mut self, enabled: bool) -> Self {
+        self.config.stitch_pages = enabled;
+        self
+    }
+
+    pub fn max_stitched_pages(mut self, max_pages: usize) -> Self {
+        self.config.max_stitched_pages = max_pages;
+        self
+    }
+
+    pub fn filename_options(mut self, options: FilenameOptions) -> Self {
+        self.config.filename_options = options;
+        self
+    }
+
+    /// Learn blocks repeated across a domain's pages and strip them.
+    pub fn strip_site_chrome(mut self, enabled: bool) -> Self {
+        self.config.strip_site_chrome = enabled;
+        self
+    }
+
+    /// Strip the elements and lines matched by user `rules` from every page.
+    pub fn strip_rules(mut self, rules: StripRules) -> Self {
+        self.config.strip_rules = rules;
+        self
+    }
+
+    /// Harvest from a WARC archive instead of the network.
+    pub fn offline_archive(mut self, archive: Arc<WarcArchive>) -> Self {
+        self.config.offline_archive = Some(archive);
+        self
+    }
+
+    /// Harvest from HTML fixtures named by [`crate::fixture_filename`] instead of the network.
+    pub fn fixture_dir(mut self, dir: PathBuf) -> Self {
+        self.config.fixture_dir = Some(dir);
+        self
+    }
+
+    /// Append every fetched response to a WARC file at `path`.
+    pub fn warc_output(mut self, path: PathBuf) -> Self {
+        self.config.warc_output = Some(path);
+        self
+    }
+
+    /// Serve repeated harvests of a URL from a disk cache for `ttl`.
+    pub fn response_cache(mut self, ttl: Duration) -> Self {
+        self.config.response_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Keep exported chunks within the budget of the model the harvest is for.
+    pub fn token_budget(mut self, tokens: u64) -> Self {
+        self.config.token_budget = Some(tokens);
+        self
+    }
+
+    /// Stop fetching from a host once its documents hold `tokens` tokens.
+    pub fn domain_token_cap(mut self, tokens: u64) -> Self {
+        self.config.domain_token_cap = Some(tokens);
+        self
+    }
+
+    pub fn export_format(mut self, format: ExportFormat) -> Self {
+        self.config.export_format = format;
+        self
+    }
+
+    /// Leave documents with a quality score below `min_score` out of exports.
+    pub fn export_min_quality(mut self, min_score: u8) -> Self {
+        self.config.export_min_quality = Some(min_score);
+        self
+    }
+
+    /// Only export documents published between `from` and `until`
+    /// (`YYYY-MM-DD`, inclusive); `None` leaves that end open.
+    pub fn export_published(mut self, from: Option<String>, until: Option<String>) -> Self {
+        self.config.export_published = (from, until);
+        self
+    }
+
+    /// Only export documents with at least one of `keywords`.
+    pub fn export_keywords(mut self, keywords: Vec<String>) -> Self {
+        self.config.export_keywords = keywords;
+        self
+    }
+
+    /// Only export documents whose URLs came from the paste or batch labeled `label`.
+    pub fn export_source_batch(mut self, label: impl Into<String>) -> Self {
+        self.config.export_source_batch = Some(label.into());
+        self
+    }
+
+    /// Write concatenated exports and their manifests encrypted with `passphrase`.
+    pub fn encrypt_exports(mut self, passphrase: Passphrase) -> Self {
+        self.config.export_passphrase = Some(passphrase);
+        self
+    }
+
+    /// Set to zero to fail rate-limited jobs immediately.
+    pub fn max_rate_limit_retries(mut self, retries: usize) -> Self {
+        self.config.max_rate_limit_retries = retries;
+        self
+    }
+
+    /// Validate and return the config without starting an engine.
+    pub fn build_config(self) -> Result<EngineConfig, EngineBuildError> {
+        let config = self.config;
+        let durations = [
+            (
+                "fetch_settings.connect_timeout",
+                config.fetch_settings.connect_timeout,
+            ),
+            (
+                "fetch_settings.request_timeout",
+                config.fetch_settings.request_timeout,
+            ),
+            ("extract_timeout", config.extract_timeout),
+            ("convert_timeout", config.convert_timeout),
+            ("tokenize_timeout", config.tokenize_timeout),
+            ("writing_timeout", config.writing_timeout),
+            ("max_retry_after", config.max_retry_after),
+        ];
+        let deadline = config
+            .job_deadline
+            .map(|deadline| ("job_deadline", deadline));
+        if let Some((name, _)) = durations
+            .into_iter()
+            .chain(deadline)
+            .find(|(_, value)| value.is_zero())
+        {
+            return Err(EngineBuildError::ZeroDuration { name });
+        }
+        if config.fetch_settings.max_bytes == 0 {
+            return Err(EngineBuildError::ZeroLimit {
+                name: "fetch_settings.max_bytes",
+            });
+        }
+        let limits = [
+            ("max_queued_jobs", config.max_queued_jobs),
+            ("max_concurrent_jobs", config.max_concurrent_jobs),
+            (
+                "max_concurrent_per_domain",
+                config.max_concurrent_per_domain,
+            ),
+            ("max_stitched_pages", config.max_stitched_pages),
+        ];
+        if let Some((name, _)) = limits.iter().find(|(_, value)| *value == 0) {
+            return Err(EngineBuildError::ZeroLimit { name });
+        }
+        if config.memory_budget == Some(0) {
+            return Err(EngineBuildError::ZeroLimit {
+                name: "memory_budget",
+            });
+        }
+        if config.token_budget == Some(0) {
+            return Err(EngineBuildError::ZeroLimit {
+                name: "token_budget",
+            });
+        }
+        if config.domain_token_cap == Some(0) {
+            return Err(EngineBuildError::ZeroLimit {
+                name: "domain_token_cap",
+            });
+        }
+        if let ExportFormat::Chunks(chunking) = config.export_format {
+            if chunking.max_tokens == 0 {
+                return Err(EngineBuildError::ZeroLimit {
+                    name: "export_chunks.max_tokens",
+                });
+            }
+            if chunking.overlap_tokens >= chunking.max_tokens {
+                return Err(EngineBuildError::ChunkOverlap {
+                    overlap_tokens: chunking.overlap_tokens,
+                    max_tokens: chunking.max_tokens,
+                });
+            }
+        }
+        ensure_output_dir(&config.output_dir)?;
+        probe_writable(&config.output_dir).map_err(|source| {
+            EngineBuildError::OutputNotWritable {
+                dir: config.output_dir.clone(),
+                source,
+            }
+        })?;
+        Ok(config)
+    }
+
+    /// Validate the config, create the runtime, and start the engine worker.
+    pub fn build(self) -> Result<EngineHandle, EngineBuildError> {
+        let config = self.build_config()?;
+        let runtime = Runtime::new()?;
+        Ok(EngineHandle::start(config, runtime))
+    }
+}
+
+/// Write a few bytes to a temporary file in `dir` and remove it again, so an
+/// unwritable output directory fails here instead of at the first job.
+fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    let mut probe = tempfile::NamedTempFile::new_in(dir)?;
+    probe.write_all(b"probe")?;
+    probe.flush()?;
+    probe.close()
+}
@@ -0,0 +1,312 @@
+//! Token-bounded chunks of a markdown document, for retrieval pipelines.
+use crate::token::TokenCounter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Upper bound on tokens per chunk. A single word longer than this still
+    /// becomes its own chunk.
+    pub max_tokens: usize,
+    /// Tokens from the end of a chunk repeated at the start of the next one
+    /// within the same section.
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: 512,
+            overlap_tokens: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Position of the chunk in its document, from 0.
+    pub index: usize,
+    /// Headings enclosing the chunk, outermost first.
+    pub heading_path: Vec<String>,
+    pub text: String,
+    pub token_count: u32,
+}
+
+/// Heading level (1-6) and text.
+type Heading = (usize, String);
+
+/// A line of the document (or a whole fenced code block) with its size.
+struct Unit<'a> {
+    text: &'a str,
+    tokens: usize,
+}
+
+/// Split `markdown` into chunks of at most `options.max_tokens` tokens.
+///
+/// Every heading starts a new chunk; within a section, chunks break between
+/// lines, and only lines longer than a chunk are split between words.
+pub fn chunk_markdown(
+    markdown: &str,
+    options: &ChunkOptions,
+    token_counter: &dyn TokenCounter,
+) -> Vec<Chunk> {
+    let max_tokens = options.max_tokens.max(1);
+    let overlap_tokens = options.overlap_tokens.min(max_tokens - 1);
+    let mut chunks = Vec::new();
+    let mut heading_path: Vec<Heading> = Vec::new();
+    let mut current: Vec<Unit> = Vec::new();
+
+    for (line, heading) in lines(markdown) {
+        if let Some((level, title)) = heading {
+            flush(&mut chunks, &mut current, &heading_path, 0);
+            heading_path.retain(|(outer, _)| *outer < level);
+            heading_path.push((level, title));
+        }
+        let tokens = token_counter.count(line) as usize;
+        if tokens > max_tokens {
+            for piece in split_words(line, max_tokens, token_counter) {
+                push_unit(
+                    &mut chunks,
+                    &mut current,
+                    &heading_path,
+                    piece,
+                    token_counter,
+                    max_tokens,
+                    overlap_tokens,
+                );
+            }
+        } else if !line.trim().is_empty() {
+            push_unit(
+                &mut chunks,
+                &mut current,
+                &heading_path,
+                line,
+                token_counter,
+                max_tokens,
+                overlap_tokens,
+            );
+        }
+    }
+    flush(&mut chunks, &mut current, &heading_path, 0);
+    chunks
+}
+
+fn push_unit<'a>(
+    chunks: &mut Vec<Chunk>,
+    current: &mut Vec<Unit<'a>>,
+    heading_path: &[Heading],
+    text: &'a str,
+    token_counter: &dyn TokenCounter,
+    max_tokens: usize,
+    overlap_tokens: usize,
+) {
+    let tokens = token_counter.count(text) as usize;
+    let used: usize = current.iter().map(|unit| unit.tokens).sum();
+    if used + tokens > max_tokens && !current.is_empty() {
+        // Keep only as much overlap as still leaves room for this unit.
+        flush(
+            chunks,
+            current,
+            heading_path,
+            overlap_tokens.min(max_tokens.saturating_sub(tokens)),
+        );
+    }
+    current.push(Unit { text, tokens });
+}
+
+/// Emit the pending units as a chunk, keeping trailing units worth at most
+/// `keep_tokens` to start the next one.
+fn flush(
+    chunks: &mut Vec<Chunk>,
+    current: &mut Vec<Unit>,
+    heading_path: &[Heading],
+    keep_tokens: usize,
+) {
+    if current.is_empty() {
+        return;
+    }
+    let text = current
+        .iter()
+        .map(|unit| unit.text.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n");
+    chunks.push(Chunk {
+        index: chunks.len(),
+        heading_path: heading_path
+            .iter()
+            .map(|(_, title)| title.clone())
+            .collect(),
+        text,
+        token_count: current.iter().map(|unit| unit.tokens as u32).sum(),
+    });
+
+    let mut kept = 0;
+    let mut keep_from = current.len();
+    while keep_from > 0 && kept + current[keep_from - 1].tokens <= keep_tokens {
+        keep_from -= 1;
+        kept += current[keep_from].tokens;
+    }
+    // Overlap that would swallow the whole chunk would repeat it forever.
+    if keep_from == 0 {
+        keep_from = current.len();
+    }
+    current.drain(..keep_from);
+}
+
+/// Tokens per top-level section of `markdown` as `(heading, tokens)`, in
+/// document order. The top level is the shallowest heading level used more
+/// than once, so a lone title heading does not swallow the whole page; text
+/// before the first such heading is listed as `(intro)`. Empty when the
+/// document has fewer than two sections.
+pub(crate) fn section_token_counts(
+    markdown: &str,
+    token_counter: &dyn TokenCounter,
+) -> Vec<(String, u32)> {
+    let lines = lines(markdown);
+    let mut levels = [0usize; 7];
+    for (level, _) in lines.iter().filter_map(|(_, heading)| heading.as_ref()) {
+        levels[*level] += 1;
+    }
+    let Some(top) = (1..=6)
+        .find(|&level| levels[level] > 1)
+        .or_else(|| (1..=6).find(|&level| levels[level] > 0))
+    else {
+        return Vec::new();
+    };
+
+    let mut sections: Vec<(String, String)> = vec![("(intro)".to_string(), String::new())];
+    for (line, heading) in lines {
+        if let Some((_, title)) = heading.filter(|(level, _)| *level <= top) {
+            sections.push((title, String::new()));
+        }
+        if let Some((_, text)) = sections.last_mut() {
+            text.push_str(line);
+        }
+    }
+    let counts: Vec<(String, u32)> = sections
+        .into_iter()
+        .filter(|(heading, text)| heading != "(intro)" || !text.trim().is_empty())
+        .map(|(heading, text)| (heading, token_counter.count(&text)))
+        .collect();
+    if counts.len() < 2 {
+        return Vec::new();
+    }
+    counts
+}
+
+/// Lines of `markdown` with fenced code blocks kept whole, each paired with
+/// its heading level and text when it is an ATX heading.
+pub(crate) fn lines(markdown: &str) -> Vec<(&str, Option<Heading>)> {
+    let mut result = Vec::new();
+    let mut fence_start = None;
+    let mut offset = 0;
+    for line in markdown.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with("```");
+        match fence_start {
+            None if is_fence => fence_start = Some(offset),
+            None => result.push((line, heading(line))),
+            Some(begin) if is_fence => {
+                result.push((&markdown[begin..offset + line.len()], None));
+                fence_start = None;
+            }
+            Some(_) => {}
+        }
+        offset += line.len();
+    }
+    if let Some(begin) = fence_start {
+        result.push((&markdown[begin..], None));
+    }
+    result
+}
+
+fn heading(line: &str) -> Option<Heading> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?.trim();
+    ((1..=6).contains(&level) && !title.is_empty()).then(|| (level, title.to_string()))
+}
+
+/// Pieces of `line` of at most `max_tokens` tokens each, split between words.
+fn split_words<'a>(
+    line: &'a str,
+    max_tokens: usize,
+    token_counter: &dyn TokenCounter,
+) -> Vec<&'a str> {
+    let mut pieces = Vec::new();
+    let mut start = None;
+    let mut tokens = 0;
+    let mut end = 0;
+    for (offset, word) in word_spans(line) {
+        let word_tokens = token_counter.count(word) as usize;
+        if let Some(begin) = start {
+            if tokens + word_tokens > max_tokens {
+                pieces.push(&line[begin..end]);
+                start = None;
+                tokens = 0;
+            }
+        }
+        start.get_or_insert(offset);
+        tokens += word_tokens;
+        end = offset + word.len();
+    }
+    if let Some(begin) = start {
+        pieces.push(&line[begin..end]);
+    }
+    pieces
+}
+
+fn word_spans(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - line.as_ptr() as usize, word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WhitespaceTokenCounter;
+
+    fn chunk(markdown: &str, max_tokens: usize, overlap_tokens: usize) -> Vec<Chunk> {
+        let options = ChunkOptions {
+            max_tokens,
+            overlap_tokens,
+        };
+        chunk_markdown(markdown, &options, &WhitespaceTokenCounter)
+    }
+
+    #[test]
+    fn headings_start_chunks_and_build_the_heading_path() {
+        let markdown = "Intro line\n# Guide\nOne two\n## Setup\nThree four\n# Other\nFive";
+        let chunks = chunk(markdown, 100, 0);
+        let paths: Vec<_> = chunks.iter().map(|c| c.heading_path.join(" > ")).collect();
+        assert_eq!(paths, vec!["", "Guide", "Guide > Setup", "Other"]);
+        assert_eq!(chunks[2].text, "## Setup\nThree four");
+        assert_eq!(chunks[2].token_count, 4);
+        assert_eq!(chunks[3].index, 3);
+    }
+
+    #[test]
+    fn section_tokens_split_at_the_repeated_heading_level() {
+        let markdown = "# Guide\nIntro words\n## Install\nRun it\n### Linux\napt install x\n## Usage\nJust go\n";
+        assert_eq!(
+            section_token_counts(markdown, &WhitespaceTokenCounter),
+            vec![
+                ("Guide".to_string(), 4),
+                ("Install".to_string(), 9),
+                ("Usage".to_string(), 4),
+            ]
+        );
+        assert!(section_token_counts("Just one paragraph\n", &WhitespaceTokenCounter).is_empty());
+    }
+
+    #[test]
+    fn long_sections_split_at_lines_with_overlap() {
+        let markdown = "a b c\nd e f\ng h i\nj k l";
+        let chunks = chunk(markdown, 6, 3);
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["a b c\nd e f", "d e f\ng h i", "g h i\nj k l"]);
+    }
+
+    #[test]
+    fn oversized_lines_split_between_words() {
+        let chunks = chunk("one two three four five", 2, 0);
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["one two", "three four", "five"]);
+    }
+}
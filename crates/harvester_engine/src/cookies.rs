@@ -0,0 +1,192 @@
+//! Browser cookies for fetching pages behind a login.
+//!
+//! Cookies come from a browser's own store (see `browser_cookies`) or from a
+//! Netscape `cookies.txt` export, the format read by curl and wget.
+use std::fmt;
+use std::path::Path;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CookieImportError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: expected 7 tab-separated fields")]
+    Malformed { line: usize },
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("no {0} cookie store found")]
+    StoreNotFound(&'static str),
+    #[error("cookie key unavailable: {0}")]
+    KeyUnavailable(String),
+    #[error("{0} cookies can only be read on Windows")]
+    UnsupportedPlatform(&'static str),
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Cookie {
+    /// Lowercase host without a leading dot.
+    pub domain: String,
+    /// Also sent to subdomains of `domain`.
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    /// Unix expiry time; 0 for session cookies.
+    pub expires: u64,
+    pub name: String,
+    pub value: String,
+}
+
+impl Cookie {
+    fn matches(&self, url: &reqwest::Url, now_unix: u64) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+        let domain_ok = host == self.domain
+            || (self.include_subdomains
+                && host
+                    .strip_suffix(self.domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')));
+        domain_ok
+            && url.path().starts_with(&self.path)
+            && (!self.secure || url.scheme() == "https")
+            && (self.expires == 0 || self.expires > now_unix)
+    }
+}
+
+// Cookie values are credentials; keep them out of logs.
+impl fmt::Debug for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cookie")
+            .field("domain", &self.domain)
+            .field("path", &self.path)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cookies attached to fetches, matched per request so a redirect to another
+/// host never carries them along.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub(crate) fn from_cookies(cookies: Vec<Cookie>) -> Self {
+        Self { cookies }
+    }
+
+    pub fn load_netscape_file(path: &Path) -> Result<Self, CookieImportError> {
+        Self::parse_netscape(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse `cookies.txt` content. `#HttpOnly_` prefixed lines are cookies;
+    /// other `#` lines are comments.
+    pub fn parse_netscape(text: &str) -> Result<Self, CookieImportError> {
+        let mut cookies = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+                return Err(CookieImportError::Malformed { line: index + 1 });
+            };
+            cookies.push(Cookie {
+                domain: domain.trim_start_matches('.').to_ascii_lowercase(),
+                include_subdomains: subdomains.eq_ignore_ascii_case("TRUE"),
+                path: path.to_string(),
+                secure: secure.eq_ignore_ascii_case("TRUE"),
+                expires: expires.parse().unwrap_or(0),
+                name: name.to_string(),
+                value: value.to_string(),
+            });
+        }
+        Ok(Self { cookies })
+    }
+
+    /// Keep only cookies for `domain`: its own, its subdomains', and parent
+    /// domain cookies that are shared with subdomains.
+    pub fn for_domain(self, domain: &str) -> Self {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        let is_subdomain = |child: &str, parent: &str| {
+            child
+                .strip_suffix(parent)
+                .is_some_and(|prefix| prefix.ends_with('.'))
+        };
+        Self {
+            cookies: self
+                .cookies
+                .into_iter()
+                .filter(|cookie| {
+                    cookie.domain == domain
+                        || is_subdomain(&cookie.domain, &domain)
+                        || (cookie.include_subdomains && is_subdomain(&domain, &cookie.domain))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// `Cookie` header value for a request to `url`, if any cookie applies.
+    pub(crate) fn header_for(&self, url: &reqwest::Url) -> Option<String> {
+        let now_unix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let pairs: Vec<String> = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.matches(url, now_unix))
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = "# Netscape HTTP Cookie File\n\
+        .example.com\tTRUE\t/\tTRUE\t0\tsession\tabc\n\
+        #HttpOnly_news.example.com\tFALSE\t/paid\tFALSE\t0\ttoken\txyz\n\
+        other.org\tFALSE\t/\tFALSE\t1\texpired\told\n";
+
+    #[test]
+    fn header_matches_host_path_scheme_and_expiry() {
+        let jar = CookieJar::parse_netscape(EXPORT).unwrap();
+        assert_eq!(jar.len(), 3);
+
+        let url = |s: &str| reqwest::Url::parse(s).unwrap();
+        assert_eq!(
+            jar.header_for(&url("https://news.example.com/paid/story")),
+            Some("session=abc; token=xyz".to_string())
+        );
+        assert_eq!(
+            jar.header_for(&url("http://news.example.com/paid/story")),
+            Some("token=xyz".to_string())
+        );
+        assert_eq!(jar.header_for(&url("https://badexample.com/")), None);
+        assert_eq!(jar.header_for(&url("https://other.org/")), None);
+
+        assert_eq!(jar.clone().for_domain("news.example.com").len(), 2);
+        assert_eq!(jar.for_domain("other.org").len(), 1);
+    }
+
+    #[test]
+    fn malformed_line_is_reported() {
+        let err = CookieJar::parse_netscape("example.com\tTRUE\n").unwrap_err();
+        assert!(matches!(err, CookieImportError::Malformed { line: 1 }));
+    }
+}
@@ -0,0 +1,158 @@
+//! Passphrase encryption for files that may hold confidential harvests.
+//!
+//! Layout: `HARVENC1`, PBKDF2-HMAC-SHA256 iteration count (u32, big endian),
+//! 16-byte salt, 12-byte nonce, then the ChaCha20-Poly1305 ciphertext and tag.
+//! A [`Passphrase`] derives its key once with a fresh salt, so repeated
+//! saves stay cheap; each encryption still draws a fresh nonce.
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use thiserror::Error;
+
+const MAGIC: &[u8; 8] = b"HARVENC1";
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + NONCE_LEN;
+const PBKDF2_ITERATIONS: u32 = 200_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CryptError {
+    #[error("passphrase is empty")]
+    EmptyPassphrase,
+    #[error("not an encrypted file")]
+    NotEncrypted,
+    #[error("wrong passphrase or corrupted file")]
+    Unreadable,
+    #[error("no system randomness available")]
+    Random,
+}
+
+/// An encryption passphrase with its derived key.
+#[derive(Clone)]
+pub struct Passphrase {
+    secret: Arc<str>,
+    salt: [u8; SALT_LEN],
+    key: Arc<LessSafeKey>,
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Passphrase(..)")
+    }
+}
+
+impl Passphrase {
+    pub fn new(secret: &str) -> Result<Self, CryptError> {
+        if secret.is_empty() {
+            return Err(CryptError::EmptyPassphrase);
+        }
+        let mut salt = [0; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| CryptError::Random)?;
+        let key = derive_key(secret, &salt, PBKDF2_ITERATIONS);
+        Ok(Self {
+            secret: secret.into(),
+            salt,
+            key: Arc::new(key),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CryptError> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| CryptError::Random)?;
+        let mut out =
+            Vec::with_capacity(HEADER_LEN + plaintext.len() + CHACHA20_POLY1305.tag_len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&nonce);
+        let mut sealed = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&out[..HEADER_LEN]),
+                &mut sealed,
+            )
+            .map_err(|_| CryptError::Unreadable)?;
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    /// Decrypt data written by [`encrypt`](Self::encrypt) under this
+    /// passphrase, in this or an earlier session.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptError> {
+        if !is_encrypted(data) {
+            return Err(CryptError::NotEncrypted);
+        }
+        if data.len() < HEADER_LEN + CHACHA20_POLY1305.tag_len() {
+            return Err(CryptError::Unreadable);
+        }
+        let (header, sealed) = data.split_at(HEADER_LEN);
+        let iterations = u32::from_be_bytes(header[8..12].try_into().expect("4 bytes"));
+        let salt = &header[12..12 + SALT_LEN];
+        let nonce: [u8; NONCE_LEN] = header[12 + SALT_LEN..].try_into().expect("nonce length");
+
+        let derived;
+        let key = if salt == self.salt && iterations == PBKDF2_ITERATIONS {
+            self.key.as_ref()
+        } else {
+            derived = derive_key(&self.secret, salt, iterations);
+            &derived
+        };
+        let mut buffer = sealed.to_vec();
+        let plaintext = key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(header),
+                &mut buffer,
+            )
+            .map_err(|_| CryptError::Unreadable)?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// The data starts with the header [`Passphrase::encrypt`] writes.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn derive_key(secret: &str, salt: &[u8], iterations: u32) -> LessSafeKey {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).unwrap_or(NonZeroU32::MIN),
+        salt,
+        secret.as_bytes(),
+        &mut key,
+    );
+    LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &key).expect("32-byte key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_across_sessions_and_rejects_wrong_passphrases() {
+        let passphrase = Passphrase::new("correct horse").unwrap();
+        let sealed = passphrase.encrypt(b"internal docs").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(passphrase.decrypt(&sealed).unwrap(), b"internal docs");
+
+        let next_session = Passphrase::new("correct horse").unwrap();
+        assert_eq!(next_session.decrypt(&sealed).unwrap(), b"internal docs");
+
+        let wrong = Passphrase::new("battery staple").unwrap();
+        assert_eq!(wrong.decrypt(&sealed), Err(CryptError::Unreadable));
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(passphrase.decrypt(&tampered), Err(CryptError::Unreadable));
+        assert_eq!(passphrase.decrypt(b"plain"), Err(CryptError::NotEncrypted));
+    }
+}
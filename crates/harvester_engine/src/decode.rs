@@ -5,35 +5,175 @@ use encoding_rs::Encoding;
 pub struct DecodedHtml {
     pub html: String,
     pub encoding_label: String,
+    /// Invalid bytes were replaced with U+FFFD rather than failing the decode.
+    pub lossy: bool,
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
 pub enum DecodeError {
     #[error("failed to decode bytes with {encoding}: {message}")]
     DecodeFailure { encoding: String, message: String },
+    /// The body starts with the signature of a binary format, whatever its
+    /// Content-Type claims.
+    #[error("body is {detected} data, not text")]
+    BinaryContent { detected: &'static str },
 }
 
-/// Decode raw bytes into UTF-8 using: Content-Type charset -> BOM -> meta charset -> chardetng fallback.
+/// Decode raw bytes into UTF-8 using: BOM -> Content-Type charset -> meta charset -> chardetng fallback.
 pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> Result<DecodedHtml, DecodeError> {
+    decode_html_with(bytes, content_type, false)
+}
+
+/// Like [`decode_html`]; with `lossy`, bytes invalid in the chosen encoding
+/// become U+FFFD and the result is marked [`DecodedHtml::lossy`].
+pub fn decode_html_with(
+    bytes: &[u8],
+    content_type: Option<&str>,
+    lossy: bool,
+) -> Result<DecodedHtml, DecodeError> {
+    if let Some(detected) = sniff_binary(bytes) {
+        return Err(DecodeError::BinaryContent { detected });
+    }
+    let decode_with = |bytes: &[u8], enc: &'static Encoding| decode_with(bytes, enc, lossy);
+
     // 1) BOM aware decode using encoding_rs helper
     if let Some((encoding, _)) = Encoding::for_bom(bytes) {
         return decode_with(bytes, encoding);
     }
 
-    // 2) Content-Type header charset
+    // 2) Content-Type header charset; a bare `utf-16` leaves the byte order open
     if let Some(label) = content_type.and_then(extract_charset) {
         if let Some(enc) = Encoding::for_label(label.as_bytes()) {
+            let enc = if label.eq_ignore_ascii_case("utf-16") {
+                detect_bomless_utf16(bytes).unwrap_or(enc)
+            } else {
+                enc
+            };
             return decode_with(bytes, enc);
         }
     }
 
-    // 3) chardetng detection with hint from meta tags (full HTML)
+    // 3) UTF-16 without a BOM, which no ASCII-based scan can read
+    if let Some(enc) = detect_bomless_utf16(bytes) {
+        return decode_with(bytes, enc);
+    }
+
+    // 4) <meta charset> or http-equiv declaration near the top of the page;
+    // a page an ASCII scan can read is not UTF-16, whatever it says
+    if let Some(enc) = prescan_meta_charset(bytes) {
+        let enc = if is_utf16(enc) {
+            encoding_rs::UTF_8
+        } else {
+            enc
+        };
+        return decode_with(bytes, enc);
+    }
+
+    // 5) chardetng statistical guess over the full document
     let mut detector = EncodingDetector::new();
     detector.feed(bytes, true);
     let enc = detector.guess(None, true);
     decode_with(bytes, enc)
 }
 
+/// Leading signatures of formats that servers send labelled as HTML: bodies
+/// compressed without a `Content-Encoding`, downloads and embedded media.
+const BINARY_SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x1f\x8b", "gzip"),
+    (b"\x28\xb5\x2f\xfd", "zstd"),
+    (b"\xfd7zXZ\x00", "xz"),
+    (b"BZh", "bzip2"),
+    (b"PK\x03\x04", "zip"),
+    (b"7z\xbc\xaf\x27\x1c", "7z"),
+    (b"%PDF-", "pdf"),
+    (b"\x89PNG\r\n\x1a\n", "png"),
+    (b"\xff\xd8\xff", "jpeg"),
+    (b"GIF8", "gif"),
+];
+
+/// Name of the binary format `bytes` start with, if any.
+pub(crate) fn sniff_binary(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() >= 12 && bytes.starts_with(b"RIFF") && &bytes[8..12] == b"WEBP" {
+        return Some("webp");
+    }
+    BINARY_SIGNATURES
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, name)| *name)
+}
+
+/// Bytes searched for a meta charset declaration; browsers look at 1024,
+/// but some pages put long comments or scripts ahead of it.
+const META_PRESCAN_BYTES: usize = 2048;
+
+/// Encoding named by a `<meta charset>` or `<meta http-equiv="Content-Type">`
+/// tag in the first [`META_PRESCAN_BYTES`] of the page.
+fn prescan_meta_charset(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = bytes[..bytes.len().min(META_PRESCAN_BYTES)].to_ascii_lowercase();
+    let mut rest = head.as_slice();
+    while let Some(start) = find(rest, b"<meta") {
+        let tag = &rest[start..];
+        let tag = &tag[..find(tag, b">").unwrap_or(tag.len())];
+        if let Some(enc) = find(tag, b"charset=").and_then(|at| charset_value(&tag[at + 8..])) {
+            return Some(enc);
+        }
+        rest = &rest[start + 5..];
+    }
+    None
+}
+
+/// Byte order of a UTF-16 page without a BOM: the one in which its head reads
+/// as a meta declaration of UTF-16, else the one in which its markup reads
+/// as ASCII with a zero byte beside every character.
+fn detect_bomless_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(META_PRESCAN_BYTES * 2) & !1];
+    for enc in [encoding_rs::UTF_16LE, encoding_rs::UTF_16BE] {
+        let (text, _) = enc.decode_without_bom_handling(head);
+        if prescan_meta_charset(text.as_bytes()).is_some_and(is_utf16) {
+            return Some(enc);
+        }
+    }
+    let pairs = head.len() / 2;
+    if pairs < 4 {
+        return None;
+    }
+    let zero_high = |high: usize, low: usize| {
+        head.chunks_exact(2)
+            .filter(|pair| pair[high] == 0 && pair[low] != 0)
+            .count()
+    };
+    if zero_high(1, 0) * 2 > pairs {
+        Some(encoding_rs::UTF_16LE)
+    } else if zero_high(0, 1) * 2 > pairs {
+        Some(encoding_rs::UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn is_utf16(enc: &'static Encoding) -> bool {
+    enc == encoding_rs::UTF_16LE || enc == encoding_rs::UTF_16BE
+}
+
+fn charset_value(value: &[u8]) -> Option<&'static Encoding> {
+    let value = value.trim_ascii_start();
+    let value = value
+        .strip_prefix(b"\"")
+        .or_else(|| value.strip_prefix(b"'"))
+        .unwrap_or(value);
+    let end = value
+        .iter()
+        .position(|b| matches!(b, b'"' | b'\'' | b';' | b'/' | b'>') || b.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    Encoding::for_label(&value[..end])
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
 fn extract_charset(content_type: &str) -> Option<String> {
     content_type
         .split(';')
@@ -48,9 +188,13 @@ fn extract_charset(content_type: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-fn decode_with(bytes: &[u8], enc: &'static Encoding) -> Result<DecodedHtml, DecodeError> {
+fn decode_with(
+    bytes: &[u8],
+    enc: &'static Encoding,
+    lossy: bool,
+) -> Result<DecodedHtml, DecodeError> {
     let (text, _, had_errors) = enc.decode(bytes);
-    if had_errors {
+    if had_errors && !lossy {
         return Err(DecodeError::DecodeFailure {
             encoding: enc.name().to_string(),
             message: "decoding error".into(),
@@ -59,5 +203,6 @@ fn decode_with(bytes: &[u8], enc: &'static Encoding) -> Result<DecodedHtml, Deco
     Ok(DecodedHtml {
         html: text.into_owned(),
         encoding_label: enc.name().to_string(),
+        lossy: had_errors,
     })
 }
@@ -0,0 +1,135 @@
+//! Line diff of a re-written document against the version it replaces.
+use std::fs;
+use std::path::PathBuf;
+
+use crate::persist::{AtomicFileWriter, PersistError};
+use crate::preview::strip_frontmatter;
+
+/// Unchanged lines kept around each change.
+const CONTEXT_LINES: usize = 2;
+/// Longer diffs are cut off with a marker line.
+const MAX_DIFF_LINES: usize = 400;
+
+/// Difference between the previous and current markdown body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentDiff {
+    pub added: usize,
+    pub removed: usize,
+    /// Changed lines prefixed with `+`/`-`, with a little context; hunks are
+    /// separated by `@@` lines. Empty when nothing changed.
+    pub text: String,
+}
+
+impl ContentDiff {
+    pub fn is_unchanged(&self) -> bool {
+        self.added == 0 && self.removed == 0
+    }
+}
+
+/// Diff two markdown documents, ignoring their frontmatter.
+pub fn diff_documents(previous: &str, current: &str) -> ContentDiff {
+    let previous: Vec<&str> = strip_frontmatter(previous).lines().collect();
+    let current: Vec<&str> = strip_frontmatter(current).lines().collect();
+    let lines = diff::slice(&previous, &current);
+    let changed: Vec<bool> = lines
+        .iter()
+        .map(|line| !matches!(line, diff::Result::Both(..)))
+        .collect();
+
+    let mut result = ContentDiff::default();
+    let mut out = Vec::new();
+    let mut last_emitted: Option<usize> = None;
+    for (index, line) in lines.iter().enumerate() {
+        let near_change = changed
+            [index.saturating_sub(CONTEXT_LINES)..(index + CONTEXT_LINES + 1).min(changed.len())]
+            .contains(&true);
+        match line {
+            diff::Result::Left(text) => {
+                result.removed += 1;
+                out.push(format!("-{text}"));
+            }
+            diff::Result::Right(text) => {
+                result.added += 1;
+                out.push(format!("+{text}"));
+            }
+            diff::Result::Both(text, _) if near_change => {
+                if last_emitted.is_some_and(|last| last + 1 < index) {
+                    out.push("@@".to_string());
+                }
+                out.push(format!(" {text}"));
+            }
+            diff::Result::Both(..) => continue,
+        }
+        last_emitted = Some(index);
+    }
+
+    if out.len() > MAX_DIFF_LINES {
+        let omitted = out.len() - MAX_DIFF_LINES;
+        out.truncate(MAX_DIFF_LINES);
+        out.push(format!("… {omitted} more diff lines"));
+    }
+    result.text = out.join("\n");
+    result
+}
+
+/// Write `content`, diffing it against the file it replaces (if any).
+pub(crate) fn write_with_diff(
+    writer: &AtomicFileWriter,
+    filename: &str,
+    content: &str,
+) -> Result<WrittenDocument, PersistError> {
+    let target = writer.target_path(filename);
+    let previous = fs::read_to_string(&target).ok();
+    if previous.as_deref() == Some(content) {
+        return Ok(WrittenDocument::unchanged(target));
+    }
+    let path = writer.write(filename, content)?;
+    Ok(WrittenDocument {
+        path,
+        diff: previous.map(|previous| diff_documents(&previous, content)),
+        unchanged: false,
+    })
+}
+
+/// Where a document was stored and how it compares to what it replaced.
+#[derive(Debug)]
+pub(crate) struct WrittenDocument {
+    pub(crate) path: PathBuf,
+    pub(crate) diff: Option<ContentDiff>,
+    /// The file already held these exact bytes, so it was left untouched and
+    /// keeps its modification time.
+    pub(crate) unchanged: bool,
+}
+
+impl WrittenDocument {
+    pub(crate) fn unchanged(path: PathBuf) -> Self {
+        Self {
+            path,
+            diff: Some(ContentDiff::default()),
+            unchanged: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontmatter_only_changes_are_unchanged() {
+        let a = "---\nfetched_utc: 1\n---\nline one\nline two\n";
+        let b = "---\nfetched_utc: 2\n---\nline one\nline two\n";
+        let diff = diff_documents(a, b);
+        assert!(diff.is_unchanged());
+        assert!(diff.text.is_empty());
+    }
+
+    #[test]
+    fn changed_lines_are_counted_with_context() {
+        let previous = "a\nb\nc\nd\ne\nf\ng\nh\ni\n";
+        let current = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let diff = diff_documents(previous, current);
+        assert_eq!((diff.added, diff.removed), (2, 1));
+        assert_eq!(diff.text, " a\n-b\n+B\n c\n d\n@@\n h\n i\n+j");
+    }
+}
@@ -0,0 +1,47 @@
+//! Stable document identifiers, so downstream systems can join a page's
+//! frontmatter, manifest entry and chunk records.
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+
+/// RFC 4122 namespace for names that are URLs.
+const NAMESPACE_URL: [u8; 16] = [
+    0x6b, 0xa7, 0xb8, 0x11, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+];
+
+/// UUIDv5 of `url` in the URL namespace, in its hyphenated lowercase form.
+/// Pass the canonical URL when there is one, so every capture of a page
+/// shares the id.
+pub fn document_id(url: &str) -> String {
+    let mut name = NAMESPACE_URL.to_vec();
+    name.extend_from_slice(url.as_bytes());
+    let hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, &name);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash.as_ref()[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_uuid_v5_in_the_url_namespace() {
+        assert_eq!(
+            document_id("https://example.com/guide"),
+            "12435bc5-de75-5b1e-8ed6-4a5b4d890459"
+        );
+        assert_ne!(
+            document_id("https://example.com/guide"),
+            document_id("https://example.com/guide/")
+        );
+    }
+}
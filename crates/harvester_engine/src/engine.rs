@@ -1,25 +1,63 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 
-use engine_logging::{engine_debug, engine_info, engine_warn};
+use engine_logging::{engine_debug, engine_info, engine_warn, with_job_context};
+use sha2::{Digest, Sha256};
 use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tokio::time::{timeout, Duration};
 use tokio_util::sync::CancellationToken;
 
+use crate::async_engine::run_worker;
+use crate::chunk::{section_token_counts, ChunkOptions};
 use crate::convert::Converter;
-use crate::decode::decode_html;
-use crate::extract::Extractor;
-use crate::fetch::{ChannelProgressSink, FetchSettings, Fetcher, ReqwestFetcher};
-use crate::frontmatter::build_markdown_document;
-use crate::persist::AtomicFileWriter;
+use crate::crypt::Passphrase;
+use crate::export::{ExportFormat, ExportOptions, ExportScope};
+use crate::extract::{canonical_url, document_title, Extractor};
+use crate::fetch::{ChannelProgressSink, FetchSettings, Fetcher, ProgressSink};
+use crate::filename::FilenameOptions;
+use crate::frontmatter::{
+    build_markdown_document_with, recount_document_tokens, FrontmatterExtras,
+};
+use crate::keywords::extract_keywords;
+use crate::local::is_local_url;
+use crate::memory::MemoryBudget;
+use crate::monitor::{WatchCheck, WatchList};
+use crate::pipeline::{
+    convert_stage, decode_stage, extract_stage, fetch_stage, stitch_following_parts,
+    summarize_stage, tokenize_stage,
+};
 use crate::preview::prepare_preview_content;
+use crate::published::published_date;
+use crate::quality::{assess_quality, ReadingMetrics};
+use crate::response_cache::{ResponseCache, RESPONSE_CACHE_DIRNAME};
+use crate::sections::{slice_to_sections, with_fragment};
+use crate::session_report::{SessionReport, SessionStats};
+use crate::strip_rules::StripRules;
+use crate::summarize::Summarizer;
+use crate::template::SiteTemplates;
 use crate::token::TokenCounter;
+use crate::token_cache::{CachedTokenCounter, TokenCache};
+use crate::versions::DocumentStore;
+use crate::walls::detect_content_wall;
+use crate::warc::{WarcArchive, WarcWriter};
 use crate::{
-    deterministic_filename, EngineEvent, FailureKind, JobId, JobOutcome, JobProgress, Stage,
+    deterministic_filename_with, EngineEvent, FailureKind, JobId, JobOutcome, JobProgress, Stage,
 };
 
+pub(crate) const DEFAULT_MAX_QUEUED_JOBS: usize = 1_000;
+pub(crate) const DEFAULT_MAX_CONCURRENT_JOBS: usize = 4;
+pub(crate) const DEFAULT_MAX_CONCURRENT_PER_DOMAIN: usize = 2;
+pub(crate) const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+pub(crate) const DEFAULT_MAX_RATE_LIMIT_RETRIES: usize = 3;
+pub(crate) const DEFAULT_MAX_STITCHED_PAGES: usize = 10;
+pub(crate) const DEFAULT_JOB_DEADLINE: Duration = Duration::from_secs(180);
+pub(crate) const DEFAULT_MEMORY_BUDGET: u64 = 256 * 1024 * 1024;
+
 #[derive(Clone)]
 pub struct EngineConfig {
     pub fetch_settings: FetchSettings,
@@ -27,12 +65,88 @@ pub struct EngineConfig {
     pub extractor: Arc<dyn Extractor>,
     pub converter: Arc<dyn Converter>,
     pub token_counter: Arc<dyn TokenCounter>,
+    /// Remember token counts by tokenizer and content hash in
+    /// `{output_dir}/token_cache.tsv`, so unchanged documents are not recounted.
+    pub token_cache: bool,
+    /// Exact counter that recounts each written document in the background
+    /// when `token_counter` is a fast estimate; see
+    /// [`EngineEvent::TokenCountRefined`].
+    pub backfill_token_counter: Option<Arc<dyn TokenCounter>>,
+    /// Summarizes each converted document into its `summary:` field.
+    pub summarizer: Arc<dyn Summarizer>,
     /// Returns UTC timestamp string. Tests can inject fixed value.
     pub fetched_utc: Arc<dyn Fn() -> String + Send + Sync>,
     pub extract_timeout: Duration,
     pub convert_timeout: Duration,
     pub tokenize_timeout: Duration,
     pub writing_timeout: Duration,
+    /// Longest one attempt at a job may take from fetch through write, on
+    /// top of the per-stage timeouts; waits between rate-limit retries are
+    /// not counted. Fails the job with `ProcessingTimeout` at the stage it
+    /// had reached.
+    pub job_deadline: Option<Duration>,
+    /// Maximum number of jobs waiting in the engine queue; further enqueues are rejected.
+    pub max_queued_jobs: usize,
+    /// Jobs fetched and processed at the same time.
+    pub max_concurrent_jobs: usize,
+    /// Jobs running at the same time against one host, so a slow origin with
+    /// many queued URLs cannot occupy every worker.
+    pub max_concurrent_per_domain: usize,
+    /// Replace bytes the page's encoding cannot decode with U+FFFD and mark
+    /// the document `decode_lossy: true`, instead of failing the job.
+    pub lossy_decode: bool,
+    /// Approximate bytes running jobs may buffer between them; new jobs wait
+    /// while another one, at `fetch_settings.max_bytes`, would not fit.
+    pub memory_budget: Option<u64>,
+    /// How document titles become filenames.
+    pub filename_options: FilenameOptions,
+    /// Keep dated snapshots (`{stem}/{YYYY-MM-DD}.md`) instead of overwriting.
+    pub versioned_snapshots: bool,
+    /// Upper bound on how long a rate-limited job waits before retrying,
+    /// whatever the server's `Retry-After` asks for.
+    pub max_retry_after: Duration,
+    /// Rate-limited retries per job before it fails with `RateLimited`.
+    pub max_rate_limit_retries: usize,
+    /// Follow "next page" links and write all parts as one document.
+    pub stitch_pages: bool,
+    /// Most pages, including the first, stitched into one document.
+    pub max_stitched_pages: usize,
+    /// Leave documents scoring below this out of exports.
+    pub export_min_quality: Option<u8>,
+    /// Only export documents published within this inclusive range of
+    /// `YYYY-MM-DD` dates; either end may be open. Undated documents are
+    /// left out once either end is set.
+    pub export_published: (Option<String>, Option<String>),
+    /// Only export documents with one of these keywords; empty exports all.
+    pub export_keywords: Vec<String>,
+    /// Only export documents of this `source_batch`; `None` exports all.
+    pub export_source_batch: Option<String>,
+    /// Encrypt concatenated exports and their manifests with this passphrase.
+    pub export_passphrase: Option<Passphrase>,
+    /// Strip blocks that repeat verbatim across pages of one domain
+    /// (headers, footers, sidebars) from later pages in the session.
+    pub strip_site_chrome: bool,
+    /// User rules removing noise elements and lines from every page.
+    pub strip_rules: StripRules,
+    /// What exports write: concatenated text, chunked JSONL, or an Obsidian vault.
+    pub export_format: ExportFormat,
+    /// Serve fetches from this archive instead of the network.
+    pub offline_archive: Option<Arc<WarcArchive>>,
+    /// Serve fetches from HTML fixtures in this directory instead of the
+    /// network, see [`crate::FixtureFetcher`].
+    pub fixture_dir: Option<PathBuf>,
+    /// Append every fetched response to this WARC file.
+    pub warc_output: Option<PathBuf>,
+    /// Token budget of the model the harvest is for; no exported chunk, and
+    /// no export fitted to the context, is larger. Replaced at runtime by [`EngineHandle::set_token_budget`].
+    pub token_budget: Option<u64>,
+    /// Tokens the documents of one host may hold in a session. Once a host's
+    /// finished jobs reach it, its remaining jobs fail with
+    /// [`FailureKind::DomainBudgetExceeded`] instead of being fetched.
+    pub domain_token_cap: Option<u64>,
+    /// Keep fetched responses in `{output_dir}/.response_cache/` and serve
+    /// repeated harvests of a URL from there for this long.
+    pub response_cache_ttl: Option<Duration>,
 }
 
 impl EngineConfig {
@@ -43,52 +157,216 @@ impl EngineConfig {
             extractor: Arc::new(crate::ReadabilityLikeExtractor),
             converter: Arc::new(crate::LinkExtractingConverter::new()),
             token_counter: Arc::new(crate::WhitespaceTokenCounter),
+            token_cache: true,
+            backfill_token_counter: None,
+            summarizer: Arc::new(crate::NoSummarizer),
             fetched_utc: Arc::new(|| "1970-01-01T00:00:00Z".to_string()),
             extract_timeout: Duration::from_secs(30),
             convert_timeout: Duration::from_secs(15),
             tokenize_timeout: Duration::from_secs(10),
             writing_timeout: Duration::from_secs(10),
+            job_deadline: Some(DEFAULT_JOB_DEADLINE),
+            max_queued_jobs: DEFAULT_MAX_QUEUED_JOBS,
+            max_concurrent_jobs: DEFAULT_MAX_CONCURRENT_JOBS,
+            max_concurrent_per_domain: DEFAULT_MAX_CONCURRENT_PER_DOMAIN,
+            memory_budget: Some(DEFAULT_MEMORY_BUDGET),
+            lossy_decode: true,
+            filename_options: FilenameOptions::default(),
+            versioned_snapshots: false,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+            max_rate_limit_retries: DEFAULT_MAX_RATE_LIMIT_RETRIES,
+            stitch_pages: false,
+            max_stitched_pages: DEFAULT_MAX_STITCHED_PAGES,
+            export_min_quality: None,
+            export_published: (None, None),
+            export_keywords: Vec::new(),
+            export_source_batch: None,
+            export_passphrase: None,
+            strip_site_chrome: false,
+            strip_rules: StripRules::default(),
+            export_format: ExportFormat::default(),
+            offline_archive: None,
+            fixture_dir: None,
+            warc_output: None,
+            token_budget: None,
+            domain_token_cap: None,
+            response_cache_ttl: None,
         }
     }
 }
 
-enum EngineCommand {
-    Enqueue { job_id: JobId, url: String },
-    Stop,
-    Export,
+pub(crate) enum EngineCommand {
+    Enqueue(QueuedJob),
+    /// Close intake and cancel queued jobs; `immediate` also cancels running ones.
+    Stop {
+        immediate: bool,
+    },
+    /// Accept new jobs again after a `Stop`.
+    Resume,
+    Export(ExportScope),
+    Watch {
+        watch_id: JobId,
+        url: String,
+        interval: Duration,
+    },
+    Unwatch {
+        watch_id: JobId,
+    },
+    SetTokenBudget(u64),
+    SetPinnedUrls(Vec<String>),
+    SetExportMinQuality(Option<u8>),
+    /// Settings for jobs started from now on; queued jobs and watches stay.
+    Reconfigure(Box<EngineConfig>),
+}
+
+/// Host a job's URL is fetched from; the key for per-domain limits.
+pub(crate) fn domain_key(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_ascii_lowercase))
+        .unwrap_or_else(|| url.to_string())
+}
+
+/// A job as enqueued by the front end.
+pub(crate) struct QueuedJob {
+    pub(crate) job_id: JobId,
+    pub(crate) url: String,
+    /// Label of the paste or batch the URL came from, written as `source_batch:`.
+    pub(crate) source_batch: Option<String>,
+}
+
+/// Work items waiting for the worker thread.
+pub(crate) enum QueuedWork {
+    Job(QueuedJob),
+    Export(ExportScope),
 }
 
 #[derive(Clone)]
 pub struct EngineHandle {
-    cmd_tx: mpsc::Sender<EngineCommand>,
+    cmd_tx: UnboundedSender<EngineCommand>,
     event_rx: Arc<Mutex<mpsc::Receiver<EngineEvent>>>,
+    queue_capacity: Arc<AtomicUsize>,
 }
 
 impl EngineHandle {
+    /// Start an engine with an unvalidated config. Prefer [`crate::EngineBuilder`],
+    /// which reports configuration and runtime errors instead of panicking.
     pub fn new(config: EngineConfig) -> Self {
-        let (cmd_tx, cmd_rx) = mpsc::channel();
+        Self::start(config, Runtime::new().expect("tokio runtime"))
+    }
+
+    pub(crate) fn start(config: EngineConfig, runtime: Runtime) -> Self {
+        let (cmd_tx, cmd_rx) = unbounded_channel();
         let (event_tx, event_rx_raw) = mpsc::channel();
         let event_rx = Arc::new(Mutex::new(event_rx_raw));
+        let queue_capacity = Arc::new(AtomicUsize::new(config.max_queued_jobs.max(1)));
         let config = Arc::new(config);
 
-        thread::spawn(move || worker_loop(cmd_rx, event_tx, config));
+        thread::spawn(move || worker_loop(cmd_rx, event_tx, config, runtime));
 
-        Self { cmd_tx, event_rx }
+        Self {
+            cmd_tx,
+            event_rx,
+            queue_capacity,
+        }
+    }
+
+    /// Jobs the engine queues before it rejects more; front ends hold the
+    /// rest back until it drains.
+    pub fn queue_capacity(&self) -> usize {
+        self.queue_capacity.load(Ordering::Relaxed)
     }
 
     pub fn enqueue(&self, job_id: JobId, url: impl Into<String>) {
-        let _ = self.cmd_tx.send(EngineCommand::Enqueue {
+        self.enqueue_labeled(job_id, url, None);
+    }
+
+    /// Like [`EngineHandle::enqueue`], with the label of the paste or batch
+    /// the URL came from written to the document's frontmatter.
+    pub fn enqueue_labeled(
+        &self,
+        job_id: JobId,
+        url: impl Into<String>,
+        source_batch: Option<String>,
+    ) {
+        let _ = self.cmd_tx.send(EngineCommand::Enqueue(QueuedJob {
             job_id,
             url: url.into(),
-        });
+            source_batch,
+        }));
     }
 
-    pub fn stop(&self, _immediate: bool) {
-        let _ = self.cmd_tx.send(EngineCommand::Stop);
+    /// Stop taking jobs and cancel the queued ones. Running jobs finish
+    /// unless `immediate`, which cancels them too.
+    pub fn stop(&self, immediate: bool) {
+        let _ = self.cmd_tx.send(EngineCommand::Stop { immediate });
+    }
+
+    /// Accept new jobs again after [`EngineHandle::stop`].
+    pub fn resume(&self) {
+        let _ = self.cmd_tx.send(EngineCommand::Resume);
     }
 
     pub fn request_export(&self) {
-        let _ = self.cmd_tx.send(EngineCommand::Export);
+        self.request_export_scoped(ExportScope::All);
+    }
+
+    /// Request an export limited to `scope`; runs once queued jobs have drained.
+    pub fn request_export_scoped(&self, scope: ExportScope) {
+        let _ = self.cmd_tx.send(EngineCommand::Export(scope));
+    }
+
+    /// Re-fetch `url` every `interval`, emitting [`EngineEvent::WatchChanged`]
+    /// when its content changes. Watches end on [`EngineHandle::stop`].
+    pub fn watch(&self, watch_id: JobId, url: impl Into<String>, interval: Duration) {
+        let _ = self.cmd_tx.send(EngineCommand::Watch {
+            watch_id,
+            url: url.into(),
+            interval,
+        });
+    }
+
+    pub fn unwatch(&self, watch_id: JobId) {
+        let _ = self.cmd_tx.send(EngineCommand::Unwatch { watch_id });
+    }
+
+    /// Replace [`EngineConfig::token_budget`] for exports requested from now on.
+    pub fn set_token_budget(&self, tokens: u64) {
+        let _ = self.cmd_tx.send(EngineCommand::SetTokenBudget(tokens));
+    }
+
+    /// Documents of these URLs are always part of fitted exports requested from now on.
+    pub fn set_pinned_urls(&self, urls: Vec<String>) {
+        let _ = self.cmd_tx.send(EngineCommand::SetPinnedUrls(urls));
+    }
+
+    /// Override [`EngineConfig::export_min_quality`] for exports requested from
+    /// now on; `None` goes back to the configured threshold.
+    pub fn set_export_min_quality(&self, min_score: Option<u8>) {
+        let _ = self
+            .cmd_tx
+            .send(EngineCommand::SetExportMinQuality(min_score));
+    }
+
+    /// Apply `config` to jobs started from now on, without losing the queue
+    /// or watches. Running jobs finish with the settings they started with.
+    /// The new settings start a new session: the session report and
+    /// duplicate detection begin afresh, in the new output directory.
+    /// Prefer [`crate::EngineBuilder::build_config`], which validates it.
+    pub fn reconfigure(&self, config: EngineConfig) {
+        self.queue_capacity
+            .store(config.max_queued_jobs.max(1), Ordering::Relaxed);
+        let _ = self
+            .cmd_tx
+            .send(EngineCommand::Reconfigure(Box::new(config)));
+    }
+
+    /// Block until the next event arrives. Returns `None` once the engine has shut down.
+    ///
+    /// Intended for a single consumer thread; concurrent callers serialize on the receiver.
+    pub fn recv(&self) -> Option<EngineEvent> {
+        let rx = self.event_rx.lock().ok()?;
+        rx.recv().ok()
     }
 
     pub fn try_recv(&self) -> Option<EngineEvent> {
@@ -98,321 +376,1106 @@ impl EngineHandle {
             None
         }
     }
+
+    /// The engine's events without its commands, for a consumer thread that
+    /// should not keep the engine alive once every handle is dropped.
+    pub fn events(&self) -> EngineEvents {
+        EngineEvents {
+            event_rx: self.event_rx.clone(),
+        }
+    }
 }
 
-fn worker_loop(
-    cmd_rx: mpsc::Receiver<EngineCommand>,
-    event_tx: mpsc::Sender<EngineEvent>,
-    config: Arc<EngineConfig>,
-) {
-    let runtime = Runtime::new().expect("tokio runtime");
-    let fetcher = Arc::new(ReqwestFetcher::new(config.fetch_settings.clone()));
-    let mut queue: VecDeque<(JobId, String)> = VecDeque::new();
-    let mut accept_new = true;
-    let cancel_token = CancellationToken::new();
-
-    loop {
-        while let Ok(cmd) = cmd_rx.try_recv() {
-            match cmd {
-                EngineCommand::Enqueue { job_id, url } => {
-                    if accept_new {
-                        queue.push_back((job_id, url));
-                    } else {
-                        let _ = event_tx.send(EngineEvent::JobCompleted {
-                            job_id,
-                            result: Err(FailureKind::Cancelled),
-                        });
-                    }
+/// Receiving side of an [`EngineHandle`], see [`EngineHandle::events`].
+#[derive(Clone)]
+pub struct EngineEvents {
+    event_rx: Arc<Mutex<mpsc::Receiver<EngineEvent>>>,
+}
+
+impl EngineEvents {
+    /// Block until the next event arrives. Returns `None` once the engine
+    /// has shut down, which it does when the last handle is dropped.
+    pub fn recv(&self) -> Option<EngineEvent> {
+        let rx = self.event_rx.lock().ok()?;
+        rx.recv().ok()
+    }
+}
+
+pub(crate) struct WorkerState {
+    queue: VecDeque<QueuedWork>,
+    accept_new: bool,
+    pub(crate) cancel_token: CancellationToken,
+    capacity: usize,
+    saturated: bool,
+    pub(crate) watches: WatchList,
+    max_running: usize,
+    max_per_domain: usize,
+    /// Running jobs per host.
+    running: HashMap<String, usize>,
+    domain_token_cap: Option<u64>,
+    /// Tokens written by the finished jobs of each host this session.
+    domain_tokens: HashMap<String, u64>,
+    pub(crate) token_budget: Option<u64>,
+    pub(crate) pinned_urls: Vec<String>,
+    /// Set from the UI; takes precedence over `EngineConfig::export_min_quality`.
+    pub(crate) export_min_quality: Option<u8>,
+    /// `AllJobsDrained` was sent for the current stop.
+    drain_reported: bool,
+    memory: Arc<MemoryBudget>,
+    /// Bytes one more job may buffer at most.
+    job_memory_reserve: u64,
+    /// `MemoryThrottled` was sent and no job has started since.
+    memory_throttled: bool,
+    /// Set by `Reconfigure` until the worker loop switches to it.
+    next_config: Option<EngineConfig>,
+}
+
+impl WorkerState {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            accept_new: true,
+            cancel_token: CancellationToken::new(),
+            capacity: capacity.max(1),
+            saturated: false,
+            watches: WatchList::default(),
+            max_running: DEFAULT_MAX_CONCURRENT_JOBS,
+            max_per_domain: DEFAULT_MAX_CONCURRENT_PER_DOMAIN,
+            running: HashMap::new(),
+            domain_token_cap: None,
+            domain_tokens: HashMap::new(),
+            token_budget: None,
+            pinned_urls: Vec::new(),
+            export_min_quality: None,
+            drain_reported: false,
+            memory: Arc::default(),
+            job_memory_reserve: 0,
+            memory_throttled: false,
+            next_config: None,
+        }
+    }
+
+    pub(crate) fn from_config(config: &EngineConfig) -> Self {
+        let mut worker = Self::new(config.max_queued_jobs);
+        worker.apply_limits(config);
+        worker.token_budget = config.token_budget;
+        worker
+    }
+
+    fn apply_limits(&mut self, config: &EngineConfig) {
+        self.capacity = config.max_queued_jobs.max(1);
+        self.max_running = config.max_concurrent_jobs.max(1);
+        self.max_per_domain = config.max_concurrent_per_domain.max(1);
+        self.job_memory_reserve = config.fetch_settings.max_bytes;
+        self.domain_token_cap = config.domain_token_cap;
+    }
+
+    /// The config from the latest `Reconfigure`, once.
+    pub(crate) fn take_config(&mut self) -> Option<EngineConfig> {
+        self.next_config.take()
+    }
+
+    fn running_jobs(&self) -> usize {
+        self.running.values().sum()
+    }
+
+    pub(crate) fn queued_jobs(&self) -> usize {
+        self.queue
+            .iter()
+            .filter(|work| matches!(work, QueuedWork::Job(_)))
+            .count()
+    }
+
+    pub(crate) fn handle_command(&mut self, cmd: EngineCommand, sink: &dyn ProgressSink) {
+        match cmd {
+            EngineCommand::Enqueue(job) => {
+                let job_id = job.job_id;
+                if !self.accept_new {
+                    sink.emit(EngineEvent::JobCompleted {
+                        job_id,
+                        result: Err(FailureKind::Cancelled),
+                    });
+                } else if self.queued_jobs() >= self.capacity {
+                    engine_warn!("[Queue] Full ({}), rejecting job {}", self.capacity, job_id);
+                    sink.emit(EngineEvent::JobCompleted {
+                        job_id,
+                        result: Err(FailureKind::QueueFull),
+                    });
+                } else {
+                    self.queue.push_back(QueuedWork::Job(job));
+                    self.update_saturation(sink);
+                }
+            }
+            EngineCommand::Stop { immediate } => {
+                self.accept_new = false;
+                if immediate {
+                    self.cancel_token.cancel();
                 }
-                EngineCommand::Stop => {
-                    accept_new = false;
-                    cancel_token.cancel();
-                    // Cancel queued (not yet started) immediately.
-                    for (job_id, _) in queue.drain(..) {
-                        let _ = event_tx.send(EngineEvent::JobCompleted {
-                            job_id,
-                            result: Err(FailureKind::Cancelled),
-                        });
+                self.watches.clear();
+                // Cancel queued (not yet started) jobs immediately; pending exports stay.
+                let mut kept = VecDeque::new();
+                for work in self.queue.drain(..) {
+                    match work {
+                        QueuedWork::Job(job) => {
+                            sink.emit(EngineEvent::JobCompleted {
+                                job_id: job.job_id,
+                                result: Err(FailureKind::Cancelled),
+                            });
+                        }
+                        export @ QueuedWork::Export(_) => kept.push_back(export),
                     }
                 }
-                EngineCommand::Export => {
-                    // Export happens when queue is empty / idle; stash command for later processing.
-                    queue.push_front((0, "__EXPORT__".to_string()));
+                self.queue = kept;
+                self.update_saturation(sink);
+            }
+            EngineCommand::Resume => {
+                if !self.accept_new {
+                    self.accept_new = true;
+                    self.cancel_token = CancellationToken::new();
+                    self.domain_tokens.clear();
+                    self.drain_reported = false;
+                }
+            }
+            EngineCommand::Export(scope) => {
+                // Export happens when queue is empty / idle; stash command for later processing.
+                self.queue.push_front(QueuedWork::Export(scope));
+            }
+            EngineCommand::Watch {
+                watch_id,
+                url,
+                interval,
+            } => self.watches.watch(watch_id, url, interval, Instant::now()),
+            EngineCommand::Unwatch { watch_id } => self.watches.unwatch(watch_id),
+            EngineCommand::SetTokenBudget(tokens) => self.token_budget = Some(tokens),
+            EngineCommand::SetPinnedUrls(urls) => self.pinned_urls = urls,
+            EngineCommand::SetExportMinQuality(min_score) => self.export_min_quality = min_score,
+            EngineCommand::Reconfigure(config) => {
+                self.apply_limits(&config);
+                if config.token_budget.is_some() {
+                    self.token_budget = config.token_budget;
                 }
+                self.update_saturation(sink);
+                self.next_config = Some(*config);
             }
         }
+    }
 
-        if let Some((job_id, url)) = queue.pop_front() {
-            if url == "__EXPORT__" {
-                if queue.is_empty() {
-                    // Only export when no active jobs; run synchronously.
-                    if let Err(_err) = crate::export::build_concatenated_export(
-                        &config.output_dir,
-                        crate::export::ExportOptions::default(),
-                    ) {
-                        let _ = event_tx.send(EngineEvent::JobCompleted {
-                            job_id: 0,
-                            result: Err(FailureKind::ProcessingError),
-                        });
-                    }
-                } else {
-                    // Re-enqueue to try later.
-                    queue.push_back((job_id, url));
+    /// Share `memory` with the jobs so new ones are deferred while it is spent.
+    pub(crate) fn with_memory(mut self, memory: Arc<MemoryBudget>) -> Self {
+        self.set_memory(memory);
+        self
+    }
+
+    pub(crate) fn set_memory(&mut self, memory: Arc<MemoryBudget>) {
+        self.memory = memory;
+    }
+
+    /// Take the next work item that may start now.
+    ///
+    /// Jobs start in queue order, skipping those whose host is already at the
+    /// per-domain limit. A deferred export runs once no job is queued or
+    /// running. Returns `None` when everything waiting is blocked on a limit.
+    pub(crate) fn next_work(&mut self, sink: &dyn ProgressSink) -> Option<QueuedWork> {
+        self.reject_capped_jobs(sink);
+        let index = if self.running_jobs() < self.max_running {
+            self.queue.iter().position(|work| match work {
+                QueuedWork::Job(job) => {
+                    let active = self
+                        .running
+                        .get(&domain_key(&job.url))
+                        .copied()
+                        .unwrap_or(0);
+                    active < self.max_per_domain
                 }
-                continue;
+                QueuedWork::Export(_) => false,
+            })
+        } else {
+            None
+        };
+        // A lone job always runs, however large, so the queue cannot stall.
+        let index = match index {
+            Some(_)
+                if self.running_jobs() > 0 && !self.memory.has_room(self.job_memory_reserve) =>
+            {
+                self.report_memory_throttled(sink);
+                None
+            }
+            index => index,
+        };
+        let work = match index {
+            Some(index) => self.queue.remove(index),
+            None if self.running_jobs() == 0 && self.queued_jobs() == 0 => self.queue.pop_front(),
+            None => None,
+        };
+        if let Some(QueuedWork::Job(job)) = &work {
+            *self.running.entry(domain_key(&job.url)).or_default() += 1;
+            self.memory_throttled = false;
+        }
+        self.update_saturation(sink);
+        work
+    }
+
+    /// A due watch check whose host has a free slot, which it takes like a
+    /// job until [`WorkerState::watch_checked`].
+    pub(crate) fn next_watch_check(&mut self, now: Instant) -> Option<WatchCheck> {
+        if self.running_jobs() >= self.max_running {
+            return None;
+        }
+        let running = &self.running;
+        let max_per_domain = self.max_per_domain;
+        let check = self.watches.start_due(now, |url| {
+            running.get(&domain_key(url)).copied().unwrap_or(0) < max_per_domain
+        })?;
+        *self.running.entry(domain_key(&check.url)).or_default() += 1;
+        Some(check)
+    }
+
+    /// Release the slot held by a watch check and schedule the next one.
+    pub(crate) fn watch_checked(&mut self, domain: &str, watch_id: JobId, digest: Option<u64>) {
+        self.job_finished(domain, 0);
+        self.watches.record(watch_id, digest, Instant::now());
+    }
+
+    /// True once per stop, when no work is queued or running any more.
+    pub(crate) fn take_drained(&mut self) -> bool {
+        let drained = !self.accept_new
+            && !self.drain_reported
+            && self.queue.is_empty()
+            && self.running_jobs() == 0;
+        if drained {
+            self.drain_reported = true;
+        }
+        drained
+    }
+
+    fn report_memory_throttled(&mut self, sink: &dyn ProgressSink) {
+        if self.memory_throttled {
+            return;
+        }
+        self.memory_throttled = true;
+        sink.emit(EngineEvent::MemoryThrottled {
+            in_use_bytes: self.memory.in_use(),
+            budget_bytes: self.memory.limit().unwrap_or_default(),
+        });
+    }
+
+    /// Release the concurrency slot held by a job on `domain`, and count the
+    /// `tokens` it wrote against the domain's cap.
+    pub(crate) fn job_finished(&mut self, domain: &str, tokens: u32) {
+        if let Some(count) = self.running.get_mut(domain) {
+            *count -= 1;
+            if *count == 0 {
+                self.running.remove(domain);
             }
-            let fetcher = fetcher.clone();
-            let event_tx = event_tx.clone();
-            let config = config.clone();
-            let child_token = cancel_token.child_token();
-            runtime.block_on(async move {
-                run_job(job_id, url, fetcher.as_ref(), event_tx, config, child_token).await;
+        }
+        if tokens > 0 {
+            *self.domain_tokens.entry(domain.to_string()).or_default() += u64::from(tokens);
+        }
+    }
+
+    /// Fail the queued jobs of hosts that reached the per-domain token cap.
+    fn reject_capped_jobs(&mut self, sink: &dyn ProgressSink) {
+        let Some(cap) = self.domain_token_cap else {
+            return;
+        };
+        let domain_tokens = &self.domain_tokens;
+        let (rejected, kept): (VecDeque<_>, VecDeque<_>) =
+            self.queue.drain(..).partition(|work| match work {
+                QueuedWork::Job(job) => domain_tokens
+                    .get(&domain_key(&job.url))
+                    .is_some_and(|&tokens| tokens >= cap),
+                QueuedWork::Export(_) => false,
             });
-        } else {
-            // Block until next command arrives.
-            match cmd_rx.recv() {
-                Ok(cmd) => {
-                    // push back into the queue / handle stop.
-                    match cmd {
-                        EngineCommand::Enqueue { job_id, url } => {
-                            if accept_new {
-                                queue.push_back((job_id, url));
-                            } else {
-                                let _ = event_tx.send(EngineEvent::JobCompleted {
-                                    job_id,
-                                    result: Err(FailureKind::Cancelled),
-                                });
-                            }
-                        }
-                        EngineCommand::Stop => {
-                            accept_new = false;
-                            cancel_token.cancel();
-                            for (job_id, _) in queue.drain(..) {
-                                let _ = event_tx.send(EngineEvent::JobCompleted {
-                                    job_id,
-                                    result: Err(FailureKind::Cancelled),
-                                });
-                            }
-                        }
-                        EngineCommand::Export => {
-                            queue.push_front((0, "__EXPORT__".to_string()));
-                        }
-                    }
-                }
-                Err(_) => break,
+        self.queue = kept;
+        for work in rejected {
+            if let QueuedWork::Job(job) = work {
+                let domain = domain_key(&job.url);
+                engine_info!(
+                    "[Queue] {} reached its token cap, skipping job {}",
+                    domain,
+                    job.job_id
+                );
+                sink.emit(EngineEvent::JobCompleted {
+                    job_id: job.job_id,
+                    result: Err(FailureKind::DomainBudgetExceeded { domain, cap }),
+                });
             }
         }
     }
+
+    /// Signal saturation when the queue fills up, and resumption once it has
+    /// drained to half capacity, so intake doesn't flap around the limit.
+    fn update_saturation(&mut self, sink: &dyn ProgressSink) {
+        let queued = self.queued_jobs();
+        if !self.saturated && queued >= self.capacity {
+            self.saturated = true;
+            engine_info!("[Queue] Saturated at {} jobs", queued);
+            sink.emit(EngineEvent::QueueSaturated {
+                capacity: self.capacity,
+            });
+        } else if self.saturated && queued <= self.capacity / 2 {
+            self.saturated = false;
+            engine_info!("[Queue] Resumed at {} jobs", queued);
+            sink.emit(EngineEvent::QueueResumed);
+        }
+    }
 }
 
-async fn run_job(
-    job_id: JobId,
-    url: String,
-    fetcher: &dyn Fetcher,
+fn worker_loop(
+    cmd_rx: UnboundedReceiver<EngineCommand>,
     event_tx: mpsc::Sender<EngineEvent>,
     config: Arc<EngineConfig>,
-    cancel_token: CancellationToken,
+    runtime: Runtime,
 ) {
-    engine_info!("Job {} starting: {}", job_id, url);
-    let sink = ChannelProgressSink::new(event_tx.clone());
-
-    let fetch_result = fetcher.fetch(job_id, &url, &sink).await;
-    let fetch_output = match fetch_result {
-        Ok(out) => {
-            engine_debug!(
-                "Job {} fetched {} bytes from {}",
-                job_id,
-                out.metadata.byte_len,
-                out.metadata.final_url
+    let sink: Arc<dyn ProgressSink> = Arc::new(ChannelProgressSink::new(event_tx));
+    runtime.block_on(run_worker(cmd_rx, sink, config));
+}
+
+/// `token_budget` caps the chunk size of chunked exports and the size of
+/// exports fitted to the model's context, which always include the documents
+/// of `pinned_urls`; `session` goes into the manifest.
+pub(crate) fn run_export(
+    scope: &ExportScope,
+    config: &EngineConfig,
+    token_budget: Option<u64>,
+    pinned_urls: Vec<String>,
+    session: Option<SessionReport>,
+    sink: &dyn ProgressSink,
+) {
+    let exported_utc = (config.fetched_utc)();
+    let options = ExportOptions {
+        min_quality_score: config.export_min_quality,
+        published_from: config.export_published.0.clone(),
+        published_until: config.export_published.1.clone(),
+        keywords: config.export_keywords.clone(),
+        source_batch: config.export_source_batch.clone(),
+        encryption: config.export_passphrase.clone(),
+        session,
+        pinned_urls,
+        ..scope.to_options(&exported_utc)
+    };
+    let result = match &config.export_format {
+        ExportFormat::Concatenated => {
+            crate::export::build_concatenated_export(&config.output_dir, options)
+        }
+        ExportFormat::Chunks(chunking) => crate::export::build_chunked_export(
+            &config.output_dir,
+            options,
+            &ChunkOptions {
+                max_tokens: token_budget.map_or(chunking.max_tokens, |budget| {
+                    chunking.max_tokens.min(budget as usize)
+                }),
+                ..*chunking
+            },
+            config.token_counter.as_ref(),
+        ),
+        ExportFormat::ObsidianVault => {
+            crate::vault::build_obsidian_vault(&config.output_dir, options)
+        }
+        ExportFormat::Html => crate::html_export::build_html_export(&config.output_dir, options),
+        ExportFormat::FitToContext(order) => crate::export::build_fitted_export(
+            &config.output_dir,
+            options,
+            *order,
+            token_budget.unwrap_or(u64::MAX),
+            config.token_counter.as_ref(),
+        ),
+    };
+    match result {
+        Ok(summary) => {
+            engine_info!(
+                "[Export] Wrote {} docs ({} tokens) to {:?}",
+                summary.doc_count,
+                summary.total_tokens,
+                summary.output_path
             );
-            out
+            sink.emit(EngineEvent::ExportCompleted {
+                exported_utc,
+                summary,
+            });
         }
         Err(err) => {
-            // Error already logged in fetch.rs
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(err.kind),
+            engine_warn!("[Export] Failed in {:?}: {}", config.output_dir, err);
+            sink.emit(EngineEvent::ExportFailed {
+                message: err.to_string(),
             });
-            return;
         }
-    };
+    }
+}
 
-    // Check cancellation after fetching stage boundary.
-    if cancel_token.is_cancelled() {
-        let _ = event_tx.send(EngineEvent::JobCompleted {
-            job_id,
-            result: Err(FailureKind::Cancelled),
-        });
-        return;
+/// State shared by every job of one worker session.
+pub(crate) struct SessionShared {
+    pub(crate) templates: SiteTemplates,
+    pub(crate) warc: Option<WarcWriter>,
+    pub(crate) token_cache: Option<TokenCache>,
+    pub(crate) response_cache: Option<ResponseCache>,
+    pub(crate) stats: SessionStats,
+    pub(crate) memory: Arc<MemoryBudget>,
+    /// SHA-256 of each document body written this session, with the job and
+    /// file that hold it.
+    content_owners: Mutex<ContentOwners>,
+}
+
+type ContentOwners = HashMap<[u8; 32], (JobId, String)>;
+
+impl SessionShared {
+    pub(crate) fn from_config(config: &EngineConfig) -> Self {
+        let warc =
+            config
+                .warc_output
+                .as_deref()
+                .and_then(|path| match WarcWriter::append_to(path) {
+                    Ok(writer) => Some(writer),
+                    Err(err) => {
+                        engine_warn!("Cannot open WARC output {}: {}", path.display(), err);
+                        None
+                    }
+                });
+        Self {
+            templates: SiteTemplates::default(),
+            warc,
+            token_cache: config
+                .token_cache
+                .then(|| TokenCache::open(&config.output_dir)),
+            response_cache: config
+                .response_cache_ttl
+                .map(|ttl| ResponseCache::new(config.output_dir.join(RESPONSE_CACHE_DIRNAME), ttl)),
+            stats: SessionStats::default(),
+            memory: Arc::new(MemoryBudget::new(config.memory_budget)),
+            content_owners: Mutex::new(HashMap::new()),
+        }
     }
 
-    let decoded = match timeout(config.extract_timeout, async {
-        decode_html(
-            &fetch_output.bytes,
-            fetch_output.metadata.content_type.as_deref(),
-        )
-    })
-    .await
-    {
-        Ok(Ok(decoded)) => decoded,
-        Ok(Err(_)) => {
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(FailureKind::ProcessingError),
-            });
-            return;
+    /// The earlier job whose document has the same `body` under another
+    /// filename, if any; otherwise `job_id` becomes the owner of that content.
+    /// Re-harvesting the same page is not a duplicate: it rewrites its file.
+    pub(crate) fn duplicate_of(&self, job_id: JobId, filename: &str, body: &str) -> Option<JobId> {
+        let digest: [u8; 32] = Sha256::digest(body.as_bytes()).into();
+        let mut owners = self
+            .content_owners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (owner, owner_file) = owners
+            .entry(digest)
+            .or_insert_with(|| (job_id, filename.to_string()));
+        (*owner != job_id && owner_file != filename).then_some(*owner)
+    }
+
+    /// `token_counter` backed by the session's token cache, if enabled.
+    pub(crate) fn token_counter<'a>(
+        &'a self,
+        token_counter: &'a dyn TokenCounter,
+    ) -> CachedTokenCounter<'a> {
+        CachedTokenCounter::new(self.token_cache.as_ref(), token_counter)
+    }
+}
+
+/// Run `job` to completion, fetching again after rate limits. Returns the
+/// tokens of the document it wrote, 0 when it failed.
+pub(crate) async fn run_job(
+    job: QueuedJob,
+    fetcher: Arc<dyn Fetcher>,
+    sink: Arc<dyn ProgressSink>,
+    config: Arc<EngineConfig>,
+    session: Arc<SessionShared>,
+    cancel_token: CancellationToken,
+) -> u32 {
+    let job_id = job.job_id;
+    engine_info!("Job {} starting: {}", job_id, job.url);
+    session.stats.job_started();
+    let mut retries = 0;
+    let result = loop {
+        let tracker = StageTracker::new(sink.as_ref());
+        let attempt = run_pipeline(
+            &job,
+            fetcher.as_ref(),
+            &tracker,
+            &config,
+            &session,
+            &cancel_token,
+        );
+        // A write already handed to a blocking thread still completes; the
+        // document store replaces files atomically, so none is left half written.
+        let result = match config.job_deadline {
+            Some(deadline) => timeout(deadline, attempt).await.unwrap_or_else(|_| {
+                let stage = tracker.stage();
+                engine_warn!(
+                    "Job {} exceeded its {:?} deadline at stage {:?}",
+                    job_id,
+                    deadline,
+                    stage
+                );
+                Err(FailureKind::ProcessingTimeout { stage })
+            }),
+            None => attempt.await,
+        };
+        let Err(FailureKind::RateLimited { retry_after_secs }) = result else {
+            break result;
+        };
+        if retries >= config.max_rate_limit_retries {
+            break result;
         }
-        Err(_) => {
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(FailureKind::ProcessingTimeout {
-                    stage: Stage::Sanitizing,
-                }),
-            });
-            return;
+        retries += 1;
+        let delay = Duration::from_secs(retry_after_secs).min(config.max_retry_after);
+        engine_info!(
+            "Job {} rate limited, retry {} in {:?}",
+            job_id,
+            retries,
+            delay
+        );
+        sink.emit(EngineEvent::JobRetryScheduled { job_id, delay });
+        // The job keeps its per-host slot while waiting, so the rest of the
+        // queue for the same host backs off with it.
+        tokio::select! {
+            _ = cancel_token.cancelled() => break Err(FailureKind::Cancelled),
+            _ = tokio::time::sleep(delay) => {}
         }
     };
-
-    if cancel_token.is_cancelled() {
-        let _ = event_tx.send(EngineEvent::JobCompleted {
-            job_id,
-            result: Err(FailureKind::Cancelled),
+    let tokens = result
+        .as_ref()
+        .ok()
+        .and_then(|outcome| outcome.tokens)
+        .unwrap_or(0);
+    let written = result
+        .as_ref()
+        .ok()
+        .and_then(|outcome| Some((outcome.output_path.clone()?, outcome.tokens)));
+    session.stats.job_finished(&result);
+    sink.emit(EngineEvent::JobCompleted { job_id, result });
+    if let (Some(counter), Some((path, estimate))) = (&config.backfill_token_counter, written) {
+        let counter = counter.clone();
+        // Not awaited: the job's slot is free while the document is recounted.
+        tokio::task::spawn_blocking(move || {
+            with_job_context(job_id, || {
+                let counter = session.token_counter(counter.as_ref());
+                match recount_document_tokens(&path, &counter) {
+                    Ok(tokens) if Some(tokens) != estimate => {
+                        engine_debug!("Token count refined: {:?} -> {}", estimate, tokens);
+                        sink.emit(EngineEvent::TokenCountRefined { job_id, tokens });
+                    }
+                    Ok(_) => {}
+                    Err(err) => engine_warn!("Token backfill failed: {}", err),
+                }
+            })
         });
-        return;
     }
+    tokens
+}
 
-    let extracted = match timeout(config.extract_timeout, async {
-        config.extractor.extract(&decoded.html)
-    })
-    .await
-    {
-        Ok(content) => content,
-        Err(_) => {
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(FailureKind::ProcessingTimeout {
-                    stage: Stage::Converting,
-                }),
-            });
-            return;
-        }
-    };
+/// Forwards events while remembering the last stage the job reported, so a
+/// job that runs out of time can say where it was.
+struct StageTracker<'a> {
+    inner: &'a dyn ProgressSink,
+    stage: Mutex<Stage>,
+}
 
-    let conversion = match timeout(config.convert_timeout, async {
-        config.converter.to_markdown(
-            &extracted.content_html,
-            Some(fetch_output.metadata.final_url.as_str()),
-        )
-    })
-    .await
-    {
-        Ok(output) => output,
-        Err(_) => {
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(FailureKind::ProcessingTimeout {
-                    stage: Stage::Converting,
-                }),
-            });
-            return;
+impl<'a> StageTracker<'a> {
+    fn new(inner: &'a dyn ProgressSink) -> Self {
+        Self {
+            inner,
+            stage: Mutex::new(Stage::Downloading),
         }
-    };
+    }
 
-    let markdown = conversion.markdown;
-    let preview_content = prepare_preview_content(&markdown);
+    fn stage(&self) -> Stage {
+        self.stage
+            .lock()
+            .map(|stage| *stage)
+            .unwrap_or(Stage::Queued)
+    }
+}
 
-    let _ = event_tx.send(EngineEvent::Progress(JobProgress {
+impl ProgressSink for StageTracker<'_> {
+    fn emit(&self, event: EngineEvent) {
+        if let EngineEvent::Progress(progress) = &event {
+            if let Ok(mut stage) = self.stage.lock() {
+                *stage = progress.stage;
+            }
+        }
+        self.inner.emit(event);
+    }
+}
+
+/// Report that `job_id` entered `stage`, with whatever is known so far.
+fn emit_stage(
+    sink: &dyn ProgressSink,
+    job_id: JobId,
+    stage: Stage,
+    tokens: Option<u32>,
+    content_preview: Option<String>,
+) {
+    sink.emit(EngineEvent::Progress(JobProgress {
         job_id,
-        stage: Stage::Converting,
+        stage,
         bytes: None,
-        tokens: None,
-        content_preview: Some(preview_content.clone()),
+        tokens,
+        content_preview,
     }));
+}
 
+fn check_cancelled(cancel_token: &CancellationToken) -> Result<(), FailureKind> {
     if cancel_token.is_cancelled() {
-        let _ = event_tx.send(EngineEvent::JobCompleted {
-            job_id,
-            result: Err(FailureKind::Cancelled),
-        });
-        return;
+        Err(FailureKind::Cancelled)
+    } else {
+        Ok(())
     }
+}
 
-    let tokens = match timeout(config.tokenize_timeout, async {
-        config.token_counter.count(&markdown)
-    })
-    .await
-    {
-        Ok(t) => t,
-        Err(_) => {
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Err(FailureKind::ProcessingTimeout {
-                    stage: Stage::Tokenizing,
-                }),
-            });
-            return;
+async fn run_pipeline(
+    job: &QueuedJob,
+    fetcher: &dyn Fetcher,
+    sink: &dyn ProgressSink,
+    config: &EngineConfig,
+    session: &SessionShared,
+    cancel_token: &CancellationToken,
+) -> Result<JobOutcome, FailureKind> {
+    let (job_id, url) = (job.job_id, job.url.as_str());
+    let local_source = is_local_url(url);
+    let mut memory = session.memory.charge();
+    let response_cache = session.response_cache.as_ref().filter(|_| !local_source);
+    let cached = response_cache.and_then(|cache| cache.get(url));
+    let from_cache = cached.is_some();
+    let fetch_output = match cached {
+        Some(output) => {
+            engine_info!("Job {} served from the response cache", job_id);
+            output
+        }
+        None => {
+            let output = fetch_stage(job_id, url, fetcher, sink, config).await?;
+            if let Some(cache) = response_cache {
+                cache.put(url, &output);
+            }
+            output
         }
     };
-
-    let _ = event_tx.send(EngineEvent::Progress(JobProgress {
+    engine_debug!(
+        "Job {} fetched {} bytes from {}",
         job_id,
-        stage: Stage::Tokenizing,
-        bytes: None,
-        tokens: Some(tokens),
-        content_preview: None,
-    }));
-
-    if cancel_token.is_cancelled() {
-        let _ = event_tx.send(EngineEvent::JobCompleted {
-            job_id,
-            result: Err(FailureKind::Cancelled),
-        });
-        return;
+        fetch_output.metadata.byte_len,
+        fetch_output.metadata.final_url
+    );
+    if let Some(warc) = session.warc.as_ref().filter(|_| !local_source) {
+        if let Err(err) = warc.write_response(&fetch_output, &(config.fetched_utc)()) {
+            engine_warn!(
+                "Job {} could not append to {}: {}",
+                job_id,
+                warc.path().display(),
+                err
+            );
+        }
     }
 
-    let (token_count, doc) = build_markdown_document(
-        fetch_output.metadata.final_url.as_str(),
+    memory.add(fetch_output.bytes.len());
+
+    // Check cancellation after fetching stage boundary.
+    check_cancelled(cancel_token)?;
+    emit_stage(sink, job_id, Stage::Sanitizing, None, None);
+    let decoded = decode_stage(&fetch_output, config).await?;
+    memory.add(decoded.html.len());
+
+    check_cancelled(cancel_token)?;
+    emit_stage(sink, job_id, Stage::Converting, None, None);
+    let extracted = extract_stage(&decoded.html, config).await?;
+    engine_debug!("Job {} extracted via {}", job_id, extracted.strategy);
+    // The canonical URL names the document, so tracking-parameter variants and
+    // mirrors of one page are written to the same file.
+    let canonical = canonical_url(&decoded.html, &fetch_output.metadata.final_url);
+    let title = document_title(
         extracted.title.as_deref(),
+        canonical
+            .as_deref()
+            .unwrap_or(&fetch_output.metadata.final_url),
+    );
+    let mut conversion =
+        convert_stage(&extracted, &fetch_output.metadata.final_url, config).await?;
+    let stripped_chrome = config.strip_site_chrome.then(|| {
+        let final_url = &fetch_output.metadata.final_url;
+        let (markdown, stripped) =
+            session
+                .templates
+                .strip(&domain_key(final_url), final_url, &conversion.markdown);
+        conversion.markdown = markdown;
+        if stripped.blocks > 0 {
+            engine_info!(
+                "Job {} stripped {} repeated site lines ({} bytes)",
+                job_id,
+                stripped.blocks,
+                stripped.bytes
+            );
+        }
+        stripped
+    });
+    let quality = assess_quality(&decoded.html, &conversion.markdown);
+    if quality.is_low() {
+        engine_info!("Job {} flagged low quality: {:?}", job_id, quality);
+    }
+    let content_warning = detect_content_wall(&decoded.html, quality.word_count);
+    if let Some(warning) = content_warning {
+        engine_warn!("Job {} looks like partial content: {}", job_id, warning);
+    }
+    let page = stitch_following_parts(
+        job_id,
+        &decoded.html,
+        &fetch_output.metadata.final_url,
+        conversion,
+        fetcher,
+        sink,
+        config,
+    )
+    .await;
+
+    let (markdown, section) = slice_to_sections(url, page.markdown);
+    memory.add(markdown.len());
+    let reading = ReadingMetrics::of(&markdown);
+    let preview_content = prepare_preview_content(&markdown);
+
+    check_cancelled(cancel_token)?;
+    emit_stage(
+        sink,
+        job_id,
+        Stage::Tokenizing,
+        None,
+        Some(preview_content.clone()),
+    );
+    let token_counter = session.token_counter(config.token_counter.as_ref());
+    let tokens = tokenize_stage(&markdown, &token_counter, config).await?;
+    let summary = summarize_stage(title.as_deref(), &markdown, config).await;
+
+    check_cancelled(cancel_token)?;
+    emit_stage(sink, job_id, Stage::Writing, Some(tokens), None);
+    let fetched_utc = (config.fetched_utc)();
+    let (token_count, doc) = build_markdown_document_with(
+        &with_fragment(
+            canonical
+                .as_deref()
+                .unwrap_or(&fetch_output.metadata.final_url),
+            section.as_deref(),
+        ),
+        title.as_deref(),
         &decoded.encoding_label,
-        &(config.fetched_utc)(),
+        &fetched_utc,
         &markdown,
-        config.token_counter.as_ref(),
+        &token_counter,
+        &FrontmatterExtras {
+            part_urls: page.part_urls,
+            published: published_date(&decoded.html, &fetch_output.metadata.final_url),
+            reading: Some(reading),
+            quality_score: Some(quality.score),
+            content_warning,
+            summary,
+            keywords: extract_keywords(&markdown),
+            source_batch: job.source_batch.clone(),
+            section_tokens: section_token_counts(&markdown, &token_counter),
+            extraction: Some(extracted.strategy),
+            stripped_chrome_bytes: stripped_chrome
+                .filter(|stripped| stripped.blocks > 0)
+                .map(|stripped| stripped.bytes),
+            local_source,
+            decode_lossy: decoded.lossy,
+            response_headers: fetch_output.metadata.provenance_headers(),
+        },
     );
 
-    let filename = deterministic_filename(extracted.title.as_deref(), &url);
-    let writer = AtomicFileWriter::new(config.output_dir.clone());
+    let filename = deterministic_filename_with(
+        title.as_deref(),
+        &with_fragment(canonical.as_deref().unwrap_or(url), section.as_deref()),
+        &config.filename_options,
+    );
+    if let Some(original) = session.duplicate_of(job_id, &filename, &markdown) {
+        engine_info!(
+            "Job {} completed: same content as job {}, no document written",
+            job_id,
+            original
+        );
+        return Ok(JobOutcome {
+            final_url: fetch_output.metadata.final_url,
+            canonical_url: canonical,
+            tokens: Some(token_count),
+            bytes_written: None,
+            output_path: None,
+            diff: None,
+            content_preview: Some(preview_content),
+            extracted_links: page.links,
+            quality: Some(quality),
+            reading: Some(reading),
+            content_warning,
+            stripped_chrome,
+            from_cache,
+            unchanged: false,
+            duplicate_of: Some(original),
+        });
+    }
+    let store = DocumentStore::from_config(config);
 
-    let doc_for_write = doc.clone();
+    let doc_len = doc.len() as u64;
     let write_result = timeout(config.writing_timeout, async move {
-        tokio::task::spawn_blocking(move || writer.write(&filename, &doc)).await
+        tokio::task::spawn_blocking(move || {
+            with_job_context(job_id, || store.write(&filename, &fetched_utc, &doc))
+        })
+        .await
     })
     .await;
 
     match write_result {
-        Ok(Ok(Ok(_path))) => {
-            engine_info!(
-                "Job {} completed: {} tokens, {} bytes written",
-                job_id,
-                token_count,
-                doc_for_write.len()
-            );
-            let _ = event_tx.send(EngineEvent::JobCompleted {
-                job_id,
-                result: Ok(JobOutcome {
-                    final_url: fetch_output.metadata.final_url,
-                    tokens: Some(token_count),
-                    bytes_written: Some(doc_for_write.len() as u64),
-                    content_preview: Some(preview_content),
-                    extracted_links: conversion.links,
-                }),
-            });
+        Ok(Ok(Ok(written))) => {
+            if written.unchanged {
+                engine_info!(
+                    "Job {} completed: {} tokens, document unchanged",
+                    job_id,
+                    token_count
+                );
+            } else {
+                engine_info!(
+                    "Job {} completed: {} tokens, {} bytes written",
+                    job_id,
+                    token_count,
+                    doc_len
+                );
+            }
+            Ok(JobOutcome {
+                final_url: fetch_output.metadata.final_url,
+                canonical_url: canonical,
+                tokens: Some(token_count),
+                bytes_written: Some(doc_len),
+                output_path: Some(written.path),
+                diff: written.diff,
+                content_preview: Some(preview_content),
+                extracted_links: page.links,
+                quality: Some(quality),
+                reading: Some(reading),
+                content_warning,
+                stripped_chrome,
+                from_cache,
+                unchanged: written.unchanged,
+                duplicate_of: None,
+            })
         }
         _ => {
             engine_warn!("Job {} failed: write error", job_id);
-            let _ = event_tx.send(EngineEvent::JobCompleted {
+            Err(FailureKind::ProcessingError)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enqueue(worker: &mut WorkerState, job_id: JobId, sink: &ChannelProgressSink) {
+        worker.handle_command(
+            EngineCommand::Enqueue(QueuedJob {
                 job_id,
-                result: Err(FailureKind::ProcessingError),
-            });
+                url: format!("https://example.com/{job_id}"),
+                source_batch: None,
+            }),
+            sink,
+        );
+    }
+
+    #[test]
+    fn full_queue_signals_saturation_and_rejects_overflow() {
+        let (tx, rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(2);
+
+        enqueue(&mut worker, 1, &sink);
+        enqueue(&mut worker, 2, &sink);
+        enqueue(&mut worker, 3, &sink);
+
+        let events: Vec<_> = rx.try_iter().collect();
+        assert_eq!(
+            events,
+            vec![
+                EngineEvent::QueueSaturated { capacity: 2 },
+                EngineEvent::JobCompleted {
+                    job_id: 3,
+                    result: Err(FailureKind::QueueFull),
+                },
+            ]
+        );
+        assert_eq!(worker.queued_jobs(), 2);
+    }
+
+    #[test]
+    fn watch_picked_after_a_stop_is_scheduled() {
+        let (tx, _rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(2);
+        worker.handle_command(EngineCommand::Stop { immediate: false }, &sink);
+        worker.handle_command(
+            EngineCommand::Watch {
+                watch_id: 1,
+                url: "https://example.com/1".to_string(),
+                interval: std::time::Duration::from_secs(60),
+            },
+            &sink,
+        );
+
+        let check = worker.next_watch_check(Instant::now()).expect("check due");
+        assert_eq!(check.watch_id, 1);
+    }
+
+    #[test]
+    fn draining_to_half_capacity_resumes_intake() {
+        let (tx, rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(2);
+        enqueue(&mut worker, 1, &sink);
+        enqueue(&mut worker, 2, &sink);
+        let _ = rx.try_iter().count();
+
+        assert!(worker.next_work(&sink).is_some());
+
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![EngineEvent::QueueResumed]
+        );
+    }
+
+    #[test]
+    fn busy_host_does_not_block_other_hosts() {
+        let (tx, _rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(10);
+        worker.max_running = 3;
+        worker.max_per_domain = 1;
+        for (job_id, url) in [
+            (1, "https://slow.example/a"),
+            (2, "https://slow.example/b"),
+            (3, "https://fast.example/a"),
+        ] {
+            worker.handle_command(
+                EngineCommand::Enqueue(QueuedJob {
+                    job_id,
+                    url: url.to_string(),
+                    source_batch: None,
+                }),
+                &sink,
+            );
         }
+        worker.handle_command(EngineCommand::Export(ExportScope::All), &sink);
+
+        let started = |work: Option<QueuedWork>| match work {
+            Some(QueuedWork::Job(job)) => Some(job.job_id),
+            _ => None,
+        };
+        assert_eq!(started(worker.next_work(&sink)), Some(1));
+        assert_eq!(started(worker.next_work(&sink)), Some(3));
+        // Job 2 waits for slow.example; the export waits for every job.
+        assert!(worker.next_work(&sink).is_none());
+
+        worker.job_finished("slow.example", 0);
+        assert_eq!(started(worker.next_work(&sink)), Some(2));
+        worker.job_finished("slow.example", 0);
+        worker.job_finished("fast.example", 0);
+        assert!(matches!(
+            worker.next_work(&sink),
+            Some(QueuedWork::Export(ExportScope::All))
+        ));
+    }
+
+    #[test]
+    fn host_past_its_token_cap_gets_no_more_jobs() {
+        let (tx, rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(10);
+        worker.domain_token_cap = Some(100);
+        for job_id in 1..=3 {
+            enqueue(&mut worker, job_id, &sink);
+        }
+
+        assert!(worker.next_work(&sink).is_some());
+        worker.job_finished("example.com", 120);
+        assert!(worker.next_work(&sink).is_none());
+        let failed: Vec<_> = rx
+            .try_iter()
+            .filter_map(|event| match event {
+                EngineEvent::JobCompleted {
+                    job_id,
+                    result: Err(FailureKind::DomainBudgetExceeded { domain, cap }),
+                } => Some((job_id, domain, cap)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            failed,
+            [2, 3].map(|job_id| (job_id, "example.com".to_string(), 100))
+        );
+    }
+
+    #[test]
+    fn drain_is_reported_once_per_stop() {
+        let (tx, _rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(10);
+        enqueue(&mut worker, 1, &sink);
+        assert!(worker.next_work(&sink).is_some());
+        assert!(!worker.take_drained(), "not stopped yet");
+
+        worker.handle_command(EngineCommand::Stop { immediate: false }, &sink);
+        assert!(!worker.take_drained(), "job 1 still running");
+        worker.job_finished("example.com", 0);
+        assert!(worker.take_drained());
+        assert!(!worker.take_drained());
+
+        worker.handle_command(EngineCommand::Resume, &sink);
+        enqueue(&mut worker, 2, &sink);
+        assert_eq!(worker.queued_jobs(), 1);
+        assert!(!worker.cancel_token.is_cancelled());
+    }
+
+    #[test]
+    fn only_an_immediate_stop_cancels_running_jobs() {
+        let (tx, rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let mut worker = WorkerState::new(10);
+        enqueue(&mut worker, 1, &sink);
+        enqueue(&mut worker, 2, &sink);
+        assert!(worker.next_work(&sink).is_some());
+
+        worker.handle_command(EngineCommand::Stop { immediate: false }, &sink);
+        assert!(!worker.cancel_token.is_cancelled(), "job 1 may finish");
+        let cancelled: Vec<JobId> = rx
+            .try_iter()
+            .filter_map(|event| match event {
+                EngineEvent::JobCompleted {
+                    job_id,
+                    result: Err(FailureKind::Cancelled),
+                } => Some(job_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cancelled, vec![2], "queued jobs are cancelled");
+
+        worker.handle_command(EngineCommand::Stop { immediate: true }, &sink);
+        assert!(worker.cancel_token.is_cancelled());
+    }
+
+    #[test]
+    fn spent_memory_budget_defers_new_jobs() {
+        let (tx, rx) = mpsc::channel();
+        let sink = ChannelProgressSink::new(tx);
+        let memory = Arc::new(MemoryBudget::new(Some(100)));
+        let mut worker = WorkerState::new(10).with_memory(memory.clone());
+        worker.job_memory_reserve = 60;
+        enqueue(&mut worker, 1, &sink);
+        enqueue(&mut worker, 2, &sink);
+
+        assert!(worker.next_work(&sink).is_some(), "a lone job always runs");
+        let mut charge = memory.charge();
+        charge.add(50);
+        assert!(worker.next_work(&sink).is_none());
+        assert!(worker.next_work(&sink).is_none());
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![EngineEvent::MemoryThrottled {
+                in_use_bytes: 50,
+                budget_bytes: 100,
+            }],
+            "reported once per throttle"
+        );
+
+        drop(charge);
+        assert!(worker.next_work(&sink).is_some());
     }
 }
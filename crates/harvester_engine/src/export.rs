@@ -3,7 +3,13 @@ use std::path::{Path, PathBuf};
 
 use serde_json::json;
 
+use crate::chunk::{chunk_markdown, ChunkOptions};
+use crate::crypt::{CryptError, Passphrase};
+use crate::doc_id::document_id;
 use crate::persist::{ensure_output_dir, AtomicFileWriter, PersistError};
+use crate::session_report::SessionReport;
+use crate::token::TokenCounter;
+use crate::versions::list_versions;
 
 #[derive(Debug, Clone)]
 pub struct ExportOptions {
@@ -11,6 +17,34 @@ pub struct ExportOptions {
     pub manifest_filename: Option<String>,
     pub delimiter_start: String,
     pub delimiter_end: String,
+    /// Only include documents whose `fetched_utc` sorts after this timestamp.
+    /// Timestamps are compared as strings, so they must share the RFC 3339 format.
+    pub fetched_after: Option<String>,
+    /// Include every dated snapshot of a page, not just the latest.
+    pub all_versions: bool,
+    /// Leave out documents whose `quality_score` is below this. Documents
+    /// without a score are always included.
+    pub min_quality_score: Option<u8>,
+    /// Only include documents published on or after this `YYYY-MM-DD` date;
+    /// a month-only `published` date counts as the first of the month.
+    /// Undated documents are left out.
+    pub published_from: Option<String>,
+    /// Like `published_from`, for documents published on or before this date.
+    pub published_until: Option<String>,
+    /// Only include documents with at least one of these `keywords`,
+    /// compared case-insensitively. Empty includes every document.
+    pub keywords: Vec<String>,
+    /// Only include documents whose `source_batch` label equals this.
+    /// Unlabeled documents are left out once it is set.
+    pub source_batch: Option<String>,
+    /// Encrypt the concatenated export and its manifest with this
+    /// passphrase; both are written with an added `.enc` extension.
+    pub encryption: Option<Passphrase>,
+    /// Totals of the harvesting session, added to the manifest as `session`.
+    pub session: Option<SessionReport>,
+    /// Documents with these URLs are always part of a fitted export, placed
+    /// first and counted against the budget before any other document.
+    pub pinned_urls: Vec<String>,
 }
 
 impl Default for ExportOptions {
@@ -20,10 +54,130 @@ impl Default for ExportOptions {
             manifest_filename: Some("manifest.json".to_string()),
             delimiter_start: "===== DOC START =====".to_string(),
             delimiter_end: "===== DOC END =====".to_string(),
+            fetched_after: None,
+            all_versions: false,
+            min_quality_score: None,
+            published_from: None,
+            published_until: None,
+            keywords: Vec::new(),
+            source_batch: None,
+            encryption: None,
+            session: None,
+            pinned_urls: Vec::new(),
         }
     }
 }
 
+impl ExportOptions {
+    /// Options for an incremental export: only documents fetched after `since_utc`
+    /// (everything when `None`), written to files stamped with `stamp_utc`.
+    pub fn new_only(since_utc: Option<String>, stamp_utc: &str) -> Self {
+        let stamp = filename_stamp(stamp_utc);
+        Self {
+            output_filename: format!("export-{stamp}.txt"),
+            manifest_filename: Some(format!("manifest-{stamp}.json")),
+            fetched_after: since_utc,
+            ..Self::default()
+        }
+    }
+
+    fn includes(&self, doc: &DocMeta) -> bool {
+        let recent = match self.fetched_after.as_deref() {
+            Some(since) => doc.fetched_utc.as_str() > since,
+            None => true,
+        };
+        let good_enough = match (self.min_quality_score, doc.quality_score) {
+            (Some(min), Some(score)) => score >= min,
+            _ => true,
+        };
+        let published = doc.published.as_deref().map(|date| match date.len() {
+            7 => format!("{date}-01"),
+            _ => date.to_string(),
+        });
+        let published = published.as_deref();
+        let after_from = match self.published_from.as_deref() {
+            Some(from) => published.is_some_and(|date| date >= from),
+            None => true,
+        };
+        let before_until = match self.published_until.as_deref() {
+            Some(until) => published.is_some_and(|date| date <= until),
+            None => true,
+        };
+        let on_topic = self.keywords.is_empty()
+            || self.keywords.iter().any(|wanted| {
+                doc.keywords
+                    .iter()
+                    .any(|keyword| keyword.eq_ignore_ascii_case(wanted))
+            });
+        let in_batch = match self.source_batch.as_deref() {
+            Some(wanted) => doc.source_batch.as_deref() == Some(wanted),
+            None => true,
+        };
+        recent && good_enough && after_from && before_until && on_topic && in_batch
+    }
+}
+
+/// What an export writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    /// One delimited text file, see [`build_concatenated_export`].
+    #[default]
+    Concatenated,
+    /// Chunked JSONL, see [`build_chunked_export`].
+    Chunks(ChunkOptions),
+    /// Obsidian notes, see [`crate::build_obsidian_vault`].
+    ObsidianVault,
+    /// One self-contained HTML file, see [`crate::build_html_export`].
+    Html,
+    /// One delimited text file that fits the token budget, see
+    /// [`build_fitted_export`].
+    FitToContext(FitOrder),
+}
+
+/// Order in which [`build_fitted_export`] offers documents to the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FitOrder {
+    /// Filename order, like the other exports.
+    #[default]
+    Filename,
+    /// Most recently fetched first.
+    Newest,
+    /// Highest quality score first; unscored documents last.
+    BestQuality,
+    /// Fewest tokens first, so as many documents as possible fit.
+    Smallest,
+    /// Most recently published first; undated documents last.
+    Published,
+}
+
+/// Which documents an export request covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Every document, written to the default export file.
+    All,
+    /// Only documents fetched after the given timestamp (all when `None`),
+    /// written to a date-stamped export file.
+    NewSince(Option<String>),
+}
+
+impl ExportScope {
+    pub fn to_options(&self, stamp_utc: &str) -> ExportOptions {
+        match self {
+            ExportScope::All => ExportOptions::default(),
+            ExportScope::NewSince(since) => ExportOptions::new_only(since.clone(), stamp_utc),
+        }
+    }
+}
+
+/// Windows-safe stamp derived from an RFC 3339 timestamp: `2024-05-01T10-00-00`.
+fn filename_stamp(timestamp: &str) -> String {
+    timestamp
+        .chars()
+        .take(19)
+        .map(|c| if c == ':' { '-' } else { c })
+        .collect()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExportSummary {
     pub doc_count: usize,
@@ -38,92 +192,316 @@ pub enum ExportError {
     Io(#[from] std::io::Error),
     #[error("persist error: {0}")]
     Persist(#[from] PersistError),
+    #[error("encryption error: {0}")]
+    Encrypt(#[from] CryptError),
     #[error("frontmatter missing required fields in file {0}")]
     MissingFrontmatter(String),
 }
 
 #[derive(Debug, Default)]
-struct DocMeta {
-    url: String,
-    title: String,
-    fetched_utc: String,
-    token_count: Option<u32>,
-    body: String,
-    filename: String,
+pub(crate) struct DocMeta {
+    pub(crate) url: String,
+    /// From the frontmatter; derived from `url` for documents written
+    /// before ids were recorded.
+    pub(crate) doc_id: String,
+    pub(crate) title: String,
+    pub(crate) summary: Option<String>,
+    pub(crate) keywords: Vec<String>,
+    pub(crate) source_batch: Option<String>,
+    /// `(heading, tokens)` from the `section_tokens:` block.
+    pub(crate) section_tokens: Vec<(String, u32)>,
+    pub(crate) fetched_utc: String,
+    pub(crate) token_count: Option<u32>,
+    pub(crate) quality_score: Option<u8>,
+    pub(crate) published: Option<String>,
+    pub(crate) body: String,
+    pub(crate) filename: String,
 }
 
 pub fn build_concatenated_export(
     output_dir: &Path,
     options: ExportOptions,
 ) -> Result<ExportSummary, ExportError> {
-    ensure_output_dir(output_dir)?;
-    let mut entries: Vec<_> = fs::read_dir(output_dir)?
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("md"))
+    let docs = selected_docs(output_dir, &options)?;
+    let entries: Vec<String> = docs
+        .iter()
+        .map(|doc| concatenated_entry(doc, &options))
         .collect();
-    entries.sort_by_key(|e| e.file_name());
+    write_concatenated(output_dir, options, &docs, &entries, None)
+}
 
-    let mut docs = Vec::new();
-    for entry in entries {
-        let path = entry.path();
-        let content = fs::read_to_string(&path)?;
-        let meta = parse_doc(&content, entry.file_name().to_string_lossy().as_ref())?;
-        docs.push(meta);
+/// Like [`build_concatenated_export`], but only with as many documents as
+/// fit in `max_tokens`, counted over the written text including delimiters.
+/// Documents are offered in `order`; those that would overflow the budget
+/// are skipped and listed under `excluded` in the manifest. Pinned documents
+/// ([`ExportOptions::pinned_urls`]) come first and are never skipped.
+pub fn build_fitted_export(
+    output_dir: &Path,
+    options: ExportOptions,
+    order: FitOrder,
+    max_tokens: u64,
+    token_counter: &dyn TokenCounter,
+) -> Result<ExportSummary, ExportError> {
+    let mut docs = selected_docs(output_dir, &options)?;
+    match order {
+        FitOrder::Filename => {}
+        FitOrder::Newest => docs.sort_by(|a, b| b.fetched_utc.cmp(&a.fetched_utc)),
+        FitOrder::BestQuality => docs.sort_by_key(|doc| std::cmp::Reverse(doc.quality_score)),
+        FitOrder::Smallest => docs.sort_by_key(|doc| doc.token_count.unwrap_or(0)),
+        FitOrder::Published => docs.sort_by(|a, b| b.published.cmp(&a.published)),
+    }
+    docs.sort_by_key(|doc| !options.pinned_urls.contains(&doc.url));
+
+    let mut included = Vec::new();
+    let mut entries = Vec::new();
+    let mut excluded = Vec::new();
+    let mut used: u64 = 0;
+    for doc in docs {
+        let entry = concatenated_entry(&doc, &options);
+        let tokens = token_counter.count(&entry) as u64;
+        if used + tokens <= max_tokens || options.pinned_urls.contains(&doc.url) {
+            used += tokens;
+            included.push(doc);
+            entries.push(entry);
+        } else {
+            excluded.push(doc);
+        }
     }
+    write_concatenated(output_dir, options, &included, &entries, Some(&excluded))
+}
+
+/// One document as it appears in a concatenated export.
+fn concatenated_entry(doc: &DocMeta, options: &ExportOptions) -> String {
+    format!(
+        "{start}\nurl: {}\ntitle: {}\ntokens: {}\nfetched_utc: {}\nfilename: {}\n\n{}\n{end}\n\n",
+        doc.url,
+        doc.title,
+        doc.token_count.unwrap_or(0),
+        doc.fetched_utc,
+        doc.filename,
+        doc.body.trim_end(),
+        start = options.delimiter_start,
+        end = options.delimiter_end,
+    )
+}
+
+fn write_concatenated(
+    output_dir: &Path,
+    options: ExportOptions,
+    docs: &[DocMeta],
+    entries: &[String],
+    excluded: Option<&[DocMeta]>,
+) -> Result<ExportSummary, ExportError> {
+    let total_tokens: u64 = docs
+        .iter()
+        .filter_map(|doc| doc.token_count)
+        .map(u64::from)
+        .sum();
+    let buffer = entries.concat();
+
+    let writer = AtomicFileWriter::new(output_dir.to_path_buf());
+    let encryption = options.encryption.as_ref();
+    let output_path = write_export_file(&writer, &options.output_filename, &buffer, encryption)?;
+    let manifest_path = write_manifest(
+        output_dir,
+        options.manifest_filename,
+        docs,
+        excluded,
+        total_tokens,
+        options.session.as_ref(),
+        encryption,
+    )?;
+
+    Ok(ExportSummary {
+        doc_count: docs.len(),
+        total_tokens,
+        output_path,
+        manifest_path,
+    })
+}
+
+/// Write `content` as `filename`, or encrypted as `{filename}.enc`.
+fn write_export_file(
+    writer: &AtomicFileWriter,
+    filename: &str,
+    content: &str,
+    encryption: Option<&Passphrase>,
+) -> Result<PathBuf, ExportError> {
+    Ok(match encryption {
+        Some(passphrase) => writer.write_bytes(
+            &format!("{filename}.enc"),
+            &passphrase.encrypt(content.as_bytes())?,
+        )?,
+        None => writer.write(filename, content)?,
+    })
+}
+
+/// Like [`build_concatenated_export`], but writes one JSON object per chunk
+/// (see [`chunk_markdown`]) to a `.jsonl` file named after
+/// `options.output_filename`. Chunk ids are `{filename}#{index}`.
+pub fn build_chunked_export(
+    output_dir: &Path,
+    options: ExportOptions,
+    chunking: &ChunkOptions,
+    token_counter: &dyn TokenCounter,
+) -> Result<ExportSummary, ExportError> {
+    let docs = selected_docs(output_dir, &options)?;
 
     let mut buffer = String::new();
     let mut total_tokens: u64 = 0;
     for doc in &docs {
-        if let Some(t) = doc.token_count {
-            total_tokens += t as u64;
+        total_tokens += doc.token_count.unwrap_or(0) as u64;
+        for chunk in chunk_markdown(doc.body.trim(), chunking, token_counter) {
+            let line = json!({
+                "id": format!("{}#{}", doc.filename, chunk.index),
+                "doc_id": doc.doc_id,
+                "url": doc.url,
+                "title": doc.title,
+                "fetched_utc": doc.fetched_utc,
+                "chunk_index": chunk.index,
+                "heading_path": chunk.heading_path,
+                "tokens": chunk.token_count,
+                "text": chunk.text,
+            });
+            buffer.push_str(&line.to_string());
+            buffer.push('\n');
         }
-        buffer.push_str(&options.delimiter_start);
-        buffer.push('\n');
-        buffer.push_str(&format!(
-            "url: {}\ntitle: {}\ntokens: {}\nfetched_utc: {}\nfilename: {}\n\n",
-            doc.url,
-            doc.title,
-            doc.token_count.unwrap_or(0),
-            doc.fetched_utc,
-            doc.filename
-        ));
-        buffer.push_str(doc.body.trim_end());
-        buffer.push('\n');
-        buffer.push_str(&options.delimiter_end);
-        buffer.push_str("\n\n");
     }
 
+    let filename = Path::new(&options.output_filename).with_extension("jsonl");
     let writer = AtomicFileWriter::new(output_dir.to_path_buf());
-    let output_path = writer.write(&options.output_filename, &buffer)?;
+    let output_path = writer.write(&filename.to_string_lossy(), &buffer)?;
+    let manifest_path = write_manifest(
+        output_dir,
+        options.manifest_filename,
+        &docs,
+        None,
+        total_tokens,
+        options.session.as_ref(),
+        None,
+    )?;
+
+    Ok(ExportSummary {
+        doc_count: docs.len(),
+        total_tokens,
+        output_path,
+        manifest_path,
+    })
+}
+
+/// Documents in `output_dir` that `options` includes, in filename order.
+pub(crate) fn selected_docs(
+    output_dir: &Path,
+    options: &ExportOptions,
+) -> Result<Vec<DocMeta>, ExportError> {
+    ensure_output_dir(output_dir)?;
+    let mut entries = document_files(output_dir, options.all_versions)?;
+    entries.sort();
+
+    let mut docs = Vec::new();
+    for (filename, path) in entries {
+        let content = fs::read_to_string(&path)?;
+        let meta = parse_doc(&content, &filename)?;
+        if options.includes(&meta) {
+            docs.push(meta);
+        }
+    }
+    Ok(docs)
+}
 
-    let manifest_path = if let Some(name) = options.manifest_filename {
-        let manifest = json!({
+/// `excluded` lists documents left out of a budget-fitted export.
+pub(crate) fn write_manifest(
+    output_dir: &Path,
+    manifest_filename: Option<String>,
+    docs: &[DocMeta],
+    excluded: Option<&[DocMeta]>,
+    total_tokens: u64,
+    session: Option<&SessionReport>,
+    encryption: Option<&Passphrase>,
+) -> Result<Option<PathBuf>, ExportError> {
+    let manifest_path = if let Some(name) = manifest_filename {
+        let mut manifest = json!({
             "doc_count": docs.len(),
             "total_tokens": total_tokens,
-            "files": docs.iter().map(|d| {
-                json!({
-                    "filename": d.filename,
-                    "title": d.title,
-                    "url": d.url,
-                    "tokens": d.token_count.unwrap_or(0),
-                    "fetched_utc": d.fetched_utc
-                })
-            }).collect::<Vec<_>>()
+            "files": docs.iter().map(manifest_entry).collect::<Vec<_>>()
         });
+        if let Some(excluded) = excluded {
+            manifest["excluded"] = excluded.iter().map(manifest_entry).collect();
+        }
+        if let Some(session) = session {
+            manifest["session"] = session.to_json();
+        }
         let writer = AtomicFileWriter::new(output_dir.to_path_buf());
-        let path = writer.write(&name, &manifest.to_string())?;
+        let path = write_export_file(&writer, &name, &manifest.to_string(), encryption)?;
         Some(path)
     } else {
         None
     };
+    Ok(manifest_path)
+}
 
-    Ok(ExportSummary {
-        doc_count: docs.len(),
-        total_tokens,
-        output_path,
-        manifest_path,
-    })
+fn manifest_entry(doc: &DocMeta) -> serde_json::Value {
+    let mut entry = json!({
+        "filename": doc.filename,
+        "title": doc.title,
+        "url": doc.url,
+        "doc_id": doc.doc_id,
+        "tokens": doc.token_count.unwrap_or(0),
+        "fetched_utc": doc.fetched_utc
+    });
+    if let Some(published) = &doc.published {
+        entry["published"] = json!(published);
+    }
+    if let Some(summary) = &doc.summary {
+        entry["summary"] = json!(summary);
+    }
+    if !doc.keywords.is_empty() {
+        entry["keywords"] = json!(doc.keywords);
+    }
+    if let Some(batch) = &doc.source_batch {
+        entry["source_batch"] = json!(batch);
+    }
+    if !doc.section_tokens.is_empty() {
+        entry["sections"] = doc
+            .section_tokens
+            .iter()
+            .map(|(heading, tokens)| json!({ "heading": heading, "tokens": tokens }))
+            .collect();
+    }
+    entry
+}
+
+/// Markdown documents in `output_dir` as `(display name, path)`. Snapshot
+/// directories contribute their latest version, or all with `all_versions`.
+fn document_files(
+    output_dir: &Path,
+    all_versions: bool,
+) -> Result<Vec<(String, PathBuf)>, ExportError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(output_dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md") {
+            files.push((name, path));
+        } else if file_type.is_dir() {
+            let mut versions = list_versions(&path)?;
+            if !all_versions {
+                versions = versions.pop().into_iter().collect();
+            }
+            files.extend(versions.into_iter().map(|version| {
+                let file = version
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                (format!("{name}/{file}"), version.path)
+            }));
+        }
+    }
+    Ok(files)
 }
 
 fn parse_doc(content: &str, filename: &str) -> Result<DocMeta, ExportError> {
@@ -135,18 +513,41 @@ fn parse_doc(content: &str, filename: &str) -> Result<DocMeta, ExportError> {
         filename: filename.to_string(),
         ..Default::default()
     };
+    let mut in_sections = false;
     for line in &mut lines {
         if line.trim() == "---" {
             break;
         }
+        if let Some(item) = line.strip_prefix("  - ").filter(|_| in_sections) {
+            if let Some((heading, tokens)) = item.rsplit_once(": ") {
+                if let Ok(tokens) = tokens.trim().parse::<u32>() {
+                    meta.section_tokens.push((heading.to_string(), tokens));
+                }
+            }
+            continue;
+        }
+        in_sections = line == "section_tokens:";
         if let Some((k, v)) = line.split_once(':') {
             let key = k.trim();
             let val = v.trim();
             match key {
                 "url" => meta.url = val.to_string(),
+                "doc_id" => meta.doc_id = val.to_string(),
                 "title" => meta.title = val.to_string(),
+                "summary" => meta.summary = Some(val.to_string()),
+                "keywords" => {
+                    meta.keywords = val
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|term| !term.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
                 "fetched_utc" => meta.fetched_utc = val.to_string(),
                 "token_count" => meta.token_count = val.parse::<u32>().ok(),
+                "quality_score" => meta.quality_score = val.parse::<u8>().ok(),
+                "published" => meta.published = Some(val.to_string()),
+                "source_batch" => meta.source_batch = Some(val.to_string()),
                 _ => {}
             }
         }
@@ -156,5 +557,8 @@ fn parse_doc(content: &str, filename: &str) -> Result<DocMeta, ExportError> {
     if meta.url.is_empty() || meta.title.is_empty() || meta.fetched_utc.is_empty() {
         return Err(ExportError::MissingFrontmatter(filename.to_string()));
     }
+    if meta.doc_id.is_empty() {
+        meta.doc_id = document_id(&meta.url);
+    }
     Ok(meta)
 }
@@ -1,9 +1,76 @@
-use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::fmt;
+
+use scraper::node::Node;
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::filename::path_slug;
+
+/// Paragraphs shorter than this (in characters) do not count towards a
+/// readability score.
+const MIN_PARAGRAPH_CHARS: usize = 25;
+
+/// Readability candidates scoring below this fall through to the next strategy.
+const MIN_READABILITY_SCORE: f64 = 20.0;
+
+/// Text blocks shorter than this (in characters) fall through to `<body>`.
+const MIN_BLOCK_CHARS: usize = 140;
+
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "content", "entry", "main", "post", "story", "text",
+];
+/// `<title>` texts that name no page in particular, compared case-insensitively.
+const GENERIC_TITLES: &[&str] = &[
+    "home",
+    "homepage",
+    "home page",
+    "index",
+    "untitled",
+    "untitled document",
+    "welcome",
+    "document",
+    "page",
+];
+
+const NEGATIVE_HINTS: &[&str] = &[
+    "ad-", "banner", "comment", "footer", "header", "menu", "nav", "promo", "related", "share",
+    "sidebar", "social", "sponsor", "widget",
+];
+
+/// Which step of the extraction chain produced the content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractionStrategy {
+    /// Highest-scoring container by paragraph text, readability style.
+    Readability,
+    Main,
+    Article,
+    /// Element with the most text of its own.
+    LargestBlock,
+    Body,
+    /// The whole document; the page had no `<body>`.
+    Document,
+}
+
+impl fmt::Display for ExtractionStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExtractionStrategy::Readability => "readability",
+            ExtractionStrategy::Main => "main",
+            ExtractionStrategy::Article => "article",
+            ExtractionStrategy::LargestBlock => "largest_block",
+            ExtractionStrategy::Body => "body",
+            ExtractionStrategy::Document => "document",
+        };
+        f.write_str(name)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExtractedContent {
     pub title: Option<String>,
     pub content_html: String,
+    pub strategy: ExtractionStrategy,
 }
 
 pub trait Extractor: Send + Sync {
@@ -11,48 +78,277 @@ pub trait Extractor: Send + Sync {
 }
 
 /// Lightweight "readability-like" extractor:
-/// - pulls `<title>` text if present
-/// - returns `<article>` inner_html if present
-/// - otherwise returns `<body>` inner_html
-/// - fallback to full document HTML.
+/// - takes the title from `<title>`, or when that is missing or generic
+///   ("Home"), from `og:title` or the first `<h1>`
+/// - tries, in order: readability scoring, `<main>`, `<article>`, the largest
+///   text block, `<body>`, and finally the full document HTML
+/// - records the strategy that won in [`ExtractedContent::strategy`].
 #[derive(Debug, Default)]
 pub struct ReadabilityLikeExtractor;
 
 impl Extractor for ReadabilityLikeExtractor {
     fn extract(&self, html: &str) -> ExtractedContent {
         let doc = Html::parse_document(html);
-        let title_sel = Selector::parse("title").ok();
-        let article_sel = Selector::parse("article").ok();
-        let body_sel = Selector::parse("body").ok();
-
-        let title = title_sel
-            .as_ref()
-            .and_then(|sel| doc.select(sel).next())
-            .map(|t| t.text().collect::<String>().trim().to_string())
-            .filter(|t| !t.is_empty());
-
-        let content_html = if let Some(sel) = article_sel.as_ref() {
-            if let Some(node) = doc.select(sel).next() {
-                node.inner_html()
-            } else {
-                extract_body(&doc, &body_sel)
-            }
+
+        let title = page_title(&doc);
+
+        let (strategy, node) = readability_candidate(&doc)
+            .map(|node| (ExtractionStrategy::Readability, node))
+            .or_else(|| first_match(&doc, "main").map(|node| (ExtractionStrategy::Main, node)))
+            .or_else(|| {
+                first_match(&doc, "article").map(|node| (ExtractionStrategy::Article, node))
+            })
+            .or_else(|| {
+                largest_text_block(&doc).map(|node| (ExtractionStrategy::LargestBlock, node))
+            })
+            .or_else(|| first_match(&doc, "body").map(|node| (ExtractionStrategy::Body, node)))
+            .unwrap_or((ExtractionStrategy::Document, doc.root_element()));
+
+        let content_html = if strategy == ExtractionStrategy::Document {
+            node.html()
         } else {
-            extract_body(&doc, &body_sel)
+            node.inner_html()
         };
 
         ExtractedContent {
             title,
             content_html,
+            strategy,
         }
     }
 }
 
-fn extract_body(doc: &Html, body_sel: &Option<Selector>) -> String {
-    if let Some(sel) = body_sel {
-        if let Some(node) = doc.select(sel).next() {
-            return node.inner_html();
+/// First non-generic title among `<title>`, `og:title` and the first `<h1>`;
+/// a generic `<title>` is kept when nothing better is found.
+fn page_title(doc: &Html) -> Option<String> {
+    let text = |element: ElementRef| collapse_whitespace(&element.text().collect::<String>());
+    let title = first_match(doc, "title").map(text);
+    let og_title = first_match(doc, r#"meta[property="og:title"][content]"#)
+        .and_then(|meta| meta.value().attr("content"))
+        .map(collapse_whitespace);
+    let heading = first_match(doc, "h1").map(text);
+    let candidates = [title.clone(), og_title, heading];
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| !candidate.is_empty() && !is_generic_title(candidate))
+        .or(title.filter(|title| !title.is_empty()))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_generic_title(title: &str) -> bool {
+    GENERIC_TITLES
+        .iter()
+        .any(|generic| generic.eq_ignore_ascii_case(title))
+}
+
+/// Title of the document at `url`: `extracted` unless it is missing or
+/// generic, then words from the last segment of the URL path.
+pub(crate) fn document_title(extracted: Option<&str>, url: &str) -> Option<String> {
+    match extracted {
+        Some(title) if !is_generic_title(title) => Some(title.to_string()),
+        _ => title_from_url(url).or_else(|| extracted.map(str::to_string)),
+    }
+}
+
+/// `https://example.com/blog/my-first-post.html` -> `my first post`.
+fn title_from_url(url: &str) -> Option<String> {
+    let words = collapse_whitespace(&path_slug(url)?);
+    (!words.is_empty() && !is_generic_title(&words)).then_some(words)
+}
+
+/// Absolute http(s) URL from `<link rel="canonical">`, resolved against `page_url`.
+pub(crate) fn canonical_url(html: &str, page_url: &str) -> Option<String> {
+    let doc = Html::parse_document(html);
+    let selector = Selector::parse(r#"link[rel~="canonical"][href]"#).ok()?;
+    let href = doc.select(&selector).next()?.value().attr("href")?;
+    let canonical = Url::parse(page_url).ok()?.join(href.trim()).ok()?;
+    matches!(canonical.scheme(), "http" | "https").then(|| canonical.to_string())
+}
+
+fn first_match<'a>(doc: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).ok()?;
+    doc.select(&selector).next()
+}
+
+/// Each substantial paragraph adds to its parent's score, and half that to its
+/// grandparent. Candidates are weighted by class/id hints and link density.
+fn readability_candidate(doc: &Html) -> Option<ElementRef<'_>> {
+    let paragraphs = Selector::parse("p, pre, td").ok()?;
+    let mut scores: HashMap<_, (ElementRef, f64)> = HashMap::new();
+    for paragraph in doc.select(&paragraphs) {
+        let text = paragraph.text().collect::<String>();
+        let len = text.trim().chars().count();
+        if len < MIN_PARAGRAPH_CHARS {
+            continue;
         }
+        let score = 1.0 + text.matches(',').count() as f64 + (len / 100).min(3) as f64;
+        let mut ancestors = paragraph.ancestors().filter_map(ElementRef::wrap);
+        for (weight, ancestor) in [1.0, 0.5].into_iter().zip(ancestors.by_ref()) {
+            if matches!(ancestor.value().name(), "body" | "html") {
+                break;
+            }
+            let entry = scores
+                .entry(ancestor.id())
+                .or_insert_with(|| (ancestor, class_weight(&ancestor)));
+            entry.1 += score * weight;
+        }
+    }
+    scores
+        .into_values()
+        .map(|(node, score)| (node, score * (1.0 - link_density(&node))))
+        .filter(|(_, score)| *score >= MIN_READABILITY_SCORE)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(node, _)| node)
+}
+
+fn class_weight(element: &ElementRef) -> f64 {
+    let el = element.value();
+    let hints = format!(
+        "{} {}",
+        el.attr("class").unwrap_or_default(),
+        el.attr("id").unwrap_or_default()
+    )
+    .to_ascii_lowercase();
+    let mut weight = 0.0;
+    if POSITIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight += 25.0;
+    }
+    if NEGATIVE_HINTS.iter().any(|hint| hints.contains(hint)) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+/// Share of the element's text that sits inside links.
+fn link_density(element: &ElementRef) -> f64 {
+    let total = element.text().map(str::len).sum::<usize>();
+    if total == 0 {
+        return 0.0;
+    }
+    let Ok(anchors) = Selector::parse("a") else {
+        return 0.0;
+    };
+    let linked = element
+        .select(&anchors)
+        .flat_map(|anchor| anchor.text())
+        .map(str::len)
+        .sum::<usize>();
+    linked as f64 / total as f64
+}
+
+/// The block element with the most text in its own text nodes and inline
+/// children, ignoring text in nested blocks.
+fn largest_text_block(doc: &Html) -> Option<ElementRef<'_>> {
+    let blocks = Selector::parse("div, section, td").ok()?;
+    doc.select(&blocks)
+        .map(|block| (block, own_text_len(&block)))
+        .filter(|(_, len)| *len >= MIN_BLOCK_CHARS)
+        .max_by_key(|(_, len)| *len)
+        .map(|(block, _)| block)
+}
+
+fn own_text_len(block: &ElementRef) -> usize {
+    block
+        .children()
+        .map(|child| match child.value() {
+            Node::Text(text) => text.trim().chars().count(),
+            Node::Element(el) if !is_block(el.name()) => ElementRef::wrap(child)
+                .map_or(0, |el| el.text().map(|t| t.trim().chars().count()).sum()),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn is_block(name: &str) -> bool {
+    matches!(
+        name,
+        "div"
+            | "section"
+            | "article"
+            | "main"
+            | "aside"
+            | "nav"
+            | "header"
+            | "footer"
+            | "table"
+            | "script"
+            | "style"
+            | "noscript"
+            | "form"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strategy(html: &str) -> ExtractionStrategy {
+        ReadabilityLikeExtractor.extract(html).strategy
+    }
+
+    #[test]
+    fn chain_falls_through_in_order() {
+        let paragraph = "<p>This paragraph is long enough, with commas, to count, for scoring, and then some more words that push it past one hundred characters.</p>";
+        let scored = format!(
+            r#"<body><nav><a href="/">Home</a></nav><div class="post-content">{}</div></body>"#,
+            paragraph.repeat(5)
+        );
+        let extracted = ReadabilityLikeExtractor.extract(&scored);
+        assert_eq!(extracted.strategy, ExtractionStrategy::Readability);
+        assert!(!extracted.content_html.contains("Home"));
+
+        assert_eq!(
+            strategy("<body><main><p>Short.</p></main><article>x</article></body>"),
+            ExtractionStrategy::Main
+        );
+        assert_eq!(
+            strategy("<body><article><p>Short.</p></article></body>"),
+            ExtractionStrategy::Article
+        );
+        let block = format!(
+            "<body><div>Menu</div><div>{}<br>{}</div></body>",
+            "Plain text without paragraphs ".repeat(4),
+            "and a second line of text ".repeat(3)
+        );
+        assert_eq!(strategy(&block), ExtractionStrategy::LargestBlock);
+        assert_eq!(strategy("<body><p>Hi</p></body>"), ExtractionStrategy::Body);
+    }
+
+    #[test]
+    fn generic_or_missing_titles_fall_back_in_order() {
+        let title = |html: &str| ReadabilityLikeExtractor.extract(html).title;
+        assert_eq!(
+            title(
+                r#"<title>Home</title><meta property="og:title" content="Launch notes"><h1>Ignored</h1>"#
+            ),
+            Some("Launch notes".to_string())
+        );
+        assert_eq!(
+            title("<title> </title><body><h1>Release\n  2.0</h1></body>"),
+            Some("Release 2.0".to_string())
+        );
+        assert_eq!(
+            title("<title>Home</title><p>x</p>"),
+            Some("Home".to_string())
+        );
+
+        let url = "https://example.com/blog/my-first_post.html";
+        assert_eq!(
+            document_title(Some("Home"), url),
+            Some("my first post".to_string())
+        );
+        assert_eq!(document_title(None, url), Some("my first post".to_string()));
+        assert_eq!(
+            document_title(Some("Home"), "https://example.com/"),
+            Some("Home".to_string())
+        );
+        assert_eq!(
+            document_title(Some("Real title"), url),
+            Some("Real title".to_string())
+        );
     }
-    doc.root_element().html()
 }
@@ -1,25 +1,64 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
-};
 use std::time::Duration;
 
-use engine_logging::{engine_info, engine_warn};
+use engine_logging::{engine_debug, engine_info, engine_warn};
 use futures_util::StreamExt;
-use reqwest::header::CONTENT_TYPE;
+use reqwest::header::{
+    ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, COOKIE, ETAG, LAST_MODIFIED,
+    LOCATION, RANGE, RETRY_AFTER, SERVER,
+};
+use reqwest::{Method, StatusCode};
 
+use crate::cookies::CookieJar;
 use crate::{
-    EngineEvent, FailureKind, FetchError, FetchMetadata, FetchOutput, JobId, JobProgress, Stage,
+    EngineEvent, FailureKind, FetchError, FetchMetadata, FetchOutput, JobId, JobProgress,
+    ResponseHeaders, Stage,
 };
 
+/// How many times one fetch resumes an interrupted body with a `Range` request.
+const MAX_RESUME_ATTEMPTS: usize = 3;
+
+/// Which redirects a fetch follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectPolicy {
+    /// Follow any redirect, up to `redirect_limit`.
+    #[default]
+    FollowAll,
+    /// Follow redirects within the original host (ignoring a `www.` prefix);
+    /// anything else fails with `FailureKind::CrossDomainRedirect`.
+    SameDomain,
+    /// Fail on the first redirect.
+    None,
+}
+
 #[derive(Debug, Clone)]
 pub struct FetchSettings {
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
     pub redirect_limit: usize,
+    pub redirect_policy: RedirectPolicy,
     pub max_bytes: u64,
     pub allowed_content_types: Vec<String>,
     pub user_agent: String,
+    /// Send a HEAD request first and fail fast on a disallowed content type or
+    /// a declared size over `max_bytes`. Servers that reject HEAD are fetched
+    /// normally.
+    pub head_preflight: bool,
+    /// Cookies sent with every request whose URL they match.
+    pub cookies: CookieJar,
+}
+
+impl FetchSettings {
+    /// Whether `content_type` (parameters ignored) is in `allowed_content_types`.
+    pub(crate) fn allows_content_type(&self, content_type: &str) -> bool {
+        let ct = content_type
+            .split(';')
+            .next()
+            .unwrap_or(content_type)
+            .trim();
+        self.allowed_content_types
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(ct))
+    }
 }
 
 impl Default for FetchSettings {
@@ -28,6 +67,7 @@ impl Default for FetchSettings {
             connect_timeout: Duration::from_secs(10),
             request_timeout: Duration::from_secs(30),
             redirect_limit: 5,
+            redirect_policy: RedirectPolicy::FollowAll,
             max_bytes: 5 * 1024 * 1024,
             allowed_content_types: vec![
                 "text/html".to_string(),
@@ -35,6 +75,8 @@ impl Default for FetchSettings {
                 "text/plain".to_string(),
             ],
             user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/122.0 Safari/537.36".to_string(),
+            head_preflight: false,
+            cookies: CookieJar::default(),
         }
     }
 }
@@ -69,84 +111,124 @@ pub trait Fetcher: Send + Sync {
     ) -> Result<FetchOutput, FetchError>;
 }
 
+/// HTTP fetcher backed by one pooled `reqwest` client.
+///
+/// The client is built once so keep-alive connections and TLS sessions are
+/// reused across fetches. Redirects are followed by hand, which lets each
+/// fetch count its own redirects without a per-request redirect policy.
 #[derive(Debug, Clone)]
 pub struct ReqwestFetcher {
     settings: FetchSettings,
+    client: Result<reqwest::Client, FetchError>,
 }
 
 impl ReqwestFetcher {
     pub fn new(settings: FetchSettings) -> Self {
-        Self { settings }
+        let client = reqwest::Client::builder()
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.request_timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(settings.user_agent.clone())
+            .build()
+            .map_err(|err| FetchError::new(FailureKind::Network, err.to_string()));
+        Self { settings, client }
     }
 
-    fn build_client(
+    /// Send `method` to `url`, following up to `redirect_limit` redirects.
+    async fn send_following_redirects(
         &self,
-        redirect_counter: Arc<AtomicUsize>,
-    ) -> Result<reqwest::Client, FetchError> {
-        let redirect_limit = self.settings.redirect_limit;
-        let policy = reqwest::redirect::Policy::custom(move |attempt| {
-            let count = attempt.previous().len();
-            redirect_counter.store(count, Ordering::Relaxed);
-            if count >= redirect_limit {
-                attempt.error("redirect limit exceeded")
-            } else {
-                attempt.follow()
+        method: Method,
+        url: reqwest::Url,
+    ) -> Result<(reqwest::Response, usize), FetchError> {
+        let client = self.client.as_ref().map_err(Clone::clone)?;
+        let mut current = url.clone();
+        let mut redirect_count = 0;
+        loop {
+            let mut request = client.request(method.clone(), current.clone());
+            if let Some(cookie) = self.settings.cookies.header_for(&current) {
+                request = request.header(COOKIE, cookie);
             }
-        });
-
-        reqwest::Client::builder()
-            .connect_timeout(self.settings.connect_timeout)
-            .timeout(self.settings.request_timeout)
-            .redirect(policy)
-            .user_agent(self.settings.user_agent.clone())
-            .build()
-            .map_err(|err| FetchError::new(FailureKind::Network, err.to_string()))
+            let response = request.send().await.map_err(map_reqwest_error)?;
+            if !response.status().is_redirection() {
+                return Ok((response, redirect_count));
+            }
+            let Some(location) = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+            else {
+                return Ok((response, redirect_count));
+            };
+            if self.settings.redirect_policy == RedirectPolicy::None {
+                return Err(FetchError::new(
+                    FailureKind::RedirectLimitExceeded,
+                    "redirects are disabled",
+                ));
+            }
+            if redirect_count >= self.settings.redirect_limit {
+                return Err(FetchError::new(
+                    FailureKind::RedirectLimitExceeded,
+                    format!("more than {} redirects", self.settings.redirect_limit),
+                ));
+            }
+            current = current.join(location).map_err(|err| {
+                FetchError::new(
+                    FailureKind::InvalidUrl,
+                    format!("bad redirect location: {err}"),
+                )
+            })?;
+            if self.settings.redirect_policy == RedirectPolicy::SameDomain
+                && !same_domain(&url, &current)
+            {
+                return Err(FetchError::new(
+                    FailureKind::CrossDomainRedirect {
+                        target_host: current.host_str().unwrap_or_default().to_string(),
+                    },
+                    format!("redirect to {current}"),
+                ));
+            }
+            redirect_count += 1;
+        }
     }
 
-    fn is_content_type_allowed(&self, content_type: &str) -> bool {
-        let ct = content_type
-            .split(';')
-            .next()
-            .unwrap_or(content_type)
-            .trim();
-        self.settings
-            .allowed_content_types
-            .iter()
-            .any(|allowed| allowed.eq_ignore_ascii_case(ct))
+    /// HEAD preflight. Only a successful HEAD response can fail the fetch;
+    /// anything else leaves the decision to the GET.
+    async fn preflight(&self, url: &reqwest::Url) -> Result<(), FetchError> {
+        let response = match self
+            .send_following_redirects(Method::HEAD, url.clone())
+            .await
+        {
+            Ok((response, _)) if response.status().is_success() => response,
+            Ok((response, _)) => {
+                engine_debug!(
+                    "HEAD preflight skipped for '{}': status {}",
+                    url,
+                    response.status().as_u16()
+                );
+                return Ok(());
+            }
+            Err(err) => {
+                engine_debug!("HEAD preflight skipped for '{}': {}", url, err.message);
+                return Ok(());
+            }
+        };
+        // The HEAD body is empty, so read the declared length from the header.
+        let declared_len = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        self.check_response_headers(url.as_str(), declared_len, content_type(&response))
     }
-}
 
-#[async_trait::async_trait]
-impl Fetcher for ReqwestFetcher {
-    async fn fetch(
+    /// Reject responses whose declared size or content type is not accepted.
+    fn check_response_headers(
         &self,
-        job_id: JobId,
         url: &str,
-        sink: &dyn ProgressSink,
-    ) -> Result<FetchOutput, FetchError> {
-        let parsed = reqwest::Url::parse(url).map_err(|err| {
-            engine_warn!("Invalid URL '{}': {}", url, err);
-            FetchError::new(FailureKind::InvalidUrl, err.to_string())
-        })?;
-        let redirect_counter = Arc::new(AtomicUsize::new(0));
-        let client = self.build_client(redirect_counter.clone())?;
-
-        let response = client.get(parsed.clone()).send().await.map_err(|err| {
-            let fetch_err = map_reqwest_error(err);
-            engine_warn!("Fetch failed for '{}': {}", url, fetch_err.kind);
-            fetch_err
-        })?;
-
-        let status = response.status();
-        if !status.is_success() {
-            engine_warn!("HTTP error {} for URL '{}'", status.as_u16(), url);
-            return Err(FetchError::new(
-                FailureKind::HttpStatus(status.as_u16()),
-                status.to_string(),
-            ));
-        }
-
-        if let Some(content_len) = response.content_length() {
+        content_len: Option<u64>,
+        content_type: Option<&str>,
+    ) -> Result<(), FetchError> {
+        if let Some(content_len) = content_len {
             if content_len > self.settings.max_bytes {
                 engine_warn!(
                     "Response too large for '{}': {} bytes (max {})",
@@ -164,15 +246,8 @@ impl Fetcher for ReqwestFetcher {
             }
         }
 
-        let final_url = response.url().to_string();
-        let content_type = response
-            .headers()
-            .get(CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .map(|value| value.to_string());
-
-        if let Some(ct) = content_type.as_deref() {
-            if !self.is_content_type_allowed(ct) {
+        if let Some(ct) = content_type {
+            if !self.settings.allows_content_type(ct) {
                 engine_warn!("Unsupported content type '{}' for URL '{}'", ct, url);
                 return Err(FetchError::new(
                     FailureKind::UnsupportedContentType {
@@ -182,6 +257,90 @@ impl Fetcher for ReqwestFetcher {
                 ));
             }
         }
+        Ok(())
+    }
+
+    /// Request the rest of an interrupted body starting at `offset`. Returns
+    /// `None` unless the server answers with the matching partial content.
+    async fn resume(&self, url: &reqwest::Url, offset: u64) -> Option<reqwest::Response> {
+        let client = self.client.as_ref().ok()?;
+        engine_info!("Resuming '{}' from byte {}", url, offset);
+        let mut request = client
+            .get(url.clone())
+            .header(RANGE, format!("bytes={offset}-"));
+        if let Some(cookie) = self.settings.cookies.header_for(url) {
+            request = request.header(COOKIE, cookie);
+        }
+        let response = request
+            .send()
+            .await
+            .inspect_err(|err| engine_warn!("Resume failed for '{}': {}", url, err))
+            .ok()?;
+        let start = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(content_range_start);
+        if response.status() != StatusCode::PARTIAL_CONTENT || start != Some(offset) {
+            engine_warn!(
+                "Resume rejected for '{}': status {}, range start {:?}",
+                url,
+                response.status().as_u16(),
+                start
+            );
+            return None;
+        }
+        Some(response)
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for ReqwestFetcher {
+    async fn fetch(
+        &self,
+        job_id: JobId,
+        url: &str,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchOutput, FetchError> {
+        let parsed = reqwest::Url::parse(url).map_err(|err| {
+            engine_warn!("Invalid URL '{}': {}", url, err);
+            FetchError::new(FailureKind::InvalidUrl, err.to_string())
+        })?;
+        if self.settings.head_preflight {
+            self.preflight(&parsed).await?;
+        }
+        let (response, redirect_count) = self
+            .send_following_redirects(Method::GET, parsed)
+            .await
+            .inspect_err(|fetch_err| {
+            engine_warn!("Fetch failed for '{}': {}", url, fetch_err.kind);
+        })?;
+
+        let status = response.status();
+        if let Some(retry_after_secs) = rate_limit_delay(&response) {
+            engine_warn!(
+                "Rate limited by '{}': status {}, retry after {}s",
+                url,
+                status.as_u16(),
+                retry_after_secs
+            );
+            return Err(FetchError::new(
+                FailureKind::RateLimited { retry_after_secs },
+                status.to_string(),
+            ));
+        }
+        if !status.is_success() {
+            engine_warn!("HTTP error {} for URL '{}'", status.as_u16(), url);
+            return Err(FetchError::new(
+                FailureKind::HttpStatus(status.as_u16()),
+                status.to_string(),
+            ));
+        }
+
+        let final_url = response.url().to_string();
+        let content_type = content_type(&response).map(ToOwned::to_owned);
+        let headers = provenance_headers(&response);
+        self.check_response_headers(url, response.content_length(), content_type.as_deref())?;
 
         sink.emit(EngineEvent::Progress(JobProgress {
             job_id,
@@ -197,14 +356,29 @@ impl Fetcher for ReqwestFetcher {
             url
         );
 
+        let accepts_ranges = accepts_byte_ranges(&response);
+        let resume_url = response.url().clone();
+        let mut resumes = 0;
         let mut bytes = Vec::new();
         let mut stream = response.bytes_stream();
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|err| {
-                let fetch_err = map_reqwest_error(err);
-                engine_warn!("Stream error for '{}': {}", url, fetch_err.kind);
-                fetch_err
-            })?;
+        loop {
+            let chunk = match stream.next().await {
+                None => break,
+                Some(Ok(chunk)) => chunk,
+                Some(Err(err)) => {
+                    let fetch_err = map_reqwest_error(err);
+                    engine_warn!("Stream error for '{}': {}", url, fetch_err.kind);
+                    if !accepts_ranges || bytes.is_empty() || resumes >= MAX_RESUME_ATTEMPTS {
+                        return Err(fetch_err);
+                    }
+                    resumes += 1;
+                    let Some(resumed) = self.resume(&resume_url, bytes.len() as u64).await else {
+                        return Err(fetch_err);
+                    };
+                    stream = resumed.bytes_stream();
+                    continue;
+                }
+            };
             let next_len = bytes.len() as u64 + chunk.len() as u64;
             if next_len > self.settings.max_bytes {
                 engine_warn!(
@@ -234,15 +408,82 @@ impl Fetcher for ReqwestFetcher {
         let metadata = FetchMetadata {
             original_url: url.to_string(),
             final_url,
-            redirect_count: redirect_counter.load(Ordering::Relaxed),
+            redirect_count,
             content_type,
             byte_len: bytes.len() as u64,
+            headers,
         };
 
         Ok(FetchOutput { bytes, metadata })
     }
 }
 
+fn content_type(response: &reqwest::Response) -> Option<&str> {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn provenance_headers(response: &reqwest::Response) -> ResponseHeaders {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned)
+    };
+    ResponseHeaders {
+        last_modified: header(LAST_MODIFIED),
+        etag: header(ETAG),
+        server: header(SERVER),
+    }
+}
+
+/// Seconds to wait before retrying a 429/503 response, from its `Retry-After`
+/// header (delay-seconds or HTTP-date). `None` when the response is not rate
+/// limited or carries no usable header.
+fn rate_limit_delay(response: &reqwest::Response) -> Option<u64> {
+    let status = response.status();
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(
+        at.duration_since(std::time::SystemTime::now())
+            .map_or(0, |delay| delay.as_secs()),
+    )
+}
+
+/// Hosts match case-insensitively, treating `www.example.com` as `example.com`.
+fn same_domain(a: &reqwest::Url, b: &reqwest::Url) -> bool {
+    let host = |url: &reqwest::Url| {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        host.strip_prefix("www.")
+            .map(str::to_string)
+            .unwrap_or(host)
+    };
+    host(a) == host(b)
+}
+
+fn accepts_byte_ranges(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("bytes"))
+}
+
+/// First byte position of a `Content-Range: bytes start-end/total` header.
+fn content_range_start(value: &str) -> Option<u64> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    range.split('-').next()?.trim().parse().ok()
+}
+
 fn map_reqwest_error(err: reqwest::Error) -> FetchError {
     if err.is_timeout() {
         return FetchError::new(FailureKind::Timeout, err.to_string());
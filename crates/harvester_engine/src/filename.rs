@@ -1,13 +1,92 @@
+use std::path::Path;
+
 use sha2::{Digest, Sha256};
 
+/// Longest path Windows opens without a `\\?\` prefix: `MAX_PATH` less the
+/// terminating NUL, in UTF-16 units.
+pub const MAX_PATH_LEN: usize = 259;
+
+/// Title characters kept however deep the output directory is; past that the
+/// writer falls back to extended-length paths.
+const MIN_FITTED_TITLE_CHARS: usize = 16;
+
+/// How titles become filenames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilenameOptions {
+    /// Transliterate the title to ASCII (Latin diacritics, Cyrillic, Greek);
+    /// characters without a transliteration are dropped, and a title that
+    /// leaves nothing falls back to the URL's last path segment.
+    pub transliterate: bool,
+}
+
 /// Windows-safe, deterministic filename: `{sanitized_title}--{short_hash(url)}.md`
 pub fn deterministic_filename(title: Option<&str>, url: &str) -> String {
-    let sanitized = sanitize_title(title.unwrap_or("untitled"));
+    deterministic_filename_with(title, url, &FilenameOptions::default())
+}
+
+/// Like [`deterministic_filename`], with the title handling in `options`.
+pub fn deterministic_filename_with(
+    title: Option<&str>,
+    url: &str,
+    options: &FilenameOptions,
+) -> String {
+    let sanitized = sanitize_title(title.unwrap_or("untitled"), url, options);
     let hash = short_hash(url);
     format!("{sanitized}--{hash}.md")
 }
 
-fn sanitize_title(input: &str) -> String {
+/// Shorten the title part of a [`deterministic_filename`] so that `dir`
+/// joined with it, plus `nested_len` more path characters written beneath it,
+/// stays within [`MAX_PATH_LEN`]. The same directory always yields the same
+/// name, and the `--{hash}.md` suffix is kept intact.
+pub fn fit_filename_to_path(filename: &str, dir: &Path, nested_len: usize) -> String {
+    let Some((title, suffix)) = filename.rsplit_once("--") else {
+        return filename.to_string();
+    };
+    let dir = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let path_len = path_units(
+        dir.as_os_str()
+            .to_string_lossy()
+            .trim_end_matches(['/', '\\']),
+    ) + 1
+        + path_units(filename)
+        + nested_len;
+    let Some(excess) = path_len
+        .checked_sub(MAX_PATH_LEN)
+        .filter(|&excess| excess > 0)
+    else {
+        return filename.to_string();
+    };
+
+    let mut kept = title.len();
+    let mut removed = 0;
+    for (index, c) in title.char_indices().rev() {
+        if removed >= excess || title[..index].chars().count() < MIN_FITTED_TITLE_CHARS {
+            break;
+        }
+        kept = index;
+        removed += c.len_utf16();
+    }
+    // Windows drops trailing dots and spaces from names.
+    let title = title[..kept].trim_end_matches(['_', ' ', '.']);
+    format!("{title}--{suffix}")
+}
+
+/// Length of `text` as Windows counts it towards `MAX_PATH`.
+fn path_units(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn sanitize_title(input: &str, url: &str, options: &FilenameOptions) -> String {
+    let transliterated;
+    let input = if options.transliterate {
+        transliterated = Some(transliterate(input))
+            .filter(|ascii| ascii.chars().any(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or_else(|| transliterate(&url_slug(url)));
+        transliterated.as_str()
+    } else {
+        input
+    };
     let mut cleaned: String = input
         .chars()
         .map(|c| if is_forbidden(c) { '_' } else { c })
@@ -32,7 +111,11 @@ fn sanitize_title(input: &str) -> String {
     }
     let mut final_name = compacted;
     if final_name.len() > 80 {
-        final_name.truncate(80);
+        let mut end = 80;
+        while !final_name.is_char_boundary(end) {
+            end -= 1;
+        }
+        final_name.truncate(end);
     }
     if is_reserved_windows_name(&final_name) {
         final_name.push('_');
@@ -40,6 +123,165 @@ fn sanitize_title(input: &str) -> String {
     final_name
 }
 
+/// ASCII rendering of `text`; characters without a transliteration are dropped.
+fn transliterate(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        if c.is_ascii() {
+            out.push(c);
+        } else if let Some(ascii) = transliterate_char(lower) {
+            // Title-case multi-letter transliterations: "Ж" becomes "Zh".
+            let mut letters = ascii.chars();
+            if let (true, Some(first)) = (c.is_uppercase(), letters.next()) {
+                out.push(first.to_ascii_uppercase());
+                out.push_str(letters.as_str());
+            } else {
+                out.push_str(ascii);
+            }
+        } else if c.is_whitespace() {
+            out.push(' ');
+        }
+    }
+    out
+}
+
+/// ASCII for a lowercase character, if it has one.
+fn transliterate_char(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ğ' => "g",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => "i",
+        'ł' | 'ľ' | 'ĺ' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'œ' => "oe",
+        'ř' | 'ŕ' => "r",
+        'ś' | 'š' | 'ş' | 'ș' => "s",
+        'ß' => "ss",
+        'ť' | 'ţ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        // Cyrillic (Russian, Ukrainian, Belarusian).
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' | 'ґ' => "g",
+        'д' => "d",
+        'е' | 'э' => "e",
+        'ё' => "yo",
+        'є' => "ye",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'і' => "i",
+        'ї' => "yi",
+        'й' | 'ы' => "y",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ў' => "w",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        // Greek.
+        'α' | 'ά' => "a",
+        'β' => "v",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        // Typographic punctuation.
+        '‘' | '’' | '′' => "'",
+        '“' | '”' | '«' | '»' => "\"",
+        '–' | '—' | '‐' => "-",
+        '…' => "...",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+/// [`path_slug`] of `url`, or its host when the path is empty.
+fn url_slug(url: &str) -> String {
+    path_slug(url).unwrap_or_else(|| {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_default()
+    })
+}
+
+/// Last non-empty path segment of `url` without its extension,
+/// percent-decoded, with `-`, `_` and `+` read as spaces.
+pub(crate) fn path_slug(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let segment = parsed
+        .path_segments()?
+        .rfind(|segment| !segment.is_empty())?;
+    let segment = segment.split('.').next().unwrap_or(segment);
+    Some(percent_decode(segment).replace(['-', '_', '+'], " "))
+}
+
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 fn is_forbidden(c: char) -> bool {
     matches!(
         c,
@@ -55,7 +297,7 @@ fn is_reserved_windows_name(name: &str) -> bool {
     RESERVED.iter().any(|r| r.eq_ignore_ascii_case(name))
 }
 
-fn short_hash(input: &str) -> String {
+pub(crate) fn short_hash(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
     let digest = hasher.finalize();
@@ -66,3 +308,40 @@ fn short_hash(input: &str) -> String {
     }
     hex
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliteration_covers_cyrillic_and_falls_back_to_the_url_slug() {
+        let ascii = FilenameOptions {
+            transliterate: true,
+        };
+        let name = |title, url| deterministic_filename_with(Some(title), url, &ascii);
+
+        assert!(name("Жизнь в Москве", "https://ex.ru/a").starts_with("Zhizn v Moskve--"));
+        assert!(name("Café Ærø", "https://ex.dk/b").starts_with("Cafe Aero--"));
+        assert!(name("東京の天気", "https://ex.jp/news/tokyo-weather.html")
+            .starts_with("tokyo weather--"));
+        assert!(name("東京", "https://ex.jp/").starts_with("ex.jp--"));
+        assert!(deterministic_filename(Some("東京"), "https://ex.jp/").starts_with("東京--"));
+    }
+
+    #[test]
+    fn deep_directories_shorten_the_title_deterministically() {
+        let filename = deterministic_filename(Some(&"Long title ".repeat(8)), "https://ex.com/a");
+        let shallow = Path::new("/out");
+        assert_eq!(fit_filename_to_path(&filename, shallow, 0), filename);
+
+        let deep = Path::new("/").join("d".repeat(200));
+        let fitted = fit_filename_to_path(&filename, &deep, 0);
+        assert_eq!(fitted, fit_filename_to_path(&filename, &deep, 0));
+        assert!(fitted.ends_with(filename.rsplit_once("--").unwrap().1));
+        assert!(deep.join(&fitted).to_string_lossy().len() <= MAX_PATH_LEN);
+
+        let deepest = Path::new("/").join("d".repeat(300));
+        let fitted = fit_filename_to_path(&filename, &deepest, 0);
+        assert!(fitted.starts_with("Long title Long--"));
+    }
+}
@@ -0,0 +1,85 @@
+//! Offline fetches from a fixtures directory, for demos and tests that run
+//! the whole pipeline without network access.
+use std::path::PathBuf;
+
+use engine_logging::engine_warn;
+
+use crate::fetch::{FetchSettings, Fetcher, ProgressSink};
+use crate::filename::short_hash;
+use crate::{
+    EngineEvent, FailureKind, FetchError, FetchMetadata, FetchOutput, JobId, JobProgress,
+    ResponseHeaders, Stage,
+};
+
+/// File in a fixtures directory that holds the HTML served for `url`.
+pub fn fixture_filename(url: &str) -> String {
+    format!("{}.html", short_hash(url))
+}
+
+/// Serves each URL from `{dir}/{fixture_filename(url)}` as `text/html`.
+/// URLs without a fixture fail with [`FailureKind::NotArchived`].
+#[derive(Debug, Clone)]
+pub struct FixtureFetcher {
+    dir: PathBuf,
+    settings: FetchSettings,
+}
+
+impl FixtureFetcher {
+    pub fn new(dir: PathBuf, settings: FetchSettings) -> Self {
+        Self { dir, settings }
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for FixtureFetcher {
+    async fn fetch(
+        &self,
+        job_id: JobId,
+        url: &str,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchOutput, FetchError> {
+        let path = self.dir.join(fixture_filename(url));
+        let read_path = path.clone();
+        let bytes = tokio::task::spawn_blocking(move || std::fs::read(read_path))
+            .await
+            .map_err(|err| FetchError::new(FailureKind::ProcessingError, err.to_string()))?
+            .map_err(|err| {
+                engine_warn!(
+                    "[Fixture] No fixture for '{}' at {}: {}",
+                    url,
+                    path.display(),
+                    err
+                );
+                FetchError::new(FailureKind::NotArchived, url)
+            })?;
+
+        let byte_len = bytes.len() as u64;
+        if byte_len > self.settings.max_bytes {
+            return Err(FetchError::new(
+                FailureKind::TooLarge {
+                    max_bytes: self.settings.max_bytes,
+                    actual: Some(byte_len),
+                },
+                "fixture too large",
+            ));
+        }
+        sink.emit(EngineEvent::Progress(JobProgress {
+            job_id,
+            stage: Stage::Downloading,
+            bytes: Some(byte_len),
+            tokens: None,
+            content_preview: None,
+        }));
+        Ok(FetchOutput {
+            bytes,
+            metadata: FetchMetadata {
+                original_url: url.to_string(),
+                final_url: url.to_string(),
+                redirect_count: 0,
+                content_type: Some("text/html".to_string()),
+                byte_len,
+                headers: ResponseHeaders::default(),
+            },
+        })
+    }
+}
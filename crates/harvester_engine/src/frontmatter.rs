@@ -1,4 +1,47 @@
+use std::path::Path;
+
+use crate::doc_id::document_id;
+use crate::extract::ExtractionStrategy;
+use crate::persist::{AtomicFileWriter, PersistError};
+use crate::quality::ReadingMetrics;
 use crate::token::TokenCounter;
+use crate::walls::ContentWarning;
+
+/// Optional frontmatter fields written after the standard ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontmatterExtras {
+    /// URLs of a page stitched from several parts; listed under `parts:` when
+    /// there is more than one.
+    pub part_urls: Vec<String>,
+    /// Publication date (`YYYY-MM-DD` or `YYYY-MM`), written as `published:`.
+    pub published: Option<String>,
+    /// Size of the body, written as `word_count:` and `reading_minutes:`.
+    pub reading: Option<ReadingMetrics>,
+    /// Content quality score (0-100), written as `quality_score:`.
+    pub quality_score: Option<u8>,
+    /// Suspected paywall or consent wall, written as `content_warning:`.
+    pub content_warning: Option<ContentWarning>,
+    /// Short summary of the body, written as `summary:` on one line.
+    pub summary: Option<String>,
+    /// Most frequent terms of the body, written as `keywords:`.
+    pub keywords: Vec<String>,
+    /// Label of the paste or batch the URL came from, written as `source_batch:`.
+    pub source_batch: Option<String>,
+    /// Tokens per top-level section as `(heading, tokens)`, listed under
+    /// `section_tokens:`.
+    pub section_tokens: Vec<(String, u32)>,
+    /// Extraction strategy that produced the body, written as `extraction:`.
+    pub extraction: Option<ExtractionStrategy>,
+    /// Bytes of repeated site chrome removed, written as `stripped_chrome_bytes:`.
+    pub stripped_chrome_bytes: Option<usize>,
+    /// Read from a local file rather than fetched, written as `source: local`.
+    pub local_source: bool,
+    /// Invalid bytes were replaced while decoding, written as `decode_lossy: true`.
+    pub decode_lossy: bool,
+    /// Provenance headers of the response as `(lowercase name, value)`,
+    /// listed under `response_headers:`.
+    pub response_headers: Vec<(&'static str, String)>,
+}
 
 pub fn build_markdown_document(
     url: &str,
@@ -7,16 +50,95 @@ pub fn build_markdown_document(
     fetched_utc: &str,
     body_markdown: &str,
     token_counter: &dyn TokenCounter,
+) -> (u32, String) {
+    build_markdown_document_with(
+        url,
+        title,
+        encoding,
+        fetched_utc,
+        body_markdown,
+        token_counter,
+        &FrontmatterExtras::default(),
+    )
+}
+
+/// Like [`build_markdown_document`], with the optional fields in `extras`.
+pub fn build_markdown_document_with(
+    url: &str,
+    title: Option<&str>,
+    encoding: &str,
+    fetched_utc: &str,
+    body_markdown: &str,
+    token_counter: &dyn TokenCounter,
+    extras: &FrontmatterExtras,
 ) -> (u32, String) {
     let token_count = token_counter.count(body_markdown);
     let title_val = title.unwrap_or("untitled");
+    let mut optional = String::new();
+    if extras.local_source {
+        optional.push_str("source: local\n");
+    }
+    if extras.decode_lossy {
+        optional.push_str("decode_lossy: true\n");
+    }
+    if let Some(batch) = &extras.source_batch {
+        optional.push_str(&format!("source_batch: {batch}\n"));
+    }
+    if let Some(published) = &extras.published {
+        optional.push_str(&format!("published: {published}\n"));
+    }
+    if let Some(strategy) = extras.extraction {
+        optional.push_str(&format!("extraction: {strategy}\n"));
+    }
+    if let Some(bytes) = extras.stripped_chrome_bytes {
+        optional.push_str(&format!("stripped_chrome_bytes: {bytes}\n"));
+    }
+    if let Some(reading) = extras.reading {
+        optional.push_str(&format!(
+            "word_count: {}\nreading_minutes: {}\n",
+            reading.word_count, reading.reading_minutes
+        ));
+    }
+    if let Some(score) = extras.quality_score {
+        optional.push_str(&format!("quality_score: {score}\n"));
+    }
+    if let Some(warning) = extras.content_warning {
+        optional.push_str(&format!("content_warning: {warning}\n"));
+    }
+    if let Some(summary) = &extras.summary {
+        let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+        optional.push_str(&format!("summary: {summary}\n"));
+    }
+    if !extras.keywords.is_empty() {
+        optional.push_str(&format!("keywords: {}\n", extras.keywords.join(", ")));
+    }
+    if !extras.response_headers.is_empty() {
+        optional.push_str("response_headers:\n");
+        for (name, value) in &extras.response_headers {
+            optional.push_str(&format!("  {name}: {value}\n"));
+        }
+    }
+    if !extras.section_tokens.is_empty() {
+        optional.push_str("section_tokens:\n");
+        for (heading, tokens) in &extras.section_tokens {
+            optional.push_str(&format!("  - {heading}: {tokens}\n"));
+        }
+    }
+    if extras.part_urls.len() > 1 {
+        optional.push_str("parts:\n");
+        for part in &extras.part_urls {
+            optional.push_str(&format!("  - {part}\n"));
+        }
+    }
     let frontmatter = format!(
-        "---\nurl: {url}\ntitle: {title}\nfetched_utc: {fetched_utc}\nencoding: {encoding}\ntoken_count: {token_count}\n---\n\n",
+        "---\nurl: {url}\ndoc_id: {doc_id}\ntitle: {title}\nfetched_utc: {fetched_utc}\nencoding: {encoding}\ntoken_count: {token_count}\n{optional}---\n\n",
         url = url,
+        doc_id = document_id(url),
         title = title_val,
         fetched_utc = fetched_utc,
         encoding = encoding,
         token_count = token_count,
+        optional = optional,
     );
     let doc = format!(
         "{frontmatter}{body}",
@@ -25,3 +147,40 @@ pub fn build_markdown_document(
     );
     (token_count, doc)
 }
+
+/// Recount the body of the document at `path` with `token_counter` and
+/// rewrite its `token_count:` line when the count differs. Returns the count.
+pub(crate) fn recount_document_tokens(
+    path: &Path,
+    token_counter: &dyn TokenCounter,
+) -> Result<u32, PersistError> {
+    let content = std::fs::read_to_string(path)?;
+    let malformed = || std::io::Error::new(std::io::ErrorKind::InvalidData, "no frontmatter");
+    let close = content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.find("\n---\n\n"))
+        .ok_or_else(malformed)?;
+    // `frontmatter` keeps the newline of its last field; `rest` is the
+    // closing `---` line, the blank line and the body.
+    let (frontmatter, rest) = content.split_at("---\n".len() + close + 1);
+    let tokens = token_counter.count(&rest["---\n\n".len()..]);
+
+    let mut updated = String::with_capacity(content.len());
+    let mut changed = false;
+    for line in frontmatter.split_inclusive('\n') {
+        match line.strip_prefix("token_count: ") {
+            Some(old) if old.trim() != tokens.to_string() => {
+                updated.push_str(&format!("token_count: {tokens}\n"));
+                changed = true;
+            }
+            _ => updated.push_str(line),
+        }
+    }
+    if changed {
+        updated.push_str(rest);
+        let dir = path.parent().ok_or_else(malformed)?;
+        let filename = path.file_name().ok_or_else(malformed)?.to_string_lossy();
+        AtomicFileWriter::new(dir.to_path_buf()).write(&filename, &updated)?;
+    }
+    Ok(tokens)
+}
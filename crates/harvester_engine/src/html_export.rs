@@ -0,0 +1,248 @@
+//! Single-file HTML bundle: every exported document rendered to HTML behind
+//! a table of contents, for readers who won't open raw markdown.
+use std::path::Path;
+
+use crate::export::{
+    selected_docs, write_manifest, DocMeta, ExportError, ExportOptions, ExportSummary,
+};
+use crate::persist::AtomicFileWriter;
+
+const STYLE: &str =
+    "body{font-family:sans-serif;max-width:50em;margin:auto;padding:1em;line-height:1.5}\
+    section{border-top:1px solid #ccc;margin-top:2em}\
+    .source{color:#666;font-size:.9em}\
+    blockquote{border-left:3px solid #ccc;margin-left:0;padding-left:1em;color:#444}\
+    pre{background:#f4f4f4;padding:.5em;overflow-x:auto}";
+
+/// Write the documents selected by `options` to one self-contained HTML file
+/// named after `options.output_filename`, with a table of contents linking
+/// to a `#doc-{n}` anchor per document.
+pub fn build_html_export(
+    output_dir: &Path,
+    options: ExportOptions,
+) -> Result<ExportSummary, ExportError> {
+    let docs = selected_docs(output_dir, &options)?;
+    let total_tokens: u64 = docs
+        .iter()
+        .map(|doc| doc.token_count.unwrap_or(0) as u64)
+        .sum();
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Harvest export</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>Harvest export</h1>\n<p>{count} documents, {total_tokens} tokens</p>\n<nav>\n<ol>\n",
+        count = docs.len(),
+    );
+    for (index, doc) in docs.iter().enumerate() {
+        html.push_str(&format!(
+            "<li><a href=\"#doc-{index}\">{title}</a></li>\n",
+            title = escape(&doc.title)
+        ));
+    }
+    html.push_str("</ol>\n</nav>\n");
+    for (index, doc) in docs.iter().enumerate() {
+        html.push_str(&document_section(index, doc));
+    }
+    html.push_str("</body>\n</html>\n");
+
+    let filename = Path::new(&options.output_filename).with_extension("html");
+    let writer = AtomicFileWriter::new(output_dir.to_path_buf());
+    let output_path = writer.write(&filename.to_string_lossy(), &html)?;
+    let manifest_path = write_manifest(
+        output_dir,
+        options.manifest_filename,
+        &docs,
+        None,
+        total_tokens,
+        options.session.as_ref(),
+        None,
+    )?;
+
+    Ok(ExportSummary {
+        doc_count: docs.len(),
+        total_tokens,
+        output_path,
+        manifest_path,
+    })
+}
+
+fn document_section(index: usize, doc: &DocMeta) -> String {
+    format!(
+        "<section id=\"doc-{index}\">\n<h1>{title}</h1>\n<p class=\"source\"><a href=\"{url}\">{url}</a> · fetched {fetched} · {tokens} tokens</p>\n{body}</section>\n",
+        title = escape(&doc.title),
+        url = escape(&doc.url),
+        fetched = escape(&doc.fetched_utc),
+        tokens = doc.token_count.unwrap_or(0),
+        body = render_markdown(doc.body.trim()),
+    )
+}
+
+/// Block structure of the markdown the converter writes: headings, lists,
+/// blockquotes, rules, fenced code, definition lists and paragraphs.
+/// Headings are shifted down one level below each document's `<h1>`.
+fn render_markdown(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut list_open = false;
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            close_list(&mut html, &mut list_open);
+            html.push_str(if in_code {
+                "</code></pre>\n"
+            } else {
+                "<pre><code>"
+            });
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            html.push_str(&escape(line));
+            html.push('\n');
+            continue;
+        }
+        let trimmed = line.trim();
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if !list_open {
+                html.push_str("<ul>\n");
+                list_open = true;
+            }
+            html.push_str(&format!("<li>{}</li>\n", inline(item)));
+            continue;
+        }
+        close_list(&mut html, &mut list_open);
+        if trimmed.is_empty() {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let tag = (level + 1).min(6);
+            html.push_str(&format!(
+                "<h{tag}>{}</h{tag}>\n",
+                inline(trimmed[level..].trim())
+            ));
+        } else if trimmed == "---" {
+            html.push_str("<hr>\n");
+        } else if trimmed.starts_with('>') {
+            let depth = trimmed.chars().filter(|&c| c == '>').count();
+            let text = trimmed.trim_start_matches(['>', ' ']);
+            if !text.is_empty() {
+                let open = "<blockquote>".repeat(depth);
+                let close = "</blockquote>".repeat(depth);
+                html.push_str(&format!("{open}<p>{}</p>{close}\n", inline(text)));
+            }
+        } else if let Some(definition) = trimmed.strip_prefix(": ") {
+            html.push_str(&format!("<dl><dd>{}</dd></dl>\n", inline(definition)));
+        } else {
+            html.push_str(&format!("<p>{}</p>\n", inline(trimmed)));
+        }
+    }
+    close_list(&mut html, &mut list_open);
+    if in_code {
+        html.push_str("</code></pre>\n");
+    }
+    html
+}
+
+fn close_list(html: &mut String, list_open: &mut bool) {
+    if *list_open {
+        html.push_str("</ul>\n");
+        *list_open = false;
+    }
+}
+
+/// Inline markup: `code`, **bold**, [text](url) and <url>; everything else
+/// is escaped.
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['`', '*', '[', '<']) {
+        out.push_str(&escape(&rest[..pos]));
+        let from = &rest[pos..];
+        let consumed = if let Some(code) = delimited(from, "`") {
+            out.push_str(&format!("<code>{}</code>", escape(code)));
+            Some(code.len() + 2)
+        } else if let Some(bold) = delimited(from, "**") {
+            out.push_str(&format!("<strong>{}</strong>", escape(bold)));
+            Some(bold.len() + 4)
+        } else if let Some((label, url, len)) = link(from) {
+            out.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape(url),
+                escape(label)
+            ));
+            Some(len)
+        } else {
+            None
+        };
+        match consumed {
+            Some(len) => rest = &from[len..],
+            None => {
+                out.push_str(&escape(&from[..1]));
+                rest = &from[1..];
+            }
+        }
+    }
+    out.push_str(&escape(rest));
+    out
+}
+
+/// Text between a `marker` at the start of `s` and the next `marker`.
+fn delimited<'a>(s: &'a str, marker: &str) -> Option<&'a str> {
+    let inner = s.strip_prefix(marker)?;
+    let end = inner.find(marker)?;
+    (end > 0).then(|| &inner[..end])
+}
+
+/// `[label](url)` or `<http...>` at the start of `s`: label, url and length.
+fn link(s: &str) -> Option<(&str, &str, usize)> {
+    if let Some(inner) = s.strip_prefix('<') {
+        let end = inner.find('>')?;
+        let url = &inner[..end];
+        return (url.starts_with("http://") || url.starts_with("https://")).then_some((
+            url,
+            url,
+            end + 2,
+        ));
+    }
+    if !s.starts_with('[') {
+        return None;
+    }
+    let close = s.find("](")?;
+    let label = &s[1..close];
+    let url_end = close + 2 + s[close + 2..].find(')')?;
+    let url = &s[close + 2..url_end];
+    let scriptable = url
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("javascript:");
+    (!label.contains('[') && !url.contains(char::is_whitespace) && !scriptable).then_some((
+        label,
+        url,
+        url_end + 1,
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_converter_markdown_blocks_and_inline_markup() {
+        let markdown = "# Title\nIntro with **bold**, `a<b` and [link](https://x.test/?a=1&b=2).\n- one\n- two\n> quoted\n```\nlet x = 1 < 2;\n```\n**Term**\n: meaning";
+        assert_eq!(
+            render_markdown(markdown),
+            "<h2>Title</h2>\n\
+             <p>Intro with <strong>bold</strong>, <code>a&lt;b</code> and <a href=\"https://x.test/?a=1&amp;b=2\">link</a>.</p>\n\
+             <ul>\n<li>one</li>\n<li>two</li>\n</ul>\n\
+             <blockquote><p>quoted</p></blockquote>\n\
+             <pre><code>let x = 1 &lt; 2;\n</code></pre>\n\
+             <p><strong>Term</strong></p>\n\
+             <dl><dd>meaning</dd></dl>\n"
+        );
+    }
+}
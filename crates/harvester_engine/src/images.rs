@@ -0,0 +1,49 @@
+//! Inventory of the images linked from harvested pages, as CSV, for picking
+//! figures and diagrams to download separately.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::persist::{AtomicFileWriter, PersistError};
+
+pub const IMAGE_INVENTORY_FILENAME: &str = "images.csv";
+
+/// An image found on a harvested page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub image_url: String,
+    /// The page the image was found on.
+    pub page_url: String,
+    pub alt_text: Option<String>,
+}
+
+/// Write `images` to [`IMAGE_INVENTORY_FILENAME`] in `output_dir`, one row per
+/// image and page, in the given order. An image used on several pages gets a
+/// row for each.
+pub fn write_image_inventory(
+    output_dir: &Path,
+    images: &[ImageRef],
+) -> Result<PathBuf, PersistError> {
+    let mut seen = HashSet::new();
+    let mut csv = String::from("image_url,page_url,alt_text\n");
+    for image in images {
+        if !seen.insert((&image.image_url, &image.page_url)) {
+            continue;
+        }
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            csv_field(&image.image_url),
+            csv_field(&image.page_url),
+            csv_field(image.alt_text.as_deref().unwrap_or_default())
+        ));
+    }
+    AtomicFileWriter::new(output_dir.to_path_buf()).write(IMAGE_INVENTORY_FILENAME, &csv)
+}
+
+/// RFC 4180 quoting, only where the field needs it.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,90 @@
+//! Keyword extraction by term frequency.
+//!
+//! Words are lowercased and counted across the prose of a document, leaving
+//! out code blocks, link targets and common English stopwords. The most
+//! frequent terms become the document's `keywords:`.
+use std::collections::HashMap;
+
+use crate::summarize::strip_links;
+
+/// Keywords kept per document.
+pub(crate) const MAX_KEYWORDS: usize = 8;
+
+/// Terms occurring fewer times than this are not keywords.
+const MIN_OCCURRENCES: usize = 2;
+
+/// Shorter words are almost never topical.
+const MIN_WORD_CHARS: usize = 3;
+
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "and", "any", "are", "because",
+    "been", "before", "being", "below", "between", "both", "but", "can", "could", "did", "does",
+    "doing", "down", "during", "each", "even", "few", "for", "from", "further", "had", "has",
+    "have", "having", "her", "here", "hers", "herself", "him", "himself", "his", "how", "into",
+    "its", "itself", "just", "like", "many", "may", "more", "most", "much", "must", "not", "now",
+    "off", "once", "one", "only", "other", "our", "ours", "out", "over", "own", "same", "she",
+    "should", "some", "such", "than", "that", "the", "their", "theirs", "them", "then", "there",
+    "these", "they", "this", "those", "through", "too", "under", "until", "upon", "use", "used",
+    "using", "very", "was", "way", "were", "what", "when", "where", "which", "while", "who",
+    "whom", "why", "will", "with", "within", "without", "would", "yet", "you", "your", "yours",
+];
+
+/// Up to [`MAX_KEYWORDS`] most frequent terms of `markdown`, most frequent
+/// first; ties keep the order of first occurrence.
+pub(crate) fn extract_keywords(markdown: &str) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        for word in strip_links(line).split(|c: char| !c.is_alphanumeric() && c != '-') {
+            let word = word.trim_matches('-').to_lowercase();
+            if word.chars().count() < MIN_WORD_CHARS
+                || !word.chars().any(char::is_alphabetic)
+                || STOPWORDS.contains(&word.as_str())
+            {
+                continue;
+            }
+            let first_seen = counts.len();
+            counts.entry(word).or_insert((0, first_seen)).0 += 1;
+        }
+    }
+    let mut terms: Vec<(String, (usize, usize))> = counts
+        .into_iter()
+        .filter(|(_, (count, _))| *count >= MIN_OCCURRENCES)
+        .collect();
+    terms.sort_by(|(_, (a_count, a_seen)), (_, (b_count, b_seen))| {
+        b_count.cmp(a_count).then(a_seen.cmp(b_seen))
+    });
+    terms
+        .into_iter()
+        .take(MAX_KEYWORDS)
+        .map(|(term, _)| term)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequent_terms_win_and_stopwords_are_skipped() {
+        let markdown = "# Rust ownership\n\nThe borrow checker enforces ownership. \
+            Ownership and the [borrow](https://example.com/borrow) checker work together; \
+            the checker is strict.\n\n```\nlet checker = checker;\n```\n";
+        assert_eq!(
+            extract_keywords(markdown),
+            vec!["ownership", "checker", "borrow"]
+        );
+    }
+
+    #[test]
+    fn terms_seen_once_are_not_keywords() {
+        assert!(extract_keywords("A short note about nothing much.").is_empty());
+    }
+}
@@ -1,30 +1,89 @@
 //! Harvester engine: IO pipeline and effect execution.
+mod async_engine;
+mod browser_cookies;
+mod builder;
+mod chunk;
 mod convert;
+mod cookies;
+mod crypt;
 mod decode;
+mod diff;
+mod doc_id;
 mod engine;
 mod export;
 mod extract;
 mod fetch;
 mod filename;
+mod fixture;
 mod frontmatter;
+mod html_export;
+mod images;
+mod keywords;
 mod links;
+mod local;
+mod memory;
+mod mhtml;
+mod monitor;
+mod pagination;
 mod persist;
+mod pipeline;
 mod preview;
+mod published;
+mod quality;
+mod response_cache;
+mod sections;
+mod session_report;
+mod strip_rules;
+mod summarize;
+mod template;
 mod token;
+mod token_cache;
 mod types;
+mod vault;
+mod versions;
+mod walls;
+mod warc;
 
+pub use async_engine::AsyncEngineHandle;
+pub use browser_cookies::Browser;
+pub use builder::{EngineBuildError, EngineBuilder};
+pub use chunk::{chunk_markdown, Chunk, ChunkOptions};
 pub use convert::{Converter, Html2MdConverter};
-pub use decode::{decode_html, DecodeError, DecodedHtml};
-pub use engine::{EngineConfig, EngineHandle};
-pub use export::{build_concatenated_export, ExportError, ExportOptions, ExportSummary};
-pub use extract::{ExtractedContent, Extractor, ReadabilityLikeExtractor};
-pub use fetch::{FetchSettings, Fetcher, ProgressSink, ReqwestFetcher};
-pub use filename::deterministic_filename;
-pub use frontmatter::build_markdown_document;
+pub use cookies::{Cookie, CookieImportError, CookieJar};
+pub use crypt::{is_encrypted, CryptError, Passphrase};
+pub use decode::{decode_html, decode_html_with, DecodeError, DecodedHtml};
+pub use diff::{diff_documents, ContentDiff};
+pub use doc_id::document_id;
+pub use engine::{EngineConfig, EngineEvents, EngineHandle};
+pub use export::{
+    build_chunked_export, build_concatenated_export, build_fitted_export, ExportError,
+    ExportFormat, ExportOptions, ExportScope, ExportSummary, FitOrder,
+};
+pub use extract::{ExtractedContent, ExtractionStrategy, Extractor, ReadabilityLikeExtractor};
+pub use fetch::{FetchSettings, Fetcher, ProgressSink, RedirectPolicy, ReqwestFetcher};
+pub use filename::{
+    deterministic_filename, deterministic_filename_with, fit_filename_to_path, FilenameOptions,
+    MAX_PATH_LEN,
+};
+pub use fixture::{fixture_filename, FixtureFetcher};
+pub use frontmatter::{build_markdown_document, build_markdown_document_with, FrontmatterExtras};
+pub use html_export::build_html_export;
+pub use images::{write_image_inventory, ImageRef, IMAGE_INVENTORY_FILENAME};
 pub use links::{ConversionOutput, ExtractedLink, LinkExtractingConverter, LinkKind};
+pub use mhtml::{parse_mhtml, MhtmlError, MhtmlPage};
 pub use persist::{ensure_output_dir, AtomicFileWriter, PersistError};
-pub use token::{TokenCounter, WhitespaceTokenCounter};
+pub use pipeline::{harvest_one, harvest_one_with, HarvestedDoc};
+pub use quality::{assess_quality, QualityReport, ReadingMetrics, LOW_QUALITY_SCORE};
+pub use session_report::SessionReport;
+pub use strip_rules::{StripRules, StripRulesError};
+pub use summarize::{LeadSentencesSummarizer, NoSummarizer, Summarizer};
+pub use template::StrippedChrome;
+pub use token::{ApproxTokenCounter, TokenCounter, WhitespaceTokenCounter};
 pub use types::{
     EngineEvent, FailureKind, FetchError, FetchMetadata, FetchOutput, JobId, JobOutcome,
-    JobProgress, Stage,
+    JobProgress, ResponseHeaders, Stage,
 };
+pub use vault::build_obsidian_vault;
+pub use versions::{page_history, PageVersion};
+pub use walls::{detect_content_wall, ContentWarning};
+pub use warc::{ArchivedResponse, WarcArchive, WarcError, WarcFetcher, WarcWriter};
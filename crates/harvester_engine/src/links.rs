@@ -15,8 +15,11 @@ pub enum LinkKind {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExtractedLink {
     pub url: String,
+    /// Anchor text, or the alt text of an image.
     pub text: Option<String>,
     pub kind: LinkKind,
+    /// Same registrable domain as the page it was found on.
+    pub internal: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -86,11 +89,32 @@ impl LinkExtractingConverter {
                 ctx.ensure_newline();
             }
             "p" | "div" | "section" | "article" | "header" | "footer" | "nav" | "figure"
-            | "figcaption" | "table" | "tr" | "td" | "th" | "blockquote" | "address" => {
+            | "figcaption" | "table" | "tr" | "td" | "th" | "address" | "dl" => {
                 ctx.ensure_newline();
                 self.visit_children(element, ctx);
                 ctx.ensure_newline();
             }
+            "blockquote" => {
+                ctx.ensure_newline();
+                ctx.quote_depth += 1;
+                self.visit_children(element, ctx);
+                ctx.end_quote();
+            }
+            "dt" => {
+                ctx.ensure_newline();
+                ctx.append_text("**");
+                let start = ctx.builder.len();
+                self.visit_children(element, ctx);
+                ctx.trim_trailing_space(start);
+                ctx.append_text("**");
+                ctx.ensure_newline();
+            }
+            "dd" => {
+                ctx.ensure_newline();
+                ctx.append_text(": ");
+                self.visit_children(element, ctx);
+                ctx.ensure_newline();
+            }
             "ul" | "ol" => {
                 ctx.ensure_newline();
                 self.visit_children(element, ctx);
@@ -166,7 +190,8 @@ impl LinkExtractingConverter {
     fn handle_image(&self, element: ElementRef, ctx: &mut ConversionContext) {
         if let Some(src) = element.value().attr("src").map(str::trim) {
             if let Some(url) = resolve_url(src, ctx.base_url.as_ref()) {
-                ctx.add_link(url.into(), String::new(), LinkKind::Image);
+                let alt = element.value().attr("alt").unwrap_or_default().trim();
+                ctx.add_link(url.into(), alt.to_string(), LinkKind::Image);
             }
         }
     }
@@ -193,6 +218,8 @@ struct ConversionContext {
     base_url: Option<Url>,
     max_links: usize,
     last_char: Option<char>,
+    /// Nesting of `<blockquote>`; each line inside gets one `> ` per level.
+    quote_depth: usize,
 }
 
 impl ConversionContext {
@@ -203,6 +230,7 @@ impl ConversionContext {
             base_url,
             max_links,
             last_char: None,
+            quote_depth: 0,
         }
     }
 
@@ -231,10 +259,36 @@ impl ConversionContext {
     }
 
     fn push_char(&mut self, ch: char) {
+        let at_line_start = self.builder.is_empty() || self.last_char == Some('\n');
+        if at_line_start && ch != '\n' && ch != ' ' {
+            for _ in 0..self.quote_depth {
+                self.builder.push_str("> ");
+            }
+        }
         self.builder.push(ch);
         self.last_char = Some(ch);
     }
 
+    /// Close one blockquote level with a separator line, so the text that
+    /// follows is not read as a lazy continuation of the quote.
+    fn end_quote(&mut self) {
+        self.ensure_newline();
+        self.quote_depth -= 1;
+        if self.builder.is_empty() {
+            return;
+        }
+        let separator = vec![">"; self.quote_depth].join(" ");
+        self.builder.push_str(&separator);
+        self.push_char('\n');
+    }
+
+    /// Drop whitespace written since `start`, so closing markers hug the text.
+    fn trim_trailing_space(&mut self, start: usize) {
+        let trimmed = self.builder[start..].trim_end().len();
+        self.builder.truncate(start + trimmed);
+        self.last_char = self.builder.chars().last();
+    }
+
     fn extract_substring(&self, start: usize, end: usize) -> String {
         self.builder[start..end].trim().to_string()
     }
@@ -250,8 +304,51 @@ impl ConversionContext {
             Some(text)
         };
 
-        self.links.push(ExtractedLink { url, text, kind });
+        let internal = self
+            .base_url
+            .as_ref()
+            .and_then(Url::host_str)
+            .zip(Url::parse(&url).ok().as_ref().and_then(Url::host_str))
+            .is_some_and(|(page, link)| same_registrable_domain(page, link));
+        self.links.push(ExtractedLink {
+            url,
+            text,
+            kind,
+            internal,
+        });
+    }
+}
+
+/// Second-level labels under which country-code domains register names, as
+/// in `example.co.uk`.
+const SHARED_SECOND_LEVELS: &[&str] = &["ac", "co", "com", "edu", "gov", "ne", "net", "or", "org"];
+
+/// Hosts share a registrable domain: `docs.example.com` and `example.com` do,
+/// `a.example.co.uk` and `b.other.co.uk` do not. A heuristic stand-in for the
+/// public suffix list; IP addresses must match exactly.
+fn same_registrable_domain(a: &str, b: &str) -> bool {
+    let (a, b) = (a.to_ascii_lowercase(), b.to_ascii_lowercase());
+    registrable_domain(&a) == registrable_domain(&b)
+}
+
+fn registrable_domain(host: &str) -> &str {
+    let host = host.trim_end_matches('.');
+    if host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[') {
+        return host;
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SHARED_SECOND_LEVELS.contains(second) => 3,
+        _ => 2,
+    };
+    if labels.len() <= keep {
+        return host;
     }
+    let dropped: usize = labels[..labels.len() - keep]
+        .iter()
+        .map(|label| label.len() + 1)
+        .sum();
+    &host[dropped..]
 }
 
 impl Default for LinkExtractingConverter {
@@ -0,0 +1,173 @@
+//! Saved pages on disk (`file://` URLs), read in place of a network fetch.
+use std::path::Path;
+
+use engine_logging::engine_warn;
+use url::Url;
+
+use crate::fetch::FetchSettings;
+use crate::mhtml::parse_mhtml;
+use crate::{FailureKind, FetchError, FetchMetadata, FetchOutput, ResponseHeaders};
+
+pub(crate) fn is_local_url(url: &str) -> bool {
+    url.trim_start()
+        .get(..5)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file:"))
+}
+
+/// Read the file behind a `file://` URL. Only files with a page extension
+/// are read, and `max_bytes` and the allowed content types still apply.
+/// MHTML files yield the HTML of the page they contain.
+pub(crate) async fn read_local_file(
+    url: &str,
+    settings: &FetchSettings,
+) -> Result<FetchOutput, FetchError> {
+    let parsed =
+        Url::parse(url).map_err(|err| FetchError::new(FailureKind::InvalidUrl, err.to_string()))?;
+    let path = parsed
+        .to_file_path()
+        .map_err(|_| FetchError::new(FailureKind::InvalidUrl, "not a local file path"))?;
+    let is_mhtml = is_mhtml_file(&path);
+    let content_type = if is_mhtml {
+        // Checked again below against the type of the archived page.
+        "text/html"
+    } else {
+        page_content_type(&path)
+    };
+    if !settings.allows_content_type(content_type) {
+        return Err(FetchError::new(
+            FailureKind::UnsupportedContentType {
+                content_type: content_type.to_string(),
+            },
+            format!("{} is not a saved page", path.display()),
+        ));
+    }
+
+    let max_bytes = settings.max_bytes;
+    let read_path = path.clone();
+    let bytes = tokio::task::spawn_blocking(move || {
+        let len = std::fs::metadata(&read_path)?.len();
+        if len > max_bytes {
+            return Ok(Err(len));
+        }
+        std::fs::read(&read_path).map(Ok)
+    })
+    .await
+    .map_err(|err| FetchError::new(FailureKind::ProcessingError, err.to_string()))?
+    .map_err(|err| {
+        engine_warn!("Cannot read {}: {}", path.display(), err);
+        FetchError::new(FailureKind::FileUnreadable, err.to_string())
+    })?
+    .map_err(|actual| {
+        FetchError::new(
+            FailureKind::TooLarge {
+                max_bytes,
+                actual: Some(actual),
+            },
+            "file too large",
+        )
+    })?;
+
+    let (bytes, content_type) = if is_mhtml {
+        let page = parse_mhtml(&bytes).map_err(|err| {
+            engine_warn!("Cannot read {}: {}", path.display(), err);
+            FetchError::new(FailureKind::FileUnreadable, err.to_string())
+        })?;
+        if !settings.allows_content_type(&page.content_type) {
+            return Err(FetchError::new(
+                FailureKind::UnsupportedContentType {
+                    content_type: page.content_type,
+                },
+                "unsupported page type in MHTML file",
+            ));
+        }
+        (page.html, page.content_type)
+    } else {
+        (bytes, content_type.to_string())
+    };
+
+    let byte_len = bytes.len() as u64;
+    Ok(FetchOutput {
+        bytes,
+        metadata: FetchMetadata {
+            original_url: url.to_string(),
+            final_url: parsed.to_string(),
+            redirect_count: 0,
+            content_type: Some(content_type),
+            byte_len,
+            headers: ResponseHeaders::default(),
+        },
+    })
+}
+
+/// Saved-page archive (`.mht`/`.mhtml`) whose page is unpacked before decoding.
+fn is_mhtml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mht") || ext.eq_ignore_ascii_case("mhtml"))
+}
+
+/// Content type implied by the file extension; anything that is not a
+/// saved page counts as `application/octet-stream`.
+fn page_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" | "shtml" => "text/html",
+        "xhtml" | "xht" => "application/xhtml+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_url(path: &Path) -> String {
+        Url::from_file_path(path).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn reads_saved_pages_and_rejects_other_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let page = temp.path().join("Saved Page.HTM");
+        std::fs::write(&page, "<p>Saved</p>").unwrap();
+        let other = temp.path().join("notes.pdf");
+        std::fs::write(&other, "%PDF").unwrap();
+        let settings = FetchSettings::default();
+
+        let url = file_url(&page);
+        assert!(is_local_url(&url));
+        let output = read_local_file(&url, &settings).await.unwrap();
+        assert_eq!(output.bytes, b"<p>Saved</p>");
+        assert_eq!(output.metadata.content_type.as_deref(), Some("text/html"));
+
+        let err = read_local_file(&file_url(&other), &settings)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            FailureKind::UnsupportedContentType { .. }
+        ));
+        let archive = temp.path().join("capture.mht");
+        std::fs::write(
+            &archive,
+            "Content-Type: multipart/related; boundary=b\n\n--b\nContent-Type: text/html; charset=utf-8\n\n<p>Archived</p>\n--b--\n",
+        )
+        .unwrap();
+        let output = read_local_file(&file_url(&archive), &settings)
+            .await
+            .unwrap();
+        assert_eq!(output.bytes, b"<p>Archived</p>\n");
+        assert_eq!(
+            output.metadata.content_type.as_deref(),
+            Some("text/html; charset=utf-8")
+        );
+
+        let missing = file_url(&temp.path().join("missing.html"));
+        let err = read_local_file(&missing, &settings).await.unwrap_err();
+        assert_eq!(err.kind, FailureKind::FileUnreadable);
+    }
+}
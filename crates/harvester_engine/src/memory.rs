@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Approximate bytes held by running jobs: fetched bodies, decoded HTML and
+/// markdown. Shared by the scheduler, which defers new jobs while the budget
+/// is spent, and the jobs, which charge what they buffer.
+#[derive(Debug, Default)]
+pub(crate) struct MemoryBudget {
+    limit: Option<u64>,
+    in_use: AtomicU64,
+}
+
+impl MemoryBudget {
+    pub(crate) fn new(limit: Option<u64>) -> Self {
+        Self {
+            limit,
+            in_use: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    pub(crate) fn in_use(&self) -> u64 {
+        self.in_use.load(Ordering::Relaxed)
+    }
+
+    /// True when a job that may buffer `reserve` more bytes still fits.
+    pub(crate) fn has_room(&self, reserve: u64) -> bool {
+        self.limit
+            .is_none_or(|limit| self.in_use().saturating_add(reserve) <= limit)
+    }
+
+    /// Start charging one job; everything charged is released on drop.
+    pub(crate) fn charge(&self) -> MemoryCharge<'_> {
+        MemoryCharge {
+            budget: self,
+            bytes: 0,
+        }
+    }
+}
+
+pub(crate) struct MemoryCharge<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl MemoryCharge<'_> {
+    pub(crate) fn add(&mut self, bytes: usize) {
+        let bytes = bytes as u64;
+        self.bytes += bytes;
+        self.budget.in_use.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+impl Drop for MemoryCharge<'_> {
+    fn drop(&mut self) {
+        self.budget.in_use.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
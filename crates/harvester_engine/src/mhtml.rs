@@ -0,0 +1,233 @@
+//! MHTML (`.mht`/`.mhtml`) saved-page files: a MIME `multipart/related`
+//! message whose root part is the page HTML.
+use base64::Engine as _;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MhtmlError {
+    #[error("not a multipart MHTML file")]
+    NotMultipart,
+    #[error("no HTML part in MHTML file")]
+    NoHtmlPart,
+    #[error("undecodable {0} part")]
+    BadEncoding(&'static str),
+}
+
+/// The page stored in an MHTML file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MhtmlPage {
+    /// Decoded HTML bytes, still in the part's charset.
+    pub html: Vec<u8>,
+    /// `Content-Type` of the HTML part, including any `charset`.
+    pub content_type: String,
+    /// Original URL of the page, from `Snapshot-Content-Location` or the
+    /// part's `Content-Location`.
+    pub location: Option<String>,
+}
+
+/// Extract the page HTML from an MHTML file: the part named by the `start`
+/// parameter if present, otherwise the first `text/html` part.
+pub fn parse_mhtml(bytes: &[u8]) -> Result<MhtmlPage, MhtmlError> {
+    let text = String::from_utf8_lossy(bytes);
+    let (head, body) = split_head(&text).ok_or(MhtmlError::NotMultipart)?;
+    let headers = parse_headers(head);
+    let content_type = header(&headers, "Content-Type").ok_or(MhtmlError::NotMultipart)?;
+    if !media_type(content_type).starts_with("multipart/") {
+        return Err(MhtmlError::NotMultipart);
+    }
+    let boundary = parameter(content_type, "boundary").ok_or(MhtmlError::NotMultipart)?;
+    let start = parameter(content_type, "start");
+
+    let parts: Vec<_> = split_parts(body, &boundary)
+        .into_iter()
+        .filter_map(|part| {
+            let (head, body) = split_head(part)?;
+            Some((parse_headers(head), body))
+        })
+        .collect();
+    let root = start
+        .and_then(|start| {
+            parts.iter().find(|(headers, _)| {
+                header(headers, "Content-ID").is_some_and(|id| id.trim() == start.trim())
+            })
+        })
+        .or_else(|| {
+            parts.iter().find(|(headers, _)| {
+                header(headers, "Content-Type").is_some_and(|ct| media_type(ct) == "text/html")
+            })
+        });
+    let (part_headers, part_body) = root.ok_or(MhtmlError::NoHtmlPart)?;
+
+    let html = match header(part_headers, "Content-Transfer-Encoding")
+        .map(|encoding| encoding.trim().to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("base64") => {
+            let compact: String = part_body.split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD
+                .decode(compact)
+                .map_err(|_| MhtmlError::BadEncoding("base64"))?
+        }
+        Some("quoted-printable") => decode_quoted_printable(part_body),
+        _ => part_body.as_bytes().to_vec(),
+    };
+    Ok(MhtmlPage {
+        html,
+        content_type: header(part_headers, "Content-Type")
+            .unwrap_or("text/html")
+            .to_string(),
+        location: header(&headers, "Snapshot-Content-Location")
+            .or_else(|| header(part_headers, "Content-Location"))
+            .map(ToOwned::to_owned),
+    })
+}
+
+/// Header block and body, split at the first blank line.
+fn split_head(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start_matches(['\r', '\n']);
+    let (end, sep) = text
+        .find("\r\n\r\n")
+        .map(|end| (end, 4))
+        .or_else(|| text.find("\n\n").map(|end| (end, 2)))?;
+    Some((&text[..end], &text[end + sep..]))
+}
+
+/// Header names and values, with folded continuation lines joined.
+fn parse_headers(head: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn media_type(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Value of `name=value` or `name="value"` in a header's parameters.
+fn parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, val) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| val.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Bodies between `--boundary` delimiter lines, up to the closing delimiter.
+fn split_parts<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{boundary}");
+    body.split(delimiter.as_str())
+        // Anything before the first delimiter is preamble.
+        .skip(1)
+        .take_while(|section| !section.starts_with("--"))
+        .collect()
+}
+
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // Soft line break.
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+            continue;
+        }
+        if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+            continue;
+        }
+        match bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(b'=');
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_the_quoted_printable_html_part() {
+        let mhtml = "From: <Saved by Blink>\r\n\
+            Snapshot-Content-Location: https://example.com/story\r\n\
+            MIME-Version: 1.0\r\n\
+            Content-Type: multipart/related;\r\n\
+            \ttype=\"text/html\";\r\n\
+            \tboundary=\"----MultipartBoundary--abc----\"\r\n\
+            \r\n\
+            ------MultipartBoundary--abc----\r\n\
+            Content-Type: text/html; charset=utf-8\r\n\
+            Content-Transfer-Encoding: quoted-printable\r\n\
+            Content-Location: https://example.com/story\r\n\
+            \r\n\
+            <p class=3D\"x\">Caf=C3=A9 long =\r\nline</p>\r\n\
+            ------MultipartBoundary--abc----\r\n\
+            Content-Type: image/png\r\n\
+            Content-Transfer-Encoding: base64\r\n\
+            \r\n\
+            iVBORw0KGgo=\r\n\
+            ------MultipartBoundary--abc------\r\n";
+
+        let page = parse_mhtml(mhtml.as_bytes()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(page.html).unwrap(),
+            "<p class=\"x\">Café long line</p>\r\n"
+        );
+        assert_eq!(page.content_type, "text/html; charset=utf-8");
+        assert_eq!(page.location.as_deref(), Some("https://example.com/story"));
+    }
+
+    #[test]
+    fn decodes_base64_start_part_and_rejects_plain_html() {
+        let mhtml = "Content-Type: multipart/related; boundary=b; start=\"<root>\"\n\n\
+            --b\nContent-Type: text/html\nContent-ID: <other>\n\n<p>Not me</p>\n\
+            --b\nContent-Type: text/html\nContent-ID: <root>\nContent-Transfer-Encoding: base64\n\n\
+            PHA+SGk8L3A+\n--b--\n";
+
+        assert_eq!(parse_mhtml(mhtml.as_bytes()).unwrap().html, b"<p>Hi</p>");
+        assert_eq!(
+            parse_mhtml(b"<html></html>").unwrap_err(),
+            MhtmlError::NotMultipart
+        );
+    }
+}
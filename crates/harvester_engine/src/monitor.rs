@@ -0,0 +1,289 @@
+//! Scheduled re-fetch of watched pages.
+//!
+//! A watch re-harvests its URL every `interval`. It writes a document and
+//! emits [`EngineEvent::WatchChanged`] only when the markdown body changed
+//! since the previous check. The first check after a watch is added sets
+//! the baseline and emits nothing. Checks run as worker tasks alongside jobs
+//! and share their concurrency limits.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use engine_logging::{engine_info, engine_warn, with_job_context};
+
+use crate::engine::EngineConfig;
+use crate::fetch::{Fetcher, ProgressSink};
+use crate::pipeline::harvest_one_with;
+use crate::preview::strip_frontmatter;
+use crate::versions::DocumentStore;
+use crate::{EngineEvent, FailureKind, JobId, JobOutcome};
+
+struct Watch {
+    url: String,
+    interval: Duration,
+    next_due: Instant,
+    last_digest: Option<u64>,
+    /// A check is running; the next one is scheduled when it reports back.
+    checking: bool,
+}
+
+/// Watched pages keyed by the job that first harvested them.
+#[derive(Default)]
+pub(crate) struct WatchList {
+    watches: BTreeMap<JobId, Watch>,
+}
+
+impl WatchList {
+    /// Start (or reschedule) a watch; the baseline check is due immediately.
+    pub(crate) fn watch(&mut self, watch_id: JobId, url: String, interval: Duration, now: Instant) {
+        engine_info!(
+            "[Monitor] Watching job {} every {:?}: {}",
+            watch_id,
+            interval,
+            url
+        );
+        self.watches.insert(
+            watch_id,
+            Watch {
+                url,
+                interval: interval.max(Duration::from_secs(1)),
+                next_due: now,
+                last_digest: None,
+                checking: false,
+            },
+        );
+    }
+
+    pub(crate) fn unwatch(&mut self, watch_id: JobId) {
+        if self.watches.remove(&watch_id).is_some() {
+            engine_info!("[Monitor] Stopped watching job {}", watch_id);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.watches.clear();
+    }
+
+    /// Earliest instant at which a check that is not already running is due.
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        self.watches
+            .values()
+            .filter(|watch| !watch.checking)
+            .map(|watch| watch.next_due)
+            .min()
+    }
+
+    /// Watches whose check is due, with their URL and previous digest.
+    #[cfg(test)]
+    fn due(&self, now: Instant) -> Vec<(JobId, String, Option<u64>)> {
+        self.watches
+            .iter()
+            .filter(|(_, watch)| !watch.checking && watch.next_due <= now)
+            .map(|(id, watch)| (*id, watch.url.clone(), watch.last_digest))
+            .collect()
+    }
+
+    /// The first due check whose URL `has_room`, marked as running.
+    pub(crate) fn start_due(
+        &mut self,
+        now: Instant,
+        has_room: impl Fn(&str) -> bool,
+    ) -> Option<WatchCheck> {
+        let (watch_id, watch) = self
+            .watches
+            .iter_mut()
+            .find(|(_, watch)| !watch.checking && watch.next_due <= now && has_room(&watch.url))?;
+        watch.checking = true;
+        Some(WatchCheck {
+            watch_id: *watch_id,
+            url: watch.url.clone(),
+            last_digest: watch.last_digest,
+        })
+    }
+
+    /// Record a check result and schedule the next one. A failed check keeps
+    /// the previous digest.
+    pub(crate) fn record(&mut self, watch_id: JobId, digest: Option<u64>, now: Instant) {
+        if let Some(watch) = self.watches.get_mut(&watch_id) {
+            if digest.is_some() {
+                watch.last_digest = digest;
+            }
+            watch.checking = false;
+            watch.next_due = now + watch.interval;
+        }
+    }
+}
+
+/// A due check, taken off the [`WatchList`] to run as its own task.
+pub(crate) struct WatchCheck {
+    pub(crate) watch_id: JobId,
+    pub(crate) url: String,
+    last_digest: Option<u64>,
+}
+
+/// Digest of the markdown body; the frontmatter carries a fresh timestamp on
+/// every fetch and would make every check look like a change.
+pub(crate) fn content_digest(document: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    strip_frontmatter(document).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Run one check, a full fetch-and-convert. Returns the page digest to
+/// [`WatchList::record`], or `None` when the check failed.
+pub(crate) async fn run_watch_check(
+    check: WatchCheck,
+    fetcher: Arc<dyn Fetcher>,
+    sink: Arc<dyn ProgressSink>,
+    config: Arc<EngineConfig>,
+) -> Option<u64> {
+    let WatchCheck {
+        watch_id,
+        url,
+        last_digest,
+    } = check;
+    match check_watch(
+        watch_id,
+        &url,
+        last_digest,
+        fetcher.as_ref(),
+        sink.as_ref(),
+        &config,
+    )
+    .await
+    {
+        Ok(digest) => Some(digest),
+        Err(kind) => {
+            engine_warn!("[Monitor] Check for job {} failed: {}", watch_id, kind);
+            None
+        }
+    }
+}
+
+async fn check_watch(
+    watch_id: JobId,
+    url: &str,
+    last_digest: Option<u64>,
+    fetcher: &dyn Fetcher,
+    sink: &dyn ProgressSink,
+    config: &EngineConfig,
+) -> Result<u64, FailureKind> {
+    let doc = harvest_one_with(url, config, fetcher).await?;
+    let digest = content_digest(&doc.document);
+    match last_digest {
+        None => {
+            engine_info!("[Monitor] Baseline for job {} recorded", watch_id);
+            return Ok(digest);
+        }
+        Some(previous) if previous == digest => return Ok(digest),
+        Some(_) => {}
+    }
+
+    let store = DocumentStore::from_config(config);
+    let fetched_utc = (config.fetched_utc)();
+    let filename = doc.filename.clone();
+    let document = doc.document.clone();
+    let written = tokio::task::spawn_blocking(move || {
+        with_job_context(watch_id, || store.write(&filename, &fetched_utc, &document))
+    })
+    .await
+    .map_err(|_| FailureKind::ProcessingError)?
+    .map_err(|err| {
+        engine_warn!("[Monitor] Write failed for job {}: {}", watch_id, err);
+        FailureKind::ProcessingError
+    })?;
+    engine_info!("[Monitor] Job {} changed: {}", watch_id, doc.final_url);
+    sink.emit(EngineEvent::WatchChanged {
+        watch_id,
+        outcome: JobOutcome {
+            final_url: doc.final_url,
+            canonical_url: doc.canonical_url,
+            tokens: Some(doc.token_count),
+            bytes_written: Some(doc.document.len() as u64),
+            output_path: Some(written.path),
+            diff: written.diff,
+            content_preview: Some(doc.content_preview),
+            extracted_links: doc.links,
+            quality: Some(doc.quality),
+            reading: Some(doc.reading),
+            content_warning: doc.content_warning,
+            stripped_chrome: None,
+            from_cache: false,
+            unchanged: written.unchanged,
+            duplicate_of: None,
+        },
+    });
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_ignores_frontmatter() {
+        let a = "---\nfetched_utc: 2024-01-01T00:00:00Z\n---\nbody\n";
+        let b = "---\nfetched_utc: 2024-02-01T00:00:00Z\n---\nbody\n";
+        let c = "---\nfetched_utc: 2024-02-01T00:00:00Z\n---\nother body\n";
+        assert_eq!(content_digest(a), content_digest(b));
+        assert_ne!(content_digest(a), content_digest(c));
+    }
+
+    #[test]
+    fn checks_are_rescheduled_by_interval() {
+        let start = Instant::now();
+        let mut watches = WatchList::default();
+        watches.watch(
+            1,
+            "https://example.com".into(),
+            Duration::from_secs(60),
+            start,
+        );
+        assert_eq!(watches.next_due(), Some(start));
+        assert_eq!(watches.due(start).len(), 1);
+
+        watches.record(1, Some(42), start);
+        assert!(watches.due(start).is_empty());
+        assert_eq!(watches.next_due(), Some(start + Duration::from_secs(60)));
+
+        // A failed check keeps the previous digest.
+        let later = start + Duration::from_secs(60);
+        watches.record(1, None, later);
+        assert_eq!(watches.due(later + Duration::from_secs(60))[0].2, Some(42));
+
+        watches.unwatch(1);
+        assert_eq!(watches.next_due(), None);
+    }
+
+    #[test]
+    fn running_checks_are_not_due_again() {
+        let start = Instant::now();
+        let mut watches = WatchList::default();
+        watches.watch(
+            1,
+            "https://busy.example".into(),
+            Duration::from_secs(60),
+            start,
+        );
+        watches.watch(
+            2,
+            "https://free.example".into(),
+            Duration::from_secs(60),
+            start,
+        );
+
+        let check = watches
+            .start_due(start, |url| url.contains("free"))
+            .expect("free host has room");
+        assert_eq!(check.watch_id, 2);
+        assert!(watches
+            .start_due(start, |url| url.contains("free"))
+            .is_none());
+        assert_eq!(watches.due(start).len(), 1);
+
+        watches.record(2, Some(7), start);
+        assert_eq!(watches.due(start + Duration::from_secs(60)).len(), 2);
+    }
+}
@@ -0,0 +1,107 @@
+//! "Next page" detection for articles split over several pages.
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+/// Anchor texts that lead to the following page.
+const NEXT_TEXTS: &[&str] = &[
+    "next",
+    "next page",
+    "next »",
+    "next ›",
+    "next >",
+    "next →",
+    "continue reading",
+];
+
+/// URL of the page after `html` (served from `page_url`), if it links to one.
+///
+/// Checks `rel="next"` on `<link>` and `<a>` first, then anchors reading
+/// "Next" / "Page {n}", then a bare "{n}" inside a pagination container,
+/// where `n` is `part_number + 1`. Only same-host links are returned.
+pub(crate) fn find_next_page(html: &str, page_url: &str, part_number: usize) -> Option<String> {
+    let base = Url::parse(page_url).ok()?;
+    let document = Html::parse_document(html);
+    let next_number = (part_number + 1).to_string();
+    let page_text = format!("page {next_number}");
+
+    let rel_next = Selector::parse(r#"link[rel~="next"], a[rel~="next"]"#).ok()?;
+    let anchors = Selector::parse("a[href]").ok()?;
+    let candidates = document
+        .select(&rel_next)
+        .chain(document.select(&anchors).filter(|anchor| {
+            let text = anchor_text(anchor);
+            NEXT_TEXTS.contains(&text.as_str())
+                || text == page_text
+                || (text == next_number && in_pagination(anchor))
+        }));
+
+    candidates
+        .filter_map(|element| element.value().attr("href"))
+        .filter_map(|href| base.join(href).ok())
+        .find(|next| {
+            matches!(next.scheme(), "http" | "https")
+                && next.host_str() == base.host_str()
+                && without_fragment(next) != without_fragment(&base)
+        })
+        .map(|next| without_fragment(&next))
+}
+
+fn anchor_text(anchor: &ElementRef) -> String {
+    anchor
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The anchor sits in an element whose class or id mentions paging.
+fn in_pagination(anchor: &ElementRef) -> bool {
+    anchor.ancestors().filter_map(ElementRef::wrap).any(|el| {
+        let el = el.value();
+        el.attr("class")
+            .into_iter()
+            .chain(el.attr("id"))
+            .any(|value| {
+                let value = value.to_ascii_lowercase();
+                value.contains("pagination") || value.contains("pager") || value.contains("pages")
+            })
+    })
+}
+
+fn without_fragment(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_rel_next_and_text_links_on_the_same_host() {
+        let rel =
+            r#"<html><head><link rel="next" href="/story?page=2"></head><body></body></html>"#;
+        assert_eq!(
+            find_next_page(rel, "https://example.com/story", 1).as_deref(),
+            Some("https://example.com/story?page=2")
+        );
+
+        let text = r#"<body><a href="/about">About</a><a href="p3.html">Page 3</a></body>"#;
+        assert_eq!(
+            find_next_page(text, "https://example.com/t/p2.html", 2).as_deref(),
+            Some("https://example.com/t/p3.html")
+        );
+
+        let numbered = r#"<body><p><a href="/x/2">2</a></p><div class="pagination"><a href="/t/2">2</a></div></body>"#;
+        assert_eq!(
+            find_next_page(numbered, "https://example.com/t", 1).as_deref(),
+            Some("https://example.com/t/2")
+        );
+
+        let offsite = r#"<body><a rel="next" href="https://ads.example.net/next">Next</a></body>"#;
+        assert_eq!(find_next_page(offsite, "https://example.com/t", 1), None);
+    }
+}
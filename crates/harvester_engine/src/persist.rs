@@ -5,6 +5,8 @@ use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 use thiserror::Error;
 
+use crate::filename::MAX_PATH_LEN;
+
 #[derive(Debug, Error)]
 pub enum PersistError {
     #[error("output directory missing or not writable: {0}")]
@@ -38,12 +40,26 @@ impl AtomicFileWriter {
         Self { dir }
     }
 
+    /// Path that [`write`](Self::write) writes `filename` to.
+    pub fn target_path(&self, filename: &str) -> PathBuf {
+        self.dir.join(filename)
+    }
+
+    /// Returns the target path as given, even when it had to be written
+    /// through its extended-length form.
     pub fn write(&self, filename: &str, content: &str) -> Result<PathBuf, PersistError> {
-        ensure_output_dir(&self.dir)?;
+        self.write_bytes(filename, content.as_bytes())
+    }
+
+    /// Like [`write`](Self::write), for content that is not text.
+    pub fn write_bytes(&self, filename: &str, content: &[u8]) -> Result<PathBuf, PersistError> {
+        let returned = self.target_path(filename);
+        let dir = extended_length_path(&self.dir, filename);
+        ensure_output_dir(&dir)?;
 
-        let target = self.dir.join(filename);
-        let mut tmp = NamedTempFile::new_in(&self.dir)?;
-        tmp.write_all(content.as_bytes())?;
+        let target = dir.join(filename);
+        let mut tmp = NamedTempFile::new_in(&dir)?;
+        tmp.write_all(content)?;
         tmp.flush()?;
         tmp.as_file_mut().sync_all()?;
 
@@ -53,6 +69,26 @@ impl AtomicFileWriter {
         }
         tmp.persist(&target)
             .map_err(|e| PersistError::Io(e.error))?;
-        Ok(target)
+        Ok(returned)
+    }
+}
+
+/// `dir` in its `\\?\` form when `dir` joined with `filename` would exceed
+/// `MAX_PATH` on Windows; unchanged elsewhere.
+fn extended_length_path(dir: &Path, filename: &str) -> PathBuf {
+    if !cfg!(windows) {
+        return dir.to_path_buf();
+    }
+    let Ok(absolute) = std::path::absolute(dir) else {
+        return dir.to_path_buf();
+    };
+    let absolute = absolute.to_string_lossy().into_owned();
+    let len = absolute.encode_utf16().count() + 1 + filename.encode_utf16().count();
+    if len <= MAX_PATH_LEN || absolute.starts_with(r"\\?\") {
+        return dir.to_path_buf();
+    }
+    match absolute.strip_prefix(r"\\") {
+        Some(share) => PathBuf::from(format!(r"\\?\UNC\{share}")),
+        None => PathBuf::from(format!(r"\\?\{absolute}")),
     }
 }
@@ -0,0 +1,355 @@
+use std::collections::HashSet;
+
+use engine_logging::{engine_debug, engine_info, engine_warn};
+use tokio::time::timeout;
+
+use crate::chunk::section_token_counts;
+use crate::decode::{decode_html_with, DecodeError, DecodedHtml};
+use crate::doc_id::document_id;
+use crate::engine::EngineConfig;
+use crate::extract::{canonical_url, document_title, ExtractedContent};
+use crate::fetch::{Fetcher, ProgressSink, ReqwestFetcher};
+use crate::frontmatter::{build_markdown_document_with, FrontmatterExtras};
+use crate::keywords::extract_keywords;
+use crate::links::{ConversionOutput, ExtractedLink};
+use crate::local::{is_local_url, read_local_file};
+use crate::pagination::find_next_page;
+use crate::preview::prepare_preview_content;
+use crate::published::published_date;
+use crate::quality::{assess_quality, QualityReport, ReadingMetrics};
+use crate::sections::{slice_to_sections, with_fragment};
+use crate::token::TokenCounter;
+use crate::walls::{detect_content_wall, ContentWarning};
+use crate::{deterministic_filename_with, EngineEvent, FailureKind, FetchOutput, JobId, Stage};
+
+/// A fully processed page, ready to be written or consumed directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HarvestedDoc {
+    pub final_url: String,
+    /// Target of the page's `<link rel="canonical">`, if any.
+    pub canonical_url: Option<String>,
+    pub title: Option<String>,
+    /// Deterministic filename the engine would write this document to, before
+    /// any shortening to fit the output directory within `MAX_PATH`.
+    pub filename: String,
+    pub token_count: u32,
+    /// Markdown including the frontmatter block.
+    pub document: String,
+    /// The markdown body without the frontmatter, truncated for previews.
+    pub content_preview: String,
+    /// Stable id of the document, also written as `doc_id:`; see [`document_id`].
+    pub doc_id: String,
+    pub links: Vec<ExtractedLink>,
+    pub quality: QualityReport,
+    pub reading: ReadingMetrics,
+    pub content_warning: Option<ContentWarning>,
+}
+
+/// Run fetch → decode → extract → convert → frontmatter for a single URL,
+/// without the engine queue, worker thread, or writing to disk.
+pub async fn harvest_one(url: &str, config: &EngineConfig) -> Result<HarvestedDoc, FailureKind> {
+    let fetcher = ReqwestFetcher::new(config.fetch_settings.clone());
+    harvest_one_with(url, config, &fetcher).await
+}
+
+/// Like [`harvest_one`], with an injected fetcher.
+pub async fn harvest_one_with(
+    url: &str,
+    config: &EngineConfig,
+    fetcher: &dyn Fetcher,
+) -> Result<HarvestedDoc, FailureKind> {
+    let fetch_output = fetch_stage(0, url, fetcher, &NullProgressSink, config).await?;
+    let decoded = decode_stage(&fetch_output, config).await?;
+    let extracted = extract_stage(&decoded.html, config).await?;
+    let canonical = canonical_url(&decoded.html, &fetch_output.metadata.final_url);
+    let document_url = canonical
+        .as_deref()
+        .unwrap_or(&fetch_output.metadata.final_url);
+    let title = document_title(extracted.title.as_deref(), document_url);
+    let conversion = convert_stage(&extracted, &fetch_output.metadata.final_url, config).await?;
+    let quality = assess_quality(&decoded.html, &conversion.markdown);
+    let content_warning = detect_content_wall(&decoded.html, quality.word_count);
+    let page = stitch_following_parts(
+        0,
+        &decoded.html,
+        &fetch_output.metadata.final_url,
+        conversion,
+        fetcher,
+        &NullProgressSink,
+        config,
+    )
+    .await;
+    let (markdown, section) = slice_to_sections(url, page.markdown);
+    let document_url = with_fragment(document_url, section.as_deref());
+    let reading = ReadingMetrics::of(&markdown);
+    let summary = summarize_stage(title.as_deref(), &markdown, config).await;
+    let (token_count, document) = build_markdown_document_with(
+        &document_url,
+        title.as_deref(),
+        &decoded.encoding_label,
+        &(config.fetched_utc)(),
+        &markdown,
+        config.token_counter.as_ref(),
+        &FrontmatterExtras {
+            part_urls: page.part_urls,
+            published: published_date(&decoded.html, &fetch_output.metadata.final_url),
+            reading: Some(reading),
+            quality_score: Some(quality.score),
+            content_warning,
+            summary,
+            keywords: extract_keywords(&markdown),
+            source_batch: None,
+            section_tokens: section_token_counts(&markdown, config.token_counter.as_ref()),
+            extraction: Some(extracted.strategy),
+            stripped_chrome_bytes: None,
+            local_source: is_local_url(url),
+            decode_lossy: decoded.lossy,
+            response_headers: fetch_output.metadata.provenance_headers(),
+        },
+    );
+    let doc_id = document_id(&document_url);
+    Ok(HarvestedDoc {
+        filename: deterministic_filename_with(
+            title.as_deref(),
+            &with_fragment(canonical.as_deref().unwrap_or(url), section.as_deref()),
+            &config.filename_options,
+        ),
+        final_url: fetch_output.metadata.final_url,
+        canonical_url: canonical,
+        title,
+        token_count,
+        document,
+        content_preview: prepare_preview_content(&markdown),
+        doc_id,
+        links: page.links,
+        quality,
+        reading,
+        content_warning,
+    })
+}
+
+/// Markdown and links of a page, with any stitched continuation pages appended.
+pub(crate) struct StitchedPage {
+    pub(crate) markdown: String,
+    pub(crate) links: Vec<ExtractedLink>,
+    /// URLs of every part in order, starting with the first page.
+    pub(crate) part_urls: Vec<String>,
+}
+
+/// With `stitch_pages` enabled, follow "next page" links from the first page
+/// (`html` at `url`) and append each part's markdown, up to
+/// `max_stitched_pages` pages. A part that fails to fetch or convert ends the
+/// chain; the parts gathered so far are kept.
+pub(crate) async fn stitch_following_parts(
+    job_id: JobId,
+    html: &str,
+    url: &str,
+    first: ConversionOutput,
+    fetcher: &dyn Fetcher,
+    sink: &dyn ProgressSink,
+    config: &EngineConfig,
+) -> StitchedPage {
+    let mut page = StitchedPage {
+        markdown: first.markdown,
+        links: first.links,
+        part_urls: vec![url.to_string()],
+    };
+    if !config.stitch_pages {
+        return page;
+    }
+    let mut seen: HashSet<String> = HashSet::from([url.to_string()]);
+    let mut next = find_next_page(html, url, 1);
+    while let Some(next_url) = next.take() {
+        if page.part_urls.len() >= config.max_stitched_pages || !seen.insert(next_url.clone()) {
+            break;
+        }
+        let (part_url, part_html, part) =
+            match fetch_part(job_id, &next_url, fetcher, sink, config).await {
+                Ok(part) => part,
+                Err(kind) => {
+                    engine_warn!(
+                        "[Pipeline] Stopped stitching {} at {}: {}",
+                        url,
+                        next_url,
+                        kind
+                    );
+                    break;
+                }
+            };
+        seen.insert(part_url.clone());
+        page.part_urls.push(part_url.clone());
+        page.markdown.push_str(&format!(
+            "\n\n<!-- part {}: {} -->\n\n",
+            page.part_urls.len(),
+            part_url
+        ));
+        page.markdown.push_str(&part.markdown);
+        page.links.extend(part.links);
+        next = find_next_page(&part_html, &part_url, page.part_urls.len());
+    }
+    if page.part_urls.len() > 1 {
+        engine_info!(
+            "[Pipeline] Stitched {} pages starting at {}",
+            page.part_urls.len(),
+            url
+        );
+    }
+    page
+}
+
+/// Fetch and convert one continuation page: `(final url, html, conversion)`.
+async fn fetch_part(
+    job_id: JobId,
+    url: &str,
+    fetcher: &dyn Fetcher,
+    sink: &dyn ProgressSink,
+    config: &EngineConfig,
+) -> Result<(String, String, ConversionOutput), FailureKind> {
+    let fetch_output = fetch_stage(job_id, url, fetcher, sink, config).await?;
+    let decoded = decode_stage(&fetch_output, config).await?;
+    let extracted = extract_stage(&decoded.html, config).await?;
+    let conversion = convert_stage(&extracted, &fetch_output.metadata.final_url, config).await?;
+    Ok((fetch_output.metadata.final_url, decoded.html, conversion))
+}
+
+/// Fetch `url`, or read it from disk when it is a `file://` URL.
+pub(crate) async fn fetch_stage(
+    job_id: JobId,
+    url: &str,
+    fetcher: &dyn Fetcher,
+    sink: &dyn ProgressSink,
+    config: &EngineConfig,
+) -> Result<FetchOutput, FailureKind> {
+    // Fetch errors are already logged in fetch.rs
+    let result = if is_local_url(url) {
+        read_local_file(url, &config.fetch_settings).await
+    } else {
+        fetcher.fetch(job_id, url, sink).await
+    };
+    result.map_err(|err| err.kind)
+}
+
+struct NullProgressSink;
+
+impl ProgressSink for NullProgressSink {
+    fn emit(&self, _event: EngineEvent) {}
+}
+
+pub(crate) async fn decode_stage(
+    fetch_output: &FetchOutput,
+    config: &EngineConfig,
+) -> Result<DecodedHtml, FailureKind> {
+    match timeout(config.extract_timeout, async {
+        decode_html_with(
+            &fetch_output.bytes,
+            fetch_output.metadata.content_type.as_deref(),
+            config.lossy_decode,
+        )
+    })
+    .await
+    {
+        Ok(Ok(mut decoded)) => {
+            if decoded.lossy {
+                engine_warn!(
+                    "[Pipeline] Replaced invalid {} bytes in {}",
+                    decoded.encoding_label,
+                    fetch_output.metadata.final_url
+                );
+            }
+            decoded.html = config
+                .strip_rules
+                .strip_elements(std::mem::take(&mut decoded.html));
+            Ok(decoded)
+        }
+        Ok(Err(DecodeError::BinaryContent { detected })) => {
+            engine_warn!(
+                "[Pipeline] {} is {} data, not HTML",
+                fetch_output.metadata.final_url,
+                detected
+            );
+            Err(FailureKind::BinaryContent {
+                detected: detected.to_string(),
+            })
+        }
+        Ok(Err(err)) => {
+            engine_warn!(
+                "[Pipeline] Decode failed for {}: {}",
+                fetch_output.metadata.final_url,
+                err
+            );
+            Err(FailureKind::ProcessingError)
+        }
+        Err(_) => Err(FailureKind::ProcessingTimeout {
+            stage: Stage::Sanitizing,
+        }),
+    }
+}
+
+pub(crate) async fn extract_stage(
+    html: &str,
+    config: &EngineConfig,
+) -> Result<ExtractedContent, FailureKind> {
+    timeout(config.extract_timeout, async {
+        config.extractor.extract(html)
+    })
+    .await
+    .map_err(|_| FailureKind::ProcessingTimeout {
+        stage: Stage::Converting,
+    })
+}
+
+pub(crate) async fn convert_stage(
+    extracted: &ExtractedContent,
+    final_url: &str,
+    config: &EngineConfig,
+) -> Result<ConversionOutput, FailureKind> {
+    let mut output = timeout(config.convert_timeout, async {
+        config
+            .converter
+            .to_markdown(&extracted.content_html, Some(final_url))
+    })
+    .await
+    .map_err(|_| FailureKind::ProcessingTimeout {
+        stage: Stage::Converting,
+    })?;
+    output.markdown = config
+        .strip_rules
+        .strip_text(std::mem::take(&mut output.markdown));
+    engine_debug!(
+        "[Pipeline] Converted {} to {} bytes of markdown",
+        final_url,
+        output.markdown.len()
+    );
+    Ok(output)
+}
+
+/// Summary from the configured summarizer; one that does not finish within
+/// `convert_timeout` is left out rather than failing the job.
+pub(crate) async fn summarize_stage(
+    title: Option<&str>,
+    markdown: &str,
+    config: &EngineConfig,
+) -> Option<String> {
+    timeout(config.convert_timeout, async {
+        config.summarizer.summarize(title, markdown)
+    })
+    .await
+    .unwrap_or_else(|_| {
+        engine_warn!("[Pipeline] Summarizer timed out");
+        None
+    })
+}
+
+pub(crate) async fn tokenize_stage(
+    markdown: &str,
+    token_counter: &dyn TokenCounter,
+    config: &EngineConfig,
+) -> Result<u32, FailureKind> {
+    timeout(config.tokenize_timeout, async {
+        token_counter.count(markdown)
+    })
+    .await
+    .map_err(|_| FailureKind::ProcessingTimeout {
+        stage: Stage::Tokenizing,
+    })
+}
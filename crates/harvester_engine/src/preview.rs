@@ -15,7 +15,7 @@ pub fn prepare_preview_content(markdown: &str) -> String {
     }
 }
 
-fn strip_frontmatter(markdown: &str) -> &str {
+pub(crate) fn strip_frontmatter(markdown: &str) -> &str {
     let rest = if let Some(stripped) = markdown.strip_prefix("---\r\n") {
         stripped
     } else if let Some(stripped) = markdown.strip_prefix("---\n") {
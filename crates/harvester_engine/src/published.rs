@@ -0,0 +1,144 @@
+//! Publication date heuristics.
+//!
+//! Sources are tried from most to least explicit: article meta tags, then a
+//! `<time>` element, then a date in the URL path such as `/2024/05/12/`.
+//! Dates are `YYYY-MM-DD`, or `YYYY-MM` when the URL only names the month.
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Meta tags naming the publication time, by `property`, `name` or `itemprop`.
+const DATE_META: &[&str] = &[
+    r#"meta[property="article:published_time"]"#,
+    r#"meta[property="og:published_time"]"#,
+    r#"meta[itemprop="datePublished"]"#,
+    r#"meta[name="date"]"#,
+    r#"meta[name="pubdate"]"#,
+    r#"meta[name="publish-date"]"#,
+    r#"meta[name="parsely-pub-date"]"#,
+    r#"meta[name="sailthru.date"]"#,
+    r#"meta[name="dc.date.issued"]"#,
+    r#"meta[name="DC.date.issued"]"#,
+    r#"meta[name="dcterms.created"]"#,
+];
+
+/// `<time>` elements, the explicitly marked ones first.
+const TIME_ELEMENTS: &[&str] = &[
+    "time[pubdate][datetime]",
+    r#"time[itemprop="datePublished"][datetime]"#,
+    "article time[datetime]",
+    "time[datetime]",
+];
+
+/// Years outside this range are taken for numbers that merely look like dates.
+const PLAUSIBLE_YEARS: std::ops::RangeInclusive<u32> = 1990..=2100;
+
+/// Publication date of the page at `url`, if any source names one.
+pub(crate) fn published_date(html: &str, url: &str) -> Option<String> {
+    let doc = Html::parse_document(html);
+    let attr_date = |selectors: &[&str], attr: &str| {
+        selectors.iter().find_map(|selector| {
+            let selector = Selector::parse(selector).ok()?;
+            doc.select(&selector)
+                .filter_map(|element| element.value().attr(attr))
+                .find_map(parse_date)
+        })
+    };
+    attr_date(DATE_META, "content")
+        .or_else(|| attr_date(TIME_ELEMENTS, "datetime"))
+        .or_else(|| url_date(url))
+}
+
+/// `YYYY-MM-DD` from the start of an ISO 8601 date or timestamp.
+fn parse_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    let date = value.get(..10)?;
+    let mut parts = date.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    let valid = year.len() == 4
+        && month.len() == 2
+        && day.len() == 2
+        && valid_date(
+            year.parse().ok()?,
+            month.parse().ok()?,
+            Some(day.parse().ok()?),
+        );
+    valid.then(|| date.to_string())
+}
+
+/// Date from consecutive `/YYYY/MM/` or `/YYYY/MM/DD/` path segments.
+fn url_date(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let segments: Vec<&str> = url.path_segments()?.collect();
+    segments.windows(2).enumerate().find_map(|(index, pair)| {
+        let (year, month) = (pair[0], pair[1]);
+        if year.len() != 4 || month.len() != 2 {
+            return None;
+        }
+        let (year, month): (u32, u32) = (year.parse().ok()?, month.parse().ok()?);
+        let day = segments
+            .get(index + 2)
+            .filter(|day| day.len() == 2)
+            .and_then(|day| day.parse::<u32>().ok())
+            .filter(|&day| valid_date(year, month, Some(day)));
+        if !valid_date(year, month, None) {
+            return None;
+        }
+        Some(match day {
+            Some(day) => format!("{year:04}-{month:02}-{day:02}"),
+            None => format!("{year:04}-{month:02}"),
+        })
+    })
+}
+
+fn valid_date(year: u32, month: u32, day: Option<u32>) -> bool {
+    PLAUSIBLE_YEARS.contains(&year)
+        && (1..=12).contains(&month)
+        && day.is_none_or(|day| (1..=31).contains(&day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_tags_win_over_time_elements_and_the_url() {
+        let html = r#"<head><meta property="article:published_time" content="2024-03-07T09:30:00+01:00"></head>
+            <body><time datetime="2023-01-01">Jan</time></body>"#;
+        assert_eq!(
+            published_date(html, "https://example.com/2022/02/post"),
+            Some("2024-03-07".to_string())
+        );
+    }
+
+    #[test]
+    fn time_elements_are_used_without_meta_tags() {
+        let html = r#"<body><p>Updated <time datetime="2024-06-01">June</time></p>
+            <article><time datetime="2024-05-20T12:00Z">May 20</time></article></body>"#;
+        assert_eq!(
+            published_date(html, "https://example.com/post"),
+            Some("2024-05-20".to_string())
+        );
+    }
+
+    #[test]
+    fn url_paths_give_the_day_or_the_month() {
+        assert_eq!(
+            published_date("<p>x</p>", "https://example.com/blog/2024/05/12/launch"),
+            Some("2024-05-12".to_string())
+        );
+        assert_eq!(
+            published_date("<p>x</p>", "https://example.com/2024/05/launch"),
+            Some("2024-05".to_string())
+        );
+        assert_eq!(
+            published_date("<p>x</p>", "https://example.com/products/1234/56"),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_dates_are_ignored() {
+        let html = r#"<meta name="date" content="yesterday"><time datetime="2024-13-01">x</time>"#;
+        assert_eq!(published_date(html, "https://example.com/"), None);
+    }
+}
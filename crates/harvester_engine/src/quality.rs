@@ -0,0 +1,150 @@
+//! Content quality heuristics for a harvested page.
+use scraper::node::Node;
+use scraper::{ElementRef, Html};
+
+/// Scores below this are flagged as low quality.
+pub const LOW_QUALITY_SCORE: u8 = 40;
+
+/// Word count at which a document gets full credit for length.
+const FULL_LENGTH_WORDS: usize = 300;
+
+/// Words read per minute, for reading time estimates.
+const WORDS_PER_MINUTE: u32 = 230;
+
+/// Text-to-HTML ratio at which a document gets full credit for density.
+const FULL_DENSITY_RATIO: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QualityReport {
+    /// Words in the converted markdown.
+    pub word_count: usize,
+    /// Markdown text size relative to the raw HTML, in percent.
+    pub text_to_html_percent: u8,
+    /// Share of the page's visible words left out by extraction (navigation,
+    /// footers, sidebars), in percent.
+    pub boilerplate_percent: u8,
+    /// Combined score from 0 (junk) to 100.
+    pub score: u8,
+}
+
+impl QualityReport {
+    pub fn is_low(&self) -> bool {
+        self.score < LOW_QUALITY_SCORE
+    }
+}
+
+/// Size of a document in words, whatever the token counter, and how long it
+/// takes to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadingMetrics {
+    /// Whitespace-separated words with at least one letter or digit, so
+    /// markdown markers like `#` and `-` are not counted.
+    pub word_count: u32,
+    /// Minutes at 230 words per minute, rounded up.
+    pub reading_minutes: u32,
+}
+
+impl ReadingMetrics {
+    pub fn of(markdown: &str) -> Self {
+        let word_count = markdown
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .count() as u32;
+        Self {
+            word_count,
+            reading_minutes: word_count.div_ceil(WORDS_PER_MINUTE),
+        }
+    }
+}
+
+/// Assess the markdown extracted from `html`.
+///
+/// Half the score rewards length (up to 300 words), a quarter text density,
+/// and a quarter how little of the visible page was boilerplate.
+pub fn assess_quality(html: &str, markdown: &str) -> QualityReport {
+    let word_count = markdown.split_whitespace().count();
+    let text_to_html = if html.is_empty() {
+        0.0
+    } else {
+        (markdown.trim().len() as f64 / html.len() as f64).min(1.0)
+    };
+    let page_words = visible_word_count(html);
+    let boilerplate = if page_words == 0 {
+        0.0
+    } else {
+        1.0 - (word_count as f64 / page_words as f64).min(1.0)
+    };
+
+    let length_part = (word_count.min(FULL_LENGTH_WORDS) as f64 / FULL_LENGTH_WORDS as f64) * 50.0;
+    let density_part = (text_to_html / FULL_DENSITY_RATIO).min(1.0) * 25.0;
+    let content_part = (1.0 - boilerplate) * 25.0;
+
+    QualityReport {
+        word_count,
+        text_to_html_percent: percent(text_to_html),
+        boilerplate_percent: percent(boilerplate),
+        score: (length_part + density_part + content_part).round() as u8,
+    }
+}
+
+fn percent(ratio: f64) -> u8 {
+    (ratio * 100.0).round().clamp(0.0, 100.0) as u8
+}
+
+/// Words of text a reader would see: everything except script, style and
+/// other non-rendered elements.
+fn visible_word_count(html: &str) -> usize {
+    let document = Html::parse_document(html);
+    let mut count = 0;
+    for node in document.root_element().descendants() {
+        let Node::Text(text) = node.value() else {
+            continue;
+        };
+        let hidden = node.ancestors().filter_map(ElementRef::wrap).any(|el| {
+            matches!(
+                el.value().name(),
+                "script" | "style" | "noscript" | "template" | "head"
+            )
+        });
+        if !hidden {
+            count += text.split_whitespace().count();
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_article_scores_above_link_farm() {
+        let body = "word ".repeat(400);
+        let article = format!(
+            "<html><head><script>var x = 1;</script></head><body><nav>Home About</nav><article><p>{body}</p></article></body></html>"
+        );
+        let good = assess_quality(&article, &body);
+        assert_eq!(good.word_count, 400);
+        assert_eq!(good.boilerplate_percent, 0);
+        assert!(!good.is_low(), "{good:?}");
+
+        let links: String = (0..50)
+            .map(|i| format!(r#"<li><a href="/p{i}">Link {i}</a></li>"#))
+            .collect();
+        let farm = format!("<html><body><ul>{links}</ul><p>Short teaser.</p></body></html>");
+        let poor = assess_quality(&farm, "Short teaser.");
+        assert!(poor.boilerplate_percent > 90, "{poor:?}");
+        assert!(poor.is_low(), "{poor:?}");
+    }
+
+    #[test]
+    fn reading_metrics_skip_markdown_markers_and_round_up() {
+        let metrics = ReadingMetrics::of("# Title\n\n- one item\n- two items\n\n---\n");
+        assert_eq!(metrics.word_count, 5);
+        assert_eq!(metrics.reading_minutes, 1);
+
+        let long = "word ".repeat(461);
+        assert_eq!(ReadingMetrics::of(&long).reading_minutes, 3);
+        assert_eq!(ReadingMetrics::of("").reading_minutes, 0);
+    }
+}
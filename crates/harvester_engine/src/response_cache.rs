@@ -0,0 +1,172 @@
+//! Fetched responses kept on disk for a while, so re-running a crawl does not
+//! download unchanged pages again.
+//!
+//! Each entry is `{output_dir}/.response_cache/{sha256 of the normalized URL}.cache`:
+//! one JSON line with the response metadata and the time it was stored,
+//! followed by the response body.
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use engine_logging::engine_warn;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::persist::AtomicFileWriter;
+use crate::{FetchMetadata, FetchOutput, ResponseHeaders};
+
+/// Directory under the output directory that holds cached responses.
+pub(crate) const RESPONSE_CACHE_DIRNAME: &str = ".response_cache";
+
+#[derive(Debug, Clone)]
+pub(crate) struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    pub(crate) fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// The response stored for `url`, unless it is missing, unreadable or
+    /// older than the TTL.
+    pub(crate) fn get(&self, url: &str) -> Option<FetchOutput> {
+        self.get_at(url, SystemTime::now())
+    }
+
+    fn get_at(&self, url: &str, now: SystemTime) -> Option<FetchOutput> {
+        let path = self.dir.join(entry_filename(url));
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+            Err(err) => {
+                engine_warn!("[Cache] Cannot read {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        let Some((stored_secs, output)) = parse_entry(&data) else {
+            engine_warn!("[Cache] Ignoring malformed entry {}", path.display());
+            return None;
+        };
+        let stored = UNIX_EPOCH + Duration::from_secs(stored_secs);
+        let age = now.duration_since(stored).unwrap_or_default();
+        (age < self.ttl).then_some(output)
+    }
+
+    /// Store `output` as the response for `url`; failures only cost a later
+    /// cache miss.
+    pub(crate) fn put(&self, url: &str, output: &FetchOutput) {
+        self.put_at(url, output, SystemTime::now());
+    }
+
+    fn put_at(&self, url: &str, output: &FetchOutput, now: SystemTime) {
+        let stored_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let metadata = &output.metadata;
+        let header = json!({
+            "stored_unix": stored_secs,
+            "original_url": metadata.original_url,
+            "final_url": metadata.final_url,
+            "redirect_count": metadata.redirect_count,
+            "content_type": metadata.content_type,
+            "last_modified": metadata.headers.last_modified,
+            "etag": metadata.headers.etag,
+            "server": metadata.headers.server,
+        });
+        let mut entry = header.to_string().into_bytes();
+        entry.push(b'\n');
+        entry.extend_from_slice(&output.bytes);
+        let writer = AtomicFileWriter::new(self.dir.clone());
+        if let Err(err) = writer.write_bytes(&entry_filename(url), &entry) {
+            engine_warn!("[Cache] Cannot store response for {}: {}", url, err);
+        }
+    }
+}
+
+/// URL with its fragment, surrounding whitespace and trailing `/` removed;
+/// scheme and host are lowercased.
+fn normalize_url(url: &str) -> String {
+    let trimmed = url.trim();
+    let normalized = match url::Url::parse(trimmed) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => trimmed.to_string(),
+    };
+    normalized.trim_end_matches('/').to_string()
+}
+
+fn entry_filename(url: &str) -> String {
+    let digest = Sha256::digest(normalize_url(url).as_bytes());
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("{hex}.cache")
+}
+
+fn parse_entry(data: &[u8]) -> Option<(u64, FetchOutput)> {
+    let split = data.iter().position(|&byte| byte == b'\n')?;
+    let (header, body) = (&data[..split], &data[split + 1..]);
+    let header: Value = serde_json::from_slice(header).ok()?;
+    let stored_secs = header["stored_unix"].as_u64()?;
+    let metadata = FetchMetadata {
+        original_url: header["original_url"].as_str()?.to_string(),
+        final_url: header["final_url"].as_str()?.to_string(),
+        redirect_count: header["redirect_count"].as_u64()? as usize,
+        content_type: header["content_type"].as_str().map(str::to_string),
+        byte_len: body.len() as u64,
+        headers: ResponseHeaders {
+            last_modified: header["last_modified"].as_str().map(str::to_string),
+            etag: header["etag"].as_str().map(str::to_string),
+            server: header["server"].as_str().map(str::to_string),
+        },
+    };
+    Some((
+        stored_secs,
+        FetchOutput {
+            bytes: body.to_vec(),
+            metadata,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output() -> FetchOutput {
+        FetchOutput {
+            bytes: b"<html>\n<p>cached</p></html>".to_vec(),
+            metadata: FetchMetadata {
+                original_url: "https://example.com/a".to_string(),
+                final_url: "https://example.com/a/".to_string(),
+                redirect_count: 1,
+                content_type: Some("text/html; charset=utf-8".to_string()),
+                byte_len: 27,
+                headers: ResponseHeaders {
+                    etag: Some("\"abc\"".to_string()),
+                    ..ResponseHeaders::default()
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn serves_normalized_urls_until_the_ttl_expires() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = ResponseCache::new(temp.path().to_path_buf(), Duration::from_secs(60));
+        let stored = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        cache.put_at("https://example.com/a", &output(), stored);
+
+        let later = stored + Duration::from_secs(59);
+        assert_eq!(
+            cache.get_at("https://EXAMPLE.com/a/#intro", later),
+            Some(output())
+        );
+        assert_eq!(cache.get_at("https://example.com/b", later), None);
+        assert_eq!(
+            cache.get_at("https://example.com/a", stored + Duration::from_secs(60)),
+            None
+        );
+    }
+}
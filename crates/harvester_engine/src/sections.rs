@@ -0,0 +1,132 @@
+//! Harvesting only some sections of a page.
+//!
+//! A job URL whose fragment names headings, such as
+//! `https://example.com/docs#api-reference` or `#Install,Usage`, keeps only
+//! those sections of the converted markdown. Headings match by slug, so the
+//! heading text and the anchor a site generates for it both work. A section
+//! runs until the next heading of the same or a shallower level.
+use engine_logging::{engine_info, engine_warn};
+use url::Url;
+
+use crate::chunk::lines;
+use crate::filename::percent_decode;
+
+/// Markdown limited to the sections named by the fragment of `url`, and the
+/// fragment when it matched a heading. Pages without a fragment, or whose
+/// headings match none of it, are kept whole.
+pub(crate) fn slice_to_sections(url: &str, markdown: String) -> (String, Option<String>) {
+    let Some(fragment) = Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.fragment().map(str::to_string))
+        .filter(|fragment| !fragment.is_empty())
+    else {
+        return (markdown, None);
+    };
+    match select_sections(&markdown, &fragment) {
+        Some(selected) => {
+            engine_info!(
+                "[Sections] Kept #{} of {}: {} of {} bytes",
+                fragment,
+                url,
+                selected.len(),
+                markdown.len()
+            );
+            (selected, Some(fragment))
+        }
+        None => {
+            engine_warn!(
+                "[Sections] No heading of {} matches #{}; keeping the whole page",
+                url,
+                fragment
+            );
+            (markdown, None)
+        }
+    }
+}
+
+/// `url` with `fragment` appended, so a document holding some sections of a
+/// page gets its own id and filename.
+pub(crate) fn with_fragment(url: &str, fragment: Option<&str>) -> String {
+    match fragment {
+        Some(fragment) => format!("{url}#{fragment}"),
+        None => url.to_string(),
+    }
+}
+
+/// The sections whose headings match one of the comma-separated names in
+/// `fragment`, in document order, or `None` when no heading matches.
+fn select_sections(markdown: &str, fragment: &str) -> Option<String> {
+    let wanted: Vec<String> = percent_decode(fragment)
+        .split(',')
+        .map(slug)
+        .filter(|name| !name.is_empty())
+        .collect();
+    let mut selected = String::new();
+    let mut open_level: Option<usize> = None;
+    for (line, heading) in lines(markdown) {
+        if let Some((level, title)) = heading {
+            if open_level.is_some_and(|open| level <= open) {
+                open_level = None;
+            }
+            if open_level.is_none() && wanted.contains(&slug(&title)) {
+                if !selected.is_empty() && !selected.ends_with("\n\n") {
+                    selected.push('\n');
+                }
+                open_level = Some(level);
+            }
+        }
+        if open_level.is_some() {
+            selected.push_str(line);
+        }
+    }
+    (!selected.is_empty()).then_some(selected)
+}
+
+/// Lowercase alphanumerics with runs of anything else as one `-`, the way
+/// most sites derive heading anchors.
+fn slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "# Docs\nIntro\n## Install\nRun it\n### On Linux\napt install\n## API Reference\nCall it\n```\n# not a heading\n```\n## Changelog\nOld news\n";
+
+    #[test]
+    fn fragments_select_sections_by_heading_slug() {
+        assert_eq!(
+            select_sections(PAGE, "api-reference"),
+            Some("## API Reference\nCall it\n```\n# not a heading\n```\n".to_string())
+        );
+        assert_eq!(
+            select_sections(PAGE, "Install,API%20Reference"),
+            Some(
+                "## Install\nRun it\n### On Linux\napt install\n\n## API Reference\nCall it\n```\n# not a heading\n```\n"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn unmatched_fragments_keep_the_whole_page() {
+        let (markdown, fragment) =
+            slice_to_sections("https://example.com/docs#comments", PAGE.to_string());
+        assert_eq!(markdown, PAGE);
+        assert_eq!(fragment, None);
+
+        let (markdown, fragment) =
+            slice_to_sections("https://example.com/docs#changelog", PAGE.to_string());
+        assert_eq!(markdown, "## Changelog\nOld news\n");
+        assert_eq!(fragment.as_deref(), Some("changelog"));
+    }
+}
@@ -0,0 +1,153 @@
+//! Running totals for the jobs of one worker session, written into export
+//! manifests under `"session"`.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::{FailureKind, JobOutcome};
+
+/// What the session's jobs produced so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionReport {
+    /// Finished jobs, whatever their outcome.
+    pub pages: usize,
+    /// Jobs that wrote a full document, fetched or from the response cache.
+    pub succeeded: usize,
+    /// Of `succeeded`, the jobs served from the response cache.
+    pub from_cache: usize,
+    /// Jobs that wrote what looks like a paywall or consent wall.
+    pub partial: usize,
+    /// Failed jobs by [`FailureKind::key`].
+    pub failures: BTreeMap<String, usize>,
+    pub total_tokens: u64,
+    pub total_bytes: u64,
+    /// From the first job starting to the last one finishing.
+    pub duration: Duration,
+}
+
+impl SessionReport {
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "pages": self.pages,
+            "succeeded": self.succeeded,
+            "from_cache": self.from_cache,
+            "partial": self.partial,
+            "failures": self.failures,
+            "total_tokens": self.total_tokens,
+            "total_bytes": self.total_bytes,
+            "duration_secs": self.duration.as_secs_f64(),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SessionStats {
+    inner: Mutex<StatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct StatsInner {
+    report: SessionReport,
+    started: Option<Instant>,
+}
+
+impl SessionStats {
+    pub(crate) fn job_started(&self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.started.get_or_insert_with(Instant::now);
+        }
+    }
+
+    pub(crate) fn job_finished(&self, result: &Result<JobOutcome, FailureKind>) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        let elapsed = inner.started.map(|started| started.elapsed());
+        let report = &mut inner.report;
+        report.pages += 1;
+        match result {
+            Ok(outcome) => {
+                if outcome.content_warning.is_some() {
+                    report.partial += 1;
+                } else {
+                    report.succeeded += 1;
+                    if outcome.from_cache {
+                        report.from_cache += 1;
+                    }
+                }
+                report.total_tokens += outcome.tokens.map_or(0, u64::from);
+                report.total_bytes += outcome.bytes_written.unwrap_or(0);
+            }
+            Err(kind) => *report.failures.entry(kind.key()).or_default() += 1,
+        }
+        if let Some(elapsed) = elapsed {
+            report.duration = elapsed;
+        }
+    }
+
+    /// The totals so far; `None` before any job has finished.
+    pub(crate) fn report(&self) -> Option<SessionReport> {
+        let inner = self.inner.lock().ok()?;
+        (inner.report.pages > 0).then(|| inner.report.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(tokens: u32, bytes: u64) -> JobOutcome {
+        JobOutcome {
+            final_url: "https://example.com".to_string(),
+            canonical_url: None,
+            tokens: Some(tokens),
+            bytes_written: Some(bytes),
+            output_path: None,
+            diff: None,
+            content_preview: None,
+            extracted_links: Vec::new(),
+            quality: None,
+            reading: None,
+            content_warning: None,
+            stripped_chrome: None,
+            from_cache: false,
+            unchanged: false,
+            duplicate_of: None,
+        }
+    }
+
+    #[test]
+    fn totals_count_outcomes_and_group_failures_by_kind() {
+        let stats = SessionStats::default();
+        assert_eq!(stats.report(), None);
+
+        stats.job_started();
+        stats.job_finished(&Ok(outcome(10, 100)));
+        stats.job_finished(&Ok(JobOutcome {
+            from_cache: true,
+            ..outcome(5, 50)
+        }));
+        stats.job_finished(&Err(FailureKind::HttpStatus(404)));
+        stats.job_finished(&Err(FailureKind::HttpStatus(404)));
+        stats.job_finished(&Err(FailureKind::Timeout));
+
+        let report = stats.report().unwrap();
+        assert_eq!(
+            (
+                report.pages,
+                report.succeeded,
+                report.from_cache,
+                report.partial
+            ),
+            (5, 2, 1, 0)
+        );
+        assert_eq!((report.total_tokens, report.total_bytes), (15, 150));
+        assert_eq!(
+            report.failures,
+            BTreeMap::from([("http_404".to_string(), 2), ("timeout".to_string(), 1)])
+        );
+        assert_eq!(report.to_json()["failures"]["http_404"], 2);
+    }
+}
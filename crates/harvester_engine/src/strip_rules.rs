@@ -0,0 +1,154 @@
+//! User-defined noise to strip from every page.
+//!
+//! Rules are read from a plain text file, one per line:
+//!
+//! ```text
+//! # "Was this page helpful?" widgets and newsletter boxes
+//! selector: .feedback-widget, aside.newsletter
+//! text: (?i)^sign up for our newsletter
+//! ```
+//!
+//! `selector:` rules remove matching elements while the page is sanitized,
+//! before extraction; `text:` rules are regular expressions that drop each
+//! converted markdown line they match.
+use std::path::Path;
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StripRulesError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: {message}")]
+    Invalid { line: usize, message: String },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StripRules {
+    selectors: Vec<Selector>,
+    patterns: Vec<Regex>,
+}
+
+impl StripRules {
+    pub fn load(path: &Path) -> Result<Self, StripRulesError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse rule lines; blank lines and `#` comments are skipped.
+    pub fn parse(text: &str) -> Result<Self, StripRulesError> {
+        let mut rules = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| StripRulesError::Invalid {
+                line: index + 1,
+                message,
+            };
+            match line.split_once(':') {
+                Some(("selector", value)) => rules.selectors.push(
+                    Selector::parse(value.trim())
+                        .map_err(|err| invalid(format!("bad selector: {err}")))?,
+                ),
+                Some(("text", value)) => rules.patterns.push(
+                    Regex::new(value.trim())
+                        .map_err(|err| invalid(format!("bad pattern: {err}")))?,
+                ),
+                _ => {
+                    return Err(invalid(
+                        "expected `selector: ...` or `text: ...`".to_string(),
+                    ))
+                }
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Number of rules of both kinds.
+    pub fn len(&self) -> usize {
+        self.selectors.len() + self.patterns.len()
+    }
+
+    /// `html` without the elements any selector matches.
+    pub(crate) fn strip_elements(&self, html: String) -> String {
+        if self.selectors.is_empty() {
+            return html;
+        }
+        let mut doc = Html::parse_document(&html);
+        let matched: Vec<_> = self
+            .selectors
+            .iter()
+            .flat_map(|selector| doc.select(selector).map(|element| element.id()))
+            .collect();
+        if matched.is_empty() {
+            return html;
+        }
+        for id in matched {
+            if let Some(mut node) = doc.tree.get_mut(id) {
+                node.detach();
+            }
+        }
+        doc.html()
+    }
+
+    /// `markdown` without the lines any pattern matches.
+    pub(crate) fn strip_text(&self, markdown: String) -> String {
+        if self.patterns.is_empty() {
+            return markdown;
+        }
+        markdown
+            .split_inclusive('\n')
+            .filter(|line| {
+                let line = line.trim();
+                !self.patterns.iter().any(|pattern| pattern.is_match(line))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str =
+        "# noise\n\nselector: .feedback, aside.newsletter\ntext: (?i)^was this page helpful\n";
+
+    #[test]
+    fn selectors_remove_elements_and_patterns_remove_lines() {
+        let rules = StripRules::parse(RULES).unwrap();
+        assert_eq!(rules.len(), 2);
+
+        let html = rules.strip_elements(
+            r#"<body><p>Keep me</p><div class="feedback">Rate us</div><aside class="newsletter">Join</aside></body>"#
+                .to_string(),
+        );
+        assert!(html.contains("Keep me"));
+        assert!(!html.contains("Rate us"));
+        assert!(!html.contains("Join"));
+
+        let markdown =
+            rules.strip_text("Intro\n\nWas this page helpful? Yes / No\n\nOutro\n".to_string());
+        assert_eq!(markdown, "Intro\n\n\nOutro\n");
+    }
+
+    #[test]
+    fn malformed_rules_report_their_line() {
+        let err = StripRules::parse("text: ok\nselector: ???\n").unwrap_err();
+        assert!(
+            matches!(err, StripRulesError::Invalid { line: 2, .. }),
+            "{err}"
+        );
+        let err = StripRules::parse("\nwidget: .x\n").unwrap_err();
+        assert!(
+            matches!(err, StripRulesError::Invalid { line: 2, .. }),
+            "{err}"
+        );
+    }
+}
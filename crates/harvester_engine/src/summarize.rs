@@ -0,0 +1,131 @@
+/// Produces a short summary of a converted document, written as `summary:`
+/// in its frontmatter and listed in export manifests.
+///
+/// Called once per document after conversion; implementations may call a
+/// local model or an external API. `None` leaves the summary out.
+pub trait Summarizer: Send + Sync {
+    fn summarize(&self, title: Option<&str>, markdown: &str) -> Option<String>;
+}
+
+/// Default summarizer: documents get no summary.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoSummarizer;
+
+impl Summarizer for NoSummarizer {
+    fn summarize(&self, _title: Option<&str>, _markdown: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Extractive baseline: the first `max_sentences` sentences of prose,
+/// skipping headings, lists, quotes, tables and code blocks.
+#[derive(Debug, Clone, Copy)]
+pub struct LeadSentencesSummarizer {
+    pub max_sentences: usize,
+}
+
+impl Default for LeadSentencesSummarizer {
+    fn default() -> Self {
+        Self { max_sentences: 2 }
+    }
+}
+
+impl Summarizer for LeadSentencesSummarizer {
+    fn summarize(&self, _title: Option<&str>, markdown: &str) -> Option<String> {
+        let mut sentences: Vec<String> = Vec::new();
+        let mut in_code = false;
+        for line in markdown.lines() {
+            let line = line.trim();
+            if line.starts_with("```") {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code || !is_prose(line) {
+                continue;
+            }
+            for sentence in split_sentences(&strip_links(line)) {
+                sentences.push(sentence);
+                if sentences.len() >= self.max_sentences {
+                    return Some(sentences.join(" "));
+                }
+            }
+        }
+        (!sentences.is_empty()).then(|| sentences.join(" "))
+    }
+}
+
+fn is_prose(line: &str) -> bool {
+    let list_item = line
+        .split_once(". ")
+        .is_some_and(|(number, _)| number.chars().all(|c| c.is_ascii_digit()));
+    !line.is_empty()
+        && !list_item
+        && !["#", "- ", "* ", "+ ", ">", "|", "<!--", "!["]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+}
+
+/// `[text](url)` becomes `text`; other markdown is left as is.
+pub(crate) fn strip_links(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some((text, after)) = rest[open + 1..].split_once("](") else {
+            break;
+        };
+        let Some(close) = after.find(')') else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        out.push_str(text);
+        rest = &after[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Sentences ending in `.`, `!` or `?` followed by whitespace or the end
+/// of the line; a trailing fragment without one counts as a sentence too.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let at_break = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_break {
+            let end = index + c.len_utf8();
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+            start = end;
+        }
+    }
+    let tail = text[start..].trim();
+    if !tail.is_empty() {
+        sentences.push(tail.to_string());
+    }
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lead_sentences_skip_headings_lists_and_code() {
+        let markdown = "# Guide\n\n- item one\n\n```\nlet x = 1.\n```\n\nRead the [install notes](https://x/notes) first. Then run it! Finally relax.\n";
+        let summary = LeadSentencesSummarizer { max_sentences: 2 }.summarize(None, markdown);
+        assert_eq!(
+            summary.as_deref(),
+            Some("Read the install notes first. Then run it!")
+        );
+    }
+
+    #[test]
+    fn documents_without_prose_get_no_summary() {
+        let summarizer = LeadSentencesSummarizer::default();
+        assert_eq!(summarizer.summarize(None, "# Title\n\n1. step\n"), None);
+        assert_eq!(NoSummarizer.summarize(None, "Some prose."), None);
+    }
+}
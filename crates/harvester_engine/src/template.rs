@@ -0,0 +1,139 @@
+//! Site template learning: markdown lines that repeat verbatim across pages
+//! of one domain (headers, footers, sidebars) are stripped from later pages.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// A line seen on this many other pages of the domain counts as chrome.
+const MIN_PAGES_FOR_CHROME: usize = 2;
+
+/// What [`SiteTemplates::strip`] removed from one page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StrippedChrome {
+    /// Lines (or fenced code blocks) removed.
+    pub blocks: usize,
+    pub bytes: usize,
+}
+
+/// Line hash → hashes of the page URLs it appeared on.
+type PagesByBlock = HashMap<u64, HashSet<u64>>;
+
+/// Repeated lines per domain, learned over one engine session.
+#[derive(Debug, Default)]
+pub(crate) struct SiteTemplates {
+    domains: Mutex<HashMap<String, PagesByBlock>>,
+}
+
+impl SiteTemplates {
+    /// Remove lines of `markdown` already seen on other pages of `domain`,
+    /// then learn this page's lines. Pages are keyed by `url`, so harvesting
+    /// the same URL again never makes its own content look like chrome.
+    pub(crate) fn strip(
+        &self,
+        domain: &str,
+        url: &str,
+        markdown: &str,
+    ) -> (String, StrippedChrome) {
+        let page = hash_of(url);
+        let units = split_units(markdown);
+        let Ok(mut domains) = self.domains.lock() else {
+            return (markdown.to_string(), StrippedChrome::default());
+        };
+        let seen = domains.entry(domain.to_string()).or_default();
+
+        let mut stripped = StrippedChrome::default();
+        let mut kept: Vec<&str> = Vec::with_capacity(units.len());
+        for unit in &units {
+            let other_pages = seen
+                .get(&hash_of(unit))
+                .map_or(0, |pages| pages.len() - usize::from(pages.contains(&page)));
+            if has_text(unit) && other_pages >= MIN_PAGES_FOR_CHROME {
+                stripped.blocks += 1;
+                stripped.bytes += unit.len();
+            } else if !(unit.trim().is_empty() && kept.last().is_none_or(|l| l.trim().is_empty())) {
+                kept.push(unit);
+            }
+        }
+        for unit in units.into_iter().filter(|unit| has_text(unit)) {
+            seen.entry(hash_of(unit)).or_default().insert(page);
+        }
+        if stripped.blocks == 0 {
+            return (markdown.to_string(), stripped);
+        }
+        while kept.last().is_some_and(|l| l.trim().is_empty()) {
+            kept.pop();
+        }
+        let result = kept.iter().map(|unit| format!("{unit}\n")).collect();
+        (result, stripped)
+    }
+}
+
+/// Lines of `markdown`, with each fenced code block kept as one unit.
+fn split_units(markdown: &str) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut fence_start = None;
+    let mut offset = 0;
+    for line in markdown.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with("```");
+        match fence_start {
+            None if is_fence => fence_start = Some(offset),
+            None => units.push(line.trim_end_matches(['\n', '\r'])),
+            Some(begin) if is_fence => {
+                units.push(markdown[begin..offset + line.len()].trim_end_matches(['\n', '\r']));
+                fence_start = None;
+            }
+            Some(_) => {}
+        }
+        offset += line.len();
+    }
+    if let Some(begin) = fence_start {
+        units.push(&markdown[begin..]);
+    }
+    units
+}
+
+/// Rules, table separators and blank lines never count as chrome.
+fn has_text(unit: &str) -> bool {
+    unit.chars().any(char::is_alphanumeric)
+}
+
+fn hash_of(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(body: &str) -> String {
+        format!("Site Name\n\n[Home](/) [About](/about)\n\n{body}\n\n© Example Corp\n")
+    }
+
+    #[test]
+    fn strips_lines_repeated_on_two_earlier_pages() {
+        let templates = SiteTemplates::default();
+        let (first, none) = templates.strip("ex.com", "https://ex.com/1", &page("One."));
+        assert_eq!(first, page("One."));
+        assert_eq!(none, StrippedChrome::default());
+        templates.strip("ex.com", "https://ex.com/2", &page("Two."));
+        // Re-harvesting a known page does not count as a third page.
+        let (again, _) = templates.strip("ex.com", "https://ex.com/2", &page("Two."));
+        assert_eq!(again, page("Two."));
+
+        let (third, stripped) = templates.strip("ex.com", "https://ex.com/3", &page("Three."));
+        assert_eq!(third, "Three.\n");
+        assert_eq!(stripped.blocks, 3);
+
+        let (other, _) = templates.strip("other.org", "https://other.org/", &page("Four."));
+        assert_eq!(other, page("Four."));
+    }
+
+    #[test]
+    fn fenced_code_is_one_unit() {
+        let units = split_units("Intro\n\n```\na\n\nb\n```\nOutro\n");
+        assert_eq!(units, vec!["Intro", "", "```\na\n\nb\n```", "Outro"]);
+    }
+}
@@ -1,5 +1,11 @@
 pub trait TokenCounter: Send + Sync {
     fn count(&self, text: &str) -> u32;
+
+    /// Stable name of the tokenizer, used to key cached counts. Counters whose
+    /// results depend on configuration should include it.
+    fn id(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// Simple, deterministic whitespace tokenizer as a placeholder.
@@ -10,4 +16,77 @@ impl TokenCounter for WhitespaceTokenCounter {
     fn count(&self, text: &str) -> u32 {
         text.split_whitespace().count() as u32
     }
+
+    fn id(&self) -> &str {
+        "whitespace"
+    }
+}
+
+/// Fast estimate of BPE token counts for large documents, calibrated on
+/// English prose and markdown: about four characters per token for words,
+/// three digits per token for numbers, one token per punctuation mark and
+/// per CJK character.
+///
+/// Pair it with an exact counter in `EngineConfig::backfill_token_counter`
+/// to have written documents recounted in the background.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApproxTokenCounter;
+
+impl TokenCounter for ApproxTokenCounter {
+    fn count(&self, text: &str) -> u32 {
+        let mut tokens: u32 = 0;
+        let mut letters: u32 = 0;
+        let mut digits: u32 = 0;
+        for c in text.chars() {
+            if c.is_alphabetic() && !is_cjk(c) {
+                tokens += digits.div_ceil(3);
+                digits = 0;
+                letters += 1;
+                continue;
+            }
+            if c.is_numeric() {
+                tokens += letters.div_ceil(4);
+                letters = 0;
+                digits += 1;
+                continue;
+            }
+            tokens += letters.div_ceil(4) + digits.div_ceil(3);
+            letters = 0;
+            digits = 0;
+            if !c.is_whitespace() {
+                tokens += 1;
+            }
+        }
+        tokens + letters.div_ceil(4) + digits.div_ceil(3)
+    }
+
+    fn id(&self) -> &str {
+        "approx-v1"
+    }
+}
+
+/// Han, kana and Hangul, which BPE vocabularies split about one per token.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{20000}'..='\u{2fa1f}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approx_counts_words_numbers_punctuation_and_cjk() {
+        let counter = ApproxTokenCounter;
+        assert_eq!(counter.count(""), 0);
+        // "the" 1, "tokenizer" 3, "," 1, "2024" 2, "." 1
+        assert_eq!(counter.count("the tokenizer, 2024."), 8);
+        assert_eq!(counter.count("## Install"), 4);
+        assert_eq!(counter.count("東京は晴れ"), 5);
+    }
 }
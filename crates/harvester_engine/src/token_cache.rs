@@ -0,0 +1,168 @@
+//! Token counts remembered by tokenizer and content hash, so recounting
+//! unchanged documents (re-harvests, backfills) skips the tokenizer.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use engine_logging::engine_warn;
+use sha2::{Digest, Sha256};
+
+use crate::token::TokenCounter;
+
+/// File in the output directory that holds cached counts, one
+/// `{tokenizer id}\t{sha256}\t{count}` line each.
+pub(crate) const TOKEN_CACHE_FILENAME: &str = "token_cache.tsv";
+
+/// (tokenizer id, hex SHA-256 of the text).
+type CacheKey = (String, String);
+
+/// Persistent token counts. New counts are appended to the cache file as
+/// they are computed; later lines win when the file is loaded.
+#[derive(Debug)]
+pub(crate) struct TokenCache {
+    counts: Mutex<HashMap<CacheKey, u32>>,
+    file: Option<Mutex<File>>,
+}
+
+impl TokenCache {
+    /// Load `{output_dir}/token_cache.tsv` and append to it. If the file
+    /// cannot be opened the cache still works for this session.
+    pub(crate) fn open(output_dir: &Path) -> Self {
+        let path = output_dir.join(TOKEN_CACHE_FILENAME);
+        let counts = match File::open(&path) {
+            Ok(file) => parse_lines(BufReader::new(file)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => {
+                engine_warn!("Ignoring token cache {}: {}", path.display(), err);
+                HashMap::new()
+            }
+        };
+        let file = std::fs::create_dir_all(output_dir)
+            .and_then(|()| OpenOptions::new().create(true).append(true).open(&path))
+            .map_err(|err| engine_warn!("Token cache {} not persisted: {}", path.display(), err))
+            .ok()
+            .map(Mutex::new);
+        Self {
+            counts: Mutex::new(counts),
+            file,
+        }
+    }
+
+    fn count(&self, counter: &dyn TokenCounter, text: &str) -> u32 {
+        let key = (counter.id().to_string(), hex_sha256(text));
+        if let Some(count) = self.counts.lock().ok().and_then(|c| c.get(&key).copied()) {
+            return count;
+        }
+        let count = counter.count(text);
+        if let Some(file) = &self.file {
+            let line = format!("{}\t{}\t{count}\n", key.0, key.1);
+            let written = file
+                .lock()
+                .map_err(|_| io::Error::other("token cache lock poisoned"))
+                .and_then(|mut file| file.write_all(line.as_bytes()));
+            if let Err(err) = written {
+                engine_warn!("Token cache write failed: {}", err);
+            }
+        }
+        if let Ok(mut counts) = self.counts.lock() {
+            counts.insert(key, count);
+        }
+        count
+    }
+}
+
+/// A [`TokenCounter`] that consults a [`TokenCache`], when there is one,
+/// before counting.
+pub(crate) struct CachedTokenCounter<'a> {
+    cache: Option<&'a TokenCache>,
+    inner: &'a dyn TokenCounter,
+}
+
+impl<'a> CachedTokenCounter<'a> {
+    pub(crate) fn new(cache: Option<&'a TokenCache>, inner: &'a dyn TokenCounter) -> Self {
+        Self { cache, inner }
+    }
+}
+
+impl TokenCounter for CachedTokenCounter<'_> {
+    fn count(&self, text: &str) -> u32 {
+        match self.cache {
+            Some(cache) => cache.count(self.inner, text),
+            None => self.inner.count(text),
+        }
+    }
+
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+}
+
+fn parse_lines(reader: impl BufRead) -> HashMap<CacheKey, u32> {
+    reader
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let id = fields.next()?.to_string();
+            let hash = fields.next()?.to_string();
+            let count = fields.next()?.trim().parse().ok()?;
+            Some(((id, hash), count))
+        })
+        .collect()
+}
+
+fn hex_sha256(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counting {
+        calls: AtomicUsize,
+    }
+
+    impl TokenCounter for Counting {
+        fn count(&self, text: &str) -> u32 {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            text.split_whitespace().count() as u32
+        }
+
+        fn id(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[test]
+    fn counts_are_reused_within_and_across_sessions() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let tokenizer = Counting::default();
+
+        let cache = TokenCache::open(temp.path());
+        let counter = CachedTokenCounter::new(Some(&cache), &tokenizer);
+        assert_eq!(counter.count("one two three"), 3);
+        assert_eq!(counter.count("one two three"), 3);
+        assert_eq!(tokenizer.calls.load(Ordering::Relaxed), 1);
+        drop(cache);
+
+        let reopened = TokenCache::open(temp.path());
+        let counter = CachedTokenCounter::new(Some(&reopened), &tokenizer);
+        assert_eq!(counter.count("one two three"), 3);
+        assert_eq!(tokenizer.calls.load(Ordering::Relaxed), 1);
+        // A different tokenizer has its own entries.
+        let whitespace = crate::WhitespaceTokenCounter;
+        let counter = CachedTokenCounter::new(Some(&reopened), &whitespace);
+        assert_eq!(counter.count("one two three"), 3);
+        let lines = std::fs::read_to_string(temp.path().join(TOKEN_CACHE_FILENAME)).unwrap();
+        assert_eq!(lines.lines().count(), 2);
+    }
+}
@@ -1,5 +1,12 @@
+use crate::diff::ContentDiff;
+use crate::export::ExportSummary;
 use crate::links::ExtractedLink;
+use crate::quality::{QualityReport, ReadingMetrics};
+use crate::template::StrippedChrome;
+use crate::walls::ContentWarning;
 use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub type JobId = u64;
 
@@ -30,6 +37,44 @@ pub enum EngineEvent {
         job_id: JobId,
         result: Result<JobOutcome, FailureKind>,
     },
+    /// An export finished; `exported_utc` is the engine clock at export time.
+    ExportCompleted {
+        exported_utc: String,
+        summary: ExportSummary,
+    },
+    ExportFailed {
+        message: String,
+    },
+    /// The job queue reached capacity; further enqueues are rejected until resumed.
+    QueueSaturated {
+        capacity: usize,
+    },
+    /// The job queue drained enough to accept new work again.
+    QueueResumed,
+    /// Running jobs hold about `in_use_bytes` of the memory budget; queued
+    /// jobs wait until enough of them finish.
+    MemoryThrottled {
+        in_use_bytes: u64,
+        budget_bytes: u64,
+    },
+    /// After a stop, every queued and running job has completed; sent once per stop.
+    AllJobsDrained,
+    /// The server rate-limited the job; it is fetched again after `delay`.
+    JobRetryScheduled {
+        job_id: JobId,
+        delay: Duration,
+    },
+    /// A watched page changed since its previous check; the new document is written.
+    WatchChanged {
+        watch_id: JobId,
+        outcome: JobOutcome,
+    },
+    /// The backfill counter recounted a completed job's document and its
+    /// `token_count` was rewritten.
+    TokenCountRefined {
+        job_id: JobId,
+        tokens: u32,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -41,10 +86,35 @@ pub struct FetchOutput {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct JobOutcome {
     pub final_url: String,
+    /// Target of the page's `<link rel="canonical">`; used as the document URL.
+    pub canonical_url: Option<String>,
     pub tokens: Option<u32>,
     pub bytes_written: Option<u64>,
+    /// Where the markdown document was written.
+    pub output_path: Option<PathBuf>,
+    /// Diff against the document previously written to the same path;
+    /// `None` when the file did not exist yet.
+    pub diff: Option<ContentDiff>,
     pub content_preview: Option<String>,
     pub extracted_links: Vec<ExtractedLink>,
+    /// Quality of the first page's extracted content.
+    pub quality: Option<QualityReport>,
+    /// Words and reading time of the written body, all parts included.
+    pub reading: Option<ReadingMetrics>,
+    /// Set when the page looks like a paywall or consent wall rather than the
+    /// article; the document is still written.
+    pub content_warning: Option<ContentWarning>,
+    /// Repeated site chrome removed from the page; `None` when site template
+    /// stripping is off.
+    pub stripped_chrome: Option<StrippedChrome>,
+    /// The page came from the response cache instead of a fresh fetch.
+    pub from_cache: bool,
+    /// The document was byte-identical to the file on disk, so the write was
+    /// skipped and the file's modification time left alone.
+    pub unchanged: bool,
+    /// Earlier job of this session whose document has the same body; no
+    /// second file is written.
+    pub duplicate_of: Option<JobId>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,6 +124,31 @@ pub struct FetchMetadata {
     pub redirect_count: usize,
     pub content_type: Option<String>,
     pub byte_len: u64,
+    pub headers: ResponseHeaders,
+}
+
+impl FetchMetadata {
+    /// `(name, value)` of the provenance headers the response carried,
+    /// `Content-Type` included.
+    pub fn provenance_headers(&self) -> Vec<(&'static str, String)> {
+        [
+            ("content-type", &self.content_type),
+            ("last-modified", &self.headers.last_modified),
+            ("etag", &self.headers.etag),
+            ("server", &self.headers.server),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value.clone()?)))
+        .collect()
+    }
+}
+
+/// Response headers kept for provenance and conditional re-fetches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseHeaders {
+    pub last_modified: Option<String>,
+    pub etag: Option<String>,
+    pub server: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -77,12 +172,70 @@ pub enum FailureKind {
     HttpStatus(u16),
     Timeout,
     RedirectLimitExceeded,
-    TooLarge { max_bytes: u64, actual: Option<u64> },
-    UnsupportedContentType { content_type: String },
-    ProcessingTimeout { stage: Stage },
+    TooLarge {
+        max_bytes: u64,
+        actual: Option<u64>,
+    },
+    UnsupportedContentType {
+        content_type: String,
+    },
+    ProcessingTimeout {
+        stage: Stage,
+    },
     Cancelled,
+    QueueFull,
     ProcessingError,
     Network,
+    /// A redirect left the original host while only same-domain redirects are allowed.
+    CrossDomainRedirect {
+        target_host: String,
+    },
+    /// HTTP 429 or 503 with a `Retry-After` header.
+    RateLimited {
+        retry_after_secs: u64,
+    },
+    /// An offline harvest found no capture of the URL in the WARC archive or
+    /// fixtures directory.
+    NotArchived,
+    /// A `file://` job whose file could not be read.
+    FileUnreadable,
+    /// The body was binary data of the `detected` format (`gzip`, `pdf`, ...)
+    /// despite a text Content-Type.
+    BinaryContent {
+        detected: String,
+    },
+    /// Documents from `domain` already reached the per-domain token cap, so
+    /// the job was not fetched.
+    DomainBudgetExceeded {
+        domain: String,
+        cap: u64,
+    },
+}
+
+impl FailureKind {
+    /// Short stable name for grouping failures: `http_404`, `timeout`, ...
+    pub fn key(&self) -> String {
+        let key = match self {
+            FailureKind::HttpStatus(code) => return format!("http_{code}"),
+            FailureKind::InvalidUrl => "invalid_url",
+            FailureKind::Timeout => "timeout",
+            FailureKind::RedirectLimitExceeded => "redirect_limit",
+            FailureKind::TooLarge { .. } => "too_large",
+            FailureKind::UnsupportedContentType { .. } => "unsupported_content_type",
+            FailureKind::ProcessingTimeout { .. } => "processing_timeout",
+            FailureKind::Cancelled => "cancelled",
+            FailureKind::QueueFull => "queue_full",
+            FailureKind::ProcessingError => "processing_error",
+            FailureKind::Network => "network",
+            FailureKind::CrossDomainRedirect { .. } => "cross_domain_redirect",
+            FailureKind::RateLimited { .. } => "rate_limited",
+            FailureKind::NotArchived => "not_archived",
+            FailureKind::FileUnreadable => "file_unreadable",
+            FailureKind::BinaryContent { .. } => "binary_content",
+            FailureKind::DomainBudgetExceeded { .. } => "domain_budget",
+        };
+        key.to_string()
+    }
 }
 
 impl fmt::Display for FailureKind {
@@ -102,8 +255,21 @@ impl fmt::Display for FailureKind {
                 write!(f, "processing timeout at stage {stage:?}")
             }
             FailureKind::Cancelled => write!(f, "cancelled"),
+            FailureKind::QueueFull => write!(f, "engine queue full"),
             FailureKind::ProcessingError => write!(f, "processing error"),
             FailureKind::Network => write!(f, "network error"),
+            FailureKind::CrossDomainRedirect { target_host } => {
+                write!(f, "cross-domain redirect to {target_host}")
+            }
+            FailureKind::RateLimited { retry_after_secs } => {
+                write!(f, "rate limited (retry after {retry_after_secs}s)")
+            }
+            FailureKind::NotArchived => write!(f, "not in archive"),
+            FailureKind::FileUnreadable => write!(f, "local file unreadable"),
+            FailureKind::BinaryContent { detected } => write!(f, "binary {detected} content"),
+            FailureKind::DomainBudgetExceeded { domain, cap } => {
+                write!(f, "{domain} reached its cap of {cap} tokens")
+            }
         }
     }
 }
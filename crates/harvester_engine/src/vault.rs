@@ -0,0 +1,186 @@
+//! Obsidian vault export: one note per document in per-domain folders, with
+//! links between harvested pages rewritten as wikilinks.
+use std::collections::HashMap;
+use std::path::Path;
+
+use url::Url;
+
+use crate::export::{
+    selected_docs, write_manifest, DocMeta, ExportError, ExportOptions, ExportSummary,
+};
+use crate::persist::AtomicFileWriter;
+
+/// Directory inside the output directory that holds the vault.
+pub(crate) const VAULT_DIR: &str = "vault";
+
+/// Write the documents selected by `options` as notes under
+/// `{output_dir}/vault/{domain}/`. Markdown links and autolinks to other
+/// exported documents become `[[domain/note|text]]` wikilinks.
+pub fn build_obsidian_vault(
+    output_dir: &Path,
+    options: ExportOptions,
+) -> Result<ExportSummary, ExportError> {
+    let docs = selected_docs(output_dir, &options)?;
+    let notes: HashMap<String, String> = docs
+        .iter()
+        .map(|doc| (link_key(&doc.url), note_path(doc)))
+        .collect();
+
+    let vault_dir = output_dir.join(VAULT_DIR);
+    let mut total_tokens: u64 = 0;
+    for doc in &docs {
+        total_tokens += doc.token_count.unwrap_or(0) as u64;
+        let folder = domain_folder(&doc.url);
+        let note = format!(
+            "{frontmatter}{body}\n",
+            frontmatter = note_frontmatter(doc, &folder),
+            body = rewrite_links(doc.body.trim(), &notes)
+        );
+        let writer = AtomicFileWriter::new(vault_dir.join(&folder));
+        writer.write(&format!("{}.md", note_stem(doc)), &note)?;
+    }
+    let manifest_path = write_manifest(
+        output_dir,
+        options.manifest_filename,
+        &docs,
+        None,
+        total_tokens,
+        options.session.as_ref(),
+        None,
+    )?;
+
+    Ok(ExportSummary {
+        doc_count: docs.len(),
+        total_tokens,
+        output_path: vault_dir,
+        manifest_path,
+    })
+}
+
+fn note_frontmatter(doc: &DocMeta, folder: &str) -> String {
+    // JSON strings are valid YAML scalars, which handles quotes and colons in titles.
+    let title = serde_json::Value::String(doc.title.clone());
+    format!(
+        "---\ntitle: {title}\nsource: {url}\nfetched: {fetched}\ntokens: {tokens}\ntags:\n  - harvested\n  - {tag}\n---\n\n",
+        url = doc.url,
+        fetched = doc.fetched_utc,
+        tokens = doc.token_count.unwrap_or(0),
+        tag = folder.replace(['.', '_'], "-"),
+    )
+}
+
+/// Note name: the document filename without `.md`; snapshots use their
+/// page directory so every version maps to the same note.
+fn note_stem(doc: &DocMeta) -> &str {
+    match doc.filename.split_once('/') {
+        Some((page_dir, _)) => page_dir,
+        None => doc.filename.trim_end_matches(".md"),
+    }
+}
+
+fn note_path(doc: &DocMeta) -> String {
+    format!("{}/{}", domain_folder(&doc.url), note_stem(doc))
+}
+
+/// Host of `url` as a folder name; ports use `_` since `:` is not allowed
+/// in Windows paths.
+fn domain_folder(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            let host = parsed.host_str()?.to_ascii_lowercase();
+            Some(match parsed.port() {
+                Some(port) => format!("{host}_{port}"),
+                None => host,
+            })
+        })
+        .unwrap_or_else(|| "other".to_string())
+}
+
+/// URL without fragment or trailing slash, so links match documents
+/// regardless of those differences.
+fn link_key(url: &str) -> String {
+    match Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string().trim_end_matches('/').to_string()
+        }
+        Err(_) => url.trim().trim_end_matches('/').to_string(),
+    }
+}
+
+/// Rewrite `[text](url)` and `<url>` links whose target is an exported note.
+fn rewrite_links(body: &str, notes: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(pos) = rest.find(['[', '<']) {
+        let (before, from) = rest.split_at(pos);
+        out.push_str(before);
+        let rewritten = if from.starts_with('[') {
+            markdown_link(from).and_then(|(text, url, len)| {
+                let note = notes.get(&link_key(url))?;
+                Some((format!("[[{note}|{text}]]"), len))
+            })
+        } else {
+            autolink(from).and_then(|(url, len)| {
+                let note = notes.get(&link_key(url))?;
+                Some((format!("[[{note}]]"), len))
+            })
+        };
+        match rewritten {
+            Some((link, len)) => {
+                out.push_str(&link);
+                rest = &from[len..];
+            }
+            None => {
+                out.push_str(&from[..1]);
+                rest = &from[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `[text](url)` at the start of `s`: text, url and the length consumed.
+fn markdown_link(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let text = &s[1..close];
+    if text.contains('[') || !s[close + 1..].starts_with('(') {
+        return None;
+    }
+    let url_start = close + 2;
+    let url_end = url_start + s[url_start..].find(')')?;
+    let url = s[url_start..url_end].split_whitespace().next()?;
+    Some((text, url, url_end + 1))
+}
+
+/// `<https://...>` at the start of `s`: url and the length consumed.
+fn autolink(s: &str) -> Option<(&str, usize)> {
+    let close = s.find('>')?;
+    let url = &s[1..close];
+    (url.starts_with("http://") || url.starts_with("https://"))
+        .then_some((url, close + 1))
+        .filter(|(url, _)| !url.contains(char::is_whitespace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_to_harvested_pages_become_wikilinks() {
+        let notes = HashMap::from([(
+            link_key("https://example.com/guide/"),
+            "example.com/Guide--abc".to_string(),
+        )]);
+        let body =
+            "See [the guide](https://example.com/guide#setup) and <https://example.com/guide>, \
+            not [elsewhere](https://other.org/) or [x] alone.";
+        assert_eq!(
+            rewrite_links(body, &notes),
+            "See [[example.com/Guide--abc|the guide]] and [[example.com/Guide--abc]], \
+            not [elsewhere](https://other.org/) or [x] alone."
+        );
+    }
+}
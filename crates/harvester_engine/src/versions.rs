@@ -0,0 +1,178 @@
+//! Dated snapshots of each page.
+//!
+//! With [`EngineConfig::versioned_snapshots`] enabled, a page is written to
+//! `{filename stem}/{YYYY-MM-DD}.md` instead of overwriting `{filename}`. A
+//! second harvest on the same day replaces that day's snapshot. Exports take
+//! the latest snapshot of each page unless asked for all of them.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::diff::{diff_documents, write_with_diff, WrittenDocument};
+use crate::engine::EngineConfig;
+use crate::filename::{fit_filename_to_path, short_hash};
+use crate::persist::{AtomicFileWriter, PersistError};
+
+/// One stored snapshot of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageVersion {
+    /// `YYYY-MM-DD` taken from the snapshot's file name.
+    pub date: String,
+    pub path: PathBuf,
+}
+
+/// Where job documents are written, as configured for the engine.
+#[derive(Debug, Clone)]
+pub(crate) struct DocumentStore {
+    output_dir: PathBuf,
+    versioned: bool,
+}
+
+impl DocumentStore {
+    pub(crate) fn from_config(config: &EngineConfig) -> Self {
+        Self {
+            output_dir: config.output_dir.clone(),
+            versioned: config.versioned_snapshots,
+        }
+    }
+
+    /// Write a document and diff it against the version it supersedes. A
+    /// file that already holds exactly `content` is not rewritten.
+    pub(crate) fn write(
+        &self,
+        filename: &str,
+        fetched_utc: &str,
+        content: &str,
+    ) -> Result<WrittenDocument, PersistError> {
+        let writer = AtomicFileWriter::new(self.output_dir.clone());
+        if !self.versioned {
+            let filename = fit_filename_to_path(filename, &self.output_dir, 0);
+            return write_with_diff(&writer, &filename, content);
+        }
+        let filename = fit_filename_to_path(filename, &self.output_dir, SNAPSHOT_NESTED_LEN);
+        let dir = self.output_dir.join(snapshot_dir_name(&filename));
+        let snapshot = snapshot_filename(fetched_utc);
+        let target = dir.join(&snapshot);
+        if fs::read(&target).ok().as_deref() == Some(content.as_bytes()) {
+            return Ok(WrittenDocument::unchanged(target));
+        }
+        let previous = list_versions(&dir)
+            .ok()
+            .and_then(|versions| versions.into_iter().last())
+            .and_then(|latest| fs::read_to_string(latest.path).ok());
+        let writer = AtomicFileWriter::new(dir);
+        let path = writer.write(&snapshot, content)?;
+        Ok(WrittenDocument {
+            path,
+            diff: previous.map(|previous| diff_documents(&previous, content)),
+            unchanged: false,
+        })
+    }
+}
+
+/// Path characters a snapshot adds below `{filename}`: the date file, less
+/// the `.md` dropped from the directory name.
+const SNAPSHOT_NESTED_LEN: usize = "/2024-05-01.md".len() - ".md".len();
+
+fn snapshot_dir_name(filename: &str) -> &str {
+    filename.strip_suffix(".md").unwrap_or(filename)
+}
+
+/// `2024-05-01.md` from an RFC 3339 timestamp.
+fn snapshot_filename(fetched_utc: &str) -> String {
+    let date: String = fetched_utc.chars().take(10).collect();
+    format!("{date}.md")
+}
+
+/// Snapshots in `dir`, oldest first.
+pub(crate) fn list_versions(dir: &Path) -> io::Result<Vec<PageVersion>> {
+    let mut versions: Vec<PageVersion> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                return None;
+            }
+            let date = path.file_stem()?.to_str()?.to_string();
+            Some(PageVersion { date, path })
+        })
+        .collect();
+    versions.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok(versions)
+}
+
+/// Every stored snapshot of `url`, oldest first.
+///
+/// Snapshot directories end in the hash of the requested URL, so history
+/// survives a page changing its title.
+pub fn page_history(output_dir: &Path, url: &str) -> io::Result<Vec<PageVersion>> {
+    let suffix = format!("--{}", short_hash(url));
+    let mut versions = Vec::new();
+    let entries = match fs::read_dir(output_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(versions),
+        Err(err) => return Err(err),
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+        if is_dir && entry.file_name().to_string_lossy().ends_with(&suffix) {
+            versions.extend(list_versions(&entry.path())?);
+        }
+    }
+    versions.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deterministic_filename;
+    use tempfile::tempdir;
+
+    fn store(dir: &Path) -> DocumentStore {
+        DocumentStore {
+            output_dir: dir.to_path_buf(),
+            versioned: true,
+        }
+    }
+
+    #[test]
+    fn snapshots_are_dated_and_diffed_against_latest() {
+        let temp = tempdir().unwrap();
+        let url = "https://example.com/page";
+        let filename = deterministic_filename(Some("Page"), url);
+        let store = store(temp.path());
+
+        let first = store
+            .write(&filename, "2024-05-01T10:00:00Z", "---\n---\nold\n")
+            .unwrap();
+        assert!(first.diff.is_none());
+        assert!(first
+            .path
+            .ends_with(format!("{}/2024-05-01.md", snapshot_dir_name(&filename))));
+
+        let second = store
+            .write(&filename, "2024-05-02T10:00:00Z", "---\n---\nnew\n")
+            .unwrap();
+        assert!(!second.unchanged);
+        let again = store
+            .write(&filename, "2024-05-02T18:00:00Z", "---\n---\nnew\n")
+            .unwrap();
+        assert!(again.unchanged);
+        assert_eq!(again.path, second.path);
+        assert_eq!(second.diff.map(|d| (d.added, d.removed)), Some((1, 1)));
+
+        // A renamed page is still found by URL.
+        let renamed = deterministic_filename(Some("Page v2"), url);
+        store
+            .write(&renamed, "2024-05-03T10:00:00Z", "---\n---\nnewer\n")
+            .unwrap();
+        let history = page_history(temp.path(), url).unwrap();
+        let dates: Vec<&str> = history.iter().map(|v| v.date.as_str()).collect();
+        assert_eq!(dates, ["2024-05-01", "2024-05-02", "2024-05-03"]);
+        assert!(page_history(temp.path(), "https://other.example")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+//! Paywall and consent-wall detection.
+//!
+//! A page is only flagged when its extracted body is thin *and* it carries a
+//! known wall marker; consent banners appear on most sites and are harmless
+//! when the article itself came through.
+use std::fmt;
+
+/// Bodies with fewer words than this may be a wall instead of the article.
+const THIN_BODY_WORDS: usize = 250;
+
+/// Lowercase fragments in class/id attributes or text that mark a paywall.
+const PAYWALL_MARKERS: &[&str] = &[
+    "\"isaccessibleforfree\": false",
+    "\"isaccessibleforfree\":false",
+    "\"isaccessibleforfree\":\"false\"",
+    "paywall",
+    "regwall",
+    "piano-",
+    "tp-modal",
+    "subscriber-only",
+    "premium-content",
+    "meteredcontent",
+    "subscribe to continue reading",
+    "subscribe to keep reading",
+    "already a subscriber",
+    "this article is for subscribers",
+];
+
+/// Lowercase fragments that mark a cookie/consent interstitial.
+const CONSENT_MARKERS: &[&str] = &[
+    "onetrust",
+    "didomi",
+    "qc-cmp",
+    "cookie-consent",
+    "consent-banner",
+    "cmp-container",
+    "we value your privacy",
+    "accept all cookies",
+    "before you continue",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentWarning {
+    /// A paywall likely hid most of the article.
+    PaywallSuspected,
+    /// A cookie/consent interstitial was captured instead of the page.
+    ConsentWall,
+}
+
+impl fmt::Display for ContentWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentWarning::PaywallSuspected => write!(f, "paywall_suspected"),
+            ContentWarning::ConsentWall => write!(f, "consent_wall"),
+        }
+    }
+}
+
+/// Check raw `html` whose extracted body has `body_words` words.
+pub fn detect_content_wall(html: &str, body_words: usize) -> Option<ContentWarning> {
+    if body_words >= THIN_BODY_WORDS {
+        return None;
+    }
+    let lower = html.to_lowercase();
+    if PAYWALL_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        Some(ContentWarning::PaywallSuspected)
+    } else if CONSENT_MARKERS.iter().any(|marker| lower.contains(marker)) {
+        Some(ContentWarning::ConsentWall)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_thin_pages_with_wall_markers_only() {
+        let paywalled = r#"<article><p>Three short sentences.</p><div class="paywall-overlay">Subscribe</div></article>"#;
+        assert_eq!(
+            detect_content_wall(paywalled, 3),
+            Some(ContentWarning::PaywallSuspected)
+        );
+        // The same markers with a full article body are not a wall.
+        assert_eq!(detect_content_wall(paywalled, 900), None);
+
+        let consent = r#"<div id="onetrust-banner-sdk">We value your privacy</div>"#;
+        assert_eq!(
+            detect_content_wall(consent, 5),
+            Some(ContentWarning::ConsentWall)
+        );
+        assert_eq!(detect_content_wall("<p>Short note.</p>", 2), None);
+    }
+}
@@ -0,0 +1,502 @@
+//! WARC archives: reading captures for offline harvesting, and recording
+//! fetched responses so a harvest can be archived in a standard format.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use engine_logging::engine_warn;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use url::Url;
+
+use crate::fetch::{FetchSettings, Fetcher, ProgressSink};
+use crate::{
+    EngineEvent, FailureKind, FetchError, FetchMetadata, FetchOutput, JobId, JobProgress,
+    ResponseHeaders, Stage,
+};
+
+#[derive(Debug, Error)]
+pub enum WarcError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("malformed WARC record at byte {offset}: {reason}")]
+    Malformed { offset: usize, reason: &'static str },
+}
+
+/// An HTTP response captured in a WARC `response` record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// `Location` header of a redirect.
+    pub location: Option<String>,
+    pub headers: ResponseHeaders,
+    /// Body with transfer and content encodings removed.
+    pub body: Vec<u8>,
+}
+
+/// Responses of a WARC file, keyed by target URI. When a URI was captured
+/// more than once the last capture wins.
+#[derive(Debug, Clone, Default)]
+pub struct WarcArchive {
+    responses: HashMap<String, ArchivedResponse>,
+    /// Target URIs in first-capture order.
+    order: Vec<String>,
+}
+
+impl WarcArchive {
+    /// Read a `.warc` or gzip-compressed `.warc.gz` file.
+    pub fn open(path: &Path) -> Result<Self, WarcError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, WarcError> {
+        let mut decompressed = Vec::new();
+        let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+            MultiGzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+            decompressed.as_slice()
+        } else {
+            bytes
+        };
+
+        let mut archive = Self::default();
+        let mut offset = 0;
+        while let Some(start) = skip_blank_lines(bytes, offset) {
+            let (headers, block, next) = read_record(bytes, start)?;
+            offset = next;
+            let is_response = header(&headers, "WARC-Type")
+                .is_some_and(|value| value.eq_ignore_ascii_case("response"));
+            let Some(uri) = header(&headers, "WARC-Target-URI") else {
+                continue;
+            };
+            if !is_response {
+                continue;
+            }
+            let Some(response) = parse_http_response(block) else {
+                engine_warn!("[Warc] Skipping unreadable response for {}", uri);
+                continue;
+            };
+            let key = archive_key(uri.trim_matches(['<', '>']));
+            if archive.responses.insert(key.clone(), response).is_none() {
+                archive.order.push(key);
+            }
+        }
+        Ok(archive)
+    }
+
+    pub fn get(&self, url: &str) -> Option<&ArchivedResponse> {
+        self.responses.get(&archive_key(url))
+    }
+
+    /// URLs of successful captures, in archive order; the job list for an
+    /// offline harvest.
+    pub fn page_urls(&self) -> Vec<String> {
+        self.order
+            .iter()
+            .filter(|url| {
+                self.responses
+                    .get(*url)
+                    .is_some_and(|response| (200..300).contains(&response.status))
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+}
+
+/// Serves fetches from a [`WarcArchive`] instead of the network. Redirect,
+/// size and content-type rules from `FetchSettings` still apply.
+pub struct WarcFetcher {
+    archive: Arc<WarcArchive>,
+    settings: FetchSettings,
+}
+
+impl WarcFetcher {
+    pub fn new(archive: Arc<WarcArchive>, settings: FetchSettings) -> Self {
+        Self { archive, settings }
+    }
+}
+
+#[async_trait::async_trait]
+impl Fetcher for WarcFetcher {
+    async fn fetch(
+        &self,
+        job_id: JobId,
+        url: &str,
+        sink: &dyn ProgressSink,
+    ) -> Result<FetchOutput, FetchError> {
+        let mut current = Url::parse(url)
+            .map_err(|err| FetchError::new(FailureKind::InvalidUrl, err.to_string()))?;
+        let mut redirect_count = 0;
+        let response = loop {
+            let Some(response) = self.archive.get(current.as_str()) else {
+                engine_warn!("[Warc] No capture of '{}'", current);
+                return Err(FetchError::new(
+                    FailureKind::NotArchived,
+                    current.to_string(),
+                ));
+            };
+            let redirect = response
+                .location
+                .as_deref()
+                .filter(|_| (300..400).contains(&response.status));
+            let Some(location) = redirect else {
+                break response;
+            };
+            redirect_count += 1;
+            if redirect_count > self.settings.redirect_limit {
+                return Err(FetchError::new(
+                    FailureKind::RedirectLimitExceeded,
+                    "redirect limit exceeded",
+                ));
+            }
+            current = current
+                .join(location)
+                .map_err(|err| FetchError::new(FailureKind::InvalidUrl, err.to_string()))?;
+        };
+
+        if !(200..300).contains(&response.status) {
+            return Err(FetchError::new(
+                FailureKind::HttpStatus(response.status),
+                format!("archived status {}", response.status),
+            ));
+        }
+        let byte_len = response.body.len() as u64;
+        if byte_len > self.settings.max_bytes {
+            return Err(FetchError::new(
+                FailureKind::TooLarge {
+                    max_bytes: self.settings.max_bytes,
+                    actual: Some(byte_len),
+                },
+                "response too large",
+            ));
+        }
+        if let Some(content_type) = &response.content_type {
+            if !self.settings.allows_content_type(content_type) {
+                return Err(FetchError::new(
+                    FailureKind::UnsupportedContentType {
+                        content_type: content_type.clone(),
+                    },
+                    "unsupported content type",
+                ));
+            }
+        }
+
+        sink.emit(EngineEvent::Progress(JobProgress {
+            job_id,
+            stage: Stage::Downloading,
+            bytes: Some(byte_len),
+            tokens: None,
+            content_preview: None,
+        }));
+        Ok(FetchOutput {
+            bytes: response.body.clone(),
+            metadata: FetchMetadata {
+                original_url: url.to_string(),
+                final_url: current.to_string(),
+                redirect_count,
+                content_type: response.content_type.clone(),
+                byte_len,
+                headers: response.headers.clone(),
+            },
+        })
+    }
+}
+
+/// Appends fetched responses to a WARC file as `response` records.
+///
+/// Only the final response body is kept by the fetcher, so each record holds
+/// a reconstructed `200 OK` status line with `Content-Type`,
+/// `Content-Length` and the provenance headers of [`ResponseHeaders`] rather
+/// than the original header block.
+#[derive(Debug)]
+pub struct WarcWriter {
+    path: PathBuf,
+    file: Mutex<File>,
+    records: AtomicU64,
+}
+
+impl WarcWriter {
+    /// Open `path` for appending, creating it if missing.
+    pub fn append_to(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            records: AtomicU64::new(0),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `output` as a response record dated `date_utc` (RFC 3339).
+    pub fn write_response(&self, output: &FetchOutput, date_utc: &str) -> io::Result<()> {
+        let metadata = &output.metadata;
+        let mut http = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n",
+            output.bytes.len()
+        );
+        if let Some(content_type) = &metadata.content_type {
+            http.push_str(&format!("Content-Type: {content_type}\r\n"));
+        }
+        let kept = &metadata.headers;
+        for (name, value) in [
+            ("Last-Modified", &kept.last_modified),
+            ("ETag", &kept.etag),
+            ("Server", &kept.server),
+        ] {
+            if let Some(value) = value {
+                http.push_str(&format!("{name}: {value}\r\n"));
+            }
+        }
+        http.push_str("\r\n");
+        let mut block = http.into_bytes();
+        block.extend_from_slice(&output.bytes);
+
+        let sequence = self.records.fetch_add(1, Ordering::Relaxed);
+        let record_id = record_id(&metadata.final_url, date_utc, sequence);
+        let date = date_utc.strip_suffix("+00:00").map_or_else(
+            || date_utc.to_string(),
+            |without_offset| format!("{without_offset}Z"),
+        );
+        let head = format!(
+            "WARC/1.1\r\nWARC-Type: response\r\nWARC-Record-ID: <urn:uuid:{record_id}>\r\nWARC-Date: {date}\r\nWARC-Target-URI: {uri}\r\nContent-Type: application/http; msgtype=response\r\nContent-Length: {len}\r\n\r\n",
+            uri = metadata.final_url,
+            len = block.len(),
+        );
+
+        let mut record = head.into_bytes();
+        record.extend_from_slice(&block);
+        record.extend_from_slice(b"\r\n\r\n");
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| io::Error::other("WARC writer lock poisoned"))?;
+        file.write_all(&record)?;
+        file.flush()
+    }
+}
+
+/// UUID-shaped id derived from the record's URL, date and sequence number.
+fn record_id(url: &str, date: &str, sequence: u64) -> String {
+    let digest = Sha256::digest(format!("{url}\n{date}\n{sequence}"));
+    let hex: String = digest.iter().take(16).map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn archive_key(url: &str) -> String {
+    match Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+/// Offset of the next non-blank byte at or after `offset`, if any.
+fn skip_blank_lines(bytes: &[u8], offset: usize) -> Option<usize> {
+    (offset..bytes.len()).find(|&i| !matches!(bytes[i], b'\r' | b'\n'))
+}
+
+/// Header name and value pairs, in order.
+type Headers = Vec<(String, String)>;
+
+/// Header lines and content block of the record starting at `start`, and the
+/// offset just past the block.
+fn read_record(bytes: &[u8], start: usize) -> Result<(Headers, &[u8], usize), WarcError> {
+    let malformed = |reason| WarcError::Malformed {
+        offset: start,
+        reason,
+    };
+    if !bytes[start..].starts_with(b"WARC/") {
+        return Err(malformed("missing WARC version line"));
+    }
+    let (head, body_start) =
+        split_head(&bytes[start..]).ok_or_else(|| malformed("unterminated header block"))?;
+    let headers = parse_headers(head);
+    let len: usize = header(&headers, "Content-Length")
+        .and_then(|value| value.trim().parse().ok())
+        .ok_or_else(|| malformed("missing Content-Length"))?;
+    let block_start = start + body_start;
+    let block = bytes
+        .get(block_start..block_start + len)
+        .ok_or_else(|| malformed("truncated content block"))?;
+    Ok((headers, block, block_start + len))
+}
+
+/// Header text before the first blank line, and the offset of what follows.
+fn split_head(bytes: &[u8]) -> Option<(&str, usize)> {
+    let (end, sep) = find(bytes, b"\r\n\r\n")
+        .map(|end| (end, 4))
+        .or_else(|| find(bytes, b"\n\n").map(|end| (end, 2)))?;
+    let head = std::str::from_utf8(&bytes[..end]).ok()?;
+    Some((head, end + sep))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// `Name: value` lines after the first (version or status) line.
+fn parse_headers(head: &str) -> Headers {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn parse_http_response(block: &[u8]) -> Option<ArchivedResponse> {
+    let (head, body_start) = split_head(block)?;
+    let status = head
+        .lines()
+        .next()?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    let headers = parse_headers(head);
+    let mut body = block[body_start..].to_vec();
+    if header(&headers, "Transfer-Encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+        body = dechunk(&body)?;
+    }
+    match header(&headers, "Content-Encoding").map(str::to_ascii_lowercase) {
+        Some(encoding) if encoding == "gzip" || encoding == "x-gzip" => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(body.as_slice())
+                .read_to_end(&mut decoded)
+                .ok()?;
+            body = decoded;
+        }
+        _ => {}
+    }
+    Some(ArchivedResponse {
+        status,
+        content_type: header(&headers, "Content-Type").map(ToOwned::to_owned),
+        location: header(&headers, "Location").map(ToOwned::to_owned),
+        headers: ResponseHeaders {
+            last_modified: header(&headers, "Last-Modified").map(ToOwned::to_owned),
+            etag: header(&headers, "ETag").map(ToOwned::to_owned),
+            server: header(&headers, "Server").map(ToOwned::to_owned),
+        },
+        body,
+    })
+}
+
+/// Decode a `Transfer-Encoding: chunked` body.
+fn dechunk(mut bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = find(bytes, b"\r\n")?;
+        let size_text = std::str::from_utf8(&bytes[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_text.split(';').next()?.trim(), 16).ok()?;
+        bytes = &bytes[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(bytes.get(..size)?);
+        bytes = bytes.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(uri: &str, http: &str) -> String {
+        format!(
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-Target-URI: {uri}\r\nContent-Length: {}\r\n\r\n{http}\r\n\r\n",
+            http.len()
+        )
+    }
+
+    #[test]
+    fn parses_responses_redirects_and_chunked_bodies() {
+        let warc = [
+            "WARC/1.0\r\nWARC-Type: warcinfo\r\nContent-Length: 0\r\n\r\n\r\n\r\n".to_string(),
+            record(
+                "https://ex.com/old",
+                "HTTP/1.1 301 Moved\r\nLocation: /new\r\n\r\n",
+            ),
+            record(
+                "https://ex.com/new",
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nHello\r\n6\r\n world\r\n0\r\n\r\n",
+            ),
+        ]
+        .concat();
+        let archive = WarcArchive::parse(warc.as_bytes()).unwrap();
+
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.page_urls(), vec!["https://ex.com/new".to_string()]);
+        let page = archive.get("https://ex.com/new#top").unwrap();
+        assert_eq!(page.body, b"Hello world");
+        assert_eq!(page.content_type.as_deref(), Some("text/html"));
+        assert_eq!(
+            archive
+                .get("https://ex.com/old")
+                .unwrap()
+                .location
+                .as_deref(),
+            Some("/new")
+        );
+    }
+
+    #[test]
+    fn written_records_read_back() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("harvest.warc");
+        let writer = WarcWriter::append_to(&path).unwrap();
+        let output = FetchOutput {
+            bytes: b"<p>Hi</p>".to_vec(),
+            metadata: FetchMetadata {
+                original_url: "https://ex.com/a".to_string(),
+                final_url: "https://ex.com/a".to_string(),
+                redirect_count: 0,
+                content_type: Some("text/html".to_string()),
+                byte_len: 9,
+                headers: ResponseHeaders {
+                    etag: Some("\"v1\"".to_string()),
+                    ..ResponseHeaders::default()
+                },
+            },
+        };
+        writer
+            .write_response(&output, "2024-01-01T00:00:00+00:00")
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.contains("WARC-Date: 2024-01-01T00:00:00Z\r\n"));
+        let archive = WarcArchive::open(&path).unwrap();
+        assert_eq!(archive.get("https://ex.com/a").unwrap().body, b"<p>Hi</p>");
+    }
+}
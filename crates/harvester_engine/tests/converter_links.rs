@@ -17,6 +17,7 @@ fn anchor_links_are_extracted_and_text_preserved() {
             url: "https://example.com/path".to_string(),
             text: Some("world".to_string()),
             kind: LinkKind::Hyperlink,
+            internal: false,
         }]
     );
 }
@@ -35,10 +36,19 @@ fn image_src_urls_are_collected_and_markdown_drops_images() {
             url: "https://news.example.com/images/pic.jpg".to_string(),
             text: None,
             kind: LinkKind::Image,
+            internal: true,
         }]
     );
 }
 
+#[test]
+fn image_alt_text_becomes_the_link_text() {
+    let html = r#"<p><img src="/flow.png" alt=" Request flow "></p>"#;
+    let output = convert(html, Some("https://docs.example.com/"));
+
+    assert_eq!(output.links[0].text.as_deref(), Some("Request flow"));
+}
+
 #[test]
 fn mailto_links_are_classified_as_email() {
     let html = r#"<p><a href="mailto:foo@example.com">Ping me</a></p>"#;
@@ -49,6 +59,30 @@ fn mailto_links_are_classified_as_email() {
     assert_eq!(output.links[0].url, "mailto:foo@example.com");
 }
 
+#[test]
+fn links_on_the_pages_registrable_domain_are_internal() {
+    let html = r#"<a href="/about">About</a>
+        <a href="https://docs.example.co.uk/guide">Docs</a>
+        <a href="https://other.co.uk/">Other</a>
+        <a href="mailto:team@example.co.uk">Mail</a>"#;
+    let output = convert(html, Some("https://www.example.co.uk/blog/"));
+
+    let internal: Vec<_> = output
+        .links
+        .iter()
+        .map(|link| (link.text.as_deref().unwrap(), link.internal))
+        .collect();
+    assert_eq!(
+        internal,
+        vec![
+            ("About", true),
+            ("Docs", true),
+            ("Other", false),
+            ("Mail", false)
+        ]
+    );
+}
+
 #[test]
 fn relative_urls_resolve_with_base_and_fragments_are_skipped() {
     let html = "<a href=\"./article\">Article</a><a href=\"#top\">Skip</a>";
@@ -82,3 +116,16 @@ fn conversion_is_deterministic() {
 
     assert_eq!(first, second);
 }
+
+#[test]
+fn nested_blockquotes_and_definition_lists_keep_structure() {
+    let html =
+        "<blockquote><p>Outer</p><blockquote><p>Inner</p></blockquote><p>Back</p></blockquote>\
+        <dl><dt>Term </dt><dd>First meaning</dd><dd>Second meaning</dd></dl>";
+    let output = convert(html, None);
+
+    assert_eq!(
+        output.markdown,
+        "> Outer\n> > Inner\n>\n> Back\n\n**Term**\n: First meaning\n: Second meaning"
+    );
+}
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use harvester_engine::decode_html;
+use pretty_assertions::assert_eq;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/encodings")
+        .join(name);
+    std::fs::read(&path).unwrap_or_else(|err| panic!("{}: {err}", path.display()))
+}
+
+/// `(fixture, Content-Type header, expected encoding, text in the body)`.
+const CORPUS: &[(&str, &str, &str, &str)] = &[
+    (
+        "windows-1252.html",
+        "text/html",
+        "windows-1252",
+        "Crème brûlée, s’il vous plaît",
+    ),
+    (
+        "iso-8859-2.html",
+        "text/html",
+        "ISO-8859-2",
+        "Zażółć gęślą jaźń",
+    ),
+    (
+        "koi8-r.html",
+        "text/html",
+        "KOI8-R",
+        "Съешь же ещё этих мягких французских булок",
+    ),
+    (
+        "shift_jis.html",
+        "text/html",
+        "Shift_JIS",
+        "日本語のページです",
+    ),
+    (
+        "euc-kr-header.html",
+        "text/html; charset=euc-kr",
+        "EUC-KR",
+        "한국어 페이지입니다",
+    ),
+    ("gbk.html", "text/html", "GBK", "这是一个中文网页"),
+    (
+        "utf-16le-meta.html",
+        "text/html",
+        "UTF-16LE",
+        "Grüße aus dem Intranet",
+    ),
+    (
+        "utf-16be-sniffed.html",
+        "text/html",
+        "UTF-16BE",
+        "Grüße aus dem Intranet",
+    ),
+    (
+        "utf-16be-header.html",
+        "text/html; charset=utf-16",
+        "UTF-16BE",
+        "Grüße aus dem Intranet",
+    ),
+    (
+        "utf-16le-bom.html",
+        "text/html",
+        "UTF-16LE",
+        "Grüße aus dem Intranet",
+    ),
+    ("utf-8-undeclared.html", "text/html", "UTF-8", "naïve café"),
+];
+
+#[test]
+fn every_fixture_in_the_corpus_decodes_to_its_text() {
+    for (name, content_type, encoding, text) in CORPUS {
+        let decoded = decode_html(&fixture(name), Some(content_type))
+            .unwrap_or_else(|err| panic!("{name}: {err}"));
+        assert_eq!(decoded.encoding_label, *encoding, "{name}");
+        assert!(decoded.html.contains(text), "{name}: {}", decoded.html);
+        assert!(
+            decoded.html.contains("<title>Encoding test</title>"),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn explicit_utf16_byte_order_in_the_header_is_kept() {
+    let decoded = decode_html(
+        &fixture("utf-16le-meta.html"),
+        Some("text/html; charset=utf-16le"),
+    )
+    .unwrap();
+    assert_eq!(decoded.encoding_label, "UTF-16LE");
+}
+
+#[test]
+fn utf16_declared_in_an_ascii_page_means_utf8() {
+    let decoded = decode_html("<meta charset=\"utf-16\"><p>naïve</p>".as_bytes(), None).unwrap();
+    assert_eq!(decoded.encoding_label, "UTF-8");
+    assert!(decoded.html.contains("naïve"));
+}
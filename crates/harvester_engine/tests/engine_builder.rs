@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use harvester_engine::{
+    ChunkOptions, EngineBuildError, EngineBuilder, ExportFormat, FetchSettings,
+};
+
+fn build_error(builder: EngineBuilder) -> EngineBuildError {
+    match builder.build_config() {
+        Ok(_) => panic!("expected a validation error"),
+        Err(err) => err,
+    }
+}
+
+#[test]
+fn builder_accepts_defaults_and_creates_output_dir() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let output_dir = temp.path().join("out");
+
+    let config = EngineBuilder::new(output_dir.clone())
+        .build_config()
+        .expect("defaults are valid");
+
+    assert_eq!(config.output_dir, output_dir);
+    assert!(output_dir.is_dir());
+    // The writability probe cleans up after itself.
+    assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 0);
+}
+
+#[test]
+fn builder_rejects_zero_timeouts() {
+    let temp = tempfile::TempDir::new().unwrap();
+
+    let err =
+        build_error(EngineBuilder::new(temp.path().to_path_buf()).convert_timeout(Duration::ZERO));
+    assert!(matches!(
+        err,
+        EngineBuildError::ZeroDuration {
+            name: "convert_timeout"
+        }
+    ));
+
+    let err = build_error(
+        EngineBuilder::new(temp.path().to_path_buf()).fetch_settings(FetchSettings {
+            request_timeout: Duration::ZERO,
+            ..FetchSettings::default()
+        }),
+    );
+    assert!(matches!(err, EngineBuildError::ZeroDuration { .. }));
+}
+
+#[test]
+fn builder_rejects_zero_queue_capacity() {
+    let temp = tempfile::TempDir::new().unwrap();
+
+    let err = build_error(EngineBuilder::new(temp.path().to_path_buf()).max_queued_jobs(0));
+
+    assert!(matches!(
+        err,
+        EngineBuildError::ZeroLimit {
+            name: "max_queued_jobs"
+        }
+    ));
+
+    let err =
+        build_error(EngineBuilder::new(temp.path().to_path_buf()).max_concurrent_per_domain(0));
+    assert!(matches!(
+        err,
+        EngineBuildError::ZeroLimit {
+            name: "max_concurrent_per_domain"
+        }
+    ));
+}
+
+#[test]
+fn builder_rejects_output_path_that_is_a_file() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let file_path = temp.path().join("not_a_dir");
+    std::fs::write(&file_path, "x").unwrap();
+
+    let err = build_error(EngineBuilder::new(file_path));
+
+    assert!(matches!(err, EngineBuildError::OutputDir(_)));
+}
+
+#[test]
+fn builder_rejects_chunk_overlap_not_smaller_than_chunk() {
+    let temp = tempfile::TempDir::new().unwrap();
+
+    let err = build_error(EngineBuilder::new(temp.path().to_path_buf()).export_format(
+        ExportFormat::Chunks(ChunkOptions {
+            max_tokens: 100,
+            overlap_tokens: 100,
+        }),
+    ));
+    assert!(matches!(err, EngineBuildError::ChunkOverlap { .. }));
+}
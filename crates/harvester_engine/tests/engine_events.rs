@@ -0,0 +1,619 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use harvester_engine::{
+    fixture_filename, ApproxTokenCounter, AsyncEngineHandle, ChunkOptions, EngineConfig,
+    EngineEvent, EngineHandle, ExportFormat, FailureKind, JobOutcome, Stage, WarcArchive,
+    WhitespaceTokenCounter,
+};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn recv_blocks_until_export_event_is_delivered() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let engine = EngineHandle::new(EngineConfig::default_with_output(temp.path().to_path_buf()));
+
+    engine.request_export();
+
+    match engine.recv() {
+        Some(EngineEvent::ExportCompleted { summary, .. }) => assert_eq!(summary.doc_count, 0),
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[test]
+fn event_stream_ends_once_every_handle_is_dropped() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let engine = EngineHandle::new(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    let events = engine.events();
+
+    engine.request_export();
+    drop(engine);
+
+    assert!(matches!(
+        events.recv(),
+        Some(EngineEvent::ExportCompleted { .. })
+    ));
+    assert!(events.recv().is_none());
+}
+
+#[tokio::test]
+async fn async_handle_delivers_events_on_callers_runtime() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+
+    engine.request_export();
+
+    match engine.next_event().await {
+        Some(EngineEvent::ExportCompleted { summary, .. }) => assert_eq!(summary.doc_count, 0),
+        other => panic!("unexpected event: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn token_budget_caps_the_size_of_exported_chunks() {
+    let temp = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        temp.path().join("notes.md"),
+        "---\nurl: https://notes\ntitle: Notes\ntoken_count: 8\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\none two three four five six seven eight\n",
+    )
+    .unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.export_format = ExportFormat::Chunks(ChunkOptions::default());
+    let mut engine = AsyncEngineHandle::spawn(config);
+
+    engine.set_token_budget(3);
+    engine.request_export();
+    let summary = match engine.next_event().await {
+        Some(EngineEvent::ExportCompleted { summary, .. }) => summary,
+        other => panic!("unexpected event: {other:?}"),
+    };
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+    let tokens: Vec<u64> = export
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["tokens"]
+                .as_u64()
+                .unwrap()
+        })
+        .collect();
+    assert!(tokens.len() >= 3, "{export}");
+    assert!(tokens.iter().all(|&count| count <= 3), "{export}");
+}
+
+#[tokio::test]
+async fn rate_limited_job_is_retried_after_capped_delay() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/busy"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/busy"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><article><p>finally</p></article></body></html>",
+            "text/html",
+        ))
+        .with_priority(2)
+        .mount(&server)
+        .await;
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.max_retry_after = Duration::from_millis(50);
+    let mut engine = AsyncEngineHandle::spawn(config);
+    engine.enqueue(1, format!("{}/busy", server.uri()));
+
+    let mut retry_delay = None;
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+            .await
+            .expect("event before timeout");
+        match event {
+            Some(EngineEvent::JobRetryScheduled { job_id: 1, delay }) => retry_delay = Some(delay),
+            Some(EngineEvent::JobCompleted { job_id: 1, result }) => {
+                assert!(result.is_ok(), "unexpected result: {result:?}");
+                break;
+            }
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    }
+    assert_eq!(retry_delay, Some(Duration::from_millis(50)));
+}
+
+#[tokio::test]
+async fn job_past_its_deadline_fails_at_the_stage_it_reached() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/slow"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html><body><p>late</p></body></html>", "text/html")
+                .set_delay(Duration::from_secs(5)),
+        )
+        .mount(&server)
+        .await;
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.job_deadline = Some(Duration::from_millis(100));
+    let mut engine = AsyncEngineHandle::spawn(config);
+    engine.enqueue(1, format!("{}/slow", server.uri()));
+
+    assert_eq!(
+        next_completion(&mut engine).await,
+        Err(FailureKind::ProcessingTimeout {
+            stage: Stage::Downloading
+        })
+    );
+}
+
+#[tokio::test]
+async fn repeated_site_chrome_is_stripped_from_later_pages() {
+    let server = MockServer::start().await;
+    for page in 1..=3 {
+        Mock::given(method("GET"))
+            .and(path(format!("/p{page}")))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                format!(
+                    "<html><body><p>Example Site</p><p>Story number {page}</p><p>Copyright Example</p></body></html>"
+                ),
+                "text/html",
+            ))
+            .mount(&server)
+            .await;
+    }
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.strip_site_chrome = true;
+    let mut engine = AsyncEngineHandle::spawn(config);
+
+    let mut stripped = Vec::new();
+    for page in 1..=3 {
+        engine.enqueue(page, format!("{}/p{page}", server.uri()));
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+                .await
+                .expect("event before timeout");
+            match event {
+                Some(EngineEvent::JobCompleted { result, .. }) => {
+                    let outcome = result.expect("job succeeds");
+                    stripped.push(outcome.stripped_chrome.expect("stripping enabled").blocks);
+                    if page == 3 {
+                        let document =
+                            std::fs::read_to_string(outcome.output_path.unwrap()).unwrap();
+                        assert!(document.contains("Story number 3"));
+                        assert!(!document.contains("Copyright Example"), "{document}");
+                        assert!(document.contains("stripped_chrome_bytes: "));
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("engine stopped"),
+            }
+        }
+    }
+    assert_eq!(stripped, vec![0, 0, 2]);
+}
+
+#[tokio::test]
+async fn recorded_warc_replays_as_an_offline_harvest() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/story"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><head><title>Story</title></head><body><p>Archived words</p></body></html>",
+            "text/html",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let url = format!("{}/story", server.uri());
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let warc_path = temp.path().join("harvest.warc");
+    let mut config = EngineConfig::default_with_output(temp.path().join("live"));
+    config.warc_output = Some(warc_path.clone());
+    let mut live = AsyncEngineHandle::spawn(config);
+    live.enqueue(1, url.clone());
+    next_completion(&mut live)
+        .await
+        .expect("live fetch succeeds");
+
+    let archive = WarcArchive::open(&warc_path).unwrap();
+    assert_eq!(archive.page_urls(), vec![url.clone()]);
+    let mut config = EngineConfig::default_with_output(temp.path().join("offline"));
+    config.offline_archive = Some(Arc::new(archive));
+    let mut offline = AsyncEngineHandle::spawn(config);
+    offline.enqueue(2, url);
+    let outcome = next_completion(&mut offline)
+        .await
+        .expect("offline harvest succeeds");
+    let document = std::fs::read_to_string(outcome.output_path.unwrap()).unwrap();
+    assert!(document.contains("Archived words"), "{document}");
+
+    offline.enqueue(3, format!("{}/missing", server.uri()));
+    assert_eq!(
+        next_completion(&mut offline).await.unwrap_err(),
+        FailureKind::NotArchived
+    );
+}
+
+#[tokio::test]
+async fn fixture_directory_serves_the_full_pipeline_offline() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let fixtures = temp.path().join("fixtures");
+    std::fs::create_dir(&fixtures).unwrap();
+    let url = "https://offline.example/guide";
+    std::fs::write(
+        fixtures.join(fixture_filename(url)),
+        "<html><head><title>Guide</title></head><body><p>Fixture words</p></body></html>",
+    )
+    .unwrap();
+
+    let mut config = EngineConfig::default_with_output(temp.path().join("out"));
+    config.fixture_dir = Some(fixtures);
+    let mut engine = AsyncEngineHandle::spawn(config);
+    engine.enqueue(1, url.to_string());
+    let outcome = next_completion(&mut engine)
+        .await
+        .expect("fixture harvest succeeds");
+    let document = std::fs::read_to_string(outcome.output_path.unwrap()).unwrap();
+    assert!(document.contains("Fixture words"), "{document}");
+    assert!(document.contains("url: https://offline.example/guide"));
+
+    engine.enqueue(2, "https://offline.example/missing".to_string());
+    assert_eq!(
+        next_completion(&mut engine).await.unwrap_err(),
+        FailureKind::NotArchived
+    );
+}
+
+#[tokio::test]
+async fn repeated_harvests_are_served_from_the_response_cache() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/cached"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html><body><p>Fetched once</p></body></html>", "text/html"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.response_cache_ttl = Some(Duration::from_secs(3600));
+    let mut engine = AsyncEngineHandle::spawn(config);
+    let url = format!("{}/cached", server.uri());
+
+    engine.enqueue(1, url.clone());
+    let first = next_completion(&mut engine).await.expect("fetch succeeds");
+    assert!(!first.from_cache);
+
+    engine.enqueue(2, url);
+    let second = next_completion(&mut engine)
+        .await
+        .expect("cache hit succeeds");
+    assert!(second.from_cache);
+    assert_eq!(second.output_path, first.output_path);
+}
+
+#[tokio::test]
+async fn provenance_response_headers_are_recorded_in_frontmatter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/tagged"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("ETag", "\"v7\"")
+                .insert_header("Last-Modified", "Tue, 02 Jan 2024 10:00:00 GMT")
+                .insert_header("X-Request-Id", "ignored")
+                .set_body_raw("<html><body><p>Tagged page</p></body></html>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    engine.enqueue(1, format!("{}/tagged", server.uri()));
+
+    let outcome = next_completion(&mut engine).await.expect("job succeeds");
+    let document = std::fs::read_to_string(outcome.output_path.unwrap()).unwrap();
+    assert!(
+        document.contains(
+            "response_headers:\n  content-type: text/html\n  last-modified: Tue, 02 Jan 2024 10:00:00 GMT\n  etag: \"v7\"\n"
+        ),
+        "{document}"
+    );
+    assert!(!document.contains("ignored"), "{document}");
+}
+
+#[tokio::test]
+async fn identical_document_is_not_rewritten() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/stable"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw("<html><body><p>Same words</p></body></html>", "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.fetched_utc = Arc::new(|| "2024-01-01T00:00:00Z".to_string());
+    let mut engine = AsyncEngineHandle::spawn(config);
+    let url = format!("{}/stable", server.uri());
+
+    engine.enqueue(1, url.clone());
+    let first = next_completion(&mut engine).await.expect("job succeeds");
+    assert!(!first.unchanged);
+    let path = first.output_path.unwrap();
+    let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    engine.enqueue(2, url);
+    let second = next_completion(&mut engine).await.expect("job succeeds");
+    assert!(second.unchanged);
+    assert_eq!(second.output_path.as_ref(), Some(&path));
+    assert_eq!(
+        std::fs::metadata(&path).unwrap().modified().unwrap(),
+        written
+    );
+}
+
+#[tokio::test]
+async fn same_content_under_another_url_is_reported_not_written() {
+    let server = MockServer::start().await;
+    for mirror in ["/original", "/mirror"] {
+        Mock::given(method("GET"))
+            .and(path(mirror))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "<html><body><p>Syndicated words</p></body></html>",
+                "text/html",
+            ))
+            .mount(&server)
+            .await;
+    }
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+
+    engine.enqueue(1, format!("{}/original", server.uri()));
+    let first = next_completion(&mut engine).await.expect("job succeeds");
+    assert_eq!(first.duplicate_of, None);
+    assert!(first.output_path.is_some());
+
+    engine.enqueue(2, format!("{}/mirror", server.uri()));
+    let second = next_completion(&mut engine).await.expect("job succeeds");
+    assert_eq!(second.duplicate_of, Some(1));
+    assert_eq!(second.output_path, None);
+    assert!(second.tokens.is_some());
+}
+
+#[tokio::test]
+async fn every_stage_is_reported_in_order() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/staged"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><p>Stage by stage</p></body></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    engine.enqueue(1, format!("{}/staged", server.uri()));
+
+    let mut stages = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+            .await
+            .expect("event before timeout");
+        match event {
+            Some(EngineEvent::Progress(progress)) => {
+                if stages.last() != Some(&progress.stage) {
+                    stages.push(progress.stage);
+                }
+            }
+            Some(EngineEvent::JobCompleted { result, .. }) => {
+                result.expect("job succeeds");
+                break;
+            }
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    }
+    assert_eq!(
+        stages,
+        vec![
+            Stage::Downloading,
+            Stage::Sanitizing,
+            Stage::Converting,
+            Stage::Tokenizing,
+            Stage::Writing,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn export_manifest_carries_the_session_totals() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><p>Counted words</p></body></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    engine.enqueue(1, format!("{}/page", server.uri()));
+    next_completion(&mut engine).await.expect("job succeeds");
+    engine.enqueue(2, format!("{}/missing", server.uri()));
+    next_completion(&mut engine)
+        .await
+        .expect_err("missing page fails");
+
+    engine.request_export();
+    let summary = loop {
+        match engine.next_event().await {
+            Some(EngineEvent::ExportCompleted { summary, .. }) => break summary,
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    };
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap())
+            .unwrap();
+    let session = &manifest["session"];
+    assert_eq!(session["pages"], 2, "{manifest}");
+    assert_eq!(session["succeeded"], 1);
+    assert_eq!(session["failures"]["http_404"], 1);
+    assert!(session["total_bytes"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn stopping_reports_the_drain_and_resume_accepts_new_jobs() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><p>After the pause</p></body></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    engine.enqueue(1, format!("{}/page", server.uri()));
+    engine.stop(false);
+
+    let mut completed = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+            .await
+            .expect("event before timeout");
+        match event {
+            Some(EngineEvent::JobCompleted { job_id, .. }) => completed.push(job_id),
+            Some(EngineEvent::AllJobsDrained) => break,
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    }
+    assert_eq!(completed, vec![1], "drain follows every completion");
+
+    engine.resume();
+    engine.enqueue(2, format!("{}/page", server.uri()));
+    next_completion(&mut engine)
+        .await
+        .expect("resumed engine harvests again");
+}
+
+#[tokio::test]
+async fn reconfigured_engine_writes_later_jobs_to_the_new_directory() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><p>Written twice</p></body></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+    let first = tempfile::TempDir::new().unwrap();
+    let second = tempfile::TempDir::new().unwrap();
+    let mut engine = AsyncEngineHandle::spawn(EngineConfig::default_with_output(
+        first.path().to_path_buf(),
+    ));
+    engine.enqueue(1, format!("{}/page", server.uri()));
+    next_completion(&mut engine).await.expect("first harvest");
+
+    engine.reconfigure(EngineConfig::default_with_output(
+        second.path().to_path_buf(),
+    ));
+    engine.enqueue(2, format!("{}/page", server.uri()));
+    next_completion(&mut engine)
+        .await
+        .expect("reconfigured engine keeps harvesting");
+
+    let markdown_files = |dir: &std::path::Path| {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == "md")
+            })
+            .count()
+    };
+    assert_eq!(markdown_files(first.path()), 1);
+    assert_eq!(markdown_files(second.path()), 1);
+}
+
+async fn next_completion(engine: &mut AsyncEngineHandle) -> Result<JobOutcome, FailureKind> {
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+            .await
+            .expect("event before timeout");
+        match event {
+            Some(EngineEvent::JobCompleted { result, .. }) => return result,
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn estimated_token_counts_are_backfilled_with_the_exact_counter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/long"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><body><p>Internationalization considerations everywhere</p></body></html>",
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.token_counter = Arc::new(ApproxTokenCounter);
+    config.backfill_token_counter = Some(Arc::new(WhitespaceTokenCounter));
+    let mut engine = AsyncEngineHandle::spawn(config);
+    engine.enqueue(1, format!("{}/long", server.uri()));
+
+    let outcome = next_completion(&mut engine).await.expect("job succeeds");
+    assert_ne!(outcome.tokens, Some(3));
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+            .await
+            .expect("event before timeout");
+        match event {
+            Some(EngineEvent::TokenCountRefined { job_id, tokens }) => {
+                assert_eq!((job_id, tokens), (1, 3));
+                break;
+            }
+            Some(_) => {}
+            None => panic!("engine stopped"),
+        }
+    }
+    let document = std::fs::read_to_string(outcome.output_path.unwrap()).unwrap();
+    assert!(document.contains("\ntoken_count: 3\n"), "{document}");
+    assert!(document.ends_with("Internationalization considerations everywhere"));
+}
@@ -1,5 +1,6 @@
 use harvester_engine::{
-    decode_html, Converter, Extractor, Html2MdConverter, ReadabilityLikeExtractor,
+    decode_html, decode_html_with, Converter, DecodeError, Extractor, Html2MdConverter,
+    ReadabilityLikeExtractor,
 };
 use pretty_assertions::assert_eq;
 
@@ -22,6 +23,41 @@ fn decode_handles_utf8_bom() {
     assert_eq!(decoded.encoding_label, "UTF-8");
 }
 
+#[test]
+fn decode_honours_meta_charset_before_guessing() {
+    let bytes = b"<html><head><meta charset=\"windows-1251\"></head><body>\xcf\xf0\xe8\xe2\xe5\xf2</body></html>";
+    let decoded = decode_html(bytes, Some("text/html")).unwrap();
+    assert_eq!(decoded.encoding_label, "windows-1251");
+    assert!(decoded.html.contains("Привет"), "{}", decoded.html);
+}
+
+#[test]
+fn decode_honours_http_equiv_content_type() {
+    let bytes = b"<HTML><HEAD><META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=ISO-8859-2\"></HEAD><BODY>\xb3\xf3d\xbf</BODY></HTML>";
+    let decoded = decode_html(bytes, None).unwrap();
+    assert_eq!(decoded.encoding_label, "ISO-8859-2");
+    assert!(decoded.html.contains("łódż"), "{}", decoded.html);
+}
+
+#[test]
+fn lossy_decode_replaces_stray_bytes_instead_of_failing() {
+    let bytes = b"<p>caf\xe9 au lait</p>";
+    assert!(decode_html(bytes, Some("text/html; charset=utf-8")).is_err());
+
+    let decoded = decode_html_with(bytes, Some("text/html; charset=utf-8"), true).unwrap();
+    assert!(decoded.lossy);
+    assert_eq!(decoded.html, "<p>caf\u{FFFD} au lait</p>");
+}
+
+#[test]
+fn decode_rejects_compressed_bytes_instead_of_producing_garbage() {
+    let gzip = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x00\x03\xcb\x48\xcd\xc9\xc9\x07\x00";
+    assert_eq!(
+        decode_html_with(gzip, Some("text/html; charset=utf-8"), true),
+        Err(DecodeError::BinaryContent { detected: "gzip" })
+    );
+}
+
 #[test]
 fn extractor_prefers_article_then_body() {
     let html = r#"
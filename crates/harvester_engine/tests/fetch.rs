@@ -1,11 +1,13 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use harvester_engine::{
-    EngineEvent, FailureKind, FetchSettings, Fetcher, JobProgress, ProgressSink, ReqwestFetcher,
-    Stage,
+    CookieJar, EngineEvent, FailureKind, FetchSettings, Fetcher, JobProgress, ProgressSink,
+    RedirectPolicy, ReqwestFetcher, Stage,
 };
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 #[derive(Default)]
@@ -141,3 +143,282 @@ async fn fetcher_rejects_too_large_response() {
         }
     );
 }
+
+#[tokio::test]
+async fn fetcher_follows_and_counts_redirects_up_to_limit() {
+    let server = MockServer::start().await;
+    for (from, to) in [("/a", "/b"), ("/b", "/c")] {
+        Mock::given(method("GET"))
+            .and(path(from))
+            .respond_with(ResponseTemplate::new(302).insert_header("Location", to))
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/c"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("<html>c</html>", "text/html"))
+        .mount(&server)
+        .await;
+
+    let fetcher = ReqwestFetcher::new(FetchSettings::default());
+    let sink = TestSink::new();
+    let output = fetcher
+        .fetch(1, &format!("{}/a", server.uri()), &sink)
+        .await
+        .expect("fetch ok");
+    assert_eq!(output.metadata.redirect_count, 2);
+    assert_eq!(output.metadata.final_url, format!("{}/c", server.uri()));
+
+    let strict = ReqwestFetcher::new(FetchSettings {
+        redirect_limit: 1,
+        ..FetchSettings::default()
+    });
+    let err = strict
+        .fetch(2, &format!("{}/a", server.uri()), &sink)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, FailureKind::RedirectLimitExceeded);
+}
+
+#[tokio::test]
+async fn head_preflight_rejects_unsupported_content_type_without_get() {
+    let server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+        .and(path("/doc.pdf"))
+        .respond_with(ResponseTemplate::new(200).insert_header("Content-Type", "application/pdf"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/doc.pdf"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("%PDF"))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let settings = FetchSettings {
+        head_preflight: true,
+        ..FetchSettings::default()
+    };
+    let fetcher = ReqwestFetcher::new(settings);
+    let sink = TestSink::new();
+    let url = format!("{}/doc.pdf", server.uri());
+
+    let err = fetcher.fetch(5, &url, &sink).await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        FailureKind::UnsupportedContentType { ref content_type } if content_type == "application/pdf"
+    ));
+    assert!(sink.take().is_empty());
+}
+
+#[tokio::test]
+async fn head_preflight_falls_back_to_get_when_head_is_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("HEAD"))
+        .and(path("/page"))
+        .respond_with(ResponseTemplate::new(405))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", "text/html")
+                .set_body_string("<html>ok</html>"),
+        )
+        .mount(&server)
+        .await;
+
+    let settings = FetchSettings {
+        head_preflight: true,
+        ..FetchSettings::default()
+    };
+    let fetcher = ReqwestFetcher::new(settings);
+    let sink = TestSink::new();
+    let url = format!("{}/page", server.uri());
+
+    let output = fetcher.fetch(6, &url, &sink).await.unwrap();
+    assert_eq!(output.bytes, b"<html>ok</html>");
+}
+
+/// Read one request head from `stream` and return it lowercased.
+fn read_request_head(stream: &mut TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+        head.push(byte[0]);
+    }
+    String::from_utf8_lossy(&head).to_ascii_lowercase()
+}
+
+#[tokio::test]
+async fn fetcher_resumes_interrupted_body_with_range_request() {
+    let body = b"<html>0123456789</html>";
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || {
+        // First connection: advertise the full length, then drop mid-body.
+        let (mut stream, _) = listener.accept().unwrap();
+        read_request_head(&mut stream);
+        let head = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nAccept-Ranges: bytes\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&body[..10]).unwrap();
+        drop(stream);
+
+        // Second connection: serve the remainder as partial content.
+        let (mut stream, _) = listener.accept().unwrap();
+        let request = read_request_head(&mut stream);
+        let head = format!(
+            "HTTP/1.1 206 Partial Content\r\nContent-Type: text/html\r\nContent-Range: bytes 10-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len() - 1,
+            body.len(),
+            body.len() - 10
+        );
+        stream.write_all(head.as_bytes()).unwrap();
+        stream.write_all(&body[10..]).unwrap();
+        request
+    });
+
+    let fetcher = ReqwestFetcher::new(FetchSettings::default());
+    let sink = TestSink::new();
+    let url = format!("http://{addr}/page");
+
+    let output = fetcher.fetch(7, &url, &sink).await.unwrap();
+    assert_eq!(output.bytes, body);
+    assert!(server.join().unwrap().contains("range: bytes=10-"));
+}
+
+#[tokio::test]
+async fn fetcher_reports_retry_after_on_rate_limit() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/busy"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/down"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let fetcher = ReqwestFetcher::new(FetchSettings::default());
+    let sink = TestSink::new();
+
+    let err = fetcher
+        .fetch(8, &format!("{}/busy", server.uri()), &sink)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.kind,
+        FailureKind::RateLimited {
+            retry_after_secs: 30
+        }
+    );
+
+    // Without Retry-After a 503 stays a plain HTTP failure.
+    let err = fetcher
+        .fetch(9, &format!("{}/down", server.uri()), &sink)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, FailureKind::HttpStatus(503));
+}
+
+#[tokio::test]
+async fn same_domain_policy_rejects_cross_domain_redirect() {
+    let target = MockServer::start().await;
+    let origin = MockServer::start().await;
+    // Both servers listen on 127.0.0.1; address the target as "localhost" so the hosts differ.
+    let target_url = target.uri().replace("127.0.0.1", "localhost");
+    Mock::given(method("GET"))
+        .and(path("/short"))
+        .respond_with(
+            ResponseTemplate::new(302).insert_header("Location", format!("{target_url}/farm")),
+        )
+        .mount(&origin)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/local"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/page"))
+        .mount(&origin)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", "text/html")
+                .set_body_string("<html>ok</html>"),
+        )
+        .mount(&origin)
+        .await;
+
+    let settings = FetchSettings {
+        redirect_policy: RedirectPolicy::SameDomain,
+        ..FetchSettings::default()
+    };
+    let fetcher = ReqwestFetcher::new(settings);
+    let sink = TestSink::new();
+
+    let err = fetcher
+        .fetch(10, &format!("{}/short", origin.uri()), &sink)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.kind,
+        FailureKind::CrossDomainRedirect {
+            target_host: "localhost".to_string()
+        }
+    );
+    assert!(target.received_requests().await.unwrap().is_empty());
+
+    let output = fetcher
+        .fetch(11, &format!("{}/local", origin.uri()), &sink)
+        .await
+        .unwrap();
+    assert_eq!(output.metadata.redirect_count, 1);
+
+    let settings = FetchSettings {
+        redirect_policy: RedirectPolicy::None,
+        ..FetchSettings::default()
+    };
+    let err = ReqwestFetcher::new(settings)
+        .fetch(12, &format!("{}/local", origin.uri()), &sink)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, FailureKind::RedirectLimitExceeded);
+}
+
+#[tokio::test]
+async fn fetcher_sends_imported_cookies_to_matching_host() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/account"))
+        .and(header("cookie", "session=abc"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("Content-Type", "text/html")
+                .set_body_string("<html>logged in</html>"),
+        )
+        .mount(&server)
+        .await;
+
+    let cookies = CookieJar::parse_netscape("127.0.0.1\tFALSE\t/\tFALSE\t0\tsession\tabc\n")
+        .unwrap()
+        .for_domain("127.0.0.1");
+    let settings = FetchSettings {
+        cookies,
+        ..FetchSettings::default()
+    };
+    let fetcher = ReqwestFetcher::new(settings);
+    let sink = TestSink::new();
+
+    let output = fetcher
+        .fetch(13, &format!("{}/account", server.uri()), &sink)
+        .await
+        .unwrap();
+    assert_eq!(output.bytes, b"<html>logged in</html>");
+}
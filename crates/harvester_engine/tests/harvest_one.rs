@@ -0,0 +1,250 @@
+use std::sync::Arc;
+
+use harvester_engine::{harvest_one, EngineConfig, FailureKind, LeadSentencesSummarizer};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn config() -> EngineConfig {
+    let mut config = EngineConfig::default_with_output(std::env::temp_dir());
+    config.fetched_utc = Arc::new(|| "2024-01-01T00:00:00Z".to_string());
+    config
+}
+
+#[tokio::test]
+async fn harvest_one_returns_markdown_document_with_frontmatter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/article"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Hello</title></head><body><article><p>Some words <a href="/next">here</a></p></article></body></html>"#,
+            "text/html; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    let url = format!("{}/article", server.uri());
+
+    let doc = harvest_one(&url, &config()).await.expect("harvest ok");
+
+    assert_eq!(doc.title.as_deref(), Some("Hello"));
+    assert!(doc.filename.starts_with("Hello--"));
+    assert!(doc.document.starts_with("---\nurl: "));
+    assert!(doc.document.contains("fetched_utc: 2024-01-01T00:00:00Z"));
+    assert!(doc.document.contains("extraction: article\n"));
+    assert!(doc.document.contains("Some words here"));
+    assert_eq!(doc.token_count, 3);
+    assert_eq!(doc.reading.word_count, 3);
+    assert!(doc.document.contains("word_count: 3\nreading_minutes: 1\n"));
+    assert_eq!(doc.links.len(), 1);
+    assert_eq!(doc.links[0].url, format!("{}/next", server.uri()));
+}
+
+#[tokio::test]
+async fn harvest_one_writes_the_summarizer_output_into_frontmatter() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/guide"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Guide</title></head><body><article><h1>Guide</h1><p>First point. Second point. Third point.</p></article></body></html>"#,
+            "text/html; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    let url = format!("{}/guide", server.uri());
+
+    let doc = harvest_one(&url, &config()).await.expect("harvest ok");
+    assert!(!doc.document.contains("summary:"));
+    assert!(doc.document.contains("keywords: point\n"));
+
+    let mut summarizing = config();
+    summarizing.summarizer = Arc::new(LeadSentencesSummarizer { max_sentences: 2 });
+    let doc = harvest_one(&url, &summarizing).await.expect("harvest ok");
+    assert!(doc
+        .document
+        .contains("summary: First point. Second point.\n"));
+}
+
+#[tokio::test]
+async fn harvest_one_keeps_only_the_sections_named_by_the_fragment() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/docs"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Docs</title></head><body><article><h2>Install</h2><p>Run the installer.</p><h2>API Reference</h2><p>Call the api.</p></article></body></html>"#,
+            "text/html; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    let whole = harvest_one(&format!("{}/docs", server.uri()), &config())
+        .await
+        .expect("harvest ok");
+    let url = format!("{}/docs#api-reference", server.uri());
+
+    let doc = harvest_one(&url, &config()).await.expect("harvest ok");
+
+    assert!(doc.document.contains("Call the api."));
+    assert!(!doc.document.contains("Run the installer."));
+    assert!(doc.document.contains("/docs#api-reference\n"));
+    assert_ne!(doc.filename, whole.filename);
+}
+
+#[tokio::test]
+async fn harvest_one_marks_documents_with_replaced_bytes() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/stray"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            b"<html><head><title>Stray</title></head><body><article><p>caf\xe9</p></article></body></html>".to_vec(),
+            "text/html; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+    let url = format!("{}/stray", server.uri());
+
+    let doc = harvest_one(&url, &config()).await.expect("harvest ok");
+    assert!(doc.document.contains("decode_lossy: true\n"));
+    assert!(doc.document.contains("caf\u{FFFD}"));
+
+    let mut strict = config();
+    strict.lossy_decode = false;
+    assert_eq!(
+        harvest_one(&url, &strict).await.unwrap_err(),
+        FailureKind::ProcessingError
+    );
+}
+
+#[tokio::test]
+async fn harvest_one_rejects_binary_bodies_labelled_as_html() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/report"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n".to_vec(), "text/html"),
+        )
+        .mount(&server)
+        .await;
+    let url = format!("{}/report", server.uri());
+
+    let err = harvest_one(&url, &config()).await.unwrap_err();
+
+    assert_eq!(
+        err,
+        FailureKind::BinaryContent {
+            detected: "pdf".to_string()
+        }
+    );
+}
+
+#[tokio::test]
+async fn harvest_one_surfaces_fetch_failure_kind() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/gone"))
+        .respond_with(ResponseTemplate::new(410))
+        .mount(&server)
+        .await;
+    let url = format!("{}/gone", server.uri());
+
+    let err = harvest_one(&url, &config()).await.unwrap_err();
+
+    assert_eq!(err, FailureKind::HttpStatus(410));
+}
+
+#[tokio::test]
+async fn harvest_one_stitches_paginated_article_with_part_provenance() {
+    let server = MockServer::start().await;
+    let page = |body: &str, next: Option<&str>| {
+        let link = next
+            .map(|href| format!(r#"<nav class="pagination"><a href="{href}">Next</a></nav>"#))
+            .unwrap_or_default();
+        ResponseTemplate::new(200).set_body_raw(
+            format!("<html><head><title>Guide</title></head><body><article><p>{body}</p></article>{link}</body></html>"),
+            "text/html",
+        )
+    };
+    Mock::given(method("GET"))
+        .and(path("/guide"))
+        .respond_with(page("Part one", Some("/guide/2")))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/guide/2"))
+        .respond_with(page("Part two", Some("/guide/3")))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/guide/3"))
+        .respond_with(page("Part three", Some("/guide")))
+        .mount(&server)
+        .await;
+    let url = format!("{}/guide", server.uri());
+
+    let mut config = config();
+    config.stitch_pages = true;
+    let doc = harvest_one(&url, &config).await.expect("harvest ok");
+
+    let one = doc.document.find("Part one").unwrap();
+    let two = doc.document.find("Part two").unwrap();
+    let three = doc.document.find("Part three").unwrap();
+    assert!(one < two && two < three);
+    assert!(doc
+        .document
+        .contains(&format!("parts:\n  - {url}\n  - {url}/2\n  - {url}/3\n---")));
+    // The link back to the first page ends the chain instead of looping.
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+    config.max_stitched_pages = 2;
+    let doc = harvest_one(&url, &config).await.expect("harvest ok");
+    assert!(doc.document.contains("Part two"));
+    assert!(!doc.document.contains("Part three"));
+}
+
+#[tokio::test]
+async fn harvest_one_uses_canonical_url_for_document_and_filename() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/story"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"<html><head><title>Story</title><link rel="canonical" href="https://news.example.com/story"></head><body><article><p>Text</p></article></body></html>"#,
+            "text/html",
+        ))
+        .mount(&server)
+        .await;
+
+    let tracked = harvest_one(
+        &format!("{}/story?utm_source=feed", server.uri()),
+        &config(),
+    )
+    .await
+    .expect("harvest ok");
+    let plain = harvest_one(&format!("{}/story", server.uri()), &config())
+        .await
+        .expect("harvest ok");
+
+    assert_eq!(
+        tracked.canonical_url.as_deref(),
+        Some("https://news.example.com/story")
+    );
+    assert!(tracked
+        .document
+        .starts_with("---\nurl: https://news.example.com/story\n"));
+    assert_eq!(tracked.filename, plain.filename);
+}
+
+#[tokio::test]
+async fn harvest_one_reads_saved_pages_from_file_urls() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let page = temp.path().join("saved.html");
+    std::fs::write(
+        &page,
+        "<html><head><title>Saved</title></head><body><article><p>Kept offline</p></article></body></html>",
+    )
+    .unwrap();
+    let url = url::Url::from_file_path(&page).unwrap().to_string();
+
+    let doc = harvest_one(&url, &config()).await.expect("harvest ok");
+
+    assert_eq!(doc.title.as_deref(), Some("Saved"));
+    assert!(doc.document.contains("source: local\n"));
+    assert!(doc.document.contains("Kept offline"));
+}
@@ -1,6 +1,8 @@
 use harvester_engine::{
-    build_concatenated_export, build_markdown_document, deterministic_filename, Converter,
-    ExportOptions, Extractor, Html2MdConverter, ReadabilityLikeExtractor, TokenCounter,
+    build_chunked_export, build_concatenated_export, build_fitted_export, build_html_export,
+    build_markdown_document, build_obsidian_vault, deterministic_filename, document_id,
+    write_image_inventory, ChunkOptions, Converter, ExportOptions, Extractor, FitOrder,
+    Html2MdConverter, ImageRef, Passphrase, ReadabilityLikeExtractor, TokenCounter,
     WhitespaceTokenCounter,
 };
 use pretty_assertions::assert_eq;
@@ -40,6 +42,7 @@ fn frontmatter_includes_token_count() {
     );
 
     assert!(doc.contains("url: https://example.com"));
+    assert!(doc.contains(&format!("doc_id: {}", document_id("https://example.com"))));
     assert!(doc.contains("title: Example"));
     assert!(doc.contains("token_count: 2"));
     assert!(doc.contains("---\n\nhello world"));
@@ -68,8 +71,8 @@ fn pipeline_assemble_markdown_end_to_end() {
 fn concatenated_export_builds_delimited_output_and_manifest() {
     let temp = tempfile::TempDir::new().unwrap();
     let dir = temp.path();
-    let md1 = "---\nurl: https://a\ntitle: A\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nBody A\n";
-    let md2 = "---\nurl: https://b\ntitle: B\ntoken_count: 3\nfetched_utc: 2024-01-02T00:00:00Z\nencoding: UTF-8\n---\n\nBody B\n";
+    let md1 = "---\nurl: https://a\ntitle: A\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nsection_tokens:\n  - Setup: Linux: 1\n  - Usage: 1\n---\n\nBody A\n";
+    let md2 = "---\nurl: https://b\ntitle: B\nsummary: About B.\ntoken_count: 3\nfetched_utc: 2024-01-02T00:00:00Z\nencoding: UTF-8\n---\n\nBody B\n";
     std::fs::write(dir.join("a.md"), md1).unwrap();
     std::fs::write(dir.join("b.md"), md2).unwrap();
 
@@ -86,6 +89,11 @@ fn concatenated_export_builds_delimited_output_and_manifest() {
     let manifest = std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap();
     assert!(manifest.contains("\"doc_count\":2"));
     assert!(manifest.contains("\"total_tokens\":5"));
+    assert!(manifest.contains(&format!("\"doc_id\":\"{}\"", document_id("https://a"))));
+    assert!(manifest.contains("\"summary\":\"About B.\""));
+    assert!(manifest.contains(
+        "\"sections\":[{\"heading\":\"Setup: Linux\",\"tokens\":1},{\"heading\":\"Usage\",\"tokens\":1}]"
+    ));
 }
 
 #[test]
@@ -103,3 +111,364 @@ fn concatenated_export_creates_missing_output_dir() {
     assert!(manifest.contains("\"doc_count\":0"));
     assert!(manifest.contains("\"total_tokens\":0"));
 }
+
+#[test]
+fn new_only_export_skips_documents_fetched_before_last_export() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let old = "---\nurl: https://old\ntitle: Old\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nOld body\n";
+    let new = "---\nurl: https://new\ntitle: New\ntoken_count: 3\nfetched_utc: 2024-03-01T00:00:00Z\nencoding: UTF-8\n---\n\nNew body\n";
+    std::fs::write(dir.join("old.md"), old).unwrap();
+    std::fs::write(dir.join("new.md"), new).unwrap();
+
+    let options = ExportOptions::new_only(
+        Some("2024-02-01T00:00:00Z".to_string()),
+        "2024-03-02T12:30:00Z",
+    );
+    let summary = build_concatenated_export(dir, options).unwrap();
+
+    assert_eq!(summary.doc_count, 1);
+    assert_eq!(summary.total_tokens, 3);
+    assert_eq!(
+        summary.output_path.file_name().unwrap(),
+        "export-2024-03-02T12-30-00.txt"
+    );
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+    assert!(export.contains("url: https://new"));
+    assert!(!export.contains("url: https://old"));
+}
+
+#[test]
+fn export_takes_latest_snapshot_unless_all_versions_requested() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let snapshots = dir.join("Page--abcd1234");
+    std::fs::create_dir(&snapshots).unwrap();
+    let doc = |date: &str, body: &str| {
+        format!("---\nurl: https://page\ntitle: Page\ntoken_count: 1\nfetched_utc: {date}T00:00:00Z\n---\n\n{body}\n")
+    };
+    std::fs::write(snapshots.join("2024-05-01.md"), doc("2024-05-01", "First")).unwrap();
+    std::fs::write(snapshots.join("2024-05-02.md"), doc("2024-05-02", "Second")).unwrap();
+
+    let summary = build_concatenated_export(dir, ExportOptions::default()).unwrap();
+    assert_eq!(summary.doc_count, 1);
+    let export = std::fs::read_to_string(&summary.output_path).unwrap();
+    assert!(export.contains("filename: Page--abcd1234/2024-05-02.md"));
+    assert!(!export.contains("First"));
+
+    let options = ExportOptions {
+        all_versions: true,
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+    assert_eq!(summary.doc_count, 2);
+}
+
+#[test]
+fn export_leaves_out_documents_below_min_quality() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let thin = "---\nurl: https://thin\ntitle: Thin\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nquality_score: 12\n---\n\nThin body\n";
+    let good = "---\nurl: https://good\ntitle: Good\ntoken_count: 3\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nquality_score: 80\n---\n\nGood body\n";
+    let unscored = "---\nurl: https://old\ntitle: Old\ntoken_count: 4\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nOld body\n";
+    std::fs::write(dir.join("thin.md"), thin).unwrap();
+    std::fs::write(dir.join("good.md"), good).unwrap();
+    std::fs::write(dir.join("old.md"), unscored).unwrap();
+
+    let options = ExportOptions {
+        min_quality_score: Some(40),
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+
+    assert_eq!(summary.doc_count, 2);
+    assert!(export.contains("url: https://good"));
+    assert!(export.contains("url: https://old"));
+    assert!(!export.contains("url: https://thin"));
+}
+
+#[test]
+fn keyword_filtered_export_lists_keywords_in_the_manifest() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let rust = "---\nurl: https://rust\ntitle: Rust\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nkeywords: ownership, borrow\n---\n\nRust body\n";
+    let bread = "---\nurl: https://bread\ntitle: Bread\ntoken_count: 3\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nkeywords: dough, oven\n---\n\nBread body\n";
+    std::fs::write(dir.join("rust.md"), rust).unwrap();
+    std::fs::write(dir.join("bread.md"), bread).unwrap();
+
+    let options = ExportOptions {
+        keywords: vec!["Borrow".to_string()],
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+    assert_eq!(summary.doc_count, 1);
+    assert!(export.contains("url: https://rust"));
+
+    let manifest = std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap();
+    assert!(manifest.contains("\"keywords\":[\"ownership\",\"borrow\"]"));
+}
+
+#[test]
+fn source_batch_filtered_export_keeps_only_that_batch() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let ticket = "---\nurl: https://tracker/1\ntitle: Ticket\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\nsource_batch: bug-triage\n---\n\nTicket body\n";
+    let news = "---\nurl: https://news\ntitle: News\ntoken_count: 3\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nNews body\n";
+    std::fs::write(dir.join("ticket.md"), ticket).unwrap();
+    std::fs::write(dir.join("news.md"), news).unwrap();
+
+    let options = ExportOptions {
+        source_batch: Some("bug-triage".to_string()),
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+    assert_eq!(summary.doc_count, 1);
+    assert!(export.contains("url: https://tracker/1"));
+
+    let manifest = std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap();
+    assert!(manifest.contains("\"source_batch\":\"bug-triage\""));
+}
+
+#[test]
+fn encrypted_export_and_manifest_decrypt_with_the_passphrase() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let doc = "---\nurl: https://intranet/spec\ntitle: Spec\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nSecret body\n";
+    std::fs::write(dir.join("spec.md"), doc).unwrap();
+    let passphrase = Passphrase::new("hunter2").unwrap();
+
+    let options = ExportOptions {
+        encryption: Some(passphrase.clone()),
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+
+    assert!(summary.output_path.ends_with("export.txt.enc"));
+    assert!(!dir.join("export.txt").exists());
+    let sealed = std::fs::read(&summary.output_path).unwrap();
+    assert!(!String::from_utf8_lossy(&sealed).contains("Secret body"));
+    let export = String::from_utf8(passphrase.decrypt(&sealed).unwrap()).unwrap();
+    assert!(export.contains("Secret body"));
+    let manifest = std::fs::read(summary.manifest_path.unwrap()).unwrap();
+    assert!(String::from_utf8(passphrase.decrypt(&manifest).unwrap())
+        .unwrap()
+        .contains("https://intranet/spec"));
+}
+
+#[test]
+fn fitted_export_stays_within_the_budget_and_lists_what_was_left_out() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let long_body = "word ".repeat(30);
+    for (name, tokens, body) in [
+        ("a", 4, "four words of text"),
+        ("b", 30, long_body.as_str()),
+        ("c", 2, "two words"),
+    ] {
+        let doc = format!("---\nurl: https://{name}\ntitle: {name}\ntoken_count: {tokens}\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\n{body}\n");
+        std::fs::write(dir.join(format!("{name}.md")), doc).unwrap();
+    }
+
+    let summary = build_fitted_export(
+        dir,
+        ExportOptions::default(),
+        FitOrder::Smallest,
+        45,
+        &CountingTokens,
+    )
+    .unwrap();
+    let export = std::fs::read_to_string(&summary.output_path).unwrap();
+    assert_eq!(summary.doc_count, 2);
+    assert!(CountingTokens.count(&export) <= 45);
+    assert!(export.find("url: https://c").unwrap() < export.find("url: https://a").unwrap());
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap())
+            .unwrap();
+    assert_eq!(manifest["files"].as_array().unwrap().len(), 2);
+    assert_eq!(manifest["excluded"][0]["url"], "https://b");
+}
+
+#[test]
+fn fitted_export_always_includes_pinned_documents_first() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let long_body = "word ".repeat(30);
+    for (name, tokens, body) in [
+        ("a", 4, "four words of text"),
+        ("b", 30, long_body.as_str()),
+    ] {
+        let doc = format!("---\nurl: https://{name}\ntitle: {name}\ntoken_count: {tokens}\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\n{body}\n");
+        std::fs::write(dir.join(format!("{name}.md")), doc).unwrap();
+    }
+
+    let options = ExportOptions {
+        pinned_urls: vec!["https://b".to_string()],
+        ..ExportOptions::default()
+    };
+    let summary =
+        build_fitted_export(dir, options, FitOrder::Smallest, 20, &CountingTokens).unwrap();
+
+    let export = std::fs::read_to_string(&summary.output_path).unwrap();
+    assert_eq!(
+        summary.doc_count, 1,
+        "the pinned document alone is over budget"
+    );
+    assert!(export.contains("url: https://b"));
+    assert!(!export.contains("url: https://a"));
+}
+
+#[test]
+fn published_dates_order_fitted_exports_and_filter_by_range() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    for (name, published) in [
+        ("a", Some("2023-11-02")),
+        ("b", Some("2024-05")),
+        ("c", None),
+        ("d", Some("2024-02-29")),
+    ] {
+        let published = published
+            .map(|date| format!("published: {date}\n"))
+            .unwrap_or_default();
+        let doc = format!("---\nurl: https://{name}\ntitle: {name}\ntoken_count: 1\nfetched_utc: 2024-06-01T00:00:00Z\nencoding: UTF-8\n{published}---\n\nbody\n");
+        std::fs::write(dir.join(format!("{name}.md")), doc).unwrap();
+    }
+
+    let summary = build_fitted_export(
+        dir,
+        ExportOptions::default(),
+        FitOrder::Published,
+        1_000,
+        &CountingTokens,
+    )
+    .unwrap();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(summary.manifest_path.unwrap()).unwrap())
+            .unwrap();
+    let urls: Vec<&str> = manifest["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["url"].as_str().unwrap())
+        .collect();
+    assert_eq!(urls, ["https://b", "https://d", "https://a", "https://c"]);
+    assert_eq!(manifest["files"][0]["published"], "2024-05");
+
+    let options = ExportOptions {
+        published_from: Some("2024-01-01".to_string()),
+        published_until: Some("2024-05-01".to_string()),
+        ..ExportOptions::default()
+    };
+    let summary = build_concatenated_export(dir, options).unwrap();
+    let export = std::fs::read_to_string(&summary.output_path).unwrap();
+    assert_eq!(summary.doc_count, 2, "{export}");
+    assert!(export.contains("url: https://b") && export.contains("url: https://d"));
+}
+
+#[test]
+fn chunked_export_writes_jsonl_with_ids_urls_and_heading_paths() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let doc = "---\nurl: https://guide\ntitle: Guide\ntoken_count: 9\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\n# Guide\nIntro words here\n## Install\nRun the installer now\n";
+    std::fs::write(dir.join("guide.md"), doc).unwrap();
+
+    let chunking = ChunkOptions {
+        max_tokens: 16,
+        overlap_tokens: 0,
+    };
+    let summary =
+        build_chunked_export(dir, ExportOptions::default(), &chunking, &CountingTokens).unwrap();
+    assert!(summary.output_path.ends_with("export.jsonl"));
+    let export = std::fs::read_to_string(summary.output_path).unwrap();
+    let lines: Vec<serde_json::Value> = export
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["id"], "guide.md#0");
+    assert_eq!(lines[0]["url"], "https://guide");
+    assert_eq!(lines[0]["doc_id"], document_id("https://guide"));
+    assert_eq!(lines[1]["doc_id"], lines[0]["doc_id"]);
+    assert_eq!(
+        lines[1]["heading_path"],
+        serde_json::json!(["Guide", "Install"])
+    );
+    assert_eq!(lines[1]["text"], "## Install\nRun the installer now");
+    assert_eq!(lines[1]["tokens"], 6);
+}
+
+#[test]
+fn obsidian_vault_export_writes_notes_per_domain_with_wikilinks() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let guide = "---\nurl: https://example.com/guide\ntitle: Guide: Setup\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nSetup steps\n";
+    let index = "---\nurl: https://example.com/\ntitle: Index\ntoken_count: 3\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nRead [the guide](https://example.com/guide/).\n";
+    std::fs::write(dir.join("Guide--aaa.md"), guide).unwrap();
+    std::fs::write(dir.join("Index--bbb.md"), index).unwrap();
+
+    let summary = build_obsidian_vault(dir, ExportOptions::default()).unwrap();
+    assert_eq!(summary.doc_count, 2);
+    assert_eq!(summary.output_path, dir.join("vault"));
+
+    let note = std::fs::read_to_string(dir.join("vault/example.com/Index--bbb.md")).unwrap();
+    assert!(note.starts_with("---\ntitle: \"Index\"\nsource: https://example.com/\n"));
+    assert!(note.contains("tags:\n  - harvested\n  - example-com\n"));
+    assert!(note.contains("Read [[example.com/Guide--aaa|the guide]]."));
+    let guide_note = std::fs::read_to_string(dir.join("vault/example.com/Guide--aaa.md")).unwrap();
+    assert!(guide_note.contains("title: \"Guide: Setup\""));
+}
+
+#[test]
+fn html_export_writes_one_file_with_toc_and_anchors() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let dir = temp.path();
+    let a = "---\nurl: https://a.test/\ntitle: A & B\ntoken_count: 2\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\n# Heading\nBody text\n";
+    let b = "---\nurl: https://b.test/\ntitle: Second\ntoken_count: 1\nfetched_utc: 2024-01-01T00:00:00Z\nencoding: UTF-8\n---\n\nOther\n";
+    std::fs::write(dir.join("a.md"), a).unwrap();
+    std::fs::write(dir.join("b.md"), b).unwrap();
+
+    let summary = build_html_export(dir, ExportOptions::default()).unwrap();
+    assert!(summary.output_path.ends_with("export.html"));
+    let html = std::fs::read_to_string(summary.output_path).unwrap();
+
+    assert!(html.contains("<li><a href=\"#doc-0\">A &amp; B</a></li>"));
+    assert!(html.contains("<li><a href=\"#doc-1\">Second</a></li>"));
+    assert!(html.contains("<section id=\"doc-0\">\n<h1>A &amp; B</h1>"));
+    assert!(html.contains("<h2>Heading</h2>\n<p>Body text</p>"));
+}
+
+#[test]
+fn image_inventory_lists_each_image_with_its_page() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let image = |image_url: &str, page_url: &str, alt: Option<&str>| ImageRef {
+        image_url: image_url.to_string(),
+        page_url: page_url.to_string(),
+        alt_text: alt.map(str::to_string),
+    };
+    let path = write_image_inventory(
+        temp.path(),
+        &[
+            image(
+                "https://a.test/flow.png",
+                "https://a.test/guide",
+                Some("Flow, \"v2\""),
+            ),
+            image("https://a.test/flow.png", "https://a.test/guide", None),
+            image("https://a.test/flow.png", "https://a.test/faq", None),
+        ],
+    )
+    .unwrap();
+
+    assert!(path.ends_with("images.csv"));
+    assert_eq!(
+        std::fs::read_to_string(path).unwrap(),
+        "image_url,page_url,alt_text\n\
+         https://a.test/flow.png,https://a.test/guide,\"Flow, \"\"v2\"\"\"\n\
+         https://a.test/flow.png,https://a.test/faq,\n"
+    );
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use harvester_engine::{AsyncEngineHandle, EngineConfig, EngineEvent};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn page(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(
+        format!("<html><head><title>Watched</title></head><body><article><p>{body}</p></article></body></html>"),
+        "text/html; charset=utf-8",
+    )
+}
+
+#[tokio::test]
+async fn watch_reports_change_only_after_content_moves() {
+    let server = MockServer::start().await;
+    // Baseline and first re-check see the same content; later checks see an update.
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(page("original text"))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(page("updated text"))
+        .with_priority(2)
+        .mount(&server)
+        .await;
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut engine =
+        AsyncEngineHandle::spawn(EngineConfig::default_with_output(temp.path().to_path_buf()));
+    engine.watch(7, format!("{}/page", server.uri()), Duration::from_secs(1));
+
+    let event = tokio::time::timeout(Duration::from_secs(10), engine.next_event())
+        .await
+        .expect("change event before timeout");
+    match event {
+        Some(EngineEvent::WatchChanged { watch_id, outcome }) => {
+            assert_eq!(watch_id, 7);
+            let path = outcome.output_path.expect("document written");
+            let written = std::fs::read_to_string(path).unwrap();
+            assert!(written.contains("updated text"));
+            let preview = outcome.content_preview.expect("preview");
+            assert!(preview.contains("updated text"));
+            assert!(!preview.contains("fetched_utc"));
+        }
+        other => panic!("unexpected event: {other:?}"),
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn slow_watch_check_does_not_hold_up_jobs() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/slow"))
+        .respond_with(page("watched text").set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/job"))
+        .respond_with(page("job text"))
+        .mount(&server)
+        .await;
+
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut config = EngineConfig::default_with_output(temp.path().to_path_buf());
+    config.max_concurrent_per_domain = 2;
+    let mut engine = AsyncEngineHandle::spawn(config);
+    engine.watch(7, format!("{}/slow", server.uri()), Duration::from_secs(60));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    engine.enqueue(1, format!("{}/job", server.uri()));
+
+    let completed = tokio::time::timeout(Duration::from_secs(3), async {
+        loop {
+            match engine.next_event().await {
+                Some(EngineEvent::JobCompleted { job_id, result }) => break (job_id, result),
+                Some(_) => continue,
+                None => panic!("engine stopped"),
+            }
+        }
+    })
+    .await
+    .expect("job finishes while the watch check is still fetching");
+    assert_eq!(completed.0, 1);
+    assert!(completed.1.is_ok());
+}